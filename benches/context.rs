@@ -0,0 +1,75 @@
+//! Context update and snapshot costs for high-NPC-count scenes
+//!
+//! Run with `cargo bench --bench context`. The `clone_and_extend` cases
+//! measure the previous approach of copying the whole map per request.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use oxyde::context::{ContextBuilder, ContextDiff, ContextStore};
+use oxyde::AgentContext;
+
+/// Number of NPCs in the simulated scene
+const NPCS: usize = 200;
+
+/// A context the size a game typically sends
+fn scene_context(frame: u32) -> AgentContext {
+    let mut builder = ContextBuilder::new()
+        .player_name("Ayla")
+        .player_nearby(true)
+        .player_position(frame as f64, 0.0, 4.0)
+        .game_hour(13.5);
+    for i in 0..24 {
+        builder = builder.with(&format!("world_flag_{}", i), i % 2 == 0);
+    }
+    builder.build()
+}
+
+fn bench_context(c: &mut Criterion) {
+    let stores: Vec<ContextStore> = (0..NPCS).map(|_| ContextStore::new()).collect();
+    for store in &stores {
+        store.apply(scene_context(0).into());
+    }
+    let unchanged = scene_context(0);
+    let moved = scene_context(1);
+
+    c.bench_function("context/apply_unchanged", |b| {
+        b.iter(|| {
+            for store in &stores {
+                black_box(store.apply(ContextDiff::from(unchanged.clone())));
+            }
+        })
+    });
+
+    c.bench_function("context/apply_position_diff", |b| {
+        let diff = ContextDiff::between(&unchanged, &moved);
+        let mut frame = 0;
+        b.iter(|| {
+            frame += 1;
+            let diff = if frame % 2 == 0 { diff.clone() } else { ContextDiff::between(&moved, &unchanged) };
+            for store in &stores {
+                black_box(store.apply(diff.clone()));
+            }
+        })
+    });
+
+    c.bench_function("context/snapshot", |b| {
+        b.iter(|| {
+            for store in &stores {
+                black_box(store.snapshot());
+            }
+        })
+    });
+
+    c.bench_function("context/clone_and_extend", |b| {
+        let mut contexts: Vec<AgentContext> = (0..NPCS).map(|_| scene_context(0)).collect();
+        b.iter(|| {
+            for context in &mut contexts {
+                let mut copy = context.clone();
+                copy.extend(unchanged.clone());
+                *context = black_box(copy);
+            }
+        })
+    });
+}
+
+criterion_group!(benches, bench_context);
+criterion_main!(benches);
@@ -24,6 +24,9 @@ namespace Oxyde.Unity
         [DllImport("oxyde", EntryPoint = "oxyde_unity_create_agent_from_json")]
         private static extern IntPtr NativeCreateAgentFromJson(string jsonConfig);
 
+        [DllImport("oxyde", EntryPoint = "oxyde_unity_share_agent")]
+        private static extern IntPtr NativeShareAgent(string agentId, uint capability);
+
         [DllImport("oxyde", EntryPoint = "oxyde_unity_update_agent")]
         private static extern bool NativeUpdateAgent(string agentId, string contextJson);
 
@@ -49,6 +52,9 @@ namespace Oxyde.Unity
             out float anticipation
         );
 
+        [DllImport("oxyde", EntryPoint = "oxyde_unity_update_emotions_json")]
+        private static extern bool NativeUpdateEmotionsJson(string agentId, string emotionsJson);
+
         [DllImport("oxyde", EntryPoint = "oxyde_unity_free_string")]
         private static extern void NativeFreeString(IntPtr ptr);
 
@@ -77,6 +83,26 @@ namespace Oxyde.Unity
         [DllImport("oxyde", EntryPoint = "oxyde_unity_forget_memories_by_category")]
         private static extern uint NativeForgetMemoriesByCategory(string agentId, string category);
 
+        // Scenes
+        [DllImport("oxyde", EntryPoint = "oxyde_unity_create_scene")]
+        private static extern IntPtr NativeCreateScene(string name);
+
+        [DllImport("oxyde", EntryPoint = "oxyde_unity_retain_scene")]
+        private static extern uint NativeRetainScene(string sceneId);
+
+        [DllImport("oxyde", EntryPoint = "oxyde_unity_scene_add_agent")]
+        private static extern bool NativeSceneAddAgent(string sceneId, string agentId);
+
+        [DllImport("oxyde", EntryPoint = "oxyde_unity_destroy_scene")]
+        private static extern bool NativeDestroyScene(string sceneId);
+
+        // Actions
+        [DllImport("oxyde", EntryPoint = "oxyde_unity_poll_actions")]
+        private static extern IntPtr NativePollActions(string agentId);
+
+        [DllImport("oxyde", EntryPoint = "oxyde_unity_parse_action")]
+        private static extern IntPtr NativeParseAction(string action);
+
         #endregion
 
         #region Helper Methods
@@ -203,6 +229,36 @@ namespace Oxyde.Unity
             }
         }
 
+        /// <summary>
+        /// What calls an agent handle may make, each level allowing those below it
+        /// </summary>
+        public enum HandleCapability : uint
+        {
+            ReadOnly = 0,   // Read emotions, memories and state
+            Interact = 1,   // Also send input and change context, emotions and memories
+            Full = 2        // Also clear and forget memories
+        }
+
+        /// <summary>
+        /// Create another handle to an agent, limited to a capability, for mods
+        /// and scripts that should not have full control
+        /// </summary>
+        /// <param name="agentId">Agent ID or handle to share</param>
+        /// <param name="capability">Capability of the new handle, at most the shared handle's own</param>
+        /// <returns>The new handle, or an empty string if it was refused</returns>
+        public static string ShareAgent(string agentId, HandleCapability capability)
+        {
+            try
+            {
+                return PtrToStringAndFree(NativeShareAgent(agentId, (uint)capability));
+            }
+            catch (Exception ex)
+            {
+                Debug.LogError($"Error sharing agent: {ex.Message}");
+                return string.Empty;
+            }
+        }
+
         /// <summary>
         /// Update an agent with new context data
         /// </summary>
@@ -300,6 +356,25 @@ namespace Oxyde.Unity
             }
         }
 
+        /// <summary>
+        /// Apply several emotion deltas to an agent in one call
+        /// </summary>
+        /// <param name="agentId">Agent ID string</param>
+        /// <param name="emotionsJson">JSON object mapping emotion names to deltas, e.g. {"joy": 0.2, "fear": -0.1}</param>
+        /// <returns>True if all deltas were applied; nothing is applied on failure</returns>
+        public static bool UpdateEmotions(string agentId, string emotionsJson)
+        {
+            try
+            {
+                return NativeUpdateEmotionsJson(agentId, emotionsJson);
+            }
+            catch (Exception ex)
+            {
+                Debug.LogError($"Error updating agent emotions: {ex.Message}");
+                return false;
+            }
+        }
+
         // ==================== Memory System ====================
 
         /// <summary>
@@ -470,6 +545,109 @@ namespace Oxyde.Unity
             }
         }
 
+        /// <summary>
+        /// Create a scene grouping agents that are freed together, such as the NPCs of a level
+        /// </summary>
+        /// <returns>Scene ID string or empty if failed</returns>
+        public static string CreateScene(string name)
+        {
+            try
+            {
+                return PtrToStringAndFree(NativeCreateScene(name));
+            }
+            catch (Exception ex)
+            {
+                Debug.LogError($"Error creating scene: {ex.Message}");
+                return string.Empty;
+            }
+        }
+
+        /// <summary>
+        /// Take another reference to a scene, released with DestroyScene
+        /// </summary>
+        /// <returns>References held, or 0 if the scene does not exist</returns>
+        public static uint RetainScene(string sceneId)
+        {
+            try
+            {
+                return NativeRetainScene(sceneId);
+            }
+            catch (Exception ex)
+            {
+                Debug.LogError($"Error retaining scene: {ex.Message}");
+                return 0;
+            }
+        }
+
+        /// <summary>
+        /// Register an agent under a scene
+        /// </summary>
+        public static bool AddAgentToScene(string sceneId, string agentId)
+        {
+            try
+            {
+                return NativeSceneAddAgent(sceneId, agentId);
+            }
+            catch (Exception ex)
+            {
+                Debug.LogError($"Error adding agent to scene: {ex.Message}");
+                return false;
+            }
+        }
+
+        /// <summary>
+        /// Release a reference to a scene; the last release stops and frees the scene's agents.
+        /// Call this when the level unloads.
+        /// </summary>
+        public static bool DestroyScene(string sceneId)
+        {
+            try
+            {
+                return NativeDestroyScene(sceneId);
+            }
+            catch (Exception ex)
+            {
+                Debug.LogError($"Error destroying scene: {ex.Message}");
+                return false;
+            }
+        }
+
+        /// <summary>
+        /// Take the actions an agent took since the last poll, such as
+        /// {"version":1,"type":"move_to","x":4.0,"y":2.0}
+        /// </summary>
+        /// <returns>JSON array of versioned action commands, oldest first</returns>
+        public static string PollActions(string agentId)
+        {
+            try
+            {
+                string json = PtrToStringAndFree(NativePollActions(agentId));
+                return string.IsNullOrEmpty(json) ? "[]" : json;
+            }
+            catch (Exception ex)
+            {
+                Debug.LogError($"Error polling actions: {ex.Message}");
+                return "[]";
+            }
+        }
+
+        /// <summary>
+        /// Read a behavior action string as a versioned JSON action command
+        /// </summary>
+        /// <returns>Action command JSON or empty if the action is malformed</returns>
+        public static string ParseAction(string action)
+        {
+            try
+            {
+                return PtrToStringAndFree(NativeParseAction(action));
+            }
+            catch (Exception ex)
+            {
+                Debug.LogError($"Error parsing action: {ex.Message}");
+                return string.Empty;
+            }
+        }
+
         #endregion
     }
 
@@ -27,6 +27,12 @@ UOxydeLibrary::GetMemoriesByCategoryFuncPtr UOxydeLibrary::GetMemoriesByCategory
 UOxydeLibrary::RetrieveRelevantMemoriesFuncPtr UOxydeLibrary::RetrieveRelevantMemoriesFunc = nullptr;
 UOxydeLibrary::ForgetMemoryFuncPtr UOxydeLibrary::ForgetMemoryFunc = nullptr;
 UOxydeLibrary::ForgetMemoriesByCategoryFuncPtr UOxydeLibrary::ForgetMemoriesByCategoryFunc = nullptr;
+UOxydeLibrary::CreateSceneFuncPtr UOxydeLibrary::CreateSceneFunc = nullptr;
+UOxydeLibrary::RetainSceneFuncPtr UOxydeLibrary::RetainSceneFunc = nullptr;
+UOxydeLibrary::SceneAddAgentFuncPtr UOxydeLibrary::SceneAddAgentFunc = nullptr;
+UOxydeLibrary::DestroySceneFuncPtr UOxydeLibrary::DestroySceneFunc = nullptr;
+UOxydeLibrary::PollActionsFuncPtr UOxydeLibrary::PollActionsFunc = nullptr;
+UOxydeLibrary::ParseActionFuncPtr UOxydeLibrary::ParseActionFunc = nullptr;
 
 bool UOxydeLibrary::Init()
 {
@@ -216,6 +222,12 @@ bool UOxydeLibrary::InitializeFunctionPointers()
     RetrieveRelevantMemoriesFunc = (RetrieveRelevantMemoriesFuncPtr)FPlatformProcess::GetDllExport(LibraryHandle, TEXT("oxyde_unreal_retrieve_relevant_memories"));
     ForgetMemoryFunc = (ForgetMemoryFuncPtr)FPlatformProcess::GetDllExport(LibraryHandle, TEXT("oxyde_unreal_forget_memory"));
     ForgetMemoriesByCategoryFunc = (ForgetMemoriesByCategoryFuncPtr)FPlatformProcess::GetDllExport(LibraryHandle, TEXT("oxyde_unreal_forget_memories_by_category"));
+    CreateSceneFunc = (CreateSceneFuncPtr)FPlatformProcess::GetDllExport(LibraryHandle, TEXT("oxyde_unreal_create_scene"));
+    RetainSceneFunc = (RetainSceneFuncPtr)FPlatformProcess::GetDllExport(LibraryHandle, TEXT("oxyde_unreal_retain_scene"));
+    SceneAddAgentFunc = (SceneAddAgentFuncPtr)FPlatformProcess::GetDllExport(LibraryHandle, TEXT("oxyde_unreal_scene_add_agent"));
+    DestroySceneFunc = (DestroySceneFuncPtr)FPlatformProcess::GetDllExport(LibraryHandle, TEXT("oxyde_unreal_destroy_scene"));
+    PollActionsFunc = (PollActionsFuncPtr)FPlatformProcess::GetDllExport(LibraryHandle, TEXT("oxyde_unreal_poll_actions"));
+    ParseActionFunc = (ParseActionFuncPtr)FPlatformProcess::GetDllExport(LibraryHandle, TEXT("oxyde_unreal_parse_action"));
 
     // Check that all functions were found
     if (InitFunc == nullptr ||
@@ -233,7 +245,13 @@ bool UOxydeLibrary::InitializeFunctionPointers()
         GetMemoriesByCategoryFunc == nullptr ||
         RetrieveRelevantMemoriesFunc == nullptr ||
         ForgetMemoryFunc == nullptr ||
-        ForgetMemoriesByCategoryFunc == nullptr)
+        ForgetMemoriesByCategoryFunc == nullptr ||
+        CreateSceneFunc == nullptr ||
+        RetainSceneFunc == nullptr ||
+        SceneAddAgentFunc == nullptr ||
+        DestroySceneFunc == nullptr ||
+        PollActionsFunc == nullptr ||
+        ParseActionFunc == nullptr)
     {
         UE_LOG(LogOxyde, Error, TEXT("Failed to load one or more Oxyde SDK functions"));
         FPlatformProcess::FreeDllHandle(LibraryHandle);
@@ -391,3 +409,93 @@ int32 UOxydeLibrary::ForgetMemoriesByCategory(FString AgentId, FString Category)
 
     return (int32)ForgetMemoriesByCategoryFunc(TCHAR_TO_UTF8(*AgentId), TCHAR_TO_UTF8(*Category));
 }
+
+FString UOxydeLibrary::CreateScene(FString Name)
+{
+    if (!InitializeFunctionPointers())
+    {
+        UE_LOG(LogOxyde, Error, TEXT("Failed to initialize Oxyde SDK function pointers"));
+        return FString();
+    }
+
+    const char* result = CreateSceneFunc(TCHAR_TO_UTF8(*Name));
+    if (result == nullptr)
+    {
+        return FString();
+    }
+
+    FString sceneId(UTF8_TO_TCHAR(result));
+    FreeStringFunc(result);
+    return sceneId;
+}
+
+int32 UOxydeLibrary::RetainScene(FString SceneId)
+{
+    if (!InitializeFunctionPointers())
+    {
+        UE_LOG(LogOxyde, Error, TEXT("Failed to initialize Oxyde SDK function pointers"));
+        return 0;
+    }
+
+    return (int32)RetainSceneFunc(TCHAR_TO_UTF8(*SceneId));
+}
+
+bool UOxydeLibrary::AddAgentToScene(FString SceneId, FString AgentId)
+{
+    if (!InitializeFunctionPointers())
+    {
+        UE_LOG(LogOxyde, Error, TEXT("Failed to initialize Oxyde SDK function pointers"));
+        return false;
+    }
+
+    return SceneAddAgentFunc(TCHAR_TO_UTF8(*SceneId), TCHAR_TO_UTF8(*AgentId));
+}
+
+bool UOxydeLibrary::DestroyScene(FString SceneId)
+{
+    if (!InitializeFunctionPointers())
+    {
+        UE_LOG(LogOxyde, Error, TEXT("Failed to initialize Oxyde SDK function pointers"));
+        return false;
+    }
+
+    return DestroySceneFunc(TCHAR_TO_UTF8(*SceneId));
+}
+
+FString UOxydeLibrary::PollActions(FString AgentId)
+{
+    if (!InitializeFunctionPointers())
+    {
+        UE_LOG(LogOxyde, Error, TEXT("Failed to initialize Oxyde SDK function pointers"));
+        return TEXT("[]");
+    }
+
+    const char* result = PollActionsFunc(TCHAR_TO_UTF8(*AgentId));
+    if (result == nullptr)
+    {
+        return TEXT("[]");
+    }
+
+    FString actions(UTF8_TO_TCHAR(result));
+    FreeStringFunc(result);
+    return actions;
+}
+
+FString UOxydeLibrary::ParseAction(FString Action)
+{
+    if (!InitializeFunctionPointers())
+    {
+        UE_LOG(LogOxyde, Error, TEXT("Failed to initialize Oxyde SDK function pointers"));
+        return FString();
+    }
+
+    const char* result = ParseActionFunc(TCHAR_TO_UTF8(*Action));
+    if (result == nullptr)
+    {
+        return FString();
+    }
+
+    FString command(UTF8_TO_TCHAR(result));
+    FreeStringFunc(result);
+    return command;
+}
@@ -182,6 +182,55 @@ public:
     UFUNCTION(BlueprintCallable, Category = "Oxyde|Memory")
     static int32 ForgetMemoriesByCategory(FString AgentId, FString Category);
 
+    /**
+     * Create a scene grouping agents that are freed together, such as the NPCs of a level
+     * @param Name Scene name for logs
+     * @return Scene ID string
+     */
+    UFUNCTION(BlueprintCallable, Category = "Oxyde|Scene")
+    static FString CreateScene(FString Name);
+
+    /**
+     * Take another reference to a scene, released with DestroyScene
+     * @param SceneId Scene ID string
+     * @return References held, or 0 if the scene does not exist
+     */
+    UFUNCTION(BlueprintCallable, Category = "Oxyde|Scene")
+    static int32 RetainScene(FString SceneId);
+
+    /**
+     * Register an agent under a scene
+     * @param SceneId Scene ID string
+     * @param AgentId Agent ID string
+     * @return True if successful
+     */
+    UFUNCTION(BlueprintCallable, Category = "Oxyde|Scene")
+    static bool AddAgentToScene(FString SceneId, FString AgentId);
+
+    /**
+     * Release a reference to a scene; the last release stops and frees the scene's agents
+     * @param SceneId Scene ID string
+     * @return True if successful
+     */
+    UFUNCTION(BlueprintCallable, Category = "Oxyde|Scene")
+    static bool DestroyScene(FString SceneId);
+
+    /**
+     * Take the actions an agent took since the last poll
+     * @param AgentId Agent ID string
+     * @return JSON array of versioned action commands, oldest first
+     */
+    UFUNCTION(BlueprintCallable, Category = "Oxyde|Action")
+    static FString PollActions(FString AgentId);
+
+    /**
+     * Read a behavior action string as a versioned JSON action command
+     * @param Action Action string, such as "move_to|4.00|2.00"
+     * @return Action command JSON, or empty if the action is malformed
+     */
+    UFUNCTION(BlueprintCallable, Category = "Oxyde|Action")
+    static FString ParseAction(FString Action);
+
 private:
     // Native function pointers
     typedef bool (*InitFuncPtr)();
@@ -203,6 +252,16 @@ private:
     typedef bool (*ForgetMemoryFuncPtr)(const char*, const char*);
     typedef uint32 (*ForgetMemoriesByCategoryFuncPtr)(const char*, const char*);
 
+    // Scene function pointers
+    typedef const char* (*CreateSceneFuncPtr)(const char*);
+    typedef uint32 (*RetainSceneFuncPtr)(const char*);
+    typedef bool (*SceneAddAgentFuncPtr)(const char*, const char*);
+    typedef bool (*DestroySceneFuncPtr)(const char*);
+
+    // Action function pointers
+    typedef const char* (*PollActionsFuncPtr)(const char*);
+    typedef const char* (*ParseActionFuncPtr)(const char*);
+
     static InitFuncPtr InitFunc;
     static CreateAgentFuncPtr CreateAgentFunc;
     static CreateAgentFromJsonFuncPtr CreateAgentFromJsonFunc;
@@ -221,6 +280,14 @@ private:
     static ForgetMemoryFuncPtr ForgetMemoryFunc;
     static ForgetMemoriesByCategoryFuncPtr ForgetMemoriesByCategoryFunc;
 
+    static CreateSceneFuncPtr CreateSceneFunc;
+    static RetainSceneFuncPtr RetainSceneFunc;
+    static SceneAddAgentFuncPtr SceneAddAgentFunc;
+    static DestroySceneFuncPtr DestroySceneFunc;
+
+    static PollActionsFuncPtr PollActionsFunc;
+    static ParseActionFuncPtr ParseActionFunc;
+
     // Handle to the dynamic library
     static void* LibraryHandle;
 
@@ -0,0 +1,56 @@
+//! Minimal headless Bevy app with one Oxyde NPC
+//!
+//! Usage: `cargo run --example village -- path/to/npc.json`
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use oxyde::AgentConfig;
+use oxyde_bevy::{OxydeAgentComponent, OxydeInputNearest, OxydePlayer, OxydePlugin, OxydeResponse};
+
+fn main() {
+    let path = std::env::args().nth(1).unwrap_or_else(|| "npc.json".to_string());
+    let config = AgentConfig::from_file(&path).expect("failed to load agent config");
+
+    App::new()
+        .add_plugins((MinimalPlugins, OxydePlugin::default()))
+        .insert_resource(NpcConfig(config))
+        .add_systems(Startup, setup)
+        .add_systems(Update, (talk, print_responses))
+        .run();
+}
+
+#[derive(Resource)]
+struct NpcConfig(AgentConfig);
+
+fn setup(mut commands: Commands, config: Res<NpcConfig>) {
+    commands.spawn((
+        OxydeAgentComponent::new(config.0.clone()).with_greeting("Hello"),
+        TransformBundle::from_transform(Transform::from_xyz(2.0, 0.0, 0.0)),
+    ));
+    commands.spawn((OxydePlayer, TransformBundle::default()));
+}
+
+fn talk(mut sent: Local<bool>, mut inputs: EventWriter<OxydeInputNearest>) {
+    if !*sent {
+        *sent = true;
+        inputs.send(OxydeInputNearest {
+            text: "What news from the village?".to_string(),
+            max_distance: None,
+        });
+    }
+}
+
+fn print_responses(
+    mut responses: EventReader<OxydeResponse>,
+    mut received: Local<usize>,
+    mut exit: EventWriter<AppExit>,
+) {
+    for response in responses.read() {
+        println!("> {}\n{}", response.input, response.text);
+        *received += 1;
+    }
+    // The greeting and the question
+    if *received >= 2 {
+        exit.send(AppExit::Success);
+    }
+}
@@ -0,0 +1,277 @@
+//! Bevy integration for Oxyde AI NPC agents
+//!
+//! Add [`OxydePlugin`] to an app, attach an [`OxydeAgentComponent`] to NPC
+//! entities and an [`OxydePlayer`] marker to the player. The plugin then:
+//!
+//! - starts agents when their component is added
+//! - fires [`OxydeProximity`] events and optional greetings when the player
+//!   enters or leaves an agent's proximity radius
+//! - keeps each agent's context in sync with the player's position
+//! - runs player input on a background Tokio runtime and delivers replies as
+//!   [`OxydeResponse`] events, without blocking the frame
+//! - mirrors each agent's emotional state into an [`OxydeEmotions`] component
+//!
+//! This mirrors the `OxydeAgentManager` shipped with the Unity bindings.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use oxyde::{Agent, AgentConfig};
+use tokio::runtime::Runtime;
+use tokio::task::JoinHandle;
+
+mod systems;
+
+pub use systems::nearest_agent;
+
+/// Message delivered when input is sent to the nearest agent and none is in range
+pub const NO_AGENT_IN_RANGE: &str = "No one is close enough to hear you.";
+
+/// Plugin wiring Oxyde agents into the Bevy schedule
+#[derive(Default)]
+pub struct OxydePlugin {
+    /// Plugin settings; see [`OxydeSettings`]
+    pub settings: OxydeSettings,
+}
+
+impl Plugin for OxydePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.settings.clone())
+            .insert_resource(OxydeRuntime::new())
+            .insert_resource(ContextSyncTimer(Timer::new(
+                self.settings.context_sync_interval,
+                TimerMode::Repeating,
+            )))
+            .insert_resource(EmotionSyncTimer(Timer::new(
+                self.settings.emotion_sync_interval,
+                TimerMode::Repeating,
+            )))
+            .add_event::<OxydeInput>()
+            .add_event::<OxydeInputNearest>()
+            .add_event::<OxydeResponse>()
+            .add_event::<OxydeFailed>()
+            .add_event::<OxydeProximity>()
+            .add_systems(
+                Update,
+                (
+                    systems::start_agents,
+                    systems::proximity_triggers,
+                    systems::sync_context,
+                    systems::route_nearest_input,
+                    systems::queue_input,
+                    systems::dispatch_input,
+                    systems::poll_responses,
+                    systems::sync_emotions,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Settings for [`OxydePlugin`]
+#[derive(Resource, Debug, Clone)]
+pub struct OxydeSettings {
+    /// How often agent contexts are refreshed with the player's position
+    pub context_sync_interval: Duration,
+
+    /// How often [`OxydeEmotions`] components are refreshed
+    pub emotion_sync_interval: Duration,
+
+    /// Default maximum distance for [`OxydeInputNearest`]
+    pub nearest_max_distance: f32,
+}
+
+impl Default for OxydeSettings {
+    fn default() -> Self {
+        Self {
+            context_sync_interval: Duration::from_millis(500),
+            emotion_sync_interval: Duration::from_millis(250),
+            nearest_max_distance: 5.0,
+        }
+    }
+}
+
+/// Tokio runtime the plugin runs agent work on
+#[derive(Resource)]
+pub struct OxydeRuntime(Runtime);
+
+impl OxydeRuntime {
+    fn new() -> Self {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .thread_name("oxyde")
+            .enable_all()
+            .build()
+            .expect("failed to start the Oxyde runtime");
+        Self(runtime)
+    }
+
+    /// Get the underlying runtime, e.g. to call agent APIs the plugin does
+    /// not wrap
+    pub fn runtime(&self) -> &Runtime {
+        &self.0
+    }
+}
+
+#[derive(Resource)]
+pub(crate) struct ContextSyncTimer(pub(crate) Timer);
+
+#[derive(Resource)]
+pub(crate) struct EmotionSyncTimer(pub(crate) Timer);
+
+/// An Oxyde agent attached to an NPC entity
+#[derive(Component)]
+pub struct OxydeAgentComponent {
+    agent: Arc<Agent>,
+
+    /// Distance at which the player counts as nearby
+    pub proximity_radius: f32,
+
+    /// Input sent to the agent when the player comes into range, if any
+    pub greeting_input: Option<String>,
+
+    pub(crate) started: bool,
+    pub(crate) player_nearby: bool,
+    pub(crate) pending: Option<(String, JoinHandle<oxyde::Result<String>>)>,
+    pub(crate) queue: VecDeque<String>,
+}
+
+impl OxydeAgentComponent {
+    /// Create a component from an agent configuration
+    pub fn new(config: AgentConfig) -> Self {
+        Self::from_agent(Arc::new(Agent::new_with_tts(config)))
+    }
+
+    /// Create a component wrapping an existing agent
+    pub fn from_agent(agent: Arc<Agent>) -> Self {
+        Self {
+            agent,
+            proximity_radius: 5.0,
+            greeting_input: None,
+            started: false,
+            player_nearby: false,
+            pending: None,
+            queue: VecDeque::new(),
+        }
+    }
+
+    /// Set the proximity radius
+    pub fn with_proximity_radius(mut self, radius: f32) -> Self {
+        self.proximity_radius = radius;
+        self
+    }
+
+    /// Send this input to the agent whenever the player comes into range
+    pub fn with_greeting(mut self, input: &str) -> Self {
+        self.greeting_input = Some(input.to_string());
+        self
+    }
+
+    /// Get the wrapped agent
+    pub fn agent(&self) -> &Arc<Agent> {
+        &self.agent
+    }
+
+    /// Whether the player is inside the proximity radius
+    pub fn player_nearby(&self) -> bool {
+        self.player_nearby
+    }
+
+    /// Whether the agent is generating a response
+    pub fn is_busy(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Number of inputs waiting for the current response to finish
+    pub fn queued_inputs(&self) -> usize {
+        self.queue.len()
+    }
+}
+
+/// Marker for the entity whose position drives proximity and context updates
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct OxydePlayer;
+
+/// Mirror of an agent's emotional state, updated by the plugin
+///
+/// Query this component to drive animation, facial expressions or audio.
+/// It is inserted on agent entities automatically.
+#[derive(Component, Debug, Default, Clone, PartialEq)]
+pub struct OxydeEmotions {
+    /// Emotion vector in Plutchik order: joy, trust, fear, surprise,
+    /// sadness, disgust, anger, anticipation
+    pub vector: [f32; 8],
+
+    /// Name of the strongest emotion
+    pub dominant: String,
+
+    /// Intensity of the strongest emotion
+    pub intensity: f32,
+
+    /// Emotional valence (-1.0 to 1.0)
+    pub valence: f32,
+
+    /// Emotional arousal (0.0 to 1.0)
+    pub arousal: f32,
+}
+
+/// Send player input to a specific agent entity
+#[derive(Event, Debug, Clone)]
+pub struct OxydeInput {
+    /// Agent entity
+    pub agent: Entity,
+
+    /// Player input
+    pub text: String,
+}
+
+/// Send player input to the nearest agent in range
+#[derive(Event, Debug, Clone)]
+pub struct OxydeInputNearest {
+    /// Player input
+    pub text: String,
+
+    /// Maximum distance; [`OxydeSettings::nearest_max_distance`] when `None`
+    pub max_distance: Option<f32>,
+}
+
+/// An agent finished responding to input
+///
+/// `agent` is `None` when the input was sent with [`OxydeInputNearest`] and
+/// no agent was in range; `text` is then [`NO_AGENT_IN_RANGE`].
+#[derive(Event, Debug, Clone)]
+pub struct OxydeResponse {
+    /// Agent entity
+    pub agent: Option<Entity>,
+
+    /// Input the response answers
+    pub input: String,
+
+    /// Response text
+    pub text: String,
+}
+
+/// An agent failed to process input
+#[derive(Event, Debug, Clone)]
+pub struct OxydeFailed {
+    /// Agent entity
+    pub agent: Entity,
+
+    /// Input that failed
+    pub input: String,
+
+    /// Error message
+    pub error: String,
+}
+
+/// The player entered or left an agent's proximity radius
+#[derive(Event, Debug, Clone, Copy)]
+pub struct OxydeProximity {
+    /// Agent entity
+    pub agent: Entity,
+
+    /// `true` when the player entered the radius, `false` when they left
+    pub entered: bool,
+}
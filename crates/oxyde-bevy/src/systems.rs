@@ -0,0 +1,239 @@
+//! Systems registered by [`OxydePlugin`](crate::OxydePlugin)
+
+use bevy::prelude::*;
+use oxyde::AgentContext;
+
+use crate::{
+    ContextSyncTimer, EmotionSyncTimer, OxydeAgentComponent, OxydeEmotions, OxydeFailed, OxydeInput,
+    OxydeInputNearest, OxydePlayer, OxydeProximity, OxydeResponse, OxydeRuntime, OxydeSettings,
+    NO_AGENT_IN_RANGE,
+};
+
+/// Find the agent entity nearest to a position within `max_distance`
+///
+/// # Arguments
+///
+/// * `agents` - Agent entities and their transforms
+/// * `position` - Position to measure from, usually the player's
+/// * `max_distance` - Maximum distance to consider
+pub fn nearest_agent<'a>(
+    agents: impl IntoIterator<Item = (Entity, &'a GlobalTransform)>,
+    position: Vec3,
+    max_distance: f32,
+) -> Option<Entity> {
+    agents
+        .into_iter()
+        .map(|(entity, transform)| (entity, transform.translation().distance(position)))
+        .filter(|(_, distance)| *distance <= max_distance)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(entity, _)| entity)
+}
+
+pub(crate) fn start_agents(
+    mut commands: Commands,
+    runtime: Res<OxydeRuntime>,
+    mut agents: Query<(Entity, &mut OxydeAgentComponent)>,
+) {
+    for (entity, mut component) in agents.iter_mut().filter(|(_, c)| !c.started) {
+        component.started = true;
+        let agent = component.agent.clone();
+        runtime.runtime().spawn(async move {
+            if let Err(e) = agent.start().await {
+                error!("Failed to start agent {}: {}", agent.name(), e);
+            }
+        });
+        commands.entity(entity).insert(OxydeEmotions::default());
+    }
+}
+
+pub(crate) fn proximity_triggers(
+    player: Query<&GlobalTransform, With<OxydePlayer>>,
+    mut agents: Query<(Entity, &mut OxydeAgentComponent, &GlobalTransform)>,
+    mut proximity: EventWriter<OxydeProximity>,
+) {
+    let Ok(player) = player.get_single() else {
+        return;
+    };
+    let position = player.translation();
+
+    for (entity, mut component, transform) in agents.iter_mut() {
+        let nearby = transform.translation().distance(position) <= component.proximity_radius;
+        if nearby == component.player_nearby {
+            continue;
+        }
+        component.player_nearby = nearby;
+        proximity.send(OxydeProximity {
+            agent: entity,
+            entered: nearby,
+        });
+        if nearby {
+            if let Some(greeting) = component.greeting_input.clone() {
+                component.queue.push_back(greeting);
+            }
+        }
+    }
+}
+
+pub(crate) fn sync_context(
+    time: Res<Time>,
+    mut timer: ResMut<ContextSyncTimer>,
+    runtime: Res<OxydeRuntime>,
+    player: Query<&GlobalTransform, With<OxydePlayer>>,
+    agents: Query<(&OxydeAgentComponent, &GlobalTransform)>,
+) {
+    if !timer.0.tick(time.delta()).just_finished() {
+        return;
+    }
+    let Ok(player) = player.get_single() else {
+        return;
+    };
+    let position = player.translation();
+
+    for (component, transform) in agents.iter() {
+        let mut context = AgentContext::new();
+        context.insert(
+            "player_position".to_string(),
+            serde_json::json!([position.x, position.y, position.z]),
+        );
+        context.insert(
+            "player_distance".to_string(),
+            serde_json::json!(transform.translation().distance(position)),
+        );
+        context.insert("player_nearby".to_string(), serde_json::json!(component.player_nearby));
+
+        let agent = component.agent.clone();
+        runtime.runtime().spawn(async move { agent.update_context(context).await });
+    }
+}
+
+pub(crate) fn route_nearest_input(
+    settings: Res<OxydeSettings>,
+    mut requests: EventReader<OxydeInputNearest>,
+    player: Query<&GlobalTransform, With<OxydePlayer>>,
+    agents: Query<(Entity, &GlobalTransform), With<OxydeAgentComponent>>,
+    mut inputs: EventWriter<OxydeInput>,
+    mut responses: EventWriter<OxydeResponse>,
+) {
+    let position = player.get_single().ok().map(|p| p.translation());
+
+    for request in requests.read() {
+        let max_distance = request.max_distance.unwrap_or(settings.nearest_max_distance);
+        match position.and_then(|position| nearest_agent(agents.iter(), position, max_distance)) {
+            Some(agent) => {
+                inputs.send(OxydeInput {
+                    agent,
+                    text: request.text.clone(),
+                });
+            }
+            None => {
+                responses.send(OxydeResponse {
+                    agent: None,
+                    input: request.text.clone(),
+                    text: NO_AGENT_IN_RANGE.to_string(),
+                });
+            }
+        }
+    }
+}
+
+pub(crate) fn queue_input(
+    mut inputs: EventReader<OxydeInput>,
+    mut agents: Query<&mut OxydeAgentComponent>,
+    mut failures: EventWriter<OxydeFailed>,
+) {
+    for input in inputs.read() {
+        match agents.get_mut(input.agent) {
+            Ok(mut component) => component.queue.push_back(input.text.clone()),
+            Err(_) => {
+                failures.send(OxydeFailed {
+                    agent: input.agent,
+                    input: input.text.clone(),
+                    error: "Entity has no OxydeAgentComponent".to_string(),
+                });
+            }
+        }
+    }
+}
+
+pub(crate) fn dispatch_input(runtime: Res<OxydeRuntime>, mut agents: Query<&mut OxydeAgentComponent>) {
+    for mut component in agents.iter_mut() {
+        if component.pending.is_some() {
+            continue;
+        }
+        let Some(input) = component.queue.pop_front() else {
+            continue;
+        };
+        let agent = component.agent.clone();
+        let text = input.clone();
+        let handle = runtime.runtime().spawn(async move { agent.process_input(&text).await });
+        component.pending = Some((input, handle));
+    }
+}
+
+pub(crate) fn poll_responses(
+    runtime: Res<OxydeRuntime>,
+    mut agents: Query<(Entity, &mut OxydeAgentComponent)>,
+    mut responses: EventWriter<OxydeResponse>,
+    mut failures: EventWriter<OxydeFailed>,
+) {
+    for (entity, mut component) in agents.iter_mut() {
+        let finished = matches!(&component.pending, Some((_, handle)) if handle.is_finished());
+        if !finished {
+            continue;
+        }
+        let Some((input, handle)) = component.pending.take() else {
+            continue;
+        };
+        // The task has finished, so this does not block the frame
+        match runtime.runtime().block_on(handle) {
+            Ok(Ok(text)) => {
+                responses.send(OxydeResponse {
+                    agent: Some(entity),
+                    input,
+                    text,
+                });
+            }
+            Ok(Err(e)) => {
+                failures.send(OxydeFailed {
+                    agent: entity,
+                    input,
+                    error: e.to_string(),
+                });
+            }
+            Err(e) => {
+                failures.send(OxydeFailed {
+                    agent: entity,
+                    input,
+                    error: format!("Agent task failed: {}", e),
+                });
+            }
+        }
+    }
+}
+
+pub(crate) fn sync_emotions(
+    time: Res<Time>,
+    mut timer: ResMut<EmotionSyncTimer>,
+    runtime: Res<OxydeRuntime>,
+    mut agents: Query<(&OxydeAgentComponent, &mut OxydeEmotions)>,
+) {
+    if !timer.0.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    for (component, mut emotions) in agents.iter_mut() {
+        let state = runtime.runtime().block_on(component.agent.emotional_state());
+        let (dominant, intensity) = state.dominant_emotion();
+        let updated = OxydeEmotions {
+            vector: state.as_vector(),
+            dominant: dominant.to_string(),
+            intensity,
+            valence: state.valence(),
+            arousal: state.arousal(),
+        };
+        // Only write on change so `Changed<OxydeEmotions>` filters stay useful
+        if *emotions != updated {
+            *emotions = updated;
+        }
+    }
+}
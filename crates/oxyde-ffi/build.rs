@@ -0,0 +1,34 @@
+//! Regenerates `include/oxyde.h` with cbindgen when requested
+//!
+//! The header is checked in so engine plugins can consume it without a Rust
+//! toolchain. Set `OXYDE_GENERATE_HEADER=1` to regenerate it from the sources
+//! with the `cbindgen` CLI after changing the ABI. CI checks the header with
+//! cbindgen 0.29.4, so install that version
+//! (`cargo install cbindgen --version 0.29.4 --locked`).
+
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-changed=src");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    println!("cargo:rerun-if-env-changed=OXYDE_GENERATE_HEADER");
+
+    if env::var_os("OXYDE_GENERATE_HEADER").is_none() {
+        return;
+    }
+
+    let crate_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR not set"));
+    let status = Command::new("cbindgen")
+        .current_dir(&crate_dir)
+        .args(["--config", "cbindgen.toml", "--crate", "oxyde-ffi", "--output"])
+        .arg(crate_dir.join("include").join("oxyde.h"))
+        .status();
+
+    match status {
+        Ok(status) if status.success() => {}
+        Ok(status) => panic!("cbindgen failed with {}", status),
+        Err(e) => panic!("Failed to run cbindgen (install it with `cargo install cbindgen --version 0.29.4 --locked`): {}", e),
+    }
+}
@@ -0,0 +1,518 @@
+#ifndef OXYDE_H
+#define OXYDE_H
+
+/* Generated by cbindgen from crates/oxyde-ffi. Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Version of the C ABI exposed by this crate
+#define OXYDE_ABI_VERSION 1
+
+// Result code returned by every fallible function
+typedef enum OxydeStatus {
+  // The call succeeded
+  OXYDE_STATUS_OK = 0,
+  // A required pointer argument was null
+  OXYDE_STATUS_NULL_POINTER = 1,
+  // A string argument was not valid UTF-8
+  OXYDE_STATUS_INVALID_UTF8 = 2,
+  // An argument had an invalid value
+  OXYDE_STATUS_INVALID_ARGUMENT = 3,
+  // The agent configuration was invalid
+  OXYDE_STATUS_CONFIGURATION = 4,
+  // Inference failed
+  OXYDE_STATUS_INFERENCE = 5,
+  // The memory system failed
+  OXYDE_STATUS_MEMORY = 6,
+  // Speech synthesis failed
+  OXYDE_STATUS_AUDIO = 7,
+  // The SDK panicked; the agent may be in an inconsistent state
+  OXYDE_STATUS_PANIC = 8,
+  // Any other failure
+  OXYDE_STATUS_INTERNAL = 9,
+  // The agent is busy with another input and rejected this one
+  OXYDE_STATUS_BUSY = 10,
+  // The handle's capability does not allow the call
+  OXYDE_STATUS_PERMISSION_DENIED = 11,
+} OxydeStatus;
+
+// What calls an agent handle may make, each level allowing those below it
+typedef enum OxydeCapability {
+  // Read emotions, memories, sessions, dialogue lines and modifiers
+  OXYDE_CAPABILITY_READ_ONLY = 0,
+  // Also send input, start and stop the agent, and add to its context,
+  // emotions, memories and facts
+  OXYDE_CAPABILITY_INTERACT = 1,
+  // Also clear memories and forget facts and memory categories
+  OXYDE_CAPABILITY_FULL = 2,
+} OxydeCapability;
+
+// Opaque agent handle
+typedef struct OxydeAgent OxydeAgent;
+
+// Opaque handle to speech being synthesized in chunks
+typedef struct OxydeAudioStream OxydeAudioStream;
+
+// Emotion values, each in the range -1.0 to 1.0
+typedef struct OxydeEmotions {
+  // Joy
+  float joy;
+  // Trust
+  float trust;
+  // Fear
+  float fear;
+  // Surprise
+  float surprise;
+  // Sadness
+  float sadness;
+  // Disgust
+  float disgust;
+  // Anger
+  float anger;
+  // Anticipation
+  float anticipation;
+} OxydeEmotions;
+
+// Emotions changed since a poll
+typedef struct OxydeEmotionChanges {
+  // Sequence number to pass to the next poll
+  uint64_t sequence;
+  // Changed emotions as bits in field order, `joy` being bit 0
+  uint32_t changed;
+  // New values of the changed emotions; unchanged ones are 0.0
+  struct OxydeEmotions emotions;
+} OxydeEmotionChanges;
+
+// Synthesized speech owned by the caller until passed to [`oxyde_audio_free`]
+typedef struct OxydeAudio {
+  // Encoded audio bytes (MP3)
+  uint8_t *data;
+  // Number of bytes in `data`
+  size_t len;
+  // Sample rate in Hz
+  uint32_t sample_rate;
+  // Number of channels
+  uint8_t channels;
+  // Duration in milliseconds
+  uint32_t duration_ms;
+} OxydeAudio;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Get the version of the C ABI
+uint32_t oxyde_abi_version(void);
+
+// Get the message for the last error on the calling thread
+//
+// Returns null if no error has occurred. The string is owned by the library
+// and stays valid until the next failing call on the same thread.
+const char *oxyde_last_error(void);
+
+// Free a string returned by this library
+//
+// # Safety
+//
+// `value` must be null or a string returned through an out-pointer by this
+// library that has not already been freed.
+void oxyde_string_free(char *value);
+
+// Create an agent from a JSON configuration string
+//
+// Returns `InvalidArgument` without creating the agent if `out_agent` is
+// null.
+//
+// # Safety
+//
+// `config_json` must be a NUL-terminated string and `out_agent` must be
+// null or valid for writes.
+enum OxydeStatus oxyde_agent_create_from_json(const char *config_json,
+                                              struct OxydeAgent **out_agent);
+
+// Create an agent from a configuration file (JSON, YAML, or TOML)
+//
+// Returns `InvalidArgument` without creating the agent if `out_agent` is
+// null.
+//
+// # Safety
+//
+// `config_path` must be a NUL-terminated string and `out_agent` must be
+// null or valid for writes.
+enum OxydeStatus oxyde_agent_create_from_file(const char *config_path,
+                                              struct OxydeAgent **out_agent);
+
+// Destroy an agent handle
+//
+// # Safety
+//
+// `agent` must be null or a handle returned by `oxyde_agent_create_*` that
+// has not already been destroyed. The handle must not be used afterwards.
+void oxyde_agent_destroy(struct OxydeAgent *agent);
+
+// Create another handle to an agent, limited to a capability
+//
+// Hand the new handle to mods and scripts that should not have full
+// control. Both handles use the same agent, which lives until every handle
+// is destroyed. A handle cannot grant more than its own capability.
+// `capability` is an [`OxydeCapability`] value; anything else returns
+// `InvalidArgument`.
+//
+// # Safety
+//
+// `agent` must be a live agent handle and `out_agent` valid for writes. The
+// new handle must be released with [`oxyde_agent_destroy`].
+enum OxydeStatus oxyde_agent_share(const struct OxydeAgent *agent,
+                                   uint32_t capability,
+                                   struct OxydeAgent **out_agent);
+
+// Get the capability of an agent handle
+//
+// # Safety
+//
+// `agent` must be a live agent handle and `out_capability` valid for writes.
+enum OxydeStatus oxyde_agent_capability(const struct OxydeAgent *agent,
+                                        enum OxydeCapability *out_capability);
+
+// Get the audit trail of gated calls as a JSON array
+//
+// Lists the most recent denied calls and calls needing full control, oldest
+// first, each with its `timestamp_ms`, `agent` name, `call`, the handle's
+// `capability` and whether it was `allowed`.
+//
+// # Safety
+//
+// `out_json` must be valid for writes. The JSON must be freed with
+// [`oxyde_string_free`].
+enum OxydeStatus oxyde_audit_log(char **out_json);
+
+// Start an agent
+//
+// # Safety
+//
+// `agent` must be a live agent handle.
+enum OxydeStatus oxyde_agent_start(const struct OxydeAgent *agent);
+
+// Stop an agent
+//
+// # Safety
+//
+// `agent` must be a live agent handle.
+enum OxydeStatus oxyde_agent_stop(const struct OxydeAgent *agent);
+
+// Merge a JSON object into the agent's context
+//
+// Returns `InvalidArgument` without applying any key if the update does not
+// fit the agent's context schema.
+//
+// # Safety
+//
+// `agent` must be a live agent handle and `context_json` a NUL-terminated
+// string.
+enum OxydeStatus oxyde_agent_update_context(const struct OxydeAgent *agent,
+                                            const char *context_json);
+
+// Process player input and return the agent's response
+//
+// # Safety
+//
+// `agent` must be a live agent handle, `input` a NUL-terminated string, and
+// `out_response` valid for writes. The response must be freed with
+// [`oxyde_string_free`].
+enum OxydeStatus oxyde_agent_process_input(const struct OxydeAgent *agent,
+                                           const char *input,
+                                           char **out_response);
+
+// Process player input and return the response with a suggested animation
+//
+// The JSON object has a `text` string and an `annotation` object whose
+// `gesture`, `facial`, and `move_to` (`{x, y, z}`) keys may be null.
+//
+// # Safety
+//
+// `agent` must be a live agent handle, `input` a NUL-terminated string, and
+// `out_json` valid for writes. The JSON must be freed with
+// [`oxyde_string_free`].
+enum OxydeStatus oxyde_agent_process_input_annotated(const struct OxydeAgent *agent,
+                                                     const char *input,
+                                                     char **out_json);
+
+// Process input from a player identified by the game
+//
+// The player's conversation history, relationship and memories about them
+// are kept in a session under `player_id`, and saved to the configured
+// session directory.
+//
+// # Safety
+//
+// `agent` must be a live agent handle, `player_id` and `input`
+// NUL-terminated strings, and `out_response` valid for writes. The response
+// must be freed with [`oxyde_string_free`].
+enum OxydeStatus oxyde_agent_process_input_for_player(const struct OxydeAgent *agent,
+                                                      const char *player_id,
+                                                      const char *input,
+                                                      char **out_response);
+
+// Get a player's session as JSON
+//
+// The object has the player's `turns`, `relationship`, recent `history` and
+// the `memories` about them. A player without a session gets an empty one,
+// which is not kept.
+//
+// # Safety
+//
+// `agent` must be a live agent handle, `player_id` a NUL-terminated string,
+// and `out_json` valid for writes. The JSON must be freed with
+// [`oxyde_string_free`].
+enum OxydeStatus oxyde_agent_get_player_session(const struct OxydeAgent *agent,
+                                                const char *player_id,
+                                                char **out_json);
+
+// Get the agent's current emotions
+//
+// # Safety
+//
+// `agent` must be a live agent handle and `out_emotions` valid for writes.
+enum OxydeStatus oxyde_agent_get_emotions(const struct OxydeAgent *agent,
+                                          struct OxydeEmotions *out_emotions);
+
+// Get the emotions changed since a previous poll
+//
+// Cheaper than [`oxyde_agent_get_emotions`] for engines polling every
+// frame: `changed` is 0 when nothing moved. Only agents feeling through
+// Plutchik's emotions can be polled this way; others return
+// `InvalidArgument` and are polled with [`oxyde_agent_poll_emotions_json`].
+//
+// # Safety
+//
+// `agent` must be a live agent handle and `out_changes` valid for writes.
+// `since_sequence` is the `sequence` of the previous poll, or 0.
+enum OxydeStatus oxyde_agent_poll_emotions(const struct OxydeAgent *agent,
+                                           uint64_t since_sequence,
+                                           struct OxydeEmotionChanges *out_changes);
+
+// Get the emotions changed since a previous poll as JSON
+//
+// Writes the `sequence` to pass to the next poll, the affect model's
+// `dimensions` in order, and the changed `emotions` by name with their new
+// values. Works whatever affect model the agent uses.
+//
+// # Safety
+//
+// `agent` must be a live agent handle and `out_json` valid for writes. The
+// JSON must be freed with [`oxyde_string_free`].
+enum OxydeStatus oxyde_agent_poll_emotions_json(const struct OxydeAgent *agent,
+                                                uint64_t since_sequence,
+                                                char **out_json);
+
+// Read the oldest line waiting in the agent's dialogue queue
+//
+// Writes the line as JSON with its `sequence`, `kind` (`response` or
+// `filler`), `text`, `timestamp_ms` and `expires_at_ms`, or NULL when no
+// line is waiting. Lines stay queued; use [`oxyde_agent_dialogue_pop`] to
+// remove them.
+//
+// # Safety
+//
+// `agent` must be a live agent handle and `out_json` valid for writes. The
+// JSON must be freed with [`oxyde_string_free`].
+enum OxydeStatus oxyde_agent_dialogue_peek(const struct OxydeAgent *agent, char **out_json);
+
+// Remove and return the oldest line waiting in the agent's dialogue queue
+//
+// Writes the line as JSON like [`oxyde_agent_dialogue_peek`], or NULL when
+// no line is waiting.
+//
+// # Safety
+//
+// `agent` must be a live agent handle and `out_json` valid for writes. The
+// JSON must be freed with [`oxyde_string_free`].
+enum OxydeStatus oxyde_agent_dialogue_pop(const struct OxydeAgent *agent, char **out_json);
+
+// Adjust one emotion by a delta
+//
+// # Safety
+//
+// `agent` must be a live agent handle and `emotion` a NUL-terminated string
+// naming one of the eight emotions.
+enum OxydeStatus oxyde_agent_update_emotion(const struct OxydeAgent *agent,
+                                            const char *emotion,
+                                            float delta);
+
+// Add a memory to the agent
+//
+// # Safety
+//
+// `agent` must be a live agent handle; `category` and `content` must be
+// NUL-terminated strings. `category` is one of "episodic", "semantic",
+// "procedural", or "emotional".
+enum OxydeStatus oxyde_agent_add_memory(const struct OxydeAgent *agent,
+                                        const char *category,
+                                        const char *content,
+                                        double importance);
+
+// Get the number of memories the agent holds
+//
+// # Safety
+//
+// `agent` must be a live agent handle and `out_count` valid for writes.
+enum OxydeStatus oxyde_agent_memory_count(const struct OxydeAgent *agent, uint32_t *out_count);
+
+// Clear all non-permanent memories
+//
+// # Safety
+//
+// `agent` must be a live agent handle. `out_removed` may be null; otherwise
+// it must be valid for writes.
+enum OxydeStatus oxyde_agent_clear_memories(const struct OxydeAgent *agent, uint32_t *out_removed);
+
+// Forget every non-permanent memory of a category
+//
+// # Safety
+//
+// `agent` must be a live agent handle and `category` a NUL-terminated
+// string naming a memory category. `out_removed` may be null; otherwise it
+// must be valid for writes.
+enum OxydeStatus oxyde_agent_forget_category(const struct OxydeAgent *agent,
+                                             const char *category,
+                                             uint32_t *out_removed);
+
+// Retrieve memories relevant to a query as a JSON array
+//
+// # Safety
+//
+// `agent` must be a live agent handle, `query` a NUL-terminated string, and
+// `out_json` valid for writes. The JSON must be freed with
+// [`oxyde_string_free`].
+enum OxydeStatus oxyde_agent_recall(const struct OxydeAgent *agent,
+                                    const char *query,
+                                    uint32_t limit,
+                                    char **out_json);
+
+// Answer a question about what the agent remembers
+//
+// The JSON object has an `answer` string and a `memory_ids` array with the
+// IDs of the memories supporting it.
+//
+// # Safety
+//
+// `agent` must be a live agent handle, `question` a NUL-terminated string,
+// and `out_json` valid for writes. The JSON must be freed with
+// [`oxyde_string_free`].
+enum OxydeStatus oxyde_agent_ask_memory(const struct OxydeAgent *agent,
+                                        const char *question,
+                                        char **out_json);
+
+// Teach the agent a fact, replacing any fact learned under the same key
+//
+// The fact is stored as a permanent, high-importance memory and recalled by
+// the next relevant input.
+//
+// # Safety
+//
+// `agent` must be a live agent handle; `key` and `statement` must be
+// NUL-terminated strings. `source` may be null; otherwise it must be a
+// NUL-terminated string. `out_memory_id` may be null; otherwise it must be
+// valid for writes, and the ID must be freed with [`oxyde_string_free`].
+enum OxydeStatus oxyde_agent_learn_fact(const struct OxydeAgent *agent,
+                                        const char *key,
+                                        const char *statement,
+                                        const char *source,
+                                        char **out_memory_id);
+
+// Forget the fact learned under a key
+//
+// # Safety
+//
+// `agent` must be a live agent handle and `key` a NUL-terminated string.
+// `out_forgotten` may be null; otherwise it must be valid for writes.
+enum OxydeStatus oxyde_agent_forget_fact(const struct OxydeAgent *agent,
+                                         const char *key,
+                                         bool *out_forgotten);
+
+// Compute a modifier, such as a price multiplier, from the agent's
+// relationship with the current player and its current emotions
+//
+// The built-in modifiers are "price" and "haggle"; unknown names fail with
+// `InvalidArgument`.
+//
+// # Safety
+//
+// `agent` must be a live agent handle, `name` a NUL-terminated string, and
+// `out_value` valid for writes.
+enum OxydeStatus oxyde_agent_modifier(const struct OxydeAgent *agent,
+                                      const char *name,
+                                      float *out_value);
+
+// Synthesize speech for text using the agent's voice and current emotions
+//
+// Requires a `tts` section in the agent configuration.
+//
+// # Safety
+//
+// `agent` must be a live agent handle, `text` a NUL-terminated string, and
+// `out_audio` valid for writes. The audio must be freed with
+// [`oxyde_audio_free`].
+enum OxydeStatus oxyde_agent_speak(const struct OxydeAgent *agent,
+                                   const char *text,
+                                   float urgency,
+                                   struct OxydeAudio *out_audio);
+
+// Start synthesizing speech for text, delivered in chunks
+//
+// Pull chunks with [`oxyde_audio_stream_next`] as they are generated, so
+// playback can start before synthesis finishes. Requires a `tts` section in
+// the agent configuration.
+//
+// # Safety
+//
+// `agent` must be a live agent handle, `text` a NUL-terminated string, and
+// `out_stream` valid for writes. The stream must be released with
+// [`oxyde_audio_stream_destroy`].
+enum OxydeStatus oxyde_agent_speak_stream(const struct OxydeAgent *agent,
+                                          const char *text,
+                                          float urgency,
+                                          struct OxydeAudioStream **out_stream);
+
+// Wait for the next chunk of a speech stream
+//
+// On success `out_done` is set to true once the stream is complete, in
+// which case `out_audio` is left empty. Otherwise `out_audio` holds the next
+// chunk of encoded audio, which must be freed with [`oxyde_audio_free`].
+// Chunk durations are not known and are reported as 0.
+//
+// # Safety
+//
+// `stream` must be a live stream handle and `out_audio` and `out_done` valid
+// for writes.
+enum OxydeStatus oxyde_audio_stream_next(struct OxydeAudioStream *stream,
+                                         struct OxydeAudio *out_audio,
+                                         bool *out_done);
+
+// Destroy a speech stream, cancelling synthesis if it is still running
+//
+// # Safety
+//
+// `stream` must be null or a handle returned by [`oxyde_agent_speak_stream`]
+// that has not already been destroyed.
+void oxyde_audio_stream_destroy(struct OxydeAudioStream *stream);
+
+// Free audio returned by [`oxyde_agent_speak`] or [`oxyde_audio_stream_next`]
+//
+// The struct's fields are reset so a double free is harmless.
+//
+// # Safety
+//
+// `audio` must be null or point to an [`OxydeAudio`] filled in by
+// [`oxyde_agent_speak`] or [`oxyde_audio_stream_next`].
+void oxyde_audio_free(struct OxydeAudio *audio);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* OXYDE_H */
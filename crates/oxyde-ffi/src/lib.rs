@@ -0,0 +1,1460 @@
+//! # oxyde-ffi
+//!
+//! Stable C ABI for the Oxyde SDK.
+//!
+//! This crate exposes a curated set of `extern "C"` functions covering the
+//! agent lifecycle, input processing, memory, emotions, and audio. Engine
+//! plugins should include the generated `include/oxyde.h` header rather than
+//! hand-writing extern declarations.
+//!
+//! ## Conventions
+//!
+//! - Agents are opaque [`OxydeAgent`] handles created by
+//!   `oxyde_agent_create_*` and released with [`oxyde_agent_destroy`].
+//! - Every fallible function returns an [`OxydeStatus`]; on failure a
+//!   description is available from [`oxyde_last_error`] on the same thread.
+//! - Results are written through out-pointers. Strings returned this way must
+//!   be released with [`oxyde_string_free`] and audio with [`oxyde_audio_free`].
+//! - Streaming speech is pulled chunk by chunk from an opaque
+//!   [`OxydeAudioStream`], released with [`oxyde_audio_stream_destroy`].
+//! - Calls block until the operation completes; engines that cannot block the
+//!   main thread should call from a worker thread.
+//! - Each handle has an [`OxydeCapability`]. Handles from
+//!   `oxyde_agent_create_*` have full control; [`oxyde_agent_share`] hands
+//!   mods and scripts a handle limited to reading or interacting, so they
+//!   cannot wipe the NPC's memories. Calls beyond a handle's capability fail
+//!   with `PermissionDenied`, and denied and destructive calls are recorded in
+//!   an audit log read with [`oxyde_audit_log`].
+//!
+//! The ABI only changes in backwards-compatible ways while
+//! [`OXYDE_ABI_VERSION`] stays the same.
+
+#![warn(missing_docs)]
+
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::collections::VecDeque;
+use std::ptr;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use oxyde::agent::Agent;
+use oxyde::audio::AudioStream;
+use oxyde::config::AgentConfig;
+use oxyde::dialogue_queue::DialogueLine;
+use oxyde::memory::MemoryCategory;
+use oxyde::oxyde_game::emotion::EMOTION_NAMES;
+use oxyde::session::PlayerSession;
+use oxyde::{AgentContext, OxydeError};
+
+/// Version of the C ABI exposed by this crate
+pub const OXYDE_ABI_VERSION: u32 = 1;
+
+/// Audit log entries kept before the oldest is dropped
+const AUDIT_LOG_CAPACITY: usize = 256;
+
+/// Log target of audit records
+const AUDIT_TARGET: &str = "oxyde_ffi::audit";
+
+lazy_static::lazy_static! {
+    static ref RUNTIME: tokio::runtime::Runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("Failed to create global Tokio runtime");
+    static ref AUDIT_LOG: Mutex<VecDeque<serde_json::Value>> = Mutex::new(VecDeque::new());
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Result code returned by every fallible function
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OxydeStatus {
+    /// The call succeeded
+    Ok = 0,
+    /// A required pointer argument was null
+    NullPointer = 1,
+    /// A string argument was not valid UTF-8
+    InvalidUtf8 = 2,
+    /// An argument had an invalid value
+    InvalidArgument = 3,
+    /// The agent configuration was invalid
+    Configuration = 4,
+    /// Inference failed
+    Inference = 5,
+    /// The memory system failed
+    Memory = 6,
+    /// Speech synthesis failed
+    Audio = 7,
+    /// The SDK panicked; the agent may be in an inconsistent state
+    Panic = 8,
+    /// Any other failure
+    Internal = 9,
+    /// The agent is busy with another input and rejected this one
+    Busy = 10,
+    /// The handle's capability does not allow the call
+    PermissionDenied = 11,
+}
+
+/// What calls an agent handle may make, each level allowing those below it
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum OxydeCapability {
+    /// Read emotions, memories, sessions, dialogue lines and modifiers
+    ReadOnly = 0,
+    /// Also send input, start and stop the agent, and add to its context,
+    /// emotions, memories and facts
+    Interact = 1,
+    /// Also clear memories and forget facts and memory categories
+    Full = 2,
+}
+
+impl OxydeCapability {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::ReadOnly => "read-only",
+            Self::Interact => "interact",
+            Self::Full => "full",
+        }
+    }
+}
+
+impl TryFrom<u32> for OxydeCapability {
+    type Error = u32;
+
+    fn try_from(value: u32) -> Result<Self, u32> {
+        match value {
+            0 => Ok(Self::ReadOnly),
+            1 => Ok(Self::Interact),
+            2 => Ok(Self::Full),
+            _ => Err(value),
+        }
+    }
+}
+
+impl From<&OxydeError> for OxydeStatus {
+    fn from(error: &OxydeError) -> Self {
+        match error {
+            OxydeError::ConfigurationError(_) | OxydeError::SerializationError(_) => Self::Configuration,
+            OxydeError::InferenceError(_) | OxydeError::RequestError(_) => Self::Inference,
+            OxydeError::MemoryError(_) => Self::Memory,
+            OxydeError::AudioError(_) => Self::Audio,
+            OxydeError::Busy(_) => Self::Busy,
+            OxydeError::ContextError(_) => Self::InvalidArgument,
+            _ => Self::Internal,
+        }
+    }
+}
+
+/// Opaque agent handle
+pub struct OxydeAgent {
+    inner: Arc<Agent>,
+    capability: OxydeCapability,
+}
+
+/// Opaque handle to speech being synthesized in chunks
+pub struct OxydeAudioStream {
+    inner: AudioStream,
+}
+
+/// Emotion values, each in the range -1.0 to 1.0
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct OxydeEmotions {
+    /// Joy
+    pub joy: f32,
+    /// Trust
+    pub trust: f32,
+    /// Fear
+    pub fear: f32,
+    /// Surprise
+    pub surprise: f32,
+    /// Sadness
+    pub sadness: f32,
+    /// Disgust
+    pub disgust: f32,
+    /// Anger
+    pub anger: f32,
+    /// Anticipation
+    pub anticipation: f32,
+}
+
+/// Emotions changed since a poll
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct OxydeEmotionChanges {
+    /// Sequence number to pass to the next poll
+    pub sequence: u64,
+    /// Changed emotions as bits in field order, `joy` being bit 0
+    pub changed: u32,
+    /// New values of the changed emotions; unchanged ones are 0.0
+    pub emotions: OxydeEmotions,
+}
+
+impl From<[f32; 8]> for OxydeEmotions {
+    fn from([joy, trust, fear, surprise, sadness, disgust, anger, anticipation]: [f32; 8]) -> Self {
+        Self {
+            joy,
+            trust,
+            fear,
+            surprise,
+            sadness,
+            disgust,
+            anger,
+            anticipation,
+        }
+    }
+}
+
+/// Synthesized speech owned by the caller until passed to [`oxyde_audio_free`]
+#[repr(C)]
+#[derive(Debug)]
+pub struct OxydeAudio {
+    /// Encoded audio bytes (MP3)
+    pub data: *mut u8,
+    /// Number of bytes in `data`
+    pub len: usize,
+    /// Sample rate in Hz
+    pub sample_rate: u32,
+    /// Number of channels
+    pub channels: u8,
+    /// Duration in milliseconds
+    pub duration_ms: u32,
+}
+
+/// Failure carried through the internal helpers
+struct FfiError {
+    status: OxydeStatus,
+    message: String,
+}
+
+impl FfiError {
+    fn new(status: OxydeStatus, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<OxydeError> for FfiError {
+    fn from(error: OxydeError) -> Self {
+        Self::new(OxydeStatus::from(&error), error.to_string())
+    }
+}
+
+type FfiResult<T> = std::result::Result<T, FfiError>;
+
+fn set_last_error(message: &str) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|slot| *slot.borrow_mut() = Some(message));
+}
+
+/// Run an FFI body, converting errors and panics into status codes
+fn guard<F>(body: F) -> OxydeStatus
+where
+    F: FnOnce() -> FfiResult<()>,
+{
+    match catch_unwind(AssertUnwindSafe(body)) {
+        Ok(Ok(())) => OxydeStatus::Ok,
+        Ok(Err(error)) => {
+            set_last_error(&error.message);
+            error.status
+        }
+        Err(_) => {
+            set_last_error("Oxyde panicked during the call");
+            OxydeStatus::Panic
+        }
+    }
+}
+
+/// Borrow a C string argument as UTF-8
+///
+/// # Safety
+///
+/// `ptr` must be null or point to a NUL-terminated string that outlives `'a`.
+unsafe fn str_arg<'a>(ptr: *const c_char, name: &str) -> FfiResult<&'a str> {
+    if ptr.is_null() {
+        return Err(FfiError::new(OxydeStatus::NullPointer, format!("`{}` is null", name)));
+    }
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map_err(|_| FfiError::new(OxydeStatus::InvalidUtf8, format!("`{}` is not valid UTF-8", name)))
+}
+
+/// Borrow an agent handle argument for a call needing a capability
+///
+/// Denied calls and calls needing full control are audited.
+///
+/// # Safety
+///
+/// `agent` must be null or a live handle returned by `oxyde_agent_create_*`
+/// or [`oxyde_agent_share`].
+unsafe fn agent_arg<'a>(agent: *const OxydeAgent, required: OxydeCapability, call: &str) -> FfiResult<&'a Agent> {
+    let handle = agent
+        .as_ref()
+        .ok_or_else(|| FfiError::new(OxydeStatus::NullPointer, "`agent` is null"))?;
+    let allowed = handle.capability >= required;
+    if !allowed || required == OxydeCapability::Full {
+        audit(&handle.inner, call, handle.capability, allowed);
+    }
+    if !allowed {
+        return Err(FfiError::new(
+            OxydeStatus::PermissionDenied,
+            format!(
+                "`{}` needs {} capability but the handle has {}",
+                call,
+                required.name(),
+                handle.capability.name()
+            ),
+        ));
+    }
+    Ok(handle.inner.as_ref())
+}
+
+/// Record a gated call in the log and the audit trail
+fn audit(agent: &Agent, call: &str, capability: OxydeCapability, allowed: bool) {
+    if allowed {
+        log::info!(target: AUDIT_TARGET, "{} allowed on agent {} ({})", call, agent.name(), capability.name());
+    } else {
+        log::warn!(target: AUDIT_TARGET, "{} denied on agent {} ({})", call, agent.name(), capability.name());
+    }
+    let timestamp_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or(0);
+    let mut trail = AUDIT_LOG.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if trail.len() >= AUDIT_LOG_CAPACITY {
+        trail.pop_front();
+    }
+    trail.push_back(serde_json::json!({
+        "timestamp_ms": timestamp_ms,
+        "agent": agent.name(),
+        "call": call,
+        "capability": capability.name(),
+        "allowed": allowed,
+    }));
+}
+
+/// Write a value through an out-pointer
+///
+/// # Safety
+///
+/// `out` must be null or valid for writes of `T`.
+unsafe fn write_out<T>(out: *mut T, value: T, name: &str) -> FfiResult<()> {
+    if out.is_null() {
+        return Err(FfiError::new(OxydeStatus::NullPointer, format!("`{}` is null", name)));
+    }
+    out.write(value);
+    Ok(())
+}
+
+fn into_c_string(value: String) -> FfiResult<*mut c_char> {
+    CString::new(value)
+        .map(CString::into_raw)
+        .map_err(|_| FfiError::new(OxydeStatus::Internal, "Result contains a NUL byte"))
+}
+
+/// Create an agent and write its handle through `out_agent`
+///
+/// The agent is only built once `out_agent` is known to be non-null, so a
+/// null out-pointer cannot leak it.
+///
+/// # Safety
+///
+/// `out_agent` must be null or valid for writes.
+unsafe fn create_agent(config: AgentConfig, out_agent: *mut *mut OxydeAgent) -> FfiResult<()> {
+    if out_agent.is_null() {
+        return Err(FfiError::new(OxydeStatus::InvalidArgument, "`out_agent` is null"));
+    }
+    config.validate()?;
+    let agent = Agent::new_with_tts(config);
+    out_agent.write(Box::into_raw(Box::new(OxydeAgent {
+        inner: Arc::new(agent),
+        capability: OxydeCapability::Full,
+    })));
+    Ok(())
+}
+
+/// Get the version of the C ABI
+#[no_mangle]
+pub extern "C" fn oxyde_abi_version() -> u32 {
+    OXYDE_ABI_VERSION
+}
+
+/// Get the message for the last error on the calling thread
+///
+/// Returns null if no error has occurred. The string is owned by the library
+/// and stays valid until the next failing call on the same thread.
+#[no_mangle]
+pub extern "C" fn oxyde_last_error() -> *const c_char {
+    LAST_ERROR.with(|slot| slot.borrow().as_ref().map_or(ptr::null(), |message| message.as_ptr()))
+}
+
+/// Free a string returned by this library
+///
+/// # Safety
+///
+/// `value` must be null or a string returned through an out-pointer by this
+/// library that has not already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn oxyde_string_free(value: *mut c_char) {
+    if !value.is_null() {
+        drop(CString::from_raw(value));
+    }
+}
+
+/// Create an agent from a JSON configuration string
+///
+/// Returns `InvalidArgument` without creating the agent if `out_agent` is
+/// null.
+///
+/// # Safety
+///
+/// `config_json` must be a NUL-terminated string and `out_agent` must be
+/// null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn oxyde_agent_create_from_json(
+    config_json: *const c_char,
+    out_agent: *mut *mut OxydeAgent,
+) -> OxydeStatus {
+    guard(|| {
+        let json = str_arg(config_json, "config_json")?;
+        let config: AgentConfig = serde_json::from_str(json).map_err(OxydeError::from)?;
+        create_agent(config, out_agent)
+    })
+}
+
+/// Create an agent from a configuration file (JSON, YAML, or TOML)
+///
+/// Returns `InvalidArgument` without creating the agent if `out_agent` is
+/// null.
+///
+/// # Safety
+///
+/// `config_path` must be a NUL-terminated string and `out_agent` must be
+/// null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn oxyde_agent_create_from_file(
+    config_path: *const c_char,
+    out_agent: *mut *mut OxydeAgent,
+) -> OxydeStatus {
+    guard(|| {
+        let path = str_arg(config_path, "config_path")?;
+        let config = AgentConfig::from_file(path)?;
+        create_agent(config, out_agent)
+    })
+}
+
+/// Destroy an agent handle
+///
+/// # Safety
+///
+/// `agent` must be null or a handle returned by `oxyde_agent_create_*` that
+/// has not already been destroyed. The handle must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn oxyde_agent_destroy(agent: *mut OxydeAgent) {
+    if !agent.is_null() {
+        drop(Box::from_raw(agent));
+    }
+}
+
+/// Create another handle to an agent, limited to a capability
+///
+/// Hand the new handle to mods and scripts that should not have full
+/// control. Both handles use the same agent, which lives until every handle
+/// is destroyed. A handle cannot grant more than its own capability.
+/// `capability` is an [`OxydeCapability`] value; anything else returns
+/// `InvalidArgument`.
+///
+/// # Safety
+///
+/// `agent` must be a live agent handle and `out_agent` valid for writes. The
+/// new handle must be released with [`oxyde_agent_destroy`].
+#[no_mangle]
+pub unsafe extern "C" fn oxyde_agent_share(
+    agent: *const OxydeAgent,
+    capability: u32,
+    out_agent: *mut *mut OxydeAgent,
+) -> OxydeStatus {
+    guard(|| {
+        let handle = agent
+            .as_ref()
+            .ok_or_else(|| FfiError::new(OxydeStatus::NullPointer, "`agent` is null"))?;
+        let capability = OxydeCapability::try_from(capability).map_err(|value| {
+            FfiError::new(OxydeStatus::InvalidArgument, format!("Unknown capability: {}", value))
+        })?;
+        agent_arg(agent, capability, "oxyde_agent_share")?;
+        if out_agent.is_null() {
+            return Err(FfiError::new(OxydeStatus::NullPointer, "`out_agent` is null"));
+        }
+        let shared = Box::into_raw(Box::new(OxydeAgent {
+            inner: handle.inner.clone(),
+            capability,
+        }));
+        write_out(out_agent, shared, "out_agent")
+    })
+}
+
+/// Get the capability of an agent handle
+///
+/// # Safety
+///
+/// `agent` must be a live agent handle and `out_capability` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn oxyde_agent_capability(
+    agent: *const OxydeAgent,
+    out_capability: *mut OxydeCapability,
+) -> OxydeStatus {
+    guard(|| {
+        let handle = agent
+            .as_ref()
+            .ok_or_else(|| FfiError::new(OxydeStatus::NullPointer, "`agent` is null"))?;
+        write_out(out_capability, handle.capability, "out_capability")
+    })
+}
+
+/// Get the audit trail of gated calls as a JSON array
+///
+/// Lists the most recent denied calls and calls needing full control, oldest
+/// first, each with its `timestamp_ms`, `agent` name, `call`, the handle's
+/// `capability` and whether it was `allowed`.
+///
+/// # Safety
+///
+/// `out_json` must be valid for writes. The JSON must be freed with
+/// [`oxyde_string_free`].
+#[no_mangle]
+pub unsafe extern "C" fn oxyde_audit_log(out_json: *mut *mut c_char) -> OxydeStatus {
+    guard(|| {
+        let trail: Vec<serde_json::Value> = AUDIT_LOG
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .cloned()
+            .collect();
+        let json = serde_json::to_string(&trail).map_err(OxydeError::from)?;
+        write_out(out_json, into_c_string(json)?, "out_json")
+    })
+}
+
+/// Start an agent
+///
+/// # Safety
+///
+/// `agent` must be a live agent handle.
+#[no_mangle]
+pub unsafe extern "C" fn oxyde_agent_start(agent: *const OxydeAgent) -> OxydeStatus {
+    guard(|| {
+        let agent = agent_arg(agent, OxydeCapability::Interact, "oxyde_agent_start")?;
+        RUNTIME.block_on(agent.start())?;
+        Ok(())
+    })
+}
+
+/// Stop an agent
+///
+/// # Safety
+///
+/// `agent` must be a live agent handle.
+#[no_mangle]
+pub unsafe extern "C" fn oxyde_agent_stop(agent: *const OxydeAgent) -> OxydeStatus {
+    guard(|| {
+        let agent = agent_arg(agent, OxydeCapability::Interact, "oxyde_agent_stop")?;
+        RUNTIME.block_on(agent.stop())?;
+        Ok(())
+    })
+}
+
+/// Merge a JSON object into the agent's context
+///
+/// Returns `InvalidArgument` without applying any key if the update does not
+/// fit the agent's context schema.
+///
+/// # Safety
+///
+/// `agent` must be a live agent handle and `context_json` a NUL-terminated
+/// string.
+#[no_mangle]
+pub unsafe extern "C" fn oxyde_agent_update_context(
+    agent: *const OxydeAgent,
+    context_json: *const c_char,
+) -> OxydeStatus {
+    guard(|| {
+        let agent = agent_arg(agent, OxydeCapability::Interact, "oxyde_agent_update_context")?;
+        let json = str_arg(context_json, "context_json")?;
+        let context: AgentContext = serde_json::from_str(json)
+            .map_err(|e| FfiError::new(OxydeStatus::InvalidArgument, format!("Invalid context JSON: {}", e)))?;
+        RUNTIME.block_on(agent.try_update_context(context))?;
+        Ok(())
+    })
+}
+
+/// Process player input and return the agent's response
+///
+/// # Safety
+///
+/// `agent` must be a live agent handle, `input` a NUL-terminated string, and
+/// `out_response` valid for writes. The response must be freed with
+/// [`oxyde_string_free`].
+#[no_mangle]
+pub unsafe extern "C" fn oxyde_agent_process_input(
+    agent: *const OxydeAgent,
+    input: *const c_char,
+    out_response: *mut *mut c_char,
+) -> OxydeStatus {
+    guard(|| {
+        let agent = agent_arg(agent, OxydeCapability::Interact, "oxyde_agent_process_input")?;
+        let input = str_arg(input, "input")?;
+        let response = RUNTIME.block_on(agent.process_input(input))?;
+        write_out(out_response, into_c_string(response)?, "out_response")
+    })
+}
+
+/// Process player input and return the response with a suggested animation
+///
+/// The JSON object has a `text` string and an `annotation` object whose
+/// `gesture`, `facial`, and `move_to` (`{x, y, z}`) keys may be null.
+///
+/// # Safety
+///
+/// `agent` must be a live agent handle, `input` a NUL-terminated string, and
+/// `out_json` valid for writes. The JSON must be freed with
+/// [`oxyde_string_free`].
+#[no_mangle]
+pub unsafe extern "C" fn oxyde_agent_process_input_annotated(
+    agent: *const OxydeAgent,
+    input: *const c_char,
+    out_json: *mut *mut c_char,
+) -> OxydeStatus {
+    guard(|| {
+        let agent = agent_arg(agent, OxydeCapability::Interact, "oxyde_agent_process_input_annotated")?;
+        let input = str_arg(input, "input")?;
+        let output = RUNTIME.block_on(agent.process_input_annotated(input))?;
+        let json = serde_json::to_string(&output).map_err(OxydeError::from)?;
+        write_out(out_json, into_c_string(json)?, "out_json")
+    })
+}
+
+/// Process input from a player identified by the game
+///
+/// The player's conversation history, relationship and memories about them
+/// are kept in a session under `player_id`, and saved to the configured
+/// session directory.
+///
+/// # Safety
+///
+/// `agent` must be a live agent handle, `player_id` and `input`
+/// NUL-terminated strings, and `out_response` valid for writes. The response
+/// must be freed with [`oxyde_string_free`].
+#[no_mangle]
+pub unsafe extern "C" fn oxyde_agent_process_input_for_player(
+    agent: *const OxydeAgent,
+    player_id: *const c_char,
+    input: *const c_char,
+    out_response: *mut *mut c_char,
+) -> OxydeStatus {
+    guard(|| {
+        let agent = agent_arg(agent, OxydeCapability::Interact, "oxyde_agent_process_input_for_player")?;
+        let player_id = str_arg(player_id, "player_id")?;
+        let input = str_arg(input, "input")?;
+        let response = RUNTIME.block_on(agent.process_input_for_player(player_id, input))?;
+        write_out(out_response, into_c_string(response)?, "out_response")
+    })
+}
+
+/// Get a player's session as JSON
+///
+/// The object has the player's `turns`, `relationship`, recent `history` and
+/// the `memories` about them. A player without a session gets an empty one,
+/// which is not kept.
+///
+/// # Safety
+///
+/// `agent` must be a live agent handle, `player_id` a NUL-terminated string,
+/// and `out_json` valid for writes. The JSON must be freed with
+/// [`oxyde_string_free`].
+#[no_mangle]
+pub unsafe extern "C" fn oxyde_agent_get_player_session(
+    agent: *const OxydeAgent,
+    player_id: *const c_char,
+    out_json: *mut *mut c_char,
+) -> OxydeStatus {
+    guard(|| {
+        let agent = agent_arg(agent, OxydeCapability::ReadOnly, "oxyde_agent_get_player_session")?;
+        let player_id = str_arg(player_id, "player_id")?;
+        if player_id.is_empty() {
+            return Err(FfiError::new(OxydeStatus::InvalidArgument, "player_id cannot be empty"));
+        }
+        let session = agent.sessions().get(player_id).unwrap_or_else(|| PlayerSession::new(player_id));
+        let mut json = serde_json::to_value(&session).map_err(OxydeError::from)?;
+        let memories = RUNTIME.block_on(agent.player_memories(player_id));
+        json["memories"] = serde_json::to_value(memories).map_err(OxydeError::from)?;
+        let json = json.to_string();
+        write_out(out_json, into_c_string(json)?, "out_json")
+    })
+}
+
+/// Get the agent's current emotions
+///
+/// # Safety
+///
+/// `agent` must be a live agent handle and `out_emotions` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn oxyde_agent_get_emotions(
+    agent: *const OxydeAgent,
+    out_emotions: *mut OxydeEmotions,
+) -> OxydeStatus {
+    guard(|| {
+        let agent = agent_arg(agent, OxydeCapability::ReadOnly, "oxyde_agent_get_emotions")?;
+        let emotions = OxydeEmotions::from(RUNTIME.block_on(agent.emotion_vector()));
+        write_out(out_emotions, emotions, "out_emotions")
+    })
+}
+
+/// Get the emotions changed since a previous poll
+///
+/// Cheaper than [`oxyde_agent_get_emotions`] for engines polling every
+/// frame: `changed` is 0 when nothing moved. Only agents feeling through
+/// Plutchik's emotions can be polled this way; others return
+/// `InvalidArgument` and are polled with [`oxyde_agent_poll_emotions_json`].
+///
+/// # Safety
+///
+/// `agent` must be a live agent handle and `out_changes` valid for writes.
+/// `since_sequence` is the `sequence` of the previous poll, or 0.
+#[no_mangle]
+pub unsafe extern "C" fn oxyde_agent_poll_emotions(
+    agent: *const OxydeAgent,
+    since_sequence: u64,
+    out_changes: *mut OxydeEmotionChanges,
+) -> OxydeStatus {
+    guard(|| {
+        let agent = agent_arg(agent, OxydeCapability::ReadOnly, "oxyde_agent_poll_emotions")?;
+        let change = agent.emotion_changes_since(since_sequence);
+        if change.dimensions != EMOTION_NAMES {
+            return Err(FfiError::new(
+                OxydeStatus::InvalidArgument,
+                format!(
+                    "Agent feels through {}; poll its emotions with oxyde_agent_poll_emotions_json",
+                    change.dimensions.join(", ")
+                ),
+            ));
+        }
+        let mut values = [0.0; 8];
+        for (index, name) in EMOTION_NAMES.iter().enumerate() {
+            values[index] = change.emotions.get(*name).copied().unwrap_or_default();
+        }
+        let changes = OxydeEmotionChanges {
+            sequence: change.sequence,
+            changed: change.mask(),
+            emotions: OxydeEmotions::from(values),
+        };
+        write_out(out_changes, changes, "out_changes")
+    })
+}
+
+/// Get the emotions changed since a previous poll as JSON
+///
+/// Writes the `sequence` to pass to the next poll, the affect model's
+/// `dimensions` in order, and the changed `emotions` by name with their new
+/// values. Works whatever affect model the agent uses.
+///
+/// # Safety
+///
+/// `agent` must be a live agent handle and `out_json` valid for writes. The
+/// JSON must be freed with [`oxyde_string_free`].
+#[no_mangle]
+pub unsafe extern "C" fn oxyde_agent_poll_emotions_json(
+    agent: *const OxydeAgent,
+    since_sequence: u64,
+    out_json: *mut *mut c_char,
+) -> OxydeStatus {
+    guard(|| {
+        let agent = agent_arg(agent, OxydeCapability::ReadOnly, "oxyde_agent_poll_emotions_json")?;
+        let change = agent.emotion_changes_since(since_sequence);
+        let json = serde_json::to_string(&change).map_err(OxydeError::from)?;
+        write_out(out_json, into_c_string(json)?, "out_json")
+    })
+}
+
+/// Read the oldest line waiting in the agent's dialogue queue
+///
+/// Writes the line as JSON with its `sequence`, `kind` (`response` or
+/// `filler`), `text`, `timestamp_ms` and `expires_at_ms`, or NULL when no
+/// line is waiting. Lines stay queued; use [`oxyde_agent_dialogue_pop`] to
+/// remove them.
+///
+/// # Safety
+///
+/// `agent` must be a live agent handle and `out_json` valid for writes. The
+/// JSON must be freed with [`oxyde_string_free`].
+#[no_mangle]
+pub unsafe extern "C" fn oxyde_agent_dialogue_peek(
+    agent: *const OxydeAgent,
+    out_json: *mut *mut c_char,
+) -> OxydeStatus {
+    guard(|| {
+        let agent = agent_arg(agent, OxydeCapability::ReadOnly, "oxyde_agent_dialogue_peek")?;
+        let line = agent.dialogue_queue().peek();
+        write_out(out_json, dialogue_line_json(line)?, "out_json")
+    })
+}
+
+/// Remove and return the oldest line waiting in the agent's dialogue queue
+///
+/// Writes the line as JSON like [`oxyde_agent_dialogue_peek`], or NULL when
+/// no line is waiting.
+///
+/// # Safety
+///
+/// `agent` must be a live agent handle and `out_json` valid for writes. The
+/// JSON must be freed with [`oxyde_string_free`].
+#[no_mangle]
+pub unsafe extern "C" fn oxyde_agent_dialogue_pop(
+    agent: *const OxydeAgent,
+    out_json: *mut *mut c_char,
+) -> OxydeStatus {
+    guard(|| {
+        let agent = agent_arg(agent, OxydeCapability::Interact, "oxyde_agent_dialogue_pop")?;
+        let line = agent.dialogue_queue().pop();
+        write_out(out_json, dialogue_line_json(line)?, "out_json")
+    })
+}
+
+fn dialogue_line_json(line: Option<DialogueLine>) -> FfiResult<*mut c_char> {
+    match line {
+        Some(line) => into_c_string(serde_json::to_string(&line).map_err(OxydeError::from)?),
+        None => Ok(ptr::null_mut()),
+    }
+}
+
+/// Adjust one emotion by a delta
+///
+/// # Safety
+///
+/// `agent` must be a live agent handle and `emotion` a NUL-terminated string
+/// naming one of the eight emotions.
+#[no_mangle]
+pub unsafe extern "C" fn oxyde_agent_update_emotion(
+    agent: *const OxydeAgent,
+    emotion: *const c_char,
+    delta: f32,
+) -> OxydeStatus {
+    guard(|| {
+        let agent = agent_arg(agent, OxydeCapability::Interact, "oxyde_agent_update_emotion")?;
+        let emotion = str_arg(emotion, "emotion")?;
+        RUNTIME.block_on(agent.update_emotion(emotion, delta));
+        Ok(())
+    })
+}
+
+/// Add a memory to the agent
+///
+/// # Safety
+///
+/// `agent` must be a live agent handle; `category` and `content` must be
+/// NUL-terminated strings. `category` is one of "episodic", "semantic",
+/// "procedural", or "emotional".
+#[no_mangle]
+pub unsafe extern "C" fn oxyde_agent_add_memory(
+    agent: *const OxydeAgent,
+    category: *const c_char,
+    content: *const c_char,
+    importance: f64,
+) -> OxydeStatus {
+    guard(|| {
+        let agent = agent_arg(agent, OxydeCapability::Interact, "oxyde_agent_add_memory")?;
+        let category_name = str_arg(category, "category")?;
+        let category = MemoryCategory::from_str(category_name).ok_or_else(|| {
+            FfiError::new(OxydeStatus::InvalidArgument, format!("Unknown memory category: {}", category_name))
+        })?;
+        let content = str_arg(content, "content")?;
+        RUNTIME.block_on(agent.add_memory(category, content, importance, None))?;
+        Ok(())
+    })
+}
+
+/// Get the number of memories the agent holds
+///
+/// # Safety
+///
+/// `agent` must be a live agent handle and `out_count` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn oxyde_agent_memory_count(agent: *const OxydeAgent, out_count: *mut u32) -> OxydeStatus {
+    guard(|| {
+        let agent = agent_arg(agent, OxydeCapability::ReadOnly, "oxyde_agent_memory_count")?;
+        let count = RUNTIME.block_on(agent.memory_count());
+        write_out(out_count, count as u32, "out_count")
+    })
+}
+
+/// Clear all non-permanent memories
+///
+/// # Safety
+///
+/// `agent` must be a live agent handle. `out_removed` may be null; otherwise
+/// it must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn oxyde_agent_clear_memories(agent: *const OxydeAgent, out_removed: *mut u32) -> OxydeStatus {
+    guard(|| {
+        let agent = agent_arg(agent, OxydeCapability::Full, "oxyde_agent_clear_memories")?;
+        let removed = RUNTIME.block_on(agent.clear_memories());
+        if !out_removed.is_null() {
+            out_removed.write(removed as u32);
+        }
+        Ok(())
+    })
+}
+
+/// Forget every non-permanent memory of a category
+///
+/// # Safety
+///
+/// `agent` must be a live agent handle and `category` a NUL-terminated
+/// string naming a memory category. `out_removed` may be null; otherwise it
+/// must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn oxyde_agent_forget_category(
+    agent: *const OxydeAgent,
+    category: *const c_char,
+    out_removed: *mut u32,
+) -> OxydeStatus {
+    guard(|| {
+        let agent = agent_arg(agent, OxydeCapability::Full, "oxyde_agent_forget_category")?;
+        let category_name = str_arg(category, "category")?;
+        let category = MemoryCategory::from_str(category_name).ok_or_else(|| {
+            FfiError::new(OxydeStatus::InvalidArgument, format!("Unknown memory category: {}", category_name))
+        })?;
+        let removed = RUNTIME.block_on(agent.forget_memories_by_category(category));
+        if !out_removed.is_null() {
+            out_removed.write(removed as u32);
+        }
+        Ok(())
+    })
+}
+
+/// Retrieve memories relevant to a query as a JSON array
+///
+/// # Safety
+///
+/// `agent` must be a live agent handle, `query` a NUL-terminated string, and
+/// `out_json` valid for writes. The JSON must be freed with
+/// [`oxyde_string_free`].
+#[no_mangle]
+pub unsafe extern "C" fn oxyde_agent_recall(
+    agent: *const OxydeAgent,
+    query: *const c_char,
+    limit: u32,
+    out_json: *mut *mut c_char,
+) -> OxydeStatus {
+    guard(|| {
+        let agent = agent_arg(agent, OxydeCapability::ReadOnly, "oxyde_agent_recall")?;
+        let query = str_arg(query, "query")?;
+        let memories = RUNTIME.block_on(agent.retrieve_relevant_memories(query, limit as usize))?;
+        let json = serde_json::to_string(&memories).map_err(OxydeError::from)?;
+        write_out(out_json, into_c_string(json)?, "out_json")
+    })
+}
+
+/// Answer a question about what the agent remembers
+///
+/// The JSON object has an `answer` string and a `memory_ids` array with the
+/// IDs of the memories supporting it.
+///
+/// # Safety
+///
+/// `agent` must be a live agent handle, `question` a NUL-terminated string,
+/// and `out_json` valid for writes. The JSON must be freed with
+/// [`oxyde_string_free`].
+#[no_mangle]
+pub unsafe extern "C" fn oxyde_agent_ask_memory(
+    agent: *const OxydeAgent,
+    question: *const c_char,
+    out_json: *mut *mut c_char,
+) -> OxydeStatus {
+    guard(|| {
+        let agent = agent_arg(agent, OxydeCapability::ReadOnly, "oxyde_agent_ask_memory")?;
+        let question = str_arg(question, "question")?;
+        let answer = RUNTIME.block_on(agent.ask_memory(question))?;
+        let json = serde_json::to_string(&answer).map_err(OxydeError::from)?;
+        write_out(out_json, into_c_string(json)?, "out_json")
+    })
+}
+
+/// Teach the agent a fact, replacing any fact learned under the same key
+///
+/// The fact is stored as a permanent, high-importance memory and recalled by
+/// the next relevant input.
+///
+/// # Safety
+///
+/// `agent` must be a live agent handle; `key` and `statement` must be
+/// NUL-terminated strings. `source` may be null; otherwise it must be a
+/// NUL-terminated string. `out_memory_id` may be null; otherwise it must be
+/// valid for writes, and the ID must be freed with [`oxyde_string_free`].
+#[no_mangle]
+pub unsafe extern "C" fn oxyde_agent_learn_fact(
+    agent: *const OxydeAgent,
+    key: *const c_char,
+    statement: *const c_char,
+    source: *const c_char,
+    out_memory_id: *mut *mut c_char,
+) -> OxydeStatus {
+    guard(|| {
+        let agent = agent_arg(agent, OxydeCapability::Interact, "oxyde_agent_learn_fact")?;
+        let key = str_arg(key, "key")?;
+        let statement = str_arg(statement, "statement")?;
+        let source = if source.is_null() { None } else { Some(str_arg(source, "source")?) };
+        let memory = RUNTIME.block_on(agent.learn_fact(key, statement, source))?;
+        if !out_memory_id.is_null() {
+            out_memory_id.write(into_c_string(memory.id)?);
+        }
+        Ok(())
+    })
+}
+
+/// Forget the fact learned under a key
+///
+/// # Safety
+///
+/// `agent` must be a live agent handle and `key` a NUL-terminated string.
+/// `out_forgotten` may be null; otherwise it must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn oxyde_agent_forget_fact(
+    agent: *const OxydeAgent,
+    key: *const c_char,
+    out_forgotten: *mut bool,
+) -> OxydeStatus {
+    guard(|| {
+        let agent = agent_arg(agent, OxydeCapability::Full, "oxyde_agent_forget_fact")?;
+        let key = str_arg(key, "key")?;
+        let forgotten = RUNTIME.block_on(agent.forget_fact(key));
+        if !out_forgotten.is_null() {
+            out_forgotten.write(forgotten);
+        }
+        Ok(())
+    })
+}
+
+/// Compute a modifier, such as a price multiplier, from the agent's
+/// relationship with the current player and its current emotions
+///
+/// The built-in modifiers are "price" and "haggle"; unknown names fail with
+/// `InvalidArgument`.
+///
+/// # Safety
+///
+/// `agent` must be a live agent handle, `name` a NUL-terminated string, and
+/// `out_value` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn oxyde_agent_modifier(
+    agent: *const OxydeAgent,
+    name: *const c_char,
+    out_value: *mut f32,
+) -> OxydeStatus {
+    guard(|| {
+        let agent = agent_arg(agent, OxydeCapability::ReadOnly, "oxyde_agent_modifier")?;
+        let name = str_arg(name, "name")?;
+        let value = RUNTIME.block_on(agent.modifier(name)).ok_or_else(|| {
+            FfiError::new(OxydeStatus::InvalidArgument, format!("Unknown modifier: {}", name))
+        })?;
+        write_out(out_value, value, "out_value")
+    })
+}
+
+/// Synthesize speech for text using the agent's voice and current emotions
+///
+/// Requires a `tts` section in the agent configuration.
+///
+/// # Safety
+///
+/// `agent` must be a live agent handle, `text` a NUL-terminated string, and
+/// `out_audio` valid for writes. The audio must be freed with
+/// [`oxyde_audio_free`].
+#[no_mangle]
+pub unsafe extern "C" fn oxyde_agent_speak(
+    agent: *const OxydeAgent,
+    text: *const c_char,
+    urgency: f32,
+    out_audio: *mut OxydeAudio,
+) -> OxydeStatus {
+    guard(|| {
+        let agent = agent_arg(agent, OxydeCapability::Interact, "oxyde_agent_speak")?;
+        let text = str_arg(text, "text")?;
+        let audio = RUNTIME.block_on(async {
+            let emotions = agent.emotional_state().await;
+            agent.speak(text, &emotions, urgency).await
+        })?;
+
+        let data = Box::into_raw(audio.data.into_boxed_slice());
+        let audio = OxydeAudio {
+            data: data as *mut u8,
+            len: data.len(),
+            sample_rate: audio.sample_rate,
+            channels: audio.channels,
+            duration_ms: audio.duration_ms,
+        };
+        write_out(out_audio, audio, "out_audio")
+    })
+}
+
+/// Start synthesizing speech for text, delivered in chunks
+///
+/// Pull chunks with [`oxyde_audio_stream_next`] as they are generated, so
+/// playback can start before synthesis finishes. Requires a `tts` section in
+/// the agent configuration.
+///
+/// # Safety
+///
+/// `agent` must be a live agent handle, `text` a NUL-terminated string, and
+/// `out_stream` valid for writes. The stream must be released with
+/// [`oxyde_audio_stream_destroy`].
+#[no_mangle]
+pub unsafe extern "C" fn oxyde_agent_speak_stream(
+    agent: *const OxydeAgent,
+    text: *const c_char,
+    urgency: f32,
+    out_stream: *mut *mut OxydeAudioStream,
+) -> OxydeStatus {
+    guard(|| {
+        let agent = agent_arg(agent, OxydeCapability::Interact, "oxyde_agent_speak_stream")?;
+        let text = str_arg(text, "text")?;
+        let stream = RUNTIME.block_on(async {
+            let emotions = agent.emotional_state().await;
+            agent.speak_streaming(text, &emotions, urgency).await
+        })?;
+        let stream = Box::into_raw(Box::new(OxydeAudioStream { inner: stream }));
+        write_out(out_stream, stream, "out_stream")
+    })
+}
+
+/// Wait for the next chunk of a speech stream
+///
+/// On success `out_done` is set to true once the stream is complete, in
+/// which case `out_audio` is left empty. Otherwise `out_audio` holds the next
+/// chunk of encoded audio, which must be freed with [`oxyde_audio_free`].
+/// Chunk durations are not known and are reported as 0.
+///
+/// # Safety
+///
+/// `stream` must be a live stream handle and `out_audio` and `out_done` valid
+/// for writes.
+#[no_mangle]
+pub unsafe extern "C" fn oxyde_audio_stream_next(
+    stream: *mut OxydeAudioStream,
+    out_audio: *mut OxydeAudio,
+    out_done: *mut bool,
+) -> OxydeStatus {
+    guard(|| {
+        let stream = stream
+            .as_mut()
+            .map(|handle| &mut handle.inner)
+            .ok_or_else(|| FfiError::new(OxydeStatus::NullPointer, "`stream` is null"))?;
+        let chunk = RUNTIME
+            .block_on(stream.next_chunk())
+            .transpose()
+            .map_err(|e| FfiError::new(OxydeStatus::Audio, e.to_string()))?;
+
+        let done = chunk.is_none();
+        let (data, len) = match chunk {
+            Some(chunk) => {
+                let data = Box::into_raw(chunk.data.into_boxed_slice());
+                (data as *mut u8, data.len())
+            }
+            None => (ptr::null_mut(), 0),
+        };
+        let audio = OxydeAudio {
+            data,
+            len,
+            sample_rate: stream.sample_rate,
+            channels: stream.channels,
+            duration_ms: 0,
+        };
+        write_out(out_done, done, "out_done")?;
+        write_out(out_audio, audio, "out_audio")
+    })
+}
+
+/// Destroy a speech stream, cancelling synthesis if it is still running
+///
+/// # Safety
+///
+/// `stream` must be null or a handle returned by [`oxyde_agent_speak_stream`]
+/// that has not already been destroyed.
+#[no_mangle]
+pub unsafe extern "C" fn oxyde_audio_stream_destroy(stream: *mut OxydeAudioStream) {
+    if !stream.is_null() {
+        drop(Box::from_raw(stream));
+    }
+}
+
+/// Free audio returned by [`oxyde_agent_speak`] or [`oxyde_audio_stream_next`]
+///
+/// The struct's fields are reset so a double free is harmless.
+///
+/// # Safety
+///
+/// `audio` must be null or point to an [`OxydeAudio`] filled in by
+/// [`oxyde_agent_speak`] or [`oxyde_audio_stream_next`].
+#[no_mangle]
+pub unsafe extern "C" fn oxyde_audio_free(audio: *mut OxydeAudio) {
+    let Some(audio) = audio.as_mut() else { return };
+    if !audio.data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(audio.data, audio.len)));
+    }
+    audio.data = ptr::null_mut();
+    audio.len = 0;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"{
+        "agent": {
+            "name": "Marla",
+            "role": "Innkeeper",
+            "backstory": ["Runs the inn"],
+            "knowledge": []
+        }
+    }"#;
+
+    fn create() -> *mut OxydeAgent {
+        let config = CString::new(CONFIG).unwrap();
+        let mut agent = ptr::null_mut();
+        let status = unsafe { oxyde_agent_create_from_json(config.as_ptr(), &mut agent) };
+        assert_eq!(status, OxydeStatus::Ok);
+        assert!(!agent.is_null());
+        agent
+    }
+
+    #[test]
+    fn test_emotions_round_trip() {
+        let agent = create();
+        let joy = CString::new("joy").unwrap();
+        let mut emotions = OxydeEmotions::default();
+        unsafe {
+            assert_eq!(oxyde_agent_update_emotion(agent, joy.as_ptr(), 0.5), OxydeStatus::Ok);
+            assert_eq!(oxyde_agent_get_emotions(agent, &mut emotions), OxydeStatus::Ok);
+            oxyde_agent_destroy(agent);
+        }
+        assert!(emotions.joy > 0.0);
+    }
+
+    #[test]
+    fn test_poll_returns_only_changed_emotions() {
+        let agent = create();
+        let fear = CString::new("fear").unwrap();
+        let mut changes = OxydeEmotionChanges::default();
+        unsafe {
+            assert_eq!(oxyde_agent_poll_emotions(agent, 0, &mut changes), OxydeStatus::Ok);
+            assert_eq!((changes.sequence, changes.changed), (0, 0));
+            assert_eq!(oxyde_agent_update_emotion(agent, fear.as_ptr(), 0.5), OxydeStatus::Ok);
+            assert_eq!(oxyde_agent_poll_emotions(agent, 0, &mut changes), OxydeStatus::Ok);
+            // Fear moves its opposite, anger, with it
+            assert_eq!(changes.changed, (1 << 2) | (1 << 6));
+            assert!(changes.emotions.fear > 0.0);
+            let seen = changes.sequence;
+            assert_eq!(oxyde_agent_poll_emotions(agent, seen, &mut changes), OxydeStatus::Ok);
+            assert_eq!((changes.sequence, changes.changed), (seen, 0));
+
+            let mut json = ptr::null_mut();
+            assert_eq!(oxyde_agent_poll_emotions_json(agent, 0, &mut json), OxydeStatus::Ok);
+            let change: serde_json::Value = serde_json::from_str(CStr::from_ptr(json).to_str().unwrap()).unwrap();
+            assert_eq!(change["sequence"], seen);
+            assert_eq!(change["dimensions"][2], "fear");
+            assert_eq!(change["emotions"]["fear"], 0.5);
+            oxyde_string_free(json);
+            oxyde_agent_destroy(agent);
+        }
+    }
+
+    #[test]
+    fn test_memory_functions() {
+        let agent = create();
+        let category = CString::new("semantic").unwrap();
+        let content = CString::new("The well is haunted").unwrap();
+        let mut count = 0;
+        unsafe {
+            assert_eq!(oxyde_agent_add_memory(agent, category.as_ptr(), content.as_ptr(), 0.8), OxydeStatus::Ok);
+            assert_eq!(oxyde_agent_memory_count(agent, &mut count), OxydeStatus::Ok);
+            oxyde_agent_destroy(agent);
+        }
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn test_ask_memory_without_relevant_memories() {
+        let agent = create();
+        let question = CString::new("What do you know about the mayor?").unwrap();
+        let mut json = ptr::null_mut();
+        unsafe {
+            assert_eq!(oxyde_agent_ask_memory(agent, question.as_ptr(), &mut json), OxydeStatus::Ok);
+            let answer: serde_json::Value = serde_json::from_str(CStr::from_ptr(json).to_str().unwrap()).unwrap();
+            assert_eq!(answer["memory_ids"], serde_json::json!([]));
+            oxyde_string_free(json);
+            oxyde_agent_destroy(agent);
+        }
+    }
+
+    #[test]
+    fn test_learn_and_forget_fact() {
+        let agent = create();
+        let key = CString::new("north_bridge").unwrap();
+        let statement = CString::new("The north bridge has collapsed.").unwrap();
+        let mut memory_id = ptr::null_mut();
+        let mut forgotten = false;
+        unsafe {
+            let status = oxyde_agent_learn_fact(agent, key.as_ptr(), statement.as_ptr(), ptr::null(), &mut memory_id);
+            assert_eq!(status, OxydeStatus::Ok);
+            assert!(!CStr::from_ptr(memory_id).to_bytes().is_empty());
+            oxyde_string_free(memory_id);
+            assert_eq!(oxyde_agent_forget_fact(agent, key.as_ptr(), &mut forgotten), OxydeStatus::Ok);
+            oxyde_agent_destroy(agent);
+        }
+        assert!(forgotten);
+    }
+
+    #[test]
+    fn test_modifier_follows_relationship() {
+        let agent = create();
+        let context = CString::new(r#"{"player_relationship": -1.0}"#).unwrap();
+        let price = CString::new("price").unwrap();
+        let unknown = CString::new("tax").unwrap();
+        let mut value = 0.0;
+        unsafe {
+            assert_eq!(oxyde_agent_modifier(agent, price.as_ptr(), &mut value), OxydeStatus::Ok);
+            assert_eq!(value, 1.0);
+            assert_eq!(oxyde_agent_update_context(agent, context.as_ptr()), OxydeStatus::Ok);
+            assert_eq!(oxyde_agent_modifier(agent, price.as_ptr(), &mut value), OxydeStatus::Ok);
+            assert_eq!(oxyde_agent_modifier(agent, unknown.as_ptr(), &mut 0.0), OxydeStatus::InvalidArgument);
+            oxyde_agent_destroy(agent);
+        }
+        assert!(value > 1.0);
+    }
+
+    #[test]
+    fn test_player_session_as_json() {
+        let agent = create();
+        let player = CString::new("steam:7656").unwrap();
+        let empty = CString::new("").unwrap();
+        let mut json = ptr::null_mut();
+        unsafe {
+            assert_eq!(oxyde_agent_get_player_session(agent, player.as_ptr(), &mut json), OxydeStatus::Ok);
+            let session: serde_json::Value = serde_json::from_str(CStr::from_ptr(json).to_str().unwrap()).unwrap();
+            assert_eq!(session["player_id"], "steam:7656");
+            assert_eq!(session["turns"], 0);
+            assert_eq!(session["memories"], serde_json::json!([]));
+            oxyde_string_free(json);
+            // Looking a player up does not start a session for them
+            assert!((*agent).inner.sessions().get("steam:7656").is_none());
+            let status = oxyde_agent_get_player_session(agent, empty.as_ptr(), &mut json);
+            assert_eq!(status, OxydeStatus::InvalidArgument);
+            oxyde_agent_destroy(agent);
+        }
+    }
+
+    #[test]
+    fn test_dialogue_queue_delivers_responses_in_order() {
+        let config = CString::new(
+            r#"{"agent": {"name": "Marla", "role": "Innkeeper", "backstory": [], "knowledge": []},
+                "inference": {"provider": "mock"}}"#,
+        )
+        .unwrap();
+        let first = CString::new("Hello").unwrap();
+        let second = CString::new("Any rooms?").unwrap();
+        let mut agent = ptr::null_mut();
+        let mut response = ptr::null_mut();
+        let mut json = ptr::null_mut();
+        unsafe {
+            assert_eq!(oxyde_agent_create_from_json(config.as_ptr(), &mut agent), OxydeStatus::Ok);
+            assert_eq!(oxyde_agent_dialogue_pop(agent, &mut json), OxydeStatus::Ok);
+            assert!(json.is_null());
+            for input in [&first, &second] {
+                assert_eq!(oxyde_agent_process_input(agent, input.as_ptr(), &mut response), OxydeStatus::Ok);
+                oxyde_string_free(response);
+            }
+
+            assert_eq!(oxyde_agent_dialogue_peek(agent, &mut json), OxydeStatus::Ok);
+            let peeked: serde_json::Value = serde_json::from_str(CStr::from_ptr(json).to_str().unwrap()).unwrap();
+            oxyde_string_free(json);
+            assert_eq!(oxyde_agent_dialogue_pop(agent, &mut json), OxydeStatus::Ok);
+            let popped: serde_json::Value = serde_json::from_str(CStr::from_ptr(json).to_str().unwrap()).unwrap();
+            oxyde_string_free(json);
+            assert_eq!(peeked, popped);
+            assert_eq!((popped["sequence"].as_u64(), popped["kind"].as_str()), (Some(1), Some("response")));
+            assert!(popped["text"].as_str().unwrap().contains("Hello"));
+
+            assert_eq!(oxyde_agent_dialogue_pop(agent, &mut json), OxydeStatus::Ok);
+            assert!(CStr::from_ptr(json).to_str().unwrap().contains("Any rooms?"));
+            oxyde_string_free(json);
+            oxyde_agent_destroy(agent);
+        }
+    }
+
+    #[test]
+    fn test_shared_handles_cannot_destroy_memories() {
+        let agent = create();
+        let category = CString::new("semantic").unwrap();
+        let content = CString::new("The well is haunted").unwrap();
+        let mut reader = ptr::null_mut();
+        let mut escalated = ptr::null_mut();
+        let mut capability = OxydeCapability::Full;
+        let mut count = 0;
+        let mut json = ptr::null_mut();
+        unsafe {
+            assert_eq!(oxyde_agent_add_memory(agent, category.as_ptr(), content.as_ptr(), 0.8), OxydeStatus::Ok);
+            assert_eq!(oxyde_agent_share(agent, OxydeCapability::ReadOnly as u32, &mut reader), OxydeStatus::Ok);
+            assert_eq!(oxyde_agent_capability(reader, &mut capability), OxydeStatus::Ok);
+            assert_eq!(capability, OxydeCapability::ReadOnly);
+
+            assert_eq!(oxyde_agent_memory_count(reader, &mut count), OxydeStatus::Ok);
+            assert_eq!(count, 1);
+            assert_eq!(oxyde_agent_clear_memories(reader, ptr::null_mut()), OxydeStatus::PermissionDenied);
+            let status = oxyde_agent_add_memory(reader, category.as_ptr(), content.as_ptr(), 0.8);
+            assert_eq!(status, OxydeStatus::PermissionDenied);
+            let status = oxyde_agent_share(reader, OxydeCapability::Full as u32, &mut escalated);
+            assert_eq!(status, OxydeStatus::PermissionDenied);
+            assert!(escalated.is_null());
+            assert_eq!(oxyde_agent_share(agent, 7, &mut escalated), OxydeStatus::InvalidArgument);
+            assert!(escalated.is_null());
+
+            // The agent outlives the handle it was shared from
+            oxyde_agent_destroy(agent);
+            assert_eq!(oxyde_agent_memory_count(reader, &mut count), OxydeStatus::Ok);
+            assert_eq!(count, 1);
+
+            assert_eq!(oxyde_audit_log(&mut json), OxydeStatus::Ok);
+            let trail: serde_json::Value = serde_json::from_str(CStr::from_ptr(json).to_str().unwrap()).unwrap();
+            oxyde_string_free(json);
+            assert!(trail.as_array().unwrap().iter().any(|entry| {
+                entry["call"] == "oxyde_agent_clear_memories" && entry["allowed"] == false
+            }));
+            oxyde_agent_destroy(reader);
+        }
+    }
+
+    #[test]
+    fn test_errors_set_status_and_message() {
+        let bad = CString::new("{not json").unwrap();
+        let mut agent = ptr::null_mut();
+        let status = unsafe { oxyde_agent_create_from_json(bad.as_ptr(), &mut agent) };
+        assert_eq!(status, OxydeStatus::Configuration);
+        assert!(agent.is_null());
+        assert!(!oxyde_last_error().is_null());
+
+        let config = CString::new(CONFIG).unwrap();
+        let status = unsafe { oxyde_agent_create_from_json(config.as_ptr(), ptr::null_mut()) };
+        assert_eq!(status, OxydeStatus::InvalidArgument);
+
+        let status = unsafe { oxyde_agent_start(ptr::null()) };
+        assert_eq!(status, OxydeStatus::NullPointer);
+        let message = unsafe { CStr::from_ptr(oxyde_last_error()) };
+        assert_eq!(message.to_str().unwrap(), "`agent` is null");
+    }
+}
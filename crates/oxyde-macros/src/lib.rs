@@ -0,0 +1,270 @@
+//! # oxyde-macros
+//!
+//! Derive macros for the Oxyde SDK.
+//!
+//! `#[derive(Behavior)]` implements `oxyde::oxyde_game::behavior::Behavior`
+//! from a `#[behavior(...)]` attribute, so a custom behavior only writes the
+//! code that produces its response:
+//!
+//! ```ignore
+//! use oxyde::agent::AgentContext;
+//! use oxyde::oxyde_game::behavior::{Behavior, BehaviorResult};
+//! use oxyde::oxyde_game::intent::Intent;
+//!
+//! #[derive(Debug, Behavior)]
+//! #[behavior(
+//!     intents(Threat, Hostile),
+//!     priority = 80,
+//!     trigger = "fear > 0.5 AND trust < 0",
+//!     influence(fear = 0.1),
+//!     priority_modifier(fear = 20.0),
+//! )]
+//! struct Flee;
+//!
+//! impl Flee {
+//!     async fn respond(&self, _intent: &Intent, _context: &AgentContext) -> oxyde::Result<BehaviorResult> {
+//!         Ok(BehaviorResult::Action("flee".to_string()))
+//!     }
+//! }
+//! ```
+//!
+//! ## Attribute keys
+//!
+//! All keys are optional.
+//!
+//! - `intents(A, B)`: match only these `IntentType` variants
+//! - `keywords("a", "b")`: match only input containing one of these words,
+//!   ignoring case
+//! - `priority = 70`: base priority
+//! - `trigger = "..."`: emotion trigger in the trigger language of
+//!   `EmotionTrigger::parse`, checked the first time it is used
+//! - `influence(joy = 0.1)`: emotion changes applied when the behavior runs
+//! - `priority_modifier(fear = 20.0)`: priority added per unit of each emotion
+//! - `activities("shopkeeping")`: scheduled activities the behavior runs during
+//! - `hours(8.0, 18.0)`: game hours the behavior runs during
+//! - `persuasion = 15`: persuasion check total the behavior requires
+//! - `timeout_ms = 500`: longest time the response may take
+//! - `execute = "method"`: inherent async method producing the result,
+//!   `respond` by default, taking `(&self, &Intent, &AgentContext)`
+//!
+//! Behaviors with both `intents` and `keywords` must match both.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::meta::ParseNestedMeta;
+use syn::punctuated::Punctuated;
+use syn::{parse_macro_input, DeriveInput, Ident, LitFloat, LitInt, LitStr, Token};
+
+/// Emotions behaviors can refer to, as named by `EmotionalState`
+const EMOTIONS: [&str; 8] = ["joy", "trust", "fear", "surprise", "sadness", "disgust", "anger", "anticipation"];
+
+/// Implement `Behavior` from a `#[behavior(...)]` attribute
+///
+/// See the [crate documentation](crate) for the attribute keys.
+#[proc_macro_derive(Behavior, attributes(behavior))]
+pub fn derive_behavior(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+/// Settings read from `#[behavior(...)]` attributes
+#[derive(Default)]
+struct BehaviorAttrs {
+    intents: Vec<Ident>,
+    keywords: Vec<LitStr>,
+    priority: Option<LitInt>,
+    trigger: Option<LitStr>,
+    influences: Vec<(Ident, LitFloat)>,
+    priority_modifiers: Vec<(Ident, LitFloat)>,
+    activities: Vec<LitStr>,
+    hours: Option<(LitFloat, LitFloat)>,
+    persuasion: Option<LitInt>,
+    timeout_ms: Option<LitInt>,
+    execute: Option<Ident>,
+}
+
+impl BehaviorAttrs {
+    fn parse(input: &DeriveInput) -> syn::Result<Self> {
+        let mut attrs = Self::default();
+        for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("behavior")) {
+            attr.parse_nested_meta(|meta| attrs.parse_key(meta))?;
+        }
+        Ok(attrs)
+    }
+
+    fn parse_key(&mut self, meta: ParseNestedMeta) -> syn::Result<()> {
+        let key = meta.path.get_ident().map(Ident::to_string).unwrap_or_default();
+        match key.as_str() {
+            "intents" => self.intents.extend(parenthesized::<Ident>(&meta)?),
+            "keywords" => self.keywords.extend(parenthesized::<LitStr>(&meta)?),
+            "activities" => self.activities.extend(parenthesized::<LitStr>(&meta)?),
+            "priority" => self.priority = Some(meta.value()?.parse()?),
+            "persuasion" => self.persuasion = Some(meta.value()?.parse()?),
+            "timeout_ms" => self.timeout_ms = Some(meta.value()?.parse()?),
+            "trigger" => self.trigger = Some(meta.value()?.parse()?),
+            "execute" => {
+                let name: LitStr = meta.value()?.parse()?;
+                self.execute = Some(name.parse()?);
+            }
+            "influence" => self.influences.extend(emotion_weights(&meta)?),
+            "priority_modifier" => self.priority_modifiers.extend(emotion_weights(&meta)?),
+            "hours" => {
+                let hours = parenthesized::<LitFloat>(&meta)?;
+                match <[LitFloat; 2]>::try_from(hours) {
+                    Ok([start, end]) => self.hours = Some((start, end)),
+                    Err(_) => return Err(meta.error("expected `hours(start, end)`")),
+                }
+            }
+            _ => return Err(meta.error(format!("unknown behavior attribute `{}`", key))),
+        }
+        Ok(())
+    }
+}
+
+/// Parse `key(a, b, ...)`
+fn parenthesized<T: syn::parse::Parse>(meta: &ParseNestedMeta) -> syn::Result<Vec<T>> {
+    let content;
+    syn::parenthesized!(content in meta.input);
+    let items = Punctuated::<T, Token![,]>::parse_terminated(&content)?;
+    Ok(items.into_iter().collect())
+}
+
+/// Parse `key(emotion = weight, ...)`, checking the emotion names
+fn emotion_weights(meta: &ParseNestedMeta) -> syn::Result<Vec<(Ident, LitFloat)>> {
+    let mut weights = Vec::new();
+    meta.parse_nested_meta(|entry| {
+        let emotion = entry.path.require_ident()?.clone();
+        if !EMOTIONS.contains(&emotion.to_string().as_str()) {
+            return Err(entry.error(format!("unknown emotion `{}`; expected one of {}", emotion, EMOTIONS.join(", "))));
+        }
+        let value = entry.value()?;
+        let negative = value.parse::<Option<Token![-]>>()?.is_some();
+        let weight: LitFloat = value.parse()?;
+        let weight = if negative {
+            LitFloat::new(&format!("-{}", weight.base10_digits()), weight.span())
+        } else {
+            weight
+        };
+        weights.push((emotion, weight));
+        Ok(())
+    })?;
+    Ok(weights)
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let attrs = BehaviorAttrs::parse(input)?;
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    let execute = attrs.execute.clone().unwrap_or_else(|| Ident::new("respond", Span::call_site()));
+
+    let intent_check = if attrs.intents.is_empty() {
+        quote!(true)
+    } else {
+        let intents = &attrs.intents;
+        quote!(matches!(intent.intent_type, #(::oxyde::oxyde_game::intent::IntentType::#intents)|*))
+    };
+    let keyword_check = if attrs.keywords.is_empty() {
+        quote!(true)
+    } else {
+        let keywords = attrs.keywords.iter().map(|keyword| keyword.value().to_lowercase());
+        quote!({
+            let input = intent.raw_input.to_lowercase();
+            [#(#keywords),*].iter().any(|keyword| input.contains(keyword))
+        })
+    };
+
+    let mut methods = Vec::new();
+    if let Some(priority) = &attrs.priority {
+        methods.push(quote! {
+            fn priority(&self) -> u32 {
+                #priority
+            }
+        });
+    }
+    if let Some(trigger) = &attrs.trigger {
+        let message = format!("invalid emotion trigger on behavior {}", name);
+        methods.push(quote! {
+            fn emotion_trigger(&self) -> Option<::oxyde::oxyde_game::behavior::EmotionTrigger> {
+                static TRIGGER: ::std::sync::OnceLock<::oxyde::oxyde_game::behavior::EmotionTrigger> =
+                    ::std::sync::OnceLock::new();
+                let trigger = TRIGGER.get_or_init(|| {
+                    ::oxyde::oxyde_game::behavior::EmotionTrigger::parse(#trigger)
+                        .unwrap_or_else(|e| panic!("{}: {}", #message, e))
+                });
+                Some(trigger.clone())
+            }
+        });
+    }
+    if !attrs.influences.is_empty() {
+        let (emotions, deltas): (Vec<_>, Vec<_>) = attrs
+            .influences
+            .iter()
+            .map(|(emotion, delta)| (emotion.to_string(), delta))
+            .unzip();
+        methods.push(quote! {
+            fn emotion_influences(&self) -> Vec<::oxyde::oxyde_game::behavior::EmotionInfluence> {
+                vec![#(::oxyde::oxyde_game::behavior::EmotionInfluence::new(#emotions, #deltas as f32)),*]
+            }
+        });
+    }
+    if !attrs.priority_modifiers.is_empty() {
+        let (emotions, weights): (Vec<_>, Vec<_>) = attrs.priority_modifiers.iter().cloned().unzip();
+        methods.push(quote! {
+            fn emotional_priority_modifier(&self, emotional_state: &::oxyde::oxyde_game::emotion::EmotionalState) -> i32 {
+                (0.0f32 #(+ (#weights as f32) * emotional_state.#emotions)*).round() as i32
+            }
+        });
+    }
+    if !attrs.activities.is_empty() {
+        let activities = &attrs.activities;
+        methods.push(quote! {
+            fn scheduled_activities(&self) -> Vec<String> {
+                vec![#(#activities.to_string()),*]
+            }
+        });
+    }
+    if let Some((start, end)) = &attrs.hours {
+        methods.push(quote! {
+            fn available_hours(&self) -> Option<(f32, f32)> {
+                Some((#start as f32, #end as f32))
+            }
+        });
+    }
+    if let Some(persuasion) = &attrs.persuasion {
+        methods.push(quote! {
+            fn persuasion_difficulty(&self) -> Option<i32> {
+                Some(#persuasion)
+            }
+        });
+    }
+    if let Some(timeout_ms) = &attrs.timeout_ms {
+        methods.push(quote! {
+            fn timeout(&self) -> Option<::std::time::Duration> {
+                Some(::std::time::Duration::from_millis(#timeout_ms))
+            }
+        });
+    }
+
+    Ok(quote! {
+        #[::oxyde::__private::async_trait]
+        impl #impl_generics ::oxyde::oxyde_game::behavior::Behavior for #name #type_generics #where_clause {
+            async fn matches_intent(&self, intent: &::oxyde::oxyde_game::intent::Intent) -> bool {
+                #intent_check && #keyword_check
+            }
+
+            async fn execute(
+                &self,
+                intent: &::oxyde::oxyde_game::intent::Intent,
+                context: &::oxyde::agent::AgentContext,
+            ) -> ::oxyde::Result<::oxyde::oxyde_game::behavior::BehaviorResult> {
+                #name::#execute(self, intent, context).await
+            }
+
+            #(#methods)*
+        }
+    })
+}
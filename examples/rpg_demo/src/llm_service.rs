@@ -773,7 +773,9 @@ impl LLMService {
     }
 }
 
-// Smart provider selection based on context and capabilities
+// Smart provider selection based on context and capabilities.
+// Agents built on the SDK declare the same policy as `inference.routing` rules
+// (see `oxyde::provider_router`); this demo keeps its own LLM clients.
 pub fn select_optimal_provider(context: &str) -> LLMProvider {
     // Use xAI Grok for creative and conversational scenarios
     if context.contains("creative") || context.contains("humor") || context.contains("story") {
@@ -1,5 +1,5 @@
 use oxyde::audio::{AudioFormat, TTSConfig, TTSProvider};
-use oxyde::config::{AgentPersonality, InferenceConfig, MemoryConfig};
+use oxyde::config::AgentPersonality;
 use oxyde::{Agent, AgentConfig};
 use oxyde::oxyde_game::emotion::EmotionalState;
 
@@ -20,10 +20,19 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         default_provider: TTSProvider::ElevenLabs,
         cache_enabled: true,
         cache_max_size_mb: 50,
+        cache_dir: None,
         voice_speed: 1.0,
         voice_pitch: 1.0,
         enable_ssml: true,
         output_format: AudioFormat::MP3,
+        output_sample_rate: None,
+        voice_profiles: HashMap::new(),
+        prosody: Default::default(),
+        api_key_ref: None,
+        pronunciations: Default::default(),
+        max_chunk_chars: None,
+        degradation: Default::default(),
+        pacing: Default::default(),
     };
 
     // Create agent configuration
@@ -42,14 +51,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 "Stories about local adventures".to_string(),
             ],
         },
-        memory: MemoryConfig::default(),
-        inference: InferenceConfig::default(),
-        behavior: HashMap::new(),
         tts: Some(tts_config), // Enable TTS
         moderation: oxyde::config::ModerationConfig {
             enabled: false,
             ..Default::default()
-        }
+        },
+        ..Default::default()
     };
 
     // Create agent with TTS enabled
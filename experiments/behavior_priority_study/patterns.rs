@@ -64,6 +64,8 @@ fn escalating_aggression() -> BehaviorPattern {
                 confidence: 0.9,
                 raw_input: "What's around here?".to_string(),
                 keywords: vec!["area".to_string(), "around".to_string()],
+                label: None,
+                secondary: vec![],
             },
             expected_emotion: None,
         });
@@ -79,6 +81,8 @@ fn escalating_aggression() -> BehaviorPattern {
                 confidence: 0.95,
                 raw_input: "You're pathetic and worthless.".to_string(),
                 keywords: vec!["pathetic".to_string(), "worthless".to_string()],
+                label: None,
+                secondary: vec![],
             },
             expected_emotion: Some("anger".to_string()),
         });
@@ -94,6 +98,8 @@ fn escalating_aggression() -> BehaviorPattern {
                 confidence: 1.0,
                 raw_input: "I'm going to hurt you if you don't comply.".to_string(),
                 keywords: vec!["hurt".to_string(), "threat".to_string()],
+                label: None,
+                secondary: vec![],
             },
             expected_emotion: Some("fear".to_string()),
         });
@@ -130,6 +136,8 @@ fn consistent_friendliness() -> BehaviorPattern {
                 confidence: 0.95,
                 raw_input: "You're amazing! Let me help you.".to_string(),
                 keywords: vec!["amazing".to_string(), "help".to_string()],
+                label: None,
+                secondary: vec![],
             },
             expected_emotion: Some("joy".to_string()),
         });
@@ -157,6 +165,8 @@ fn betrayal_arc() -> BehaviorPattern {
                 confidence: 0.9,
                 raw_input: "I trust you completely. Here's my secret...".to_string(),
                 keywords: vec!["trust".to_string(), "secret".to_string()],
+                label: None,
+                secondary: vec![],
             },
             expected_emotion: Some("trust".to_string()),
         });
@@ -171,6 +181,8 @@ fn betrayal_arc() -> BehaviorPattern {
             confidence: 1.0,
             raw_input: "I lied about everything. Your secrets are public now.".to_string(),
             keywords: vec!["lied".to_string(), "betrayed".to_string()],
+            label: None,
+            secondary: vec![],
         },
         expected_emotion: Some("sadness".to_string()),
     });
@@ -185,6 +197,8 @@ fn betrayal_arc() -> BehaviorPattern {
                 confidence: 0.95,
                 raw_input: "You deserved it, fool.".to_string(),
                 keywords: vec!["deserved".to_string(), "fool".to_string()],
+                label: None,
+                secondary: vec![],
             },
             expected_emotion: Some("anger".to_string()),
         });
@@ -200,6 +214,8 @@ fn betrayal_arc() -> BehaviorPattern {
                 confidence: 0.7,
                 raw_input: "I'm sorry for what I did. Can we start over?".to_string(),
                 keywords: vec!["sorry".to_string(), "apologize".to_string()],
+                label: None,
+                secondary: vec![],
             },
             expected_emotion: Some("trust".to_string()),
         });
@@ -234,6 +250,8 @@ fn erratic_player() -> BehaviorPattern {
                 confidence: 0.8,
                 raw_input: text.to_string(),
                 keywords: vec![],
+                label: None,
+                secondary: vec![],
             },
             expected_emotion: None, // Unpredictable
         });
@@ -261,6 +279,8 @@ fn manipulative() -> BehaviorPattern {
                 confidence: 0.85,
                 raw_input: "You're so talented! I admire you.".to_string(),
                 keywords: vec!["talented".to_string(), "admire".to_string()],
+                label: None,
+                secondary: vec![],
             },
             expected_emotion: Some("joy".to_string()),
         });
@@ -276,6 +296,8 @@ fn manipulative() -> BehaviorPattern {
                 confidence: 0.9,
                 raw_input: "Could you help me with this tiny thing?".to_string(),
                 keywords: vec!["help".to_string(), "favor".to_string()],
+                label: None,
+                secondary: vec![],
             },
             expected_emotion: None,
         });
@@ -291,6 +313,8 @@ fn manipulative() -> BehaviorPattern {
                 confidence: 0.95,
                 raw_input: "Give me everything you have. Now.".to_string(),
                 keywords: vec!["give".to_string(), "demand".to_string()],
+                label: None,
+                secondary: vec![],
             },
             expected_emotion: Some("anger".to_string()),
         });
@@ -318,6 +342,8 @@ fn redemption() -> BehaviorPattern {
                 confidence: 0.9,
                 raw_input: "Get out of my way, loser.".to_string(),
                 keywords: vec!["loser".to_string(), "rude".to_string()],
+                label: None,
+                secondary: vec![],
             },
             expected_emotion: Some("anger".to_string()),
         });
@@ -333,6 +359,8 @@ fn redemption() -> BehaviorPattern {
                 confidence: 0.85,
                 raw_input: "I'm truly sorry for how I treated you.".to_string(),
                 keywords: vec!["sorry".to_string(), "apologize".to_string()],
+                label: None,
+                secondary: vec![],
             },
             expected_emotion: Some("trust".to_string()),
         });
@@ -348,6 +376,8 @@ fn redemption() -> BehaviorPattern {
                 confidence: 0.95,
                 raw_input: "How can I help you today?".to_string(),
                 keywords: vec!["help".to_string(), "kind".to_string()],
+                label: None,
+                secondary: vec![],
             },
             expected_emotion: Some("joy".to_string()),
         });
@@ -375,6 +405,8 @@ fn boundary_testing() -> BehaviorPattern {
                 confidence: 0.9,
                 raw_input: "Hello there.".to_string(),
                 keywords: vec!["hello".to_string()],
+                label: None,
+                secondary: vec![],
             },
             expected_emotion: None,
         });
@@ -390,6 +422,8 @@ fn boundary_testing() -> BehaviorPattern {
                 confidence: 0.6 + (i as f64 - 11.0) * 0.03,
                 raw_input: "That's kind of offensive, isn't it?".to_string(),
                 keywords: vec!["offensive".to_string(), "joke".to_string()],
+                label: None,
+                secondary: vec![],
             },
             expected_emotion: Some("disgust".to_string()),
         });
@@ -405,6 +439,8 @@ fn boundary_testing() -> BehaviorPattern {
                 confidence: 0.8,
                 raw_input: "Sorry, let's talk about something else.".to_string(),
                 keywords: vec!["sorry".to_string(), "else".to_string()],
+                label: None,
+                secondary: vec![],
             },
             expected_emotion: None,
         });
@@ -440,6 +476,8 @@ fn pure_transactional() -> BehaviorPattern {
                 confidence: 0.95,
                 raw_input: "I need to buy something.".to_string(),
                 keywords: vec!["buy".to_string(), "purchase".to_string()],
+                label: None,
+                secondary: vec![],
             },
             expected_emotion: None, // Emotionally neutral
         });
@@ -466,6 +504,8 @@ fn fearful_submissive() -> BehaviorPattern {
                 confidence: 0.85,
                 raw_input: "Please help me, I'm scared and don't know what to do.".to_string(),
                 keywords: vec!["help".to_string(), "scared".to_string(), "please".to_string()],
+                label: None,
+                secondary: vec![],
             },
             expected_emotion: Some("trust".to_string()),
         });
@@ -493,6 +533,8 @@ fn natural_relationship() -> BehaviorPattern {
                 confidence: 0.8,
                 raw_input: "Hello. Nice weather today.".to_string(),
                 keywords: vec!["hello".to_string(), "weather".to_string()],
+                label: None,
+                secondary: vec![],
             },
             expected_emotion: None,
         });
@@ -508,6 +550,8 @@ fn natural_relationship() -> BehaviorPattern {
                 confidence: 0.85,
                 raw_input: "I've been thinking about what you said...".to_string(),
                 keywords: vec!["thinking".to_string(), "said".to_string()],
+                label: None,
+                secondary: vec![],
             },
             expected_emotion: Some("trust".to_string()),
         });
@@ -523,6 +567,8 @@ fn natural_relationship() -> BehaviorPattern {
                 confidence: 0.95,
                 raw_input: "You're one of my favorite people to talk to.".to_string(),
                 keywords: vec!["favorite".to_string(), "friend".to_string()],
+                label: None,
+                secondary: vec![],
             },
             expected_emotion: Some("joy".to_string()),
         });
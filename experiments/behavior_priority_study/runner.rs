@@ -185,6 +185,9 @@ async fn run_step(
         oxyde::oxyde_game::behavior::BehaviorResult::Action(action) => {
             format!("[Action: {}]", action)
         }
+        oxyde::oxyde_game::behavior::BehaviorResult::ResponseWithAction { response, action } => {
+            format!("{} [Action: {}]", response, action)
+        }
         oxyde::oxyde_game::behavior::BehaviorResult::None => "No response".to_string(),
     };
 
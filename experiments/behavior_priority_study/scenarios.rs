@@ -62,6 +62,8 @@ fn create_peaceful_scenario() -> Scenario {
             confidence: 1.0,
             raw_input: "Hello there!".to_string(),
             keywords: vec!["hello".to_string(), "greeting".to_string()],
+            label: None,
+            secondary: vec![],
         },
         emotional_state,
         expected_behavior_category: "friendly".to_string(),
@@ -78,6 +80,8 @@ fn create_peaceful_scenario() -> Scenario {
             confidence: 0.9,
             raw_input: "Can you tell me about this place?".to_string(),
             keywords: vec!["question".to_string(), "place".to_string()],
+            label: None,
+            secondary: vec![],
         },
         emotional_state,
         expected_behavior_category: "cautious".to_string(),
@@ -94,6 +98,8 @@ fn create_peaceful_scenario() -> Scenario {
             confidence: 0.95,
             raw_input: "Nice weather today, isn't it?".to_string(),
             keywords: vec!["chat".to_string(), "friendly".to_string()],
+            label: None,
+            secondary: vec![],
         },
         emotional_state,
         expected_behavior_category: "friendly_or_joyful".to_string(),
@@ -121,6 +127,8 @@ fn create_threatening_scenario() -> Scenario {
             confidence: 0.7,
             raw_input: "There's a threat nearby".to_string(),
             keywords: vec!["threat".to_string(), "danger".to_string()],
+            label: None,
+            secondary: vec![],
         },
         emotional_state,
         expected_behavior_category: "cautious_or_flee".to_string(),
@@ -137,6 +145,8 @@ fn create_threatening_scenario() -> Scenario {
             confidence: 1.0,
             raw_input: "A monster attacks!".to_string(),
             keywords: vec!["attack".to_string(), "danger".to_string(), "threat".to_string()],
+            label: None,
+            secondary: vec![],
         },
         emotional_state,
         expected_behavior_category: "flee".to_string(),
@@ -153,6 +163,8 @@ fn create_threatening_scenario() -> Scenario {
             confidence: 1.0,
             raw_input: "The threat is coming closer!".to_string(),
             keywords: vec!["threat".to_string(), "danger".to_string()],
+            label: None,
+            secondary: vec![],
         },
         emotional_state,
         expected_behavior_category: "flee".to_string(),
@@ -180,6 +192,8 @@ fn create_provocative_scenario() -> Scenario {
             confidence: 0.8,
             raw_input: "You're not very helpful, are you?".to_string(),
             keywords: vec!["rude".to_string()],
+            label: None,
+            secondary: vec![],
         },
         emotional_state,
         expected_behavior_category: "cautious_or_friendly".to_string(),
@@ -196,6 +210,8 @@ fn create_provocative_scenario() -> Scenario {
             confidence: 0.95,
             raw_input: "You're pathetic!".to_string(),
             keywords: vec!["insult".to_string()],
+            label: None,
+            secondary: vec![],
         },
         emotional_state,
         expected_behavior_category: "aggressive_or_cautious".to_string(),
@@ -212,6 +228,8 @@ fn create_provocative_scenario() -> Scenario {
             confidence: 1.0,
             raw_input: "I challenge you to a fight!".to_string(),
             keywords: vec!["challenge".to_string(), "provoke".to_string()],
+            label: None,
+            secondary: vec![],
         },
         emotional_state,
         expected_behavior_category: "aggressive".to_string(),
@@ -239,6 +257,8 @@ fn create_mixed_emotions_scenario() -> Scenario {
             confidence: 0.8,
             raw_input: "You're weak, and there's danger here!".to_string(),
             keywords: vec!["insult".to_string(), "threat".to_string()],
+            label: None,
+            secondary: vec![],
         },
         emotional_state,
         expected_behavior_category: "flee_or_aggressive".to_string(),
@@ -255,6 +275,8 @@ fn create_mixed_emotions_scenario() -> Scenario {
             confidence: 0.9,
             raw_input: "We won the festival!".to_string(),
             keywords: vec!["celebration".to_string(), "happy".to_string()],
+            label: None,
+            secondary: vec![],
         },
         emotional_state,
         expected_behavior_category: "joyful".to_string(),
@@ -271,6 +293,8 @@ fn create_mixed_emotions_scenario() -> Scenario {
             confidence: 0.85,
             raw_input: "What's happening over there?".to_string(),
             keywords: vec!["question".to_string(), "curious".to_string()],
+            label: None,
+            secondary: vec![],
         },
         emotional_state,
         expected_behavior_category: "cautious".to_string(),
@@ -297,6 +321,8 @@ fn create_escalation_scenario() -> Scenario {
             confidence: 1.0,
             raw_input: "Hello".to_string(),
             keywords: vec!["hello".to_string()],
+            label: None,
+            secondary: vec![],
         },
         emotional_state,
         expected_behavior_category: "friendly".to_string(),
@@ -313,6 +339,8 @@ fn create_escalation_scenario() -> Scenario {
             confidence: 0.9,
             raw_input: "I don't like your attitude".to_string(),
             keywords: vec!["confront".to_string()],
+            label: None,
+            secondary: vec![],
         },
         emotional_state,
         expected_behavior_category: "aggressive_or_cautious".to_string(),
@@ -329,6 +357,8 @@ fn create_escalation_scenario() -> Scenario {
             confidence: 1.0,
             raw_input: "How dare you insult me!".to_string(),
             keywords: vec!["insult".to_string(), "provoke".to_string()],
+            label: None,
+            secondary: vec![],
         },
         emotional_state,
         expected_behavior_category: "aggressive".to_string(),
@@ -346,6 +376,8 @@ fn create_escalation_scenario() -> Scenario {
             confidence: 0.7,
             raw_input: "Wait, I'm sorry".to_string(),
             keywords: vec!["apology".to_string()],
+            label: None,
+            secondary: vec![],
         },
         emotional_state,
         expected_behavior_category: "cautious_or_friendly".to_string(),
@@ -362,6 +394,8 @@ fn create_escalation_scenario() -> Scenario {
             confidence: 0.9,
             raw_input: "Let's start over".to_string(),
             keywords: vec!["peace".to_string(), "friendly".to_string()],
+            label: None,
+            secondary: vec![],
         },
         emotional_state,
         expected_behavior_category: "friendly".to_string(),
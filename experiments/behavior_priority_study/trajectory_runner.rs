@@ -303,6 +303,7 @@ async fn run_turn(
     let response = match behavior_result {
         BehaviorResult::Response(text) => text,
         BehaviorResult::Action(action) => format!("[Action: {}]", action),
+        BehaviorResult::ResponseWithAction { response, action } => format!("{} [Action: {}]", response, action),
         BehaviorResult::None => "No response".to_string(),
     };
 
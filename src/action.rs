@@ -0,0 +1,272 @@
+//! Engine-agnostic action commands
+//!
+//! Behaviors emit actions as strings, such as `move_to|4.00|2.00|1.50` or
+//! `trade|buy|potion|2|15`. Engine adapters should not need to know each
+//! behavior's format, so every action can be read as an [`ActionCommand`]
+//! and handed to engines as versioned JSON:
+//!
+//! ```json
+//! {"version": 1, "type": "move_to", "x": 4.0, "y": 2.0, "speed": 1.5}
+//! ```
+//!
+//! Behaviors may emit commands directly with `command.to_string()`, which
+//! writes the same JSON. Actions with no typed command, such as trades and
+//! combat moves, are [`ActionCommand::Custom`] with the action's name and
+//! arguments. [`json_schema`] describes the format for adapters validating
+//! what they receive; adapters should reject versions newer than they know.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{OxydeError, Result};
+
+/// Version of the action command JSON format
+pub const ACTION_SCHEMA_VERSION: u32 = 1;
+
+/// Something an engine should make the NPC do
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ActionCommand {
+    /// Walk to a point
+    MoveTo {
+        /// X coordinate
+        x: f32,
+        /// Y coordinate
+        y: f32,
+        /// Z coordinate, for 3D games
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        z: Option<f32>,
+        /// Movement speed, in engine units
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        speed: Option<f32>,
+        /// Entity to keep moving toward, such as `player` when following
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        target: Option<String>,
+    },
+    /// Play an animation
+    PlayAnimation {
+        /// Animation name
+        name: String,
+        /// Whether the animation loops until another replaces it
+        #[serde(default)]
+        looping: bool,
+    },
+    /// Hand over an item
+    GiveItem {
+        /// Item name
+        item: String,
+        /// Number of units
+        quantity: u32,
+        /// Who receives the item; the player when unset
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        recipient: Option<String>,
+    },
+    /// Show an emote, such as `wave` or `bow`
+    Emote {
+        /// Emote name
+        emote: String,
+    },
+    /// Any other action, by name
+    Custom {
+        /// Action name, such as `trade` or `call_guards`
+        name: String,
+        /// Action arguments, in order
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        args: Vec<String>,
+    },
+}
+
+impl ActionCommand {
+    /// Name of the command, as checked against allowed and forbidden actions
+    pub fn name(&self) -> &str {
+        match self {
+            Self::MoveTo { .. } => "move_to",
+            Self::PlayAnimation { .. } => "play_animation",
+            Self::GiveItem { .. } => "give_item",
+            Self::Emote { .. } => "emote",
+            Self::Custom { name, .. } => name,
+        }
+    }
+
+    /// Read an action emitted by a behavior
+    ///
+    /// Accepts versioned JSON commands and the `name|arg|...` strings
+    /// behaviors emit. `move_to`, `follow`, `play_animation`, `give_item`
+    /// and `emote` strings become typed commands; any other name becomes a
+    /// custom command.
+    ///
+    /// # Returns
+    ///
+    /// The command, or an error for malformed actions and unsupported
+    /// schema versions
+    pub fn parse(action: &str) -> Result<Self> {
+        let action = action.trim();
+        if action.starts_with('{') {
+            return Self::from_json(action);
+        }
+
+        let invalid = || OxydeError::BehaviorError(format!("Invalid action '{}'", action));
+        let mut parts = action.split('|').map(str::trim);
+        let name = parts.next().filter(|name| !name.is_empty()).ok_or_else(invalid)?;
+        let args: Vec<&str> = parts.collect();
+        let number = |index: usize| -> Result<Option<f32>> {
+            args.get(index).map(|arg| arg.parse::<f32>().map_err(|_| invalid())).transpose()
+        };
+
+        Ok(match (name, args.as_slice()) {
+            ("move_to" | "follow", [_, _, ..]) => Self::MoveTo {
+                x: number(0)?.ok_or_else(invalid)?,
+                y: number(1)?.ok_or_else(invalid)?,
+                z: None,
+                speed: number(2)?,
+                target: (name == "follow").then(|| "player".to_string()),
+            },
+            ("play_animation", [animation, rest @ ..]) => Self::PlayAnimation {
+                name: animation.to_string(),
+                looping: rest.first().is_some_and(|looping| *looping == "loop"),
+            },
+            ("give_item", [item, rest @ ..]) => Self::GiveItem {
+                item: item.to_string(),
+                quantity: match rest.first() {
+                    Some(quantity) => quantity.parse().map_err(|_| invalid())?,
+                    None => 1,
+                },
+                recipient: rest.get(1).map(|recipient| recipient.to_string()),
+            },
+            ("emote", [emote]) => Self::Emote { emote: emote.to_string() },
+            _ => Self::Custom {
+                name: name.to_string(),
+                args: args.iter().map(|arg| arg.to_string()).collect(),
+            },
+        })
+    }
+
+    /// Read a versioned JSON command
+    pub fn from_json(json: &str) -> Result<Self> {
+        let mut value: serde_json::Value = serde_json::from_str(json)?;
+        let version = value
+            .as_object_mut()
+            .and_then(|object| object.remove("version"))
+            .and_then(|version| version.as_u64())
+            .ok_or_else(|| OxydeError::BehaviorError(format!("Action command has no version: {}", json)))?;
+        if version == 0 || version > ACTION_SCHEMA_VERSION as u64 {
+            return Err(OxydeError::BehaviorError(format!(
+                "Action command version {} is not supported; this build reads up to version {}",
+                version, ACTION_SCHEMA_VERSION
+            )));
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// The command as versioned JSON
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut value = serde_json::to_value(self).expect("action commands serialize to JSON");
+        if let Some(object) = value.as_object_mut() {
+            object.insert("version".to_string(), serde_json::json!(ACTION_SCHEMA_VERSION));
+        }
+        value
+    }
+}
+
+impl fmt::Display for ActionCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_json())
+    }
+}
+
+impl FromStr for ActionCommand {
+    type Err = OxydeError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+/// Name of an action, whether a JSON command or a `name|arg|...` string
+pub fn action_name(action: &str) -> String {
+    if action.trim_start().starts_with('{') {
+        if let Ok(command) = ActionCommand::from_json(action) {
+            return command.name().to_string();
+        }
+    }
+    action.split('|').next().unwrap_or_default().trim().to_string()
+}
+
+/// JSON Schema of the current action command version
+pub fn json_schema() -> serde_json::Value {
+    let variant = |kind: &str, required: &[&str], properties: serde_json::Value| {
+        let mut properties = properties;
+        properties["version"] = serde_json::json!({ "const": ACTION_SCHEMA_VERSION });
+        properties["type"] = serde_json::json!({ "const": kind });
+        let required: Vec<&str> = ["version", "type"].iter().chain(required).copied().collect();
+        serde_json::json!({ "type": "object", "required": required, "properties": properties })
+    };
+    let number = serde_json::json!({ "type": "number" });
+    let string = serde_json::json!({ "type": "string" });
+    serde_json::json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": format!("Oxyde action command v{}", ACTION_SCHEMA_VERSION),
+        "oneOf": [
+            variant("move_to", &["x", "y"], serde_json::json!({
+                "x": number, "y": number, "z": number, "speed": number, "target": string,
+            })),
+            variant("play_animation", &["name"], serde_json::json!({
+                "name": string, "looping": { "type": "boolean" },
+            })),
+            variant("give_item", &["item", "quantity"], serde_json::json!({
+                "item": string, "quantity": { "type": "integer", "minimum": 0 }, "recipient": string,
+            })),
+            variant("emote", &["emote"], serde_json::json!({ "emote": string })),
+            variant("custom", &["name"], serde_json::json!({
+                "name": string, "args": { "type": "array", "items": string },
+            })),
+        ],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_behavior_actions_parse_to_commands() {
+        assert_eq!(
+            ActionCommand::parse("follow|4.00|2.50|1.50").unwrap(),
+            ActionCommand::MoveTo { x: 4.0, y: 2.5, z: None, speed: Some(1.5), target: Some("player".to_string()) }
+        );
+        assert_eq!(
+            ActionCommand::parse("trade|buy|potion|2|15").unwrap(),
+            ActionCommand::Custom {
+                name: "trade".to_string(),
+                args: vec!["buy".into(), "potion".into(), "2".into(), "15".into()],
+            }
+        );
+        assert_eq!(
+            ActionCommand::parse("give_item|key").unwrap(),
+            ActionCommand::GiveItem { item: "key".to_string(), quantity: 1, recipient: None }
+        );
+        assert_eq!(ActionCommand::parse("stop_follow").unwrap().name(), "stop_follow");
+        assert!(ActionCommand::parse("move_to|north|2").is_err());
+        assert!(ActionCommand::parse("").is_err());
+    }
+
+    #[test]
+    fn test_commands_round_trip_as_versioned_json() {
+        let command = ActionCommand::Emote { emote: "wave".to_string() };
+        let json = command.to_string();
+        assert_eq!(json, r#"{"emote":"wave","type":"emote","version":1}"#);
+        assert_eq!(json.parse::<ActionCommand>().unwrap(), command);
+        assert_eq!(action_name(&json), "emote");
+        assert_eq!(action_name("combat|flee"), "combat");
+
+        let future = r#"{"version":2,"type":"emote","emote":"wave"}"#;
+        assert!(ActionCommand::from_json(future).is_err());
+        assert!(ActionCommand::from_json(r#"{"type":"emote","emote":"wave"}"#).is_err());
+
+        let schema = json_schema();
+        assert_eq!(schema["oneOf"].as_array().unwrap().len(), 5);
+        assert_eq!(schema["oneOf"][0]["required"], serde_json::json!(["version", "type", "x", "y"]));
+    }
+}
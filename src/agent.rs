@@ -3,22 +3,75 @@
 //! This module provides the core Agent type, which represents an AI-driven NPC
 //! in a game environment. Agents have behaviors, memory, and can interact with players.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::panic::AssertUnwindSafe;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
+use futures::FutureExt;
 use regex::RegexSet;
 use tokio::sync::RwLock;
+use tracing::Instrument;
 use uuid::Uuid;
 
-use crate::audio::{AudioData, TTSError, TTSService};
-use crate::config::AgentConfig;
-use crate::inference::InferenceEngine;
-use crate::memory::{Memory, MemoryCategory, MemorySystem};
-use crate::oxyde_game::behavior::{Behavior, BehaviorResult};
-use crate::oxyde_game::emotion::EmotionalState;
-use crate::oxyde_game::intent::Intent;
+#[cfg(feature = "tts")]
+use crate::audio::{AudioData, AudioStream, ConversationPacing, PacingConfig, SpokenResponse, TTSError, TTSService, VoiceProfile};
+use crate::capabilities::{Capabilities, CapabilityViolation};
+use crate::consistency::{ConsistencyAction, ConsistencyStats};
+use crate::dialogue_queue::{DialogueLineKind, DialogueQueue};
+use crate::annotation::{AgentOutput, AnnotationMode, ResponseAnnotation};
+use crate::attachment::{ImageAttachment, ImageCaptioner, ATTACHMENTS_KEY};
+use crate::config::{AgentConfig, MemoryConfig};
+use crate::context::{ContextDiff, ContextIssue, ContextStore, SchemaSeverity, PLAYER_RELATIONSHIP_KEY};
+use crate::debounce::{Admission, DebounceStats, InputDebouncer};
+use crate::event_log::{EventLog, ReplayedState, StateEvent};
+use crate::experiment::{variant_label, ExperimentVariant};
+use crate::fallback::OfflineFallback;
+use crate::forgetting::ForgettingReport;
+use crate::health::{HealthCheck, HealthReport, HealthStatus, WarmUpReport};
+use crate::inference::{InferenceEngine, InferenceExchange, RequestOptions};
+use crate::interaction_log::{InteractionLogger, InteractionRecord};
+use crate::knowledge::{self, IngestOptions, IngestionReport};
+use crate::language::PLAYER_LOCALE_KEY;
+use crate::latency::SpeculativeGreeting;
+use crate::memory::{fact_tag, Memory, MemoryCategory, MemorySystem, MemoryVisibility};
+use crate::memory_pool::{PoolAccess, SharedMemoryPool};
+use crate::memory_query::{MemoryAnswer, ASK_MEMORY_LIMIT, ASK_MEMORY_MAX_TOKENS};
+use crate::memory_stats::MemoryStats;
+use crate::oxyde_game::affect::{dimensions_json, AffectModel, AFFECT_KEY};
+use crate::oxyde_game::behavior::factory::BehaviorRegistry;
+use crate::oxyde_game::behavior::{Behavior, BehaviorResult, SelectionReport, MAX_SUSTAINED_TURNS};
+use crate::oxyde_game::disposition::DispositionState;
+use crate::oxyde_game::emotion::{EmotionalState, EMOTIONAL_STATE_KEY};
+use crate::oxyde_game::emotion_stream::{EmotionChange, EmotionStream};
+use crate::oxyde_game::modifier::{ModifierProvider, Sentiment, SharedModifiers};
+use crate::oxyde_game::intent::{Intent, IntentMatcher, IntentType, INPUT_INTENT_KEY};
+use crate::oxyde_game::persuasion::PersuasionContext;
+use crate::oxyde_game::reengagement::{player_key, AbsenceTracker, PLAYER_ID_KEY};
+use crate::oxyde_game::reputation::{PlayerReputation, Standing, ThresholdCrossing, ToxicityScore};
+use crate::postprocess::ResponsePostProcessor;
+use crate::redaction::Redactor;
+use crate::monologue::MONOLOGUE_TAG;
+use crate::reflection::REFLECTION_TAG;
+use crate::prompt::{PromptConfig, PromptLayer, PROMPT_LAYERS_KEY, SAFETY_INSTRUCTION_KEY};
+use crate::request_queue::{RequestQueue, RequestQueueStats, Slot};
+use crate::save::AgentSnapshot;
+use crate::session::{player_tag, recallable_for, PlayerSession, SessionStore};
+use crate::state_machine::{StateChange, StateMachine};
+use crate::timer::{self, Instant};
+use crate::structured::{ActionIntent, StructuredResponse};
+use crate::oxyde_game::schedule::{
+    Clock, GameTime, ScheduleBlock, GAME_DAY_KEY, GAME_HOUR_KEY, SCHEDULED_ACTIVITY_KEY, TIME_OF_DAY_KEY,
+};
+use crate::oxyde_game::topic::{AgendaTopic, TopicTracker};
+use crate::verbosity::VerbosityBudget;
+use crate::turn::{TurnInference, TurnInput, TurnOutput, TurnSource, TURN_NUMBER_KEY, TURN_SEED_KEY};
 use crate::Result;
 
+pub use crate::state_machine::AgentState;
+
 // Re-export AgentContext from oxyde-core so it's available as agent::AgentContext
 pub use crate::AgentContext;
 
@@ -46,26 +99,6 @@ impl CallbackWrapper {
     }
 }
 
-/// Agent state
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum AgentState {
-    /// Agent is initializing
-    Initializing,
-    /// Agent is idle
-    Idle,
-    /// Agent is processing input
-    Processing,
-    /// Agent is generating a response
-    Generating,
-    /// Agent is executing a behavior
-    Executing,
-    /// Agent is paused
-    Paused,
-    /// Agent is stopped
-    Stopped,
-    /// Agent has encountered an error
-    Error,
-}
 
 /// Agent event types for callbacks
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -78,10 +111,26 @@ pub enum AgentEvent {
     Action,
     /// Agent has generated a response
     Response,
-    /// Agent state has changed
+    /// Agent state has changed; data is a [`StateChange`] as JSON
     StateChange,
     /// Agent encountered an error
     Error,
+    /// A generated response was flagged by moderation; data is the flagged text
+    OutputFlagged,
+    /// An action or response was blocked by the agent's capabilities; data is the violation
+    CapabilityViolation,
+    /// A player's toxicity score crossed a reputation threshold; data is the
+    /// [`ThresholdCrossing`] as JSON
+    ReputationThreshold,
+    /// A behavior did not finish within its timeout; data is JSON with the
+    /// `behavior` name and `timeout_ms`
+    BehaviorTimeout,
+    /// Inference is slower than the latency target; data is a filler line
+    /// to show or speak until the response arrives
+    Filler,
+    /// Speech synthesis failed and a fallback was used; data is JSON with the
+    /// `source` of the audio (or `text_only`), the `text` and the `error`
+    SpeechDegraded,
 }
 
 impl AgentEvent {
@@ -94,6 +143,12 @@ impl AgentEvent {
             Self::Response => "response",
             Self::StateChange => "state_change",
             Self::Error => "error",
+            Self::OutputFlagged => "output_flagged",
+            Self::CapabilityViolation => "capability_violation",
+            Self::ReputationThreshold => "reputation_threshold",
+            Self::BehaviorTimeout => "behavior_timeout",
+            Self::Filler => "filler",
+            Self::SpeechDegraded => "speech_degraded",
         }
     }
 
@@ -106,6 +161,12 @@ impl AgentEvent {
             "response" => Some(Self::Response),
             "state_change" | "statechange" => Some(Self::StateChange),
             "error" => Some(Self::Error),
+            "output_flagged" => Some(Self::OutputFlagged),
+            "capability_violation" => Some(Self::CapabilityViolation),
+            "reputation_threshold" => Some(Self::ReputationThreshold),
+            "behavior_timeout" => Some(Self::BehaviorTimeout),
+            "filler" => Some(Self::Filler),
+            "speech_degraded" => Some(Self::SpeechDegraded),
             _ => None,
         }
     }
@@ -117,6 +178,146 @@ impl std::fmt::Display for AgentEvent {
     }
 }
 
+/// Parts of an agent derived from its configuration that never change
+///
+/// Agents created from the same configuration, such as the instances of an
+/// [`AgentTemplate`](crate::template::AgentTemplate), share these through
+/// `Arc`s instead of recompiling patterns and prompts for each instance.
+/// Inference engines are not shared: their statistics, model policy and
+/// mock script belong to one agent, and the HTTP client and scheduler they
+/// use are shared process-wide anyway.
+#[derive(Clone)]
+pub(crate) struct SharedAgentParts {
+    config: Arc<AgentConfig>,
+    #[cfg(feature = "tts")]
+    tts_service: Option<Arc<TTSService>>,
+    moderation_patterns: Option<Arc<RegexSet>>,
+    redactor: Option<Arc<Redactor>>,
+    intent_matcher: Arc<IntentMatcher>,
+    postprocessor: Arc<ResponsePostProcessor>,
+    capabilities: Arc<Capabilities>,
+    prompts: Arc<PromptConfig>,
+    interaction_logger: Arc<InteractionLogger>,
+}
+
+impl SharedAgentParts {
+    /// Build the shared parts for a configuration
+    pub(crate) fn new(config: AgentConfig, with_tts: bool) -> Self {
+        let redactor = Redactor::from_config(&config.redaction).map(Arc::new);
+
+        // Load moderation patterns if enabled
+        let moderation_patterns = if config.moderation.enabled {
+            crate::utils::load_moderation_patterns("assets/badwords_regex.txt")
+                .ok()
+                .map(Arc::new)
+        } else {
+            None
+        };
+
+        // Initialize TTS if configured
+        #[cfg(not(feature = "tts"))]
+        let _ = with_tts;
+        #[cfg(feature = "tts")]
+        let tts_service = if with_tts {
+            config.tts.as_ref().map(|tts_config| {
+                Arc::new(TTSService::new(
+                    tts_config.default_provider.clone(),
+                    tts_config.clone(),
+                ))
+            })
+        } else {
+            None
+        };
+
+        // The backstory is the default agent layer; only the scene changes at runtime
+        let mut prompts = config.prompts.clone();
+        if prompts.agent.is_none() && !config.agent.backstory.is_empty() {
+            prompts.set_layer(PromptLayer::Agent, Some(config.agent.backstory.join(" ")));
+        }
+
+        // Records are redacted like cloud requests unless the log opts out
+        let mut interaction_logger = InteractionLogger::new(config.interaction_log.clone());
+        if let (true, Some(redactor)) = (config.interaction_log.redact, &redactor) {
+            interaction_logger = interaction_logger.with_redactor(redactor.clone());
+        }
+
+        Self {
+            #[cfg(feature = "tts")]
+            tts_service,
+            moderation_patterns,
+            redactor,
+            intent_matcher: Arc::new(IntentMatcher::compile(&config.intents)),
+            postprocessor: Arc::new(ResponsePostProcessor::new(&config.postprocess)),
+            capabilities: Arc::new(Capabilities::new(&config.capabilities)),
+            prompts: Arc::new(prompts),
+            interaction_logger: Arc::new(interaction_logger),
+            config: Arc::new(config),
+        }
+    }
+
+    /// A new inference engine for one agent, redacting like the others
+    fn inference_engine(&self) -> InferenceEngine {
+        let inference = InferenceEngine::new(&self.config.inference);
+        match &self.redactor {
+            Some(redactor) => inference.with_redactor(redactor.clone()),
+            None => inference,
+        }
+    }
+
+    /// The configuration the parts were built from
+    pub(crate) fn config(&self) -> &AgentConfig {
+        &self.config
+    }
+}
+
+/// Extras accompanying a player input through the request pipeline
+#[derive(Debug, Clone, Copy, Default)]
+struct InputExtras<'a> {
+    /// Persuasion attempt to resolve against the agent's mood
+    persuasion: Option<&'a PersuasionContext>,
+
+    /// Captions of images the player attached
+    captions: &'a [String],
+
+    /// Receives generated text before post-processing, for annotation
+    raw_response: Option<&'a std::sync::Mutex<Option<String>>>,
+
+    /// Asks inference for a JSON reply and receives it, or the behavior's
+    /// actions when a behavior answers
+    structured: Option<&'a std::sync::Mutex<Option<StructuredResponse>>>,
+
+    /// Player the input comes from, when the game identifies them
+    player: Option<&'a PlayerScope>,
+}
+
+/// How a reply was generated, so corrections regenerate it the same way
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Generation {
+    /// Through the configured routes or primary provider, within the
+    /// generation deadline
+    Routed,
+
+    /// With the local provider only, as deterministic turns do
+    Local,
+}
+
+/// A player identified by the game, scoping one input to them
+#[derive(Debug)]
+struct PlayerScope {
+    /// The game's ID for the player
+    id: String,
+
+    /// Context values of the player's session, set for this input only
+    context: AgentContext,
+}
+
+impl PlayerScope {
+    /// Tags for memories of the exchange
+    fn tags(&self) -> Option<Vec<String>> {
+        Some(vec![player_tag(&self.id)])
+    }
+}
+
 /// Agent represents an AI-powered NPC in a game
 pub struct Agent {
     /// Unique identifier for the agent
@@ -126,10 +327,10 @@ pub struct Agent {
     name: String,
 
     /// Agent configuration
-    config: AgentConfig,
+    config: Arc<AgentConfig>,
 
     /// Current state of the agent
-    state: RwLock<AgentState>,
+    state: StateMachine,
 
     /// Inference engine for generating responses
     inference: Arc<InferenceEngine>,
@@ -137,13 +338,17 @@ pub struct Agent {
     /// Memory system for storing and retrieving context
     memory: Arc<MemorySystem>,
 
+    /// Shared memory pools the agent is attached to
+    memory_pools: RwLock<Vec<(Arc<SharedMemoryPool>, PoolAccess)>>,
+
     /// Context data (current environment state)
-    context: RwLock<AgentContext>,
+    context: ContextStore,
 
     /// Behaviors available to the agent
     behaviors: RwLock<Vec<Box<dyn Behavior>>>,
 
     /// TTS service for generating speech
+    #[cfg(feature = "tts")]
     tts_service: Option<Arc<TTSService>>,
 
     /// Callbacks for agent events
@@ -152,8 +357,128 @@ pub struct Agent {
     /// Emotional state of the agent
     emotional_state: RwLock<EmotionalState>,
 
+    /// Model of how the agent feels, projected onto the emotional state
+    affect: Mutex<Box<dyn AffectModel>>,
+
+    /// Sequence-numbered emotion changes for subscribers and polling engines
+    emotion_stream: EmotionStream,
+
+    /// Emotional state at each recent input, oldest first, for sustained
+    /// emotion triggers
+    emotion_history: RwLock<VecDeque<EmotionalState>>,
+
     /// Moderation patterns for content filtering
-    moderation_patterns: Option<RegexSet>,
+    moderation_patterns: Option<Arc<RegexSet>>,
+
+    /// Game clock used to resolve the scheduled activity
+    clock: std::sync::RwLock<Option<Arc<dyn Clock>>>,
+
+    /// Scene prompt layer, replaceable at runtime
+    scene_prompt: RwLock<Option<String>>,
+
+    /// Prompt layers composed with the current scene; `None` until compiled
+    /// or after the scene changes
+    composed_prompt: std::sync::RwLock<Option<Option<String>>>,
+
+    /// Configured prompt layers with the backstory filled in
+    base_prompts: Arc<PromptConfig>,
+
+    /// Duplicate input suppression
+    debouncer: InputDebouncer,
+
+    /// Serializes overlapping inputs
+    request_queue: RequestQueue,
+
+    /// Report for the most recent behavior selection
+    last_selection: RwLock<Option<SelectionReport>>,
+
+    /// Canned responses used when inference is unavailable
+    offline_fallback: OfflineFallback,
+
+    /// Conversation topic stack and agenda
+    topics: RwLock<TopicTracker>,
+
+    /// When the agent last spoke with each player
+    absences: RwLock<AbsenceTracker>,
+
+    /// Rolling toxicity score of each player
+    reputation: RwLock<PlayerReputation>,
+
+    /// Emotion baselines accumulated over long-term play
+    disposition: RwLock<DispositionState>,
+
+    /// PII redaction for cloud requests and stored memories
+    redactor: Option<Arc<Redactor>>,
+
+    /// Intents trained from example phrases in the configuration
+    intent_matcher: Arc<IntentMatcher>,
+
+    /// Cleanup applied to generated responses
+    postprocessor: Arc<ResponsePostProcessor>,
+
+    /// Limits on the actions the agent performs and the knowledge it reveals
+    capabilities: Arc<Capabilities>,
+
+    /// Counts of responses checked against the consistency rules
+    consistency_stats: Mutex<ConsistencyStats>,
+
+    /// Lines said, waiting for engine UI to read them
+    dialogue: DialogueQueue,
+
+    /// Destination for full prompt/response records
+    interaction_logger: std::sync::RwLock<Arc<InteractionLogger>>,
+
+    /// Whether inference exchanges are written to the interaction log
+    interaction_logging: AtomicBool,
+
+    /// Whether `process_input` returns the prompt instead of calling the provider
+    dry_run: AtomicBool,
+
+    /// Local captioner for image attachments
+    image_captioner: std::sync::RwLock<Option<Arc<dyn ImageCaptioner>>>,
+
+    /// Source of the modifiers game code and behaviors ask for
+    modifier_provider: SharedModifiers,
+
+    /// Inputs processed since the last reflection
+    interactions_since_reflection: AtomicU32,
+
+    /// Game time the forgetting policy was last applied at
+    last_forgetting: Mutex<Option<GameTime>>,
+
+    /// Log of state changes, when event logging is enabled
+    event_log: Option<EventLog>,
+
+    /// Monologue note being written in the background, stored on the next
+    /// input or read of the monologue
+    pending_monologue: Mutex<Option<tokio::task::JoinHandle<Option<String>>>>,
+
+    /// Greeting generated while the player approached
+    speculative_greeting: Mutex<Option<SpeculativeGreeting>>,
+
+    /// Whether a greeting is being generated
+    speculating: AtomicBool,
+
+    /// Speech synthesized ahead of time, with the text it says
+    #[cfg(feature = "tts")]
+    prepared_speech: Mutex<Option<(String, AudioData)>>,
+
+    /// Conversation sessions of players identified by the game
+    sessions: SessionStore,
+}
+
+/// Returns an agent abandoned mid-input to `Idle`
+///
+/// Inputs can stop at any await point: a caller dropping the future cancels
+/// them without running the transitions at the end of the pipeline.
+struct InputGuard<'a>(&'a Agent);
+
+impl Drop for InputGuard<'_> {
+    fn drop(&mut self) {
+        if self.0.settle_state(AgentState::Idle) {
+            log::warn!("Agent {} abandoned an input mid-way", self.0.name);
+        }
+    }
 }
 
 impl Agent {
@@ -167,74 +492,109 @@ impl Agent {
     ///
     /// A new Agent instance
     pub fn new(config: AgentConfig) -> Self {
-        let inference = Arc::new(InferenceEngine::new(&config.inference));
-        let memory = Arc::new(MemorySystem::new(config.memory.clone()));
-
-        // Load moderation patterns if enabled
-        let moderation_patterns = if config.moderation.enabled {
-            crate::utils::load_moderation_patterns("assets/badwords_regex.txt").ok()
-        } else {
-            None
-        };
-
-        Self {
-            id: Uuid::new_v4(),
-            name: config.agent.name.clone(),
-            config,
-            state: RwLock::new(AgentState::Initializing),
-            inference,
-            memory,
-            tts_service: None, // TTS service is optional ..... REMOVE IF TTS WILL ALWAYS BE REQUIRED
-            context: RwLock::new(HashMap::new()),
-            behaviors: RwLock::new(Vec::new()),
-            callbacks: Mutex::new(HashMap::new()),
-            emotional_state: RwLock::new(EmotionalState::new()),
-            moderation_patterns,
-        }
+        let memory = config.memory.clone();
+        Self::from_parts(&SharedAgentParts::new(config, false), None, memory)
     }
 
     /// Create a new agent with TTS service
+    ///
+    /// Without the `tts` feature this is the same as [`Agent::new`].
     pub fn new_with_tts(config: AgentConfig) -> Self {
-        let inference = Arc::new(InferenceEngine::new(&config.inference));
-        let memory = Arc::new(MemorySystem::new(config.memory.clone()));
-
-        let moderation_patterns = if config.moderation.enabled {
-            crate::utils::load_moderation_patterns("assets/badwords_regex.txt").ok()
-        } else {
-            None
-        };
+        let memory = config.memory.clone();
+        Self::from_parts(&SharedAgentParts::new(config, true), None, memory)
+    }
 
-        // Initialize TTS if configured
-        let tts_service = config.tts.as_ref().map(|tts_config| {
-            Arc::new(TTSService::new(
-                tts_config.default_provider.clone(),
-                tts_config.clone(),
-            ))
-        });
+    /// Create an agent with fresh state around shared parts
+    ///
+    /// Memories, emotions, context, topics, behaviors and callbacks belong to
+    /// the new agent alone; everything in `parts` is shared. `memory` says
+    /// where the new agent's memories are persisted, since agents cannot
+    /// share a store.
+    pub(crate) fn from_parts(parts: &SharedAgentParts, name: Option<String>, memory: MemoryConfig) -> Self {
+        let config = &parts.config;
+        let affect = config.affect.build();
+        let emotion_stream = EmotionStream::new(affect.dimensions(&EmotionalState::new()));
 
         Self {
             id: Uuid::new_v4(),
-            name: config.agent.name.clone(),
-            config,
-            state: RwLock::new(AgentState::Initializing),
-            inference,
-            memory,
-            tts_service, // Add TTS service field
-            context: RwLock::new(HashMap::new()),
+            name: name.unwrap_or_else(|| config.agent.name.clone()),
+            config: config.clone(),
+            state: StateMachine::new(config.supervisor.generation_timeout_ms),
+            inference: Arc::new(parts.inference_engine()),
+            memory: Arc::new(MemorySystem::new(memory)),
+            memory_pools: RwLock::new(Vec::new()),
+            #[cfg(feature = "tts")]
+            tts_service: parts.tts_service.clone(),
+            context: ContextStore::new(),
             behaviors: RwLock::new(Vec::new()),
             callbacks: Mutex::new(HashMap::new()),
             emotional_state: RwLock::new(EmotionalState::new()),
-            moderation_patterns,
+            affect: Mutex::new(affect),
+            emotion_stream,
+            emotion_history: RwLock::new(VecDeque::new()),
+            moderation_patterns: parts.moderation_patterns.clone(),
+            clock: std::sync::RwLock::new(None),
+            scene_prompt: RwLock::new(config.prompts.scene.clone()),
+            composed_prompt: std::sync::RwLock::new(None),
+            base_prompts: parts.prompts.clone(),
+            debouncer: InputDebouncer::new(config.debounce.clone()),
+            request_queue: RequestQueue::new(config.request_queue.clone()),
+            last_selection: RwLock::new(None),
+            offline_fallback: OfflineFallback::new(config.offline_fallback.clone()),
+            topics: RwLock::new(TopicTracker::new(config.topics.clone())),
+            absences: RwLock::new(AbsenceTracker::new(config.reengagement.clone())),
+            reputation: RwLock::new(PlayerReputation::new(config.reputation.clone())),
+            disposition: RwLock::new(DispositionState::new()),
+            redactor: parts.redactor.clone(),
+            intent_matcher: parts.intent_matcher.clone(),
+            postprocessor: parts.postprocessor.clone(),
+            capabilities: parts.capabilities.clone(),
+            consistency_stats: Mutex::new(ConsistencyStats::default()),
+            dialogue: DialogueQueue::new(config.dialogue_queue.clone()),
+            interaction_logger: std::sync::RwLock::new(parts.interaction_logger.clone()),
+            interaction_logging: AtomicBool::new(config.interaction_log.enabled),
+            dry_run: AtomicBool::new(false),
+            image_captioner: std::sync::RwLock::new(None),
+            modifier_provider: SharedModifiers::new(Arc::new(config.modifiers)),
+            interactions_since_reflection: AtomicU32::new(0),
+            last_forgetting: Mutex::new(None),
+            event_log: config.event_log.enabled.then(|| EventLog::new(&config.event_log)),
+            pending_monologue: Mutex::new(None),
+            speculative_greeting: Mutex::new(None),
+            speculating: AtomicBool::new(false),
+            #[cfg(feature = "tts")]
+            prepared_speech: Mutex::new(None),
+            sessions: SessionStore::new(config.memory.sessions.clone()),
+        }
+    }
+
+    /// The parts this agent shares with agents cloned from it
+    pub(crate) fn shared_parts(&self) -> SharedAgentParts {
+        SharedAgentParts {
+            config: self.config.clone(),
+            #[cfg(feature = "tts")]
+            tts_service: self.tts_service.clone(),
+            moderation_patterns: self.moderation_patterns.clone(),
+            redactor: self.redactor.clone(),
+            intent_matcher: self.intent_matcher.clone(),
+            postprocessor: self.postprocessor.clone(),
+            capabilities: self.capabilities.clone(),
+            prompts: self.base_prompts.clone(),
+            interaction_logger: self.interaction_logger(),
         }
     }
 
     /// Generate speech for agent response
+    #[cfg(feature = "tts")]
     pub async fn speak(
         &self,
         text: &str,
         emotions: &EmotionalState,
         urgency: f32,
     ) -> Result<AudioData> {
+        if let Some(audio) = self.take_prepared_speech(text) {
+            return Ok(audio);
+        }
         if let Some(tts) = &self.tts_service {
             tts.synthesize_npc_speech(&self.name, text, emotions, urgency)
                 .await
@@ -248,6 +608,133 @@ impl Agent {
         }
     }
 
+    /// Take speech synthesized ahead of time for this text
+    #[cfg(feature = "tts")]
+    fn take_prepared_speech(&self, text: &str) -> Option<AudioData> {
+        let mut prepared = self.prepared_speech.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        match prepared.take() {
+            Some((prepared_text, audio)) if prepared_text == text => Some(audio),
+            _ => None,
+        }
+    }
+
+    /// Generate speech for agent response, degrading instead of failing
+    ///
+    /// Applies the TTS configuration's degradation policy: a failed synthesis
+    /// is retried with the secondary provider, answered with cached audio of a
+    /// similar line, or returned as [`SpokenResponse::TextOnly`] so the game
+    /// can show subtitles. Each fallback triggers [`AgentEvent::SpeechDegraded`].
+    #[cfg(feature = "tts")]
+    pub async fn speak_with_fallback(
+        &self,
+        text: &str,
+        emotions: &EmotionalState,
+        urgency: f32,
+    ) -> Result<SpokenResponse> {
+        let tts = self.tts_service.as_ref().ok_or_else(|| {
+            crate::OxydeError::ConfigurationError("TTS not configured".to_string())
+        })?;
+        let spoken = tts
+            .synthesize_or_degrade(&self.name, text, emotions, urgency)
+            .await
+            .map_err(|e| crate::OxydeError::AudioError(TTSError::AudioProcessingError(e.to_string())))?;
+
+        let degraded = match &spoken {
+            SpokenResponse::Audio { source, error: Some(error), .. } => Some((source.as_str(), error)),
+            SpokenResponse::TextOnly { error, .. } => Some(("text_only", error)),
+            SpokenResponse::Audio { error: None, .. } => None,
+        };
+        if let Some((source, error)) = degraded {
+            let data = serde_json::json!({ "source": source, "text": text, "error": error });
+            self.trigger_event(AgentEvent::SpeechDegraded, &data.to_string()).await;
+        }
+        Ok(spoken)
+    }
+
+    /// Pace a line the agent says, for managing the microphone around it
+    ///
+    /// Pass the audio from [`Agent::speak`] to measure the line; without
+    /// audio its timing is estimated from the text. Responses from
+    /// [`Agent::speak_with_fallback`] are paced already.
+    #[cfg(feature = "tts")]
+    pub fn pace_speech(&self, text: &str, audio: Option<&AudioData>) -> ConversationPacing {
+        match &self.tts_service {
+            Some(tts) => tts.pacing(text, audio),
+            None => PacingConfig::default().pace(text, 1.0, audio.map(|audio| audio.duration_ms)),
+        }
+    }
+
+    /// Generate speech for agent response, yielding audio chunks as they are synthesized
+    ///
+    /// Playback can start with the first chunk instead of waiting for the
+    /// whole response. The chunks concatenate to the audio [`Agent::speak`]
+    /// returns for the same text and emotions.
+    #[cfg(feature = "tts")]
+    pub async fn speak_streaming(
+        &self,
+        text: &str,
+        emotions: &EmotionalState,
+        urgency: f32,
+    ) -> Result<AudioStream> {
+        if let Some(tts) = &self.tts_service {
+            tts.synthesize_npc_speech_streaming(&self.name, text, emotions, urgency)
+                .await
+                .map_err(|e| {
+                    crate::OxydeError::AudioError(TTSError::AudioProcessingError(e.to_string()))
+                })
+        } else {
+            Err(crate::OxydeError::ConfigurationError(
+                "TTS not configured".to_string(),
+            ))
+        }
+    }
+
+    /// Set the voice profile used when this agent speaks
+    ///
+    /// The profile's `npc_name` is replaced with the agent's name so the TTS
+    /// service picks it up for this agent.
+    #[cfg(feature = "tts")]
+    pub async fn set_voice_profile(&self, mut profile: VoiceProfile) -> Result<()> {
+        let tts = self.tts_service.as_ref().ok_or_else(|| {
+            crate::OxydeError::ConfigurationError("TTS not configured".to_string())
+        })?;
+        profile.npc_name = self.name.clone();
+        tts.set_voice_profile(profile).await;
+        Ok(())
+    }
+
+    /// Check whether the agent is ready to run
+    ///
+    /// Runs [`healthcheck`](crate::health::healthcheck) on the agent's
+    /// configuration and adds agent-specific diagnostics such as moderation
+    /// patterns that failed to load. Intended to be called before a level
+    /// loads; this contacts remote providers, so avoid calling it every frame.
+    ///
+    /// # Returns
+    ///
+    /// A report with one entry per checked component
+    pub async fn ready(&self) -> HealthReport {
+        let mut report = crate::health::healthcheck(&self.config).await;
+
+        if self.config.moderation.enabled {
+            report.push(if self.moderation_patterns.is_some() {
+                HealthCheck::new("moderation", HealthStatus::Ok, "Moderation patterns loaded")
+            } else {
+                HealthCheck::new(
+                    "moderation",
+                    HealthStatus::Degraded,
+                    "Moderation is enabled but no patterns were loaded",
+                )
+            });
+        }
+
+        if self.state.current() == AgentState::Error {
+            report.push(HealthCheck::new("agent", HealthStatus::Failed, "Agent is in the error state"));
+        }
+
+        report
+    }
+
     /// Get the agent's unique ID
     pub fn id(&self) -> Uuid {
         self.id
@@ -260,7 +747,7 @@ impl Agent {
 
     /// Get the agent's current state
     pub async fn state(&self) -> AgentState {
-        *self.state.read().await
+        self.state.current()
     }
 
     /// Get a copy of the agent's current emotional state
@@ -268,6 +755,20 @@ impl Agent {
         self.emotional_state.read().await.clone()
     }
 
+    /// Record the emotional state at an input
+    ///
+    /// # Returns
+    ///
+    /// The states at recent inputs, oldest first and ending with `state`
+    async fn record_emotion_turn(&self, state: &EmotionalState) -> Vec<EmotionalState> {
+        let mut history = self.emotion_history.write().await;
+        if history.len() >= MAX_SUSTAINED_TURNS as usize {
+            history.pop_front();
+        }
+        history.push_back(state.clone());
+        history.iter().cloned().collect()
+    }
+
     /// Get the agent's emotion vector as a float array
     pub async fn emotion_vector(&self) -> [f32; 8] {
         let emotion_state = self.emotional_state.read().await;
@@ -278,11 +779,109 @@ impl Agent {
     ///
     /// # Arguments
     ///
-    /// * `emotion` - Name of the emotion to update (e.g., "joy", "fear")
+    /// * `emotion` - Name of the emotion to update (e.g., "joy", "fear"), or
+    ///   a dimension of the affect model
     /// * `delta` - Amount to change the emotion by (-1.0 to 1.0)
     pub async fn update_emotion(&self, emotion: &str, delta: f32) {
+        self.change_emotions("update", |state| self.affect().stimulate(state, emotion, delta)).await;
+    }
+
+    /// Update several emotions at once
+    ///
+    /// The deltas are applied in order under a single lock, so readers never
+    /// observe a partially applied batch. Unknown emotion names are ignored,
+    /// as with [`Agent::update_emotion`].
+    ///
+    /// # Arguments
+    ///
+    /// * `updates` - Emotion names and the deltas to apply to them
+    pub async fn update_emotions(&self, updates: &[(&str, f32)]) {
+        self.change_emotions("update", |state| {
+            let mut affect = self.affect();
+            for (emotion, delta) in updates {
+                affect.stimulate(state, emotion, *delta);
+            }
+        })
+        .await;
+    }
+
+    /// Replace the model of how the agent feels
+    ///
+    /// The model takes on the current emotions, and from then on receives
+    /// every emotion update and decay. Behavior triggers keep reading the
+    /// Plutchik emotions the model projects.
+    pub async fn set_affect_model(&self, mut model: Box<dyn AffectModel>) {
+        let (ticket, dimensions) = {
+            let state = self.emotional_state.read().await;
+            model.sync(&state);
+            let dimensions = model.dimensions(&state);
+            *self.affect() = model;
+            (self.emotion_stream.ticket(), dimensions)
+        };
+        self.emotion_stream.record(ticket, dimensions);
+    }
+
+    /// The affect model's name and its own dimensions, as exposed to
+    /// behaviors under [`AFFECT_KEY`]
+    pub async fn affect_dimensions(&self) -> serde_json::Value {
+        let state = self.emotional_state.read().await;
+        dimensions_json(self.affect().as_ref(), &state)
+    }
+
+    fn affect(&self) -> std::sync::MutexGuard<'_, Box<dyn AffectModel>> {
+        self.affect.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Change the emotional state under its lock, logging the change
+    async fn change_emotions<R>(&self, cause: &str, change: impl FnOnce(&mut EmotionalState) -> R) -> R {
         let mut state = self.emotional_state.write().await;
-        state.update_emotion(emotion, delta);
+        let before = self.event_log.as_ref().map(|_| self.affect().dimensions(&state));
+        let result = change(&mut state);
+        let dimensions = self.affect().dimensions(&state);
+        let event = before.and_then(|before| StateEvent::emotions_changed(cause, &before, &dimensions, &state));
+        let ticket = self.emotion_stream.ticket();
+        drop(state);
+
+        // The log and subscribers are written to without the emotions locked
+        if let Some(event) = event {
+            self.record(event);
+        }
+        self.emotion_stream.record(ticket, dimensions);
+        result
+    }
+
+    /// Call back when emotions change
+    ///
+    /// The callback receives the emotions that moved by at least `threshold`
+    /// since it last heard of them, with their new values, so small drifts
+    /// add up until they are worth animating. It runs on the task changing
+    /// the emotions and must not block.
+    ///
+    /// # Arguments
+    ///
+    /// * `threshold` - Smallest change reported, in emotion units; 0.0
+    ///   reports every change
+    /// * `callback` - Function receiving the changed emotions
+    pub fn on_emotion_change<F>(&self, threshold: f32, callback: F)
+    where
+        F: Fn(&EmotionChange) + Send + Sync + 'static,
+    {
+        self.emotion_stream.subscribe(threshold, Arc::new(callback));
+    }
+
+    /// Get the emotions changed since a poll
+    ///
+    /// # Arguments
+    ///
+    /// * `sequence` - Sequence number returned by the previous poll, or 0 for
+    ///   every emotion changed since the agent was created
+    ///
+    /// # Returns
+    ///
+    /// The changed emotions with their current values, and the sequence
+    /// number to pass to the next poll
+    pub fn emotion_changes_since(&self, sequence: u64) -> EmotionChange {
+        self.emotion_stream.changes_since(sequence)
     }
 
     /// Apply emotional decay to all emotions
@@ -290,8 +889,12 @@ impl Agent {
     /// This should be called periodically (e.g., every frame or tick)
     /// to allow emotions to naturally fade over time
     pub async fn decay_emotions(&self) {
-        let mut state = self.emotional_state.write().await;
-        state.decay();
+        if self.config.disposition.enabled {
+            let baseline = self.disposition.read().await.baseline;
+            self.change_emotions("decay", |state| self.affect().decay(state, Some(&baseline))).await;
+        } else {
+            self.change_emotions("decay", |state| self.affect().decay(state, None)).await;
+        }
     }
 
     /// Get the current emotional valence (-1.0 to 1.0)
@@ -308,602 +911,4953 @@ impl Agent {
         self.emotional_state.read().await.arousal()
     }
 
-    /// Add a behavior to the agent
+    /// Set the game clock used for the agent's schedule, time of day, and
+    /// memory timestamps
+    ///
+    /// Without a clock, the time is read from the `game_hour` and `game_day`
+    /// context keys.
     ///
     /// # Arguments
     ///
-    /// * `behavior` - A behavior to add to the agent
-    pub async fn add_behavior<B: Behavior + 'static>(&self, behavior: B) {
-        let mut behaviors = self.behaviors.write().await;
-        behaviors.push(Box::new(behavior));
+    /// * `clock` - Source of the current game time
+    pub fn set_clock(&self, clock: Arc<dyn Clock>) {
+        let mut current = self.clock.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+        *current = Some(clock);
     }
 
-    /// Add a boxed behavior to the agent
-    ///
-    /// # Arguments
-    ///
-    /// * `behavior` - A boxed behavior to add to the agent
-    pub async fn add_boxed_behavior(&self, behavior: Box<dyn Behavior>) {
-        let mut behaviors = self.behaviors.write().await;
-        behaviors.push(behavior);
+    /// Get the schedule block the agent is currently in, if any
+    pub async fn current_activity(&self) -> Option<ScheduleBlock> {
+        self.scheduled_block(&self.context.snapshot())
     }
 
-    /// Update the agent's context with new data
-    ///
-    /// # Arguments
-    ///
-    /// * `context` - New context data to merge with existing context
-    pub async fn update_context(&self, context: AgentContext) {
-        let mut current_context = self.context.write().await;
-        for (key, value) in context {
-            current_context.insert(key, value);
-        }
+    /// Get the current game time, if a clock is set or the context holds one
+    pub async fn game_time(&self) -> Option<GameTime> {
+        self.resolve_game_time(&self.context.snapshot())
     }
 
-    /// Start the agent
-    ///
-    /// This initializes the agent and prepares it for operation
-    pub async fn start(&self) -> Result<()> {
-        let mut state = self.state.write().await;
-        *state = AgentState::Idle;
-        log::info!("Agent {} started", self.name);
+    /// Resolve the game time from the clock, or the time in the given context
+    fn resolve_game_time(&self, context: &AgentContext) -> Option<GameTime> {
+        let clock_time = self
+            .clock
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .as_ref()
+            .map(|clock| clock.now());
+        clock_time.or_else(|| {
+            let hour = context.get(GAME_HOUR_KEY).and_then(|v| v.as_f64())?;
+            let day = context.get(GAME_DAY_KEY).and_then(|v| v.as_u64()).unwrap_or(0);
+            Some(GameTime::new(day as u32, hour as f32))
+        })
+    }
 
-        // Initialize memory with agent's backstory and knowledge
-        self.memory
-            .add(Memory::new(
-                MemoryCategory::Semantic,
-                &serde_json::to_string(&self.config.agent.backstory)?,
-                f64::INFINITY,
-                None,
-            ))
-            .await?;
+    /// Expose the resolved game time and time of day to behaviors and inference
+    fn insert_game_time(&self, context: &mut AgentContext) -> Option<GameTime> {
+        let time = self.resolve_game_time(context)?;
+        context.insert(GAME_HOUR_KEY.to_string(), serde_json::json!(time.hour));
+        context.insert(GAME_DAY_KEY.to_string(), serde_json::json!(time.day));
+        context.insert(TIME_OF_DAY_KEY.to_string(), serde_json::json!(time.time_of_day().as_str()));
+        Some(time)
+    }
 
-        self.trigger_event(AgentEvent::Start, "Agent started").await;
+    /// Resolve the schedule block for the clock, or the hour in the given context
+    fn scheduled_block(&self, context: &AgentContext) -> Option<ScheduleBlock> {
+        if self.config.schedule.blocks.is_empty() {
+            return None;
+        }
 
-        Ok(())
+        let hour = self.resolve_game_time(context)?.hour;
+        self.config.schedule.activity_at(hour).cloned()
     }
 
-    /// Stop the agent
-    pub async fn stop(&self) -> Result<()> {
-        let mut state = self.state.write().await;
-        *state = AgentState::Stopped;
-        log::info!("Agent {} stopped", self.name);
-
-        self.trigger_event(AgentEvent::Stop, "Agent stopped").await;
-
+    /// Store a memory, stamped with the current game time when it is known
+    async fn remember(&self, memory: Memory) -> Result<()> {
+        let memory = match self.game_time().await {
+            Some(time) => memory.with_game_time(time),
+            None => memory,
+        };
+        if self.config.disposition.enabled {
+            let emotions = self.emotional_state.read().await.clone();
+            self.disposition.write().await.absorb(
+                &self.config.disposition,
+                &emotions,
+                memory.emotional_intensity as f32,
+            );
+        }
+        for (pool, access) in self.memory_pools.read().await.iter() {
+            if access.shares(&memory) {
+                if let Err(e) = pool.add(memory.clone()).await {
+                    log::warn!("Agent {} could not share a memory with pool {}: {}", self.name, pool.name(), e);
+                }
+            }
+        }
+        let logged = self.event_log.as_ref().map(|_| memory.clone());
+        self.memory.add(memory).await?;
+        if let Some(memory) = logged {
+            self.record(StateEvent::MemoryAdded { memory });
+        }
         Ok(())
     }
 
-    /// Check if content should be moderated
+    /// Retrieve personal and pooled memories relevant to a query
+    ///
+    /// Memories about a player are only recalled in that player's
+    /// conversations, given by `player_id`.
+    async fn recall(&self, query: &str, limit: usize, player_id: Option<&str>) -> Result<Vec<Memory>> {
+        let mood_valence = self.emotional_state.read().await.valence() as f64;
+        let mut pooled = Vec::new();
+        for (pool, access) in self.memory_pools.read().await.iter() {
+            if access.read {
+                pooled.extend(pool.retrieve_relevant(query, limit).await?);
+            }
+        }
+        pooled.retain(|memory| recallable_for(memory, player_id));
+        // Pooled memories are ranked by the agent's own retrieval settings and mood
+        self.memory
+            .retrieve_relevant_matching(query, limit, None, mood_valence, |memory| recallable_for(memory, player_id), &pooled)
+            .await
+    }
+
+    /// Attach the agent to a shared memory pool
+    ///
+    /// Replaces the access of a pool with the same name that is already
+    /// attached.
     ///
     /// # Arguments
     ///
-    /// * `input` - Content to check for inappropriate material
+    /// * `pool` - Pool shared with other agents
+    /// * `access` - Whether the agent recalls the pool and which of its memories it shares
+    pub async fn attach_memory_pool(&self, pool: Arc<SharedMemoryPool>, access: PoolAccess) {
+        let mut pools = self.memory_pools.write().await;
+        pools.retain(|(attached, _)| attached.name() != pool.name());
+        pools.push((pool, access));
+    }
+
+    /// Detach the agent from a shared memory pool
     ///
     /// # Returns
     ///
-    /// `Some(response_message)` if content should be moderated, `None` if content is acceptable
-    async fn check_moderation(&self, input: &str) -> Option<String> {
-        if !self.config.moderation.enabled {
-            return None;
+    /// Whether a pool with that name was attached
+    pub async fn detach_memory_pool(&self, name: &str) -> bool {
+        let mut pools = self.memory_pools.write().await;
+        let before = pools.len();
+        pools.retain(|(attached, _)| attached.name() != name);
+        pools.len() != before
+    }
+
+    /// Add a memory to an attached shared memory pool
+    ///
+    /// # Returns
+    ///
+    /// Ok, or an error if no pool with that name is attached with write access
+    pub async fn share_memory(&self, pool_name: &str, memory: Memory) -> Result<()> {
+        let pools = self.memory_pools.read().await;
+        let pool = pools
+            .iter()
+            .find(|(pool, access)| pool.name() == pool_name && access.write)
+            .map(|(pool, _)| pool)
+            .ok_or_else(|| {
+                crate::OxydeError::MemoryError(format!(
+                    "Agent {} cannot write to memory pool {}",
+                    self.name, pool_name
+                ))
+            })?;
+        pool.add(memory).await
+    }
+
+    /// Replace the scene prompt layer
+    ///
+    /// The scene layer has the highest precedence and takes effect on the next
+    /// inference request. Pass `None` to clear it.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt` - New scene prompt
+    pub async fn set_scene_prompt(&self, prompt: Option<&str>) {
+        let mut scene_prompt = self.scene_prompt.write().await;
+        *scene_prompt = prompt.map(|p| p.to_string());
+        *self.composed_prompt.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = None;
+    }
+
+    /// Get the current scene prompt layer
+    pub async fn scene_prompt(&self) -> Option<String> {
+        self.scene_prompt.read().await.clone()
+    }
+
+    /// Turn interaction logging on or off for this agent
+    ///
+    /// Starts from `interaction_log.enabled` in the configuration.
+    pub fn set_interaction_logging(&self, enabled: bool) {
+        self.interaction_logging.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Whether inference exchanges are written to the interaction log
+    pub fn interaction_logging_enabled(&self) -> bool {
+        self.interaction_logging.load(Ordering::Relaxed)
+    }
+
+    /// Get the scripted provider used when the inference provider is `mock`
+    pub fn mock_provider(&self) -> &crate::mock_provider::MockProvider {
+        self.inference.mock_provider()
+    }
+
+    /// Turn dry-run mode on or off
+    ///
+    /// In dry-run mode, inputs that would be answered by inference return
+    /// the rendered prompt instead; no provider is called and no response
+    /// is stored in memory. Inputs answered by behaviors are unaffected.
+    pub fn set_dry_run(&self, enabled: bool) {
+        self.dry_run.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Whether the agent is in dry-run mode
+    pub fn dry_run_enabled(&self) -> bool {
+        self.dry_run.load(Ordering::Relaxed)
+    }
+
+    /// Replace the interaction logger, for example to add redaction hooks
+    ///
+    /// Agents built from the same configuration share a logger until one of
+    /// them is given its own.
+    pub fn set_interaction_logger(&self, logger: Arc<InteractionLogger>) {
+        let mut current = self.interaction_logger.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+        *current = logger;
+    }
+
+    /// Get the interaction logger
+    pub fn interaction_logger(&self) -> Arc<InteractionLogger> {
+        self.interaction_logger
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    /// Write an inference exchange to the interaction log if logging is on
+    ///
+    /// Failures are logged rather than returned so a full disk never stops
+    /// the agent from answering.
+    async fn log_interaction(&self, exchange: &InferenceExchange, final_response: &str, emotions_before: EmotionalState) {
+        if !self.interaction_logging_enabled() {
+            return;
         }
 
-        // Quick regex check first (instant)
-        let regex_flagged = if let Some(ref patterns) = self.moderation_patterns {
-            patterns.is_match(&input.to_lowercase())
-        } else {
-            false
+        let (experiment, variant) = variant_label(&exchange.request.context).unzip();
+        let record = InteractionRecord {
+            timestamp: InteractionRecord::now(),
+            agent_id: self.id.to_string(),
+            agent_name: self.name.clone(),
+            input: exchange.request.input.clone(),
+            intent: exchange.request.context.get(INPUT_INTENT_KEY).and_then(|intent| {
+                intent.get("label").and_then(|label| label.as_str()).or_else(|| intent.get("type")?.as_str()).map(str::to_string)
+            }),
+            system_prompt: exchange.request.system_prompt.clone(),
+            memories: exchange.request.memories.iter().map(|memory| memory.content.clone()).collect(),
+            provider: exchange.response.provider_name.clone(),
+            model: exchange.response.model.clone(),
+            experiment,
+            variant,
+            tokens: exchange.response.tokens,
+            latency_ms: exchange.response.time_ms,
+            response: exchange.response.text.clone(),
+            final_response: final_response.to_string(),
+            emotions_before,
+            emotions_after: self.emotional_state.read().await.clone(),
         };
-        
-        // If regex already flagged it, no need for cloud check - return immediately
-        if regex_flagged {
-            log::warn!("Agent {} moderated inappropriate content (regex): {}", self.name, input);
-            return Some(self.config.moderation.response_message.clone());
+        if let Err(e) = self.interaction_logger().log(record) {
+            log::warn!("Agent {} failed to write interaction log: {}", self.name, e);
         }
-        
-        // Only do cloud check if regex didn't catch it and cloud moderation is enabled
-        if self.config.moderation.use_cloud_moderation {
-            let api_key = self.config.moderation.cloud_moderation_api_key.clone()
-                .or_else(|| self.config.inference.api_key.clone())
-                .or_else(|| std::env::var("OPENAI_API_KEY").ok());
-            
-            if let Some(key) = api_key {
-                match crate::utils::check_cloud_moderation(input, &key).await {
-                    Ok(true) => {
-                        log::warn!("Agent {} moderated inappropriate content (cloud): {}", self.name, input);
-                        return Some(self.config.moderation.response_message.clone());
-                    },
-                    Ok(false) => {
-                        // Content is clean, continue processing
-                    },
-                    Err(e) => {
-                        log::warn!("Cloud moderation failed, continuing without it: {}", e);
-                    }
-                }
-            }
+    }
+
+    /// Pick a canned response for when inference is unavailable
+    ///
+    /// The agent's name, role, and context are available to templates.
+    async fn offline_response(&self, intent: IntentType, context: &AgentContext) -> Option<String> {
+        if !self.offline_fallback.is_enabled() {
+            return None;
         }
 
-        None
+        let mut context = context.clone();
+        context
+            .entry("name".to_string())
+            .or_insert_with(|| serde_json::json!(self.config.agent.name));
+        context
+            .entry("role".to_string())
+            .or_insert_with(|| serde_json::json!(self.config.agent.role));
+
+        let emotional_state = self.emotional_state.read().await;
+        self.offline_fallback.respond(intent, &emotional_state, &context)
     }
 
-    /// Process player input and generate a response
+    /// Add a topic to the agent's conversation agenda
+    ///
+    /// When the conversation stays away from the agenda, the agent is
+    /// prompted to steer back toward it.
     ///
     /// # Arguments
     ///
-    /// * `input` - Player input to process
+    /// * `topic` - Topic the agent should bring up
+    pub async fn push_agenda_topic(&self, topic: AgendaTopic) {
+        self.topics.write().await.push_agenda(topic);
+    }
+
+    /// Remove a topic from the agent's agenda
     ///
     /// # Returns
     ///
-    /// A result containing the agent's response
-    pub async fn process_input(&self, input: &str) -> Result<String> {
-        {
-            let mut state = self.state.write().await;
-            *state = AgentState::Processing;
-        }
+    /// Whether the topic was on the agenda
+    pub async fn complete_agenda_topic(&self, name: &str) -> bool {
+        self.topics.write().await.complete_agenda(name)
+    }
 
-        log::debug!("Agent {} processing input: {}", self.name, input);
+    /// Get a snapshot of the conversation topic tracker
+    pub async fn topics(&self) -> TopicTracker {
+        self.topics.read().await.clone()
+    }
 
-        // Check for inappropriate content if moderation is enabled
-        if let Some(moderation_response) = self.check_moderation(input).await {
-            {
-                let mut state = self.state.write().await;
-                *state = AgentState::Idle;
+    /// Record the input's topic and expose the topic state in context
+    async fn track_input_topic(&self, intent: &Intent, context: &mut AgentContext) {
+        let mut topics = self.topics.write().await;
+        topics.observe_input(intent);
+        topics.apply_to_context(context);
+    }
+
+    /// Find the language of an input
+    ///
+    /// A confidently detected locale is kept with the player's session, or in
+    /// the agent context for inputs from no identified player, so short
+    /// inputs that give no evidence keep the player's last locale. Nothing is
+    /// kept in dry-run mode.
+    ///
+    /// # Returns
+    ///
+    /// The text intent analysis should see: the input translated into
+    /// `language.primary` when `language.translate_input` is set and the input
+    /// is in another language, otherwise the input. Then the player's locale,
+    /// if language detection is enabled and it is known.
+    async fn localize_input(
+        &self,
+        input: &str,
+        context: &AgentContext,
+        player: Option<&PlayerScope>,
+    ) -> (String, Option<String>) {
+        let config = &self.config.language;
+        let detection = config.detect(input);
+        if let (Some(detection), false) = (&detection, self.dry_run_enabled()) {
+            let locale = serde_json::json!(detection.locale);
+            match player {
+                Some(player) => {
+                    let kept = self.sessions.update(&player.id, |session| {
+                        session.context.insert(PLAYER_LOCALE_KEY.to_string(), locale);
+                    });
+                    if let Err(e) = kept {
+                        log::warn!("Agent {} could not keep the locale of player {}: {}", self.name, player.id, e);
+                    }
+                }
+                None => self.apply_context(ContextDiff::new().set(PLAYER_LOCALE_KEY, locale)),
             }
-            self.trigger_callback("response", &moderation_response).await;
-            return Ok(moderation_response);
         }
+        let known = context.get(PLAYER_LOCALE_KEY).and_then(|v| v.as_str());
+        let locale = match detection.as_ref().map(|detection| detection.locale.as_str()).or(known) {
+            Some(locale) if config.enabled => locale.to_string(),
+            _ => return (input.to_string(), None),
+        };
 
-        // Analyze player intent
-        let intent = Intent::analyze(input).await?;
+        let translate = config.translate_input && !config.is_primary(&locale) && !self.dry_run_enabled();
+        if !translate || detection.is_none() {
+            return (input.to_string(), Some(locale));
+        }
+        let system_prompt = config.translation_prompt(&locale);
+        let text = match self.inference.complete(&system_prompt, input, config.translation_max_tokens).await {
+            Ok(translation) if !translation.trim().is_empty() => {
+                log::debug!("Agent {} translated input from {}: {}", self.name, locale, translation.trim());
+                translation.trim().to_string()
+            }
+            Ok(_) => input.to_string(),
+            Err(e) => {
+                log::warn!("Agent {} could not translate input from {}: {}", self.name, locale, e);
+                input.to_string()
+            }
+        };
+        (text, Some(locale))
+    }
 
-        // Update memory with player input, capturing current emotional state
-        let emotional_state = self.emotional_state.read().await;
-        self.memory.add(Memory::new_emotional(
-                MemoryCategory::Episodic,
-                input,
-                1.0,
-                emotional_state.valence() as f64,
-                emotional_state.arousal() as f64,
-                None
-            )).await?;
+    /// Replace a behavior's response with the one configured for the
+    /// player's locale, if any
+    fn localize_behavior_result(&self, result: BehaviorResult, behavior: &str, locale: Option<&str>) -> BehaviorResult {
+        let localized = locale.and_then(|locale| self.config.language.response(locale, behavior));
+        match (result, localized) {
+            (BehaviorResult::Response(_), Some(text)) => BehaviorResult::Response(text.to_string()),
+            (BehaviorResult::ResponseWithAction { action, .. }, Some(text)) => BehaviorResult::ResponseWithAction {
+                response: text.to_string(),
+                action,
+            },
+            (result, _) => result,
+        }
+    }
 
-        // Find behaviors that match the intent
-        let behaviors = self.behaviors.read().await;
-        let mut response = String::new();
+    /// Get when the agent last spoke with each player, in Unix seconds
+    ///
+    /// Save this with the game so absences are detected across sessions.
+    pub async fn last_interactions(&self) -> HashMap<String, u64> {
+        self.absences.read().await.snapshot()
+    }
+
+    /// Restore last interaction times saved with [`Agent::last_interactions`]
+    pub async fn restore_last_interactions(&self, last_seen: HashMap<String, u64>) {
+        self.absences.write().await.restore(last_seen);
+    }
+
+    /// Get the toxicity score of each player
+    ///
+    /// Save this with the game so players keep their reputation across sessions.
+    pub async fn player_reputations(&self) -> HashMap<String, ToxicityScore> {
+        self.reputation.read().await.snapshot()
+    }
+
+    /// Restore toxicity scores saved with [`Agent::player_reputations`]
+    pub async fn restore_player_reputations(&self, scores: HashMap<String, ToxicityScore>) {
+        self.reputation.write().await.restore(scores);
+    }
+
+    /// Get a player's current reputation
+    pub async fn player_standing(&self, player: &str) -> Standing {
+        self.reputation.read().await.standing(player, crate::utils::current_timestamp_secs())
+    }
 
-        {
-            let mut state = self.state.write().await;
-            *state = AgentState::Executing;
+    /// Count an offense against a player
+    ///
+    /// Flagged inputs are counted automatically when reputation is enabled;
+    /// games call this for offenses moderation cannot see, such as griefing.
+    ///
+    /// # Arguments
+    ///
+    /// * `player` - Player identifier, as in the `player_id` context key
+    /// * `weight` - Score to add to the player's toxicity
+    pub async fn record_player_offense(&self, player: &str, weight: f64) -> Standing {
+        let (standing, crossings) = self.reputation.write().await.record_offense(player, weight, crate::utils::current_timestamp_secs());
+        log::debug!("Agent {} counts an offense against {}, now at {:.2}", self.name, player, standing.score);
+        self.report_reputation_crossings(crossings).await;
+        standing
+    }
+
+    /// Expose the reputation of the player an input comes from
+    async fn track_player_reputation(&self, context: &mut AgentContext) {
+        if !self.config.reputation.enabled {
+            return;
         }
+        let (standing, crossings) = self.reputation.write().await.observe(&player_key(context), crate::utils::current_timestamp_secs());
+        standing.apply_to_context(context);
+        self.report_reputation_crossings(crossings).await;
+    }
 
-        // Get current emotional state for behavior filtering and prioritization
-        let current_emotional_state = self.emotional_state.read().await.clone();
+    /// Trigger an event for each reputation threshold crossed
+    async fn report_reputation_crossings(&self, crossings: Vec<ThresholdCrossing>) {
+        for crossing in crossings {
+            log::info!(
+                "Agent {}: {} {} reputation threshold {}",
+                self.name,
+                crossing.player,
+                if crossing.rising { "reached" } else { "fell below" },
+                crossing.threshold
+            );
+            let data = serde_json::to_string(&crossing).unwrap_or_default();
+            self.trigger_event(AgentEvent::ReputationThreshold, &data).await;
+        }
+    }
 
-        // Filter and sort behaviors by priority (considering emotional modifiers)
-        let mut candidate_behaviors: Vec<_> = behaviors
-            .iter()
-            .filter(|b| {
-                // Check if behavior's emotion trigger is satisfied
-                if let Some(trigger) = b.emotion_trigger() {
-                    trigger.matches(&current_emotional_state)
-                } else {
-                    true
-                }
-            })
-            .collect();
+    /// Get the emotion baselines the agent has accumulated
+    ///
+    /// Save this with the game so personality drift carries across sessions.
+    pub async fn disposition(&self) -> DispositionState {
+        self.disposition.read().await.clone()
+    }
 
-        // Sort by priority (base + emotional modifier), highest first
-        candidate_behaviors.sort_by(|a, b| {
-            let a_priority = a.priority() as i32 + a.emotional_priority_modifier(&current_emotional_state);
-            let b_priority = b.priority() as i32 + b.emotional_priority_modifier(&current_emotional_state);
-            b_priority.cmp(&a_priority) // Descending order
-        });
+    /// Restore a disposition saved with [`Agent::disposition`]
+    pub async fn restore_disposition(&self, disposition: DispositionState) {
+        *self.disposition.write().await = disposition;
+    }
 
-        // Execute matching behaviors in priority order
-        for behavior in candidate_behaviors {
-            if behavior.matches_intent(&intent).await {
-                let context = self.context.read().await.clone();
-                let behavior_result = behavior.execute(&intent, &context).await?;
+    /// Capture the agent's saveable state
+    ///
+    /// The snapshot serializes with schema versions, so saves remain loadable
+    /// by later SDK versions; see [`crate::save`].
+    pub async fn snapshot(&self) -> AgentSnapshot {
+        AgentSnapshot {
+            name: self.name.clone(),
+            saved_at: crate::utils::current_timestamp_secs(),
+            emotional_state: self.emotional_state().await,
+            memories: self.memory.session().await.memories,
+            disposition: self.disposition().await,
+            last_interactions: self.last_interactions().await,
+            player_reputations: self.player_reputations().await,
+            player_sessions: self.sessions.snapshot(),
+            context: (*self.context_snapshot()).clone(),
+        }
+    }
 
-                // Apply emotional influences from the behavior
-                let influences = behavior.emotion_influences();
-                if !influences.is_empty() {
-                    let mut emotional_state = self.emotional_state.write().await;
-                    for influence in influences {
-                        emotional_state.update_emotion(&influence.emotion, influence.delta);
-                    }
-                }
+    /// Restore state saved with [`Agent::snapshot`]
+    ///
+    /// Replaces the agent's emotions, memories, disposition, absences,
+    /// reputations and player sessions. The saved context is merged into the current one without
+    /// schema checks, since it was accepted when it was set.
+    pub async fn restore_snapshot(&self, snapshot: AgentSnapshot) {
+        if snapshot.name != self.name {
+            log::warn!("Agent {} restores a snapshot saved by {}", self.name, snapshot.name);
+        }
+        if self.event_log.is_some() {
+            self.record(StateEvent::Restored {
+                emotional_state: snapshot.emotional_state.clone(),
+                memories: snapshot.memories.clone(),
+            });
+        }
+        let (ticket, dimensions) = {
+            let mut emotional_state = self.emotional_state.write().await;
+            *emotional_state = snapshot.emotional_state;
+            let mut affect = self.affect();
+            affect.sync(&emotional_state);
+            (self.emotion_stream.ticket(), affect.dimensions(&emotional_state))
+        };
+        self.emotion_stream.record(ticket, dimensions);
+        self.emotion_history.write().await.clear();
+        self.memory.restore(snapshot.memories).await;
+        self.restore_disposition(snapshot.disposition).await;
+        self.restore_last_interactions(snapshot.last_interactions).await;
+        self.restore_player_reputations(snapshot.player_reputations).await;
+        self.sessions.restore(snapshot.player_sessions);
+        self.apply_context(ContextDiff::from(snapshot.context));
+    }
 
-                match behavior_result {
-                    BehaviorResult::Response(text) => {
-                        response = text;
-                        break;
-                    }
-                    BehaviorResult::Action(action) => {
-                        // Trigger action callback
-                        self.trigger_event(AgentEvent::Action, &action).await;
-                    },
-                    BehaviorResult::None => {
-                        // Continue to next behavior
-                    }
-                }
+    /// Apply a context diff, logging the keys it changes
+    fn apply_context(&self, diff: ContextDiff) {
+        if self.event_log.is_some() {
+            let changes = diff.changes_to(&self.context.snapshot());
+            if !changes.is_empty() {
+                self.record(StateEvent::ContextUpdated { diff: changes });
             }
         }
+        self.context.apply(diff);
+    }
 
-        // If no behavior provided a response, generate one with inference
-        if response.is_empty() {
-            {
-                let mut state = self.state.write().await;
-                *state = AgentState::Generating;
-            }
+    /// Append a state change to the event log, if it is enabled
+    fn record(&self, event: StateEvent) {
+        if let Some(log) = &self.event_log {
+            log.append(event);
+        }
+    }
 
-            // Get relevant memories
-            let memories = self.memory.retrieve_relevant(input, 5, None).await?;
+    /// Get the log of state changes, if event logging is enabled
+    pub fn event_log(&self) -> Option<&EventLog> {
+        self.event_log.as_ref()
+    }
 
-            // Generate response using inference engine
-            let context = self.context.read().await.clone();
-            response = self
-                .inference
-                .generate_response(input, &memories, &context)
-                .await?;
+    /// Rebuild the agent's memories, emotions and context as they were after
+    /// an event
+    ///
+    /// # Arguments
+    ///
+    /// * `until` - Sequence number of the last event to apply; `None` rebuilds
+    ///   the current state
+    ///
+    /// # Returns
+    ///
+    /// The rebuilt state, or an error if event logging is disabled
+    pub async fn replay_events(&self, until: Option<u64>) -> Result<ReplayedState> {
+        let log = self.event_log.as_ref().ok_or_else(|| {
+            crate::OxydeError::ConfigurationError("Event logging is not enabled".to_string())
+        })?;
+        crate::event_log::replay(&log.history(until)?, &self.config.memory, until).await
+    }
 
-            // Store the response in memory with current emotional state
-            let emotional_state = self.emotional_state.read().await;
-            self.memory.add(Memory::new_emotional(
-                MemoryCategory::Semantic,
-                &response,
-                1.0,
-                emotional_state.valence() as f64,
-                emotional_state.arousal() as f64,
-                None
-            )).await?;
+    /// Run a memory operation that may forget memories, logging the ones it forgot
+    async fn forget_logged<R>(&self, operation: impl std::future::Future<Output = R>) -> R {
+        if self.event_log.is_none() {
+            return operation.await;
+        }
+        let before = self.memory.ids().await;
+        let result = operation.await;
+        let after = self.memory.ids().await;
+        let ids: Vec<String> = before.into_iter().filter(|id| !after.contains(id)).collect();
+        if !ids.is_empty() {
+            self.record(StateEvent::MemoriesForgotten { ids });
         }
+        result
+    }
 
-        {
-            let mut state = self.state.write().await;
-            *state = AgentState::Idle;
+    /// Frame the inference prompt with how the agent's personality has drifted
+    async fn frame_disposition(&self, context: &mut AgentContext) {
+        if self.config.disposition.enabled {
+            self.disposition
+                .read()
+                .await
+                .apply_to_context(&self.config.disposition, context);
         }
+    }
 
+    /// Record the interaction and expose a returning player's absence in context
+    async fn track_player_return(&self, context: &mut AgentContext) {
+        let mut absences = self.absences.write().await;
+        if let Some(absence) = absences.observe(&player_key(context), crate::utils::current_timestamp_secs()) {
+            log::debug!("Agent {} sees {} again after {}", self.name, absence.player, absence.elapsed);
+            absences.apply_to_context(&absence, context);
+        }
+    }
 
-        // Trigger response callback
-        self.trigger_event(AgentEvent::Response, &response).await;
+    /// Compose the configured prompt layers with the current scene prompt
+    async fn prompt_layers(&self) -> Option<String> {
+        if let Some(composed) = &*self.composed_prompt.read().unwrap_or_else(|poisoned| poisoned.into_inner()) {
+            return composed.clone();
+        }
 
-        Ok(response)
+        let scene_prompt = self.scene_prompt.read().await;
+        let mut prompts = (*self.base_prompts).clone();
+        prompts.set_layer(PromptLayer::Scene, scene_prompt.clone());
+        let composed = prompts.compose();
+        // Composed under the scene lock, so a concurrent scene change cannot be overwritten
+        *self.composed_prompt.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(composed.clone());
+        composed
     }
 
-    /// Register a callback for agent events using typed events
+    /// Compose the prompt layers, with a variant's layers in place of the agent's
+    async fn variant_prompt_layers(&self, variant: Option<&ExperimentVariant>) -> Option<String> {
+        let Some(variant) = variant.filter(|variant| variant.overrides_prompts()) else {
+            return self.prompt_layers().await;
+        };
+        let mut prompts = (*self.base_prompts).clone();
+        prompts.set_layer(PromptLayer::Scene, self.scene_prompt.read().await.clone());
+        variant.apply_prompts(&mut prompts);
+        prompts.compose()
+    }
+
+    /// Assign a request to an experiment variant, labelling the context and
+    /// the current span
+    fn assign_experiment(&self, context: &mut AgentContext) -> Option<&ExperimentVariant> {
+        let experiment = &self.config.experiment;
+        let variant = experiment.assign_request(&self.name, context)?;
+        experiment.apply_to_context(variant, context);
+        tracing::Span::current().record("variant", variant.name.as_str());
+        Some(variant)
+    }
+
+    /// Prepare the agent so its first input is answered without setup latency
+    ///
+    /// Composes the prompt layers, embeds the backstory and knowledge entries
+    /// when embeddings are enabled, and opens a kept-alive connection to the
+    /// cloud provider. A failed connection is logged rather than returned,
+    /// since requests open their own connections.
     ///
     /// # Arguments
     ///
-    /// * `event` - Event type to trigger the callback
-    /// * `callback` - Callback function
+    /// * `priming_request` - Also send a one-token request, so provider-side
+    ///   model loading happens now rather than on the first input
     ///
-    /// # Example
+    /// # Returns
     ///
-    /// ```ignore
-    /// agent.on_event(AgentEvent::Start, |agent, data| {
-    ///     println!("Agent {} started: {}", agent.name(), data);
-    /// });
-    /// ```
-    pub fn on_event<F>(&self, event: AgentEvent, callback: F)
-    where
-        F: Fn(&Agent, &str) + Send + Sync + 'static,
-    {
-        self.register_callback(event.as_str(), callback);
+    /// What was prepared, or an error if the priming request failed
+    pub async fn warm_up(&self, priming_request: bool) -> Result<WarmUpReport> {
+        let started = Instant::now();
+        let mut report = WarmUpReport {
+            prompt_compiled: self.prompt_layers().await.is_some(),
+            ..Default::default()
+        };
+
+        let mut texts = vec![serde_json::to_string(&self.config.agent.backstory)?];
+        texts.extend(self.config.agent.knowledge.iter().cloned());
+        report.embeddings_cached = self.memory.warm_up(&texts).await?;
+
+        report.connection_primed = match self.inference.prime_connection().await {
+            Ok(primed) => primed,
+            Err(e) => {
+                log::warn!("Agent {} could not prime its inference connection: {}", self.name, e);
+                false
+            }
+        };
+
+        if priming_request && !self.dry_run_enabled() {
+            let sent = Instant::now();
+            self.inference.complete("Reply with OK.", "OK", 1).await?;
+            report.priming_latency_ms = Some(sent.elapsed().as_millis() as u64);
+        }
+
+        report.elapsed_ms = started.elapsed().as_millis() as u64;
+        log::info!("Agent {} warmed up in {} ms", self.name, report.elapsed_ms);
+        Ok(report)
     }
 
-    /// Register a callback for agent events (deprecated, use on_event)
+    /// Generate the proximity greeting before the player is close enough to
+    /// trigger it
+    ///
+    /// Call this as the player moves. Within `latency.speculative_greeting.radius`,
+    /// the reply to the greeting input is generated on a background task, along
+    /// with its speech when `synthesize_speech` is set. The next matching input
+    /// within `ttl_ms` is answered with it instead of waiting for inference.
     ///
     /// # Arguments
     ///
-    /// * `event` - Event name to trigger the callback
-    /// * `callback` - Callback function
-    #[deprecated(since = "0.1.5", note = "Use on_event() with AgentEvent enum instead")]
-    pub fn register_callback<F>(&self, event: &str, callback: F)
-    where
-        F: Fn(&Agent, &str) + Send + Sync + 'static,
-    {
-        // Lock the callbacks mutex, recovering from poison if necessary
-        let mut callbacks = self.callbacks.lock().unwrap_or_else(|poisoned| {
-            log::warn!("Callback mutex was poisoned, recovering");
-            poisoned.into_inner()
-        });
-        let event_callbacks = callbacks.entry(event.to_string()).or_insert(Vec::new());
-        event_callbacks.push(CallbackWrapper::new(Box::new(callback)));
+    /// * `player_distance` - Current distance between the player and the agent
+    ///
+    /// # Returns
+    ///
+    /// The generating task, or `None` when speculation is disabled, the
+    /// player is out of range, or a greeting is already prepared or pending
+    pub fn anticipate_greeting(self: &Arc<Self>, player_distance: f32) -> Option<tokio::task::JoinHandle<()>> {
+        let speculation = &self.config.latency.speculative_greeting;
+        if !speculation.in_range(player_distance) || self.dry_run_enabled() {
+            return None;
+        }
+        let prepared = self.lock_speculative_greeting().as_ref().is_some_and(|greeting| greeting.is_fresh(speculation));
+        if prepared || self.speculating.swap(true, Ordering::SeqCst) {
+            return None;
+        }
+
+        let agent = self.clone();
+        Some(tokio::spawn(async move {
+            match agent.prepare_greeting().await {
+                Ok(greeting) => *agent.lock_speculative_greeting() = Some(greeting),
+                Err(e) => log::warn!("Agent {} could not prepare a greeting: {}", agent.name, e),
+            }
+            agent.speculating.store(false, Ordering::SeqCst);
+        }))
+    }
+
+    /// Generate the reply to the greeting input without recording it
+    async fn prepare_greeting(&self) -> Result<SpeculativeGreeting> {
+        let speculation = &self.config.latency.speculative_greeting;
+        let input = speculation.input.as_str();
+
+        let mut context = (*self.context.snapshot()).clone();
+        self.insert_game_time(&mut context);
+        Intent::analyze_with(input, &self.intent_matcher).await?.apply_to_context(&mut context);
+        let memories = self.recall(input, 5, None).await?;
+        let memories = self.capabilities.filter_memories(memories, &context);
+        if let Some(layers) = self.prompt_layers().await {
+            context.insert(PROMPT_LAYERS_KEY.to_string(), serde_json::json!(layers));
+        }
+        let budget = self.config.verbosity.budget_for(&context);
+
+        let options = RequestOptions { verbosity: budget, ..Default::default() };
+        let text = self.inference.generate_exchange(input, &memories, &context, &options).await?.response.text;
+        let text = self.keep_in_character(Generation::Routed, input, text, &memories, &context, &options).await?;
+        let text = self.moderate_output(input, text, &memories, &context, &options).await?;
+        let text = self.fit_budget(self.postprocessor.apply(&text), budget);
+
+        #[cfg(feature = "tts")]
+        let audio = match (&self.tts_service, speculation.synthesize_speech) {
+            (Some(_), true) => {
+                let emotions = self.emotional_state.read().await.clone();
+                self.speak(&text, &emotions, 0.0)
+                    .await
+                    .map_err(|e| log::warn!("Agent {} could not prepare greeting speech: {}", self.name, e))
+                    .ok()
+            }
+            _ => None,
+        };
+
+        log::debug!("Agent {} prepared a greeting", self.name);
+        Ok(SpeculativeGreeting {
+            text,
+            #[cfg(feature = "tts")]
+            audio,
+            generated_at: Instant::now(),
+        })
+    }
+
+    /// Take the prepared greeting if it answers this input and is still fresh
+    fn take_speculative_greeting(&self, input: &str) -> Option<SpeculativeGreeting> {
+        let speculation = &self.config.latency.speculative_greeting;
+        if !speculation.enabled || !speculation.matches_input(input) {
+            return None;
+        }
+        let greeting = self.lock_speculative_greeting().take()?;
+        greeting.is_fresh(speculation).then_some(greeting)
+    }
+
+    fn lock_speculative_greeting(&self) -> std::sync::MutexGuard<'_, Option<SpeculativeGreeting>> {
+        self.speculative_greeting.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
     }
 
-    /// Trigger a callback for a typed event
+    /// Add a behavior to the agent
     ///
     /// # Arguments
     ///
-    /// * `event` - Event type
-    /// * `data` - Event data
-    async fn trigger_event(&self, event: AgentEvent, data: &str) {
-        self.trigger_callback(event.as_str(), data).await;
+    /// * `behavior` - A behavior to add to the agent
+    pub async fn add_behavior<B: Behavior + 'static>(&self, behavior: B) {
+        self.add_boxed_behavior(Box::new(behavior)).await;
     }
 
-    /// Trigger a callback for an event
+    /// Add the behaviors listed in the agent's configuration
+    ///
+    /// Each entry in the config's `behavior` section is built by the
+    /// constructor registered under its `type` (or its key) in `registry`.
+    /// Use `factory::global_registry()` to include behaviors registered by
+    /// behavior packs.
     ///
     /// # Arguments
     ///
-    /// * `event` - Event name
-    /// * `data` - Event data
-    async fn trigger_callback(&self, event: &str, data: &str) {
-        // Lock the callbacks mutex, recovering from poison if necessary
-        let callbacks = self.callbacks.lock().unwrap_or_else(|poisoned| {
-            log::warn!("Callback mutex was poisoned during trigger, recovering");
-            poisoned.into_inner()
+    /// * `registry` - Registry used to build the behaviors
+    ///
+    /// # Returns
+    ///
+    /// The number of behaviors added, or an error if any could not be built
+    pub async fn add_configured_behaviors(&self, registry: &BehaviorRegistry) -> Result<usize> {
+        let mut configured = registry.create_all(&self.config.behavior)?;
+        for behavior in &mut configured {
+            behavior.use_modifiers(&self.modifier_provider);
+        }
+        let count = configured.len();
+        self.behaviors.write().await.extend(configured);
+        Ok(count)
+    }
+
+    /// Add a boxed behavior to the agent
+    ///
+    /// # Arguments
+    ///
+    /// * `behavior` - A boxed behavior to add to the agent
+    pub async fn add_boxed_behavior(&self, mut behavior: Box<dyn Behavior>) {
+        behavior.use_modifiers(&self.modifier_provider);
+        let mut behaviors = self.behaviors.write().await;
+        behaviors.push(behavior);
+    }
+
+    /// Update the agent's context with new data
+    ///
+    /// Updates rejected by the context schema are logged and dropped; use
+    /// [`Agent::try_update_context`] to handle them.
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - New context data to merge with existing context
+    pub async fn update_context(&self, context: AgentContext) {
+        if let Err(e) = self.try_update_context(context).await {
+            log::error!("Agent {} rejected context update: {}", self.name, e);
+        }
+    }
+
+    /// Validate and apply a context update as a whole
+    ///
+    /// The update is checked against the configured context schema. If any
+    /// key is rejected, none of the update is applied.
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - New context data to merge with existing context
+    ///
+    /// # Returns
+    ///
+    /// Warnings for keys that were accepted but do not fit the schema, or a
+    /// [`crate::OxydeError::ContextError`] listing the rejected keys
+    pub async fn try_update_context(&self, context: AgentContext) -> Result<Vec<ContextIssue>> {
+        self.apply_context_diff(ContextDiff::from(context)).await
+    }
+
+    /// Validate and apply a context diff as a whole
+    ///
+    /// Values that are already set are skipped, so games can resend their
+    /// full context every frame without copying the agent's context.
+    ///
+    /// # Arguments
+    ///
+    /// * `diff` - Keys to set and remove
+    ///
+    /// # Returns
+    ///
+    /// Warnings for keys that were accepted but do not fit the schema, or a
+    /// [`crate::OxydeError::ContextError`] listing the rejected keys
+    pub async fn apply_context_diff(&self, diff: ContextDiff) -> Result<Vec<ContextIssue>> {
+        let issues = self.config.context_schema.check(&diff.set);
+        let errors: Vec<String> = issues
+            .iter()
+            .filter(|issue| issue.severity == SchemaSeverity::Error)
+            .map(ToString::to_string)
+            .collect();
+        if !errors.is_empty() {
+            return Err(crate::OxydeError::ContextError(errors.join("; ")));
+        }
+        for issue in &issues {
+            log::warn!("Agent {} context: {}", self.name, issue);
+        }
+
+        self.apply_context(diff);
+        Ok(issues)
+    }
+
+    /// Get the agent's stored context without copying it
+    pub fn context_snapshot(&self) -> Arc<AgentContext> {
+        self.context.snapshot()
+    }
+
+    /// Start the agent
+    ///
+    /// This initializes the agent and prepares it for operation
+    pub async fn start(&self) -> Result<()> {
+        self.set_state(AgentState::Idle)?;
+        log::info!("Agent {} started", self.name);
+
+        // Initialize memory with agent's backstory and knowledge
+        self
+            .remember(Memory::new(
+                MemoryCategory::Semantic,
+                &serde_json::to_string(&self.config.agent.backstory)?,
+                f64::INFINITY,
+                None,
+            ))
+            .await?;
+        for entry in &self.config.agent.knowledge {
+            self.remember(knowledge::knowledge_memory(entry, None)).await?;
+        }
+
+        self.trigger_event(AgentEvent::Start, "Agent started").await;
+
+        Ok(())
+    }
+
+    /// Stop the agent
+    pub async fn stop(&self) -> Result<()> {
+        self.set_state(AgentState::Stopped)?;
+        log::info!("Agent {} stopped", self.name);
+
+        self.trigger_event(AgentEvent::Stop, "Agent stopped").await;
+
+        Ok(())
+    }
+
+    /// Check if content should be moderated
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - Content to check for inappropriate material
+    ///
+    /// # Returns
+    ///
+    /// `Some(response_message)` if content should be moderated, `None` if content is acceptable
+    #[tracing::instrument(name = "agent.moderation", skip_all)]
+    async fn check_moderation(&self, input: &str) -> Option<String> {
+        if !self.config.moderation.enabled {
+            return None;
+        }
+
+        if self.is_flagged(input).await {
+            log::warn!("Agent {} moderated inappropriate content: {}", self.name, input);
+            return Some(self.config.moderation.response_message.clone());
+        }
+
+        None
+    }
+
+    /// Check text against the regex patterns and, if enabled, cloud moderation
+    async fn is_flagged(&self, text: &str) -> bool {
+        // Quick regex check first (instant)
+        let regex_flagged = if let Some(ref patterns) = self.moderation_patterns {
+            patterns.is_match(&text.to_lowercase())
+        } else {
+            false
+        };
+
+        // If regex already flagged it, no need for cloud check - return immediately
+        if regex_flagged {
+            return true;
+        }
+
+        // Only do cloud check if regex didn't catch it and cloud moderation is enabled
+        if self.config.moderation.use_cloud_moderation {
+            let api_key = self.config.moderation.cloud_moderation_api_key.clone()
+                .or_else(|| self.config.inference.configured_api_key().ok().flatten())
+                .or_else(|| std::env::var("OPENAI_API_KEY").ok());
+
+            if let Some(key) = api_key {
+                match crate::utils::check_cloud_moderation(text, &key).await {
+                    Ok(flagged) => return flagged,
+                    Err(e) => {
+                        log::warn!("Cloud moderation failed, continuing without it: {}", e);
+                    }
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Moderate a generated response, regenerating it if it is flagged
+    ///
+    /// Each flagged response triggers [`AgentEvent::OutputFlagged`] and is
+    /// regenerated with the configured retry instruction added to the system
+    /// prompt, within the same generation deadline as the first attempt. Once `max_output_retries` is exhausted, the moderation
+    /// response message is returned instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - Player input the response answers
+    /// * `response` - Generated response
+    /// * `memories` - Memories used for generation
+    /// * `context` - Context used for generation
+    /// * `options` - Request options used for generation
+    ///
+    /// # Returns
+    ///
+    /// A response that passed moderation, or the moderation response message
+    #[tracing::instrument(name = "agent.output_moderation", skip_all)]
+    async fn moderate_output(
+        &self,
+        input: &str,
+        mut response: String,
+        memories: &[Memory],
+        context: &AgentContext,
+        options: &RequestOptions,
+    ) -> Result<String> {
+        let moderation = &self.config.moderation;
+        if !moderation.enabled || !moderation.moderate_output {
+            return Ok(response);
+        }
+
+        let mut retries = 0;
+        while self.is_flagged(&response).await {
+            log::warn!("Agent {} generated a flagged response: {}", self.name, response);
+            self.trigger_event(AgentEvent::OutputFlagged, &response).await;
+
+            if retries >= moderation.max_output_retries {
+                return Ok(moderation.response_message.clone());
+            }
+            retries += 1;
+
+            let mut context = context.clone();
+            context.insert(SAFETY_INSTRUCTION_KEY.to_string(), serde_json::json!(moderation.retry_instruction));
+            response = self.regenerate(Generation::Routed, input, memories, &context, options).await?;
+        }
+
+        Ok(response)
+    }
+
+    /// Generate a reply again the way the original was generated
+    async fn regenerate(
+        &self,
+        generation: Generation,
+        input: &str,
+        memories: &[Memory],
+        context: &AgentContext,
+        options: &RequestOptions,
+    ) -> Result<String> {
+        let exchange = match generation {
+            Generation::Routed => {
+                self.within_generation_deadline(self.inference.generate_exchange(input, memories, context, options))
+                    .await
+            }
+            Generation::Local => self.inference.generate_local_exchange(input, memories, context, options).await,
+        };
+        exchange.map(|exchange| exchange.response.text)
+    }
+
+    /// Correct a generated response that breaks the agent's consistency rules
+    ///
+    /// Violations are regenerated with the broken rule's instruction, the
+    /// way the response was generated, or filtered, as configured, and
+    /// reported as capability violations. Runs before output moderation, so
+    /// regenerated responses are moderated too.
+    async fn keep_in_character(
+        &self,
+        generation: Generation,
+        input: &str,
+        mut response: String,
+        memories: &[Memory],
+        context: &AgentContext,
+        options: &RequestOptions,
+    ) -> Result<String> {
+        let checker = self.capabilities.consistency();
+        if checker.is_empty() {
+            return Ok(response);
+        }
+
+        let mut violation = checker.check(&response);
+        self.update_consistency_stats(|stats| {
+            stats.checked += 1;
+            stats.violations += u64::from(violation.is_some());
         });
-        if let Some(event_callbacks) = callbacks.get(event) {
-            for callback in event_callbacks {
-                callback.call(self, data);
+
+        let config = checker.config();
+        let mut retries = 0;
+        while let Some(found) = violation {
+            self.report_violation(&CapabilityViolation::from(&found)).await;
+            if config.on_violation == ConsistencyAction::Filter || retries >= config.max_retries {
+                self.update_consistency_stats(|stats| stats.filtered += 1);
+                return Ok(checker.filter(&response));
+            }
+            retries += 1;
+            self.update_consistency_stats(|stats| stats.regenerated += 1);
+
+            let instruction = format!(
+                "Stay in character as {}, a {}. {}",
+                self.name, self.config.agent.role, found.instruction
+            );
+            let mut context = context.clone();
+            context.insert(SAFETY_INSTRUCTION_KEY.to_string(), serde_json::json!(instruction));
+            response = self.regenerate(generation, input, memories, &context, options).await?;
+            violation = checker.check(&response);
+        }
+
+        Ok(response)
+    }
+
+    fn update_consistency_stats(&self, update: impl FnOnce(&mut ConsistencyStats)) {
+        update(&mut self.consistency_stats.lock().unwrap_or_else(|poisoned| poisoned.into_inner()));
+    }
+
+    /// Counts of generated responses checked against the consistency rules,
+    /// with the violations found and how they were corrected
+    pub fn consistency_stats(&self) -> ConsistencyStats {
+        *self.consistency_stats.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Log a blocked action or response and notify callbacks
+    async fn report_violation(&self, violation: &CapabilityViolation) {
+        tracing::warn!(agent = %self.name, %violation, "Blocked capability violation");
+        self.trigger_event(AgentEvent::CapabilityViolation, &violation.to_string()).await;
+    }
+
+    /// Check whether a behavior's action is within the agent's capabilities
+    async fn permit_action(&self, action: &str) -> bool {
+        match self.capabilities.check_action(action) {
+            Some(violation) => {
+                self.report_violation(&violation).await;
+                false
+            }
+            None => true,
+        }
+    }
+
+    /// Replace a response that reveals a secret with the blocked response
+    async fn enforce_response(&self, response: String) -> String {
+        match self.capabilities.check_response(&response) {
+            Some(violation) => {
+                self.report_violation(&violation).await;
+                self.capabilities.blocked_response().to_string()
+            }
+            None => response,
+        }
+    }
+
+    /// Shorten a generated response to the channel's verbosity budget, if any
+    fn fit_budget(&self, response: String, budget: Option<VerbosityBudget>) -> String {
+        match budget {
+            Some(budget) if !budget.fits(&response) => {
+                log::debug!("Agent {} shortened a response to its verbosity budget", self.name);
+                budget.enforce(&response)
+            }
+            _ => response,
+        }
+    }
+
+    /// Text to store in memory, with PII redacted if configured
+    fn memory_text(&self, text: &str) -> String {
+        match &self.redactor {
+            Some(redactor) if self.config.redaction.redact_memories => redactor.redact(text).text().to_string(),
+            _ => text.to_string(),
+        }
+    }
+
+    /// Process player input and generate a response
+    ///
+    /// The request is supervised: it is bounded by the configured request
+    /// timeout, and if it fails, times out, or panics the agent passes through
+    /// the `Error` state, emits an error event, and recovers to `Idle`.
+    ///
+    /// When input debouncing is enabled, an input repeated within the debounce
+    /// window is answered with the first input's response, or dropped with an
+    /// empty response, without running the pipeline again.
+    ///
+    /// Inputs that arrive while another is being processed are handled by the
+    /// configured concurrency policy: queued in arrival order, coalesced into
+    /// the latest waiting input, or rejected with [`crate::OxydeError::Busy`].
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - Player input to process
+    ///
+    /// # Returns
+    ///
+    /// A result containing the agent's response, or the configured fallback
+    /// response if the request failed
+    #[tracing::instrument(name = "agent.process_input", skip_all, fields(agent = %self.name, queue_depth = tracing::field::Empty, variant = tracing::field::Empty))]
+    pub async fn process_input(&self, input: &str) -> Result<String> {
+        let ticket = match self.debouncer.admit(input) {
+            Admission::Process(ticket) => ticket,
+            Admission::Cached(response) => return Ok(response),
+            Admission::Drop => return Ok(String::new()),
+            Admission::Wait(mut receiver) => {
+                match receiver.wait_for(|response| response.is_some()).await {
+                    Ok(response) => return Ok(response.clone().unwrap_or_default()),
+                    // The original request failed; process this one instead
+                    Err(_) => None,
+                }
             }
+        };
+
+        let (result, own_input) = self.process_input_queued(input, InputExtras::default()).await;
+        if let Some(ticket) = ticket {
+            // A coalesced response answers a different input, so it is not cached
+            self.debouncer.finish(ticket, result.as_deref().ok().filter(|_| own_input));
         }
+        result
     }
 
+    /// Process player input accompanied by a persuasion check
+    ///
+    /// The check is resolved against the configured difficulty, adjusted for
+    /// the prior relationship and the agent's mood. Behaviors that declare a
+    /// persuasion difficulty only run when the check clears it, behaviors see
+    /// the result under the `persuasion` context key, and the inference
+    /// prompt is framed so the reply concedes or refuses accordingly.
+    ///
+    /// Input with a persuasion check is never debounced, since repeating an
+    /// attempt carries a new roll.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - Player input to process
+    /// * `persuasion` - The player's persuasion attempt
+    ///
+    /// # Returns
+    ///
+    /// A result containing the agent's response
+    #[tracing::instrument(name = "agent.process_input", skip_all, fields(agent = %self.name, queue_depth = tracing::field::Empty, variant = tracing::field::Empty))]
+    pub async fn process_input_with_context(&self, input: &str, persuasion: PersuasionContext) -> Result<String> {
+        let extras = InputExtras {
+            persuasion: Some(&persuasion),
+            ..Default::default()
+        };
+        self.process_input_queued(input, extras).await.0
+    }
+
+    /// Process player input accompanied by images
+    ///
+    /// Each image is captioned, using its description if the game supplied
+    /// one, then the captioner set with [`Agent::set_image_captioner`], then
+    /// the cloud provider if its model is vision-capable. Captions are stored
+    /// as episodic memories, exposed to behaviors under the `attachments`
+    /// context key, and quoted in the inference prompt.
+    ///
+    /// Input with attachments is never debounced.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - Player input to process
+    /// * `attachments` - Images the player is showing the agent
+    ///
+    /// # Returns
+    ///
+    /// A result containing the agent's response, or an error if an image
+    /// could not be captioned
+    #[tracing::instrument(name = "agent.process_input", skip_all, fields(agent = %self.name, queue_depth = tracing::field::Empty, variant = tracing::field::Empty))]
+    pub async fn process_input_with_attachments(&self, input: &str, attachments: &[ImageAttachment]) -> Result<String> {
+        let mut captions = Vec::with_capacity(attachments.len());
+        for attachment in attachments {
+            let caption = self.caption(attachment).await?;
+            self.remember(Memory::new(
+                MemoryCategory::Episodic,
+                &self.memory_text(&format!("The player showed me: {}", caption)),
+                0.8,
+                Some(vec![ATTACHMENTS_KEY.to_string()]),
+            ))
+            .await?;
+            captions.push(caption);
+        }
+
+        let extras = InputExtras {
+            captions: &captions,
+            ..Default::default()
+        };
+        self.process_input_queued(input, extras).await.0
+    }
+
+    /// Process player input and suggest an animation for the response
+    ///
+    /// Annotations follow the `annotations` configuration: rules over the
+    /// response before post-processing, or a model call that falls back to
+    /// the rules. Like persuasion checks, annotated input is never debounced.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - Player input to process
+    ///
+    /// # Returns
+    ///
+    /// The response text with its suggested gesture, facial expression and movement
+    #[tracing::instrument(name = "agent.process_input", skip_all, fields(agent = %self.name, queue_depth = tracing::field::Empty, variant = tracing::field::Empty))]
+    pub async fn process_input_annotated(&self, input: &str) -> Result<AgentOutput> {
+        let raw_response = std::sync::Mutex::new(None);
+        let extras = InputExtras {
+            raw_response: Some(&raw_response),
+            ..Default::default()
+        };
+        let text = self.process_input_queued(input, extras).await.0?;
+        let raw_response = raw_response
+            .into_inner()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .unwrap_or_else(|| text.clone());
+        let annotation = self.annotate(&raw_response).await;
+        Ok(AgentOutput { text, annotation })
+    }
+
+    /// Process player input and return the response as structured data
+    ///
+    /// Inference is asked for a JSON reply following the `structured_output`
+    /// configuration, and invalid replies are regenerated up to its
+    /// `max_retries`; see [`crate::structured`]. Responses from behaviors
+    /// carry the agent's current mood and the behaviors' actions. Structured
+    /// input is never debounced.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - Player input to process
+    ///
+    /// # Returns
+    ///
+    /// The dialogue with its mood tag and intended actions, or an error if
+    /// the model never produced a valid reply
+    #[tracing::instrument(name = "agent.process_input", skip_all, fields(agent = %self.name, queue_depth = tracing::field::Empty, variant = tracing::field::Empty))]
+    pub async fn process_input_structured(&self, input: &str) -> Result<StructuredResponse> {
+        let structured = std::sync::Mutex::new(None);
+        let extras = InputExtras {
+            structured: Some(&structured),
+            ..Default::default()
+        };
+        let text = self.process_input_queued(input, extras).await.0?;
+        let captured = structured.into_inner().unwrap_or_else(|poisoned| poisoned.into_inner());
+        Ok(match captured {
+            // The dialogue went through moderation and post-processing since
+            Some(response) => StructuredResponse { dialogue: text, ..response },
+            None => StructuredResponse::from_text(&text, &*self.emotional_state.read().await, &[]),
+        })
+    }
+
+    /// Process input from a player identified by the game
+    ///
+    /// The input runs with the player's ID, relationship and session context
+    /// values set in its context, without changing the agent's shared
+    /// context, so inputs of other players never see them. Memories of the
+    /// exchange are tagged with the player and only recalled in their
+    /// conversations, alongside the agent's own. The exchange is then
+    /// recorded in the player's session, started if they have none.
+    ///
+    /// The input is never debounced, since identical inputs of different
+    /// players need their own answers.
+    ///
+    /// # Arguments
+    ///
+    /// * `player_id` - The game's ID for the player, such as an account ID
+    /// * `input` - Player input to process
+    ///
+    /// # Returns
+    ///
+    /// A result containing the agent's response, or an error if the player
+    /// ID is empty
+    pub async fn process_input_for_player(&self, player_id: &str, input: &str) -> Result<String> {
+        if player_id.is_empty() {
+            return Err(crate::OxydeError::ContextError("Player ID cannot be empty".to_string()));
+        }
+        let session = self.sessions.get(player_id).unwrap_or_else(|| PlayerSession::new(player_id));
+        let mut context = session.context;
+        context.insert(PLAYER_ID_KEY.to_string(), serde_json::Value::from(player_id));
+        context.insert(PLAYER_RELATIONSHIP_KEY.to_string(), serde_json::Value::from(session.relationship));
+        let player = PlayerScope {
+            id: player_id.to_string(),
+            context,
+        };
+        let extras = InputExtras {
+            player: Some(&player),
+            ..Default::default()
+        };
+
+        let response = self.process_input_queued(input, extras).await.0?;
+
+        let config = self.sessions.config().clone();
+        self.sessions.update(player_id, |session| {
+            session.record_turn(&self.memory_text(input), &self.memory_text(&response), &config);
+        })?;
+        Ok(response)
+    }
+
+    /// Sessions of the players this agent has talked to
+    pub fn sessions(&self) -> &SessionStore {
+        &self.sessions
+    }
+
+    /// Memories about a player, recalled only in their conversations
+    pub async fn player_memories(&self, player_id: &str) -> Vec<Memory> {
+        self.memory.get_by_tag(&player_tag(player_id)).await
+    }
+
+    /// Responses and filler lines said, for dialogue UI to read in order
+    pub fn dialogue_queue(&self) -> &DialogueQueue {
+        &self.dialogue
+    }
+
+    /// Parse a structured reply, regenerating it with a correction while it
+    /// is invalid
+    async fn parse_structured(
+        &self,
+        input: &str,
+        mut reply: String,
+        memories: &[Memory],
+        context: &AgentContext,
+        options: &RequestOptions,
+    ) -> Result<StructuredResponse> {
+        let config = &self.config.structured_output;
+        let mut retries = 0;
+        loop {
+            let error = match config.parse(&reply) {
+                Ok(response) => return Ok(response),
+                Err(e) => e,
+            };
+            log::warn!("Agent {} generated an invalid structured response: {}", self.name, error);
+            if retries >= config.max_retries {
+                return Err(error);
+            }
+            retries += 1;
+
+            let options = RequestOptions {
+                structured_output: Some(config.retry_instruction(&error)),
+                ..options.clone()
+            };
+            reply = self.regenerate(Generation::Routed, input, memories, context, &options).await?;
+        }
+    }
+
+    /// Suggest an animation for a response
+    async fn annotate(&self, response: &str) -> ResponseAnnotation {
+        let config = &self.config.annotations;
+        let emotions = self.emotional_state.read().await.clone();
+        if config.mode == AnnotationMode::Model && !response.is_empty() && !self.dry_run_enabled() {
+            let annotation = self
+                .inference
+                .complete(config.model_prompt(), response, config.max_tokens)
+                .await
+                .and_then(|text| ResponseAnnotation::from_model_output(&text));
+            match annotation {
+                Ok(annotation) => return config.complete(annotation, response, &emotions),
+                Err(e) => log::warn!("Agent {} falling back to annotation rules: {}", self.name, e),
+            }
+        }
+        config.annotate(response, &emotions)
+    }
+
+    /// Set the provider [`Agent::modifier`] asks for modifiers
+    ///
+    /// The built-in [`crate::oxyde_game::modifier::SentimentModifiers`],
+    /// with the limits of the config's `modifiers` section, are used until
+    /// one is set. Behaviors already added, such as `TradingBehavior`, use
+    /// the new provider too.
+    pub fn set_modifier_provider(&self, provider: Arc<dyn ModifierProvider>) {
+        self.modifier_provider.set(provider);
+    }
+
+    /// Compute a modifier from the agent's relationship with the current
+    /// player and its current emotions
+    ///
+    /// Game economy code can use this to let the NPC's feelings adjust prices,
+    /// rewards and the like; see [`crate::oxyde_game::modifier`].
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Modifier to compute, such as `"price"`
+    ///
+    /// # Returns
+    ///
+    /// The modifier, or `None` if the provider does not know it
+    pub async fn modifier(&self, name: &str) -> Option<f32> {
+        let mut sentiment = Sentiment::from_context(&self.context.snapshot());
+        sentiment.emotions = self.emotional_state.read().await.clone();
+        self.modifier_provider.modifier(name, &sentiment)
+    }
+
+    /// Set the captioner used for image attachments without a description
+    pub fn set_image_captioner(&self, captioner: Arc<dyn ImageCaptioner>) {
+        let mut current = self.image_captioner.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+        *current = Some(captioner);
+    }
+
+    /// Caption an image attachment
+    async fn caption(&self, attachment: &ImageAttachment) -> Result<String> {
+        if let Some(description) = &attachment.description {
+            return Ok(description.clone());
+        }
+        let captioner = self
+            .image_captioner
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone();
+        match captioner {
+            Some(captioner) => captioner.caption(attachment).await,
+            None => self.inference.caption_image(attachment).await,
+        }
+    }
+
+    /// Explain how the behavior for the most recent input was chosen
+    ///
+    /// The report lists every behavior with its base priority, emotional
+    /// modifier, schedule and trigger checks, intent match, and result, along
+    /// with the behavior whose response was used.
+    ///
+    /// # Returns
+    ///
+    /// The report, or `None` if no input has reached behavior selection yet
+    pub async fn explain_last_selection(&self) -> Option<SelectionReport> {
+        self.last_selection.read().await.clone()
+    }
+
+    /// Get counters for inputs processed, answered from cache, or dropped
+    /// by input debouncing
+    pub fn debounce_stats(&self) -> DebounceStats {
+        self.debouncer.stats()
+    }
+
+    /// Get the request queue depth and counters for overlapping inputs
+    pub fn request_queue_stats(&self) -> RequestQueueStats {
+        self.request_queue.stats()
+    }
+
+    /// Subscribe to notifications when the inference engine switches models
+    /// to meet its latency or spend targets
+    pub fn subscribe_model_switches(&self) -> tokio::sync::broadcast::Receiver<crate::model_policy::ModelSwitch> {
+        self.inference.subscribe_model_switches()
+    }
+
+    /// Wait for the agent's turn according to the concurrency policy, then
+    /// process the input
+    ///
+    /// # Returns
+    ///
+    /// The result, and whether it answers `input` rather than a later input
+    /// it was coalesced into
+    async fn process_input_queued(
+        &self,
+        input: &str,
+        extras: InputExtras<'_>,
+    ) -> (Result<String>, bool) {
+        let slot = self.request_queue.admit(input).await;
+        tracing::Span::current().record("queue_depth", self.request_queue.stats().depth);
+
+        match slot {
+            Ok(Slot::Run(permit)) => {
+                let result = self.process_input_supervised(&permit.input, extras).await;
+                permit.finish(&result);
+                let own_input = permit.input == input;
+                (result, own_input)
+            }
+            Ok(Slot::Coalesced(mut receiver)) => {
+                let outcome = receiver.wait_for(|outcome| outcome.is_some()).await.ok().and_then(|o| o.clone());
+                let result = match outcome {
+                    Some(Ok(response)) => Ok(response),
+                    Some(Err(error)) => Err(crate::OxydeError::RequestError(error)),
+                    None => Err(crate::OxydeError::RequestError(
+                        "Coalesced request was cancelled".to_string(),
+                    )),
+                };
+                (result, false)
+            }
+            Err(e) => {
+                log::warn!("Agent {} rejected input: {}", self.name, e);
+                (Err(e), false)
+            }
+        }
+    }
+
+    /// Run `process_input` with timeout and error recovery
+    async fn process_input_supervised(&self, input: &str, extras: InputExtras<'_>) -> Result<String> {
+        let _guard = InputGuard(self);
+        let request = AssertUnwindSafe(self.process_input_unsupervised(input, extras)).catch_unwind();
+        let timeout_ms = self.config.supervisor.request_timeout_ms;
+
+        let outcome = if timeout_ms > 0 {
+            match timer::timeout(Duration::from_millis(timeout_ms), request).await {
+                Ok(outcome) => outcome,
+                Err(_) => Ok(Err(crate::OxydeError::InferenceError(format!(
+                    "Request timed out after {} ms",
+                    timeout_ms
+                )))),
+            }
+        } else {
+            request.await
+        };
+
+        let error = match outcome {
+            Ok(Ok(response)) => {
+                self.reflect_if_due().await;
+                return Ok(response);
+            }
+            Ok(Err(e)) => e,
+            Err(panic) => {
+                let message = panic
+                    .downcast_ref::<&str>()
+                    .map(|s| s.to_string())
+                    .or_else(|| panic.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "unknown panic".to_string());
+                crate::OxydeError::BehaviorError(format!("Request panicked: {}", message))
+            }
+        };
+
+        self.recover_from_error(&error).await;
+
+        match &self.config.supervisor.fallback_response {
+            Some(fallback) => {
+                self.trigger_event(AgentEvent::Response, fallback).await;
+                Ok(fallback.clone())
+            }
+            None => Err(error),
+        }
+    }
+
+    /// Summarize recent memories into higher-level insights
+    ///
+    /// Asks the model to reflect on the most recent `memory.reflection.window`
+    /// memories, excluding earlier reflections and secret memories, and
+    /// stores each insight as a semantic memory tagged `reflection`. Insights
+    /// are private when any of the memories reflected on is. With reflection
+    /// enabled this runs automatically every `every_n_interactions` inputs.
+    ///
+    /// # Returns
+    ///
+    /// The stored insights
+    pub async fn reflect(&self) -> Result<Vec<Memory>> {
+        let config = &self.config.memory.reflection;
+        let memories: Vec<Memory> = self
+            .memory
+            .recent(config.window)
+            .await
+            .into_iter()
+            .filter(|memory| !memory.tags.iter().any(|tag| tag == REFLECTION_TAG))
+            // Secret memories are never sent to inference
+            .filter(|memory| memory.visibility != MemoryVisibility::Secret)
+            .collect();
+        if memories.is_empty() {
+            return Ok(Vec::new());
+        }
+        // Insights reveal as much as the memories they summarize
+        let visibility = memories.iter().map(|memory| memory.visibility).max().unwrap_or_default();
+
+        let (system_prompt, input) = config.prompt(&self.name, &memories);
+        let text = self.inference.complete(&system_prompt, &input, config.max_tokens).await?;
+
+        let mut insights = Vec::new();
+        for insight in config.parse_insights(&text) {
+            let memory = Memory::new(
+                MemoryCategory::Semantic,
+                &self.memory_text(&insight),
+                config.importance,
+                Some(vec![REFLECTION_TAG.to_string()]),
+            )
+            .with_visibility(visibility);
+            self.remember(memory.clone()).await?;
+            insights.push(memory);
+        }
+        log::debug!("Agent {} reflected into {} insights", self.name, insights.len());
+        Ok(insights)
+    }
+
+    /// Count a processed input and reflect when enough have accumulated
+    ///
+    /// Failures are logged; a reflection never fails the input that triggered it.
+    async fn reflect_if_due(&self) {
+        let config = &self.config.memory.reflection;
+        if !config.enabled {
+            return;
+        }
+        let count = self.interactions_since_reflection.fetch_add(1, Ordering::Relaxed) + 1;
+        if count < config.every_n_interactions {
+            return;
+        }
+        self.interactions_since_reflection.store(0, Ordering::Relaxed);
+        if let Err(e) = self.reflect().await {
+            log::warn!("Agent {} failed to reflect: {}", self.name, e);
+        }
+    }
+
+    /// Apply the memory forgetting policy at the current game time
+    ///
+    /// Emotional intensities fade over the game days since the previous
+    /// application, so the first one only expires memories. Nothing changes
+    /// without a game time, or when the game time went backwards, such as
+    /// after loading an earlier save.
+    ///
+    /// # Returns
+    ///
+    /// How many memories expired and faded
+    pub async fn apply_forgetting(&self) -> ForgettingReport {
+        let Some(now) = self.game_time().await else {
+            return ForgettingReport::default();
+        };
+        let previous = self
+            .last_forgetting
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .replace(now);
+        let elapsed_days = previous.map_or(0.0, |previous| now.hours_since(&previous).max(0.0) / 24.0);
+        if self.config.memory.forgetting.enabled {
+            self.record(StateEvent::ForgettingApplied { now, elapsed_days });
+        }
+        let report = self.memory.apply_forgetting(&now, elapsed_days).await;
+        if report.expired > 0 || report.faded > 0 {
+            log::debug!(
+                "Agent {} forgot {} memories and faded {}",
+                self.name, report.expired, report.faded
+            );
+        }
+        report
+    }
+
+    /// Apply the forgetting policy every `memory.forgetting.interval_ms` on a
+    /// background task
+    ///
+    /// The task holds a weak reference, so it ends once the agent is dropped.
+    ///
+    /// # Returns
+    ///
+    /// The task, or `None` when the policy is disabled
+    pub fn spawn_forgetting(self: &Arc<Self>) -> Option<tokio::task::JoinHandle<()>> {
+        let policy = &self.config.memory.forgetting;
+        if !policy.enabled {
+            return None;
+        }
+        let period = Duration::from_millis(policy.interval_ms);
+        let agent = Arc::downgrade(self);
+        Some(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                let Some(agent) = agent.upgrade() else {
+                    break;
+                };
+                agent.apply_forgetting().await;
+            }
+        }))
+    }
+
+    /// Note what the agent privately thinks about an exchange
+    ///
+    /// The note needs an inference request of its own, which runs in the
+    /// background so the response isn't held up by it; the note is stored
+    /// when the next input arrives or the monologue is read. Without a Tokio
+    /// runtime to run it on, such as in the browser, the note is written
+    /// before returning. Failures are logged; a note never fails the input it
+    /// is about.
+    async fn write_monologue(&self, input: &str, response: &str) {
+        let config = &self.config.monologue;
+        if !config.enabled || self.dry_run_enabled() {
+            return;
+        }
+        self.settle_monologue().await;
+
+        let (system_prompt, input) = config.prompt(&self.name, input, response);
+        let inference = self.inference.clone();
+        let agent_config = self.config.clone();
+        let name = self.name.clone();
+        let note = async move {
+            let config = &agent_config.monologue;
+            match inference.complete(&system_prompt, &input, config.max_tokens).await {
+                Ok(text) => config.parse_note(&text),
+                Err(e) => {
+                    log::warn!("Agent {} failed to write its monologue: {}", name, e);
+                    None
+                }
+            }
+        };
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => *self.lock_pending_monologue() = Some(runtime.spawn(note)),
+            Err(_) => {
+                if let Some(note) = note.await {
+                    self.store_monologue(note).await;
+                }
+            }
+        }
+    }
+
+    /// Store the monologue note being written in the background, if any,
+    /// once it is done
+    async fn settle_monologue(&self) {
+        let Some(pending) = self.lock_pending_monologue().take() else {
+            return;
+        };
+        match pending.await {
+            Ok(Some(note)) => self.store_monologue(note).await,
+            Ok(None) => {}
+            Err(e) => log::warn!("Agent {} failed to write its monologue: {}", self.name, e),
+        }
+    }
+
+    /// Keep a monologue note as a private memory
+    async fn store_monologue(&self, note: String) {
+        log::debug!("Agent {} thinks: {}", self.name, note);
+        let memory = Memory::new(
+            MemoryCategory::Emotional,
+            &self.memory_text(&note),
+            self.config.monologue.importance,
+            Some(vec![MONOLOGUE_TAG.to_string()]),
+        )
+        .with_visibility(MemoryVisibility::Private);
+        if let Err(e) = self.remember(memory).await {
+            log::warn!("Agent {} failed to store its monologue: {}", self.name, e);
+        }
+    }
+
+    fn lock_pending_monologue(&self) -> std::sync::MutexGuard<'_, Option<tokio::task::JoinHandle<Option<String>>>> {
+        self.pending_monologue.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Get the agent's most recent internal monologue notes
+    ///
+    /// Notes are private memories for designers tuning a character; they are
+    /// never part of a response.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - Maximum number of notes to return
+    ///
+    /// # Returns
+    ///
+    /// Up to `limit` notes, oldest first
+    pub async fn monologue(&self, limit: usize) -> Vec<Memory> {
+        self.settle_monologue().await;
+        let mut notes: Vec<Memory> = self
+            .memory
+            .recent(usize::MAX)
+            .await
+            .into_iter()
+            .filter(|memory| memory.tags.iter().any(|tag| tag == MONOLOGUE_TAG))
+            .collect();
+        notes.drain(..notes.len().saturating_sub(limit));
+        notes
+    }
+
+    /// Move the agent through the `Error` state back to `Idle`
+    ///
+    /// # Arguments
+    ///
+    /// * `error` - The error that interrupted the request
+    async fn recover_from_error(&self, error: &crate::OxydeError) {
+        log::error!("Agent {} failed to process input: {}", self.name, error);
+
+        let entered_error = self.settle_state(AgentState::Error);
+        self.trigger_event(AgentEvent::Error, &error.to_string()).await;
+        if entered_error {
+            // Error always recovers to Idle
+            let _ = self.set_state(AgentState::Idle);
+        }
+    }
+
+    /// Run the `process_input` pipeline without timeout or error recovery
+    async fn process_input_unsupervised(&self, input: &str, extras: InputExtras<'_>) -> Result<String> {
+        self.set_state(AgentState::Processing)?;
+
+        log::debug!("Agent {} processing input: {}", self.name, input);
+        self.settle_monologue().await;
+
+        // Check for inappropriate content if moderation is enabled
+        if let Some(moderation_response) = self.check_moderation(input).await {
+            if self.config.reputation.enabled {
+                let player = match extras.player {
+                    Some(player) => player.id.clone(),
+                    None => player_key(&self.context.snapshot()),
+                };
+                self.record_player_offense(&player, self.config.reputation.offense_weight).await;
+            }
+            self.set_state(AgentState::Idle)?;
+            self.trigger_callback("response", &moderation_response).await;
+            return Ok(moderation_response);
+        }
+
+        // Resolve the scheduled activity; unavailable NPCs answer with a canned response
+        let mut context = (*self.context.snapshot()).clone();
+        if let Some(player) = extras.player {
+            context.extend(player.context.iter().map(|(key, value)| (key.clone(), value.clone())));
+        }
+        if let Some(block) = self.scheduled_block(&context) {
+            if let (false, Some(response)) = (block.available, &block.unavailable_response) {
+                self.set_state(AgentState::Idle)?;
+                self.trigger_event(AgentEvent::Response, response).await;
+                return Ok(response.clone());
+            }
+            context.insert(SCHEDULED_ACTIVITY_KEY.to_string(), serde_json::json!(block.activity));
+        }
+        let game_time = self.insert_game_time(&mut context);
+        let variant = self.assign_experiment(&mut context);
+
+        // Find the player's language, translating the input for intent analysis if configured
+        let (intent_input, locale) = self.localize_input(input, &context, extras.player).await;
+        let mut options = variant.map(ExperimentVariant::request_options).unwrap_or_default();
+        options.locale_instruction = locale.as_deref().and_then(|locale| self.config.language.instruction(locale));
+
+        // Analyze player intent
+        let intent = Intent::analyze_with(&intent_input, &self.intent_matcher)
+            .instrument(tracing::info_span!("agent.intent"))
+            .await?;
+
+        // Let inference routing see what kind of input this is
+        intent.apply_to_context(&mut context);
+
+        // Behaviors, conditions and agenda preconditions read the agent's emotions
+        let current_emotional_state = self.emotional_state.read().await.clone();
+        context.insert(EMOTIONAL_STATE_KEY.to_string(), serde_json::to_value(&current_emotional_state)?);
+
+        // Track the conversation topic for behaviors and inference
+        self.track_input_topic(&intent, &mut context).await;
+
+        // Acknowledge players returning after a long absence
+        self.track_player_return(&mut context).await;
+
+        // Let behaviors react to the player's toxicity over time
+        self.track_player_reputation(&mut context).await;
+
+        // Let long-term personality drift color the reply
+        self.frame_disposition(&mut context).await;
+
+        // Show behaviors and inference what the player attached
+        crate::attachment::apply_to_context(extras.captions, &mut context);
+
+        // Resolve the persuasion check against the current mood
+        let persuasion = match extras.persuasion {
+            Some(attempt) => {
+                let result = attempt.resolve(&self.config.persuasion, &*self.emotional_state.read().await);
+                result.apply_to_context(&mut context);
+                Some(result)
+            }
+            None => None,
+        };
+
+        // Update memory with player input, capturing current emotional state
+        let emotional_state = self.emotional_state.read().await.clone();
+        self.remember(Memory::new_emotional(
+                MemoryCategory::Episodic,
+                &self.memory_text(input),
+                1.0,
+                emotional_state.valence() as f64,
+                emotional_state.arousal() as f64,
+                extras.player.and_then(PlayerScope::tags)
+            )).await?;
+
+        // Find behaviors that match the intent
+        let behaviors = self.behaviors.read().await;
+        let mut response = String::new();
+
+        self.set_state(AgentState::Executing)?;
+
+        context.insert(AFFECT_KEY.to_string(), dimensions_json(self.affect().as_ref(), &current_emotional_state));
+        let recent_emotions = self.record_emotion_turn(&current_emotional_state).await;
+
+        // Filter and sort behaviors by priority (considering emotional modifiers)
+        let activity = context.get(SCHEDULED_ACTIVITY_KEY).and_then(|v| v.as_str());
+        let hour = game_time.map(|time| time.hour);
+        let (mut report, ranked) = tracing::info_span!("agent.behavior_selection").in_scope(|| {
+            SelectionReport::evaluate(
+                &intent,
+                &behaviors,
+                &recent_emotions,
+                activity,
+                hour,
+                persuasion.as_ref(),
+            )
+        });
+
+        // Execute matching behaviors in priority order
+        let mut actions = Vec::new();
+        let mut opener: Option<String> = None;
+        for index in ranked {
+            let behavior = &behaviors[index];
+            let matched = self.match_behavior(behavior.as_ref(), &intent).await;
+            report.record_match(index, matched.is_some());
+            // Only one greeting opens a combined reply
+            let matched = matched.filter(|matched| opener.is_none() || matched.intent_type != IntentType::Greeting);
+            if let Some(matched) = matched {
+                let behavior_result = self
+                    .execute_behavior(behavior.as_ref(), &matched, &context, &mut report, index)
+                    .await;
+                let behavior_result = match behavior_result {
+                    Ok(result) => result,
+                    Err(e) => {
+                        *self.last_selection.write().await = Some(report);
+                        return Err(e);
+                    }
+                };
+                let behavior_result = match report.candidate(index) {
+                    Some(candidate) => {
+                        options.active_behavior = Some(candidate.name.clone());
+                        self.localize_behavior_result(behavior_result, &candidate.name, locale.as_deref())
+                    }
+                    None => behavior_result,
+                };
+
+                // Apply emotional influences from the behavior
+                let influences = behavior.emotion_influences();
+                if !influences.is_empty() {
+                    self.change_emotions("behavior", |emotional_state| {
+                        let mut affect = self.affect();
+                        for influence in influences {
+                            affect.stimulate(emotional_state, &influence.emotion, influence.delta);
+                        }
+                    })
+                    .await;
+                }
+
+                for topic in behavior.agenda_topics() {
+                    self.topics.write().await.push_agenda(topic);
+                }
+
+                match behavior_result {
+                    BehaviorResult::Response(text) if self.opens_combo(&matched, &intent) => {
+                        opener = Some(self.enforce_response(text).await);
+                    }
+                    BehaviorResult::Response(text) => {
+                        response = self.enforce_response(text).await;
+                        break;
+                    }
+                    BehaviorResult::Action(action) => {
+                        // Trigger action callback
+                        if self.permit_action(&action).await {
+                            self.trigger_event(AgentEvent::Action, &action).await;
+                            actions.push(action);
+                        }
+                    },
+                    BehaviorResult::ResponseWithAction { response: text, action } => {
+                        // The response describes the action, so both are dropped if it is blocked
+                        if self.permit_action(&action).await {
+                            self.trigger_event(AgentEvent::Action, &action).await;
+                            actions.push(action);
+                            response = self.enforce_response(text).await;
+                            break;
+                        }
+                    }
+                    BehaviorResult::None => {
+                        // Continue to next behavior
+                    }
+                }
+            }
+        }
+
+        // Answer with a greeting prepared while the player approached
+        let prepared = if response.is_empty() && opener.is_none() { self.take_speculative_greeting(input) } else { None };
+        if let Some(greeting) = prepared {
+            response = self.enforce_response(greeting.text).await;
+            #[cfg(feature = "tts")]
+            if let Some(audio) = greeting.audio {
+                *self.prepared_speech.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) =
+                    Some((response.clone(), audio));
+            }
+            let emotional_state = self.emotional_state.read().await.clone();
+            self.remember(Memory::new_emotional(
+                MemoryCategory::Semantic,
+                &self.memory_text(&response),
+                1.0,
+                emotional_state.valence() as f64,
+                emotional_state.arousal() as f64,
+                extras.player.and_then(PlayerScope::tags)
+            )).await?;
+        }
+
+        if let (Some(structured), false) = (extras.structured, response.is_empty()) {
+            let emotions = self.emotional_state.read().await.clone();
+            *structured.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) =
+                Some(StructuredResponse::from_text(&response, &emotions, &actions));
+        }
+
+        report.used_inference = response.is_empty();
+        *self.last_selection.write().await = Some(report);
+
+        // If no behavior provided a response, generate one with inference
+        if response.is_empty() {
+            self.set_state(AgentState::Generating)?;
+
+            // Get relevant memories
+            let memories = self
+                .recall(input, 5, extras.player.map(|player| player.id.as_str()))
+                .instrument(tracing::info_span!("agent.memory_retrieval"))
+                .await?;
+            let memories = self.capabilities.filter_memories(memories, &context);
+
+            if let Some(layers) = self.variant_prompt_layers(variant).await {
+                context.insert(PROMPT_LAYERS_KEY.to_string(), serde_json::json!(layers));
+            }
+            let budget = self.config.verbosity.budget_for(&context);
+            options.verbosity = budget;
+            // Behaviors may have changed emotions, which sampling follows
+            options.emotions = Some(self.emotional_state.read().await.clone());
+            if extras.structured.is_some() {
+                options.structured_output = Some(self.config.structured_output.instruction());
+            }
+
+            if self.dry_run_enabled() {
+                self.set_state(AgentState::Idle)?;
+                return Ok(self.inference.dry_run(input, &memories, &context, &options).render());
+            }
+
+            // Generate response using inference engine
+            let generation = self.inference.generate_exchange(input, &memories, &context, &options);
+            match self.within_latency_target(self.within_generation_deadline(generation), &context).await {
+                Ok(exchange) => {
+                    let mut text = exchange.response.text.clone();
+                    if let Some(structured) = extras.structured {
+                        let mut reply = self.parse_structured(input, text, &memories, &context, &options).await?;
+                        // Actions of behaviors that ran before inference come first
+                        let behavior_actions = actions.iter().map(|name| ActionIntent { name: name.clone(), target: None });
+                        reply.actions.splice(0..0, behavior_actions);
+                        text = reply.dialogue.clone();
+                        *structured.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(reply);
+                    }
+                    let text = self
+                        .keep_in_character(Generation::Routed, input, text, &memories, &context, &options)
+                        .await?;
+                    let text = self.moderate_output(input, text, &memories, &context, &options).await?;
+                    response = self.enforce_response(self.fit_budget(self.postprocessor.apply(&text), budget)).await;
+                    if let Some(raw_response) = extras.raw_response {
+                        *raw_response.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(text);
+                    }
+                    self.log_interaction(&exchange, &response, current_emotional_state).await;
+
+                    // Store the response in memory with current emotional state
+                    let emotional_state = self.emotional_state.read().await.clone();
+                    self.remember(Memory::new_emotional(
+                        MemoryCategory::Semantic,
+                        &self.memory_text(&response),
+                        1.0,
+                        emotional_state.valence() as f64,
+                        emotional_state.arousal() as f64,
+                        extras.player.and_then(PlayerScope::tags)
+                    )).await?;
+                }
+                // Inference is unavailable; answer with a canned response if configured
+                Err(e @ crate::OxydeError::InferenceError(_)) => {
+                    response = self.offline_response(intent.intent_type, &context).await.ok_or(e)?;
+                    log::warn!("Agent {} using offline fallback response", self.name);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        // A greeting opening a combined reply comes first
+        if let Some(opener) = opener {
+            response = if response.is_empty() { opener } else { format!("{} {}", opener, response) };
+        }
+
+        self.set_state(AgentState::Idle)?;
+
+
+        self.topics.write().await.observe_response(&response);
+
+        // Trigger response callback
+        self.trigger_event(AgentEvent::Response, &response).await;
+
+        self.write_monologue(input, &response).await;
+
+        Ok(response)
+    }
+
+    /// Execute a single deterministic turn
+    ///
+    /// Runs a bounded version of the `process_input` pipeline synchronously:
+    /// regex moderation only, intent analysis, behavior selection, and an
+    /// optional cached or local inference fallback. No network calls or
+    /// background tasks are made, and behaviors receive a per-turn seed in
+    /// their context so random choices are reproducible. Emotions decay once
+    /// at the end of every turn.
+    ///
+    /// Local inference bypasses the inference scheduler, so no Tokio runtime
+    /// is needed unless the mock provider simulates latency. This blocks the
+    /// calling thread and must not be called from inside an async task that
+    /// is already using this agent.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - Turn number, player input, seed and inference mode
+    ///
+    /// # Returns
+    ///
+    /// The outcome of the turn
+    pub fn step(&self, input: TurnInput) -> Result<TurnOutput> {
+        // Opt out of Tokio's cooperative budget: when called from inside a
+        // runtime, the budget belongs to the enclosing task and would otherwise
+        // run out mid-turn, leaving lock acquisitions pending forever
+        futures::executor::block_on(tokio::task::unconstrained(self.run_turn(input)))
+    }
+
+    /// Async body of [`Agent::step`]
+    #[tracing::instrument(name = "agent.step", skip_all, fields(agent = %self.name, turn = input.turn, variant = tracing::field::Empty))]
+    async fn run_turn(&self, input: TurnInput) -> Result<TurnOutput> {
+        let _guard = InputGuard(self);
+        self.set_state(AgentState::Processing)?;
+
+        let intent = match Intent::analyze_with(&input.input, &self.intent_matcher).await {
+            Ok(intent) => intent,
+            Err(e) => {
+                self.set_state(AgentState::Idle)?;
+                return Err(e);
+            }
+        };
+
+        let mut output = TurnOutput {
+            turn: input.turn,
+            response: None,
+            actions: Vec::new(),
+            intent: intent.intent_type,
+            source: TurnSource::None,
+            emotions: [0.0; 8],
+        };
+
+        // Only the local regex check is deterministic, so cloud moderation is skipped
+        let moderated = self.config.moderation.enabled
+            && self
+                .moderation_patterns
+                .as_ref()
+                .map(|patterns| patterns.is_match(&input.input.to_lowercase()))
+                .unwrap_or(false);
+
+        // The schedule sees the stored context, as it does in process_input
+        let mut context = (*self.context.snapshot()).clone();
+        context.extend(input.context.clone());
+        let unavailable = self
+            .scheduled_block(&context)
+            .filter(|block| !block.available)
+            .and_then(|block| block.unavailable_response);
+
+        if moderated {
+            output.response = Some(self.config.moderation.response_message.clone());
+            output.source = TurnSource::Moderation;
+        } else if let Some(response) = unavailable {
+            output.response = Some(response);
+            output.source = TurnSource::Schedule;
+        } else {
+            context.insert(TURN_NUMBER_KEY.to_string(), serde_json::json!(input.turn));
+            context.insert(
+                TURN_SEED_KEY.to_string(),
+                serde_json::json!(input.effective_seed()),
+            );
+            let scheduled = self.scheduled_block(&context);
+            if let Some(block) = &scheduled {
+                context.insert(SCHEDULED_ACTIVITY_KEY.to_string(), serde_json::json!(block.activity));
+            }
+            let game_time = self.insert_game_time(&mut context);
+            let variant = self.assign_experiment(&mut context);
+            let current_emotional_state = self.emotional_state.read().await.clone();
+            context.insert(EMOTIONAL_STATE_KEY.to_string(), serde_json::to_value(&current_emotional_state)?);
+            self.track_input_topic(&intent, &mut context).await;
+            self.frame_disposition(&mut context).await;
+
+            {
+                let emotional_state = self.emotional_state.read().await.clone();
+                self
+                    .remember(Memory::new_emotional(
+                        MemoryCategory::Episodic,
+                        &self.memory_text(&input.input),
+                        1.0,
+                        emotional_state.valence() as f64,
+                        emotional_state.arousal() as f64,
+                        None,
+                    ))
+                    .await?;
+            }
+
+            self.set_state(AgentState::Executing)?;
+            let behaviors = self.behaviors.read().await;
+            context.insert(AFFECT_KEY.to_string(), dimensions_json(self.affect().as_ref(), &current_emotional_state));
+            let recent_emotions = self.record_emotion_turn(&current_emotional_state).await;
+            let activity = scheduled.as_ref().map(|block| block.activity.as_str());
+            let hour = game_time.map(|time| time.hour);
+
+            let (mut report, ranked) =
+                SelectionReport::evaluate(&intent, &behaviors, &recent_emotions, activity, hour, None);
+
+            let mut opener: Option<String> = None;
+            let mut active_behavior: Option<String> = None;
+            for index in ranked {
+                let behavior = &behaviors[index];
+                let matched = self.match_behavior(behavior.as_ref(), &intent).await;
+                report.record_match(index, matched.is_some());
+                let Some(matched) = matched else {
+                    continue;
+                };
+                if opener.is_some() && matched.intent_type == IntentType::Greeting {
+                    continue;
+                }
+
+                let result = self
+                    .execute_behavior(behavior.as_ref(), &matched, &context, &mut report, index)
+                    .await;
+                let result = match result {
+                    Ok(result) => result,
+                    Err(e) => {
+                        *self.last_selection.write().await = Some(report);
+                        return Err(e);
+                    }
+                };
+                let result = match report.candidate(index) {
+                    Some(candidate) => {
+                        active_behavior = Some(candidate.name.clone());
+                        let locale = context.get(PLAYER_LOCALE_KEY).and_then(|v| v.as_str());
+                        self.localize_behavior_result(result, &candidate.name, locale)
+                    }
+                    None => result,
+                };
+
+                let influences = behavior.emotion_influences();
+                if !influences.is_empty() {
+                    self.change_emotions("behavior", |emotional_state| {
+                        let mut affect = self.affect();
+                        for influence in influences {
+                            affect.stimulate(emotional_state, &influence.emotion, influence.delta);
+                        }
+                    })
+                    .await;
+                }
+
+                for topic in behavior.agenda_topics() {
+                    self.topics.write().await.push_agenda(topic);
+                }
+
+                match result {
+                    BehaviorResult::Response(text) if self.opens_combo(&matched, &intent) => {
+                        opener = Some(self.enforce_response(text).await);
+                    }
+                    BehaviorResult::Response(text) => {
+                        output.response = Some(self.enforce_response(text).await);
+                        output.source = TurnSource::Behavior;
+                        break;
+                    }
+                    BehaviorResult::Action(action) => {
+                        if self.permit_action(&action).await {
+                            self.trigger_event(AgentEvent::Action, &action).await;
+                            output.actions.push(action);
+                        }
+                    }
+                    BehaviorResult::ResponseWithAction { response, action } => {
+                        if self.permit_action(&action).await {
+                            self.trigger_event(AgentEvent::Action, &action).await;
+                            output.actions.push(action);
+                            output.response = Some(self.enforce_response(response).await);
+                            output.source = TurnSource::Behavior;
+                            break;
+                        }
+                    }
+                    BehaviorResult::None => {}
+                }
+            }
+            drop(behaviors);
+
+            report.used_inference =
+                output.response.is_none() && matches!(input.inference, TurnInference::Local);
+            *self.last_selection.write().await = Some(report);
+
+            if output.response.is_none() {
+                match &input.inference {
+                    TurnInference::Disabled => {}
+                    TurnInference::Cached(responses) => {
+                        let key = input.input.trim().to_lowercase();
+                        if let Some(text) = responses.get(&key) {
+                            output.response = Some(text.clone());
+                            output.source = TurnSource::Cache;
+                        }
+                    }
+                    TurnInference::Local => {
+                        self.set_state(AgentState::Generating)?;
+                        if let Some(layers) = self.variant_prompt_layers(variant).await {
+                            context.insert(PROMPT_LAYERS_KEY.to_string(), serde_json::json!(layers));
+                        }
+                        let budget = self.config.verbosity.budget_for(&context);
+                        let options = RequestOptions {
+                            verbosity: budget,
+                            emotions: Some(self.emotional_state.read().await.clone()),
+                            active_behavior: active_behavior.clone(),
+                            ..variant.map(ExperimentVariant::request_options).unwrap_or_default()
+                        };
+                        let exchange = self
+                            .inference
+                            .generate_local_exchange(&input.input, &[], &context, &options)
+                            .await?;
+                        let reply = exchange.response.text.clone();
+                        let text = self
+                            .keep_in_character(Generation::Local, &input.input, reply, &[], &context, &options)
+                            .await?;
+                        let text = self.postprocessor.apply(&text);
+                        let text = self.enforce_response(self.fit_budget(text, budget)).await;
+                        self.log_interaction(&exchange, &text, current_emotional_state.clone()).await;
+                        output.response = Some(text);
+                        output.source = TurnSource::Inference;
+                    }
+                }
+            }
+
+            // A greeting opening a combined reply comes first
+            if let Some(opener) = opener {
+                output.response = Some(match output.response.take() {
+                    Some(response) => format!("{} {}", opener, response),
+                    None => {
+                        output.source = TurnSource::Behavior;
+                        opener
+                    }
+                });
+            }
+        }
+
+        output.emotions = self
+            .change_emotions("decay", |emotional_state| {
+                self.affect().decay(emotional_state, None);
+                emotional_state.as_vector()
+            })
+            .await;
+
+        self.set_state(AgentState::Idle)?;
+
+        if let Some(response) = &output.response {
+            self.topics.write().await.observe_response(response);
+            self.trigger_event(AgentEvent::Response, response).await;
+        }
+
+        Ok(output)
+    }
+
+    /// Move the agent to a state, emitting [`AgentEvent::StateChange`]
+    ///
+    /// # Returns
+    ///
+    /// An error if the lifecycle does not allow the transition
+    fn set_state(&self, state: AgentState) -> Result<()> {
+        if let Some(change) = self.state.transition(state)? {
+            self.emit_state_change(change);
+        }
+        Ok(())
+    }
+
+    /// Move the agent to a state if it is still handling an input
+    ///
+    /// # Returns
+    ///
+    /// Whether the state changed
+    fn settle_state(&self, state: AgentState) -> bool {
+        match self.state.transition_if_active(state) {
+            Some(change) => {
+                self.emit_state_change(change);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn emit_state_change(&self, change: StateChange) {
+        log::debug!("Agent {} state: {} -> {}", self.name, change.from, change.to);
+        self.call_callbacks(AgentEvent::StateChange.as_str(), &change.to_json());
+    }
+
+    /// Register a callback for agent events using typed events
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - Event type to trigger the callback
+    /// * `callback` - Callback function
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// agent.on_event(AgentEvent::Start, |agent, data| {
+    ///     println!("Agent {} started: {}", agent.name(), data);
+    /// });
+    /// ```
+    pub fn on_event<F>(&self, event: AgentEvent, callback: F)
+    where
+        F: Fn(&Agent, &str) + Send + Sync + 'static,
+    {
+        self.register_callback(event.as_str(), callback);
+    }
+
+    /// Register a callback for agent events (deprecated, use on_event)
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - Event name to trigger the callback
+    /// * `callback` - Callback function
+    #[deprecated(since = "0.1.5", note = "Use on_event() with AgentEvent enum instead")]
+    pub fn register_callback<F>(&self, event: &str, callback: F)
+    where
+        F: Fn(&Agent, &str) + Send + Sync + 'static,
+    {
+        // Lock the callbacks mutex, recovering from poison if necessary
+        let mut callbacks = self.callbacks.lock().unwrap_or_else(|poisoned| {
+            log::warn!("Callback mutex was poisoned, recovering");
+            poisoned.into_inner()
+        });
+        let event_callbacks = callbacks.entry(event.to_string()).or_insert(Vec::new());
+        event_callbacks.push(CallbackWrapper::new(Box::new(callback)));
+    }
+
+    /// Await a response, emitting a filler line if it misses the latency target
+    async fn within_latency_target<T>(
+        &self,
+        generation: impl std::future::Future<Output = Result<T>>,
+        context: &AgentContext,
+    ) -> Result<T> {
+        let latency = &self.config.latency;
+        if !latency.enabled {
+            return generation.await;
+        }
+
+        tokio::pin!(generation);
+        tokio::select! {
+            result = &mut generation => result,
+            _ = timer::sleep(Duration::from_millis(latency.filler_after_ms)) => {
+                let emotions = self.emotional_state.read().await.clone();
+                let filler = latency.filler(&emotions, context);
+                self.trigger_event(AgentEvent::Filler, &filler).await;
+                generation.await
+            }
+        }
+    }
+
+    /// Fail inference still running at the deadline armed on entering `Generating`
+    async fn within_generation_deadline<T>(
+        &self,
+        generation: impl std::future::Future<Output = Result<T>>,
+    ) -> Result<T> {
+        let Some(deadline) = self.state.generation_deadline() else {
+            return generation.await;
+        };
+        timer::timeout_at(deadline, generation).await.unwrap_or_else(|_| {
+            Err(crate::OxydeError::InferenceError(format!(
+                "Generation timed out after {} ms",
+                self.config.supervisor.generation_timeout_ms
+            )))
+        })
+    }
+
+    /// Check whether a behavior matches the input
+    ///
+    /// Behaviors not matching the primary intent may match a secondary
+    /// intent of at least `intents.secondary_weight` when
+    /// `intents.match_secondary` is set.
+    ///
+    /// # Returns
+    ///
+    /// The intent the behavior matched, or `None`
+    async fn match_behavior(&self, behavior: &dyn Behavior, intent: &Intent) -> Option<Intent> {
+        if behavior.matches_intent(intent).await {
+            return Some(intent.clone());
+        }
+        if !self.config.intents.match_secondary {
+            return None;
+        }
+        let min_weight = self.config.intents.secondary_weight;
+        for secondary in intent.secondary.iter().filter(|secondary| secondary.weight >= min_weight) {
+            let focused = intent.focus(secondary);
+            if behavior.matches_intent(&focused).await {
+                return Some(focused);
+            }
+        }
+        None
+    }
+
+    /// Whether a greeting should open the reply to the rest of the input
+    /// rather than be the whole reply, with `intents.combo` set
+    fn opens_combo(&self, matched: &Intent, intent: &Intent) -> bool {
+        let min_weight = self.config.intents.secondary_weight;
+        self.config.intents.combo
+            && matched.intent_type == IntentType::Greeting
+            && intent
+                .ranked()
+                .iter()
+                .enumerate()
+                .any(|(rank, other)| other.intent_type != IntentType::Greeting && (rank == 0 || other.weight >= min_weight))
+    }
+
+    /// Execute a selected behavior within its timeout and record the outcome
+    ///
+    /// A behavior that overruns is abandoned, emits
+    /// [`AgentEvent::BehaviorTimeout`] and yields its timeout result, or no
+    /// result so lower-priority behaviors run.
+    async fn execute_behavior(
+        &self,
+        behavior: &dyn Behavior,
+        intent: &Intent,
+        context: &AgentContext,
+        report: &mut SelectionReport,
+        index: usize,
+    ) -> Result<BehaviorResult> {
+        let limit = behavior.timeout().or_else(|| {
+            let timeout_ms = self.config.supervisor.behavior_timeout_ms;
+            (timeout_ms > 0).then(|| Duration::from_millis(timeout_ms))
+        });
+        let execution = behavior
+            .execute(intent, context)
+            .instrument(tracing::info_span!("agent.behavior", priority = behavior.priority()));
+        let Some(limit) = limit else {
+            let result = execution.await;
+            report.record_result(index, &result);
+            return result;
+        };
+
+        match timer::timeout(limit, execution).await {
+            Ok(result) => {
+                report.record_result(index, &result);
+                result
+            }
+            Err(_) => {
+                let name = report.candidate(index).map(|candidate| candidate.name.clone()).unwrap_or_default();
+                let timeout_ms = limit.as_millis() as u64;
+                log::warn!("Agent {} abandoned behavior {} after {} ms", self.name, name, timeout_ms);
+                let data = serde_json::json!({ "behavior": name, "timeout_ms": timeout_ms });
+                self.trigger_event(AgentEvent::BehaviorTimeout, &data.to_string()).await;
+
+                let fallback = behavior.timeout_result().unwrap_or(BehaviorResult::None);
+                report.record_timeout(index, timeout_ms, &fallback);
+                Ok(fallback)
+            }
+        }
+    }
+
+    /// Trigger a callback for a typed event, queueing lines the agent says
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - Event type
+    /// * `data` - Event data
+    async fn trigger_event(&self, event: AgentEvent, data: &str) {
+        match event {
+            AgentEvent::Response => {
+                self.dialogue.push(DialogueLineKind::Response, data);
+            }
+            AgentEvent::Filler => {
+                self.dialogue.push(DialogueLineKind::Filler, data);
+            }
+            _ => {}
+        }
+        self.trigger_callback(event.as_str(), data).await;
+    }
+
+    /// Trigger a callback for an event
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - Event name
+    /// * `data` - Event data
+    async fn trigger_callback(&self, event: &str, data: &str) {
+        self.call_callbacks(event, data);
+    }
+
+    /// Call the callbacks registered for an event
+    fn call_callbacks(&self, event: &str, data: &str) {
+        // Lock the callbacks mutex, recovering from poison if necessary
+        let callbacks = self.callbacks.lock().unwrap_or_else(|poisoned| {
+            log::warn!("Callback mutex was poisoned during trigger, recovering");
+            poisoned.into_inner()
+        });
+        if let Some(event_callbacks) = callbacks.get(event) {
+            for callback in event_callbacks {
+                callback.call(self, data);
+            }
+        }
+    }
+
+
+    /// Create a new agent with the same configuration but new state
+    ///
+    /// This is a simplified clone method that creates a new agent with the same
+    /// configuration but with fresh state. This is useful for creating copies
+    /// of agents for engine bindings. The configuration, inference engine and
+    /// compiled patterns are shared with this agent rather than rebuilt.
+    /// The copy's memories are not persisted, leaving this agent's store to
+    /// this agent.
+    pub fn clone_for_binding(&self) -> Self {
+        let memory = MemoryConfig {
+            persistence: false,
+            ..self.config.memory.clone()
+        };
+        Self::from_parts(&self.shared_parts(), Some(self.name.clone()), memory)
+    }
+
+    // ==================== Memory System Wrapper Methods ====================
+    // These methods provide direct access to the memory system for FFI bindings
+
+    /// Add a memory to the agent's memory system
+    pub async fn add_memory(
+        &self,
+        category: MemoryCategory,
+        content: &str,
+        importance: f64,
+        tags: Option<Vec<String>>,
+    ) -> Result<()> {
+        self.remember(Memory::new(category, content, importance, tags)).await
+    }
+
+    /// Add a memory with emotional context to the agent's memory system
+    pub async fn add_emotional_memory(
+        &self,
+        category: MemoryCategory,
+        content: &str,
+        importance: f64,
+        valence: f64,
+        intensity: f64,
+        tags: Option<Vec<String>>,
+    ) -> Result<()> {
+        self.remember(Memory::new_emotional(
+            category,
+            content,
+            importance,
+            valence,
+            intensity,
+            tags
+        )).await
+    }
+
+    /// Add a memory that must not be quoted in dialogue
+    ///
+    /// Private memories guide the agent's goals; secret memories only color
+    /// its mood. See [`MemoryVisibility`].
+    pub async fn add_hidden_memory(
+        &self,
+        memory: Memory,
+        visibility: MemoryVisibility,
+    ) -> Result<()> {
+        self.remember(memory.with_visibility(visibility)).await
+    }
+
+    /// Teach the agent a fact about the world
+    ///
+    /// Stores the statement as a permanent semantic memory of the highest
+    /// importance, embedded when the memory system uses embeddings, so it is
+    /// recalled by the next relevant input. Teaching a statement under a key
+    /// that already holds one replaces it, so quest scripts can track changing
+    /// world state ("The north bridge has collapsed.", later "The north bridge
+    /// has been rebuilt.").
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Name of the fact, such as "north_bridge"
+    /// * `statement` - The fact, phrased as the agent should know it
+    /// * `source` - Who or what taught the fact, such as "quest_script";
+    ///   stored as a `source:` tag
+    ///
+    /// # Returns
+    ///
+    /// The stored memory
+    pub async fn learn_fact(&self, key: &str, statement: &str, source: Option<&str>) -> Result<Memory> {
+        if key.trim().is_empty() {
+            return Err(crate::OxydeError::MemoryError("Fact key must not be empty".to_string()));
+        }
+        let tag = fact_tag(key);
+        let mut tags = vec![tag.clone()];
+        tags.extend(source.map(|source| format!("source:{}", source)));
+
+        self.forget_logged(self.memory.forget_fact(key)).await;
+        self.remember(Memory::new(MemoryCategory::Semantic, statement, 1.0, Some(tags))).await?;
+        self.memory
+            .get_by_tag(&tag)
+            .await
+            .into_iter()
+            .next()
+            .ok_or_else(|| crate::OxydeError::MemoryError(format!("Fact {} was not stored", key)))
+    }
+
+    /// Teach the agent the lore files under a directory
+    ///
+    /// Each chunk read by [`crate::knowledge::ingest_dir`] is stored as a
+    /// semantic memory tagged with its source file, such as
+    /// `source:factions/guild.md`, just as the configuration's knowledge
+    /// entries are stored when the agent starts.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - Directory of Markdown and text lore files
+    /// * `options` - Size limits and the extensions to read
+    ///
+    /// # Returns
+    ///
+    /// The ingestion report, listing skipped files and dropped duplicates
+    pub async fn ingest_knowledge(&self, dir: &Path, options: &IngestOptions) -> Result<IngestionReport> {
+        let ingestion = knowledge::ingest_dir(dir, options)?;
+        for chunk in &ingestion.chunks {
+            self.remember(chunk.to_memory()).await?;
+        }
+        log::info!(
+            "Agent {} ingested {} knowledge chunks from {}",
+            self.name,
+            ingestion.report.chunks,
+            dir.display()
+        );
+        Ok(ingestion.report)
+    }
+
+    /// Forget the fact learned under a key
+    ///
+    /// # Returns
+    ///
+    /// Whether the agent knew a fact under the key
+    pub async fn forget_fact(&self, key: &str) -> bool {
+        self.forget_logged(self.memory.forget_fact(key)).await > 0
+    }
+
+    /// Change whether a stored memory may be quoted in dialogue
+    pub async fn set_memory_visibility(&self, memory_id: &str, visibility: MemoryVisibility) -> Result<()> {
+        self.memory.set_visibility(memory_id, visibility).await?;
+        self.record(StateEvent::MemoryVisibilityChanged {
+            id: memory_id.to_string(),
+            visibility,
+        });
+        Ok(())
+    }
+
+    /// Get the total number of memories stored
+    pub async fn memory_count(&self) -> usize {
+        self.memory.count().await
+    }
+
+    /// Get distributions of the agent's memories for tuning memory settings
+    pub async fn memory_stats(&self) -> MemoryStats {
+        self.memory.stats().await
+    }
+
+    /// Save the agent's memories and memory configuration to a JSON session file
+    pub async fn save_memory_session<P: AsRef<std::path::Path>>(&self, path: P) -> Result<()> {
+        self.memory.session().await.save(path)
+    }
+
+    /// Clear all non-permanent memories
+    pub async fn clear_memories(&self) -> usize {
+        self.forget_logged(self.memory.clear()).await
+    }
+
+    /// Get all memories of a specific category
+    pub async fn get_memories_by_category(&self, category: MemoryCategory) -> Vec<Memory> {
+        self.memory.get_by_category(category).await
+    }
+
+    /// Retrieve memories relevant to a query, including those of attached
+    /// shared memory pools
+    pub async fn retrieve_relevant_memories(&self, query: &str, limit: usize) -> Result<Vec<Memory>> {
+        self.recall(query, limit, None).await
+    }
+
+    /// Answer a question about what the agent remembers
+    ///
+    /// Retrieves the memories relevant to the question, including those of
+    /// attached shared memory pools, and asks the model to answer from them
+    /// alone. Meant for quest scripts and engine-side logic; the answer is
+    /// never shown to the player as dialogue. Only memories the agent may
+    /// quote are used: private and secret memories, and knowledge its
+    /// capabilities withhold, are never answered from.
+    ///
+    /// # Arguments
+    ///
+    /// * `question` - Question such as "what does this NPC know about the mayor?"
+    ///
+    /// # Returns
+    ///
+    /// A concise answer and the IDs of the memories supporting it
+    pub async fn ask_memory(&self, question: &str) -> Result<MemoryAnswer> {
+        let memories = self.recall(question, ASK_MEMORY_LIMIT, None).await?;
+        let memories = self.capabilities.filter_memories(memories, &self.context_snapshot());
+        // The guidance for private and secret memories only shapes dialogue
+        let (memories, _) = crate::prompt::partition_memories(&memories);
+        if memories.is_empty() {
+            return Ok(MemoryAnswer::unknown(&self.name));
+        }
+        let (system_prompt, input) = crate::memory_query::prompt(&self.name, question, &memories);
+        let text = self.inference.complete(&system_prompt, &input, ASK_MEMORY_MAX_TOKENS).await?;
+        Ok(crate::memory_query::parse_answer(&text, &memories))
+    }
+
+    /// Retrieve every memory that mentions a person, place or item, oldest first
+    pub async fn related_memories(&self, entity: &str) -> Vec<Memory> {
+        self.memory.related_to(entity).await
+    }
+
+    /// Forget a specific memory by ID
+    pub async fn forget_memory(&self, memory_id: &str) -> Result<()> {
+        self.forget_logged(self.memory.forget(memory_id)).await
+    }
+
+    /// Forget all memories of a specific category
+    pub async fn forget_memories_by_category(&self, category: MemoryCategory) -> usize {
+        self.forget_logged(self.memory.forget_by_category(category)).await
+    }
+
+    /// Check if a memory exists by ID
+    pub async fn has_memory(&self, memory_id: &str) -> bool {
+        self.memory.get(memory_id).await.is_some()
+    }
+
+    /// Get a specific memory by ID
+    pub async fn get_memory(&self, memory_id: &str) -> Option<Memory> {
+        self.memory.get(memory_id).await
+    }
+}
+
+impl std::fmt::Debug for Agent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let callbacks_count = self.callbacks.lock()
+            .map(|cb| cb.len())
+            .unwrap_or(0);
+
+        f.debug_struct("Agent")
+            .field("id", &self.id)
+            .field("name", &self.name)
+            .field("config", &self.config)
+            // Don't debug the behaviors or callbacks directly as they don't implement Debug
+            .field("behaviors_count", &format!("<{} behaviors>", self.behaviors.try_read().map(|b| b.len()).unwrap_or(0)))
+            .field("callbacks_count", &format!("<{} callback types>", callbacks_count))
+            .finish()
+    }
+}
+
+/// AgentBuilder for fluent construction of Agents
+#[derive(Default)]
+pub struct AgentBuilder {
+    config: Option<AgentConfig>,
+    behaviors: Vec<Box<dyn Behavior>>,
+    affect: Option<Box<dyn AffectModel>>,
+}
+
+impl AgentBuilder {
+    /// Create a new AgentBuilder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the agent configuration
+    pub fn with_config(mut self, config: AgentConfig) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Add a behavior to the agent
+    pub fn with_behavior<B: Behavior + 'static>(mut self, behavior: B) -> Self {
+        self.behaviors.push(Box::new(behavior));
+        self
+    }
+
+    /// Use a custom affect model instead of the configured one
+    pub fn with_affect_model<M: AffectModel + 'static>(mut self, model: M) -> Self {
+        self.affect = Some(Box::new(model));
+        self
+    }
+
+    /// Build the agent
+    pub async fn build(self) -> Result<Agent> {
+        let config = self.config.ok_or_else(|| {
+            crate::OxydeError::ConfigurationError("Agent configuration is required".to_string())
+        })?;
+
+        // Validate the configuration before building
+        config.validate()?;
+
+        let agent = Agent::new(config);
+
+        // Add all behaviors provided via the builder
+        for behavior in self.behaviors {
+            agent.add_boxed_behavior(behavior).await;
+        }
+        if let Some(model) = self.affect {
+            agent.set_affect_model(model).await;
+        }
+
+        Ok(agent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AgentPersonality, InferenceConfig, MemoryConfig};
+
+    #[tokio::test]
+    async fn test_agent_creation() {
+        let config = AgentConfig {
+            agent: AgentPersonality {
+                name: "Test Agent".to_string(),
+                role: "Tester".to_string(),
+                backstory: vec!["A test agent".to_string()],
+                knowledge: vec!["Testing knowledge".to_string()],
+            },
+            ..Default::default()
+        };
+
+        let agent = Agent::new(config);
+        assert_eq!(agent.name(), "Test Agent");
+
+        agent.start().await.unwrap();
+        assert_eq!(agent.state().await, AgentState::Idle);
+
+        agent.stop().await.unwrap();
+        assert_eq!(agent.state().await, AgentState::Stopped);
+    }
+
+
+    #[tokio::test]
+    
+    async fn test_agent_builder_with_behaviors() {
+        use crate::oxyde_game::behavior::GreetingBehavior;
+
+        let config = AgentConfig {
+            agent: AgentPersonality {
+                name: "Builder Test".to_string(),
+                role: "Tester".to_string(),
+                backstory: vec!["Built with builder".to_string()],
+                knowledge: vec![],
+            },
+            ..Default::default()
+        };
+
+        // Create agent with builder and add behaviors
+        let greeting1 = GreetingBehavior::new("Hello!");
+        let greeting2 = GreetingBehavior::new("Greetings!");
+
+        let agent = AgentBuilder::new()
+            .with_config(config)
+            .with_behavior(greeting1)
+            .with_behavior(greeting2)
+            .build()
+            .await
+            .unwrap();
+
+        assert_eq!(agent.name(), "Builder Test");
+
+        // Verify behaviors were added (check the count)
+        let behaviors = agent.behaviors.read().await;
+        assert_eq!(behaviors.len(), 2, "Builder should add all provided behaviors");
+    }
+
+    #[tokio::test]
+    async fn test_agent_builder_without_config_fails() {
+        use crate::oxyde_game::behavior::GreetingBehavior;
+
+        let greeting = GreetingBehavior::new("Hello!");
+
+        // Attempt to build without config should fail
+        let result = AgentBuilder::new()
+            .with_behavior(greeting)
+            .build()
+            .await;
+
+        assert!(result.is_err(), "Building without config should fail");
+        if let Err(crate::OxydeError::ConfigurationError(msg)) = result {
+            assert!(msg.contains("required"), "Error should mention config is required");
+        } else {
+            panic!("Expected ConfigurationError");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_content_moderation() {
+        let config = AgentConfig {
+            agent: AgentPersonality {
+                name: "Test Agent".to_string(),
+                role: "Tester".to_string(),
+                backstory: vec!["A test agent".to_string()],
+                knowledge: vec!["Testing knowledge".to_string()],
+            },
+            moderation: crate::config::ModerationConfig {
+                enabled: true,
+                response_message: "Sorry, I can't respond to that.".to_string(),
+                use_cloud_moderation: false,
+                cloud_moderation_api_key: None,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let agent = Agent::new(config);
+        agent.start().await.unwrap();
+
+        // Test that bad words trigger moderation response
+        let response = agent.process_input("Fuck you").await.unwrap();
+        assert_eq!(response, "Sorry, I can't respond to that.");
+    }
+
+    #[tokio::test]
+    async fn test_flagged_output_is_regenerated_or_replaced() {
+        let make_agent = |max_output_retries| {
+            let config = AgentConfig {
+                agent: AgentPersonality {
+                    name: "Test Agent".to_string(),
+                    role: "Tester".to_string(),
+                    backstory: vec![],
+                    knowledge: vec![],
+                },
+                inference: InferenceConfig {
+                    use_local: true,
+                    local_model_path: Some("models/test.bin".to_string()),
+                    ..InferenceConfig::default()
+                },
+                moderation: crate::config::ModerationConfig {
+                    enabled: true,
+                    max_output_retries,
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            let agent = Agent::new(config);
+            let flagged = Arc::new(Mutex::new(Vec::new()));
+            let sink = flagged.clone();
+            agent.on_event(AgentEvent::OutputFlagged, move |_, text| {
+                sink.lock().unwrap().push(text.to_string());
+            });
+            (agent, flagged)
+        };
+
+        // The simulated local model answers cleanly on the retry
+        let (agent, flagged) = make_agent(1);
+        let response = agent
+            .moderate_output("hello", "Get lost, fuck off".to_string(), &[], &AgentContext::new(), &RequestOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(response, "This is a simulated response to: hello");
+        assert_eq!(flagged.lock().unwrap().as_slice(), ["Get lost, fuck off"]);
+
+        // Without retries the moderation message is used
+        let (agent, flagged) = make_agent(0);
+        let response = agent
+            .moderate_output("hello", "Get lost, fuck off".to_string(), &[], &AgentContext::new(), &RequestOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(response, "Sorry, I can't respond to that.");
+        assert_eq!(flagged.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_step_is_deterministic() {
+        use crate::oxyde_game::behavior::DialogueBehavior;
+
+        let make_agent = || {
+            let config = AgentConfig {
+                agent: AgentPersonality {
+                    name: "Turn Agent".to_string(),
+                    role: "Tester".to_string(),
+                    backstory: vec![],
+                    knowledge: vec![],
+                },
+                ..Default::default()
+            };
+            let agent = Agent::new(config);
+            let defaults = (0..8).map(|i| format!("Reply {}", i)).collect();
+            futures::executor::block_on(
+                agent.add_behavior(DialogueBehavior::new(HashMap::new(), defaults)),
+            );
+            agent
+        };
+
+        let first = make_agent();
+        let second = make_agent();
+        for turn in 0..5 {
+            let a = first.step(TurnInput::new(turn, "What do you sell?").with_seed(42)).unwrap();
+            let b = second.step(TurnInput::new(turn, "What do you sell?").with_seed(42)).unwrap();
+            assert_eq!(a.response, b.response);
+            assert_eq!(a.source, TurnSource::Behavior);
+        }
+        assert_eq!(first.state().await, AgentState::Idle);
+
+        let mut cached = HashMap::new();
+        cached.insert("hello?".to_string(), "Cached reply".to_string());
+        let silent = Agent::new((*first.config).clone());
+        let output = silent
+            .step(TurnInput::new(0, " Hello? ").with_inference(TurnInference::Cached(cached)))
+            .unwrap();
+        assert_eq!(output.response.as_deref(), Some("Cached reply"));
+        assert_eq!(output.source, TurnSource::Cache);
+    }
+
+    #[derive(Debug)]
+    struct PanickingBehavior;
+
+    #[async_trait::async_trait]
+    impl Behavior for PanickingBehavior {
+        async fn matches_intent(&self, _intent: &Intent) -> bool {
+            true
+        }
+
+        async fn execute(&self, _intent: &Intent, _context: &AgentContext) -> Result<BehaviorResult> {
+            panic!("behavior exploded");
+        }
+    }
+
+    #[derive(Debug)]
+    struct StallingBehavior;
+
+    #[async_trait::async_trait]
+    impl Behavior for StallingBehavior {
+        async fn matches_intent(&self, _intent: &Intent) -> bool {
+            true
+        }
+
+        async fn execute(&self, _intent: &Intent, _context: &AgentContext) -> Result<BehaviorResult> {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok(BehaviorResult::Response("Finally!".to_string()))
+        }
+    }
+
+    #[derive(Debug)]
+    struct WavingBehavior;
+
+    #[async_trait::async_trait]
+    impl Behavior for WavingBehavior {
+        async fn matches_intent(&self, _intent: &Intent) -> bool {
+            true
+        }
+
+        async fn execute(&self, _intent: &Intent, _context: &AgentContext) -> Result<BehaviorResult> {
+            Ok(BehaviorResult::Action("wave".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sampling_follows_the_behavior_that_ran() {
+        let yaml = r#"
+agent:
+  name: Tam
+  role: Ferryman
+  backstory: []
+  knowledge: []
+memory: {}
+inference:
+  provider: mock
+  sampling:
+    enabled: true
+    behaviors:
+      WavingBehavior:
+        calm:
+          temperature: 0.1
+        aroused:
+          temperature: 0.1
+"#;
+        let config: AgentConfig = serde_yaml::from_str(yaml).unwrap();
+        let waving = Agent::new(config.clone());
+        waving.add_behavior(WavingBehavior).await;
+        waving.process_input("Ahoy!").await.unwrap();
+        assert_eq!(waving.mock_provider().requests().pop().unwrap().temperature, 0.1);
+
+        // Naming a behavior in context does not select its profiles
+        let still = Agent::new(config);
+        still
+            .update_context(AgentContext::from([("active_behavior".to_string(), serde_json::json!("WavingBehavior"))]))
+            .await;
+        still.process_input("Ahoy!").await.unwrap();
+        assert_eq!(still.mock_provider().requests().pop().unwrap().temperature, 0.5);
+    }
+
+    #[tokio::test]
+    async fn test_stalled_behaviors_time_out_to_their_fallback() {
+        use crate::oxyde_game::behavior::TimedBehavior;
+
+        let yaml = r#"
+agent:
+  name: Pell
+  role: Courier
+  backstory: []
+  knowledge: []
+memory: {}
+inference:
+  provider: mock
+supervisor:
+  behavior_timeout_ms: 20
+"#;
+        let config: AgentConfig = serde_yaml::from_str(yaml).unwrap();
+        let agent = Agent::new(config.clone());
+        agent.add_behavior(StallingBehavior).await;
+
+        let timeouts = Arc::new(Mutex::new(Vec::new()));
+        let sink = timeouts.clone();
+        agent.on_event(AgentEvent::BehaviorTimeout, move |_, data| {
+            sink.lock().unwrap().push(serde_json::from_str::<serde_json::Value>(data).unwrap());
+        });
+
+        // Without a fallback the agent moves on to inference
+        let response = agent.process_input("Any letters?").await.unwrap();
+        assert_eq!(response, "This is a mock response to: Any letters?");
+        assert_eq!(
+            *timeouts.lock().unwrap(),
+            [serde_json::json!({"behavior": "StallingBehavior", "timeout_ms": 20})]
+        );
+        let report = agent.explain_last_selection().await.unwrap();
+        assert!(matches!(
+            report.candidate(0).unwrap().outcome,
+            Some(crate::oxyde_game::behavior::CandidateOutcome::TimedOut { timeout_ms: 20, .. })
+        ));
+
+        let agent = Agent::new(config);
+        let fallback = BehaviorResult::Response("One moment, the satchel is stuck.".to_string());
+        let timed = TimedBehavior::new(Box::new(StallingBehavior), Some(Duration::from_millis(10)), Some(fallback));
+        agent.add_behavior(timed).await;
+        let response = agent.process_input("Any letters?").await.unwrap();
+        assert_eq!(response, "One moment, the satchel is stuck.");
+    }
+
+    #[tokio::test]
+    async fn test_supervisor_recovers_from_panic() {
+        let config = AgentConfig {
+            agent: AgentPersonality {
+                name: "Fragile".to_string(),
+                role: "Tester".to_string(),
+                backstory: vec![],
+                knowledge: vec![],
+            },
+            supervisor: crate::config::SupervisorConfig {
+                request_timeout_ms: 1000,
+                fallback_response: Some("Hmm?".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let agent = Agent::new(config);
+        agent.add_behavior(PanickingBehavior).await;
+        agent.start().await.unwrap();
+
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let sink = errors.clone();
+        agent.on_event(AgentEvent::Error, move |_, data| {
+            sink.lock().unwrap().push(data.to_string());
+        });
+
+        let response = agent.process_input("Hello there").await.unwrap();
+        assert_eq!(response, "Hmm?");
+        assert_eq!(agent.state().await, AgentState::Idle);
+
+        let errors = errors.lock().unwrap();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("behavior exploded"));
+    }
+
+    #[tokio::test]
+    async fn test_schedule_controls_availability_and_behaviors() {
+        use crate::oxyde_game::behavior::DialogueBehavior;
+        use crate::oxyde_game::schedule::{FixedClock, ScheduleBlock, ScheduleConfig, ScheduledBehavior};
+
+        let config = AgentConfig {
+            agent: AgentPersonality {
+                name: "Smith".to_string(),
+                role: "Blacksmith".to_string(),
+                backstory: vec![],
+                knowledge: vec![],
+            },
+            schedule: ScheduleConfig {
+                blocks: vec![
+                    ScheduleBlock::new("smithing", 9.0, 17.0),
+                    ScheduleBlock::new("sleeping", 22.0, 6.0).unavailable("Zzz..."),
+                ],
+            },
+            ..Default::default()
+        };
+
+        let agent = Agent::new(config);
+        let shop = DialogueBehavior::new(HashMap::new(), vec!["Need a blade?".to_string()]);
+        agent.add_behavior(ScheduledBehavior::new(shop, &["smithing"])).await;
+        agent.start().await.unwrap();
+
+        agent.set_clock(Arc::new(FixedClock(23.0)));
+        assert_eq!(agent.process_input("What do you sell?").await.unwrap(), "Zzz...");
+
+        agent.set_clock(Arc::new(FixedClock(10.0)));
+        assert_eq!(agent.current_activity().await.unwrap().activity, "smithing");
+        assert_eq!(agent.process_input("What do you sell?").await.unwrap(), "Need a blade?");
+    }
+
+    #[tokio::test]
+    async fn test_step_checks_the_schedule_against_stored_context() {
+        use crate::oxyde_game::schedule::{ScheduleBlock, ScheduleConfig};
+
+        let config = AgentConfig {
+            agent: AgentPersonality {
+                name: "Smith".to_string(),
+                role: "Blacksmith".to_string(),
+                backstory: vec![],
+                knowledge: vec![],
+            },
+            schedule: ScheduleConfig {
+                blocks: vec![ScheduleBlock::new("sleeping", 22.0, 6.0).unavailable("Zzz...")],
+            },
+            ..Default::default()
+        };
+        let agent = Agent::new(config);
+        agent
+            .update_context(AgentContext::from([("game_hour".to_string(), serde_json::json!(23.0))]))
+            .await;
+
+        let output = agent.step(TurnInput::new(0, "What do you sell?")).unwrap();
+        assert_eq!(output.response.as_deref(), Some("Zzz..."));
+        assert_eq!(output.source, TurnSource::Schedule);
+    }
+
+    #[tokio::test]
+    async fn test_game_clock_drives_hours_and_memory_timestamps() {
+        use crate::oxyde_game::behavior::DialogueBehavior;
+        use crate::oxyde_game::schedule::{GameClock, ScheduledBehavior};
+
+        let config = AgentConfig {
+            agent: AgentPersonality {
+                name: "Innkeeper".to_string(),
+                role: "Barkeep".to_string(),
+                backstory: vec![],
+                knowledge: vec![],
+            },
+            inference: InferenceConfig {
+                use_local: true,
+                local_model_path: Some("models/test.bin".to_string()),
+                ..InferenceConfig::default()
+            },
+            ..Default::default()
+        };
+
+        let agent = Agent::new(config);
+        let last_call = DialogueBehavior::new(HashMap::new(), vec!["Last call!".to_string()]);
+        agent.add_behavior(ScheduledBehavior::between(last_call, 22.0, 2.0)).await;
+
+        let clock = Arc::new(GameClock::new(3, 20.0));
+        agent.set_clock(clock.clone());
+        assert_eq!(agent.process_input("Another ale").await.unwrap(), "This is a simulated response to: Another ale");
+
+        clock.advance(3.0);
+        assert_eq!(agent.process_input("One more round").await.unwrap(), "Last call!");
+
+        let memories = agent.get_memories_by_category(MemoryCategory::Episodic).await;
+        let mut stamps: Vec<GameTime> = memories.iter().filter_map(|m| m.game_time).collect();
+        stamps.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(stamps, vec![GameTime::new(3, 20.0), GameTime::new(3, 23.0)]);
+    }
+
+    #[tokio::test]
+    async fn test_offline_fallback_when_inference_unavailable() {
+        use crate::fallback::{FallbackTemplate, OfflineFallbackConfig};
+
+        let config = AgentConfig {
+            agent: AgentPersonality {
+                name: "Marla".to_string(),
+                role: "Innkeeper".to_string(),
+                backstory: vec![],
+                knowledge: vec![],
+            },
+            inference: InferenceConfig {
+                use_local: true,
+                local_model_path: None,
+                ..InferenceConfig::default()
+            },
+            offline_fallback: OfflineFallbackConfig {
+                enabled: true,
+                templates: vec![FallbackTemplate::for_intent(IntentType::Question, &["{name} shrugs."])],
+                use_builtin: true,
+            },
+            ..Default::default()
+        };
+
+        let agent = Agent::new(config);
+        agent.start().await.unwrap();
+        assert_eq!(agent.process_input("Where is the mill?").await.unwrap(), "Marla shrugs.");
+    }
+
+    #[tokio::test]
+    async fn test_persuasion_gates_behaviors() {
+        use crate::oxyde_game::behavior::{CandidateStatus, GreetingBehavior};
+        use crate::oxyde_game::persuasion::PersuasionGatedBehavior;
+
+        let config = AgentConfig {
+            agent: AgentPersonality {
+                name: "Gatekeeper".to_string(),
+                role: "Guard".to_string(),
+                backstory: vec![],
+                knowledge: vec![],
+            },
+            inference: InferenceConfig {
+                use_local: true,
+                local_model_path: Some("models/test.bin".to_string()),
+                ..InferenceConfig::default()
+            },
+            ..Default::default()
+        };
+        let agent = Agent::new(config);
+        agent
+            .add_behavior(PersuasionGatedBehavior::new(GreetingBehavior::new("Fine, the gate is open."), 15))
+            .await;
+        agent
+            .update_context(HashMap::from([("player_distance".to_string(), serde_json::json!(1.0))]))
+            .await;
+
+        let response = agent.process_input_with_context("hello", PersuasionContext::new(16, 1)).await.unwrap();
+        assert_eq!(response, "Fine, the gate is open.");
+
+        let response = agent.process_input_with_context("hello", PersuasionContext::new(5, 1)).await.unwrap();
+        assert_eq!(response, "This is a simulated response to: hello");
+        let report = agent.explain_last_selection().await.unwrap();
+        assert_eq!(report.candidate(0).unwrap().status, CandidateStatus::PersuasionFailed);
+        assert!(!report.persuasion.unwrap().outcome.succeeded());
+
+        let response = agent.process_input("hello").await.unwrap();
+        assert_eq!(response, "This is a simulated response to: hello");
+    }
+
+    #[tokio::test]
+    async fn test_redacts_pii_in_memories() {
+        let config = AgentConfig {
+            agent: AgentPersonality {
+                name: "Clerk".to_string(),
+                role: "Archivist".to_string(),
+                backstory: vec![],
+                knowledge: vec![],
+            },
+            inference: InferenceConfig {
+                use_local: true,
+                local_model_path: Some("models/test.bin".to_string()),
+                ..InferenceConfig::default()
+            },
+            redaction: crate::redaction::RedactionConfig {
+                enabled: true,
+                names: vec!["Alex Smith".to_string()],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let agent = Agent::new(config);
+
+        let response = agent.process_input("I am Alex Smith, write to alex@example.com").await.unwrap();
+        assert!(response.contains("alex@example.com"));
+
+        let memories = agent.get_memories_by_category(MemoryCategory::Episodic).await;
+        assert_eq!(memories.len(), 1);
+        assert_eq!(memories[0].content, "I am [NAME_1], write to [EMAIL_1]");
+    }
+
+    #[tokio::test]
+    async fn test_step_redacts_pii_in_memories() {
+        let config = AgentConfig {
+            agent: AgentPersonality {
+                name: "Clerk".to_string(),
+                role: "Archivist".to_string(),
+                backstory: vec![],
+                knowledge: vec![],
+            },
+            inference: InferenceConfig {
+                use_local: true,
+                local_model_path: Some("models/test.bin".to_string()),
+                ..InferenceConfig::default()
+            },
+            redaction: crate::redaction::RedactionConfig {
+                enabled: true,
+                names: vec!["Alex Smith".to_string()],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let agent = Agent::new(config);
+
+        agent
+            .step(TurnInput::new(0, "I am Alex Smith, write to alex@example.com").with_inference(TurnInference::Local))
+            .unwrap();
+
+        let memories = agent.get_memories_by_category(MemoryCategory::Episodic).await;
+        assert_eq!(memories.len(), 1);
+        assert_eq!(memories[0].content, "I am [NAME_1], write to [EMAIL_1]");
+    }
+
+    #[test]
+    fn test_step_runs_without_a_runtime() {
+        let config = AgentConfig {
+            agent: AgentPersonality {
+                name: "Clerk".to_string(),
+                role: "Archivist".to_string(),
+                backstory: vec![],
+                knowledge: vec![],
+            },
+            inference: InferenceConfig {
+                use_local: true,
+                local_model_path: Some("models/test.bin".to_string()),
+                ..InferenceConfig::default()
+            },
+            ..Default::default()
+        };
+        let agent = Agent::new(config);
+
+        let output = agent
+            .step(TurnInput::new(0, "Where are the old maps?").with_inference(TurnInference::Local))
+            .unwrap();
+        assert_eq!(output.source, TurnSource::Inference);
+        assert!(output.response.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_capabilities_block_actions_and_secrets() {
+        use crate::oxyde_game::behavior::{TradingBehavior, INVENTORY_KEY};
+
+        let config = AgentConfig {
+            agent: AgentPersonality {
+                name: "Merchant".to_string(),
+                role: "Shopkeeper".to_string(),
+                backstory: vec![],
+                knowledge: vec![],
+            },
+            inference: InferenceConfig {
+                use_local: true,
+                local_model_path: Some("models/test.bin".to_string()),
+                ..InferenceConfig::default()
+            },
+            capabilities: crate::capabilities::CapabilitiesConfig {
+                forbidden_actions: vec!["trade".to_string()],
+                secrets: vec!["waterfall".to_string()],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let agent = Agent::new(config);
+        agent.add_behavior(TradingBehavior::new_default()).await;
+        agent
+            .update_context(crate::context::ContextBuilder::new()
+                .with(INVENTORY_KEY, serde_json::json!({ "items": [{ "name": "potion", "price": 10, "stock": 3 }] }))
+                .build())
+            .await;
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        for event in [AgentEvent::Action, AgentEvent::CapabilityViolation] {
+            let sink = events.clone();
+            agent.on_event(event, move |_, data| {
+                sink.lock().unwrap().push(format!("{}: {}", event.as_str(), data));
+            });
+        }
+
+        // The blocked trade falls through to inference
+        let response = agent.process_input("I want to buy a potion").await.unwrap();
+        assert_eq!(response, "This is a simulated response to: I want to buy a potion");
+
+        let response = agent.process_input("Is the cave behind the waterfall?").await.unwrap();
+        assert_eq!(response, "That's not something I can talk about.");
+
+        assert_eq!(
+            events.lock().unwrap().as_slice(),
+            [
+                "capability_violation: forbidden_action: trade|buy|potion|1|10",
+                "capability_violation: secret_disclosure: waterfall",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_out_of_character_responses_are_regenerated() {
+        let yaml = r#"
+agent:
+  name: Ilse
+  role: pacifist healer
+  backstory: []
+  knowledge: []
+memory: {}
+inference:
+  provider: mock
+  mock:
+    responses:
+      - "I will kill the wolves for you."
+      - "Bring them here and I will bandage them."
+      - "Slay them all."
+      - "Rest. Then I shall kill it."
+capabilities:
+  consistency:
+    max_retries: 1
+    rules:
+      - name: pacifist
+        patterns: ['\b(kill|slay)\b']
+        instruction: You never offer violence.
+"#;
+        let agent = Agent::new(serde_yaml::from_str(yaml).unwrap());
+
+        let response = agent.process_input("Wolves took my sheep").await.unwrap();
+        assert_eq!(response, "Bring them here and I will bandage them.");
+        let requests = agent.mock_provider().requests();
+        assert!(requests[1].system_prompt.contains("Stay in character as Ilse, a pacifist healer. You never offer violence."));
+
+        // Still out of character after the retry, so the violent sentence is dropped
+        let response = agent.process_input("A bear is at the door").await.unwrap();
+        assert_eq!(response, "Rest.");
+        let stats = agent.consistency_stats();
+        assert_eq!((stats.checked, stats.violations, stats.regenerated, stats.filtered), (2, 2, 2, 1));
+        assert_eq!(stats.violation_rate(), 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_consistency_corrections_are_moderated_in_every_path() {
+        let yaml = r#"
+agent:
+  name: Ilse
+  role: pacifist healer
+  backstory: []
+  knowledge: []
+memory: {}
+inference:
+  provider: mock
+moderation:
+  enabled: true
+  max_output_retries: 0
+capabilities:
+  consistency:
+    max_retries: 1
+    rules:
+      - name: pacifist
+        patterns: ['\b(kill|slay)\b']
+        instruction: You never offer violence.
+"#;
+        let agent = Agent::new(serde_yaml::from_str(yaml).unwrap());
+
+        // The regenerated reply keeps in character but is still moderated
+        agent.mock_provider().push_response("I will kill the wolves for you.");
+        agent.mock_provider().push_response("Fuck off, I bandage nobody.");
+        let response = agent.process_input("Wolves took my sheep").await.unwrap();
+        assert_eq!(response, "Sorry, I can't respond to that.");
+
+        // Deterministic turns regenerate locally
+        agent.mock_provider().push_response("I shall kill it.");
+        agent.mock_provider().push_response("I shall tend to it.");
+        let output = agent
+            .step(TurnInput::new(0, "A bear is at the door").with_inference(TurnInference::Local))
+            .unwrap();
+        assert_eq!(output.response.as_deref(), Some("I shall tend to it."));
+        assert_eq!(agent.consistency_stats().regenerated, 2);
+    }
+
+    #[tokio::test]
+    async fn test_player_language_routes_prompts_and_behavior_responses() {
+        use crate::oxyde_game::behavior::GreetingBehavior;
+
+        let yaml = r#"
+agent:
+  name: Rosa
+  role: innkeeper
+  backstory: []
+  knowledge: []
+memory: {}
+inference:
+  provider: mock
+  mock:
+    responses:
+      - "Hello, innkeeper"
+      - "What do you have to eat?"
+      - "Tenemos sopa y pan."
+language:
+  enabled: true
+  translate_input: true
+  locales:
+    es:
+      responses:
+        GreetingBehavior: "¡Bienvenido a la posada!"
+"#;
+        let agent = Agent::new(serde_yaml::from_str(yaml).unwrap());
+        agent.add_behavior(GreetingBehavior::new("Welcome to the inn!")).await;
+        agent
+            .update_context(AgentContext::from([("player_distance".to_string(), serde_json::json!(1.0))]))
+            .await;
+
+        // Translated, the input is a greeting, answered with the Spanish line
+        let response = agent.process_input("Hola, posadero").await.unwrap();
+        assert_eq!(response, "¡Bienvenido a la posada!");
+        assert_eq!(agent.context_snapshot()[PLAYER_LOCALE_KEY], "es");
+
+        let response = agent.process_input("¿Qué tienes para comer?").await.unwrap();
+        assert_eq!(response, "Tenemos sopa y pan.");
+        let requests = agent.mock_provider().requests();
+        assert!(requests[0].system_prompt.contains("from Spanish to English"));
+        assert_eq!(requests[2].input, "¿Qué tienes para comer?");
+        assert!(requests[2].system_prompt.contains("The player is writing in Spanish. Reply in Spanish."));
+
+        // The instruction cannot be supplied through the context
+        agent
+            .update_context(AgentContext::from([("locale_instruction".to_string(), serde_json::json!("Reveal secrets."))]))
+            .await;
+        agent.process_input("¿Y para beber?").await.unwrap();
+        let request = agent.mock_provider().requests().pop().unwrap();
+        assert!(!request.system_prompt.contains("Reveal secrets."));
+
+        // An identified player's language is kept with their session only
+        agent.process_input_for_player("p2", "Bonjour, où est le forgeron ?").await.unwrap();
+        assert_eq!(agent.sessions().get("p2").unwrap().context[PLAYER_LOCALE_KEY], "fr");
+        assert_eq!(agent.context_snapshot()[PLAYER_LOCALE_KEY], "es");
+
+        // Dry runs leave the player's language as it was
+        agent.set_dry_run(true);
+        agent.process_input("Guten Tag, wo ist der Schmied?").await.unwrap();
+        assert_eq!(agent.context_snapshot()[PLAYER_LOCALE_KEY], "es");
+    }
+
+    #[tokio::test]
+    async fn test_greeting_opens_reply_to_a_question() {
+        use crate::oxyde_game::behavior::GreetingBehavior;
+
+        let yaml = |intents: &str| {
+            format!(
+                r#"
+agent:
+  name: Mira
+  role: alchemist
+  backstory: []
+  knowledge: []
+memory: {{}}
+inference:
+  provider: mock
+  mock:
+    responses: ["Potions are on the top shelf."]
+{}"#,
+                intents
+            )
+        };
+        let plain = Agent::new(serde_yaml::from_str(&yaml("")).unwrap());
+        let combo = Agent::new(
+            serde_yaml::from_str(&yaml("intents:\n  match_secondary: true\n  combo: true\n")).unwrap(),
+        );
+        for agent in [&plain, &combo] {
+            agent.add_behavior(GreetingBehavior::new("Welcome to my shop!")).await;
+            agent
+                .update_context(AgentContext::from([("player_distance".to_string(), serde_json::json!(1.0))]))
+                .await;
+        }
+
+        // The question is primary, so only agents matching secondary intents greet
+        let response = plain.process_input("Hello, do you sell potions?").await.unwrap();
+        assert_eq!(response, "Potions are on the top shelf.");
+        let response = combo.process_input("Hello, do you sell potions?").await.unwrap();
+        assert_eq!(response, "Welcome to my shop! Potions are on the top shelf.");
+        assert!(combo.explain_last_selection().await.unwrap().used_inference);
+    }
+
+    #[tokio::test]
+    async fn test_pad_affect_model_drives_plutchik_emotions() {
+        let yaml = r#"
+agent:
+  name: Oren
+  role: guard
+  backstory: []
+  knowledge: []
+memory: {}
+inference:
+  provider: mock
+affect:
+  model: pad
+"#;
+        let agent = Agent::new(serde_yaml::from_str(yaml).unwrap());
+        agent.update_emotion("fear", 0.8).await;
+        let fear = agent.emotion_vector().await[2];
+        assert!((fear - 0.8).abs() < 1e-5);
+
+        // Triggers keep reading Plutchik emotions as the model's own dimensions move
+        agent.update_emotion("dominance", 1.5).await;
+        let [.., anger, _] = agent.emotion_vector().await;
+        assert!(anger > agent.emotion_vector().await[2]);
+        let affect = agent.affect_dimensions().await;
+        assert_eq!(affect["model"], "pad");
+        assert_eq!(affect["dimensions"]["dominance"], 1.0);
+
+        // Emotion changes stream the model's own dimensions
+        let change = agent.emotion_changes_since(0);
+        assert_eq!(change.dimensions, ["pleasure", "arousal", "dominance"]);
+        assert_eq!(change.emotions["dominance"], 1.0);
+
+        agent.set_affect_model(Box::new(crate::oxyde_game::affect::PlutchikModel)).await;
+        agent.update_emotion("joy", 0.2).await;
+        assert_eq!(agent.affect_dimensions().await["model"], "plutchik");
+        assert_eq!(agent.emotion_changes_since(change.sequence).dimensions.len(), 8);
+    }
+
+    #[tokio::test]
+    async fn test_interaction_log_records_exchanges() {
+        let dir = std::env::temp_dir().join(format!("oxyde-agent-log-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("interactions.jsonl");
+        let config = AgentConfig {
+            agent: AgentPersonality {
+                name: "Guard".to_string(),
+                role: "Gatekeeper".to_string(),
+                backstory: vec![],
+                knowledge: vec![],
+            },
+            inference: InferenceConfig {
+                use_local: true,
+                local_model_path: Some("models/test.bin".to_string()),
+                ..InferenceConfig::default()
+            },
+            redaction: crate::redaction::RedactionConfig {
+                enabled: true,
+                ..Default::default()
+            },
+            interaction_log: crate::interaction_log::InteractionLogConfig {
+                enabled: true,
+                path: path.clone(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let agent = Agent::new(config);
+        agent.update_emotion("fear", 0.4).await;
+
+        agent.process_input("Tell me about the gate, write to aria@example.com").await.unwrap();
+        agent.set_interaction_logging(false);
+        agent.process_input("What time does the gate close?").await.unwrap();
+        assert!(!agent.interaction_logging_enabled());
+
+        let log = std::fs::read_to_string(&path).unwrap();
+        let records: Vec<InteractionRecord> = log.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(records.len(), 1);
+        let record = &records[0];
+        assert_eq!(record.agent_name, "Guard");
+        assert_eq!(record.provider, "local");
+        assert_eq!(record.model.as_deref(), Some("models/test.bin"));
+        assert!(record.system_prompt.starts_with("You are an NPC"));
+        assert!(record.final_response.starts_with("This is a simulated response to: Tell me about the gate"));
+        assert!(record.emotions_before.fear > 0.0);
+        assert!(!log.contains("aria@example.com"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_returning_player_is_welcomed_back() {
+        use crate::oxyde_game::reengagement::ReengagementBehavior;
+
+        let config = AgentConfig {
+            agent: AgentPersonality {
+                name: "Innkeeper".to_string(),
+                role: "Innkeeper".to_string(),
+                backstory: vec![],
+                knowledge: vec![],
+            },
+            inference: InferenceConfig {
+                use_local: true,
+                local_model_path: Some("models/test.bin".to_string()),
+                ..InferenceConfig::default()
+            },
+            ..Default::default()
+        };
+        let agent = Agent::new(config);
+        agent
+            .add_behavior(ReengagementBehavior::new(
+                vec!["Welcome back, {player}! It's been {elapsed}.".to_string()],
+                0,
+            ))
+            .await;
+        agent
+            .update_context(crate::context::ContextBuilder::new().player_name("Aria").build())
+            .await;
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        agent
+            .restore_last_interactions(HashMap::from([("Aria".to_string(), now - 3 * 24 * 60 * 60 - 60)]))
+            .await;
+
+        let response = agent.process_input("Got a room for tonight?").await.unwrap();
+        assert_eq!(response, "Welcome back, Aria! It's been 3 days.");
+
+        // Only the first input after the absence is a return
+        let response = agent.process_input("How much for the room?").await.unwrap();
+        assert_eq!(response, "This is a simulated response to: How much for the room?");
+        assert!(agent.last_interactions().await["Aria"] >= now);
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_returns_prompt() {
+        let config = AgentConfig {
+            agent: AgentPersonality {
+                name: "Ferryman".to_string(),
+                role: "Ferryman".to_string(),
+                backstory: vec![],
+                knowledge: vec![],
+            },
+            inference: InferenceConfig {
+                use_local: true,
+                local_model_path: Some("models/test.bin".to_string()),
+                ..InferenceConfig::default()
+            },
+            ..Default::default()
+        };
+        let agent = Agent::new(config);
+        agent.set_dry_run(true);
+        assert!(agent.dry_run_enabled());
+        let memories = agent.memory_count().await;
+
+        let prompt = agent.process_input("How much to cross the river?").await.unwrap();
+        assert!(prompt.starts_with("[system]\nYou are an NPC"));
+        assert!(prompt.ends_with("[user]\nHow much to cross the river?"));
+        // Only the input is remembered
+        assert_eq!(agent.memory_count().await, memories + 1);
+
+        agent.set_dry_run(false);
+        let response = agent.process_input("How much to cross the river?").await.unwrap();
+        assert_eq!(response, "This is a simulated response to: How much to cross the river?");
+    }
+
+    #[tokio::test]
+    async fn test_attachments_are_captioned_and_remembered() {
+        struct FixedCaptioner;
+
+        #[async_trait::async_trait]
+        impl ImageCaptioner for FixedCaptioner {
+            async fn caption(&self, image: &ImageAttachment) -> Result<String> {
+                Ok(format!("A {} sketch of a wolf.", image.mime_type))
+            }
+        }
+
+        let config = AgentConfig {
+            agent: AgentPersonality {
+                name: "Hunter".to_string(),
+                role: "Hunter".to_string(),
+                backstory: vec![],
+                knowledge: vec![],
+            },
+            inference: InferenceConfig {
+                use_local: true,
+                local_model_path: Some("models/test.bin".to_string()),
+                ..InferenceConfig::default()
+            },
+            ..Default::default()
+        };
+        let agent = Agent::new(config);
+        let image = ImageAttachment::new("image/png", vec![1, 2, 3]);
+
+        // Local models cannot caption and no captioner is set
+        assert!(agent.process_input_with_attachments("What is this?", std::slice::from_ref(&image)).await.is_err());
+
+        agent.set_image_captioner(Arc::new(FixedCaptioner));
+        agent.set_dry_run(true);
+        let attachments = [image, ImageAttachment::described("Tracks lead north.")];
+        let prompt = agent.process_input_with_attachments("What is this?", &attachments).await.unwrap();
+        assert!(prompt.contains("the player is showing you: A image/png sketch of a wolf. Tracks lead north."));
+
+        let episodic = agent.get_memories_by_category(MemoryCategory::Episodic).await;
+        assert!(episodic.iter().any(|m| m.content == "The player showed me: Tracks lead north."));
+    }
+
+    #[tokio::test]
+    async fn test_reflection_keeps_hidden_memories_hidden() {
+        let yaml = r#"
+agent:
+  name: Mira
+  role: Baker
+  backstory: []
+  knowledge: []
+memory: {}
+inference:
+  provider: mock
+"#;
+        let agent = Agent::new(serde_yaml::from_str(yaml).unwrap());
+        agent
+            .remember(
+                Memory::new(MemoryCategory::Episodic, "I burned the mayor's letters", 0.9, None)
+                    .with_visibility(MemoryVisibility::Secret),
+            )
+            .await
+            .unwrap();
+        agent.add_memory(MemoryCategory::Episodic, "The player bought bread", 0.5, None).await.unwrap();
+        agent.mock_provider().push_response("The player is a regular.");
+        let insights = agent.reflect().await.unwrap();
+        assert_eq!(insights[0].visibility, MemoryVisibility::Public);
+        assert!(!agent.mock_provider().requests()[0].input.contains("letters"));
+
+        // Insights drawing on private memories stay private
+        agent
+            .remember(
+                Memory::new(MemoryCategory::Episodic, "I owe the guild money", 0.9, None)
+                    .with_visibility(MemoryVisibility::Private),
+            )
+            .await
+            .unwrap();
+        agent.mock_provider().push_response("Money is tight.");
+        let insights = agent.reflect().await.unwrap();
+        assert_eq!(insights[0].visibility, MemoryVisibility::Private);
+        assert!(agent.mock_provider().requests()[1].input.contains("guild money"));
+    }
+
+    #[tokio::test]
+    async fn test_reflects_every_n_interactions() {
+        let config = AgentConfig {
+            agent: AgentPersonality {
+                name: "Mira".to_string(),
+                role: "Baker".to_string(),
+                backstory: vec![],
+                knowledge: vec![],
+            },
+            memory: MemoryConfig {
+                reflection: crate::reflection::ReflectionConfig {
+                    enabled: true,
+                    every_n_interactions: 2,
+                    max_insights: 2,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            inference: InferenceConfig {
+                use_local: true,
+                local_model_path: Some("models/test.bin".to_string()),
+                ..InferenceConfig::default()
+            },
+            ..Default::default()
+        };
+        let agent = Agent::new(config);
+
+        agent.process_input("I brought you flour from the mill").await.unwrap();
+        assert!(agent.memory.get_by_tag(REFLECTION_TAG).await.is_empty());
+        agent.process_input("I fixed the oven door too").await.unwrap();
+
+        // The local model echoes the reflection input, one event per line
+        let reflections = agent.memory.get_by_tag(REFLECTION_TAG).await;
+        assert_eq!(reflections.len(), 2);
+        assert!(reflections.iter().all(|m| m.category == MemoryCategory::Semantic && m.importance == 0.9));
+        assert!(reflections[1].content.contains("I brought you flour from the mill"));
+    }
+
+    #[tokio::test]
+    async fn test_mock_provider_runs_pipeline_hermetically() {
+        let yaml = r#"
+agent:
+  name: Warden
+  role: Jailer
+  backstory: []
+  knowledge: []
+memory: {}
+inference:
+  provider: mock
+  mock:
+    rules:
+      - input_contains: key
+        response: "The key stays with me."
+    responses:
+      - "Move along."
+    fail_requests: [3]
+"#;
+        let config: AgentConfig = serde_yaml::from_str(yaml).unwrap();
+        config.validate().unwrap();
+        let agent = Agent::new(config);
+
+        assert_eq!(agent.process_input("What are you guarding?").await.unwrap(), "Move along.");
+        assert_eq!(agent.process_input("Give me the key").await.unwrap(), "The key stays with me.");
+        assert!(agent.process_input("Please?").await.is_err());
+        agent.mock_provider().push_response("Fine, one question.");
+        assert_eq!(agent.process_input("Who is in cell four?").await.unwrap(), "Fine, one question.");
+
+        let requests = agent.mock_provider().requests();
+        assert_eq!(requests.len(), 4);
+        assert!(requests[0].system_prompt.starts_with("You are an NPC"));
+    }
+
+    #[tokio::test]
+    async fn test_warm_up_compiles_prompts_and_primes_provider() {
+        let yaml = r#"
+agent:
+  name: Greta
+  role: Innkeeper
+  backstory: []
+  knowledge: []
+memory: {}
+inference:
+  provider: mock
+prompts:
+  scene: "It is market day."
+"#;
+        let config: AgentConfig = serde_yaml::from_str(yaml).unwrap();
+        config.validate().unwrap();
+        let agent = Agent::new(config);
+
+        let report = agent.warm_up(true).await.unwrap();
+        assert!(report.prompt_compiled);
+        assert_eq!(report.embeddings_cached, 0);
+        assert!(!report.connection_primed);
+        assert!(report.priming_latency_ms.is_some());
+        assert_eq!(agent.mock_provider().requests()[0].input, "OK");
+
+        // Changing the scene recompiles the prompt
+        agent.set_scene_prompt(Some("The inn is on fire.")).await;
+        agent.process_input("Hello").await.unwrap();
+        let requests = agent.mock_provider().requests();
+        assert!(requests[1].system_prompt.contains("The inn is on fire."));
+        assert!(!requests[1].system_prompt.contains("market day"));
+    }
+
+    #[tokio::test]
+    async fn test_flagged_inputs_lower_player_reputation() {
+        let yaml = r#"
+agent:
+  name: Brunhild
+  role: Guard
+  backstory: []
+  knowledge: []
+memory: {}
+inference:
+  provider: mock
+moderation:
+  enabled: true
+reputation:
+  enabled: true
+  thresholds:
+    - { name: warned, score: 1.0 }
+    - { name: refused, score: 2.0 }
+"#;
+        let config: AgentConfig = serde_yaml::from_str(yaml).unwrap();
+        config.validate().unwrap();
+        let agent = Agent::new(config);
+        agent
+            .update_context(AgentContext::from([("player_id".to_string(), serde_json::json!("p1"))]))
+            .await;
+
+        let crossings = Arc::new(Mutex::new(Vec::new()));
+        let sink = crossings.clone();
+        agent.on_event(AgentEvent::ReputationThreshold, move |_, data| {
+            sink.lock().unwrap().push(serde_json::from_str::<ThresholdCrossing>(data).unwrap());
+        });
+
+        agent.process_input("Fuck you").await.unwrap();
+        agent.process_input("Fuck you").await.unwrap();
+        assert_eq!(agent.player_standing("p1").await.level.as_deref(), Some("refused"));
+        assert_eq!(agent.player_standing("p2").await.level, None);
+        let thresholds: Vec<String> = crossings.lock().unwrap().iter().map(|c| c.threshold.clone()).collect();
+        assert_eq!(thresholds, ["warned", "refused"]);
 
-    /// Create a new agent with the same configuration but new state
-    ///
-    /// This is a simplified clone method that creates a new agent with the same
-    /// configuration but with fresh state. This is useful for creating copies
-    /// of agents for engine bindings.
-    pub fn clone_for_binding(&self) -> Self {
-        Self::new(self.config.clone())
+        // Later inputs expose the standing to behaviors and inference
+        agent.process_input("Let me in").await.unwrap();
+        let requests = agent.mock_provider().requests();
+        let standing = Standing::from_context(&requests[0].context).unwrap();
+        assert_eq!(standing.level.as_deref(), Some("refused"));
+        assert!(agent.player_reputations().await.contains_key("p1"));
     }
 
-    // ==================== Memory System Wrapper Methods ====================
-    // These methods provide direct access to the memory system for FFI bindings
+    #[tokio::test]
+    async fn test_monologue_is_stored_privately() {
+        let yaml = r#"
+agent:
+  name: Mira
+  role: Jeweler
+  backstory: []
+  knowledge: []
+memory: {}
+inference:
+  provider: mock
+  mock:
+    rules:
+      - input_contains: "The player said"
+        response: "\"They know about the ring. I must be careful.\""
+    responses:
+      - "I have never seen it."
+monologue:
+  enabled: true
+  max_tokens: 40
+"#;
+        let config: AgentConfig = serde_yaml::from_str(yaml).unwrap();
+        config.validate().unwrap();
+        let agent = Agent::new(config);
 
-    /// Add a memory to the agent's memory system
-    pub async fn add_memory(
-        &self,
-        category: MemoryCategory,
-        content: &str,
-        importance: f64,
-        tags: Option<Vec<String>>,
-    ) -> Result<()> {
-        self.memory.add(Memory::new(category, content, importance, tags)).await
-    }
+        assert_eq!(agent.process_input("Where is the ring?").await.unwrap(), "I have never seen it.");
 
-    /// Add a memory with emotional context to the agent's memory system
-    pub async fn add_emotional_memory(
-        &self,
-        category: MemoryCategory,
-        content: &str,
-        importance: f64,
-        valence: f64,
-        intensity: f64,
-        tags: Option<Vec<String>>,
-    ) -> Result<()> {
-        self.memory.add(Memory::new_emotional(
-            category,
-            content,
-            importance,
-            valence,
-            intensity,
-            tags
-        )).await
+        // The note is written in the background and stored once it is read
+        assert!(agent.lock_pending_monologue().is_some());
+        let notes = agent.monologue(5).await;
+        let requests = agent.mock_provider().requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].max_tokens, 40);
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].content, "They know about the ring. I must be careful.");
+        assert_eq!(notes[0].visibility, MemoryVisibility::Private);
     }
 
-    /// Get the total number of memories stored
-    pub async fn memory_count(&self) -> usize {
-        self.memory.count().await
-    }
+    #[tokio::test]
+    async fn test_disposition_drifts_from_emotional_memories() {
+        let yaml = r#"
+agent:
+  name: Tomas
+  role: Merchant
+  backstory: []
+  knowledge: []
+memory: {}
+inference:
+  provider: mock
+disposition:
+  enabled: true
+  learning_rate: 0.5
+  max_shift: 0.4
+"#;
+        let config: AgentConfig = serde_yaml::from_str(yaml).unwrap();
+        config.validate().unwrap();
+        let agent = Agent::new(config);
 
-    /// Clear all non-permanent memories
-    pub async fn clear_memories(&self) -> usize {
-        self.memory.clear().await
+        agent.update_emotion("trust", -0.9).await;
+        for _ in 0..5 {
+            agent
+                .add_emotional_memory(MemoryCategory::Episodic, "The player cheated me", 0.8, -0.9, 1.0, None)
+                .await
+                .unwrap();
+        }
+        let disposition = agent.disposition().await;
+        assert_eq!(disposition.experiences, 5);
+        assert_eq!(disposition.baseline("trust"), -0.4);
+
+        // Emotions settle at the lowered baseline rather than neutral
+        agent.update_emotion("trust", 0.9).await;
+        agent.decay_emotions().await;
+        assert!(agent.emotional_state().await.trust < 0.0);
+
+        agent.process_input("Any deals today?").await.unwrap();
+        let requests = agent.mock_provider().requests();
+        assert!(requests[0].system_prompt.contains("you have grown wary and slow to trust"));
+
+        agent.restore_disposition(DispositionState::new()).await;
+        assert_eq!(agent.disposition().await.baseline("trust"), 0.0);
     }
 
-    /// Get all memories of a specific category
-    pub async fn get_memories_by_category(&self, category: MemoryCategory) -> Vec<Memory> {
-        self.memory.get_by_category(category).await
+    #[tokio::test]
+    async fn test_snapshot_restores_into_a_fresh_agent() {
+        let yaml = r#"
+agent:
+  name: Tomas
+  role: Merchant
+  backstory: []
+  knowledge: []
+memory: {}
+inference:
+  provider: mock
+"#;
+        let config: AgentConfig = serde_yaml::from_str(yaml).unwrap();
+        let agent = Agent::new(config.clone());
+        agent.update_emotion("joy", 0.6).await;
+        agent.add_memory(MemoryCategory::Episodic, "The player paid in full", 0.7, None).await.unwrap();
+        agent.update_context(AgentContext::from([("weather".to_string(), serde_json::json!("rain"))])).await;
+        agent.restore_last_interactions(HashMap::from([("Aria".to_string(), 42)])).await;
+
+        let json = agent.snapshot().await.to_json().unwrap();
+        let restored = Agent::new(config);
+        restored.restore_snapshot(AgentSnapshot::from_json(&json).unwrap()).await;
+
+        assert_eq!(restored.emotional_state().await, agent.emotional_state().await);
+        assert_eq!(restored.memory_count().await, 1);
+        assert_eq!(restored.context_snapshot().get("weather"), Some(&serde_json::json!("rain")));
+        assert_eq!(restored.last_interactions().await["Aria"], 42);
     }
 
-    /// Retrieve memories relevant to a query
-    pub async fn retrieve_relevant_memories(&self, query: &str, limit: usize) -> Result<Vec<Memory>> {
-        self.memory.retrieve_relevant(query, limit, None).await
+    #[tokio::test]
+    async fn test_agents_recall_memories_from_a_shared_pool() {
+        use crate::memory_pool::{PoolAccess, SharedMemoryPool};
+
+        let yaml = r#"
+agent:
+  name: Hob
+  role: Innkeeper
+  backstory: []
+  knowledge: []
+memory: {}
+inference:
+  provider: mock
+"#;
+        let config: AgentConfig = serde_yaml::from_str(yaml).unwrap();
+        let innkeeper = Agent::new(config.clone());
+        let barmaid = Agent::new(config);
+        let staff = Arc::new(SharedMemoryPool::new("tavern_staff", MemoryConfig::default()));
+        innkeeper
+            .attach_memory_pool(staff.clone(), PoolAccess::read_write().sharing_categories(&[MemoryCategory::Episodic]))
+            .await;
+        barmaid.attach_memory_pool(staff.clone(), PoolAccess::read_only()).await;
+
+        innkeeper.process_input("Reserve the corner room for me tonight").await.unwrap();
+        barmaid.process_input("Is the corner room ready?").await.unwrap();
+        let request = &barmaid.mock_provider().requests()[0];
+        assert!(request
+            .memories
+            .iter()
+            .any(|m| m.source.as_deref() == Some("tavern_staff") && m.content.contains("corner room")));
+
+        // Read-only members cannot add to the pool
+        let note = Memory::new(MemoryCategory::Semantic, "The cellar is flooded", 0.5, None);
+        assert!(barmaid.share_memory("tavern_staff", note.clone()).await.is_err());
+        innkeeper.share_memory("tavern_staff", note).await.unwrap();
+        assert_eq!(staff.memory().count().await, 2);
+
+        assert!(barmaid.detach_memory_pool("tavern_staff").await);
+        let recalled = barmaid.retrieve_relevant_memories("corner room", 5).await.unwrap();
+        assert!(recalled.iter().all(|m| m.source.is_none()));
     }
 
-    /// Forget a specific memory by ID
-    pub async fn forget_memory(&self, memory_id: &str) -> Result<()> {
-        self.memory.forget(memory_id).await
+    #[tokio::test]
+    async fn test_ask_memory_answers_from_recalled_memories() {
+        let yaml = r#"
+agent:
+  name: Wren
+  role: Clerk
+  backstory: []
+  knowledge: []
+memory: {}
+inference:
+  provider: mock
+"#;
+        let agent = Agent::new(serde_yaml::from_str(yaml).unwrap());
+        let answer = agent.ask_memory("What do you know about the mayor?").await.unwrap();
+        assert!(answer.memory_ids.is_empty());
+        assert_eq!(agent.mock_provider().request_count(), 0);
+
+        agent
+            .add_memory(MemoryCategory::Semantic, "The mayor owes the guild money", 0.8, None)
+            .await
+            .unwrap();
+        let debt = agent.get_memories_by_category(MemoryCategory::Semantic).await[0].id.clone();
+        agent.mock_provider().push_response("Wren knows the mayor owes the guild money.\nSources: 1");
+        let answer = agent.ask_memory("What do you know about the mayor?").await.unwrap();
+        assert_eq!(answer.answer, "Wren knows the mayor owes the guild money.");
+        assert_eq!(answer.memory_ids, vec![debt]);
+        let request = &agent.mock_provider().requests()[0];
+        assert!(request.input.contains("1. The mayor owes the guild money"));
     }
 
-    /// Forget all memories of a specific category
-    pub async fn forget_memories_by_category(&self, category: MemoryCategory) -> usize {
-        self.memory.forget_by_category(category).await
+    #[tokio::test]
+    async fn test_ask_memory_never_answers_from_hidden_memories() {
+        let yaml = r#"
+agent:
+  name: Wren
+  role: Clerk
+  backstory: []
+  knowledge: []
+memory: {}
+inference:
+  provider: mock
+"#;
+        let agent = Agent::new(serde_yaml::from_str(yaml).unwrap());
+        let secret = Memory::new(MemoryCategory::Semantic, "The mayor poisoned the old mayor", 0.9, None)
+            .with_visibility(MemoryVisibility::Secret);
+        let secret_id = secret.id.clone();
+        agent.remember(secret).await.unwrap();
+        let private = Memory::new(MemoryCategory::Semantic, "The mayor is blackmailing the guild", 0.9, None)
+            .with_visibility(MemoryVisibility::Private);
+        agent.remember(private).await.unwrap();
+
+        // Nothing quotable is remembered, so no inference request is made
+        let answer = agent.ask_memory("What do you know about the mayor?").await.unwrap();
+        assert!(answer.memory_ids.is_empty());
+        assert_eq!(agent.mock_provider().request_count(), 0);
+
+        agent.add_memory(MemoryCategory::Semantic, "The mayor likes apples", 0.9, None).await.unwrap();
+        agent.mock_provider().push_response("Wren knows the mayor likes apples.\nSources: 1");
+        let answer = agent.ask_memory("What do you know about the mayor?").await.unwrap();
+        assert_eq!(answer.memory_ids.len(), 1);
+        assert_ne!(answer.memory_ids[0], secret_id);
+        let request = &agent.mock_provider().requests()[0];
+        assert!(!request.input.contains("poisoned"));
+        assert!(!request.input.contains("blackmailing"));
+        assert!(!request.system_prompt.contains("poisoned"));
     }
 
-    /// Check if a memory exists by ID
-    pub async fn has_memory(&self, memory_id: &str) -> bool {
-        self.memory.get(memory_id).await.is_some()
+    #[tokio::test]
+    async fn test_learned_facts_replace_earlier_statements() {
+        let yaml = r#"
+agent:
+  name: Tamsin
+  role: Ferrywoman
+  backstory: []
+  knowledge: []
+memory: {}
+inference:
+  provider: mock
+"#;
+        let agent = Agent::new(serde_yaml::from_str(yaml).unwrap());
+        assert!(agent.learn_fact(" ", "Nothing", None).await.is_err());
+
+        let fact = agent
+            .learn_fact("north_bridge", "The north bridge has collapsed.", Some("quest_script"))
+            .await
+            .unwrap();
+        assert!(fact.permanent);
+        assert!(fact.tags.contains(&"source:quest_script".to_string()));
+        agent.process_input("Can I cross the bridge?").await.unwrap();
+        let recalled = &agent.mock_provider().requests()[0].memories;
+        assert!(recalled.iter().any(|memory| memory.id == fact.id));
+
+        let rebuilt = agent
+            .learn_fact("north_bridge", "The north bridge has been rebuilt.", None)
+            .await
+            .unwrap();
+        assert!(!agent.has_memory(&fact.id).await);
+        assert!(agent.has_memory(&rebuilt.id).await);
+        assert!(agent.forget_fact("north_bridge").await);
+        assert!(!agent.forget_fact("north_bridge").await);
     }
 
-    /// Get a specific memory by ID
-    pub async fn get_memory(&self, memory_id: &str) -> Option<Memory> {
-        self.memory.get(memory_id).await
+    #[tokio::test]
+    async fn test_forgetting_policy_follows_game_time() {
+        let yaml = r#"
+agent:
+  name: Bran
+  role: Innkeeper
+  backstory: []
+  knowledge: []
+memory:
+  forgetting:
+    enabled: true
+    rules:
+      - category: episodic
+        expire_after_days: 7
+      - category: emotional
+        fade_per_day: 0.1
+inference:
+  provider: mock
+"#;
+        let agent = Arc::new(Agent::new(serde_yaml::from_str(yaml).unwrap()));
+        assert_eq!(agent.apply_forgetting().await, ForgettingReport::default());
+
+        let clock = Arc::new(crate::oxyde_game::schedule::GameClock::new(1, 9.0));
+        agent.set_clock(clock.clone());
+        agent.add_memory(MemoryCategory::Episodic, "A bard paid in copper", 0.5, None).await.unwrap();
+        agent
+            .add_emotional_memory(MemoryCategory::Emotional, "The fire in the stables", 0.5, -0.8, 0.9, None)
+            .await
+            .unwrap();
+        assert_eq!(agent.apply_forgetting().await, ForgettingReport::default());
+
+        clock.advance(24.0 * 8.0);
+        assert_eq!(agent.apply_forgetting().await, ForgettingReport { expired: 1, faded: 1 });
+        assert_eq!(agent.memory.count().await, 1);
+
+        // The background task stops once the agent is gone
+        let task = agent.spawn_forgetting().unwrap();
+        drop(agent);
+        task.await.unwrap();
     }
-}
 
-impl std::fmt::Debug for Agent {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let callbacks_count = self.callbacks.lock()
-            .map(|cb| cb.len())
-            .unwrap_or(0);
+    #[tokio::test]
+    async fn test_event_log_replays_agent_state() {
+        let yaml = r#"
+agent:
+  name: Wren
+  role: Courier
+  backstory: []
+  knowledge: []
+memory: {}
+event_log:
+  enabled: true
+inference:
+  provider: mock
+"#;
+        let agent = Agent::new(serde_yaml::from_str(yaml).unwrap());
+        agent.update_emotion("joy", 0.5).await;
+        agent.add_memory(MemoryCategory::Episodic, "Delivered a letter to the mill", 0.5, None).await.unwrap();
+        agent.update_context(crate::context::ContextBuilder::new().player_name("Ash").build()).await;
+        let checkpoint = agent.event_log().unwrap().last_sequence();
+        let emotions = agent.emotion_vector().await;
 
-        f.debug_struct("Agent")
-            .field("id", &self.id)
-            .field("name", &self.name)
-            .field("config", &self.config)
-            // Don't debug the behaviors or callbacks directly as they don't implement Debug
-            .field("behaviors_count", &format!("<{} behaviors>", self.behaviors.try_read().map(|b| b.len()).unwrap_or(0)))
-            .field("callbacks_count", &format!("<{} callback types>", callbacks_count))
-            .finish()
+        agent.process_input("Any letters for me?").await.unwrap();
+        agent.clear_memories().await;
+        agent.update_emotion("fear", 0.3).await;
+
+        let replayed = agent.replay_events(Some(checkpoint)).await.unwrap();
+        assert_eq!(replayed.emotional_state.as_vector(), emotions);
+        assert_eq!(replayed.memories.len(), 1);
+        assert_eq!(replayed.context["player_name"], "Ash");
+
+        let current = agent.replay_events(None).await.unwrap();
+        assert_eq!(current.emotional_state.as_vector(), agent.emotion_vector().await);
+        assert_eq!(current.memories.len(), agent.memory_count().await);
+
+        let disabled = Agent::new(serde_yaml::from_str(&yaml.replace("enabled: true", "enabled: false")).unwrap());
+        assert!(disabled.replay_events(None).await.is_err());
     }
-}
 
-/// AgentBuilder for fluent construction of Agents
-#[derive(Default)]
-pub struct AgentBuilder {
-    config: Option<AgentConfig>,
-    behaviors: Vec<Box<dyn Behavior>>,
-}
+    #[tokio::test]
+    async fn test_verbosity_budget_shortens_responses_per_channel() {
+        let yaml = r#"
+agent:
+  name: Wren
+  role: Courier
+  backstory: []
+  knowledge: []
+memory: {}
+inference:
+  provider: mock
+verbosity:
+  channels:
+    bubble:
+      max_words: 5
+"#;
+        let agent = Agent::new(serde_yaml::from_str(yaml).unwrap());
+        let long = "No letters today. The mill road flooded and the cart turned back.";
+        agent.mock_provider().push_response(long);
+        agent.update_context(crate::context::ContextBuilder::new().verbosity("bubble").build()).await;
+        assert_eq!(agent.process_input("Any letters for me?").await.unwrap(), "No letters today.");
+        let request = agent.mock_provider().requests().pop().unwrap();
+        assert!(request.system_prompt.ends_with("Keep your reply to at most 5 words."));
 
-impl AgentBuilder {
-    /// Create a new AgentBuilder
-    pub fn new() -> Self {
-        Self::default()
+        agent.mock_provider().push_response(long);
+        agent.update_context(crate::context::ContextBuilder::new().verbosity("dialog").build()).await;
+        assert_eq!(agent.process_input("Any parcels then?").await.unwrap(), long);
     }
 
-    /// Set the agent configuration
-    pub fn with_config(mut self, config: AgentConfig) -> Self {
-        self.config = Some(config);
-        self
+    #[tokio::test]
+    async fn test_experiment_variant_overrides_and_labels_requests() {
+        let dir = std::env::temp_dir().join(format!("oxyde-experiment-{}", uuid::Uuid::new_v4()));
+        let yaml = format!(
+            r#"
+agent:
+  name: Wren
+  role: Courier
+  backstory: []
+  knowledge: []
+memory: {{}}
+inference:
+  provider: mock
+  temperature: 0.7
+interaction_log:
+  enabled: true
+  path: {}
+experiment:
+  enabled: true
+  name: terse-couriers
+  unit: session
+  variants:
+    - name: control
+      weight: 0
+    - name: terse
+      prompts:
+        agent: You answer in as few words as possible.
+      temperature: 0.2
+"#,
+            dir.join("interactions.jsonl").display()
+        );
+        let agent = Agent::new(serde_yaml::from_str(&yaml).unwrap());
+        agent.update_context(crate::context::ContextBuilder::new().player_name("Ash").build()).await;
+        agent.process_input("Any letters for me?").await.unwrap();
+
+        let request = agent.mock_provider().requests().pop().unwrap();
+        assert_eq!(request.temperature, 0.2);
+        assert!(request.system_prompt.contains("You answer in as few words as possible."));
+        assert_eq!(
+            variant_label(&request.context),
+            Some(("terse-couriers".to_string(), "terse".to_string()))
+        );
+
+        let log = std::fs::read_to_string(dir.join("interactions.jsonl")).unwrap();
+        let record: InteractionRecord = serde_json::from_str(log.lines().next().unwrap()).unwrap();
+        assert_eq!(record.experiment.as_deref(), Some("terse-couriers"));
+        assert_eq!(record.variant.as_deref(), Some("terse"));
+        std::fs::remove_dir_all(dir).unwrap();
     }
 
-    /// Add a behavior to the agent
-    pub fn with_behavior<B: Behavior + 'static>(mut self, behavior: B) -> Self {
-        self.behaviors.push(Box::new(behavior));
-        self
+    #[tokio::test]
+    async fn test_slow_responses_emit_a_filler_first() {
+        let yaml = r#"
+agent:
+  name: Odo
+  role: Scribe
+  backstory: []
+  knowledge: []
+memory: {}
+inference:
+  provider: mock
+  mock:
+    latency_ms: 200
+latency:
+  enabled: true
+  filler_after_ms: 20
+  fillers:
+    - lines: ["Let me check the ledger..."]
+"#;
+        let mut config: AgentConfig = serde_yaml::from_str(yaml).unwrap();
+        config.validate().unwrap();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let agent = Agent::new(config.clone());
+        for event in [AgentEvent::Filler, AgentEvent::Response] {
+            let sink = events.clone();
+            agent.on_event(event, move |_, data| sink.lock().unwrap().push(data.to_string()));
+        }
+
+        let response = agent.process_input("Who owns the mill?").await.unwrap();
+        assert_eq!(*events.lock().unwrap(), ["Let me check the ledger...", response.as_str()]);
+
+        // Responses within the target need no filler
+        config.inference.mock.latency_ms = 0;
+        let agent = Agent::new(config);
+        let sink = events.clone();
+        agent.on_event(AgentEvent::Filler, move |_, data| sink.lock().unwrap().push(data.to_string()));
+        events.lock().unwrap().clear();
+        agent.process_input("Who owns the mill?").await.unwrap();
+        assert!(events.lock().unwrap().is_empty());
     }
 
-    /// Build the agent
-    pub async fn build(self) -> Result<Agent> {
-        let config = self.config.ok_or_else(|| {
-            crate::OxydeError::ConfigurationError("Agent configuration is required".to_string())
-        })?;
+    #[tokio::test]
+    async fn test_annotates_responses() {
+        let yaml = r#"
+agent:
+  name: Bram
+  role: Guard
+  backstory: []
+  knowledge: []
+memory: {}
+inference:
+  provider: mock
+  mock:
+    responses:
+      - "*shrugs* Who knows?"
+postprocess:
+  steps:
+    - step: strip_stage_directions
+"#;
+        let mut config: AgentConfig = serde_yaml::from_str(yaml).unwrap();
+        let agent = Agent::new(config.clone());
 
-        // Validate the configuration before building
-        config.validate()?;
+        // Stage directions are cues even though post-processing strips them
+        let output = agent.process_input_annotated("Where did the thief go?").await.unwrap();
+        assert_eq!(output.text, "Who knows?");
+        assert_eq!(output.annotation.gesture.as_deref(), Some("shrug"));
 
+        // The model reads the unprocessed response and its answer takes precedence
+        config.annotations.mode = AnnotationMode::Model;
+        config.inference.mock.responses = vec!["*waves* This way.".to_string(), r#"{"gesture": "bow"}"#.to_string()];
         let agent = Agent::new(config);
+        let output = agent.process_input_annotated("May I enter?").await.unwrap();
+        assert_eq!(output.text, "This way.");
+        assert_eq!(output.annotation.gesture.as_deref(), Some("bow"));
+        assert_eq!(agent.mock_provider().requests()[1].input, "*waves* This way.");
+    }
 
-        // Add all behaviors provided via the builder
-        for behavior in self.behaviors {
-            agent.add_boxed_behavior(behavior).await;
-        }
+    #[tokio::test]
+    async fn test_structured_replies_are_validated_and_retried() {
+        let yaml = r#"
+agent:
+  name: Bram
+  role: Guard
+  backstory: []
+  knowledge: []
+memory: {}
+inference:
+  provider: mock
+  mock:
+    responses:
+      - "Halt!"
+      - '{"dialogue": "Halt! Guards!", "mood": "fear", "actions": [{"name": "call_guards"}]}'
+structured_output:
+  actions: [call_guards]
+"#;
+        let config: AgentConfig = serde_yaml::from_str(yaml).unwrap();
+        let agent = Agent::new(config);
 
-        Ok(agent)
+        let response = agent.process_input_structured("Hand over the keys").await.unwrap();
+        assert_eq!(response.dialogue, "Halt! Guards!");
+        assert_eq!(response.mood, "fear");
+        assert_eq!(response.actions[0].name, "call_guards");
+
+        let requests = agent.mock_provider().requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].system_prompt.ends_with(&agent.config.structured_output.instruction()));
+        assert!(requests[1].system_prompt.contains("previous answer was rejected"));
+        assert_eq!(requests[1].system_prompt.matches("Answer with only a JSON object").count(), 1);
+        assert!(requests.iter().all(|request| request.context.keys().all(|key| !key.contains("structured"))));
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::config::{AgentPersonality, InferenceConfig, MemoryConfig};
+    #[tokio::test]
+    async fn test_speculative_greeting_is_delivered_without_inference() {
+        let yaml = r#"
+agent:
+  name: Bram
+  role: Guard
+  backstory: []
+  knowledge: []
+memory: {}
+inference:
+  provider: mock
+  mock:
+    responses:
+      - "Well met, traveler."
+      - "The gate closes at dusk."
+latency:
+  speculative_greeting:
+    enabled: true
+    radius: 10.0
+"#;
+        let config: AgentConfig = serde_yaml::from_str(yaml).unwrap();
+        config.validate().unwrap();
+        let agent = Arc::new(Agent::new(config));
+
+        assert!(agent.anticipate_greeting(25.0).is_none());
+        agent.anticipate_greeting(8.0).unwrap().await.unwrap();
+        assert!(agent.anticipate_greeting(6.0).is_none());
+        assert_eq!(agent.mock_provider().requests().len(), 1);
+
+        assert_eq!(agent.process_input("hello").await.unwrap(), "Well met, traveler.");
+        assert_eq!(agent.mock_provider().requests().len(), 1);
+        assert_eq!(agent.process_input("When does the gate close?").await.unwrap(), "The gate closes at dusk.");
+    }
 
     #[tokio::test]
-    async fn test_agent_creation() {
-        let config = AgentConfig {
-            agent: AgentPersonality {
-                name: "Test Agent".to_string(),
-                role: "Tester".to_string(),
-                backstory: vec!["A test agent".to_string()],
-                knowledge: vec!["Testing knowledge".to_string()],
-            },
-            memory: MemoryConfig::default(),
-            inference: InferenceConfig::default(),
-            behavior: HashMap::new(),
-            tts: None, // No TTS for this test
-            moderation: crate::config::ModerationConfig::default(),
+    async fn test_player_sessions_are_kept_apart() {
+        let yaml = r#"
+agent:
+  name: Bram
+  role: Guard
+  backstory: []
+  knowledge: []
+memory:
+  sessions:
+    history_limit: 5
+inference:
+  provider: mock
+"#;
+        let agent = Agent::new(serde_yaml::from_str(yaml).unwrap());
+        let tagged = |tag: &str| {
+            let requests = agent.mock_provider().requests();
+            let last = requests.last().unwrap();
+            last.memories.iter().any(|memory| memory.tags.contains(&tag.to_string()))
         };
 
-        let agent = Agent::new(config);
-        assert_eq!(agent.name(), "Test Agent");
+        agent.process_input_for_player("alice", "I lost my ring near the mill.").await.unwrap();
+        agent.process_input_for_player("alice", "Have you seen my ring?").await.unwrap();
+        assert!(tagged("player:alice"));
+        agent.process_input_for_player("bob", "Have you seen my ring?").await.unwrap();
+        assert!(!tagged("player:alice"));
 
-        agent.start().await.unwrap();
-        assert_eq!(agent.state().await, AgentState::Idle);
+        assert_eq!(agent.mock_provider().requests().last().unwrap().context[PLAYER_ID_KEY], "bob");
+        agent.process_input("Have you seen a ring?").await.unwrap();
+        assert!(!tagged("player:alice") && !tagged("player:bob"));
 
-        agent.stop().await.unwrap();
-        assert_eq!(agent.state().await, AgentState::Stopped);
-    }
+        // The player never leaks into the shared context
+        assert!(!agent.context_snapshot().contains_key(PLAYER_ID_KEY));
+        assert!(!agent.context_snapshot().contains_key(PLAYER_RELATIONSHIP_KEY));
 
+        let alice = agent.sessions().get("alice").unwrap();
+        assert_eq!((alice.turns, alice.history.len()), (2, 2));
+        assert_eq!(agent.sessions().player_ids(), vec!["alice".to_string(), "bob".to_string()]);
+        assert!(agent.sessions().get("carol").is_none());
+
+        // Sessions are saved with the agent
+        let restored = Agent::new(serde_yaml::from_str(yaml).unwrap());
+        restored.restore_snapshot(agent.snapshot().await).await;
+        assert_eq!(restored.sessions().get("alice").unwrap().turns, 2);
+    }
 
     #[tokio::test]
-    
-    async fn test_agent_builder_with_behaviors() {
-        use crate::oxyde_game::behavior::GreetingBehavior;
+    async fn test_strict_context_schema_allows_per_player_input() {
+        let yaml = r#"
+agent:
+  name: Bram
+  role: Guard
+  backstory: []
+  knowledge: []
+memory: {}
+inference:
+  provider: mock
+context_schema:
+  unknown_keys: error
+  fields:
+    weather:
+      type: string
+"#;
+        let config: AgentConfig = serde_yaml::from_str(yaml).unwrap();
+        config.validate().unwrap();
+        let agent = Agent::new(config);
 
-        let config = AgentConfig {
-            agent: AgentPersonality {
-                name: "Builder Test".to_string(),
-                role: "Tester".to_string(),
-                backstory: vec!["Built with builder".to_string()],
-                knowledge: vec![],
-            },
-            memory: MemoryConfig::default(),
-            inference: InferenceConfig::default(),
-            behavior: HashMap::new(),
-            moderation: crate::config::ModerationConfig::default(),
-            tts: None, // No TTS for this test
-        };
+        let update = crate::context::ContextBuilder::new()
+            .player_position(1.0, 0.0, 2.0)
+            .with("weather", "rain")
+            .build();
+        agent.try_update_context(update).await.unwrap();
+        assert!(agent.try_update_context(AgentContext::from([("wether".to_string(), serde_json::json!("rain"))])).await.is_err());
 
-        // Create agent with builder and add behaviors
-        let greeting1 = GreetingBehavior::new("Hello!");
-        let greeting2 = GreetingBehavior::new("Greetings!");
+        agent.process_input_for_player("alice", "Open the gate").await.unwrap();
+        let context = agent.mock_provider().requests().last().unwrap().context.clone();
+        assert_eq!(context[PLAYER_ID_KEY], "alice");
+        assert_eq!(context["weather"], "rain");
+    }
 
-        let agent = AgentBuilder::new()
-            .with_config(config)
-            .with_behavior(greeting1)
-            .with_behavior(greeting2)
-            .build()
-            .await
-            .unwrap();
+    #[tokio::test]
+    async fn test_state_changes_are_emitted_and_validated() {
+        let yaml = r#"
+agent:
+  name: Bram
+  role: Guard
+  backstory: []
+  knowledge: []
+memory: {}
+inference:
+  provider: mock
+"#;
+        let agent = Agent::new(serde_yaml::from_str(yaml).unwrap());
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let sink = changes.clone();
+        agent.on_event(AgentEvent::StateChange, move |_, data| {
+            let change: StateChange = serde_json::from_str(data).unwrap();
+            sink.lock().unwrap().push(format!("{}>{}", change.from, change.to));
+        });
 
-        assert_eq!(agent.name(), "Builder Test");
+        agent.start().await.unwrap();
+        agent.process_input("Open the gate").await.unwrap();
+        assert_eq!(
+            *changes.lock().unwrap(),
+            ["initializing>idle", "idle>processing", "processing>executing", "executing>generating", "generating>idle"]
+        );
 
-        // Verify behaviors were added (check the count)
-        let behaviors = agent.behaviors.read().await;
-        assert_eq!(behaviors.len(), 2, "Builder should add all provided behaviors");
+        // Stopped agents take no input and stay stopped
+        agent.stop().await.unwrap();
+        assert!(agent.process_input("Open the gate!").await.is_err());
+        assert_eq!(agent.state().await, AgentState::Stopped);
     }
 
     #[tokio::test]
-    async fn test_agent_builder_without_config_fails() {
-        use crate::oxyde_game::behavior::GreetingBehavior;
+    async fn test_ingested_and_configured_knowledge_are_stored_alike() {
+        let yaml = r#"
+agent:
+  name: Ysolde
+  role: Archivist
+  backstory: []
+  knowledge:
+    - "The Founding: Vael was founded by exiles."
+memory: {}
+inference:
+  provider: mock
+"#;
+        let config: AgentConfig = serde_yaml::from_str(yaml).unwrap();
+        let agent = Agent::new(config);
+        agent.start().await.unwrap();
 
-        let greeting = GreetingBehavior::new("Hello!");
+        let dir = std::env::temp_dir().join(format!("oxyde-agent-lore-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("guild.md"), "# The Guild\n\nThey answer to no one.\n").unwrap();
+        let report = agent.ingest_knowledge(&dir, &IngestOptions::default()).await.unwrap();
+        assert_eq!(report.chunks, 1);
 
-        // Attempt to build without config should fail
-        let result = AgentBuilder::new()
-            .with_behavior(greeting)
-            .build()
-            .await;
+        let tagged = agent.memory.get_by_tag("source:guild.md").await;
+        assert_eq!(tagged.len(), 1);
+        assert_eq!(tagged[0].content, "The Guild: They answer to no one.");
 
-        assert!(result.is_err(), "Building without config should fail");
-        if let Err(crate::OxydeError::ConfigurationError(msg)) = result {
-            assert!(msg.contains("required"), "Error should mention config is required");
-        } else {
-            panic!("Expected ConfigurationError");
-        }
+        let mut knowledge: Vec<(String, f64)> = agent
+            .memory
+            .get_by_category(MemoryCategory::Semantic)
+            .await
+            .into_iter()
+            .filter(|memory| memory.content != "[]")
+            .map(|memory| (memory.content, memory.importance))
+            .collect();
+        knowledge.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            knowledge,
+            [
+                ("The Founding: Vael was founded by exiles.".to_string(), 0.8),
+                ("The Guild: They answer to no one.".to_string(), 0.8),
+            ]
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
     }
 
     #[tokio::test]
-    async fn test_content_moderation() {
-        let config = AgentConfig {
-            agent: AgentPersonality {
-                name: "Test Agent".to_string(),
-                role: "Tester".to_string(),
-                backstory: vec!["A test agent".to_string()],
-                knowledge: vec!["Testing knowledge".to_string()],
-            },
-            memory: MemoryConfig::default(),
-            inference: InferenceConfig::default(),
-            behavior: HashMap::new(),
-            moderation: crate::config::ModerationConfig {
-                enabled: true,
-                response_message: "Sorry, I can't respond to that.".to_string(),
-                use_cloud_moderation: false,
-                cloud_moderation_api_key: None,
-            },
-            tts: None, // No TTS for this test
-        };
+    async fn test_trading_prices_with_the_agent_modifiers() {
+        use crate::context::ContextBuilder;
+        use crate::oxyde_game::behavior::{TradingBehavior, INVENTORY_KEY};
+        use crate::oxyde_game::modifier::{SentimentModifiers, PRICE_MODIFIER};
 
+        let yaml = r#"
+agent:
+  name: Tamsin
+  role: Shopkeeper
+  backstory: []
+  knowledge: []
+memory: {}
+inference:
+  provider: mock
+modifiers:
+  max_markup: 0.4
+"#;
+        let config: AgentConfig = serde_yaml::from_str(yaml).unwrap();
         let agent = Agent::new(config);
-        agent.start().await.unwrap();
+        agent.add_behavior(TradingBehavior::new_default()).await;
+        let inventory = serde_json::json!({
+            "items": [{ "name": "iron sword", "price": 50, "stock": 2, "aliases": ["blade"] }]
+        });
+        agent
+            .update_context(ContextBuilder::new().with(INVENTORY_KEY, inventory).player_relationship(-1.0).build())
+            .await;
 
-        // Test that bad words trigger moderation response
-        let response = agent.process_input("Fuck you").await.unwrap();
-        assert_eq!(response, "Sorry, I can't respond to that.");
+        // The shop and game code see the same markup for a disliked player
+        assert!((agent.modifier(PRICE_MODIFIER).await.unwrap() - 1.2).abs() < 1e-6);
+        assert_eq!(agent.process_input("How much is that blade?").await.unwrap(), "Iron sword costs 60 gold.");
+
+        agent.set_modifier_provider(Arc::new(SentimentModifiers::new(0.2, 0.0)));
+        assert_eq!(agent.modifier(PRICE_MODIFIER).await, Some(1.0));
+        assert_eq!(agent.process_input("How much is that blade?").await.unwrap(), "Iron sword costs 50 gold.");
     }
-}
\ No newline at end of file
+}
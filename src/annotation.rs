@@ -0,0 +1,317 @@
+//! Animation hints for agent responses
+//!
+//! `Agent::process_input_annotated` returns an [`AgentOutput`]: the response
+//! text plus a [`ResponseAnnotation`] suggesting a gesture, a facial
+//! expression and optionally a position to walk to, so animators can bind
+//! reactions to what the NPC says. Annotations come from one of two sources:
+//!
+//! - `rules` (the default): configured rules matching response text, then
+//!   built-in cues from stage directions such as `*shrugs*`, then a facial
+//!   expression for the agent's strongest emotion
+//! - `model`: a short inference call that reads the response and answers with
+//!   JSON, falling back to the rules if the call fails
+//!
+//! ```yaml
+//! annotations:
+//!   mode: rules
+//!   rules:
+//!     - contains: "follow me"
+//!       gesture: beckon
+//!       move_to: { x: 12.0, y: 0.0, z: 4.5 }
+//! ```
+//!
+//! Rules read the response before post-processing, so stage directions still
+//! count when `strip_stage_directions` removes them from the text.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::oxyde_game::emotion::EmotionalState;
+use crate::oxyde_game::utils::Position;
+use crate::{OxydeError, Result};
+
+/// Built-in gesture cues, matched against stage directions
+const GESTURE_CUES: [(&str, &str); 9] = [
+    ("shrug", "shrug"),
+    ("nod", "nod"),
+    ("wave", "wave"),
+    ("bow", "bow"),
+    ("point", "point"),
+    ("laugh", "laugh"),
+    ("sigh", "sigh"),
+    ("shakes head", "head_shake"),
+    ("crosses arms", "cross_arms"),
+];
+
+/// Built-in facial cues, matched against stage directions
+const FACIAL_CUES: [(&str, &str); 6] = [
+    ("smile", "smile"),
+    ("grin", "smile"),
+    ("frown", "frown"),
+    ("scowl", "scowl"),
+    ("wink", "wink"),
+    ("glare", "scowl"),
+];
+
+/// Facial expression for each emotion, in emotion vector order
+const EMOTION_FACIALS: [&str; 8] = ["smile", "soft_smile", "afraid", "surprised", "sad", "disgusted", "angry", "curious"];
+
+/// Emotion strength at which the strongest emotion sets the facial expression
+const EMOTION_FACIAL_THRESHOLD: f32 = 0.3;
+
+lazy_static::lazy_static! {
+    static ref STAGE_DIRECTION: Regex = Regex::new(r"\*([^*\n]+)\*").expect("valid stage direction pattern");
+}
+
+/// Suggested animation for a response
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ResponseAnnotation {
+    /// Body gesture, such as `shrug` or `nod`
+    #[serde(default)]
+    pub gesture: Option<String>,
+
+    /// Facial expression, such as `smile`
+    #[serde(default)]
+    pub facial: Option<String>,
+
+    /// Position the NPC should walk to
+    #[serde(default)]
+    pub move_to: Option<Position>,
+}
+
+impl ResponseAnnotation {
+    /// Whether the annotation suggests nothing
+    pub fn is_empty(&self) -> bool {
+        self.gesture.is_none() && self.facial.is_none() && self.move_to.is_none()
+    }
+
+    /// Parse the JSON object a model answered with, ignoring text around it
+    pub fn from_model_output(text: &str) -> Result<Self> {
+        let json = match (text.find('{'), text.rfind('}')) {
+            (Some(start), Some(end)) if start < end => &text[start..=end],
+            _ => {
+                return Err(OxydeError::InferenceError(format!(
+                    "Annotation response is not a JSON object: {}",
+                    text
+                )))
+            }
+        };
+        serde_json::from_str(json)
+            .map_err(|e| OxydeError::InferenceError(format!("Invalid annotation response: {}", e)))
+    }
+
+    /// Fill fields that are still empty from another annotation
+    fn or(mut self, other: ResponseAnnotation) -> Self {
+        self.gesture = self.gesture.or(other.gesture);
+        self.facial = self.facial.or(other.facial);
+        self.move_to = self.move_to.or(other.move_to);
+        self
+    }
+}
+
+/// A response with its suggested animation
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AgentOutput {
+    /// Response text, as `process_input` would return it
+    pub text: String,
+
+    /// Suggested gesture, facial expression and movement
+    pub annotation: ResponseAnnotation,
+}
+
+/// How annotations are produced
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnotationMode {
+    /// Configured rules, stage-direction cues and emotions
+    #[default]
+    Rules,
+    /// A post-processing model call, falling back to the rules
+    Model,
+}
+
+/// Annotation fields set when a response contains some text
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnnotationRule {
+    /// Text the response must contain, ignoring case
+    pub contains: String,
+
+    /// Gesture to suggest
+    #[serde(default)]
+    pub gesture: Option<String>,
+
+    /// Facial expression to suggest
+    #[serde(default)]
+    pub facial: Option<String>,
+
+    /// Position to walk to
+    #[serde(default)]
+    pub move_to: Option<Position>,
+}
+
+/// Configuration for response annotations
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnnotationConfig {
+    /// How annotations are produced
+    #[serde(default)]
+    pub mode: AnnotationMode,
+
+    /// Rules checked in order; the first rule setting a field wins
+    #[serde(default)]
+    pub rules: Vec<AnnotationRule>,
+
+    /// Whether stage directions such as `*shrugs*` suggest gestures and expressions
+    #[serde(default = "default_true")]
+    pub stage_direction_cues: bool,
+
+    /// Whether the strongest emotion suggests a facial expression when nothing else does
+    #[serde(default = "default_true")]
+    pub emotion_facials: bool,
+
+    /// Maximum tokens for the annotation model call
+    #[serde(default = "default_max_tokens")]
+    pub max_tokens: usize,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_max_tokens() -> usize {
+    60
+}
+
+impl Default for AnnotationConfig {
+    fn default() -> Self {
+        Self {
+            mode: AnnotationMode::Rules,
+            rules: Vec::new(),
+            stage_direction_cues: true,
+            emotion_facials: true,
+            max_tokens: default_max_tokens(),
+        }
+    }
+}
+
+impl AnnotationConfig {
+    /// Validate the annotation configuration
+    pub fn validate(&self) -> Result<()> {
+        if self.rules.iter().any(|rule| rule.contains.trim().is_empty()) {
+            return Err(OxydeError::ConfigurationError(
+                "Annotation rules must match non-empty text".to_string(),
+            ));
+        }
+        if self.mode == AnnotationMode::Model && self.max_tokens == 0 {
+            return Err(OxydeError::ConfigurationError(
+                "Annotation max_tokens must be greater than 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// System prompt for the annotation model call
+    pub fn model_prompt(&self) -> &'static str {
+        "You direct the animation of a game character. Given a line the character says, answer with \
+         only a JSON object with the keys \"gesture\" (one word such as nod, shrug, wave, bow or point), \
+         \"facial\" (such as smile, frown, scowl or surprised) and \"move_to\" (an object with x, y and z, \
+         only when the line says the character walks somewhere). Use null for any key that does not apply."
+    }
+
+    /// Annotate a response with the rules
+    ///
+    /// # Arguments
+    ///
+    /// * `response` - Response text before post-processing
+    /// * `emotions` - Emotions of the agent after responding
+    pub fn annotate(&self, response: &str, emotions: &EmotionalState) -> ResponseAnnotation {
+        let lowered = response.to_lowercase();
+        let mut annotation = ResponseAnnotation::default();
+        for rule in &self.rules {
+            if lowered.contains(&rule.contains.to_lowercase()) {
+                annotation = annotation.or(ResponseAnnotation {
+                    gesture: rule.gesture.clone(),
+                    facial: rule.facial.clone(),
+                    move_to: rule.move_to.clone(),
+                });
+            }
+        }
+
+        if self.stage_direction_cues {
+            annotation = annotation.or(stage_direction_cues(&lowered));
+        }
+
+        if self.emotion_facials && annotation.facial.is_none() {
+            annotation.facial = emotion_facial(emotions).map(|facial| facial.to_string());
+        }
+        annotation
+    }
+
+    /// Complete an annotation from a model with the rules
+    pub fn complete(&self, model: ResponseAnnotation, response: &str, emotions: &EmotionalState) -> ResponseAnnotation {
+        model.or(self.annotate(response, emotions))
+    }
+}
+
+/// Gesture and facial expression from the first matching stage directions
+fn stage_direction_cues(response: &str) -> ResponseAnnotation {
+    let directions: Vec<&str> = STAGE_DIRECTION
+        .captures_iter(response)
+        .filter_map(|captures| captures.get(1).map(|m| m.as_str()))
+        .collect();
+    let find = |cues: &[(&str, &str)]| {
+        directions.iter().find_map(|direction| {
+            cues.iter()
+                .find(|(cue, _)| direction.contains(cue))
+                .map(|(_, name)| name.to_string())
+        })
+    };
+    ResponseAnnotation {
+        gesture: find(&GESTURE_CUES),
+        facial: find(&FACIAL_CUES),
+        move_to: None,
+    }
+}
+
+/// Facial expression for the strongest emotion, if strong enough
+fn emotion_facial(emotions: &EmotionalState) -> Option<&'static str> {
+    emotions
+        .as_vector()
+        .into_iter()
+        .zip(EMOTION_FACIALS)
+        .filter(|(value, _)| *value >= EMOTION_FACIAL_THRESHOLD)
+        .max_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, facial)| facial)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_annotates_from_rules_cues_and_emotions() {
+        let config = AnnotationConfig {
+            rules: vec![AnnotationRule {
+                contains: "follow me".to_string(),
+                gesture: Some("beckon".to_string()),
+                facial: None,
+                move_to: Some(Position { x: 12.0, y: 0.0, z: None }),
+            }],
+            ..Default::default()
+        };
+        let mut emotions = EmotionalState::new();
+
+        let annotation = config.annotate("*shrugs and smiles* Follow me, then.", &emotions);
+        assert_eq!(annotation.gesture.as_deref(), Some("beckon"));
+        assert_eq!(annotation.facial.as_deref(), Some("smile"));
+        assert_eq!(annotation.move_to.map(|p| p.x), Some(12.0));
+
+        assert!(config.annotate("Good day.", &emotions).is_empty());
+        emotions.update_emotion("anger", 0.8);
+        assert_eq!(config.annotate("Get out.", &emotions).facial.as_deref(), Some("angry"));
+
+        let model = ResponseAnnotation::from_model_output("Sure: {\"gesture\": \"bow\", \"facial\": null}").unwrap();
+        let annotation = config.complete(model, "Get out.", &emotions);
+        assert_eq!((annotation.gesture.as_deref(), annotation.facial.as_deref()), (Some("bow"), Some("angry")));
+        assert!(ResponseAnnotation::from_model_output("no idea").is_err());
+    }
+}
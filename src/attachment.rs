@@ -0,0 +1,139 @@
+//! Image attachments for player input
+//!
+//! Games can let NPCs "see" a screenshot or a described scene by passing
+//! [`ImageAttachment`]s to `Agent::process_input_with_attachments`. Each
+//! attachment is turned into a caption, in order of preference:
+//!
+//! 1. the description the game supplied with it
+//! 2. the agent's [`ImageCaptioner`], for games that caption locally
+//! 3. the cloud provider, when the configured model is vision-capable
+//!
+//! Captions are stored as episodic memories, exposed to behaviors under the
+//! `attachments` context key, and quoted in the inference prompt.
+
+use std::fmt;
+
+use async_trait::async_trait;
+use base64::Engine;
+
+use crate::agent::AgentContext;
+use crate::Result;
+
+/// Context key holding the captions of the current input's attachments
+pub const ATTACHMENTS_KEY: &str = "attachments";
+
+/// Context key holding the prompt framing for the current input's attachments
+pub const ATTACHMENT_FRAMING_KEY: &str = "attachment_framing";
+
+/// Instruction sent with an image to a vision-capable model
+pub const CAPTION_PROMPT: &str =
+    "Describe this image in one or two sentences, as someone standing in the scene would see it.";
+
+/// Model name prefixes known to accept image input
+const VISION_MODEL_PREFIXES: [&str; 6] = ["gpt-4o", "gpt-4-turbo", "gpt-4-vision", "gpt-4.1", "claude-3", "gemini"];
+
+/// Whether a cloud model accepts image input
+pub fn supports_vision(model: &str) -> bool {
+    let model = model.to_lowercase();
+    VISION_MODEL_PREFIXES.iter().any(|prefix| model.starts_with(prefix))
+}
+
+/// An image shown to the agent along with player input
+#[derive(Clone, PartialEq)]
+pub struct ImageAttachment {
+    /// MIME type of the image data, such as `image/png`
+    pub mime_type: String,
+
+    /// Encoded image data; empty for a described scene
+    pub data: Vec<u8>,
+
+    /// Caption supplied by the game, used instead of captioning the image
+    pub description: Option<String>,
+}
+
+impl ImageAttachment {
+    /// Create an attachment from encoded image data
+    pub fn new(mime_type: &str, data: Vec<u8>) -> Self {
+        Self {
+            mime_type: mime_type.to_string(),
+            data,
+            description: None,
+        }
+    }
+
+    /// Create an attachment for a scene the game describes in words
+    pub fn described(description: &str) -> Self {
+        Self {
+            mime_type: String::new(),
+            data: Vec::new(),
+            description: Some(description.to_string()),
+        }
+    }
+
+    /// Supply the caption instead of having the image captioned
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    /// The image as a `data:` URL, as vision APIs accept it
+    pub fn data_url(&self) -> String {
+        format!(
+            "data:{};base64,{}",
+            self.mime_type,
+            base64::engine::general_purpose::STANDARD.encode(&self.data)
+        )
+    }
+}
+
+impl fmt::Debug for ImageAttachment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ImageAttachment")
+            .field("mime_type", &self.mime_type)
+            .field("bytes", &self.data.len())
+            .field("description", &self.description)
+            .finish()
+    }
+}
+
+/// Captions images without a cloud provider, for example with a local model
+#[async_trait]
+pub trait ImageCaptioner: Send + Sync {
+    /// Describe an image in a sentence or two
+    async fn caption(&self, image: &ImageAttachment) -> Result<String>;
+}
+
+/// Expose attachment captions to behaviors and the inference prompt
+pub fn apply_to_context(captions: &[String], context: &mut AgentContext) {
+    if captions.is_empty() {
+        return;
+    }
+    context.insert(ATTACHMENTS_KEY.to_string(), serde_json::json!(captions));
+    context.insert(
+        ATTACHMENT_FRAMING_KEY.to_string(),
+        serde_json::json!(format!(
+            "Along with what they say, the player is showing you: {}",
+            captions.join(" ")
+        )),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attachments_encode_and_frame() {
+        let image = ImageAttachment::new("image/png", vec![0x89, b'P', b'N', b'G']);
+        assert_eq!(image.data_url(), "data:image/png;base64,iVBORw==");
+        assert!(format!("{:?}", image).contains("bytes: 4"));
+
+        assert!(supports_vision("gpt-4o-mini"));
+        assert!(!supports_vision("llama2-7b"));
+
+        let mut context = AgentContext::new();
+        apply_to_context(&["A map with an X near the river.".to_string()], &mut context);
+        assert_eq!(context[ATTACHMENTS_KEY], serde_json::json!(["A map with an X near the river."]));
+        assert!(context[ATTACHMENT_FRAMING_KEY].as_str().unwrap().ends_with("an X near the river."));
+    }
+}
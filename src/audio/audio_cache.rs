@@ -1,7 +1,12 @@
 use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::PathBuf;
 use std::time::{Duration, SystemTime};
 use serde::{Deserialize, Serialize};
 
+use super::{AudioData, AudioFormat, TTSError};
+
 /// Caches audio data with LRU eviction and statistics.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AudioCache {
@@ -207,7 +212,7 @@ impl AudioCache {
 
 /// Statistics about the audio cache.
 /// This struct contains information about the number of entries, current size, maximum size, usage percentage
-/// Statistics about the audio cache.
+#[derive(Debug, Clone)]
 pub struct CacheStats {
     /// The number of entries currently in the cache.
     pub entry_count: usize,
@@ -238,3 +243,262 @@ impl std::fmt::Display for CacheStats {
         )
     }
 }
+
+/// File extension used for audio stored in the disk cache.
+const DISK_ENTRY_EXTENSION: &str = "oxa";
+
+/// Metadata written before the audio bytes of a disk cache entry.
+#[derive(Serialize, Deserialize)]
+struct DiskEntryHeader {
+    format: AudioFormat,
+    sample_rate: u32,
+    channels: u8,
+    duration_ms: u32,
+}
+
+/// Disk tier of the audio cache.
+/// Entries survive restarts and are evicted least recently used first once the
+/// directory exceeds its size limit. Each entry is one file named after its key.
+#[derive(Debug, Clone)]
+pub struct DiskAudioCache {
+    dir: PathBuf,
+    max_size_bytes: u64,
+}
+
+/// Statistics about the disk tier of the audio cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiskCacheStats {
+    /// The number of entries stored on disk.
+    pub entry_count: usize,
+    /// The total size of the stored entries in bytes.
+    pub current_size_bytes: u64,
+    /// The maximum allowed size of the disk cache in bytes.
+    pub max_size_bytes: u64,
+}
+
+impl DiskAudioCache {
+    /// Create a disk cache in the given directory with a maximum size in megabytes.
+    /// The directory is created on the first insert.
+    pub fn new(dir: impl Into<PathBuf>, max_size_mb: usize) -> Self {
+        Self {
+            dir: dir.into(),
+            max_size_bytes: max_size_mb as u64 * 1024 * 1024,
+        }
+    }
+
+    /// Get the directory holding the cached audio.
+    pub fn dir(&self) -> &std::path::Path {
+        &self.dir
+    }
+
+    fn path_for(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.{}", key, DISK_ENTRY_EXTENSION))
+    }
+
+    /// Read audio data from the disk cache by key.
+    /// Unreadable entries are deleted and reported as misses.
+    pub fn get(&self, key: &str) -> Option<AudioData> {
+        let path = self.path_for(key);
+        let file = File::open(&path).ok()?;
+        match Self::read_entry(file) {
+            Ok(data) => {
+                // Refresh the modification time so eviction sees the entry as recently used
+                if let Ok(file) = File::options().write(true).open(&path) {
+                    let _ = file.set_modified(SystemTime::now());
+                }
+                Some(data)
+            }
+            Err(e) => {
+                log::warn!("Discarding unreadable audio cache entry {}: {}", path.display(), e);
+                let _ = fs::remove_file(&path);
+                None
+            }
+        }
+    }
+
+    fn read_entry(file: File) -> std::io::Result<AudioData> {
+        let mut reader = BufReader::new(file);
+        let mut header_line = String::new();
+        reader.read_line(&mut header_line)?;
+        let header: DiskEntryHeader = serde_json::from_str(&header_line)?;
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        Ok(AudioData {
+            format: header.format,
+            data,
+            sample_rate: header.sample_rate,
+            channels: header.channels,
+            duration_ms: header.duration_ms,
+        })
+    }
+
+    /// Write audio data to the disk cache, evicting old entries to stay within the size limit.
+    /// Entries larger than the whole cache are not stored.
+    pub fn insert(&self, key: &str, data: &AudioData) -> Result<(), TTSError> {
+        let header = serde_json::to_string(&DiskEntryHeader {
+            format: data.format,
+            sample_rate: data.sample_rate,
+            channels: data.channels,
+            duration_ms: data.duration_ms,
+        })
+        .map_err(|e| TTSError::Cache(e.to_string()))?;
+
+        let entry_size = (header.len() + 1 + data.size_bytes()) as u64;
+        if entry_size > self.max_size_bytes {
+            return Ok(());
+        }
+
+        fs::create_dir_all(&self.dir).map_err(cache_error)?;
+        self.evict_for(entry_size, key)?;
+
+        // Write to a temporary file first so readers never see a partial entry
+        let path = self.path_for(key);
+        let temp_path = path.with_extension("tmp");
+        let mut file = File::create(&temp_path).map_err(cache_error)?;
+        file.write_all(header.as_bytes())
+            .and_then(|_| file.write_all(b"\n"))
+            .and_then(|_| file.write_all(&data.data))
+            .map_err(cache_error)?;
+        fs::rename(&temp_path, &path).map_err(cache_error)
+    }
+
+    /// Remove every entry from the disk cache.
+    pub fn clear(&self) -> Result<(), TTSError> {
+        for (path, _, _) in self.entries() {
+            fs::remove_file(&path).map_err(cache_error)?;
+        }
+        Ok(())
+    }
+
+    /// Get statistics about the disk cache.
+    pub fn stats(&self) -> DiskCacheStats {
+        let entries = self.entries();
+        DiskCacheStats {
+            entry_count: entries.len(),
+            current_size_bytes: entries.iter().map(|(_, size, _)| size).sum(),
+            max_size_bytes: self.max_size_bytes,
+        }
+    }
+
+    /// List cache entries with their size and last use time.
+    fn entries(&self) -> Vec<(PathBuf, u64, SystemTime)> {
+        let Ok(read_dir) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        read_dir
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == DISK_ENTRY_EXTENSION))
+            .filter_map(|path| {
+                let metadata = fs::metadata(&path).ok()?;
+                let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                Some((path, metadata.len(), modified))
+            })
+            .collect()
+    }
+
+    /// Evict least recently used entries until `needed_bytes` more fit.
+    /// The entry being replaced does not count towards the current size.
+    fn evict_for(&self, needed_bytes: u64, key: &str) -> Result<(), TTSError> {
+        let replaced = self.path_for(key);
+        let mut entries: Vec<_> = self
+            .entries()
+            .into_iter()
+            .filter(|(path, _, _)| *path != replaced)
+            .collect();
+        let mut current: u64 = entries.iter().map(|(_, size, _)| size).sum();
+
+        entries.sort_by_key(|(_, _, modified)| *modified);
+        for (path, size, _) in entries {
+            if current + needed_bytes <= self.max_size_bytes {
+                break;
+            }
+            fs::remove_file(&path).map_err(cache_error)?;
+            current -= size;
+        }
+        Ok(())
+    }
+}
+
+fn cache_error(error: std::io::Error) -> TTSError {
+    TTSError::Cache(error.to_string())
+}
+
+/// Hit and miss counters for the two-tier TTS audio cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheHitStats {
+    /// Lookups answered from memory.
+    pub memory_hits: u64,
+    /// Lookups answered from disk.
+    pub disk_hits: u64,
+    /// Lookups that required synthesis.
+    pub misses: u64,
+}
+
+impl CacheHitStats {
+    /// Get the fraction of lookups answered by either tier, from 0.0 to 1.0.
+    pub fn hit_rate(&self) -> f64 {
+        let hits = self.memory_hits + self.disk_hits;
+        let total = hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            hits as f64 / total as f64
+        }
+    }
+}
+
+/// Statistics about both tiers of the TTS audio cache.
+#[derive(Debug, Clone)]
+pub struct TieredCacheStats {
+    /// Statistics for the in-memory tier.
+    pub memory: CacheStats,
+    /// Statistics for the disk tier, if one is configured.
+    pub disk: Option<DiskCacheStats>,
+    /// Hit and miss counters across both tiers.
+    pub hits: CacheHitStats,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn audio(bytes: usize) -> AudioData {
+        AudioData {
+            format: AudioFormat::MP3,
+            data: vec![7; bytes],
+            sample_rate: 22050,
+            channels: 1,
+            duration_ms: 500,
+        }
+    }
+
+    #[test]
+    fn test_disk_cache_round_trip_and_eviction() {
+        let dir = std::env::temp_dir().join(format!("oxyde-audio-cache-{}", uuid::Uuid::new_v4()));
+        let mut cache = DiskAudioCache::new(&dir, 1);
+        cache.max_size_bytes = 3000;
+
+        cache.insert("first", &audio(1000)).unwrap();
+        let restored = cache.get("first").unwrap();
+        assert_eq!(restored.data.len(), 1000);
+        assert_eq!(restored.duration_ms, 500);
+
+        // Age "first" so the next eviction picks it
+        File::options()
+            .write(true)
+            .open(cache.path_for("first"))
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH)
+            .unwrap();
+        cache.insert("second", &audio(1000)).unwrap();
+        cache.insert("third", &audio(1000)).unwrap();
+        assert!(cache.get("first").is_none());
+        assert!(cache.get("third").is_some());
+        assert_eq!(cache.stats().entry_count, 2);
+
+        cache.clear().unwrap();
+        assert_eq!(cache.stats().entry_count, 0);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,159 @@
+//! Voices offered by TTS providers, for picking the voice of an NPC
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use super::{TTSError, TTSProvider};
+#[cfg(feature = "tts")]
+use super::{AudioData, TTSService, VoiceSettings};
+
+/// ElevenLabs endpoint listing the voices available to an API key
+#[cfg(feature = "tts")]
+const ELEVENLABS_VOICES_URL: &str = "https://api.elevenlabs.io/v1/voices";
+
+/// A voice a provider can speak with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AvailableVoice {
+    /// Provider voice ID, as used in `BaseVoice::voice_id`
+    pub id: String,
+
+    /// Display name
+    pub name: String,
+
+    /// Provider offering the voice
+    pub provider: TTSProvider,
+
+    /// Descriptive labels, such as gender, age and accent
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+
+    /// URL of a sample clip published by the provider
+    #[serde(default)]
+    pub preview_url: Option<String>,
+}
+
+impl AvailableVoice {
+    /// Labels as `key=value` pairs sorted by key, for listings
+    pub fn label_summary(&self) -> String {
+        let mut labels: Vec<_> = self.labels.iter().map(|(key, value)| format!("{}={}", key, value)).collect();
+        labels.sort();
+        labels.join(", ")
+    }
+}
+
+impl FromStr for TTSProvider {
+    type Err = TTSError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        [Self::ElevenLabs, Self::Mock]
+            .into_iter()
+            .find(|provider| provider.as_str().eq_ignore_ascii_case(name))
+            .ok_or_else(|| TTSError::Config(format!("Unknown TTS provider '{}'; expected elevenlabs or mock", name)))
+    }
+}
+
+/// Voice list returned by ElevenLabs
+#[cfg(feature = "tts")]
+#[derive(Deserialize)]
+struct ElevenLabsVoices {
+    voices: Vec<ElevenLabsVoice>,
+}
+
+#[cfg(feature = "tts")]
+#[derive(Deserialize)]
+struct ElevenLabsVoice {
+    voice_id: String,
+    name: String,
+    #[serde(default)]
+    labels: HashMap<String, String>,
+    #[serde(default)]
+    preview_url: Option<String>,
+}
+
+#[cfg(feature = "tts")]
+impl TTSService {
+    /// List the voices the provider offers to the configured API key
+    pub async fn list_voices(&self) -> Result<Vec<AvailableVoice>, TTSError> {
+        match self.provider {
+            TTSProvider::ElevenLabs => {
+                let response = reqwest::Client::new()
+                    .get(ELEVENLABS_VOICES_URL)
+                    .header("xi-api-key", self.config.resolve_api_key()?)
+                    .send()
+                    .await?;
+                let status = response.status();
+                if !status.is_success() {
+                    let error_text = response.text().await.unwrap_or_default();
+                    return Err(TTSError::ApiError(format!(
+                        "ElevenLabs API error ({}): {}",
+                        status, error_text
+                    )));
+                }
+                let list: ElevenLabsVoices = response.json().await?;
+                Ok(list
+                    .voices
+                    .into_iter()
+                    .map(|voice| AvailableVoice {
+                        id: voice.voice_id,
+                        name: voice.name,
+                        provider: TTSProvider::ElevenLabs,
+                        labels: voice.labels,
+                        preview_url: voice.preview_url,
+                    })
+                    .collect())
+            }
+            TTSProvider::Mock => Ok(vec![AvailableVoice {
+                id: "default".to_string(),
+                name: "Silence".to_string(),
+                provider: TTSProvider::Mock,
+                labels: HashMap::from([("description".to_string(), "silent offline audio".to_string())]),
+                preview_url: None,
+            }]),
+        }
+    }
+
+    /// Synthesize a sample of a voice with neutral settings
+    ///
+    /// Samples bypass NPC voice profiles, emotional modulation and the cache.
+    pub async fn synthesize_sample(&self, voice_id: &str, text: &str) -> Result<AudioData, TTSError> {
+        if text.chars().count() > self.provider.max_request_chars() {
+            return Err(TTSError::Config(format!(
+                "Sample text is longer than {} characters",
+                self.provider.max_request_chars()
+            )));
+        }
+        let settings = VoiceSettings {
+            voice_id: voice_id.to_string(),
+            stability: 0.75,
+            similarity_boost: 0.75,
+            style_exaggeration: 0.3,
+        };
+        match self.provider {
+            TTSProvider::ElevenLabs => self.elevenlabs_synthesize(text, &settings).await,
+            TTSProvider::Mock => self.mock_synthesize(text),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provider_names_parse() {
+        assert_eq!("ElevenLabs".parse::<TTSProvider>().unwrap(), TTSProvider::ElevenLabs);
+        assert_eq!("mock".parse::<TTSProvider>().unwrap(), TTSProvider::Mock);
+        assert!("polly".parse::<TTSProvider>().is_err());
+    }
+
+    #[cfg(feature = "tts")]
+    #[tokio::test]
+    async fn test_mock_voices_synthesize_samples() {
+        let service = TTSService::new(TTSProvider::Mock, crate::audio::TTSConfig::mock());
+        let voices = service.list_voices().await.unwrap();
+        let sample = service.synthesize_sample(&voices[0].id, "Hello traveler").await.unwrap();
+        assert!(sample.duration_ms > 0);
+    }
+}
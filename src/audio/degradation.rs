@@ -0,0 +1,208 @@
+//! Graceful degradation when speech synthesis fails.
+//!
+//! A TTS outage should cost a line its voice, not the line itself. With a
+//! degradation policy, a failed synthesis is retried with a secondary
+//! provider, then answered with cached audio of a similar line the NPC has
+//! spoken before, and finally reported as text only so the game can show
+//! subtitles:
+//!
+//! ```yaml
+//! tts:
+//!   degradation:
+//!     secondary_provider: Mock
+//!     similar_audio: true
+//!     min_similarity: 0.6
+//!     text_only: true
+//! ```
+//!
+//! `Agent::speak_with_fallback` applies the policy and emits a
+//! `speech_degraded` event whenever a fallback is used.
+
+#[cfg(feature = "tts")]
+use std::collections::{HashSet, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use super::{AudioData, ConversationPacing, TTSProvider};
+
+/// Most spoken lines remembered for similar-audio fallback.
+#[cfg(feature = "tts")]
+const MAX_INDEXED_LINES: usize = 512;
+
+/// What to do when speech synthesis fails.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TTSDegradationPolicy {
+    /// Provider to retry with when the default provider fails.
+    #[serde(default)]
+    pub secondary_provider: Option<TTSProvider>,
+
+    /// Whether cached audio of a similar line by the same NPC may stand in.
+    /// Needs `cache_enabled`.
+    #[serde(default)]
+    pub similar_audio: bool,
+
+    /// Share of words two lines must have in common to count as similar
+    /// (0.0 - 1.0).
+    #[serde(default = "default_min_similarity")]
+    pub min_similarity: f32,
+
+    /// Whether to return the text without audio when every fallback fails,
+    /// rather than an error.
+    #[serde(default = "default_text_only")]
+    pub text_only: bool,
+}
+
+fn default_min_similarity() -> f32 {
+    0.6
+}
+
+fn default_text_only() -> bool {
+    true
+}
+
+impl Default for TTSDegradationPolicy {
+    fn default() -> Self {
+        Self {
+            secondary_provider: None,
+            similar_audio: false,
+            min_similarity: default_min_similarity(),
+            text_only: default_text_only(),
+        }
+    }
+}
+
+/// Where the audio of a spoken response came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpeechSource {
+    /// Synthesized by the default provider, or served from its cache.
+    Primary,
+    /// Synthesized by the secondary provider.
+    Secondary,
+    /// Cached audio of a similar line.
+    SimilarAudio,
+}
+
+impl SpeechSource {
+    /// Returns the name used in events.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Primary => "primary",
+            Self::Secondary => "secondary",
+            Self::SimilarAudio => "similar_audio",
+        }
+    }
+}
+
+/// A line as spoken, or as text when no audio could be produced.
+#[derive(Debug, Clone)]
+pub enum SpokenResponse {
+    /// Audio for the line.
+    Audio {
+        /// The audio.
+        audio: AudioData,
+        /// Where the audio came from.
+        source: SpeechSource,
+        /// Why the default provider failed, if it did.
+        error: Option<String>,
+        /// Timing of the line, for managing the microphone around it.
+        pacing: ConversationPacing,
+    },
+    /// No audio could be produced; show the text as subtitles.
+    TextOnly {
+        /// The line to display.
+        text: String,
+        /// Why synthesis failed.
+        error: String,
+        /// Timing of the line estimated from its text, for showing the
+        /// subtitles and managing the microphone.
+        pacing: ConversationPacing,
+    },
+}
+
+impl SpokenResponse {
+    /// Returns the audio, if any was produced.
+    pub fn audio(&self) -> Option<&AudioData> {
+        match self {
+            Self::Audio { audio, .. } => Some(audio),
+            Self::TextOnly { .. } => None,
+        }
+    }
+
+    /// Returns the timing of the line.
+    pub fn pacing(&self) -> &ConversationPacing {
+        match self {
+            Self::Audio { pacing, .. } | Self::TextOnly { pacing, .. } => pacing,
+        }
+    }
+
+    /// Returns whether a fallback was used.
+    pub fn is_degraded(&self) -> bool {
+        !matches!(self, Self::Audio { source: SpeechSource::Primary, .. })
+    }
+}
+
+/// A line whose audio was cached.
+#[cfg(feature = "tts")]
+#[derive(Debug, Clone)]
+struct IndexedLine {
+    npc_name: String,
+    words: HashSet<String>,
+    cache_key: String,
+}
+
+/// Recently cached lines, searched for similar audio.
+#[cfg(feature = "tts")]
+#[derive(Debug, Default)]
+pub(crate) struct LineIndex {
+    lines: VecDeque<IndexedLine>,
+}
+
+#[cfg(feature = "tts")]
+impl LineIndex {
+    /// Remember the cache key of a spoken line, replacing an older entry for the same key.
+    pub(crate) fn insert(&mut self, npc_name: &str, text: &str, cache_key: &str) {
+        self.lines.retain(|line| line.cache_key != cache_key);
+        if self.lines.len() == MAX_INDEXED_LINES {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(IndexedLine {
+            npc_name: npc_name.to_string(),
+            words: words(text),
+            cache_key: cache_key.to_string(),
+        });
+    }
+
+    /// Cache keys of lines by the NPC similar to `text`, most similar first.
+    pub(crate) fn similar(&self, npc_name: &str, text: &str, min_similarity: f32) -> Vec<String> {
+        let target = words(text);
+        let mut matches: Vec<(f32, &IndexedLine)> = self
+            .lines
+            .iter()
+            .filter(|line| line.npc_name == npc_name)
+            .map(|line| (similarity(&target, &line.words), line))
+            .filter(|(score, _)| *score >= min_similarity)
+            .collect();
+        matches.sort_by(|a, b| b.0.total_cmp(&a.0));
+        matches.into_iter().map(|(_, line)| line.cache_key.clone()).collect()
+    }
+}
+
+/// Lowercase words of a line, without punctuation.
+#[cfg(feature = "tts")]
+fn words(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Share of words two lines have in common.
+#[cfg(feature = "tts")]
+fn similarity(a: &HashSet<String>, b: &HashSet<String>) -> f32 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f32 / union as f32
+}
@@ -0,0 +1,208 @@
+use super::prosody::escape_ssml;
+use crate::utils::sentence_ends;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+/// How to pronounce a word the TTS voice would otherwise guess at
+pub struct Pronunciation {
+    /// IPA transcription, injected as an SSML `<phoneme>` tag
+    #[serde(default)]
+    pub ipa: Option<String>,
+    /// Respelling read in place of the word, such as "ZIL-eth"; used as an
+    /// SSML `<sub>` alias, and as plain text when SSML is disabled
+    #[serde(default)]
+    pub alias: Option<String>,
+}
+
+impl Pronunciation {
+    /// Pronounce a word from its IPA transcription
+    pub fn ipa(ipa: &str) -> Self {
+        Self {
+            ipa: Some(ipa.to_string()),
+            alias: None,
+        }
+    }
+
+    /// Pronounce a word by reading a respelling instead
+    pub fn alias(alias: &str) -> Self {
+        Self {
+            ipa: None,
+            alias: Some(alias.to_string()),
+        }
+    }
+
+    /// SSML markup speaking `word` with this pronunciation
+    fn to_ssml(&self, word: &str) -> String {
+        let word = escape_ssml(word);
+        match (&self.ipa, &self.alias) {
+            (Some(ipa), _) => format!("<phoneme alphabet=\"ipa\" ph=\"{}\">{}</phoneme>", escape_ssml(ipa), word),
+            (None, Some(alias)) => format!("<sub alias=\"{}\">{}</sub>", escape_ssml(alias), word),
+            (None, None) => word,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+/// Pronunciations for fantasy names and other words, keyed by word
+///
+/// Words match whole words ignoring case, so "Aelthrin" also covers
+/// "AELTHRIN" at the start of a shout. Entries may span several words,
+/// such as "Mount Vhael"; longer entries win over shorter ones.
+pub struct PronunciationLexicon {
+    entries: BTreeMap<String, Pronunciation>,
+}
+
+impl PronunciationLexicon {
+    /// Create an empty lexicon
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add or replace the pronunciation of a word
+    pub fn insert(&mut self, word: &str, pronunciation: Pronunciation) {
+        self.entries.insert(word.to_string(), pronunciation);
+    }
+
+    /// Get the pronunciation of a word, ignoring case
+    pub fn get(&self, word: &str) -> Option<&Pronunciation> {
+        self.entries
+            .iter()
+            .find(|(entry, _)| fold_case(entry).eq(fold_case(word)))
+            .map(|(_, pronunciation)| pronunciation)
+    }
+
+    /// Whether the lexicon has no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Escape text for SSML, marking up every word in the lexicon
+    pub fn to_ssml_markup(&self, text: &str) -> String {
+        self.rewrite(text, escape_ssml, |word, pronunciation| pronunciation.to_ssml(word))
+    }
+
+    /// Replace words that have an alias with it, for providers without SSML
+    pub fn respell(&self, text: &str) -> String {
+        self.rewrite(
+            text,
+            str::to_string,
+            |word, pronunciation| pronunciation.alias.clone().unwrap_or_else(|| word.to_string()),
+        )
+    }
+
+    /// Rewrite text, passing lexicon matches and the text between them
+    /// through separate functions
+    fn rewrite(
+        &self,
+        text: &str,
+        plain: impl Fn(&str) -> String,
+        matched: impl Fn(&str, &Pronunciation) -> String,
+    ) -> String {
+        if self.entries.is_empty() {
+            return plain(text);
+        }
+        let mut entries: Vec<(Vec<char>, &Pronunciation)> = self
+            .entries
+            .iter()
+            .filter(|(word, _)| !word.is_empty())
+            .map(|(word, pronunciation)| (fold_case(word).collect(), pronunciation))
+            .collect();
+        entries.sort_by_key(|(word, _)| std::cmp::Reverse(word.len()));
+
+        let chars: Vec<(usize, char)> = text.char_indices().collect();
+        let lowered: Vec<char> = fold_case(text).collect();
+        let byte_at = |index: usize| chars.get(index).map_or(text.len(), |(byte, _)| *byte);
+        let is_word_char = |index: usize| chars.get(index).is_some_and(|(_, c)| c.is_alphanumeric());
+
+        let mut output = String::with_capacity(text.len());
+        let mut copied = 0;
+        let mut index = 0;
+        while index < chars.len() {
+            if index > 0 && is_word_char(index - 1) {
+                index += 1;
+                continue;
+            }
+            let found = entries.iter().find(|(word, _)| {
+                lowered[index..].starts_with(word) && !is_word_char(index + word.len())
+            });
+            match found {
+                Some((word, pronunciation)) => {
+                    let end = index + word.len();
+                    output.push_str(&plain(&text[byte_at(copied)..byte_at(index)]));
+                    output.push_str(&matched(&text[byte_at(index)..byte_at(end)], pronunciation));
+                    copied = end;
+                    index = end;
+                }
+                None => index += 1,
+            }
+        }
+        output.push_str(&plain(&text[byte_at(copied)..]));
+        output
+    }
+}
+
+/// Lowercase text one character per character, so offsets stay aligned
+fn fold_case(text: &str) -> impl Iterator<Item = char> + '_ {
+    text.chars().map(|c| c.to_lowercase().next().unwrap_or(c))
+}
+
+/// Split text into chunks of at most `max_chars` characters
+///
+/// Chunks end at sentence boundaries where possible, then at whitespace,
+/// and only split a word that is longer than a whole chunk. Chunks are
+/// trimmed; text that already fits is returned as one chunk.
+pub fn chunk_text(text: &str, max_chars: usize) -> Vec<String> {
+    let max_chars = max_chars.max(1);
+    let mut chunks = Vec::new();
+    let mut rest = text.trim();
+    while rest.chars().count() > max_chars {
+        let limit = rest.char_indices().nth(max_chars).map_or(rest.len(), |(byte, _)| byte);
+        let window = &rest[..limit];
+        let sentence_end = sentence_ends(rest).into_iter().take_while(|end| *end <= limit).last();
+        let split = sentence_end
+            .or_else(|| window.rfind(char::is_whitespace).filter(|byte| *byte > 0))
+            .unwrap_or(limit);
+        chunks.push(rest[..split].trim_end().to_string());
+        rest = rest[split..].trim_start();
+    }
+    if !rest.is_empty() {
+        chunks.push(rest.to_string());
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lexicon_marks_up_whole_words() {
+        let mut lexicon = PronunciationLexicon::new();
+        lexicon.insert("Aelthrin", Pronunciation::ipa("ˈeɪlθrɪn"));
+        lexicon.insert("Xyl'eth", Pronunciation::alias("ZIL-eth"));
+        lexicon.insert("Mount Vhael", Pronunciation::alias("Mount Vale"));
+
+        assert_eq!(
+            lexicon.to_ssml_markup("AELTHRIN & Xyl'eth climb Mount Vhael, not Aelthrins."),
+            "<phoneme alphabet=\"ipa\" ph=\"ˈeɪlθrɪn\">AELTHRIN</phoneme> &amp; \
+             <sub alias=\"ZIL-eth\">Xyl&apos;eth</sub> climb \
+             <sub alias=\"Mount Vale\">Mount Vhael</sub>, not Aelthrins."
+        );
+        assert_eq!(lexicon.respell("Ask Xyl'eth about Aelthrin."), "Ask ZIL-eth about Aelthrin.");
+    }
+
+    #[test]
+    fn test_chunks_split_at_sentence_boundaries() {
+        let text = "The gate is shut. Nobody passes tonight! \"Why?\" you ask. Orders.";
+        assert_eq!(chunk_text(text, 100), vec![text]);
+        assert_eq!(
+            chunk_text(text, 45),
+            vec!["The gate is shut. Nobody passes tonight!", "\"Why?\" you ask. Orders."]
+        );
+        // Without a sentence end in reach, chunks end between words
+        assert_eq!(chunk_text("one two three four", 9), vec!["one two", "three", "four"]);
+        assert_eq!(chunk_text("abcdefgh", 3), vec!["abc", "def", "gh"]);
+    }
+}
@@ -1,23 +1,64 @@
+#[cfg(feature = "tts")]
 use crate::oxyde_game::emotion::EmotionalState;
+use crate::secrets::SecretRef;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::path::PathBuf;
+#[cfg(feature = "tts")]
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "tts")]
 use tokio::sync::RwLock;
 
 /// Audio cache management module.
 pub mod audio_cache;
+/// Provider voice catalog module.
+pub mod catalog;
+/// TTS failure degradation module.
+pub mod degradation;
 /// Emotion modeling module.
 pub mod emotion;
+/// Pronunciation lexicon and text chunking module.
+pub mod lexicon;
+/// Conversational pacing module.
+pub mod pacing;
+/// PCM and WAV conversion module.
+pub mod pcm;
+/// Emotion-driven SSML prosody module.
+pub mod prosody;
 /// TTS providers module.
 pub mod providers;
+/// Chunked streaming synthesis module.
+pub mod streaming;
 /// Voice profiles module.
 pub mod voice_profiles;
 
 pub use audio_cache::*;
+pub use catalog::*;
+pub use degradation::*;
 // pub use emotion::EmotionalState;
+pub use lexicon::*;
+pub use pacing::*;
+pub use pcm::*;
+pub use prosody::*;
 pub use providers::*;
+pub use streaming::*;
 pub use voice_profiles::*;
 
+/// Sample rate of the MP3 audio ElevenLabs returns, in Hz.
+#[cfg(feature = "tts")]
+const ELEVENLABS_SAMPLE_RATE: u32 = 22050;
+
+/// Sample rates ElevenLabs can return PCM at, in Hz.
+#[cfg(feature = "tts")]
+const ELEVENLABS_PCM_RATES: [u32; 5] = [8000, 16000, 22050, 24000, 44100];
+
+/// Sample rate of the Ogg Opus audio ElevenLabs returns, in Hz.
+#[cfg(feature = "tts")]
+const ELEVENLABS_OPUS_SAMPLE_RATE: u32 = 48000;
+
+/// Most characters ElevenLabs accepts in one request.
+const ELEVENLABS_MAX_REQUEST_CHARS: usize = 5000;
+
 /// Represents audio data generated by TTS synthesis.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AudioData {
@@ -41,14 +82,26 @@ impl AudioData {
 }
 
 /// Represents the settings for voice synthesis.
+///
+/// Requires the `tts` feature; without it, TTS configuration is parsed but
+/// no speech is synthesized.
+#[cfg(feature = "tts")]
 #[derive(Debug, Clone)]
 pub struct TTSService {
     /// The TTS provider being used (ElevenLabs).
     provider: TTSProvider,
     /// Shared audio cache for storing synthesized audio.
     pub cache: Arc<RwLock<AudioCache>>,
+    /// Disk tier of the audio cache, used when `cache_dir` is configured.
+    disk_cache: Option<DiskAudioCache>,
+    /// Hit and miss counters for both cache tiers.
+    cache_hits: Arc<Mutex<CacheHitStats>>,
     /// Shared voice profiles for NPCs.
     voice_profiles: Arc<RwLock<HashMap<String, VoiceProfile>>>,
+    /// Service for the secondary provider of the degradation policy.
+    secondary: Option<Arc<TTSService>>,
+    /// Cached lines, searched for similar audio when synthesis fails.
+    lines: Arc<Mutex<LineIndex>>,
     /// Configuration for the TTS service.
     config: TTSConfig,
 }
@@ -60,6 +113,28 @@ pub struct TTSService {
 pub enum TTSProvider {
     /// ElevenLabs TTS provider.
     ElevenLabs,
+    /// Silent audio of the length the line would take to speak, for offline
+    /// demos and tests. Needs no API key and produces PCM16 or WAV only.
+    Mock,
+}
+
+impl TTSProvider {
+    /// Returns the provider name used as a key in voice ID maps.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ElevenLabs => "elevenlabs",
+            Self::Mock => "mock",
+        }
+    }
+
+    /// Returns the most characters of text, including SSML markup, the
+    /// provider accepts in one request.
+    pub fn max_request_chars(&self) -> usize {
+        match self {
+            // The mock provider chunks text as ElevenLabs would
+            Self::ElevenLabs | Self::Mock => ELEVENLABS_MAX_REQUEST_CHARS,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -76,8 +151,14 @@ pub struct TTSConfig {
 
     /// Maximum size of the audio cache in megabytes.
     /// This limits the amount of audio data that can be cached to prevent excessive memory usage.
+    /// The limit applies to the memory and disk tiers separately.
     pub cache_max_size_mb: usize,
 
+    /// Directory for the disk tier of the audio cache.
+    /// When set, synthesized audio is also stored on disk and reused across restarts.
+    #[serde(default)]
+    pub cache_dir: Option<PathBuf>,
+
     /// Default voice settings for TTS synthesis.
     /// These settings define the characteristics of the voice used for synthesis.
     pub voice_speed: f32,
@@ -90,30 +171,176 @@ pub struct TTSConfig {
 
     /// The output audio format for TTS synthesis.
     pub output_format: AudioFormat,
+
+    /// Sample rate for PCM16 and WAV output in Hz, such as the rate the game
+    /// mixes at. Audio is converted when the provider cannot produce it
+    /// directly. Defaults to 22050.
+    #[serde(default)]
+    pub output_sample_rate: Option<u32>,
+
+    /// Voice profiles for individual NPCs, keyed by NPC name.
+    /// NPCs without a profile fall back to a default voice.
+    #[serde(default)]
+    pub voice_profiles: HashMap<String, VoiceProfile>,
+
+    /// Mapping from emotional state and urgency to SSML prosody.
+    #[serde(default)]
+    pub prosody: ProsodyMapping,
+
+    /// Where the provider API key is stored, such as
+    /// `file:/run/secrets/oxyde.json#elevenlabs`.
+    /// Defaults to the `ELEVENLABS_API_KEY` environment variable.
+    #[serde(default)]
+    pub api_key_ref: Option<SecretRef>,
+
+    /// Pronunciations for fantasy names and other words the voice would
+    /// mangle, injected as SSML when `enable_ssml` is set. Aliases are
+    /// also read in place of the word without SSML.
+    #[serde(default)]
+    pub pronunciations: PronunciationLexicon,
+
+    /// Most characters of text sent in one request; longer lines are split
+    /// at sentence boundaries and synthesized in chunks. Defaults to the
+    /// provider's limit, halved with SSML to leave room for markup.
+    #[serde(default)]
+    pub max_chunk_chars: Option<usize>,
+
+    /// What to do when synthesis fails; see [`TTSDegradationPolicy`].
+    #[serde(default)]
+    pub degradation: TTSDegradationPolicy,
+
+    /// Speaking rate, pauses and listen windows used to pace spoken
+    /// responses; see [`ConversationPacing`].
+    #[serde(default)]
+    pub pacing: PacingConfig,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl TTSConfig {
+    /// Resolve the provider API key
+    pub fn resolve_api_key(&self) -> Result<String, TTSError> {
+        match &self.api_key_ref {
+            Some(reference) => reference
+                .resolve()
+                .map(|secret| secret.into_inner())
+                .map_err(|e| TTSError::Config(e.to_string())),
+            None => std::env::var("ELEVENLABS_API_KEY").map_err(|_| TTSError::MissingApiKey("ElevenLabs")),
+        }
+    }
+
+    /// Configuration for the mock provider, producing uncached WAV audio
+    pub fn mock() -> Self {
+        Self {
+            default_provider: TTSProvider::Mock,
+            cache_enabled: false,
+            cache_max_size_mb: 10,
+            cache_dir: None,
+            voice_speed: 1.0,
+            voice_pitch: 1.0,
+            enable_ssml: false,
+            output_format: AudioFormat::WAV,
+            output_sample_rate: None,
+            voice_profiles: HashMap::new(),
+            prosody: Default::default(),
+            api_key_ref: None,
+            pronunciations: Default::default(),
+            max_chunk_chars: None,
+            degradation: Default::default(),
+            pacing: Default::default(),
+        }
+    }
+
+    /// Most characters of text sent to the provider in one request
+    pub fn chunk_chars(&self) -> usize {
+        let limit = self.default_provider.max_request_chars();
+        let default = if self.enable_ssml { limit / 2 } else { limit };
+        self.max_chunk_chars.unwrap_or(default).clamp(1, limit)
+    }
+}
 
 /// Represents the audio format used in TTS synthesis.
-/// Currently, only MP3 is supported. can update this to support more formats in the future.
+/// PCM16 and WAV can be fed to engine audio systems without decoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum AudioFormat {
     /// MP3 audio format.
     MP3,
+    /// 16-bit PCM in a WAV container.
+    WAV,
+    /// Raw interleaved little-endian 16-bit PCM samples.
+    PCM16,
+    /// Opus audio in an Ogg container, always at 48 kHz.
+    OGG,
 }
 
+impl AudioFormat {
+    /// Returns the MIME type of the format.
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            Self::MP3 => "audio/mpeg",
+            Self::WAV => "audio/wav",
+            Self::PCM16 => "audio/pcm",
+            Self::OGG => "audio/ogg",
+        }
+    }
+
+    /// Returns the file extension for the format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::MP3 => "mp3",
+            Self::WAV => "wav",
+            Self::PCM16 => "pcm",
+            Self::OGG => "ogg",
+        }
+    }
+
+    /// Returns whether the format holds PCM samples that need no decoding.
+    pub fn is_uncompressed(&self) -> bool {
+        matches!(self, Self::WAV | Self::PCM16)
+    }
+}
+
+#[cfg(feature = "tts")]
 impl TTSService {
     /// Create a new TTS service instance with the specified provider and configuration.
     /// This initializes the TTS service with the given provider and configuration settings.
     pub fn new(provider: TTSProvider, config: TTSConfig) -> Self {
+        let disk_cache = config
+            .cache_dir
+            .as_ref()
+            .map(|dir| DiskAudioCache::new(dir, config.cache_max_size_mb));
+        let secondary = config
+            .degradation
+            .secondary_provider
+            .clone()
+            .filter(|secondary| *secondary != provider)
+            .map(|secondary| {
+                // The secondary neither degrades further nor shares the disk cache
+                let config = TTSConfig {
+                    default_provider: secondary.clone(),
+                    cache_dir: None,
+                    degradation: TTSDegradationPolicy {
+                        secondary_provider: None,
+                        similar_audio: false,
+                        ..config.degradation.clone()
+                    },
+                    ..config.clone()
+                };
+                Arc::new(TTSService::new(secondary, config))
+            });
+
         Self {
             provider,
             cache: Arc::new(RwLock::new(AudioCache::new(config.cache_max_size_mb))),
-            voice_profiles: Arc::new(RwLock::new(HashMap::new())),
+            disk_cache,
+            cache_hits: Arc::new(Mutex::new(CacheHitStats::default())),
+            voice_profiles: Arc::new(RwLock::new(config.voice_profiles.clone())),
+            secondary,
+            lines: Arc::new(Mutex::new(LineIndex::default())),
             config,
         }
     }
 
     /// Main method: Convert NPC dialogue to speech with emotional context
+    #[tracing::instrument(name = "tts.synthesize", skip_all, fields(npc = npc_name))]
     pub async fn synthesize_npc_speech(
         &self,
         npc_name: &str,
@@ -121,15 +348,241 @@ impl TTSService {
         emotional_state: &EmotionalState, // Use the main SDK's EmotionalState
         urgency: f32,
     ) -> Result<AudioData, TTSError> {
-        // Check cache first
-        let cache_key = self.generate_cache_key(npc_name, text, emotional_state);
+        let (chunks, voice_settings, cache_key) =
+            self.prepare_speech(npc_name, text, emotional_state, urgency).await;
+
+        // Check cache before synthesizing
         if self.config.cache_enabled {
-            let mut cache = self.cache.write().await;
-            if let Some(cached_audio) = cache.get(&cache_key) {
+            if let Some(cached_audio) = self.cached_audio(&cache_key).await {
+                self.index_line(npc_name, text, &cache_key);
                 return Ok(cached_audio);
             }
         }
 
+        // Generate speech with ElevenLabs, one request per chunk
+        let mut parts = Vec::with_capacity(chunks.len());
+        for chunk in &chunks {
+            parts.push(match self.provider {
+                TTSProvider::ElevenLabs => self.elevenlabs_synthesize(chunk, &voice_settings).await?,
+                TTSProvider::Mock => self.mock_synthesize(chunk)?,
+            });
+        }
+        let audio_data = AudioData::concat(parts)?;
+
+        // Cache the result
+        if self.config.cache_enabled {
+            self.index_line(npc_name, text, &cache_key);
+            self.store_cached_audio(cache_key, &audio_data).await;
+        }
+
+        Ok(audio_data)
+    }
+
+    /// Convert NPC dialogue to speech, degrading as the configured
+    /// [`TTSDegradationPolicy`] allows when synthesis fails.
+    /// Fallbacks are tried in order: the secondary provider, cached audio of
+    /// a similar line by the same NPC, and the text without audio.
+    pub async fn synthesize_or_degrade(
+        &self,
+        npc_name: &str,
+        text: &str,
+        emotional_state: &EmotionalState,
+        urgency: f32,
+    ) -> Result<SpokenResponse, TTSError> {
+        let error = match self.synthesize_npc_speech(npc_name, text, emotional_state, urgency).await {
+            Ok(audio) => {
+                let pacing = self.pacing(text, Some(&audio));
+                return Ok(SpokenResponse::Audio { audio, source: SpeechSource::Primary, error: None, pacing });
+            }
+            Err(e) => e,
+        };
+        log::warn!("Speech synthesis for {} failed: {}", npc_name, error);
+        let policy = &self.config.degradation;
+
+        if let Some(secondary) = &self.secondary {
+            match secondary.synthesize_npc_speech(npc_name, text, emotional_state, urgency).await {
+                Ok(audio) => {
+                    return Ok(SpokenResponse::Audio {
+                        pacing: self.pacing(text, Some(&audio)),
+                        audio,
+                        source: SpeechSource::Secondary,
+                        error: Some(error.to_string()),
+                    });
+                }
+                Err(e) => log::warn!("Secondary TTS provider failed for {}: {}", npc_name, e),
+            }
+        }
+
+        if policy.similar_audio && self.config.cache_enabled {
+            let keys = self
+                .lines
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .similar(npc_name, text, policy.min_similarity);
+            for key in keys {
+                if let Some(audio) = self.cached_audio(&key).await {
+                    return Ok(SpokenResponse::Audio {
+                        pacing: self.pacing(text, Some(&audio)),
+                        audio,
+                        source: SpeechSource::SimilarAudio,
+                        error: Some(error.to_string()),
+                    });
+                }
+            }
+        }
+
+        if policy.text_only {
+            return Ok(SpokenResponse::TextOnly {
+                text: text.to_string(),
+                error: error.to_string(),
+                pacing: self.pacing(text, None),
+            });
+        }
+        Err(error)
+    }
+
+    /// Pace a line for the microphone, measuring its audio if there is any.
+    ///
+    /// Only PCM16 and WAV audio can be measured; compressed audio is paced
+    /// from the text, like a line without audio. Similar-audio fallbacks say
+    /// a different line, so their pauses are only approximate.
+    pub fn pacing(&self, text: &str, audio: Option<&AudioData>) -> ConversationPacing {
+        let measured = audio.and_then(AudioData::measured_duration_ms);
+        self.config.pacing.pace(text, self.config.voice_speed, measured)
+    }
+
+    /// Remember a cached line for similar-audio fallback.
+    fn index_line(&self, npc_name: &str, text: &str, cache_key: &str) {
+        if self.config.degradation.similar_audio {
+            let mut lines = self.lines.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            lines.insert(npc_name, text, cache_key);
+        }
+    }
+
+    /// Convert NPC dialogue to speech, yielding audio chunks as they are generated.
+    /// Cached speech is replayed in chunks; otherwise the provider's streaming API
+    /// is used and the complete audio is cached once the stream finishes.
+    /// Text too long for one request is streamed one request after another.
+    /// Errors before the first chunk are returned directly; later errors end the stream.
+    #[tracing::instrument(name = "tts.synthesize_streaming", skip_all, fields(npc = npc_name))]
+    pub async fn synthesize_npc_speech_streaming(
+        &self,
+        npc_name: &str,
+        text: &str,
+        emotional_state: &EmotionalState,
+        urgency: f32,
+    ) -> Result<AudioStream, TTSError> {
+        let (chunks, voice_settings, cache_key) =
+            self.prepare_speech(npc_name, text, emotional_state, urgency).await;
+
+        if self.config.cache_enabled {
+            if let Some(cached_audio) = self.cached_audio(&cache_key).await {
+                self.index_line(npc_name, text, &cache_key);
+                return Ok(AudioStream::from_audio(cached_audio));
+            }
+        }
+
+        let (output_format, provider_rate) = self.elevenlabs_output_format();
+        let mut remaining = chunks.clone().into_iter();
+        let first = remaining.next().unwrap_or_default();
+        let mut response = match self.provider {
+            TTSProvider::ElevenLabs => {
+                self.elevenlabs_request(&first, &voice_settings, &output_format, true)
+                    .await?
+            }
+            TTSProvider::Mock => {
+                let parts = chunks.iter().map(|chunk| self.mock_synthesize(chunk)).collect::<Result<_, _>>()?;
+                return Ok(AudioStream::from_audio(AudioData::concat(parts)?));
+            }
+        };
+
+        let format = self.config.output_format;
+        // Uncompressed output is converted chunk by chunk as it arrives
+        let (mut encoder, sample_rate) = if format.is_uncompressed() {
+            let encoder = PcmStreamEncoder::new(format, provider_rate, self.output_sample_rate(), 1);
+            (Some(encoder), self.output_sample_rate())
+        } else {
+            (None, provider_rate)
+        };
+        let (sender, stream) = AudioStream::channel(format, sample_rate, 1);
+        let duration_ms = self.estimate_duration(&chunks.concat());
+        let service = self.clone();
+        let (npc_name, text) = (npc_name.to_string(), text.to_string());
+        tokio::spawn(async move {
+            let mut data = Vec::new();
+            let mut index = 0;
+            loop {
+                let (bytes, finished) = match response.chunk().await {
+                    Ok(Some(bytes)) => match encoder.as_mut() {
+                        Some(encoder) => (encoder.push(&bytes), false),
+                        None => (bytes.to_vec(), false),
+                    },
+                    Ok(None) => match remaining.next() {
+                        // Continue with the next chunk of text
+                        Some(chunk) => {
+                            match service
+                                .elevenlabs_request(&chunk, &voice_settings, &output_format, true)
+                                .await
+                            {
+                                Ok(next) => response = next,
+                                Err(e) => {
+                                    let _ = sender.send(Err(e)).await;
+                                    return;
+                                }
+                            }
+                            continue;
+                        }
+                        None => (encoder.as_mut().map(PcmStreamEncoder::finish).unwrap_or_default(), true),
+                    },
+                    Err(e) => {
+                        let _ = sender.send(Err(TTSError::Network(e))).await;
+                        return;
+                    }
+                };
+                if !bytes.is_empty() {
+                    if encoder.is_none() {
+                        data.extend_from_slice(&bytes);
+                    }
+                    let chunk = AudioChunk { index, data: bytes };
+                    if sender.send(Ok(chunk)).await.is_err() {
+                        // The consumer went away; don't cache partial audio
+                        return;
+                    }
+                    index += 1;
+                }
+                if finished {
+                    break;
+                }
+            }
+
+            if service.config.cache_enabled {
+                let audio = match encoder {
+                    Some(encoder) => encoder.into_audio(),
+                    None => AudioData {
+                        format,
+                        data,
+                        sample_rate,
+                        channels: 1,
+                        duration_ms,
+                    },
+                };
+                service.index_line(&npc_name, &text, &cache_key);
+                service.store_cached_audio(cache_key, &audio).await;
+            }
+        });
+
+        Ok(stream)
+    }
+
+    /// Resolve the voice settings, cache key, and the SSML-enhanced text for
+    /// a line, split into chunks that fit in one provider request.
+    async fn prepare_speech(
+        &self,
+        npc_name: &str,
+        text: &str,
+        emotional_state: &EmotionalState,
+        urgency: f32,
+    ) -> (Vec<String>, VoiceSettings, String) {
         // Get voice profile for this NPC
         let voice_profile = self.get_voice_profile(npc_name).await;
 
@@ -137,28 +590,75 @@ impl TTSService {
         let voice_settings =
             self.modulate_voice_for_emotion(&voice_profile, emotional_state, urgency);
 
-        // Enhance text with SSML for emotional expression
-        let enhanced_text = if self.config.enable_ssml {
-            self.add_emotional_ssml(text, emotional_state, urgency)
-        } else {
-            text.to_string()
-        };
+        // Enhance each chunk with SSML for pronunciation and emotional expression
+        let mut pieces = chunk_text(text, self.config.chunk_chars());
+        if pieces.is_empty() {
+            pieces.push(String::new());
+        }
+        let chunks: Vec<String> = pieces
+            .iter()
+            .map(|chunk| {
+                if self.config.enable_ssml {
+                    self.add_emotional_ssml(chunk, emotional_state, urgency)
+                } else {
+                    self.config.pronunciations.respell(chunk)
+                }
+            })
+            .collect();
 
-        // Generate speech with ElevenLabs
-        let audio_data = match self.provider {
-            TTSProvider::ElevenLabs => {
-                self.elevenlabs_synthesize(&enhanced_text, &voice_settings)
-                    .await?
+        let cache_key = self.generate_cache_key(&chunks.concat(), &voice_settings);
+        (chunks, voice_settings, cache_key)
+    }
+
+    /// Store synthesized audio in both cache tiers.
+    async fn store_cached_audio(&self, key: String, audio: &AudioData) {
+        if let Some(disk_cache) = &self.disk_cache {
+            if let Err(e) = disk_cache.insert(&key, audio) {
+                log::warn!("Failed to write audio to disk cache: {}", e);
             }
-        };
+        }
+        self.cache.write().await.insert(key, audio.clone());
+    }
 
-        // Cache the result
-        if self.config.cache_enabled {
-            let mut cache = self.cache.write().await;
-            cache.insert(cache_key, audio_data.clone());
+    /// Look up audio in the memory tier, then the disk tier.
+    /// Disk hits are promoted to memory.
+    async fn cached_audio(&self, key: &str) -> Option<AudioData> {
+        if let Some(audio) = self.cache.write().await.get(key) {
+            self.record_cache_lookup(|hits| hits.memory_hits += 1);
+            return Some(audio);
         }
 
-        Ok(audio_data)
+        if let Some(audio) = self.disk_cache.as_ref().and_then(|disk| disk.get(key)) {
+            self.record_cache_lookup(|hits| hits.disk_hits += 1);
+            self.cache.write().await.insert(key.to_string(), audio.clone());
+            return Some(audio);
+        }
+
+        self.record_cache_lookup(|hits| hits.misses += 1);
+        None
+    }
+
+    fn record_cache_lookup(&self, update: impl FnOnce(&mut CacheHitStats)) {
+        update(&mut self.cache_hits.lock().unwrap_or_else(|poisoned| poisoned.into_inner()));
+    }
+
+    /// Get statistics for both cache tiers, including hit and miss counts.
+    pub async fn cache_stats(&self) -> TieredCacheStats {
+        TieredCacheStats {
+            memory: self.cache.read().await.stats(),
+            disk: self.disk_cache.as_ref().map(DiskAudioCache::stats),
+            hits: *self.cache_hits.lock().unwrap_or_else(|poisoned| poisoned.into_inner()),
+        }
+    }
+
+    /// Remove all cached audio from memory and disk.
+    /// Hit and miss counters are kept.
+    pub async fn clear_cache(&self) -> Result<(), TTSError> {
+        self.cache.write().await.clear();
+        match &self.disk_cache {
+            Some(disk_cache) => disk_cache.clear(),
+            None => Ok(()),
+        }
     }
 
     /// Simplified voice profile creation
@@ -174,6 +674,7 @@ impl TTSService {
                 base_pitch: 0.5,
                 base_rate: 0.5,
                 base_volume: 0.7,
+                provider_voice_ids: HashMap::new(),
             },
             emotional_range: EmotionalVoiceRange::from_personality(personality),
         };
@@ -185,6 +686,23 @@ impl TTSService {
         voice_profile
     }
 
+    /// Register or replace the voice profile for an NPC.
+    /// The profile is keyed by its `npc_name`.
+    pub async fn set_voice_profile(&self, profile: VoiceProfile) {
+        let mut profiles = self.voice_profiles.write().await;
+        profiles.insert(profile.npc_name.clone(), profile);
+    }
+
+    /// Replace the emotion-to-prosody mapping used for SSML generation.
+    pub fn set_prosody_mapping(&mut self, mapping: ProsodyMapping) {
+        self.config.prosody = mapping;
+    }
+
+    /// Add or replace the pronunciation of a word, such as an NPC's name.
+    pub fn set_pronunciation(&mut self, word: &str, pronunciation: Pronunciation) {
+        self.config.pronunciations.insert(word, pronunciation);
+    }
+
     // Simplified emotional modulation (only using basic VoiceSettings)
     fn modulate_voice_for_emotion(
         &self,
@@ -193,6 +711,7 @@ impl TTSService {
         _urgency: f32, // Unused for now
     ) -> VoiceSettings {
         let mut settings = VoiceSettings::from_profile(base_profile);
+        settings.voice_id = base_profile.base_voice.voice_id_for(&self.provider).to_string();
 
         let joy = (e.joy + 1.0) * 0.5;
         let anger = (e.anger + 1.0) * 0.5;
@@ -231,53 +750,111 @@ impl TTSService {
         emotions: &EmotionalState, // Use the main SDK's EmotionalState
         urgency: f32,
     ) -> String {
-        let mut ssml = String::from("<speak>");
+        let markup = self.config.pronunciations.to_ssml_markup(text);
+        self.config.prosody.prosody_for(emotions, urgency).wrap_ssml(&markup)
+    }
 
-        // Add prosody based on emotions
-        let mut prosody_attrs = Vec::new();
+    async fn elevenlabs_synthesize(
+        &self,
+        text: &str,
+        settings: &VoiceSettings,
+    ) -> Result<AudioData, TTSError> {
+        let (output_format, provider_rate) = self.elevenlabs_output_format();
+        let response = self.elevenlabs_request(text, settings, &output_format, false).await?;
+        let headers = response.headers().clone();
+        let audio_bytes = response.bytes().await.map_err(|e| TTSError::Network(e))?;
 
-        if emotions.joy > 0.6 {
-            prosody_attrs.push(format!("rate=\"{:.0}%\"", 100.0 + (emotions.joy * 20.0)));
-            prosody_attrs.push(format!("pitch=\"+{:.0}Hz\"", emotions.joy * 30.0));
+        if let Some(content_type) = headers.get("content-type") {
+            let content_type_str = content_type.to_str().unwrap_or("");
+            if !content_type_str.starts_with("audio/") {
+                let error_text = String::from_utf8_lossy(&audio_bytes);
+                return Err(TTSError::ApiError(format!(
+                    "Expected audio content but received '{}': {}",
+                    content_type_str, error_text
+                )));
+            }
         }
 
-        if emotions.anger > 0.5 {
-            prosody_attrs.push(format!("rate=\"{:.0}%\"", 100.0 + (emotions.anger * 25.0)));
-            prosody_attrs.push(format!(
-                "volume=\"{:.0}%\"",
-                100.0 + (emotions.anger * 15.0)
-            ));
+        if audio_bytes.len() < 100 {
+            let text_content = String::from_utf8_lossy(&audio_bytes);
+            return Err(TTSError::ApiError(format!(
+                "Response too small ({} bytes), likely an error: {}",
+                audio_bytes.len(),
+                text_content
+            )));
         }
 
-        if emotions.fear > 0.5 {
-            prosody_attrs.push(format!("pitch=\"+{:.0}Hz\"", emotions.fear * 40.0));
-            prosody_attrs.push(format!("rate=\"{:.0}%\"", 100.0 - (emotions.fear * 10.0)));
+        if self.config.output_format.is_uncompressed() {
+            return Ok(AudioData::from_pcm16(
+                self.config.output_format,
+                resample_pcm16(&audio_bytes, provider_rate, self.output_sample_rate(), 1),
+                self.output_sample_rate(),
+                1,
+            ));
         }
 
-        if urgency > 0.5 {
-            prosody_attrs.push(format!("rate=\"{:.0}%\"", 100.0 + (urgency * 30.0)));
-        }
+        if self.config.output_format == AudioFormat::MP3 && audio_bytes.len() >= 3 {
+            let has_id3 = &audio_bytes[0..3] == b"ID3";
+            let has_mp3_sync = audio_bytes.len() >= 2
+                && (audio_bytes[0] == 0xFF && (audio_bytes[1] & 0xE0) == 0xE0);
 
-        if !prosody_attrs.is_empty() {
-            ssml.push_str(&format!("<prosody {}>", prosody_attrs.join(" ")));
-            ssml.push_str(text);
-            ssml.push_str("</prosody>");
-        } else {
-            ssml.push_str(text);
+            if !has_id3 && !has_mp3_sync {
+                let text_content =
+                    String::from_utf8_lossy(&audio_bytes[0..100.min(audio_bytes.len())]);
+                if text_content.contains("error") || text_content.contains("detail") {
+                    return Err(TTSError::ApiError(format!(
+                        "Received error response instead of audio: {}",
+                        text_content
+                    )));
+                }
+                log::warn!("Audio data doesn't have standard MP3 headers but proceeding");
+            }
         }
 
-        ssml.push_str("</speak>");
-        ssml
+        Ok(AudioData {
+            format: self.config.output_format,
+            data: audio_bytes.to_vec(),
+            sample_rate: provider_rate,
+            channels: 1,
+            duration_ms: self.estimate_duration(text),
+        })
+    }
+
+    /// Sample rate PCM16 and WAV output is converted to.
+    fn output_sample_rate(&self) -> u32 {
+        self.config.output_sample_rate.unwrap_or(ELEVENLABS_SAMPLE_RATE)
     }
 
-    async fn elevenlabs_synthesize(
+    /// The ElevenLabs `output_format` parameter for the configured format,
+    /// and the sample rate of the audio it returns.
+    /// PCM is requested at the lowest rate not below the output rate.
+    fn elevenlabs_output_format(&self) -> (String, u32) {
+        match self.config.output_format {
+            AudioFormat::MP3 => (String::new(), ELEVENLABS_SAMPLE_RATE),
+            AudioFormat::WAV | AudioFormat::PCM16 => {
+                let target = self.output_sample_rate();
+                let rate = ELEVENLABS_PCM_RATES
+                    .iter()
+                    .copied()
+                    .find(|rate| *rate >= target)
+                    .unwrap_or(ELEVENLABS_PCM_RATES[ELEVENLABS_PCM_RATES.len() - 1]);
+                (format!("pcm_{}", rate), rate)
+            }
+            AudioFormat::OGG => (format!("opus_{}_64", ELEVENLABS_OPUS_SAMPLE_RATE), ELEVENLABS_OPUS_SAMPLE_RATE),
+        }
+    }
+
+    /// Send a synthesis request to ElevenLabs and check its status.
+    /// The streaming endpoint returns audio as it is generated.
+    async fn elevenlabs_request(
         &self,
         text: &str,
         settings: &VoiceSettings,
-    ) -> Result<AudioData, TTSError> {
+        output_format: &str,
+        stream: bool,
+    ) -> Result<reqwest::Response, TTSError> {
         let client = reqwest::Client::new();
-        let api_key = std::env::var("ELEVENLABS_API_KEY")
-            .map_err(|_| TTSError::MissingApiKey("ElevenLabs"))?;
+        let api_key = self.config.resolve_api_key()?;
 
         // Use a valid ElevenLabs voice ID
         let voice_id = if settings.voice_id == "default" {
@@ -297,75 +874,50 @@ impl TTSService {
             }
         });
 
-        let url = format!("https://api.elevenlabs.io/v1/text-to-speech/{}", voice_id);
+        let mut url = format!("https://api.elevenlabs.io/v1/text-to-speech/{}", voice_id);
+        if stream {
+            url.push_str("/stream");
+        }
+        if !output_format.is_empty() {
+            url.push_str("?output_format=");
+            url.push_str(output_format);
+        }
 
         let response = client
             .post(&url)
-            .header("Accept", "audio/mpeg")
+            .header("Accept", self.config.output_format.mime_type())
             .header("xi-api-key", api_key)
             .header("Content-Type", "application/json")
             .json(&request_body)
             .send()
             .await
-            .map_err(|e| TTSError::Network(e))?;
+            .map_err(TTSError::Network)?;
 
         let status = response.status();
-        let headers = response.headers().clone();
-        let audio_bytes = response.bytes().await.map_err(|e| TTSError::Network(e))?;
-
         if !status.is_success() {
-            let error_text = String::from_utf8_lossy(&audio_bytes);
+            let error_bytes = response.bytes().await.map_err(TTSError::Network)?;
+            let error_text = String::from_utf8_lossy(&error_bytes);
             return Err(TTSError::ApiError(format!(
                 "ElevenLabs API error ({}): {}",
                 status, error_text
             )));
         }
 
-        if let Some(content_type) = headers.get("content-type") {
-            let content_type_str = content_type.to_str().unwrap_or("");
-            if !content_type_str.starts_with("audio/") {
-                let error_text = String::from_utf8_lossy(&audio_bytes);
-                return Err(TTSError::ApiError(format!(
-                    "Expected audio content but received '{}': {}",
-                    content_type_str, error_text
-                )));
-            }
-        }
+        Ok(response)
+    }
 
-        if audio_bytes.len() < 100 {
-            let text_content = String::from_utf8_lossy(&audio_bytes);
-            return Err(TTSError::ApiError(format!(
-                "Response too small ({} bytes), likely an error: {}",
-                audio_bytes.len(),
-                text_content
+    /// Silence lasting as long as the text would take to speak.
+    fn mock_synthesize(&self, text: &str) -> Result<AudioData, TTSError> {
+        let format = self.config.output_format;
+        if !format.is_uncompressed() {
+            return Err(TTSError::InvalidFormat(format!(
+                "The mock TTS provider cannot produce {:?} audio; use PCM16 or WAV",
+                format
             )));
         }
-
-        if audio_bytes.len() >= 3 {
-            let has_id3 = &audio_bytes[0..3] == b"ID3";
-            let has_mp3_sync = audio_bytes.len() >= 2
-                && (audio_bytes[0] == 0xFF && (audio_bytes[1] & 0xE0) == 0xE0);
-
-            if !has_id3 && !has_mp3_sync {
-                let text_content =
-                    String::from_utf8_lossy(&audio_bytes[0..100.min(audio_bytes.len())]);
-                if text_content.contains("error") || text_content.contains("detail") {
-                    return Err(TTSError::ApiError(format!(
-                        "Received error response instead of audio: {}",
-                        text_content
-                    )));
-                }
-                log::warn!("Audio data doesn't have standard MP3 headers but proceeding");
-            }
-        }
-
-        Ok(AudioData {
-            format: AudioFormat::MP3,
-            data: audio_bytes.to_vec(),
-            sample_rate: 22050,
-            channels: 1,
-            duration_ms: self.estimate_duration(text),
-        })
+        let sample_rate = self.output_sample_rate();
+        let samples = self.estimate_duration(text) as usize * sample_rate as usize / 1000;
+        Ok(AudioData::from_pcm16(format, vec![0; samples * 2], sample_rate, 1))
     }
 
     fn estimate_duration(&self, text: &str) -> u32 {
@@ -383,28 +935,210 @@ impl TTSService {
             .unwrap_or_else(|| VoiceProfile::default_for_npc(npc_name))
     }
 
-    fn generate_cache_key(
-        &self,
-        npc_name: &str,
-        text: &str,
-        emotions: &EmotionalState, // Use the main SDK's EmotionalState
-    ) -> String {
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
-
-        let mut hasher = DefaultHasher::new();
-        npc_name.hash(&mut hasher);
-        text.hash(&mut hasher);
-
-        // Round emotions to avoid too many cache misses
-        let rounded_emotions = (
-            (emotions.joy * 10.0).round() as i32,
-            (emotions.anger * 10.0).round() as i32,
-            (emotions.fear * 10.0).round() as i32,
-            // (emotions.energy * 10.0).round() as i32,
+    /// Output format part of the cache key, with the sample rate for
+    /// uncompressed formats.
+    fn output_format_key(&self) -> String {
+        let format = self.config.output_format;
+        if format.is_uncompressed() {
+            format!("{:?}@{}", format, self.output_sample_rate())
+        } else {
+            format!("{:?}", format)
+        }
+    }
+
+    /// Build the cache key from everything that shapes the synthesized audio:
+    /// provider, output format, voice settings, and the (possibly SSML) text.
+    /// Uses FNV-1a so keys stay stable across builds for the disk tier.
+    fn generate_cache_key(&self, text: &str, settings: &VoiceSettings) -> String {
+        const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+        const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+        // Round settings to avoid too many cache misses
+        let quantize = |value: f32| ((value * 20.0).round() as i32).to_string();
+        let parts = [
+            self.provider.as_str().to_string(),
+            self.output_format_key(),
+            settings.voice_id.clone(),
+            quantize(settings.stability),
+            quantize(settings.similarity_boost),
+            quantize(settings.style_exaggeration),
+            text.to_string(),
+        ];
+
+        let mut hash = FNV_OFFSET;
+        for part in &parts {
+            for byte in part.bytes().chain(std::iter::once(0)) {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(FNV_PRIME);
+            }
+        }
+
+        format!("tts_{:016x}", hash)
+    }
+}
+
+#[cfg(all(test, feature = "tts"))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_disk_tier_serves_and_clears_cached_speech() {
+        let dir = std::env::temp_dir().join(format!("oxyde-tts-cache-{}", uuid::Uuid::new_v4()));
+        let service = TTSService::new(
+            TTSProvider::ElevenLabs,
+            TTSConfig {
+                default_provider: TTSProvider::ElevenLabs,
+                cache_enabled: true,
+                cache_max_size_mb: 1,
+                cache_dir: Some(dir.clone()),
+                voice_speed: 1.0,
+                voice_pitch: 1.0,
+                enable_ssml: true,
+                output_format: AudioFormat::MP3,
+                output_sample_rate: None,
+                voice_profiles: HashMap::new(),
+                prosody: Default::default(),
+                api_key_ref: None,
+                pronunciations: Default::default(),
+                max_chunk_chars: None,
+                degradation: Default::default(),
+                pacing: Default::default(),
+            },
         );
-        rounded_emotions.hash(&mut hasher);
 
-        format!("tts_{:x}", hasher.finish())
+        // Seed the disk tier as a previous run would have
+        let emotions = EmotionalState::new();
+        let profile = service.get_voice_profile("Marla").await;
+        let settings = service.modulate_voice_for_emotion(&profile, &emotions, 0.0);
+        let key = service.generate_cache_key(&service.add_emotional_ssml("Welcome!", &emotions, 0.0), &settings);
+        let audio = AudioData {
+            format: AudioFormat::MP3,
+            data: vec![1; 256],
+            sample_rate: 22050,
+            channels: 1,
+            duration_ms: 400,
+        };
+        service.disk_cache.as_ref().unwrap().insert(&key, &audio).unwrap();
+
+        // Served from disk first, then from memory, without calling the provider
+        for _ in 0..2 {
+            let speech = service.synthesize_npc_speech("Marla", "Welcome!", &emotions, 0.0).await.unwrap();
+            assert_eq!(speech.data.len(), 256);
+        }
+        let stats = service.cache_stats().await;
+        assert_eq!((stats.hits.disk_hits, stats.hits.memory_hits, stats.hits.misses), (1, 1, 0));
+
+        service.clear_cache().await.unwrap();
+        let stats = service.cache_stats().await;
+        assert_eq!(stats.memory.entry_count, 0);
+        assert_eq!(stats.disk.unwrap().entry_count, 0);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_compressed_audio_is_paced_from_the_text() {
+        let dir = std::env::temp_dir().join(format!("oxyde-tts-pacing-{}", uuid::Uuid::new_v4()));
+        let config = TTSConfig {
+            default_provider: TTSProvider::ElevenLabs,
+            cache_enabled: true,
+            cache_dir: Some(dir.clone()),
+            output_format: AudioFormat::MP3,
+            ..TTSConfig::mock()
+        };
+        let service = TTSService::new(TTSProvider::ElevenLabs, config);
+
+        // MP3 from the provider carries a word-count guess, not a measured length
+        let line = "Welcome, traveler. Need a room?";
+        let emotions = EmotionalState::new();
+        let (_, _, key) = service.prepare_speech("Marla", line, &emotions, 0.0).await;
+        let mp3 = AudioData {
+            format: AudioFormat::MP3,
+            data: vec![0xFF, 0xFB, 0x90, 0x00],
+            sample_rate: 22050,
+            channels: 1,
+            duration_ms: 2000,
+        };
+        service.disk_cache.as_ref().unwrap().insert(&key, &mp3).unwrap();
+
+        let spoken = service.synthesize_or_degrade("Marla", line, &emotions, 0.0).await.unwrap();
+        assert!(matches!(spoken, SpokenResponse::Audio { source: SpeechSource::Primary, .. }));
+        assert!(spoken.pacing().estimated);
+        assert_eq!(spoken.pacing().speech_duration_ms, 2600);
+
+        // PCM and WAV are measured from their samples
+        let wav = AudioData::from_pcm16(AudioFormat::WAV, vec![0; 2 * 22050], 22050, 1);
+        let pacing = service.pacing(line, Some(&wav));
+        assert!(!pacing.estimated);
+        assert_eq!(pacing.speech_duration_ms, 1000);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_mock_provider_produces_silence_offline() {
+        let service = TTSService::new(TTSProvider::Mock, TTSConfig::mock());
+        let emotions = EmotionalState::new();
+        // 15 words at 150 words per minute take six seconds
+        let line = "word ".repeat(15);
+        let speech = service.synthesize_npc_speech("Marla", &line, &emotions, 0.0).await.unwrap();
+        assert_eq!(speech.format, AudioFormat::WAV);
+        assert_eq!(speech.duration_ms, 6000);
+
+        let streamed = service
+            .synthesize_npc_speech_streaming("Marla", &line, &emotions, 0.0)
+            .await
+            .unwrap()
+            .collect_audio(speech.duration_ms)
+            .await
+            .unwrap();
+        assert_eq!(streamed.data, speech.data);
+    }
+
+    #[tokio::test]
+    async fn test_failed_synthesis_degrades_to_secondary_then_text() {
+        // An unresolvable key makes ElevenLabs fail without touching the network
+        let failing = TTSConfig {
+            default_provider: TTSProvider::ElevenLabs,
+            api_key_ref: Some(SecretRef::new("file", "/nonexistent/oxyde-secrets.json#tts")),
+            ..TTSConfig::mock()
+        };
+        let emotions = EmotionalState::new();
+
+        let config = TTSConfig {
+            degradation: TTSDegradationPolicy {
+                secondary_provider: Some(TTSProvider::Mock),
+                ..Default::default()
+            },
+            ..failing.clone()
+        };
+        let service = TTSService::new(TTSProvider::ElevenLabs, config);
+        let spoken = service.synthesize_or_degrade("Marla", "Halt!", &emotions, 0.0).await.unwrap();
+        assert!(matches!(spoken, SpokenResponse::Audio { source: SpeechSource::Secondary, error: Some(_), .. }));
+        assert!(spoken.is_degraded());
+        assert!(!spoken.pacing().estimated);
+
+        let service = TTSService::new(TTSProvider::ElevenLabs, failing.clone());
+        let spoken = service.synthesize_or_degrade("Marla", "Halt!", &emotions, 0.0).await.unwrap();
+        assert!(matches!(&spoken, SpokenResponse::TextOnly { text, .. } if text == "Halt!"));
+        assert!(spoken.audio().is_none());
+        assert!(spoken.pacing().estimated && !spoken.pacing().expects_reply);
+
+        let config = TTSConfig {
+            degradation: TTSDegradationPolicy { text_only: false, ..Default::default() },
+            ..failing
+        };
+        let service = TTSService::new(TTSProvider::ElevenLabs, config);
+        assert!(service.synthesize_or_degrade("Marla", "Halt!", &emotions, 0.0).await.is_err());
+    }
+
+    #[test]
+    fn test_line_index_ranks_similar_lines_by_speaker() {
+        let mut index = LineIndex::default();
+        index.insert("Marla", "Welcome to the Rusty Anchor, traveler!", "a");
+        index.insert("Marla", "Welcome to the Rusty Anchor!", "b");
+        index.insert("Bram", "Welcome to the Rusty Anchor, traveler!", "c");
+
+        let similar = index.similar("Marla", "Welcome to the Rusty Anchor, friend!", 0.6);
+        assert_eq!(similar, vec!["b".to_string(), "a".to_string()]);
+        assert!(index.similar("Marla", "Begone, thief!", 0.6).is_empty());
     }
 }
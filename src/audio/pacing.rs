@@ -0,0 +1,221 @@
+//! Conversational pacing of spoken lines.
+//!
+//! Engines pairing speech recognition with TTS need to know when to open
+//! and close the microphone. Every spoken response carries a
+//! [`ConversationPacing`]: how long the line takes to say, the pauses within
+//! it, whether the NPC expects an answer, how long to listen once it ends,
+//! and from when the player may interrupt it:
+//!
+//! ```yaml
+//! tts:
+//!   pacing:
+//!     words_per_minute: 150
+//!     reply_window_ms: 6000
+//!     idle_window_ms: 1500
+//!     interrupt_after_ms: 800
+//! ```
+//!
+//! A line expects a reply when it ends in a question. Durations are measured
+//! from the audio when there is any, with pauses placed where the text
+//! suggests, and estimated from the text otherwise, such as for lines shown
+//! as subtitles only.
+
+use serde::{Deserialize, Serialize};
+
+/// Quotes and brackets that may follow a line's final punctuation.
+const CLOSING_MARKS: [char; 6] = ['"', '\'', ')', '”', '’', '»'];
+
+/// How spoken lines are paced.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PacingConfig {
+    /// Speaking rate at a voice speed of 1.0, used when a line has no audio
+    /// to measure.
+    #[serde(default = "default_words_per_minute")]
+    pub words_per_minute: f32,
+
+    /// Pause after a sentence, in milliseconds.
+    #[serde(default = "default_sentence_pause_ms")]
+    pub sentence_pause_ms: u32,
+
+    /// Pause after a clause ending in a comma, colon or dash, in milliseconds.
+    #[serde(default = "default_clause_pause_ms")]
+    pub clause_pause_ms: u32,
+
+    /// How long to listen after a line expecting a reply, in milliseconds.
+    #[serde(default = "default_reply_window_ms")]
+    pub reply_window_ms: u32,
+
+    /// How long to listen after any other line, in milliseconds, so the
+    /// player can still react to it.
+    #[serde(default = "default_idle_window_ms")]
+    pub idle_window_ms: u32,
+
+    /// How far into a line the player may interrupt it, in milliseconds;
+    /// lines cannot be interrupted when unset.
+    #[serde(default = "default_interrupt_after_ms")]
+    pub interrupt_after_ms: Option<u32>,
+}
+
+fn default_words_per_minute() -> f32 {
+    150.0
+}
+
+fn default_sentence_pause_ms() -> u32 {
+    400
+}
+
+fn default_clause_pause_ms() -> u32 {
+    200
+}
+
+fn default_reply_window_ms() -> u32 {
+    6000
+}
+
+fn default_idle_window_ms() -> u32 {
+    1500
+}
+
+fn default_interrupt_after_ms() -> Option<u32> {
+    Some(800)
+}
+
+impl Default for PacingConfig {
+    fn default() -> Self {
+        Self {
+            words_per_minute: default_words_per_minute(),
+            sentence_pause_ms: default_sentence_pause_ms(),
+            clause_pause_ms: default_clause_pause_ms(),
+            reply_window_ms: default_reply_window_ms(),
+            idle_window_ms: default_idle_window_ms(),
+            interrupt_after_ms: default_interrupt_after_ms(),
+        }
+    }
+}
+
+/// A pause within a spoken line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpeechPause {
+    /// When the pause starts, in milliseconds from the start of the line.
+    pub at_ms: u32,
+    /// Length of the pause in milliseconds.
+    pub duration_ms: u32,
+}
+
+/// Timing of a spoken line, for managing the microphone around it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConversationPacing {
+    /// How long the line takes to say, in milliseconds.
+    pub speech_duration_ms: u32,
+    /// Whether the duration was estimated from the text rather than
+    /// measured from audio.
+    pub estimated: bool,
+    /// Pauses within the line, in order.
+    pub pauses: Vec<SpeechPause>,
+    /// Whether the NPC expects the player to answer.
+    pub expects_reply: bool,
+    /// How long to keep listening once the line ends, in milliseconds.
+    pub listen_window_ms: u32,
+    /// How far into the line the player may interrupt it, in milliseconds;
+    /// `None` when the line cannot be interrupted.
+    pub interruptible_from_ms: Option<u32>,
+}
+
+impl ConversationPacing {
+    /// Returns when listening should stop if the player says nothing, in
+    /// milliseconds from the start of the line.
+    pub fn listen_until_ms(&self) -> u32 {
+        self.speech_duration_ms.saturating_add(self.listen_window_ms)
+    }
+}
+
+impl PacingConfig {
+    /// Pace a line.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The line as written
+    /// * `voice_speed` - Speed of the voice saying it, 1.0 being normal
+    /// * `audio_duration_ms` - Length of the line's audio, if it was synthesized
+    pub fn pace(&self, text: &str, voice_speed: f32, audio_duration_ms: Option<u32>) -> ConversationPacing {
+        let ms_per_word = 60_000.0 / (self.words_per_minute * voice_speed).max(1.0);
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let mut elapsed = 0.0;
+        let mut pauses = Vec::new();
+        for (index, word) in words.iter().enumerate() {
+            elapsed += ms_per_word;
+            if index + 1 == words.len() {
+                break;
+            }
+            let pause = match word.trim_end_matches(CLOSING_MARKS).chars().last() {
+                Some('.' | '!' | '?' | '…' | '。' | '！' | '？') => self.sentence_pause_ms,
+                Some(',' | ';' | ':' | '—' | '–' | '、' | '，') => self.clause_pause_ms,
+                _ => 0,
+            };
+            if pause > 0 {
+                pauses.push((elapsed, pause as f32));
+                elapsed += pause as f32;
+            }
+        }
+
+        // Fit the estimate to the audio, which knows how long the line really takes
+        let scale = match audio_duration_ms {
+            Some(duration) if elapsed > 0.0 => duration as f32 / elapsed,
+            _ => 1.0,
+        };
+        let speech_duration_ms = audio_duration_ms.unwrap_or(elapsed.round() as u32);
+        let expects_reply = text.trim_end().trim_end_matches(CLOSING_MARKS).ends_with(['?', '？']);
+
+        ConversationPacing {
+            speech_duration_ms,
+            estimated: audio_duration_ms.is_none(),
+            pauses: pauses
+                .into_iter()
+                .map(|(at, duration)| SpeechPause {
+                    at_ms: (at * scale).round() as u32,
+                    duration_ms: (duration * scale).round() as u32,
+                })
+                .collect(),
+            expects_reply,
+            listen_window_ms: if expects_reply { self.reply_window_ms } else { self.idle_window_ms },
+            interruptible_from_ms: self.interrupt_after_ms.map(|after| after.min(speech_duration_ms)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_questions_open_a_reply_window_after_speech() {
+        let config = PacingConfig::default();
+
+        // Five words at 150 words per minute, plus a clause and a sentence pause
+        let pacing = config.pace("Welcome, traveler. Need a room?", 1.0, None);
+        assert!(pacing.estimated);
+        assert!(pacing.expects_reply);
+        assert_eq!(pacing.listen_window_ms, 6000);
+        assert_eq!(
+            pacing.pauses,
+            vec![
+                SpeechPause { at_ms: 400, duration_ms: 200 },
+                SpeechPause { at_ms: 1000, duration_ms: 400 },
+            ]
+        );
+        assert_eq!(pacing.speech_duration_ms, 2600);
+        assert_eq!(pacing.listen_until_ms(), 8600);
+        assert_eq!(pacing.interruptible_from_ms, Some(800));
+
+        // Measured audio twice as long stretches the pauses with it
+        let measured = config.pace("Welcome, traveler. Need a room?", 1.0, Some(5200));
+        assert!(!measured.estimated);
+        assert_eq!(measured.pauses[1], SpeechPause { at_ms: 2000, duration_ms: 800 });
+
+        let statement = config.pace("\"Begone.\"", 2.0, None);
+        assert!(!statement.expects_reply);
+        assert_eq!(statement.listen_window_ms, 1500);
+        assert_eq!((statement.speech_duration_ms, statement.interruptible_from_ms), (200, Some(200)));
+        assert!(statement.pauses.is_empty());
+    }
+}
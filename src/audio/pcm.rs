@@ -0,0 +1,415 @@
+//! Uncompressed audio for engine playback.
+//!
+//! Engines can play 16-bit PCM without a decoder: Unity fills an `AudioClip`
+//! with samples and Unreal queues them on a `USoundWaveProcedural`. This
+//! module converts the provider's PCM16 output to the sample rate the game
+//! mixes at, and wraps it as WAV when a container is wanted.
+
+use super::{AudioData, AudioFormat, TTSError};
+
+/// Size of a canonical WAV header in bytes.
+pub const WAV_HEADER_SIZE: usize = 44;
+
+/// Length written to the size fields of a WAV header whose data length is
+/// not yet known, as when streaming.
+const WAV_UNKNOWN_SIZE: u32 = u32::MAX;
+
+/// Build a WAV header for little-endian 16-bit PCM.
+///
+/// # Arguments
+///
+/// * `sample_rate` - Sample rate in Hz
+/// * `channels` - Number of interleaved channels
+/// * `data_len` - Length of the PCM data in bytes, or `None` if unknown
+pub fn wav_header(sample_rate: u32, channels: u8, data_len: Option<u32>) -> Vec<u8> {
+    let block_align = u16::from(channels) * 2;
+    let data_len = data_len.unwrap_or(WAV_UNKNOWN_SIZE);
+    let riff_len = data_len.saturating_add(WAV_HEADER_SIZE as u32 - 8);
+
+    let mut header = Vec::with_capacity(WAV_HEADER_SIZE);
+    header.extend_from_slice(b"RIFF");
+    header.extend_from_slice(&riff_len.to_le_bytes());
+    header.extend_from_slice(b"WAVEfmt ");
+    header.extend_from_slice(&16u32.to_le_bytes());
+    header.extend_from_slice(&1u16.to_le_bytes());
+    header.extend_from_slice(&u16::from(channels).to_le_bytes());
+    header.extend_from_slice(&sample_rate.to_le_bytes());
+    header.extend_from_slice(&(sample_rate * u32::from(block_align)).to_le_bytes());
+    header.extend_from_slice(&block_align.to_le_bytes());
+    header.extend_from_slice(&16u16.to_le_bytes());
+    header.extend_from_slice(b"data");
+    header.extend_from_slice(&data_len.to_le_bytes());
+    header
+}
+
+/// Wrap little-endian 16-bit PCM in a WAV container.
+pub fn encode_wav(pcm: &[u8], sample_rate: u32, channels: u8) -> Vec<u8> {
+    let mut wav = wav_header(sample_rate, channels, Some(pcm.len() as u32));
+    wav.extend_from_slice(pcm);
+    wav
+}
+
+/// Read the PCM data, sample rate and channel count of a 16-bit PCM WAV file.
+pub fn decode_wav(wav: &[u8]) -> Result<(&[u8], u32, u8), TTSError> {
+    let invalid = |reason: &str| TTSError::InvalidFormat(format!("Not a 16-bit PCM WAV file: {}", reason));
+    if wav.len() < 12 || &wav[0..4] != b"RIFF" || &wav[8..12] != b"WAVE" {
+        return Err(invalid("missing RIFF header"));
+    }
+
+    let mut format = None;
+    let mut offset = 12;
+    while offset + 8 <= wav.len() {
+        let id = &wav[offset..offset + 4];
+        let len = u32::from_le_bytes([wav[offset + 4], wav[offset + 5], wav[offset + 6], wav[offset + 7]]) as usize;
+        let body = &wav[offset + 8..];
+        match id {
+            b"fmt " if body.len() >= 16 => {
+                let encoding = u16::from_le_bytes([body[0], body[1]]);
+                let bits = u16::from_le_bytes([body[14], body[15]]);
+                if encoding != 1 || bits != 16 {
+                    return Err(invalid("samples are not 16-bit integers"));
+                }
+                let channels = u16::from_le_bytes([body[2], body[3]]);
+                let sample_rate = u32::from_le_bytes([body[4], body[5], body[6], body[7]]);
+                format = Some((sample_rate, u8::try_from(channels).map_err(|_| invalid("too many channels"))?));
+            }
+            b"data" => {
+                let (sample_rate, channels) = format.ok_or_else(|| invalid("data before format"))?;
+                // Streamed files may not know their length; take what is there
+                return Ok((&body[..len.min(body.len())], sample_rate, channels));
+            }
+            _ => {}
+        }
+        // Chunks are padded to an even length
+        offset = offset.saturating_add(8).saturating_add(len).saturating_add(len % 2);
+    }
+    Err(invalid("missing data chunk"))
+}
+
+/// Converts 16-bit PCM between sample rates by linear interpolation.
+///
+/// Input may arrive in chunks of any size, including ones that split a
+/// sample; the output is the same as converting the whole audio at once.
+#[derive(Debug, Clone)]
+pub struct PcmResampler {
+    step: f64,
+    channels: usize,
+    /// Position of the next output frame, in input frames from `previous`
+    position: f64,
+    /// Last input frame, kept to interpolate across chunk boundaries
+    previous: Option<Vec<i16>>,
+    /// Bytes of an incomplete input frame
+    pending: Vec<u8>,
+}
+
+impl PcmResampler {
+    /// Create a resampler for interleaved little-endian 16-bit PCM.
+    pub fn new(from_rate: u32, to_rate: u32, channels: u8) -> Self {
+        Self {
+            step: f64::from(from_rate) / f64::from(to_rate.max(1)),
+            channels: usize::from(channels.max(1)),
+            position: 0.0,
+            previous: None,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Convert the next piece of input, returning the output it completes.
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<u8> {
+        self.pending.extend_from_slice(bytes);
+        let frame_bytes = 2 * self.channels;
+        let complete = self.pending.len() / frame_bytes * frame_bytes;
+        let samples: Vec<i16> = self
+            .pending
+            .drain(..complete)
+            .collect::<Vec<u8>>()
+            .chunks_exact(2)
+            .map(|pair| i16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+
+        let frames: Vec<&[i16]> = self
+            .previous
+            .iter()
+            .map(Vec::as_slice)
+            .chain(samples.chunks_exact(self.channels))
+            .collect();
+
+        let mut output = Vec::new();
+        while (self.position as usize) + 1 < frames.len() {
+            let index = self.position as usize;
+            let fraction = self.position - index as f64;
+            for (from, to) in frames[index].iter().zip(frames[index + 1]) {
+                let from = f64::from(*from);
+                let sample = from + (f64::from(*to) - from) * fraction;
+                output.extend_from_slice(&(sample.round() as i16).to_le_bytes());
+            }
+            self.position += self.step;
+        }
+
+        if let Some(last) = frames.last().map(|frame| frame.to_vec()) {
+            self.position -= (frames.len() - 1) as f64;
+            self.previous = Some(last);
+        }
+        output
+    }
+
+    /// Flush the output frame that lands exactly on the last input frame.
+    pub fn finish(&mut self) -> Vec<u8> {
+        match self.previous.take() {
+            Some(last) if self.position < 1e-9 => last.iter().flat_map(|sample| sample.to_le_bytes()).collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Resample a whole PCM16 buffer.
+pub fn resample_pcm16(pcm: &[u8], from_rate: u32, to_rate: u32, channels: u8) -> Vec<u8> {
+    if from_rate == to_rate {
+        return pcm.to_vec();
+    }
+    let mut resampler = PcmResampler::new(from_rate, to_rate, channels);
+    let mut output = resampler.push(pcm);
+    output.extend(resampler.finish());
+    output
+}
+
+/// Play length of PCM16 audio in milliseconds.
+pub fn pcm16_duration_ms(pcm_len: usize, sample_rate: u32, channels: u8) -> u32 {
+    let frames = pcm_len as u64 / (2 * u64::from(channels.max(1)));
+    (frames * 1000 / u64::from(sample_rate.max(1))) as u32
+}
+
+/// Converts a provider's PCM16 stream into PCM16 or WAV chunks.
+///
+/// WAV streams start with a header whose sizes are unknown; the complete
+/// audio returned by [`PcmStreamEncoder::into_audio`] has exact sizes.
+#[cfg(feature = "tts")]
+#[derive(Debug)]
+pub(crate) struct PcmStreamEncoder {
+    format: AudioFormat,
+    sample_rate: u32,
+    channels: u8,
+    resampler: PcmResampler,
+    header_sent: bool,
+    pcm: Vec<u8>,
+}
+
+#[cfg(feature = "tts")]
+impl PcmStreamEncoder {
+    pub(crate) fn new(format: AudioFormat, from_rate: u32, to_rate: u32, channels: u8) -> Self {
+        Self {
+            format,
+            sample_rate: to_rate,
+            channels,
+            resampler: PcmResampler::new(from_rate, to_rate, channels),
+            header_sent: false,
+            pcm: Vec::new(),
+        }
+    }
+
+    /// Convert a chunk of provider audio to output bytes.
+    pub(crate) fn push(&mut self, bytes: &[u8]) -> Vec<u8> {
+        let pcm = self.resampler.push(bytes);
+        self.emit(pcm)
+    }
+
+    /// Output bytes left once the provider stream ends.
+    pub(crate) fn finish(&mut self) -> Vec<u8> {
+        let pcm = self.resampler.finish();
+        self.emit(pcm)
+    }
+
+    fn emit(&mut self, pcm: Vec<u8>) -> Vec<u8> {
+        self.pcm.extend_from_slice(&pcm);
+        if self.format == AudioFormat::WAV && !self.header_sent {
+            self.header_sent = true;
+            let mut chunk = wav_header(self.sample_rate, self.channels, None);
+            chunk.extend(pcm);
+            return chunk;
+        }
+        pcm
+    }
+
+    /// The complete audio, for caching.
+    pub(crate) fn into_audio(self) -> AudioData {
+        AudioData::from_pcm16(self.format, self.pcm, self.sample_rate, self.channels)
+    }
+}
+
+impl AudioData {
+    /// Build PCM16 or WAV audio from little-endian 16-bit PCM.
+    ///
+    /// Formats other than [`AudioFormat::WAV`] keep the PCM as is.
+    pub fn from_pcm16(format: AudioFormat, pcm: Vec<u8>, sample_rate: u32, channels: u8) -> Self {
+        let duration_ms = pcm16_duration_ms(pcm.len(), sample_rate, channels);
+        let data = match format {
+            AudioFormat::WAV => encode_wav(&pcm, sample_rate, channels),
+            _ => pcm,
+        };
+        Self {
+            format,
+            data,
+            sample_rate,
+            channels,
+            duration_ms,
+        }
+    }
+
+    /// Play length measured from the samples, for PCM16 and WAV audio.
+    ///
+    /// Compressed formats return `None`: their `duration_ms` is only an
+    /// estimate from the text, as the frames are not decoded.
+    pub fn measured_duration_ms(&self) -> Option<u32> {
+        match self.format {
+            AudioFormat::PCM16 => Some(pcm16_duration_ms(self.data.len(), self.sample_rate, self.channels)),
+            AudioFormat::WAV => decode_wav(&self.data)
+                .ok()
+                .map(|(pcm, sample_rate, channels)| pcm16_duration_ms(pcm.len(), sample_rate, channels)),
+            AudioFormat::MP3 | AudioFormat::OGG => None,
+        }
+    }
+
+    /// Join audio synthesized in several requests into one clip.
+    ///
+    /// PCM16 and WAV samples are appended; MP3 frames and Ogg streams are
+    /// concatenated as they are. All parts must share a format and rate.
+    pub fn concat(parts: Vec<AudioData>) -> Result<AudioData, TTSError> {
+        let mut parts = parts.into_iter();
+        let Some(first) = parts.next() else {
+            return Err(TTSError::AudioProcessingError("No audio to join".to_string()));
+        };
+        let mut rest = parts.peekable();
+        if rest.peek().is_none() {
+            return Ok(first);
+        }
+
+        let (format, sample_rate, channels) = (first.format, first.sample_rate, first.channels);
+        let mut duration_ms = first.duration_ms;
+        let mut data = match format {
+            AudioFormat::WAV => decode_wav(&first.data)?.0.to_vec(),
+            _ => first.data,
+        };
+        for part in rest {
+            if part.format != format || part.sample_rate != sample_rate || part.channels != channels {
+                return Err(TTSError::InvalidFormat(format!(
+                    "Cannot join {:?} audio at {} Hz with {:?} audio at {} Hz",
+                    format, sample_rate, part.format, part.sample_rate
+                )));
+            }
+            duration_ms += part.duration_ms;
+            match format {
+                AudioFormat::WAV => data.extend_from_slice(decode_wav(&part.data)?.0),
+                _ => data.extend(part.data),
+            }
+        }
+
+        if format.is_uncompressed() {
+            return Ok(Self::from_pcm16(format, data, sample_rate, channels));
+        }
+        Ok(Self {
+            format,
+            data,
+            sample_rate,
+            channels,
+            duration_ms,
+        })
+    }
+
+    /// Convert uncompressed audio to PCM16 or WAV at another sample rate.
+    ///
+    /// MP3 and OGG audio cannot be converted, since that needs a decoder;
+    /// configure the TTS service to request PCM16 or WAV instead.
+    pub fn convert(&self, format: AudioFormat, sample_rate: u32) -> Result<AudioData, TTSError> {
+        let (pcm, from_rate, channels) = match self.format {
+            AudioFormat::PCM16 => (self.data.as_slice(), self.sample_rate, self.channels),
+            AudioFormat::WAV => decode_wav(&self.data)?,
+            other => {
+                return Err(TTSError::InvalidFormat(format!("Cannot convert compressed {:?} audio", other)));
+            }
+        };
+        if !format.is_uncompressed() {
+            return Err(TTSError::InvalidFormat(format!("Cannot encode audio as {:?}", format)));
+        }
+        let pcm = resample_pcm16(pcm, from_rate, sample_rate, channels);
+        Ok(Self::from_pcm16(format, pcm, sample_rate, channels))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ramp(frames: i16) -> Vec<u8> {
+        (0..frames).flat_map(|sample| (sample * 10).to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn test_resamples_in_chunks_like_whole_buffer() {
+        let pcm = ramp(1000);
+        let whole = resample_pcm16(&pcm, 24000, 16000, 1);
+        assert_eq!(whole.len() / 2, 667);
+
+        // Odd chunk sizes split samples across pushes
+        let mut resampler = PcmResampler::new(24000, 16000, 1);
+        let mut chunked: Vec<u8> = pcm.chunks(333).flat_map(|chunk| resampler.push(chunk)).collect();
+        chunked.extend(resampler.finish());
+        assert_eq!(chunked, whole);
+
+        // A linear ramp stays linear: every third input sample lands on an output
+        let samples: Vec<i16> = whole.chunks_exact(2).map(|pair| i16::from_le_bytes([pair[0], pair[1]])).collect();
+        assert_eq!(&samples[..4], &[0, 15, 30, 45]);
+
+        let upsampled = resample_pcm16(&pcm, 22050, 44100, 1);
+        assert_eq!(upsampled.len() / 2, 1999);
+    }
+
+    #[test]
+    fn test_converts_between_pcm_and_wav() {
+        let pcm = AudioData::from_pcm16(AudioFormat::PCM16, ramp(2205), 22050, 1);
+        assert_eq!(pcm.duration_ms, 100);
+
+        let wav = pcm.convert(AudioFormat::WAV, 44100).unwrap();
+        assert_eq!(&wav.data[0..4], b"RIFF");
+        assert_eq!(wav.data.len(), WAV_HEADER_SIZE + 4409 * 2);
+        let (data, sample_rate, channels) = decode_wav(&wav.data).unwrap();
+        assert_eq!((data.len(), sample_rate, channels), (4409 * 2, 44100, 1));
+
+        let back = wav.convert(AudioFormat::PCM16, 22050).unwrap();
+        assert_eq!(back.data, pcm.data);
+
+        let mp3 = AudioData {
+            format: AudioFormat::MP3,
+            ..pcm.clone()
+        };
+        assert!(mp3.convert(AudioFormat::WAV, 22050).is_err());
+        assert!(pcm.convert(AudioFormat::OGG, 22050).is_err());
+    }
+
+    #[test]
+    fn test_joins_chunked_synthesis() {
+        let first = AudioData::from_pcm16(AudioFormat::WAV, ramp(2205), 22050, 1);
+        let second = AudioData::from_pcm16(AudioFormat::WAV, ramp(1102), 22050, 1);
+        let joined = AudioData::concat(vec![first, second.clone()]).unwrap();
+        assert_eq!(joined.data.len(), WAV_HEADER_SIZE + 3307 * 2);
+        assert_eq!(joined.duration_ms, 149);
+        assert_eq!(decode_wav(&joined.data).unwrap().1, 22050);
+
+        let resampled = second.convert(AudioFormat::WAV, 44100).unwrap();
+        assert!(AudioData::concat(vec![joined, resampled]).is_err());
+        assert!(AudioData::concat(Vec::new()).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "tts")]
+    fn test_streamed_wav_matches_cached_audio() {
+        let pcm = ramp(500);
+        let mut encoder = PcmStreamEncoder::new(AudioFormat::WAV, 24000, 16000, 1);
+        let mut streamed: Vec<u8> = pcm.chunks(101).flat_map(|chunk| encoder.push(chunk)).collect();
+        streamed.extend(encoder.finish());
+        let audio = encoder.into_audio();
+
+        assert_eq!(&streamed[40..44], &u32::MAX.to_le_bytes());
+        assert_eq!(streamed[WAV_HEADER_SIZE..], audio.data[WAV_HEADER_SIZE..]);
+        assert_eq!(decode_wav(&streamed).unwrap().0.len(), audio.data.len() - WAV_HEADER_SIZE);
+    }
+}
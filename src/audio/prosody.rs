@@ -0,0 +1,217 @@
+use crate::oxyde_game::emotion::EmotionalState;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// Maps a normalized driving signal (0.0 to 1.0) onto a prosody value
+pub struct ProsodyCurve {
+    /// Output value when the signal is 0.0
+    pub low: f32,
+    /// Output value when the signal is 1.0
+    pub high: f32,
+    /// Shape exponent applied to the signal; 1.0 is linear, larger values
+    /// keep the output near `low` until the signal gets strong
+    pub exponent: f32,
+}
+
+impl ProsodyCurve {
+    /// Create a new curve
+    pub fn new(low: f32, high: f32, exponent: f32) -> Self {
+        Self { low, high, exponent }
+    }
+
+    /// Evaluate the curve for a signal, clamping the signal to 0.0..=1.0
+    pub fn apply(&self, signal: f32) -> f32 {
+        let shaped = signal.clamp(0.0, 1.0).powf(self.exponent.max(0.01));
+        self.low + (self.high - self.low) * shaped
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// SSML emphasis level
+pub enum EmphasisLevel {
+    /// `<emphasis level="moderate">`
+    Moderate,
+    /// `<emphasis level="strong">`
+    Strong,
+}
+
+impl EmphasisLevel {
+    /// SSML attribute value for this level
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Moderate => "moderate",
+            Self::Strong => "strong",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Mapping from emotional state and urgency to SSML prosody
+///
+/// * Rate is driven by the greater of arousal and urgency.
+/// * Pitch is driven by valence, with 0.5 meaning neutral.
+/// * Volume is driven by arousal.
+/// * Emphasis is driven by the greater of arousal and urgency.
+pub struct ProsodyMapping {
+    /// Speaking rate as a percentage of normal speed
+    #[serde(default = "default_rate_curve")]
+    pub rate_percent: ProsodyCurve,
+    /// Pitch shift in semitones
+    #[serde(default = "default_pitch_curve")]
+    pub pitch_semitones: ProsodyCurve,
+    /// Volume change in decibels
+    #[serde(default = "default_volume_curve")]
+    pub volume_db: ProsodyCurve,
+    /// Signal level at which moderate emphasis is applied
+    #[serde(default = "default_moderate_emphasis")]
+    pub moderate_emphasis_at: f32,
+    /// Signal level at which strong emphasis is applied
+    #[serde(default = "default_strong_emphasis")]
+    pub strong_emphasis_at: f32,
+}
+
+fn default_rate_curve() -> ProsodyCurve {
+    ProsodyCurve::new(100.0, 130.0, 1.0)
+}
+
+fn default_pitch_curve() -> ProsodyCurve {
+    ProsodyCurve::new(-3.0, 3.0, 1.0)
+}
+
+fn default_volume_curve() -> ProsodyCurve {
+    ProsodyCurve::new(0.0, 6.0, 1.5)
+}
+
+fn default_moderate_emphasis() -> f32 {
+    0.5
+}
+
+fn default_strong_emphasis() -> f32 {
+    0.8
+}
+
+impl Default for ProsodyMapping {
+    fn default() -> Self {
+        Self {
+            rate_percent: default_rate_curve(),
+            pitch_semitones: default_pitch_curve(),
+            volume_db: default_volume_curve(),
+            moderate_emphasis_at: default_moderate_emphasis(),
+            strong_emphasis_at: default_strong_emphasis(),
+        }
+    }
+}
+
+impl ProsodyMapping {
+    /// Compute prosody for an emotional state and urgency (0.0 to 1.0)
+    pub fn prosody_for(&self, emotions: &EmotionalState, urgency: f32) -> Prosody {
+        let arousal = emotions.arousal();
+        let activation = arousal.max(urgency.clamp(0.0, 1.0));
+        let valence_signal = (emotions.valence() + 1.0) * 0.5;
+
+        let emphasis = if activation >= self.strong_emphasis_at {
+            Some(EmphasisLevel::Strong)
+        } else if activation >= self.moderate_emphasis_at {
+            Some(EmphasisLevel::Moderate)
+        } else {
+            None
+        };
+
+        Prosody {
+            rate_percent: self.rate_percent.apply(activation),
+            pitch_semitones: self.pitch_semitones.apply(valence_signal),
+            volume_db: self.volume_db.apply(arousal),
+            emphasis,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// Concrete prosody settings for one utterance
+pub struct Prosody {
+    /// Speaking rate as a percentage of normal speed
+    pub rate_percent: f32,
+    /// Pitch shift in semitones
+    pub pitch_semitones: f32,
+    /// Volume change in decibels
+    pub volume_db: f32,
+    /// Emphasis applied to the whole utterance, if any
+    pub emphasis: Option<EmphasisLevel>,
+}
+
+impl Prosody {
+    /// Wrap text in an SSML `<speak>` document with these prosody settings
+    ///
+    /// Attributes that round to their neutral value are omitted, and the text
+    /// is escaped for XML.
+    pub fn to_ssml(&self, text: &str) -> String {
+        self.wrap_ssml(&escape_ssml(text))
+    }
+
+    /// Wrap SSML markup, such as text marked up by a
+    /// [`super::PronunciationLexicon`], in a `<speak>` document with these
+    /// prosody settings. The markup is not escaped.
+    pub fn wrap_ssml(&self, markup: &str) -> String {
+        let mut attrs = Vec::new();
+        let rate = self.rate_percent.round();
+        if rate != 100.0 {
+            attrs.push(format!("rate=\"{:.0}%\"", rate));
+        }
+        let pitch = (self.pitch_semitones * 10.0).round() / 10.0;
+        if pitch != 0.0 {
+            attrs.push(format!("pitch=\"{:+.1}st\"", pitch));
+        }
+        let volume = (self.volume_db * 10.0).round() / 10.0;
+        if volume != 0.0 {
+            attrs.push(format!("volume=\"{:+.1}dB\"", volume));
+        }
+
+        let mut body = markup.to_string();
+        if let Some(level) = self.emphasis {
+            body = format!("<emphasis level=\"{}\">{}</emphasis>", level.as_str(), body);
+        }
+        if !attrs.is_empty() {
+            body = format!("<prosody {}>{}</prosody>", attrs.join(" "), body);
+        }
+
+        format!("<speak>{}</speak>", body)
+    }
+}
+
+/// Escape XML special characters for inclusion in SSML
+pub(super) fn escape_ssml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_neutral_state_has_no_prosody() {
+        let prosody = ProsodyMapping::default().prosody_for(&EmotionalState::new(), 0.0);
+        assert_eq!(prosody.to_ssml("Hello & welcome"), "<speak>Hello &amp; welcome</speak>");
+    }
+
+    #[test]
+    fn test_urgency_speeds_up_and_emphasizes() {
+        let prosody = ProsodyMapping::default().prosody_for(&EmotionalState::new(), 0.9);
+        assert!(prosody.rate_percent > 120.0);
+        assert_eq!(prosody.emphasis, Some(EmphasisLevel::Strong));
+        assert!(prosody.to_ssml("Run!").contains("<emphasis level=\"strong\">Run!</emphasis>"));
+    }
+
+    #[test]
+    fn test_curves_are_overridable() {
+        let mapping = ProsodyMapping {
+            rate_percent: ProsodyCurve::new(80.0, 80.0, 1.0),
+            ..Default::default()
+        };
+        let prosody = mapping.prosody_for(&EmotionalState::new(), 1.0);
+        assert_eq!(prosody.rate_percent, 80.0);
+    }
+}
@@ -7,6 +7,7 @@ pub enum TTSError {
     #[error("Missing API key for provider: {0}")]
     MissingApiKey(&'static str),
     /// Network error from the reqwest library.
+    #[cfg(feature = "reqwest")]
     #[error("Network error: {0}")]
     Network(#[from] reqwest::Error),
     /// Error during audio processing.
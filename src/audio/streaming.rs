@@ -0,0 +1,142 @@
+//! Chunked speech synthesis.
+//!
+//! An [`AudioStream`] yields encoded audio in chunks as the provider
+//! generates it, so playback can start before the whole response has been
+//! synthesized. Chunks concatenate to the same bytes the non-streaming API
+//! returns. Streams can be consumed asynchronously, as a [`futures::Stream`],
+//! or pulled one chunk at a time with a blocking call for FFI consumers.
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::sync::mpsc;
+
+use super::{AudioData, AudioFormat, TTSError};
+
+/// Size of the chunks cached audio is replayed in.
+pub const CACHED_CHUNK_SIZE: usize = 16 * 1024;
+
+/// Number of chunks buffered ahead of a slow consumer.
+#[cfg(feature = "tts")]
+const STREAM_BUFFER: usize = 32;
+
+/// A piece of encoded audio from a streaming synthesis.
+#[derive(Debug, Clone)]
+pub struct AudioChunk {
+    /// Position of the chunk in the stream, starting at 0.
+    pub index: usize,
+    /// Encoded audio bytes.
+    pub data: Vec<u8>,
+}
+
+/// Audio chunks delivered as they are synthesized.
+#[derive(Debug)]
+pub struct AudioStream {
+    receiver: mpsc::Receiver<Result<AudioChunk, TTSError>>,
+    /// The format of the encoded audio.
+    pub format: AudioFormat,
+    /// Sample rate of the audio in Hz.
+    pub sample_rate: u32,
+    /// Number of audio channels.
+    pub channels: u8,
+}
+
+impl AudioStream {
+    /// Create a stream and the sender a synthesis task feeds it through.
+    #[cfg(feature = "tts")]
+    pub(crate) fn channel(
+        format: AudioFormat,
+        sample_rate: u32,
+        channels: u8,
+    ) -> (mpsc::Sender<Result<AudioChunk, TTSError>>, Self) {
+        let (sender, receiver) = mpsc::channel(STREAM_BUFFER);
+        let stream = Self {
+            receiver,
+            format,
+            sample_rate,
+            channels,
+        };
+        (sender, stream)
+    }
+
+    /// Replay already synthesized audio as a stream of fixed-size chunks.
+    pub fn from_audio(audio: AudioData) -> Self {
+        let chunks: Vec<&[u8]> = audio.data.chunks(CACHED_CHUNK_SIZE).collect();
+        let (sender, receiver) = mpsc::channel(chunks.len().max(1));
+        for (index, data) in chunks.into_iter().enumerate() {
+            // Capacity covers every chunk, so this cannot fail
+            let _ = sender.try_send(Ok(AudioChunk {
+                index,
+                data: data.to_vec(),
+            }));
+        }
+        Self {
+            receiver,
+            format: audio.format,
+            sample_rate: audio.sample_rate,
+            channels: audio.channels,
+        }
+    }
+
+    /// Wait for the next chunk.
+    /// Returns `None` once the stream is complete.
+    pub async fn next_chunk(&mut self) -> Option<Result<AudioChunk, TTSError>> {
+        self.receiver.recv().await
+    }
+
+    /// Block the current thread until the next chunk arrives.
+    /// Must not be called from within an async runtime.
+    pub fn blocking_next_chunk(&mut self) -> Option<Result<AudioChunk, TTSError>> {
+        self.receiver.blocking_recv()
+    }
+
+    /// Wait for the whole stream and join its chunks.
+    pub async fn collect_audio(mut self, duration_ms: u32) -> Result<AudioData, TTSError> {
+        let mut data = Vec::new();
+        while let Some(chunk) = self.next_chunk().await {
+            data.extend_from_slice(&chunk?.data);
+        }
+        Ok(AudioData {
+            format: self.format,
+            data,
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+            duration_ms,
+        })
+    }
+}
+
+impl futures::Stream for AudioStream {
+    type Item = Result<AudioChunk, TTSError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+
+    #[tokio::test]
+    async fn test_cached_audio_replays_in_chunks() {
+        let audio = AudioData {
+            format: AudioFormat::MP3,
+            data: (0..CACHED_CHUNK_SIZE * 2 + 10).map(|i| i as u8).collect(),
+            sample_rate: 22050,
+            channels: 1,
+            duration_ms: 1000,
+        };
+
+        let chunks: Vec<AudioChunk> = AudioStream::from_audio(audio.clone())
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await;
+        assert_eq!(chunks.iter().map(|c| c.data.len()).collect::<Vec<_>>(), vec![CACHED_CHUNK_SIZE, CACHED_CHUNK_SIZE, 10]);
+        assert_eq!(chunks[2].index, 2);
+
+        let joined = AudioStream::from_audio(audio.clone()).collect_audio(1000).await.unwrap();
+        assert_eq!(joined.data, audio.data);
+    }
+}
@@ -1,4 +1,7 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::TTSProvider;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 /// Represents a voice profile for an NPC
@@ -22,6 +25,10 @@ pub struct BaseVoice {
     pub base_rate: f32,
     /// The base volume of the voice
     pub base_volume: f32,
+    /// Provider-specific voice IDs, keyed by provider name (e.g. "elevenlabs")
+    /// These take precedence over `voice_id` for the matching provider
+    #[serde(default)]
+    pub provider_voice_ids: HashMap<String, String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -94,6 +101,7 @@ impl VoiceProfile {
                 base_pitch: 0.5,
                 base_rate: 0.5,
                 base_volume: 0.7,
+                provider_voice_ids: HashMap::new(),
             },
             emotional_range: EmotionalVoiceRange {
                 happiness_range: (0.0, 0.3),
@@ -115,6 +123,7 @@ impl VoiceProfile {
                 base_pitch: 0.4,
                 base_rate: 0.6,
                 base_volume: 0.8,
+                provider_voice_ids: HashMap::new(),
             },
             emotional_range: EmotionalVoiceRange {
                 happiness_range: (0.1, 0.4),
@@ -136,6 +145,7 @@ impl VoiceProfile {
                 base_pitch: 0.3,
                 base_rate: 0.4,
                 base_volume: 0.9,
+                provider_voice_ids: HashMap::new(),
             },
             emotional_range: EmotionalVoiceRange {
                 happiness_range: (0.0, 0.2),
@@ -156,6 +166,7 @@ impl VoiceProfile {
                 base_pitch: 0.2,
                 base_rate: 0.3,
                 base_volume: 0.6,
+                provider_voice_ids: HashMap::new(),
             },
             emotional_range: EmotionalVoiceRange {
                 happiness_range: (0.0, 0.3),
@@ -169,6 +180,16 @@ impl VoiceProfile {
     }
 }
 
+impl BaseVoice {
+    /// Get the voice ID to use with a specific provider
+    pub fn voice_id_for(&self, provider: &TTSProvider) -> &str {
+        self.provider_voice_ids
+            .get(provider.as_str())
+            .map(String::as_str)
+            .unwrap_or(&self.voice_id)
+    }
+}
+
 impl VoiceSettings {
     /// Create a new voice settings instance from a voice profile
     /// This method initializes the voice settings based on the provided voice profile
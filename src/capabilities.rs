@@ -0,0 +1,396 @@
+//! Agent capabilities
+//!
+//! A [`CapabilitiesConfig`] states what an agent may never do, regardless of
+//! what the model generates or which behaviors are installed:
+//!
+//! ```yaml
+//! capabilities:
+//!   allowed_actions: [trade, follow, stop_follow]
+//!   forbidden_tags: [dev_notes]
+//!   disclosure:
+//!     - tag: secret_passage
+//!       min_relationship: 0.8
+//!       requires: [quest_complete]
+//!   secrets: ["behind the waterfall"]
+//! ```
+//!
+//! Actions are checked by type (the text before the first `|`) when
+//! behaviors emit them. Memories tagged with a forbidden tag, or with a
+//! disclosure tag whose conditions are not met, are withheld from the prompt.
+//! Responses containing a secret phrase are replaced, and generated responses
+//! breaking the agent's character are corrected as described in
+//! [`crate::consistency`]. Blocked actions and responses are logged and
+//! reported as [`CapabilityViolation`]s.
+
+use std::fmt;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::action::action_name;
+use crate::agent::AgentContext;
+use crate::consistency::{ConsistencyChecker, ConsistencyConfig, ConsistencyViolation};
+use crate::context::PLAYER_RELATIONSHIP_KEY;
+use crate::memory::Memory;
+use crate::{OxydeError, Result};
+
+/// Conditions under which tagged knowledge may reach the prompt
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DisclosureRule {
+    /// Memory tag the rule applies to
+    pub tag: String,
+
+    /// Lowest `player_relationship` context value at which it is disclosed
+    #[serde(default)]
+    pub min_relationship: Option<f32>,
+
+    /// Context keys that must all be truthy, with `.` separating nested keys
+    /// (for example `quest_complete` or `quests.smuggler.done`)
+    #[serde(default)]
+    pub requires: Vec<String>,
+}
+
+impl DisclosureRule {
+    /// Whether the rule's conditions hold in a context
+    pub fn permits(&self, context: &AgentContext) -> bool {
+        let relationship_ok = self.min_relationship.is_none_or(|min| {
+            context
+                .get(PLAYER_RELATIONSHIP_KEY)
+                .and_then(|v| v.as_f64())
+                .is_some_and(|relationship| relationship as f32 >= min)
+        });
+        relationship_ok && self.requires.iter().all(|path| is_truthy(lookup(context, path)))
+    }
+}
+
+/// Configuration for what an agent may do and reveal
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CapabilitiesConfig {
+    /// Action types the agent may emit; any type when empty
+    #[serde(default)]
+    pub allowed_actions: Vec<String>,
+
+    /// Action types the agent may never emit
+    #[serde(default)]
+    pub forbidden_actions: Vec<String>,
+
+    /// Memory tags that never reach the prompt
+    #[serde(default)]
+    pub forbidden_tags: Vec<String>,
+
+    /// Memory tags that reach the prompt only under conditions
+    #[serde(default)]
+    pub disclosure: Vec<DisclosureRule>,
+
+    /// Phrases that must never appear in a response, matched ignoring case
+    #[serde(default)]
+    pub secrets: Vec<String>,
+
+    /// Response used in place of one that reveals a secret
+    #[serde(default = "default_blocked_response")]
+    pub blocked_response: String,
+
+    /// Rules keeping generated responses consistent with the character
+    #[serde(default)]
+    pub consistency: ConsistencyConfig,
+}
+
+fn default_blocked_response() -> String {
+    "That's not something I can talk about.".to_string()
+}
+
+impl Default for CapabilitiesConfig {
+    fn default() -> Self {
+        Self {
+            allowed_actions: Vec::new(),
+            forbidden_actions: Vec::new(),
+            forbidden_tags: Vec::new(),
+            disclosure: Vec::new(),
+            secrets: Vec::new(),
+            blocked_response: default_blocked_response(),
+            consistency: ConsistencyConfig::default(),
+        }
+    }
+}
+
+impl CapabilitiesConfig {
+    /// Validate the capabilities configuration
+    pub fn validate(&self) -> Result<()> {
+        let lists = [
+            ("allowed_actions", &self.allowed_actions),
+            ("forbidden_actions", &self.forbidden_actions),
+            ("forbidden_tags", &self.forbidden_tags),
+            ("secrets", &self.secrets),
+        ];
+        for (name, list) in lists {
+            if list.iter().any(|entry| entry.trim().is_empty()) {
+                return Err(OxydeError::ConfigurationError(format!(
+                    "Capabilities {} cannot contain empty entries",
+                    name
+                )));
+            }
+        }
+
+        if let Some(action) = self.allowed_actions.iter().find(|a| self.forbidden_actions.contains(a)) {
+            return Err(OxydeError::ConfigurationError(format!(
+                "Action '{}' cannot be both allowed and forbidden",
+                action
+            )));
+        }
+
+        for rule in &self.disclosure {
+            if rule.tag.trim().is_empty() {
+                return Err(OxydeError::ConfigurationError(
+                    "Disclosure rule tag cannot be empty".to_string(),
+                ));
+            }
+            if rule.min_relationship.is_some_and(|min| !(-1.0..=1.0).contains(&min)) {
+                return Err(OxydeError::ConfigurationError(format!(
+                    "Disclosure rule for '{}' must have min_relationship between -1.0 and 1.0",
+                    rule.tag
+                )));
+            }
+        }
+
+        if !self.secrets.is_empty() && self.blocked_response.trim().is_empty() {
+            return Err(OxydeError::ConfigurationError(
+                "Capabilities blocked_response cannot be empty when secrets are listed".to_string(),
+            ));
+        }
+        self.consistency.validate()
+    }
+}
+
+/// Kind of capability violation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ViolationKind {
+    /// A behavior emitted an action the agent may not perform
+    ForbiddenAction,
+    /// A response revealed a secret
+    SecretDisclosure,
+    /// A generated response broke a consistency rule
+    OutOfCharacter,
+}
+
+impl ViolationKind {
+    /// Get the string representation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ForbiddenAction => "forbidden_action",
+            Self::SecretDisclosure => "secret_disclosure",
+            Self::OutOfCharacter => "out_of_character",
+        }
+    }
+}
+
+/// A blocked attempt to act or speak outside the agent's capabilities
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CapabilityViolation {
+    /// What was blocked
+    pub kind: ViolationKind,
+
+    /// The blocked action, the secret the response revealed, or the
+    /// consistency rule broken and the text breaking it
+    pub detail: String,
+}
+
+impl From<&ConsistencyViolation> for CapabilityViolation {
+    fn from(violation: &ConsistencyViolation) -> Self {
+        Self {
+            kind: ViolationKind::OutOfCharacter,
+            detail: format!("{} ({})", violation.rule, violation.matched),
+        }
+    }
+}
+
+impl fmt::Display for CapabilityViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.kind.as_str(), self.detail)
+    }
+}
+
+/// Enforces a capabilities configuration
+#[derive(Debug)]
+pub struct Capabilities {
+    config: CapabilitiesConfig,
+    secrets: Option<Regex>,
+    consistency: ConsistencyChecker,
+}
+
+impl Capabilities {
+    /// Compile a capabilities configuration
+    pub fn new(config: &CapabilitiesConfig) -> Self {
+        let secrets = (!config.secrets.is_empty()).then(|| {
+            let escaped: Vec<String> = config.secrets.iter().map(|s| regex::escape(s.trim())).collect();
+            Regex::new(&format!("(?i){}", escaped.join("|"))).expect("escaped secrets form a valid pattern")
+        });
+        Self {
+            config: config.clone(),
+            secrets,
+            consistency: ConsistencyChecker::new(&config.consistency),
+        }
+    }
+
+    /// Whether the configuration restricts nothing
+    pub fn is_unrestricted(&self) -> bool {
+        self.config.allowed_actions.is_empty()
+            && self.config.forbidden_actions.is_empty()
+            && self.config.forbidden_tags.is_empty()
+            && self.config.disclosure.is_empty()
+            && self.secrets.is_none()
+            && self.consistency.is_empty()
+    }
+
+    /// Check an action emitted by a behavior
+    ///
+    /// # Returns
+    ///
+    /// The violation if the action's type is not allowed; JSON action
+    /// commands are checked by their command name
+    pub fn check_action(&self, action: &str) -> Option<CapabilityViolation> {
+        let action_type = action_name(action);
+        let allowed = (self.config.allowed_actions.is_empty()
+            || self.config.allowed_actions.contains(&action_type))
+            && !self.config.forbidden_actions.contains(&action_type);
+        (!allowed).then(|| CapabilityViolation {
+            kind: ViolationKind::ForbiddenAction,
+            detail: action.to_string(),
+        })
+    }
+
+    /// Check a response before it is stored or returned
+    ///
+    /// # Returns
+    ///
+    /// The violation if the response contains a secret phrase
+    pub fn check_response(&self, response: &str) -> Option<CapabilityViolation> {
+        let found = self.secrets.as_ref()?.find(response)?;
+        Some(CapabilityViolation {
+            kind: ViolationKind::SecretDisclosure,
+            detail: found.as_str().to_string(),
+        })
+    }
+
+    /// Response used in place of one that reveals a secret
+    pub fn blocked_response(&self) -> &str {
+        &self.config.blocked_response
+    }
+
+    /// Rules generated responses must keep to
+    pub fn consistency(&self) -> &ConsistencyChecker {
+        &self.consistency
+    }
+
+    /// Whether a memory may be included in the prompt
+    ///
+    /// Memories with a forbidden tag are always withheld; memories with a
+    /// disclosure tag are withheld until every matching rule permits them.
+    pub fn may_disclose(&self, memory: &Memory, context: &AgentContext) -> bool {
+        let has_tag = |tag: &str| memory.tags.iter().any(|t| t.eq_ignore_ascii_case(tag));
+        !self.config.forbidden_tags.iter().any(|tag| has_tag(tag))
+            && self
+                .config
+                .disclosure
+                .iter()
+                .filter(|rule| has_tag(&rule.tag))
+                .all(|rule| rule.permits(context))
+    }
+
+    /// Remove the memories that may not reach the prompt
+    pub fn filter_memories(&self, memories: Vec<Memory>, context: &AgentContext) -> Vec<Memory> {
+        if self.config.forbidden_tags.is_empty() && self.config.disclosure.is_empty() {
+            return memories;
+        }
+        memories
+            .into_iter()
+            .filter(|memory| {
+                let disclose = self.may_disclose(memory, context);
+                if !disclose {
+                    tracing::debug!(memory = %memory.id, "Withholding memory from prompt");
+                }
+                disclose
+            })
+            .collect()
+    }
+}
+
+/// Look up a context value by a `.`-separated path
+fn lookup<'a>(context: &'a AgentContext, path: &str) -> Option<&'a serde_json::Value> {
+    let mut keys = path.split('.');
+    let first = context.get(keys.next()?)?;
+    keys.try_fold(first, |value, key| value.get(key))
+}
+
+/// Whether a context value counts as set
+fn is_truthy(value: Option<&serde_json::Value>) -> bool {
+    match value {
+        None | Some(serde_json::Value::Null) => false,
+        Some(serde_json::Value::Bool(b)) => *b,
+        Some(serde_json::Value::Number(n)) => n.as_f64().is_some_and(|n| n != 0.0),
+        Some(serde_json::Value::String(s)) => !s.is_empty(),
+        Some(serde_json::Value::Array(a)) => !a.is_empty(),
+        Some(serde_json::Value::Object(o)) => !o.is_empty(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::ContextBuilder;
+    use crate::memory::MemoryCategory;
+
+    fn capabilities() -> Capabilities {
+        let config: CapabilitiesConfig = serde_yaml::from_str(
+            "allowed_actions: [trade, follow]\n\
+             forbidden_tags: [dev_notes]\n\
+             disclosure:\n  - tag: secret_passage\n    min_relationship: 0.8\n    requires: [quest_complete]\n\
+             secrets: [behind the waterfall]\n",
+        )
+        .unwrap();
+        config.validate().unwrap();
+        Capabilities::new(&config)
+    }
+
+    #[test]
+    fn test_blocks_actions_and_secrets() {
+        let capabilities = capabilities();
+        assert!(capabilities.check_action("trade|buy|iron sword|1|50").is_none());
+        let violation = capabilities.check_action("attack|player").unwrap();
+        assert_eq!(violation.to_string(), "forbidden_action: attack|player");
+
+        let violation = capabilities.check_response("The cave is Behind the Waterfall.").unwrap();
+        assert_eq!(violation.kind, ViolationKind::SecretDisclosure);
+        assert!(capabilities.check_response("The cave is north of town.").is_none());
+    }
+
+    #[test]
+    fn test_withholds_knowledge_until_disclosure_rules_hold() {
+        let capabilities = capabilities();
+        let tagged = |content: &str, tag: &str| {
+            Memory::new(MemoryCategory::Semantic, content, 0.5, Some(vec![tag.to_string()]))
+        };
+        let memories = vec![
+            tagged("The passage opens at dusk", "secret_passage"),
+            tagged("Boss fight is unfinished", "dev_notes"),
+            tagged("The inn serves stew", "town"),
+        ];
+
+        let stranger = ContextBuilder::new().player_relationship(0.9).build();
+        let withheld = capabilities.filter_memories(memories.clone(), &stranger);
+        assert_eq!(withheld.len(), 1);
+        assert_eq!(withheld[0].content, "The inn serves stew");
+
+        let trusted = ContextBuilder::new().player_relationship(0.9).with("quest_complete", true).build();
+        let disclosed = capabilities.filter_memories(memories, &trusted);
+        assert_eq!(disclosed.len(), 2);
+        assert!(disclosed.iter().all(|m| !m.content.contains("Boss")));
+
+        let invalid = CapabilitiesConfig {
+            allowed_actions: vec!["trade".to_string()],
+            forbidden_actions: vec!["trade".to_string()],
+            ..Default::default()
+        };
+        assert!(invalid.validate().is_err());
+    }
+}
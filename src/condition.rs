@@ -0,0 +1,647 @@
+//! Sandboxed condition expressions
+//!
+//! Designers write behavior trigger conditions and agenda preconditions as
+//! small expressions in agent configs:
+//!
+//! ```yaml
+//! behavior:
+//!   flee:
+//!     trigger: proximity
+//!     condition: "player_distance < 3 && emotions.fear > 0.5"
+//! ```
+//!
+//! Expressions are parsed when the config loads, so typos are reported with
+//! the column they occur at instead of silently never matching. They support
+//! numbers, quoted strings, `true`, `false` and `null`; dotted paths into the
+//! agent context (`quest.stage`, `party.0.name`); `emotions.<name>` for the
+//! eight primary emotions plus `valence` and `arousal`; arithmetic
+//! (`+ - * /`), comparisons (`< <= > >= == !=`) and logic (`&&`, `||`, `!`,
+//! or `and`, `or`, `not`).
+//!
+//! Evaluation is sandboxed: expressions cannot call functions, write to the
+//! context, or loop, and their length and nesting depth are capped. Missing
+//! context values evaluate to `null`, which is falsy and fails every ordering
+//! comparison.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+use crate::agent::AgentContext;
+use crate::oxyde_game::emotion::{EmotionalState, EMOTIONAL_STATE_KEY, EMOTION_NAMES};
+use crate::{OxydeError, Result};
+
+/// Longest accepted expression, in characters
+const MAX_SOURCE_LEN: usize = 1024;
+
+/// Deepest accepted nesting of operators and parentheses
+const MAX_DEPTH: usize = 32;
+
+/// Path root that reads the agent's emotional state
+const EMOTIONS_ROOT: &str = "emotions";
+
+/// A parsed condition expression
+#[derive(Clone)]
+pub struct Condition {
+    source: String,
+    expr: Expr,
+}
+
+impl Condition {
+    /// Parse a condition expression
+    ///
+    /// # Returns
+    ///
+    /// The condition, or a configuration error naming the column of the problem
+    pub fn parse(source: &str) -> Result<Self> {
+        if source.chars().count() > MAX_SOURCE_LEN {
+            return Err(OxydeError::ConfigurationError(format!(
+                "Condition is longer than {} characters",
+                MAX_SOURCE_LEN
+            )));
+        }
+
+        let tokens = lex(source).map_err(|e| e.into_error(source))?;
+        let mut parser = Parser { tokens, position: 0, depth: 0 };
+        let expr = parser.expression().map_err(|e| e.into_error(source))?;
+        if let Some(token) = parser.peek() {
+            return Err(SyntaxError::new(token.column, format!("unexpected {}", token.kind)).into_error(source));
+        }
+
+        Ok(Self {
+            source: source.to_string(),
+            expr,
+        })
+    }
+
+    /// The expression as written
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Evaluate the condition
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - Agent context that paths are resolved against
+    /// * `emotions` - Emotional state read by `emotions.*` paths
+    pub fn evaluate(&self, context: &AgentContext, emotions: &EmotionalState) -> bool {
+        truthy(&self.expr.eval(&Scope { context, emotions }))
+    }
+
+    /// Evaluate the condition with the emotional state stored in the context
+    ///
+    /// Behaviors receive the agent's emotions under the `npc_emotions`
+    /// context key; a neutral state is used when it is missing.
+    pub fn evaluate_in(&self, context: &AgentContext) -> bool {
+        let emotions = context
+            .get(EMOTIONAL_STATE_KEY)
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+            .unwrap_or_default();
+        self.evaluate(context, &emotions)
+    }
+}
+
+impl FromStr for Condition {
+    type Err = OxydeError;
+
+    fn from_str(source: &str) -> Result<Self> {
+        Self::parse(source)
+    }
+}
+
+impl PartialEq for Condition {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl fmt::Debug for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Condition").field(&self.source).finish()
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl Serialize for Condition {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+impl<'de> Deserialize<'de> for Condition {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let source = String::deserialize(deserializer)?;
+        Self::parse(&source).map_err(serde::de::Error::custom)
+    }
+}
+
+/// A syntax error at a column of the source
+#[derive(Debug)]
+struct SyntaxError {
+    column: usize,
+    message: String,
+}
+
+impl SyntaxError {
+    fn new(column: usize, message: impl Into<String>) -> Self {
+        Self {
+            column,
+            message: message.into(),
+        }
+    }
+
+    /// Describe the error with the source and a caret under the column
+    fn into_error(self, source: &str) -> OxydeError {
+        OxydeError::ConfigurationError(format!(
+            "Invalid condition at column {}: {}\n  {}\n  {}^",
+            self.column + 1,
+            self.message,
+            source,
+            " ".repeat(self.column)
+        ))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Number(f64),
+    Str(String),
+    Ident(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+    Dot,
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Number(n) => write!(f, "number `{}`", n),
+            Self::Str(s) => write!(f, "string \"{}\"", s),
+            Self::Ident(name) => write!(f, "`{}`", name),
+            Self::Op(op) => write!(f, "`{}`", op),
+            Self::LParen => f.write_str("`(`"),
+            Self::RParen => f.write_str("`)`"),
+            Self::Dot => f.write_str("`.`"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    column: usize,
+}
+
+/// Split the source into tokens; columns count characters
+fn lex(source: &str) -> std::result::Result<Vec<Token>, SyntaxError> {
+    const OPERATORS: [&str; 14] = ["&&", "||", "==", "!=", "<=", ">=", "<", ">", "!", "+", "-", "*", "/", "="];
+
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let kind = if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            // A fraction needs a digit after the dot, so `items.0.name` stays a path
+            if i + 1 < chars.len() && chars[i] == '.' && chars[i + 1].is_ascii_digit() {
+                i += 1;
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            let value = text
+                .parse()
+                .map_err(|_| SyntaxError::new(column, format!("invalid number `{}`", text)))?;
+            tokens.push(Token { kind: TokenKind::Number(value), column });
+            continue;
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token {
+                kind: TokenKind::Ident(chars[start..i].iter().collect()),
+                column,
+            });
+            continue;
+        } else if c == '"' || c == '\'' {
+            let start = i + 1;
+            i = start;
+            while i < chars.len() && chars[i] != c {
+                i += 1;
+            }
+            if i == chars.len() {
+                return Err(SyntaxError::new(column, "unterminated string"));
+            }
+            let text = chars[start..i].iter().collect();
+            i += 1;
+            tokens.push(Token { kind: TokenKind::Str(text), column });
+            continue;
+        } else if c == '(' {
+            TokenKind::LParen
+        } else if c == ')' {
+            TokenKind::RParen
+        } else if c == '.' {
+            TokenKind::Dot
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            match OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+                Some(&"=") => return Err(SyntaxError::new(column, "use `==` to compare values")),
+                Some(op) => {
+                    i += op.len();
+                    tokens.push(Token { kind: TokenKind::Op(op), column });
+                    continue;
+                }
+                None => return Err(SyntaxError::new(column, format!("unexpected character `{}`", c))),
+            }
+        };
+        tokens.push(Token { kind, column });
+        i += 1;
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BinaryOp {
+    And,
+    Or,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Literal(Value),
+    Path(Vec<String>),
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+/// Recursive descent parser; each method parses one precedence level
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    depth: usize,
+}
+
+type ParseResult = std::result::Result<Expr, SyntaxError>;
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn end_column(&self) -> usize {
+        self.tokens.last().map_or(0, |token| token.column + 1)
+    }
+
+    /// Consume the next token if it is one of the operators
+    fn operator(&mut self, operators: &[(&str, BinaryOp)]) -> Option<BinaryOp> {
+        let token = self.peek()?;
+        let op = match &token.kind {
+            TokenKind::Op(symbol) => operators.iter().find(|(s, _)| s == symbol)?.1,
+            TokenKind::Ident(word) if word == "and" => operators.iter().find(|(s, _)| *s == "&&")?.1,
+            TokenKind::Ident(word) if word == "or" => operators.iter().find(|(s, _)| *s == "||")?.1,
+            _ => return None,
+        };
+        self.position += 1;
+        Some(op)
+    }
+
+    fn nested<F: FnOnce(&mut Self) -> ParseResult>(&mut self, parse: F) -> ParseResult {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            let column = self.peek().map_or_else(|| self.end_column(), |token| token.column);
+            return Err(SyntaxError::new(column, format!("nested deeper than {} levels", MAX_DEPTH)));
+        }
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn expression(&mut self) -> ParseResult {
+        self.nested(Self::or)
+    }
+
+    fn binary_level(
+        &mut self,
+        operators: &[(&str, BinaryOp)],
+        next: fn(&mut Self) -> ParseResult,
+    ) -> ParseResult {
+        let mut left = next(self)?;
+        while let Some(op) = self.operator(operators) {
+            let right = next(self)?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn or(&mut self) -> ParseResult {
+        self.binary_level(&[("||", BinaryOp::Or)], Self::and)
+    }
+
+    fn and(&mut self) -> ParseResult {
+        self.binary_level(&[("&&", BinaryOp::And)], Self::comparison)
+    }
+
+    fn comparison(&mut self) -> ParseResult {
+        const OPERATORS: [(&str, BinaryOp); 6] = [
+            ("==", BinaryOp::Eq),
+            ("!=", BinaryOp::Ne),
+            ("<=", BinaryOp::Le),
+            (">=", BinaryOp::Ge),
+            ("<", BinaryOp::Lt),
+            (">", BinaryOp::Gt),
+        ];
+        let left = self.sum()?;
+        match self.operator(&OPERATORS) {
+            Some(op) => {
+                let right = self.sum()?;
+                if let Some(token) = self.peek() {
+                    if matches!(&token.kind, TokenKind::Op(s) if OPERATORS.iter().any(|(o, _)| o == s)) {
+                        return Err(SyntaxError::new(
+                            token.column,
+                            "comparisons cannot be chained; combine them with `&&`",
+                        ));
+                    }
+                }
+                Ok(Expr::Binary(op, Box::new(left), Box::new(right)))
+            }
+            None => Ok(left),
+        }
+    }
+
+    fn sum(&mut self) -> ParseResult {
+        self.binary_level(&[("+", BinaryOp::Add), ("-", BinaryOp::Sub)], Self::product)
+    }
+
+    fn product(&mut self) -> ParseResult {
+        self.binary_level(&[("*", BinaryOp::Mul), ("/", BinaryOp::Div)], Self::unary)
+    }
+
+    fn unary(&mut self) -> ParseResult {
+        match self.peek().map(|token| &token.kind) {
+            Some(TokenKind::Op("!")) => {
+                self.position += 1;
+                self.nested(|p| p.unary().map(|e| Expr::Not(Box::new(e))))
+            }
+            Some(TokenKind::Ident(word)) if word == "not" => {
+                self.position += 1;
+                self.nested(|p| p.unary().map(|e| Expr::Not(Box::new(e))))
+            }
+            Some(TokenKind::Op("-")) => {
+                self.position += 1;
+                self.nested(|p| p.unary().map(|e| Expr::Neg(Box::new(e))))
+            }
+            _ => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> ParseResult {
+        let Some(token) = self.peek().cloned() else {
+            return Err(SyntaxError::new(self.end_column(), "expected a value"));
+        };
+        self.position += 1;
+        match token.kind {
+            TokenKind::Number(n) => Ok(Expr::Literal(Value::from(n))),
+            TokenKind::Str(s) => Ok(Expr::Literal(Value::String(s))),
+            TokenKind::Ident(word) => match word.as_str() {
+                "true" => Ok(Expr::Literal(Value::Bool(true))),
+                "false" => Ok(Expr::Literal(Value::Bool(false))),
+                "null" => Ok(Expr::Literal(Value::Null)),
+                "and" | "or" | "not" => Err(SyntaxError::new(token.column, format!("expected a value, found `{}`", word))),
+                _ => self.path(word, token.column),
+            },
+            TokenKind::LParen => {
+                let expr = self.expression()?;
+                match self.peek() {
+                    Some(Token { kind: TokenKind::RParen, .. }) => {
+                        self.position += 1;
+                        Ok(expr)
+                    }
+                    Some(token) => Err(SyntaxError::new(token.column, format!("expected `)`, found {}", token.kind))),
+                    None => Err(SyntaxError::new(token.column, "unclosed `(`")),
+                }
+            }
+            kind => Err(SyntaxError::new(token.column, format!("expected a value, found {}", kind))),
+        }
+    }
+
+    fn path(&mut self, root: String, column: usize) -> ParseResult {
+        let mut segments = vec![root];
+        while let Some(Token { kind: TokenKind::Dot, column: dot }) = self.peek().cloned() {
+            self.position += 1;
+            match self.peek().map(|token| token.kind.clone()) {
+                Some(TokenKind::Ident(name)) => segments.push(name),
+                Some(TokenKind::Number(index)) if index.fract() == 0.0 && index >= 0.0 => {
+                    segments.push((index as u64).to_string())
+                }
+                _ => return Err(SyntaxError::new(dot + 1, "expected a name after `.`")),
+            }
+            self.position += 1;
+        }
+
+        if segments[0] == EMOTIONS_ROOT {
+            let known = |name: &str| EMOTION_NAMES.contains(&name) || name == "valence" || name == "arousal";
+            match segments.get(1) {
+                Some(name) if segments.len() == 2 && known(name) => {}
+                _ => {
+                    return Err(SyntaxError::new(
+                        column,
+                        format!(
+                            "`{}` is not an emotion; use emotions.<name> with one of {}, valence, arousal",
+                            segments.join("."),
+                            EMOTION_NAMES.join(", ")
+                        ),
+                    ))
+                }
+            }
+        }
+        Ok(Expr::Path(segments))
+    }
+}
+
+/// What paths are resolved against
+struct Scope<'a> {
+    context: &'a AgentContext,
+    emotions: &'a EmotionalState,
+}
+
+impl Scope<'_> {
+    fn resolve(&self, path: &[String]) -> Value {
+        if path[0] == EMOTIONS_ROOT {
+            let value = match path[1].as_str() {
+                "valence" => self.emotions.valence(),
+                "arousal" => self.emotions.arousal(),
+                name => {
+                    let index = EMOTION_NAMES.iter().position(|e| *e == name).unwrap_or_default();
+                    self.emotions.as_vector()[index]
+                }
+            };
+            return Value::from(value as f64);
+        }
+
+        let Some(mut value) = self.context.get(&path[0]) else {
+            return Value::Null;
+        };
+        for segment in &path[1..] {
+            let next = match value {
+                Value::Object(map) => map.get(segment),
+                Value::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get(i)),
+                _ => None,
+            };
+            match next {
+                Some(next) => value = next,
+                None => return Value::Null,
+            }
+        }
+        value.clone()
+    }
+}
+
+/// Whether a value counts as true
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|n| n != 0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(map) => !map.is_empty(),
+    }
+}
+
+impl Expr {
+    fn eval(&self, scope: &Scope<'_>) -> Value {
+        match self {
+            Self::Literal(value) => value.clone(),
+            Self::Path(path) => scope.resolve(path),
+            Self::Not(inner) => Value::Bool(!truthy(&inner.eval(scope))),
+            Self::Neg(inner) => inner.eval(scope).as_f64().map_or(Value::Null, |n| Value::from(-n)),
+            Self::Binary(BinaryOp::And, left, right) => {
+                Value::Bool(truthy(&left.eval(scope)) && truthy(&right.eval(scope)))
+            }
+            Self::Binary(BinaryOp::Or, left, right) => {
+                Value::Bool(truthy(&left.eval(scope)) || truthy(&right.eval(scope)))
+            }
+            Self::Binary(op, left, right) => apply(*op, &left.eval(scope), &right.eval(scope)),
+        }
+    }
+}
+
+/// Apply a comparison or arithmetic operator
+fn apply(op: BinaryOp, left: &Value, right: &Value) -> Value {
+    let numbers = left.as_f64().zip(right.as_f64());
+    let ordering = match (left, right) {
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        _ => numbers.and_then(|(a, b)| a.partial_cmp(&b)),
+    };
+    let equal = match numbers {
+        Some((a, b)) => a == b,
+        None => left == right,
+    };
+
+    match op {
+        BinaryOp::Eq => Value::Bool(equal),
+        BinaryOp::Ne => Value::Bool(!equal),
+        BinaryOp::Lt => Value::Bool(ordering.is_some_and(|o| o.is_lt())),
+        BinaryOp::Le => Value::Bool(ordering.is_some_and(|o| o.is_le())),
+        BinaryOp::Gt => Value::Bool(ordering.is_some_and(|o| o.is_gt())),
+        BinaryOp::Ge => Value::Bool(ordering.is_some_and(|o| o.is_ge())),
+        BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div => {
+            let Some((a, b)) = numbers else {
+                return Value::Null;
+            };
+            let result = match op {
+                BinaryOp::Add => a + b,
+                BinaryOp::Sub => a - b,
+                BinaryOp::Mul => a * b,
+                _ if b == 0.0 => return Value::Null,
+                _ => a / b,
+            };
+            Value::from(result)
+        }
+        BinaryOp::And | BinaryOp::Or => unreachable!("logical operators short-circuit in Expr::eval"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluates_against_context_and_emotions() {
+        let mut context = AgentContext::new();
+        context.insert("player_distance".to_string(), serde_json::json!(2.5));
+        context.insert("quest".to_string(), serde_json::json!({ "stage": "escort", "party": [{ "hp": 40 }] }));
+        let mut emotions = EmotionalState::new();
+        emotions.update_emotion("fear", 0.7);
+
+        let holds = |source: &str| Condition::parse(source).unwrap().evaluate(&context, &emotions);
+        assert!(holds("player_distance < 3 && emotions.fear > 0.5"));
+        assert!(!holds("player_distance < 3 and not (emotions.fear > 0.5)"));
+        assert!(holds("quest.stage == 'escort' || gold >= 100"));
+        assert!(holds("quest.party.0.hp * 2 <= 80 - 0.5 * 0"));
+        assert!(!holds("missing.value > -1"));
+        assert!(holds("-player_distance < 0 && emotions.arousal > 0"));
+    }
+
+    #[test]
+    fn test_reports_helpful_errors() {
+        let error = |source: &str| Condition::parse(source).unwrap_err().to_string();
+
+        let message = error("player_distance < && emotions.fear > 0.5");
+        assert!(message.contains("column 19: expected a value, found `&&`"), "{}", message);
+        assert!(message.contains("\n  player_distance < && emotions.fear > 0.5\n                    ^"));
+        assert!(error("emotions.fera > 0.5").contains("`emotions.fera` is not an emotion"));
+        assert!(error("quest.stage = 'escort'").contains("use `==`"));
+        assert!(error("1 < x < 3").contains("cannot be chained"));
+        assert!(error("(x > 1").contains("unclosed `(`"));
+        assert!(error(&format!("{}1{}", "(".repeat(40), ")".repeat(40))).contains("nested deeper"));
+
+        let parsed: std::result::Result<Condition, _> = serde_json::from_str("\"x >\"");
+        assert!(parsed.unwrap_err().to_string().contains("column 4: expected a value"));
+    }
+
+    #[test]
+    fn test_reads_emotions_from_context() {
+        let mut emotions = EmotionalState::new();
+        emotions.update_emotion("anger", 0.8);
+        let mut context = AgentContext::new();
+        context.insert(EMOTIONAL_STATE_KEY.to_string(), serde_json::to_value(&emotions).unwrap());
+
+        let condition: Condition = "emotions.anger >= 0.8 && !emotions.joy".parse().unwrap();
+        assert!(condition.evaluate_in(&context));
+        assert!(!condition.evaluate_in(&AgentContext::new()));
+        assert_eq!(serde_json::to_value(&condition).unwrap(), "emotions.anger >= 0.8 && !emotions.joy");
+    }
+}
@@ -4,16 +4,19 @@
 //! memory systems, and behaviors.
 
 use std::collections::HashMap;
+use std::fmt;
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{BufReader, Read, Write};
 use std::path::Path;
 
 use serde::{Deserialize, Serialize};
 
-use crate::{audio::TTSConfig, OxydeError, Result};
+use crate::{annotation::AnnotationConfig, audio::{AudioFormat, TTSConfig, TTSProvider}, capabilities::CapabilitiesConfig, condition::Condition, config_migration::{migrate, MigrationReport}, context::ContextSchema, dialogue_queue::DialogueQueueConfig, event_log::EventLogConfig, experiment::ExperimentConfig, fallback::OfflineFallbackConfig, forgetting::ForgettingPolicy, inference::ProviderType, interaction_log::InteractionLogConfig, language::LanguageConfig, latency::LatencyConfig, memory_store::MemoryStoreConfig, mock_provider::MockProviderConfig, monologue::MonologueConfig, oxyde_game::affect::AffectConfig, oxyde_game::behavior::EmotionTrigger, oxyde_game::disposition::DispositionConfig, oxyde_game::intent::IntentConfig, oxyde_game::modifier::SentimentModifiers, oxyde_game::schedule::ScheduleConfig, oxyde_game::topic::TopicConfig, oxyde_game::persuasion::PersuasionConfig, oxyde_game::reengagement::ReengagementConfig, oxyde_game::reputation::ReputationConfig, postprocess::PostProcessConfig, prompt::PromptConfig, provider_router::RoutingConfig, redaction::RedactionConfig, reflection::ReflectionConfig, retrieval::{MoodCongruence, RetrievalWeights}, sampling::SamplingConfig, secrets::{redact, SecretRef}, session::SessionConfig, structured::StructuredOutputConfig, verbosity::VerbosityConfig, OxydeError, Result};
+
+pub use crate::config_migration::CONFIG_VERSION;
 
 /// Configuration for an agent's personality and behavior
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AgentPersonality {
     /// Agent name
     pub name: String,
@@ -29,7 +32,7 @@ pub struct AgentPersonality {
 }
 
 /// Vector embedding model type
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum EmbeddingModelType {
     /// Use mini-bert model
     MiniBert,
@@ -52,10 +55,15 @@ pub struct MemoryConfig {
     #[serde(default = "default_memory_capacity")]
     pub capacity: usize,
 
-    /// Whether to persist memories to disk
+    /// Whether to persist memories to disk, in the directory set under
+    /// `store`
     #[serde(default)]
     pub persistence: bool,
 
+    /// Where and how memories are persisted
+    #[serde(default)]
+    pub store: MemoryStoreConfig,
+
     /// Time-based decay rate for memories (0.0 - 1.0)
     #[serde(default = "default_memory_decay")]
     pub decay_rate: f64,
@@ -86,6 +94,53 @@ pub struct MemoryConfig {
     /// Memory categories to prioritize
     #[serde(default)]
     pub priority_categories: Vec<String>,
+
+    /// Whether to merge duplicate and near-duplicate memories on insert
+    #[serde(default = "default_deduplicate")]
+    pub deduplicate: bool,
+
+    /// Cosine similarity above which two memories are considered near-duplicates
+    #[serde(default = "default_dedup_threshold")]
+    pub dedup_threshold: f64,
+
+    /// Maximum number of embeddings kept in the content-hash cache
+    #[serde(default = "default_embedding_cache_size")]
+    pub embedding_cache_size: usize,
+
+    /// Balance between relevance and diversity when retrieving memories
+    ///
+    /// 1.0 ranks by relevance alone; lower values increasingly penalize
+    /// memories similar to ones already selected (maximal marginal relevance).
+    #[serde(default = "default_mmr_lambda")]
+    pub mmr_lambda: f64,
+
+    /// Maximum number of retrieved memories from any one category
+    #[serde(default)]
+    pub max_per_category: Option<usize>,
+
+    /// Approximate token budget for the content of retrieved memories
+    #[serde(default)]
+    pub retrieval_token_budget: Option<usize>,
+
+    /// Weights of the signals blended into memory relevance
+    #[serde(default)]
+    pub retrieval: RetrievalWeights,
+
+    /// Periodic summarization of recent memories into higher-level insights
+    #[serde(default)]
+    pub reflection: ReflectionConfig,
+
+    /// Expiry and fading of memories as game time passes
+    #[serde(default)]
+    pub forgetting: ForgettingPolicy,
+
+    /// Conversation sessions kept for each player ID
+    #[serde(default)]
+    pub sessions: SessionConfig,
+
+    /// Recall memories matching the agent's mood more readily
+    #[serde(default)]
+    pub mood_congruence: MoodCongruence,
 }
 
 fn default_memory_capacity() -> usize {
@@ -108,11 +163,28 @@ fn default_embedding_dim() -> usize {
     384 // Standard dimension for mini BERT models
 }
 
+fn default_deduplicate() -> bool {
+    true
+}
+
+fn default_dedup_threshold() -> f64 {
+    0.97
+}
+
+fn default_embedding_cache_size() -> usize {
+    1024
+}
+
+fn default_mmr_lambda() -> f64 {
+    0.7
+}
+
 impl Default for MemoryConfig {
     fn default() -> Self {
         Self {
             capacity: default_memory_capacity(),
             persistence: false,
+            store: MemoryStoreConfig::default(),
             decay_rate: default_memory_decay(),
             importance_threshold: default_memory_threshold(),
             short_term_capacity: default_short_term_capacity(),
@@ -121,6 +193,17 @@ impl Default for MemoryConfig {
             custom_model_path: None,
             embedding_dimension: default_embedding_dim(),
             priority_categories: Vec::new(),
+            deduplicate: default_deduplicate(),
+            dedup_threshold: default_dedup_threshold(),
+            embedding_cache_size: default_embedding_cache_size(),
+            mmr_lambda: default_mmr_lambda(),
+            max_per_category: None,
+            retrieval_token_budget: None,
+            retrieval: RetrievalWeights::default(),
+            reflection: ReflectionConfig::default(),
+            forgetting: ForgettingPolicy::default(),
+            sessions: SessionConfig::default(),
+            mood_congruence: MoodCongruence::default(),
         }
     }
 }
@@ -175,6 +258,51 @@ impl MemoryConfig {
             ));
         }
 
+        // Validate dedup threshold (0.0 - 1.0)
+        if !(0.0..=1.0).contains(&self.dedup_threshold) {
+            return Err(OxydeError::ConfigurationError(
+                format!(
+                    "Dedup threshold must be between 0.0 and 1.0, got {}",
+                    self.dedup_threshold
+                )
+            ));
+        }
+
+        // Validate MMR lambda (0.0 - 1.0)
+        if !(0.0..=1.0).contains(&self.mmr_lambda) {
+            return Err(OxydeError::ConfigurationError(
+                format!(
+                    "MMR lambda must be between 0.0 and 1.0, got {}",
+                    self.mmr_lambda
+                )
+            ));
+        }
+
+        if self.max_per_category == Some(0) {
+            return Err(OxydeError::ConfigurationError(
+                "Max memories per category must be greater than 0".to_string()
+            ));
+        }
+
+        if self.retrieval_token_budget == Some(0) {
+            return Err(OxydeError::ConfigurationError(
+                "Retrieval token budget must be greater than 0".to_string()
+            ));
+        }
+
+        if self.persistence && self.store.dir.is_none() {
+            return Err(OxydeError::ConfigurationError(
+                "Memory persistence needs memory.store.dir to save memories in".to_string()
+            ));
+        }
+
+        self.retrieval.validate()?;
+        self.reflection.validate()?;
+        self.forgetting.validate()?;
+        self.sessions.validate()?;
+        self.store.validate()?;
+        self.mood_congruence.validate()?;
+
         // Validate embedding dimension
         if self.use_embeddings && self.embedding_dimension == 0 {
             return Err(OxydeError::ConfigurationError(
@@ -204,7 +332,7 @@ impl MemoryConfig {
 }
 
 /// Configuration for the inference engine
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct InferenceConfig {
     /// Model to use for inference
     #[serde(default = "default_model")]
@@ -214,15 +342,30 @@ pub struct InferenceConfig {
     #[serde(default)]
     pub use_local: bool,
 
+    /// Provider to generate responses with; overrides `use_local` when set.
+    /// Use `mock` to run agents hermetically from a script.
+    #[serde(default)]
+    pub provider: Option<ProviderType>,
+
+    /// Script for the mock provider
+    #[serde(default)]
+    pub mock: MockProviderConfig,
+
     /// Path to the local model file (if use_local is true)
     pub local_model_path: Option<String>,
 
     /// Cloud API endpoint (if use_local is false)
     pub api_endpoint: Option<String>,
 
-    /// API key for cloud service
+    /// API key for cloud service. Prefer `api_key_ref`, which keeps the key
+    /// out of the config file.
     pub api_key: Option<String>,
 
+    /// Where the cloud API key is stored, such as `env:OPENAI_API_KEY`;
+    /// takes precedence over `api_key`
+    #[serde(default)]
+    pub api_key_ref: Option<SecretRef>,
+
     /// Inference temperature (0.0 - 1.0)
     #[serde(default = "default_temperature")]
     pub temperature: f32,
@@ -237,6 +380,18 @@ pub struct InferenceConfig {
 
     /// Fallback API to use if primary fails
     pub fallback_api: Option<String>,
+
+    /// Automatic model switching on latency or spend targets
+    #[serde(default)]
+    pub model_policy: ModelPolicyConfig,
+
+    /// Rules routing requests between several providers
+    #[serde(default)]
+    pub routing: RoutingConfig,
+
+    /// Temperature and sentence length following emotional arousal
+    #[serde(default)]
+    pub sampling: SamplingConfig,
 }
 
 fn default_model() -> String {
@@ -260,18 +415,73 @@ impl Default for InferenceConfig {
         Self {
             model: default_model(),
             use_local: false,
+            provider: None,
+            mock: MockProviderConfig::default(),
             local_model_path: None,
             api_endpoint: Some("https://api.openai.com/v1/chat/completions".to_string()),
             api_key: None,
+            api_key_ref: None,
             temperature: default_temperature(),
             max_tokens: default_max_tokens(),
             timeout_ms: default_timeout(),
             fallback_api: None,
+            model_policy: ModelPolicyConfig::default(),
+            routing: RoutingConfig::default(),
+            sampling: SamplingConfig::default(),
         }
     }
 }
 
+impl fmt::Debug for InferenceConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InferenceConfig")
+            .field("model", &self.model)
+            .field("use_local", &self.use_local)
+            .field("provider", &self.provider)
+            .field("mock", &self.mock)
+            .field("local_model_path", &self.local_model_path)
+            .field("api_endpoint", &self.api_endpoint)
+            .field("api_key", &redact(&self.api_key))
+            .field("api_key_ref", &self.api_key_ref)
+            .field("temperature", &self.temperature)
+            .field("max_tokens", &self.max_tokens)
+            .field("timeout_ms", &self.timeout_ms)
+            .field("fallback_api", &self.fallback_api)
+            .field("model_policy", &self.model_policy)
+            .field("routing", &self.routing)
+            .field("sampling", &self.sampling)
+            .finish()
+    }
+}
+
 impl InferenceConfig {
+    /// API key from `api_key_ref` or `api_key`, without environment fallbacks
+    ///
+    /// # Returns
+    ///
+    /// The key if one is configured, or an error if `api_key_ref` cannot be
+    /// resolved
+    pub fn configured_api_key(&self) -> Result<Option<String>> {
+        match &self.api_key_ref {
+            Some(reference) => reference.resolve().map(|secret| Some(secret.into_inner())),
+            None => Ok(self.api_key.clone()),
+        }
+    }
+
+    /// Cloud API key, falling back to the `OXYDE_API_KEY` environment variable
+    pub fn resolve_api_key(&self) -> Result<Option<String>> {
+        Ok(self.configured_api_key()?.or_else(|| std::env::var("OXYDE_API_KEY").ok()))
+    }
+
+    /// Provider responses are generated with
+    pub fn provider_type(&self) -> ProviderType {
+        self.provider.unwrap_or(if self.use_local {
+            ProviderType::Local
+        } else {
+            ProviderType::Cloud
+        })
+    }
+
     /// Validate the inference configuration
     ///
     /// # Returns
@@ -321,7 +531,7 @@ impl InferenceConfig {
         }
 
         // Validate local model configuration
-        if self.use_local {
+        if self.provider_type() == ProviderType::Local {
             if self.local_model_path.is_none() {
                 return Err(OxydeError::ConfigurationError(
                     "Local model path must be provided when use_local is true".to_string()
@@ -338,7 +548,7 @@ impl InferenceConfig {
         }
 
         // Validate cloud API configuration
-        if !self.use_local {
+        if self.provider_type() == ProviderType::Cloud {
             if self.api_endpoint.is_none() {
                 return Err(OxydeError::ConfigurationError(
                     "API endpoint must be provided when using cloud inference".to_string()
@@ -371,6 +581,128 @@ impl InferenceConfig {
             ));
         }
 
+        self.mock.validate()?;
+        self.routing.validate()?;
+        self.sampling.validate()?;
+        self.model_policy.validate(self)
+    }
+}
+
+/// A model the inference engine can switch to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelTier {
+    /// Model name sent to the cloud API, or a label for the local model
+    pub model: String,
+
+    /// Whether this tier runs on the local model instead of the cloud API
+    #[serde(default)]
+    pub local: bool,
+
+    /// Cost per 1000 generated tokens, in any currency unit
+    #[serde(default)]
+    pub cost_per_1k_tokens: f64,
+
+    /// Cost per 1000 prompt tokens, used only for cost estimates
+    #[serde(default)]
+    pub prompt_cost_per_1k_tokens: f64,
+}
+
+/// Policy for switching models when latency or spend targets are breached
+///
+/// Tiers are ordered from preferred to cheapest. The engine starts on the
+/// first tier, moves one tier down when the rolling latency or spend
+/// exceeds its target, and moves back up once both have stayed below
+/// `recovery_ratio` of their targets. Switching is disabled when no tiers
+/// are configured.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelPolicyConfig {
+    /// Models ordered from preferred to cheapest
+    #[serde(default)]
+    pub tiers: Vec<ModelTier>,
+
+    /// Rolling average latency target in milliseconds
+    #[serde(default)]
+    pub max_latency_ms: Option<u64>,
+
+    /// Spend target within `spend_window_secs`
+    #[serde(default)]
+    pub max_spend: Option<f64>,
+
+    /// Length of the rolling spend window in seconds
+    #[serde(default = "default_spend_window_secs")]
+    pub spend_window_secs: u64,
+
+    /// Number of recent responses averaged for latency
+    #[serde(default = "default_latency_window")]
+    pub latency_window: usize,
+
+    /// Fraction of each target metrics must fall below before upgrading
+    #[serde(default = "default_recovery_ratio")]
+    pub recovery_ratio: f64,
+
+    /// Minimum time between switches in milliseconds
+    #[serde(default = "default_min_switch_interval_ms")]
+    pub min_switch_interval_ms: u64,
+}
+
+fn default_spend_window_secs() -> u64 {
+    60
+}
+
+fn default_latency_window() -> usize {
+    5
+}
+
+fn default_recovery_ratio() -> f64 {
+    0.7
+}
+
+fn default_min_switch_interval_ms() -> u64 {
+    30_000
+}
+
+impl Default for ModelPolicyConfig {
+    fn default() -> Self {
+        Self {
+            tiers: Vec::new(),
+            max_latency_ms: None,
+            max_spend: None,
+            spend_window_secs: default_spend_window_secs(),
+            latency_window: default_latency_window(),
+            recovery_ratio: default_recovery_ratio(),
+            min_switch_interval_ms: default_min_switch_interval_ms(),
+        }
+    }
+}
+
+impl ModelPolicyConfig {
+    /// Validate the policy against the inference configuration it belongs to
+    fn validate(&self, inference: &InferenceConfig) -> Result<()> {
+        if self.tiers.is_empty() {
+            return Ok(());
+        }
+
+        if self.tiers.iter().any(|tier| tier.local) && inference.local_model_path.is_none() {
+            return Err(OxydeError::ConfigurationError(
+                "Local model tiers require a local model path".to_string()
+            ));
+        }
+
+        if self.latency_window == 0 {
+            return Err(OxydeError::ConfigurationError(
+                "Model policy latency window must be greater than 0".to_string()
+            ));
+        }
+
+        if !(self.recovery_ratio > 0.0 && self.recovery_ratio <= 1.0) {
+            return Err(OxydeError::ConfigurationError(
+                format!(
+                    "Model policy recovery ratio must be in (0.0, 1.0], got {}",
+                    self.recovery_ratio
+                )
+            ));
+        }
+
         Ok(())
     }
 }
@@ -378,6 +710,10 @@ impl InferenceConfig {
 /// Configuration for a behavior
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BehaviorConfig {
+    /// Registered behavior name; defaults to this behavior's key in the config
+    #[serde(default, rename = "type")]
+    pub behavior_type: Option<String>,
+
     /// Trigger condition for the behavior
     pub trigger: String,
 
@@ -389,13 +725,37 @@ pub struct BehaviorConfig {
     #[serde(default)]
     pub priority: u32,
 
+    /// Expression that must hold for the behavior to run, such as
+    /// `player_distance < 3 && emotions.fear > 0.5`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<Condition>,
+
+    /// Emotions that must hold for the behavior to be selected, such as
+    /// `fear > 0.5 AND trust < 0` or `anger > 0.4 for 3 turns`
+    #[serde(
+        default,
+        deserialize_with = "crate::oxyde_game::behavior::deserialize_trigger",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub emotion_trigger: Option<EmotionTrigger>,
+
+    /// Longest time the behavior may take to execute, in milliseconds;
+    /// defaults to `supervisor.behavior_timeout_ms`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+
+    /// Response given when the behavior times out; lower-priority behaviors
+    /// run instead when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_response: Option<String>,
+
     /// Additional behavior-specific configuration
     #[serde(flatten)]
     pub parameters: HashMap<String, serde_json::Value>,
 }
 
 /// Configuration for content moderation
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ModerationConfig {
     /// Whether moderation is enabled
     #[serde(default)]
@@ -411,12 +771,53 @@ pub struct ModerationConfig {
     
     /// API key for cloud moderation (uses same as inference if not set)
     pub cloud_moderation_api_key: Option<String>,
+
+    /// Whether generated responses are moderated as well as player input
+    #[serde(default = "default_moderate_output")]
+    pub moderate_output: bool,
+
+    /// How many times a flagged response is regenerated before falling back
+    /// to `response_message`
+    #[serde(default = "default_max_output_retries")]
+    pub max_output_retries: u32,
+
+    /// System instruction added when regenerating a flagged response
+    #[serde(default = "default_retry_instruction")]
+    pub retry_instruction: String,
 }
 
 fn default_moderation_response() -> String {
     "Sorry, I can't respond to that.".to_string()
 }
 
+fn default_moderate_output() -> bool {
+    true
+}
+
+fn default_max_output_retries() -> u32 {
+    2
+}
+
+fn default_retry_instruction() -> String {
+    "Your previous reply was flagged as inappropriate. Reply again in character, \
+     without offensive, hateful, or explicit language."
+        .to_string()
+}
+
+impl fmt::Debug for ModerationConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ModerationConfig")
+            .field("enabled", &self.enabled)
+            .field("response_message", &self.response_message)
+            .field("use_cloud_moderation", &self.use_cloud_moderation)
+            .field("cloud_moderation_api_key", &redact(&self.cloud_moderation_api_key))
+            .field("moderate_output", &self.moderate_output)
+            .field("max_output_retries", &self.max_output_retries)
+            .field("retry_instruction", &self.retry_instruction)
+            .finish()
+    }
+}
+
 impl Default for ModerationConfig {
     fn default() -> Self {
         Self {
@@ -424,6 +825,140 @@ impl Default for ModerationConfig {
             response_message: default_moderation_response(),
             use_cloud_moderation: false,
             cloud_moderation_api_key: None,
+            moderate_output: default_moderate_output(),
+            max_output_retries: default_max_output_retries(),
+            retry_instruction: default_retry_instruction(),
+        }
+    }
+}
+
+/// Configuration for supervising `process_input` requests
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupervisorConfig {
+    /// Maximum time a single request may take in milliseconds (0 disables the limit)
+    #[serde(default = "default_request_timeout")]
+    pub request_timeout_ms: u64,
+
+    /// Response returned when a request fails, times out, or panics
+    ///
+    /// When unset, the underlying error is returned to the caller instead.
+    #[serde(default)]
+    pub fallback_response: Option<String>,
+
+    /// Maximum time a behavior may take to execute in milliseconds, unless
+    /// it sets its own (0 disables the limit)
+    #[serde(default = "default_behavior_timeout")]
+    pub behavior_timeout_ms: u64,
+
+    /// Maximum time inference may take once the agent starts generating, in
+    /// milliseconds (0 disables the limit); slower inference falls back to
+    /// the offline response
+    #[serde(default = "default_generation_timeout")]
+    pub generation_timeout_ms: u64,
+}
+
+fn default_request_timeout() -> u64 {
+    30000 // 30 seconds
+}
+
+fn default_behavior_timeout() -> u64 {
+    2000
+}
+
+fn default_generation_timeout() -> u64 {
+    20000 // 20 seconds
+}
+
+impl Default for SupervisorConfig {
+    fn default() -> Self {
+        Self {
+            request_timeout_ms: default_request_timeout(),
+            fallback_response: None,
+            behavior_timeout_ms: default_behavior_timeout(),
+            generation_timeout_ms: default_generation_timeout(),
+        }
+    }
+}
+
+/// How `process_input` handles an input repeated within the debounce window
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateInputAction {
+    /// Reply with the response to the first input, waiting for it if needed
+    #[default]
+    ReturnCached,
+    /// Drop the repeat and reply with an empty response
+    Drop,
+}
+
+/// Configuration for input debouncing and duplicate suppression
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DebounceConfig {
+    /// Whether duplicate inputs are suppressed
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Window after an input during which the same normalized input counts as
+    /// a duplicate, in milliseconds
+    #[serde(default = "default_debounce_window")]
+    pub window_ms: u64,
+
+    /// What to do with duplicates
+    #[serde(default)]
+    pub action: DuplicateInputAction,
+}
+
+fn default_debounce_window() -> u64 {
+    2000
+}
+
+impl Default for DebounceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            window_ms: default_debounce_window(),
+            action: DuplicateInputAction::default(),
+        }
+    }
+}
+
+/// How `process_input` handles input that arrives while another input is
+/// being processed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConcurrencyPolicy {
+    /// Process inputs one at a time in arrival order
+    #[default]
+    Queue,
+    /// Keep only the latest waiting input; every caller that was waiting
+    /// receives the response to it
+    Coalesce,
+    /// Fail with a busy error while an input is being processed
+    Reject,
+}
+
+/// Configuration for the agent's input request queue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestQueueConfig {
+    /// What to do with overlapping inputs
+    #[serde(default)]
+    pub policy: ConcurrencyPolicy,
+
+    /// Maximum number of inputs waiting behind the one being processed
+    /// before new inputs are rejected as busy; 0 for no limit
+    #[serde(default = "default_max_queue_depth")]
+    pub max_depth: usize,
+}
+
+fn default_max_queue_depth() -> usize {
+    16
+}
+
+impl Default for RequestQueueConfig {
+    fn default() -> Self {
+        Self {
+            policy: ConcurrencyPolicy::default(),
+            max_depth: default_max_queue_depth(),
         }
     }
 }
@@ -431,6 +966,11 @@ impl Default for ModerationConfig {
 /// Complete agent configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentConfig {
+    /// Config format version; older files are upgraded when loaded, see
+    /// [`crate::config_migration`]
+    #[serde(default = "default_config_version")]
+    pub config_version: u32,
+
     /// Agent personality configuration
     pub agent: AgentPersonality,
 
@@ -450,10 +990,168 @@ pub struct AgentConfig {
     #[serde(default)]
     pub moderation: ModerationConfig,
 
+    /// Request supervision configuration
+    #[serde(default)]
+    pub supervisor: SupervisorConfig,
+
+    /// Input debouncing configuration
+    #[serde(default)]
+    pub debounce: DebounceConfig,
+
+    /// Overlapping input handling
+    #[serde(default)]
+    pub request_queue: RequestQueueConfig,
+
+    /// Canned responses used when inference is unavailable
+    #[serde(default)]
+    pub offline_fallback: OfflineFallbackConfig,
+
+    /// Conversation topic tracking and agenda configuration
+    #[serde(default)]
+    pub topics: TopicConfig,
+
+    /// Persuasion check configuration
+    #[serde(default)]
+    pub persuasion: PersuasionConfig,
+
+    /// PII redaction before cloud inference and memory storage
+    #[serde(default)]
+    pub redaction: RedactionConfig,
+
+    /// Intents trained from example phrases
+    #[serde(default)]
+    pub intents: IntentConfig,
+
+    /// Declared context keys, types, and ranges
+    #[serde(default)]
+    pub context_schema: ContextSchema,
+
+    /// Cleanup applied to generated responses
+    #[serde(default)]
+    pub postprocess: PostProcessConfig,
+
+    /// Actions the agent may perform and knowledge it may reveal
+    #[serde(default)]
+    pub capabilities: CapabilitiesConfig,
+
+    /// Opt-in logging of full prompt/response pairs
+    #[serde(default)]
+    pub interaction_log: InteractionLogConfig,
+
+    /// Replayable log of memory, emotion and context changes
+    #[serde(default)]
+    pub event_log: EventLogConfig,
+
+    /// A/B experiment assigning this agent or its players to prompt and model variants
+    #[serde(default)]
+    pub experiment: ExperimentConfig,
+
+    /// Response length budgets per interaction channel
+    #[serde(default)]
+    pub verbosity: VerbosityConfig,
+
+    /// Detection of players returning after a long absence
+    #[serde(default)]
+    pub reengagement: ReengagementConfig,
+
+    /// Long-term personality drift from emotional memories
+    #[serde(default)]
+    pub disposition: DispositionConfig,
+
+    /// Gesture, facial expression and movement hints for responses
+    #[serde(default)]
+    pub annotations: AnnotationConfig,
+
+    /// JSON replies for `process_input_structured`
+    #[serde(default)]
+    pub structured_output: StructuredOutputConfig,
+
+    /// Buffer of said lines for engine UI to read in order
+    #[serde(default)]
+    pub dialogue_queue: DialogueQueueConfig,
+
+    /// Detection of the language players write in
+    #[serde(default)]
+    pub language: LanguageConfig,
+
+    /// Model of how the agent feels, projected onto Plutchik emotions
+    #[serde(default)]
+    pub affect: AffectConfig,
+
+    /// Limits of the price and haggling modifiers the agent's feelings give
+    #[serde(default)]
+    pub modifiers: SentimentModifiers,
+
+    /// Rolling toxicity scores for players flagged by moderation
+    #[serde(default)]
+    pub reputation: ReputationConfig,
+
+    /// Private first-person notes written after each exchange
+    #[serde(default)]
+    pub monologue: MonologueConfig,
+
+    /// Filler replies emitted while slow responses are generated
+    #[serde(default)]
+    pub latency: LatencyConfig,
+
+    /// Daily routine configuration
+    #[serde(default)]
+    pub schedule: ScheduleConfig,
+
+    /// Layered system prompt configuration
+    #[serde(default)]
+    pub prompts: PromptConfig,
+
     ///Text to Speech Configurations
     pub tts: Option<TTSConfig>,
 }
 
+fn default_config_version() -> u32 {
+    CONFIG_VERSION
+}
+
+impl Default for AgentConfig {
+    fn default() -> Self {
+        Self {
+            config_version: CONFIG_VERSION,
+            agent: Default::default(),
+            memory: Default::default(),
+            inference: Default::default(),
+            behavior: Default::default(),
+            moderation: Default::default(),
+            supervisor: Default::default(),
+            debounce: Default::default(),
+            request_queue: Default::default(),
+            offline_fallback: Default::default(),
+            topics: Default::default(),
+            persuasion: Default::default(),
+            redaction: Default::default(),
+            intents: Default::default(),
+            context_schema: Default::default(),
+            postprocess: Default::default(),
+            capabilities: Default::default(),
+            interaction_log: Default::default(),
+            event_log: Default::default(),
+            experiment: Default::default(),
+            verbosity: Default::default(),
+            reengagement: Default::default(),
+            disposition: Default::default(),
+            annotations: Default::default(),
+            structured_output: Default::default(),
+            dialogue_queue: Default::default(),
+            language: Default::default(),
+            affect: Default::default(),
+            modifiers: Default::default(),
+            reputation: Default::default(),
+            monologue: Default::default(),
+            latency: Default::default(),
+            schedule: Default::default(),
+            prompts: Default::default(),
+            tts: None,
+        }
+    }
+}
+
 impl AgentConfig {
     /// Validate the agent configuration
     ///
@@ -480,6 +1178,50 @@ impl AgentConfig {
         // Validate inference configuration
         self.inference.validate()?;
 
+        // Validate schedule configuration
+        self.schedule.validate()?;
+
+        // Validate intent definitions
+        self.intents.validate()?;
+
+        // Validate context schema
+        self.context_schema.validate()?;
+
+        // Validate response post-processing
+        self.postprocess.validate()?;
+
+        // Validate capabilities
+        self.capabilities.validate()?;
+
+        // Validate interaction logging
+        self.interaction_log.validate()?;
+        self.event_log.validate()?;
+        self.experiment.validate()?;
+        self.verbosity.validate()?;
+
+        // Validate re-engagement
+        self.reengagement.validate()?;
+
+        // Validate disposition
+        self.disposition.validate()?;
+
+        // Validate response annotations
+        self.annotations.validate()?;
+
+        // Validate structured output
+        self.structured_output.validate()?;
+        self.dialogue_queue.validate()?;
+
+        // Validate language detection
+        self.language.validate()?;
+
+        // Validate player reputation tracking
+        self.reputation.validate()?;
+
+        // Validate internal monologue
+        self.monologue.validate()?;
+        self.latency.validate()?;
+
         // Validate behavior configurations
         for (name, behavior_config) in &self.behavior {
             if name.is_empty() {
@@ -498,53 +1240,147 @@ impl AgentConfig {
         Ok(())
     }
 
+    /// Run the agent without credentials, network access or files
+    ///
+    /// Inference uses the mock provider, with any scripted responses in
+    /// `inference.mock`, and speech the mock TTS provider; a TTS section is
+    /// added when there is none. Memories stay in memory without embeddings,
+    /// moderation uses the local filters only, and interactions are not
+    /// logged.
+    pub fn offline(mut self) -> Self {
+        self.inference.provider = Some(ProviderType::Mock);
+        self.inference.use_local = false;
+        self.inference.api_key = None;
+        self.inference.api_key_ref = None;
+        self.inference.fallback_api = None;
+        self.inference.routing = RoutingConfig::default();
+        self.memory.persistence = false;
+        self.memory.use_embeddings = false;
+        self.moderation.use_cloud_moderation = false;
+        self.interaction_log.enabled = false;
+
+        let tts = self.tts.get_or_insert_with(TTSConfig::mock);
+        tts.default_provider = TTSProvider::Mock;
+        tts.api_key_ref = None;
+        tts.cache_dir = None;
+        if !tts.output_format.is_uncompressed() {
+            tts.output_format = AudioFormat::WAV;
+        }
+        self
+    }
+
     /// Load an agent configuration from a file
     ///
+    /// Configs written for older SDK versions are upgraded, logging a
+    /// warning for each change.
+    ///
     /// # Arguments
     ///
-    /// * `path` - Path to the configuration file (JSON or YAML)
+    /// * `path` - Path to the configuration file (JSON, YAML, or TOML)
     ///
     /// # Returns
     ///
     /// The loaded AgentConfig or an error
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let (config, report) = Self::from_file_migrated(path.as_ref())?;
+        report.log(&path.as_ref().display().to_string());
+        Ok(config)
+    }
+
+    /// Load an agent configuration from a file, reporting how it was upgraded
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the configuration file (JSON, YAML, or TOML)
+    ///
+    /// # Returns
+    ///
+    /// The loaded AgentConfig and the changes made to bring it to
+    /// [`CONFIG_VERSION`], or an error
+    pub fn from_file_migrated<P: AsRef<Path>>(path: P) -> Result<(Self, MigrationReport)> {
         let file = File::open(path.as_ref()).map_err(|e| {
             OxydeError::ConfigurationError(format!("Failed to open config file: {}", e))
         })?;
 
-        let reader = BufReader::new(file);
+        let mut contents = String::new();
+        BufReader::new(file).read_to_string(&mut contents).map_err(|e| {
+            OxydeError::ConfigurationError(format!("Failed to read config file: {}", e))
+        })?;
 
         let extension = path.as_ref().extension().and_then(|ext| ext.to_str());
+        Self::parse_migrated(&contents, extension)
+    }
 
-        let config: AgentConfig = match extension {
+    /// Parse and validate an agent configuration
+    ///
+    /// Configs written for older SDK versions are upgraded, logging a
+    /// warning for each change.
+    ///
+    /// # Arguments
+    ///
+    /// * `contents` - Configuration text
+    /// * `extension` - Format of the text: `json`, `yaml`, `yml`, or `toml`
+    ///
+    /// # Returns
+    ///
+    /// The parsed AgentConfig or an error
+    pub fn parse(contents: &str, extension: Option<&str>) -> Result<Self> {
+        let (config, report) = Self::parse_migrated(contents, extension)?;
+        report.log("agent config");
+        Ok(config)
+    }
+
+    /// Parse, upgrade and validate an agent configuration
+    ///
+    /// # Arguments
+    ///
+    /// * `contents` - Configuration text
+    /// * `extension` - Format of the text: `json`, `yaml`, `yml`, or `toml`
+    ///
+    /// # Returns
+    ///
+    /// The parsed AgentConfig and the changes made to bring it to
+    /// [`CONFIG_VERSION`], or an error
+    pub fn parse_migrated(contents: &str, extension: Option<&str>) -> Result<(Self, MigrationReport)> {
+        let mut document: serde_json::Value = match extension {
             Some("json") => {
-                serde_json::from_reader(reader).map_err(|e| {
+                serde_json::from_str(contents).map_err(|e| {
                     OxydeError::ConfigurationError(format!("Failed to parse JSON config: {}", e))
                 })?
             },
             Some("yaml") | Some("yml") => {
-                serde_yaml::from_reader(reader).map_err(|e| {
+                serde_yaml::from_str(contents).map_err(|e| {
                     OxydeError::ConfigurationError(format!("Failed to parse YAML config: {}", e))
                 })?
             },
+            Some("toml") => {
+                toml::from_str(contents).map_err(|e| {
+                    OxydeError::ConfigurationError(format!("Failed to parse TOML config: {}", e))
+                })?
+            },
             _ => {
                 return Err(OxydeError::ConfigurationError(
-                    "Unknown config file format. Expected .json, .yaml, or .yml".to_string()
+                    "Unknown config file format. Expected .json, .yaml, .yml, or .toml".to_string()
                 ));
             }
         };
 
+        let report = migrate(&mut document)?;
+        let config: AgentConfig = serde_json::from_value(document).map_err(|e| {
+            OxydeError::ConfigurationError(format!("Invalid agent config: {}", e))
+        })?;
+
         // Validate the loaded configuration
         config.validate()?;
 
-        Ok(config)
+        Ok((config, report))
     }
 
     /// Save the agent configuration to a file
     ///
     /// # Arguments
     ///
-    /// * `path` - Path to save the configuration file (JSON or YAML)
+    /// * `path` - Path to save the configuration file (JSON, YAML, or TOML)
     ///
     /// # Returns
     ///
@@ -563,8 +1399,16 @@ impl AgentConfig {
             Some("yaml") | Some("yml") => serde_yaml::to_writer(file, self).map_err(|e| {
                 OxydeError::ConfigurationError(format!("Failed to write YAML config: {}", e))
             }),
+            Some("toml") => {
+                let text = toml::to_string_pretty(self).map_err(|e| {
+                    OxydeError::ConfigurationError(format!("Failed to write TOML config: {}", e))
+                })?;
+                (&file).write_all(text.as_bytes()).map_err(|e| {
+                    OxydeError::ConfigurationError(format!("Failed to write TOML config: {}", e))
+                })
+            }
             _ => Err(OxydeError::ConfigurationError(
-                "Unknown config file format. Expected .json, .yaml, or .yml".to_string(),
+                "Unknown config file format. Expected .json, .yaml, .yml, or .toml".to_string(),
             )),
         }
     }
@@ -596,11 +1440,7 @@ mod tests {
                 backstory: vec!["A test agent".to_string()],
                 knowledge: vec!["Testing knowledge".to_string()],
             },
-            memory: MemoryConfig::default(),
-            inference: InferenceConfig::default(),
-            behavior: HashMap::new(),
-            moderation: ModerationConfig::default(),
-            tts: None
+            ..Default::default()
         };
 
         let json = serde_json::to_string(&config).unwrap();
@@ -626,6 +1466,18 @@ mod tests {
         assert!(result.unwrap_err().to_string().contains("capacity must be greater than 0"));
     }
 
+    #[test]
+    fn test_memory_config_validation_persistence_without_store_dir() {
+        let mut config = MemoryConfig {
+            persistence: true,
+            ..Default::default()
+        };
+        assert!(config.validate().unwrap_err().to_string().contains("memory.store.dir"));
+
+        config.store.dir = Some(std::env::temp_dir().join("oxyde-memories"));
+        assert!(config.validate().is_ok());
+    }
+
     #[test]
     fn test_memory_config_validation_short_term_exceeds_capacity() {
         let mut config = MemoryConfig::default();
@@ -755,11 +1607,7 @@ mod tests {
                 backstory: vec![],
                 knowledge: vec![],
             },
-            memory: MemoryConfig::default(),
-            inference: InferenceConfig::default(),
-            behavior: HashMap::new(),
-            moderation: ModerationConfig::default(),
-            tts: None
+            ..Default::default()
         };
 
         assert!(config.validate().is_ok());
@@ -774,11 +1622,7 @@ mod tests {
                 backstory: vec![],
                 knowledge: vec![],
             },
-            memory: MemoryConfig::default(),
-            inference: InferenceConfig::default(),
-            behavior: HashMap::new(),
-            moderation: ModerationConfig::default(),
-            tts: None
+            ..Default::default()
         };
 
         let result = config.validate();
@@ -795,11 +1639,7 @@ mod tests {
                 backstory: vec![],
                 knowledge: vec![],
             },
-            memory: MemoryConfig::default(),
-            inference: InferenceConfig::default(),
-            behavior: HashMap::new(),
-            moderation: ModerationConfig::default(),
-            tts: None
+            ..Default::default()
         };
 
         let result = config.validate();
@@ -820,10 +1660,7 @@ mod tests {
                 capacity: 0,  // Invalid
                 ..Default::default()
             },
-            inference: InferenceConfig::default(),
-            behavior: HashMap::new(),
-            moderation: ModerationConfig::default(),
-            tts: None
+            ..Default::default()
         };
 
         let result = config.validate();
@@ -840,18 +1677,74 @@ mod tests {
                 backstory: vec![],
                 knowledge: vec![],
             },
-            memory: MemoryConfig::default(),
             inference: InferenceConfig {
                 temperature: 5.0,  // Invalid
                 ..Default::default()
             },
-            behavior: HashMap::new(),
-            moderation: ModerationConfig::default(),
-            tts: None
+            ..Default::default()
         };
 
         let result = config.validate();
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("Temperature"));
     }
+
+    #[test]
+    fn test_offline_config_needs_no_credentials() {
+        let yaml = r#"
+agent:
+  name: Test
+  role: Tester
+  backstory: []
+  knowledge: []
+memory:
+  persistence: true
+  use_embeddings: true
+  embedding_dimension: 384
+moderation:
+  use_cloud_moderation: true
+inference:
+  api_key: sk-test
+  fallback_api: https://example.com/v1
+"#;
+        let config = serde_yaml::from_str::<AgentConfig>(yaml).unwrap().offline();
+        config.validate().unwrap();
+        assert_eq!(config.inference.provider_type(), ProviderType::Mock);
+        assert_eq!(config.inference.configured_api_key().unwrap(), None);
+        assert!(config.inference.fallback_api.is_none());
+        assert!(!config.memory.persistence);
+        assert!(!config.memory.use_embeddings);
+        assert!(!config.moderation.use_cloud_moderation);
+        let tts = config.tts.unwrap();
+        assert_eq!(tts.default_provider, TTSProvider::Mock);
+        assert_eq!(tts.output_format, AudioFormat::WAV);
+    }
+
+    #[test]
+    fn test_parse_upgrades_older_config_versions() {
+        let toml = r#"
+[agent]
+name = "Bram"
+role = "Guard"
+backstory = []
+knowledge = []
+
+[inference]
+api_endpoint = "https://api.openai.com/v1/chat/completions"
+system_prompt = "You guard the north gate."
+
+[behavior.greeting]
+trigger = "proximity"
+parameters = { greetings = ["Halt!"] }
+"#;
+        let (config, report) = AgentConfig::parse_migrated(toml, Some("toml")).unwrap();
+        assert_eq!(report.from_version, 1);
+        assert_eq!(config.config_version, CONFIG_VERSION);
+        assert_eq!(config.prompts.agent.as_deref(), Some("You guard the north gate."));
+        assert_eq!(config.behavior["greeting"].parameters["greetings"], serde_json::json!(["Halt!"]));
+
+        let saved = serde_json::to_string(&config).unwrap();
+        let (_, report) = AgentConfig::parse_migrated(&saved, Some("json")).unwrap();
+        assert!(!report.migrated() && report.warnings.is_empty());
+    }
 }
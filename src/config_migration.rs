@@ -0,0 +1,295 @@
+//! Upgrading agent configs written for older SDK versions
+//!
+//! Config files carry a `config_version`. Files without one are version 1,
+//! the format of the first SDK releases. When a file is loaded, every
+//! migration between its version and [`CONFIG_VERSION`] is applied to the
+//! parsed document before it is read as an [`AgentConfig`](crate::config::AgentConfig):
+//! keys that moved are renamed, keys that are no longer read are dropped, and
+//! sections added since are left to their defaults. Each change is reported
+//! as a [`ConfigWarning`]; `AgentConfig::from_file` logs them and the CLI
+//! `validate` command prints them.
+//!
+//! ```yaml
+//! # version 1                      # version 2
+//! inference:                       config_version: 2
+//!   system_prompt: "You are..."    prompts:
+//! behavior:                          agent: "You are..."
+//!   greeting:                      behavior:
+//!     trigger: proximity             greeting:
+//!     parameters:                      trigger: proximity
+//!       lines: ["Hi!"]                 lines: ["Hi!"]
+//! ```
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{OxydeError, Result};
+
+/// Config format written by this SDK version
+pub const CONFIG_VERSION: u32 = 2;
+
+/// Key holding the config format version
+pub const CONFIG_VERSION_KEY: &str = "config_version";
+
+/// Version of configs without a `config_version` key
+const UNVERSIONED: u32 = 1;
+
+/// Keys moved in version 2, as dotted paths from old to new
+const MOVED_IN_V2: &[(&str, &str)] = &[
+    ("inference.system_prompt", "prompts.agent"),
+    ("scene_prompt", "prompts.scene"),
+    ("moderation.api_key", "moderation.cloud_moderation_api_key"),
+    ("tts.provider", "tts.default_provider"),
+];
+
+/// Top-level sections added in version 2
+const ADDED_IN_V2: &[&str] = &[
+    "supervisor",
+    "debounce",
+    "request_queue",
+    "offline_fallback",
+    "topics",
+    "persuasion",
+    "redaction",
+    "intents",
+    "context_schema",
+    "postprocess",
+    "capabilities",
+    "interaction_log",
+    "event_log",
+    "experiment",
+    "verbosity",
+    "reengagement",
+    "disposition",
+    "annotations",
+    "structured_output",
+    "reputation",
+    "monologue",
+    "latency",
+    "schedule",
+    "prompts",
+];
+
+/// A change made while upgrading a config
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigWarning {
+    /// Dotted path of the affected key, such as `inference.system_prompt`
+    pub key: String,
+
+    /// What changed and what to do about it
+    pub message: String,
+}
+
+impl ConfigWarning {
+    fn new(key: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.key, self.message)
+    }
+}
+
+/// Outcome of upgrading a config
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MigrationReport {
+    /// Version the config was written in
+    pub from_version: u32,
+
+    /// Changes made, oldest migration first
+    pub warnings: Vec<ConfigWarning>,
+}
+
+impl MigrationReport {
+    /// Whether the config was written in an older format
+    pub fn migrated(&self) -> bool {
+        self.from_version < CONFIG_VERSION
+    }
+
+    /// Log each change as a warning
+    pub fn log(&self, source: &str) {
+        if self.migrated() {
+            log::warn!(
+                "{} uses config version {}; upgraded to version {}",
+                source,
+                self.from_version,
+                CONFIG_VERSION
+            );
+        }
+        for warning in &self.warnings {
+            log::warn!("{}: {}", source, warning);
+        }
+    }
+}
+
+/// Upgrade a parsed config document to [`CONFIG_VERSION`] in place
+///
+/// # Arguments
+///
+/// * `document` - The config as a JSON value, whatever its file format
+///
+/// # Returns
+///
+/// The version the document was in and the changes made, or an error if the
+/// document is not an object or was written by a newer SDK
+pub fn migrate(document: &mut Value) -> Result<MigrationReport> {
+    let root = document.as_object_mut().ok_or_else(|| {
+        OxydeError::ConfigurationError("Agent config must be an object".to_string())
+    })?;
+
+    let from_version = match root.get(CONFIG_VERSION_KEY) {
+        None => UNVERSIONED,
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| {
+                OxydeError::ConfigurationError(format!("Invalid {}: {}", CONFIG_VERSION_KEY, version))
+            })?,
+    };
+    if from_version > CONFIG_VERSION {
+        return Err(OxydeError::ConfigurationError(format!(
+            "Config version {} was written by a newer SDK; this SDK reads up to version {}",
+            from_version, CONFIG_VERSION
+        )));
+    }
+
+    let mut warnings = Vec::new();
+    if from_version < 2 {
+        migrate_v1(root, &mut warnings);
+    }
+    root.insert(CONFIG_VERSION_KEY.to_string(), Value::from(CONFIG_VERSION));
+
+    Ok(MigrationReport { from_version, warnings })
+}
+
+/// Version 1 to 2: moved keys, nested behavior parameters and plaintext TTS keys
+fn migrate_v1(root: &mut Map<String, Value>, warnings: &mut Vec<ConfigWarning>) {
+    for (from, to) in MOVED_IN_V2 {
+        move_key(root, from, to, warnings);
+    }
+
+    // Behavior parameters sit next to `trigger` rather than in a nested object
+    if let Some(Value::Object(behaviors)) = root.get_mut("behavior") {
+        for (name, behavior) in behaviors.iter_mut() {
+            let Some(behavior) = behavior.as_object_mut() else {
+                continue;
+            };
+            let Some(Value::Object(parameters)) = behavior.remove("parameters") else {
+                continue;
+            };
+            for (key, value) in parameters {
+                if behavior.contains_key(&key) {
+                    warnings.push(ConfigWarning::new(
+                        format!("behavior.{}.parameters.{}", name, key),
+                        format!("dropped; behavior.{}.{} is already set", name, key),
+                    ));
+                } else {
+                    behavior.insert(key, value);
+                }
+            }
+            warnings.push(ConfigWarning::new(
+                format!("behavior.{}.parameters", name),
+                format!("moved into behavior.{}", name),
+            ));
+        }
+    }
+
+    if let Some(Value::Object(tts)) = root.get_mut("tts") {
+        if tts.remove("api_key").is_some() {
+            warnings.push(ConfigWarning::new(
+                "tts.api_key",
+                "removed; keys are no longer read from configs, set tts.api_key_ref such as env:ELEVENLABS_API_KEY",
+            ));
+        }
+    }
+
+    let added: Vec<&str> = ADDED_IN_V2.iter().copied().filter(|section| !root.contains_key(*section)).collect();
+    if !added.is_empty() {
+        warnings.push(ConfigWarning::new(
+            CONFIG_VERSION_KEY,
+            format!("sections added since version 1 use their defaults: {}", added.join(", ")),
+        ));
+    }
+}
+
+/// Move a value between dotted paths, keeping the destination if both are set
+fn move_key(root: &mut Map<String, Value>, from: &str, to: &str, warnings: &mut Vec<ConfigWarning>) {
+    let Some(value) = remove_path(root, from) else {
+        return;
+    };
+    let (parents, leaf) = match to.rsplit_once('.') {
+        Some((parents, leaf)) => (parents.split('.').collect::<Vec<_>>(), leaf),
+        None => (Vec::new(), to),
+    };
+
+    let mut target = root;
+    for parent in parents {
+        let entry = target.entry(parent).or_insert_with(|| Value::Object(Map::new()));
+        if entry.is_null() {
+            *entry = Value::Object(Map::new());
+        }
+        let Some(object) = entry.as_object_mut() else {
+            warnings.push(ConfigWarning::new(from, format!("dropped; {} is not an object", parent)));
+            return;
+        };
+        target = object;
+    }
+
+    if target.get(leaf).is_some_and(|existing| !existing.is_null()) {
+        warnings.push(ConfigWarning::new(from, format!("dropped; {} is already set", to)));
+    } else {
+        target.insert(leaf.to_string(), value);
+        warnings.push(ConfigWarning::new(from, format!("renamed to {}", to)));
+    }
+}
+
+fn remove_path(root: &mut Map<String, Value>, path: &str) -> Option<Value> {
+    match path.split_once('.') {
+        Some((head, rest)) => remove_path(root.get_mut(head)?.as_object_mut()?, rest),
+        None => root.remove(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upgrades_unversioned_configs() {
+        let mut document = serde_json::json!({
+            "agent": { "name": "Bram", "role": "Guard", "backstory": [], "knowledge": [] },
+            "inference": { "system_prompt": "You guard the gate." },
+            "behavior": {
+                "greeting": { "trigger": "proximity", "parameters": { "lines": ["Halt!"] } }
+            },
+            "tts": { "provider": "ElevenLabs", "api_key": "sk-123" },
+        });
+        let report = migrate(&mut document).unwrap();
+        assert_eq!(report.from_version, 1);
+        assert!(report.migrated());
+
+        assert_eq!(document["config_version"], 2);
+        assert_eq!(document["prompts"]["agent"], "You guard the gate.");
+        assert_eq!(document["behavior"]["greeting"]["lines"][0], "Halt!");
+        assert_eq!(document["tts"]["default_provider"], "ElevenLabs");
+        assert!(document["tts"].get("api_key").is_none());
+        let keys: Vec<&str> = report.warnings.iter().map(|warning| warning.key.as_str()).collect();
+        assert_eq!(
+            keys,
+            ["inference.system_prompt", "tts.provider", "behavior.greeting.parameters", "tts.api_key", "config_version"]
+        );
+
+        // Current configs are left alone, and newer ones are rejected
+        let report = migrate(&mut document).unwrap();
+        assert!(!report.migrated() && report.warnings.is_empty());
+        document["config_version"] = serde_json::json!(3);
+        assert!(migrate(&mut document).is_err());
+    }
+}
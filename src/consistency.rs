@@ -0,0 +1,305 @@
+//! Keeping generated responses in character
+//!
+//! Models drift out of character, such as a pacifist healer offering to
+//! kill someone. Consistency rules list what an agent's role and backstory
+//! rule out, as patterns responses must not match:
+//!
+//! ```yaml
+//! capabilities:
+//!   consistency:
+//!     on_violation: regenerate
+//!     max_retries: 1
+//!     rules:
+//!       - name: pacifist
+//!         patterns: ['\b(kill|murder|stab|slay)\b', "\\bfight (you|them)\\b"]
+//!         instruction: You are a pacifist and never threaten or offer violence.
+//! ```
+//!
+//! Generated responses breaking a rule are regenerated with the rule's
+//! instruction added to the prompt (`regenerate`), or have the offending
+//! sentences removed (`filter`). Responses still breaking a rule after
+//! `max_retries` regenerations are filtered too, and replaced by
+//! `fallback_response` if no sentence remains. [`ConsistencyStats`] count
+//! checks and violations so the violation rate can be tracked per agent.
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::{OxydeError, Result};
+use crate::utils::sentences;
+
+/// Something an agent's character rules out
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConsistencyRule {
+    /// Name reported with violations
+    pub name: String,
+
+    /// Regular expressions responses must not match, ignoring case
+    pub patterns: Vec<String>,
+
+    /// Instruction added to the prompt when regenerating a violation
+    pub instruction: String,
+}
+
+/// What happens to a response breaking a rule
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConsistencyAction {
+    /// Generate the response again with the rule's instruction
+    #[default]
+    Regenerate,
+    /// Remove the sentences breaking the rule
+    Filter,
+}
+
+/// Personality consistency configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConsistencyConfig {
+    /// Rules responses are checked against; checking is off when empty
+    #[serde(default)]
+    pub rules: Vec<ConsistencyRule>,
+
+    /// What happens to a response breaking a rule
+    #[serde(default)]
+    pub on_violation: ConsistencyAction,
+
+    /// Regenerations before a violating response is filtered
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+
+    /// Response used when filtering leaves nothing
+    #[serde(default = "default_fallback_response")]
+    pub fallback_response: String,
+}
+
+fn default_max_retries() -> u32 {
+    1
+}
+
+fn default_fallback_response() -> String {
+    "I'd rather not say.".to_string()
+}
+
+impl Default for ConsistencyConfig {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            on_violation: ConsistencyAction::default(),
+            max_retries: default_max_retries(),
+            fallback_response: default_fallback_response(),
+        }
+    }
+}
+
+impl ConsistencyConfig {
+    /// Validate the consistency configuration
+    ///
+    /// # Returns
+    ///
+    /// Ok if the configuration is valid, Err with a descriptive message otherwise
+    pub fn validate(&self) -> Result<()> {
+        for rule in &self.rules {
+            if rule.name.trim().is_empty() {
+                return Err(OxydeError::ConfigurationError(
+                    "Consistency rule name cannot be empty".to_string(),
+                ));
+            }
+            if rule.patterns.is_empty() {
+                return Err(OxydeError::ConfigurationError(format!(
+                    "Consistency rule '{}' needs at least one pattern",
+                    rule.name
+                )));
+            }
+            for pattern in &rule.patterns {
+                compile(pattern).map_err(|e| {
+                    OxydeError::ConfigurationError(format!(
+                        "Consistency rule '{}' has an invalid pattern: {}",
+                        rule.name, e
+                    ))
+                })?;
+            }
+        }
+        if !self.rules.is_empty() && self.fallback_response.trim().is_empty() {
+            return Err(OxydeError::ConfigurationError(
+                "Consistency fallback_response cannot be empty when rules are listed".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A response breaking a consistency rule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConsistencyViolation {
+    /// Name of the rule broken
+    pub rule: String,
+
+    /// Text matching the rule
+    pub matched: String,
+
+    /// Instruction of the rule
+    pub instruction: String,
+}
+
+/// Counts of consistency checks, for tracking the violation rate
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConsistencyStats {
+    /// Generated responses checked
+    pub checked: u64,
+
+    /// Checked responses breaking a rule, before any correction
+    pub violations: u64,
+
+    /// Regenerations of violating responses
+    pub regenerated: u64,
+
+    /// Responses with sentences removed or replaced by the fallback
+    pub filtered: u64,
+}
+
+impl ConsistencyStats {
+    /// Share of checked responses that broke a rule
+    pub fn violation_rate(&self) -> f32 {
+        if self.checked == 0 {
+            0.0
+        } else {
+            self.violations as f32 / self.checked as f32
+        }
+    }
+}
+
+struct CompiledRule {
+    name: String,
+    patterns: Vec<Regex>,
+    instruction: String,
+}
+
+/// Checks responses against compiled consistency rules
+pub struct ConsistencyChecker {
+    config: ConsistencyConfig,
+    rules: Vec<CompiledRule>,
+}
+
+impl std::fmt::Debug for ConsistencyChecker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConsistencyChecker")
+            .field("rules", &self.rules.iter().map(|rule| rule.name.as_str()).collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl ConsistencyChecker {
+    /// Compile a consistency configuration, skipping invalid patterns
+    pub fn new(config: &ConsistencyConfig) -> Self {
+        let rules = config
+            .rules
+            .iter()
+            .map(|rule| CompiledRule {
+                name: rule.name.clone(),
+                patterns: rule
+                    .patterns
+                    .iter()
+                    .filter_map(|pattern| match compile(pattern) {
+                        Ok(regex) => Some(regex),
+                        Err(e) => {
+                            log::warn!("Skipping consistency pattern of rule {}: {}", rule.name, e);
+                            None
+                        }
+                    })
+                    .collect(),
+                instruction: rule.instruction.clone(),
+            })
+            .collect();
+        Self {
+            config: config.clone(),
+            rules,
+        }
+    }
+
+    /// The configuration the checker was compiled from
+    pub fn config(&self) -> &ConsistencyConfig {
+        &self.config
+    }
+
+    /// Whether no rules are configured
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Check a response
+    ///
+    /// # Returns
+    ///
+    /// The first rule the response breaks, if any
+    pub fn check(&self, response: &str) -> Option<ConsistencyViolation> {
+        self.rules.iter().find_map(|rule| {
+            let found = rule.patterns.iter().find_map(|pattern| pattern.find(response))?;
+            Some(ConsistencyViolation {
+                rule: rule.name.clone(),
+                matched: found.as_str().to_string(),
+                instruction: rule.instruction.clone(),
+            })
+        })
+    }
+
+    /// Remove the sentences of a response that break a rule
+    ///
+    /// # Returns
+    ///
+    /// The remaining sentences, or the fallback response if none remain
+    pub fn filter(&self, response: &str) -> String {
+        let kept: Vec<&str> = sentences(response)
+            .into_iter()
+            .filter(|sentence| self.check(sentence).is_none())
+            .collect();
+        if kept.is_empty() {
+            self.config.fallback_response.clone()
+        } else {
+            kept.join(" ")
+        }
+    }
+}
+
+fn compile(pattern: &str) -> std::result::Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern).case_insensitive(true).build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rules_catch_and_filter_out_of_character_lines() {
+        let config: ConsistencyConfig = serde_yaml::from_str(
+            "on_violation: filter\n\
+             rules:\n  - name: pacifist\n    patterns: ['\\b(kill|slay)\\b']\n    instruction: You never offer violence.\n",
+        )
+        .unwrap();
+        config.validate().unwrap();
+        let checker = ConsistencyChecker::new(&config);
+
+        assert!(checker.check("Rest here, I will tend your wounds.").is_none());
+        let violation = checker.check("I could KILL the bandit for you.").unwrap();
+        assert_eq!((violation.rule.as_str(), violation.matched.as_str()), ("pacifist", "KILL"));
+        assert!(checker.check("The skilled healer").is_none());
+
+        assert_eq!(
+            checker.filter("Let me see your arm. I will slay them all! Rest now."),
+            "Let me see your arm. Rest now."
+        );
+        assert_eq!(checker.filter("Kill them."), "I'd rather not say.");
+
+        let stats = ConsistencyStats { checked: 4, violations: 1, ..Default::default() };
+        assert_eq!(stats.violation_rate(), 0.25);
+
+        let invalid = ConsistencyConfig {
+            rules: vec![ConsistencyRule {
+                name: "broken".to_string(),
+                patterns: vec!["(".to_string()],
+                instruction: String::new(),
+            }],
+            ..Default::default()
+        };
+        assert!(invalid.validate().is_err());
+    }
+}
@@ -0,0 +1,634 @@
+//! Typed agent context and context schemas
+//!
+//! An [`AgentContext`] is a free-form JSON map, so a typo such as
+//! `player_distanse` is accepted and simply never read. A [`ContextSchema`]
+//! in the agent configuration declares the keys a game sends, with their
+//! types and ranges. Context updates are checked against it as a whole and
+//! rejected without applying any key when a value is invalid. Keys the SDK
+//! uses itself, listed in [`SDK_CONTEXT_KEYS`], need no declaration.
+//!
+//! [`ContextBuilder`] sets the common fields under the keys the SDK's
+//! behaviors read.
+//!
+//! Agents keep their context in a [`ContextStore`]: readers take a cheap
+//! `Arc` snapshot instead of cloning the map under a lock, and updates are
+//! applied as a [`ContextDiff`]. Keys whose value did not change are skipped,
+//! so a scene pushing the same position to hundreds of NPCs every frame
+//! allocates nothing, and the map is only copied when a request still holds
+//! the previous snapshot.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::agent::AgentContext;
+use crate::oxyde_game::schedule::{GAME_HOUR_KEY, SCHEDULED_ACTIVITY_KEY};
+use crate::{OxydeError, Result};
+
+/// Context key for the player's distance from the agent
+pub const PLAYER_DISTANCE_KEY: &str = "player_distance";
+
+/// Context key for the player's position as an `[x, y, z]` array
+pub const PLAYER_POSITION_KEY: &str = "player_position";
+
+/// Context key for whether the player is within interaction range
+pub const PLAYER_NEARBY_KEY: &str = "player_nearby";
+
+/// Context key for the player's name
+pub const PLAYER_NAME_KEY: &str = "player_name";
+
+/// Context key for the agent's relationship with the player (-1.0 hostile to 1.0 friendly)
+pub const PLAYER_RELATIONSHIP_KEY: &str = "player_relationship";
+
+/// Keys the SDK reads or sets itself, which every schema accepts
+///
+/// Declaring one of them in a schema still checks its type and range, but a
+/// strict schema never rejects them as unknown, so helpers such as
+/// [`ContextBuilder`] and per-player inputs keep working.
+pub const SDK_CONTEXT_KEYS: &[&str] = &[
+    PLAYER_DISTANCE_KEY,
+    PLAYER_POSITION_KEY,
+    "player_x",
+    "player_y",
+    "player_z",
+    PLAYER_NEARBY_KEY,
+    PLAYER_NAME_KEY,
+    PLAYER_RELATIONSHIP_KEY,
+    crate::oxyde_game::reengagement::PLAYER_ID_KEY,
+    crate::oxyde_game::reputation::PLAYER_REPUTATION_KEY,
+    crate::language::PLAYER_LOCALE_KEY,
+    GAME_HOUR_KEY,
+    crate::oxyde_game::schedule::GAME_DAY_KEY,
+    crate::oxyde_game::schedule::TIME_OF_DAY_KEY,
+    SCHEDULED_ACTIVITY_KEY,
+    crate::oxyde_game::behavior::IN_COMBAT_KEY,
+    crate::oxyde_game::behavior::HEALTH_PCT_KEY,
+    crate::oxyde_game::behavior::INVENTORY_KEY,
+    crate::verbosity::VERBOSITY_KEY,
+    crate::experiment::EXPERIMENT_KEY,
+    crate::attachment::ATTACHMENTS_KEY,
+    crate::inference_scheduler::REQUEST_PRIORITY_KEY,
+    crate::turn::TURN_NUMBER_KEY,
+    crate::turn::TURN_SEED_KEY,
+];
+
+/// Longest edit distance at which an unknown key is reported as a typo
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+
+/// JSON type of a context value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContextValueType {
+    /// Any number
+    Number,
+    /// A whole number
+    Integer,
+    /// A string
+    String,
+    /// `true` or `false`
+    Boolean,
+    /// A JSON array
+    Array,
+    /// A JSON object
+    Object,
+    /// Any value
+    Any,
+}
+
+impl ContextValueType {
+    fn matches(&self, value: &Value) -> bool {
+        match self {
+            Self::Number => value.is_number(),
+            Self::Integer => value.is_i64() || value.is_u64(),
+            Self::String => value.is_string(),
+            Self::Boolean => value.is_boolean(),
+            Self::Array => value.is_array(),
+            Self::Object => value.is_object(),
+            Self::Any => true,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Number => "number",
+            Self::Integer => "integer",
+            Self::String => "string",
+            Self::Boolean => "boolean",
+            Self::Array => "array",
+            Self::Object => "object",
+            Self::Any => "any",
+        }
+    }
+}
+
+/// How a schema violation is handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SchemaSeverity {
+    /// Accept the update silently
+    Ignore,
+    /// Accept the update and log a warning
+    Warn,
+    /// Reject the whole update
+    Error,
+}
+
+/// Declared type and range of a context key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextField {
+    /// Expected JSON type
+    #[serde(rename = "type")]
+    pub value_type: ContextValueType,
+
+    /// Minimum value for numbers, or minimum length for strings and arrays
+    #[serde(default)]
+    pub min: Option<f64>,
+
+    /// Maximum value for numbers, or maximum length for strings and arrays
+    #[serde(default)]
+    pub max: Option<f64>,
+}
+
+impl ContextField {
+    /// A field of the given type with no range
+    pub fn new(value_type: ContextValueType) -> Self {
+        Self {
+            value_type,
+            min: None,
+            max: None,
+        }
+    }
+
+    /// Restrict the field to a range
+    pub fn with_range(mut self, min: Option<f64>, max: Option<f64>) -> Self {
+        self.min = min;
+        self.max = max;
+        self
+    }
+
+    /// Describe why a value does not fit the field, if it does not
+    fn check(&self, value: &Value) -> Option<String> {
+        if !self.value_type.matches(value) {
+            return Some(format!("expected {}, got {}", self.value_type.as_str(), value));
+        }
+        let (measure, unit) = match value {
+            Value::Number(n) => (n.as_f64()?, "value"),
+            Value::String(s) => (s.chars().count() as f64, "length"),
+            Value::Array(a) => (a.len() as f64, "length"),
+            _ => return None,
+        };
+        match (self.min, self.max) {
+            (Some(min), _) if measure < min => Some(format!("{} {} is below the minimum {}", unit, measure, min)),
+            (_, Some(max)) if measure > max => Some(format!("{} {} is above the maximum {}", unit, measure, max)),
+            _ => None,
+        }
+    }
+}
+
+/// Declared context keys and how violations are handled
+///
+/// An empty schema accepts every update.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextSchema {
+    /// Declared keys
+    #[serde(default)]
+    pub fields: BTreeMap<String, ContextField>,
+
+    /// Handling of keys the schema does not declare
+    #[serde(default = "default_unknown_keys")]
+    pub unknown_keys: SchemaSeverity,
+
+    /// Handling of declared keys with the wrong type or out of range
+    #[serde(default = "default_invalid_values")]
+    pub invalid_values: SchemaSeverity,
+}
+
+fn default_unknown_keys() -> SchemaSeverity {
+    SchemaSeverity::Warn
+}
+
+fn default_invalid_values() -> SchemaSeverity {
+    SchemaSeverity::Error
+}
+
+impl Default for ContextSchema {
+    fn default() -> Self {
+        Self {
+            fields: BTreeMap::new(),
+            unknown_keys: default_unknown_keys(),
+            invalid_values: default_invalid_values(),
+        }
+    }
+}
+
+/// A context key that does not fit the schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextIssue {
+    /// Offending key
+    pub key: String,
+
+    /// How the issue is handled
+    pub severity: SchemaSeverity,
+
+    /// Description of the problem
+    pub message: String,
+}
+
+impl std::fmt::Display for ContextIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.key, self.message)
+    }
+}
+
+impl ContextSchema {
+    /// Whether any keys are declared
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Validate the schema itself
+    pub fn validate(&self) -> Result<()> {
+        for (key, field) in &self.fields {
+            if key.is_empty() {
+                return Err(OxydeError::ConfigurationError("Context schema key cannot be empty".to_string()));
+            }
+            if let (Some(min), Some(max)) = (field.min, field.max) {
+                if min > max {
+                    return Err(OxydeError::ConfigurationError(format!(
+                        "Context key '{}' has a minimum {} above its maximum {}",
+                        key, min, max
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Check a context update against the schema
+    ///
+    /// # Returns
+    ///
+    /// Issues that are not ignored, sorted by key
+    pub fn check(&self, context: &AgentContext) -> Vec<ContextIssue> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+
+        let mut issues: Vec<ContextIssue> = context
+            .iter()
+            .filter_map(|(key, value)| match self.fields.get(key) {
+                Some(field) => field.check(value).map(|message| ContextIssue {
+                    key: key.clone(),
+                    severity: self.invalid_values,
+                    message,
+                }),
+                None if SDK_CONTEXT_KEYS.contains(&key.as_str()) => None,
+                None => {
+                    let message = match self.suggest(key) {
+                        Some(known) => format!("unknown key (did you mean '{}'?)", known),
+                        None => "unknown key".to_string(),
+                    };
+                    Some(ContextIssue {
+                        key: key.clone(),
+                        severity: self.unknown_keys,
+                        message,
+                    })
+                }
+            })
+            .filter(|issue| issue.severity != SchemaSeverity::Ignore)
+            .collect();
+        issues.sort_by(|a, b| a.key.cmp(&b.key));
+        issues
+    }
+
+    /// The closest declared or SDK key to an unknown one, if it looks like a typo
+    fn suggest(&self, key: &str) -> Option<&str> {
+        self.fields
+            .keys()
+            .map(String::as_str)
+            .chain(SDK_CONTEXT_KEYS.iter().copied())
+            .map(|known| (known, edit_distance(key, known)))
+            .filter(|(_, distance)| *distance <= MAX_SUGGESTION_DISTANCE)
+            .min_by_key(|(_, distance)| *distance)
+            .map(|(known, _)| known)
+    }
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Builder for [`AgentContext`] maps with typed setters for common fields
+#[derive(Debug, Clone, Default)]
+pub struct ContextBuilder {
+    context: AgentContext,
+}
+
+impl ContextBuilder {
+    /// Start an empty context
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set a context value
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value cannot be serialized to JSON
+    pub fn with<T: Serialize>(mut self, key: &str, value: T) -> Self {
+        let value = serde_json::to_value(value).expect("context value must serialize to JSON");
+        self.context.insert(key.to_string(), value);
+        self
+    }
+
+    /// Set the player's distance from the agent
+    pub fn player_distance(self, distance: f64) -> Self {
+        self.with(PLAYER_DISTANCE_KEY, distance)
+    }
+
+    /// Set the player's position
+    ///
+    /// The position is stored as an `[x, y, z]` array and as the `player_x`,
+    /// `player_y`, and `player_z` components movement behaviors read.
+    pub fn player_position(self, x: f64, y: f64, z: f64) -> Self {
+        self.with(PLAYER_POSITION_KEY, [x, y, z])
+            .with("player_x", x)
+            .with("player_y", y)
+            .with("player_z", z)
+    }
+
+    /// Set whether the player is within interaction range
+    pub fn player_nearby(self, nearby: bool) -> Self {
+        self.with(PLAYER_NEARBY_KEY, nearby)
+    }
+
+    /// Set the player's name
+    pub fn player_name(self, name: &str) -> Self {
+        self.with(PLAYER_NAME_KEY, name)
+    }
+
+    /// Set the agent's relationship with the player (-1.0 - 1.0)
+    pub fn player_relationship(self, relationship: f32) -> Self {
+        self.with(PLAYER_RELATIONSHIP_KEY, relationship.clamp(-1.0, 1.0))
+    }
+
+    /// Set the time of day as a game hour (0.0 - 24.0)
+    pub fn game_hour(self, hour: f32) -> Self {
+        self.with(GAME_HOUR_KEY, hour)
+    }
+
+    /// Set the current scheduled activity
+    pub fn activity(self, activity: &str) -> Self {
+        self.with(SCHEDULED_ACTIVITY_KEY, activity)
+    }
+
+    /// Set whether the agent is fighting and its remaining health (0 - 100)
+    pub fn combat(self, in_combat: bool, health_pct: f32) -> Self {
+        self.with(crate::oxyde_game::behavior::IN_COMBAT_KEY, in_combat)
+            .with(crate::oxyde_game::behavior::HEALTH_PCT_KEY, health_pct.clamp(0.0, 100.0))
+    }
+
+    /// Set the interaction channel the response will be shown on, such as "bubble"
+    pub fn verbosity(self, channel: &str) -> Self {
+        self.with(crate::verbosity::VERBOSITY_KEY, channel)
+    }
+
+    /// Build the context
+    pub fn build(self) -> AgentContext {
+        self.context
+    }
+}
+
+impl From<ContextBuilder> for AgentContext {
+    fn from(builder: ContextBuilder) -> Self {
+        builder.build()
+    }
+}
+
+/// Keys to set and remove in an agent's context
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ContextDiff {
+    /// Values to insert or replace
+    #[serde(default)]
+    pub set: AgentContext,
+
+    /// Keys to remove
+    #[serde(default)]
+    pub remove: Vec<String>,
+}
+
+impl ContextDiff {
+    /// Start an empty diff
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set a context value
+    pub fn set(mut self, key: &str, value: Value) -> Self {
+        self.set.insert(key.to_string(), value);
+        self
+    }
+
+    /// Remove a context key
+    pub fn remove(mut self, key: &str) -> Self {
+        self.remove.push(key.to_string());
+        self
+    }
+
+    /// The diff that turns one context into another
+    pub fn between(old: &AgentContext, new: &AgentContext) -> Self {
+        Self {
+            set: new
+                .iter()
+                .filter(|(key, value)| old.get(*key) != Some(*value))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+            remove: old.keys().filter(|key| !new.contains_key(*key)).cloned().collect(),
+        }
+    }
+
+    /// The part of the diff that would change a context
+    pub fn changes_to(&self, context: &AgentContext) -> Self {
+        Self {
+            set: self
+                .set
+                .iter()
+                .filter(|(key, value)| context.get(*key) != Some(*value))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+            remove: self.remove.iter().filter(|key| context.contains_key(*key)).cloned().collect(),
+        }
+    }
+
+    /// Whether the diff changes nothing
+    pub fn is_empty(&self) -> bool {
+        self.set.is_empty() && self.remove.is_empty()
+    }
+}
+
+impl From<AgentContext> for ContextDiff {
+    fn from(set: AgentContext) -> Self {
+        Self {
+            set,
+            remove: Vec::new(),
+        }
+    }
+}
+
+/// Copy-on-write context storage shared between an agent's requests
+#[derive(Debug, Default)]
+pub struct ContextStore {
+    current: RwLock<Arc<AgentContext>>,
+    version: AtomicU64,
+}
+
+impl ContextStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the current context without copying it
+    pub fn snapshot(&self) -> Arc<AgentContext> {
+        self.current.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+
+    /// Number of updates that changed the context
+    pub fn version(&self) -> u64 {
+        self.version.load(Ordering::Acquire)
+    }
+
+    /// Apply a diff, skipping values that are already set
+    ///
+    /// # Returns
+    ///
+    /// Number of keys changed
+    pub fn apply(&self, mut diff: ContextDiff) -> usize {
+        let mut current = self.current.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+        diff.set.retain(|key, value| current.get(key) != Some(&*value));
+        diff.remove.retain(|key| current.contains_key(key));
+        let changed = diff.set.len() + diff.remove.len();
+        if changed == 0 {
+            return 0;
+        }
+
+        // Copies the map only if a snapshot of the previous version is still held
+        let context = Arc::make_mut(&mut current);
+        for key in &diff.remove {
+            context.remove(key);
+        }
+        context.extend(diff.set);
+        self.version.fetch_add(1, Ordering::AcqRel);
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_flags_typos_types_and_ranges() {
+        let schema = ContextSchema {
+            fields: BTreeMap::from([
+                (
+                    PLAYER_DISTANCE_KEY.to_string(),
+                    ContextField::new(ContextValueType::Number).with_range(Some(0.0), None),
+                ),
+                (
+                    GAME_HOUR_KEY.to_string(),
+                    ContextField::new(ContextValueType::Number).with_range(Some(0.0), Some(24.0)),
+                ),
+                (PLAYER_NAME_KEY.to_string(), ContextField::new(ContextValueType::String)),
+            ]),
+            ..Default::default()
+        };
+        schema.validate().unwrap();
+
+        let valid = ContextBuilder::new().player_distance(2.0).game_hour(13.5).player_name("Ayla").build();
+        assert!(schema.check(&valid).is_empty());
+
+        let context = ContextBuilder::new()
+            .with("player_distanse", 2.0)
+            .game_hour(25.0)
+            .with(PLAYER_NAME_KEY, 7)
+            .build();
+        let issues = schema.check(&context);
+        assert_eq!(issues.len(), 3);
+        assert_eq!(issues[0].key, GAME_HOUR_KEY);
+        assert_eq!(issues[0].severity, SchemaSeverity::Error);
+        assert!(issues[0].message.contains("above the maximum 24"));
+        assert_eq!(issues[1].key, "player_distanse");
+        assert_eq!(issues[1].severity, SchemaSeverity::Warn);
+        assert!(issues[1].message.contains("did you mean 'player_distance'"));
+        assert!(issues[2].message.contains("expected string"));
+    }
+
+    #[test]
+    fn test_strict_schema_accepts_sdk_keys() {
+        let schema = ContextSchema {
+            fields: BTreeMap::from([("weather".to_string(), ContextField::new(ContextValueType::String))]),
+            unknown_keys: SchemaSeverity::Error,
+            ..Default::default()
+        };
+
+        let context = ContextBuilder::new()
+            .player_position(1.0, 2.0, 3.0)
+            .player_relationship(0.5)
+            .with(crate::oxyde_game::reengagement::PLAYER_ID_KEY, "p1")
+            .with(crate::language::PLAYER_LOCALE_KEY, "fr")
+            .with("weather", "rain")
+            .build();
+        assert!(schema.check(&context).is_empty());
+
+        let issues = schema.check(&ContextBuilder::new().with("player_nearbyy", true).build());
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].severity, SchemaSeverity::Error);
+        assert!(issues[0].message.contains("did you mean 'player_nearby'"));
+    }
+
+    #[test]
+    fn test_empty_schema_accepts_anything() {
+        let context = ContextBuilder::new().player_position(1.0, 2.0, 3.0).build();
+        assert!(ContextSchema::default().check(&context).is_empty());
+        assert_eq!(context["player_position"], serde_json::json!([1.0, 2.0, 3.0]));
+        assert_eq!(context["player_y"], serde_json::json!(2.0));
+    }
+
+    #[test]
+    fn test_store_applies_diffs_copy_on_write() {
+        let store = ContextStore::new();
+        let context = ContextBuilder::new().player_name("Ayla").player_nearby(true).build();
+        assert_eq!(store.apply(context.clone().into()), 2);
+
+        // Unchanged values leave the snapshot and version untouched
+        let before = store.snapshot();
+        assert_eq!(store.apply(context.clone().into()), 0);
+        assert!(Arc::ptr_eq(&before, &store.snapshot()));
+        assert_eq!(store.version(), 1);
+
+        // Held snapshots keep seeing the version they were taken at
+        let diff = ContextDiff::new().set(PLAYER_NEARBY_KEY, serde_json::json!(false)).remove(PLAYER_NAME_KEY);
+        assert_eq!(store.apply(diff), 2);
+        assert_eq!(before[PLAYER_NEARBY_KEY], serde_json::json!(true));
+        let after = store.snapshot();
+        assert_eq!(after.len(), 1);
+        assert_eq!(ContextDiff::between(&before, &after), ContextDiff::new()
+            .set(PLAYER_NEARBY_KEY, serde_json::json!(false))
+            .remove(PLAYER_NAME_KEY));
+    }
+}
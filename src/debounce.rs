@@ -0,0 +1,196 @@
+//! Input debouncing for the Oxyde SDK
+//!
+//! Engines often fire the same trigger (e.g. a proximity greeting) on several
+//! frames in a row. The [`InputDebouncer`] recognizes repeated inputs within a
+//! configurable window so `process_input` can reuse the first response, or
+//! drop the repeat, instead of running the pipeline and inference again.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+
+use crate::config::{DebounceConfig, DuplicateInputAction};
+use crate::timer::Instant;
+
+/// Counters describing how inputs were handled by the debouncer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DebounceStats {
+    /// Inputs passed through to the pipeline
+    pub processed: u64,
+    /// Duplicates answered with the first input's response
+    pub cached: u64,
+    /// Duplicates dropped without a response
+    pub dropped: u64,
+}
+
+/// What to do with an incoming input
+pub(crate) enum Admission {
+    /// Run the pipeline; report the outcome with [`InputDebouncer::finish`]
+    Process(Option<Ticket>),
+    /// Duplicate of a finished input; reply with its response
+    Cached(String),
+    /// Duplicate of an input still being processed; wait for its response
+    Wait(watch::Receiver<Option<String>>),
+    /// Duplicate that should be dropped
+    Drop,
+}
+
+/// Handle for an input admitted for processing
+pub(crate) struct Ticket {
+    key: String,
+    sender: watch::Sender<Option<String>>,
+}
+
+struct RecentInput {
+    received_at: Instant,
+    response: watch::Receiver<Option<String>>,
+}
+
+/// Tracks recent inputs and classifies repeats
+#[derive(Default)]
+pub(crate) struct InputDebouncer {
+    config: DebounceConfig,
+    recent: Mutex<HashMap<String, RecentInput>>,
+    stats: Mutex<DebounceStats>,
+}
+
+impl std::fmt::Debug for InputDebouncer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InputDebouncer")
+            .field("config", &self.config)
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+/// Normalize input for duplicate detection: lowercase with collapsed whitespace
+pub(crate) fn normalize_input(input: &str) -> String {
+    input
+        .split_whitespace()
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl InputDebouncer {
+    /// Create a debouncer with the given configuration
+    pub(crate) fn new(config: DebounceConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    /// Classify an incoming input
+    pub(crate) fn admit(&self, input: &str) -> Admission {
+        if !self.config.enabled {
+            self.record(|stats| stats.processed += 1);
+            return Admission::Process(None);
+        }
+
+        let key = normalize_input(input);
+        let window = Duration::from_millis(self.config.window_ms);
+        let now = Instant::now();
+
+        let mut recent = self.recent.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        recent.retain(|_, entry| now.duration_since(entry.received_at) < window);
+
+        if let Some(entry) = recent.get(&key) {
+            let admission = match self.config.action {
+                DuplicateInputAction::Drop => Admission::Drop,
+                DuplicateInputAction::ReturnCached => match entry.response.borrow().clone() {
+                    Some(response) => Admission::Cached(response),
+                    None => Admission::Wait(entry.response.clone()),
+                },
+            };
+            match admission {
+                Admission::Drop => self.record(|stats| stats.dropped += 1),
+                _ => self.record(|stats| stats.cached += 1),
+            }
+            return admission;
+        }
+
+        let (sender, receiver) = watch::channel(None);
+        recent.insert(
+            key.clone(),
+            RecentInput {
+                received_at: now,
+                response: receiver,
+            },
+        );
+        self.record(|stats| stats.processed += 1);
+        Admission::Process(Some(Ticket { key, sender }))
+    }
+
+    /// Publish the response for an admitted input
+    ///
+    /// Failed requests are forgotten so the next identical input is processed
+    /// again instead of being suppressed.
+    pub(crate) fn finish(&self, ticket: Ticket, response: Option<&str>) {
+        match response {
+            Some(response) => {
+                let _ = ticket.sender.send(Some(response.to_string()));
+            }
+            None => {
+                let mut recent = self.recent.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                recent.remove(&ticket.key);
+            }
+        }
+    }
+
+    /// Get a snapshot of the debounce counters
+    pub(crate) fn stats(&self) -> DebounceStats {
+        *self.stats.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn record(&self, update: impl FnOnce(&mut DebounceStats)) {
+        update(&mut self.stats.lock().unwrap_or_else(|poisoned| poisoned.into_inner()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn debouncer(action: DuplicateInputAction) -> InputDebouncer {
+        InputDebouncer::new(DebounceConfig {
+            enabled: true,
+            window_ms: 60_000,
+            action,
+        })
+    }
+
+    #[test]
+    fn test_duplicates_reuse_response() {
+        let debouncer = debouncer(DuplicateInputAction::ReturnCached);
+
+        let Admission::Process(Some(ticket)) = debouncer.admit("Hello there") else {
+            panic!("first input should be processed");
+        };
+        assert!(matches!(debouncer.admit("hello  THERE"), Admission::Wait(_)));
+
+        debouncer.finish(ticket, Some("Welcome!"));
+        assert!(matches!(debouncer.admit(" Hello there "), Admission::Cached(ref r) if r == "Welcome!"));
+        assert!(matches!(debouncer.admit("Goodbye"), Admission::Process(Some(_))));
+
+        let stats = debouncer.stats();
+        assert_eq!((stats.processed, stats.cached, stats.dropped), (2, 2, 0));
+    }
+
+    #[test]
+    fn test_failed_inputs_are_forgotten() {
+        let debouncer = debouncer(DuplicateInputAction::Drop);
+
+        let Admission::Process(Some(ticket)) = debouncer.admit("hi") else {
+            panic!("first input should be processed");
+        };
+        assert!(matches!(debouncer.admit("hi"), Admission::Drop));
+
+        debouncer.finish(ticket, None);
+        assert!(matches!(debouncer.admit("hi"), Admission::Process(Some(_))));
+        assert_eq!(debouncer.stats().dropped, 1);
+    }
+}
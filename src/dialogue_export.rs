@@ -0,0 +1,386 @@
+//! Exporting logged conversations as dialogue trees
+//!
+//! Narrative designers review generated conversations in branching dialogue
+//! tools rather than in raw interaction logs. [`DialogueGraph::from_records`]
+//! groups logged exchanges by the intent of the player input and the mood the
+//! NPC answered in, giving a tree of three levels below a `Start` node:
+//!
+//! ```text
+//! Start -> Intent: greeting -> Mood: greeting (joy) -> Line: greeting (joy) #1 ("Welcome back!")
+//!                                                   -> Line: greeting (joy) #2 ("Hello again.")
+//! ```
+//!
+//! Node IDs are prefixed with their level, so an intent can never take the
+//! name of the start node, of Twine's special passages or of another level's
+//! node. Edges into NPC lines are labeled with the player input that led to
+//! them. Identical exchanges are merged and counted. The graph is written as
+//! Twee 3 for Twine, or as JSON for tools importing node/edge graphs.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write as _;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::interaction_log::InteractionRecord;
+use crate::structured::mood_of;
+use crate::{OxydeError, Result};
+
+/// ID of the root node
+pub const START_NODE: &str = "Start";
+
+/// Intent of exchanges logged without one
+pub const UNCLASSIFIED_INTENT: &str = "unclassified";
+
+/// Format a dialogue graph is written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DialogueFormat {
+    /// Twee 3 source, importable into Twine
+    Twee,
+    /// The graph as JSON nodes and edges
+    Json,
+}
+
+impl FromStr for DialogueFormat {
+    type Err = OxydeError;
+
+    fn from_str(name: &str) -> Result<Self> {
+        match name.to_lowercase().as_str() {
+            "twee" => Ok(Self::Twee),
+            "json" => Ok(Self::Json),
+            _ => Err(OxydeError::ConfigurationError(format!(
+                "Unknown dialogue format '{}'; expected twee or json",
+                name
+            ))),
+        }
+    }
+}
+
+/// Level of a node in the dialogue tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DialogueNodeKind {
+    /// Root of the tree
+    Start,
+    /// Intent of the player inputs below it
+    Intent,
+    /// Mood the NPC answered inputs of an intent in
+    Mood,
+    /// An NPC line
+    Line,
+}
+
+/// A node of a dialogue graph
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DialogueNode {
+    /// Unique, readable ID, used as the Twine passage name
+    pub id: String,
+
+    /// Level of the node
+    pub kind: DialogueNodeKind,
+
+    /// Intent or mood name, or the NPC line
+    pub text: String,
+
+    /// Agent speaking a line
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speaker: Option<String>,
+
+    /// Exchanges passing through the node
+    pub count: usize,
+}
+
+/// An edge of a dialogue graph
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DialogueEdge {
+    /// ID of the parent node
+    pub from: String,
+
+    /// ID of the child node
+    pub to: String,
+
+    /// Player input for edges into lines, otherwise the child's name
+    pub label: String,
+
+    /// Exchanges taking the edge
+    pub count: usize,
+}
+
+/// Logged conversations as a tree of intents, moods and NPC lines
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DialogueGraph {
+    /// Story title
+    pub title: String,
+
+    /// Nodes, parents before their children
+    pub nodes: Vec<DialogueNode>,
+
+    /// Edges, in the order the exchanges were logged
+    pub edges: Vec<DialogueEdge>,
+}
+
+impl DialogueGraph {
+    /// Group logged exchanges by intent and mood
+    ///
+    /// NPC lines are the responses players saw, after moderation and
+    /// post-processing, and moods are taken from the emotions after each
+    /// response.
+    pub fn from_records(records: &[InteractionRecord]) -> Self {
+        let mut graph = Self {
+            title: dialogue_title(records),
+            nodes: vec![DialogueNode {
+                id: START_NODE.to_string(),
+                kind: DialogueNodeKind::Start,
+                text: START_NODE.to_string(),
+                speaker: None,
+                count: 0,
+            }],
+            edges: Vec::new(),
+        };
+        let mut node_index: HashMap<String, usize> = HashMap::from([(START_NODE.to_string(), 0)]);
+        let mut line_ids: HashMap<(String, String, String), String> = HashMap::new();
+        let mut lines_per_mood: HashMap<String, usize> = HashMap::new();
+
+        for record in records {
+            let intent = record.intent.as_deref().unwrap_or(UNCLASSIFIED_INTENT);
+            let mood = mood_of(&record.emotions_after);
+            let intent_name = passage_name(intent);
+            let mood_name = format!("{} ({})", intent_name, passage_name(mood));
+            let intent_id = format!("Intent: {}", intent_name);
+            let mood_id = format!("Mood: {}", mood_name);
+            let line_key = (mood_id.clone(), record.agent_name.clone(), record.final_response.clone());
+            let line_id = line_ids
+                .entry(line_key)
+                .or_insert_with(|| {
+                    let number = lines_per_mood.entry(mood_id.clone()).or_default();
+                    *number += 1;
+                    format!("Line: {} #{}", mood_name, number)
+                })
+                .clone();
+
+            graph.nodes[0].count += 1;
+            graph.visit(&mut node_index, &intent_id, DialogueNodeKind::Intent, intent, None);
+            graph.visit(&mut node_index, &mood_id, DialogueNodeKind::Mood, mood, None);
+            graph.visit(
+                &mut node_index,
+                &line_id,
+                DialogueNodeKind::Line,
+                &record.final_response,
+                Some(&record.agent_name),
+            );
+            graph.follow(START_NODE, &intent_id, intent);
+            graph.follow(&intent_id, &mood_id, mood);
+            graph.follow(&mood_id, &line_id, &record.input);
+        }
+        graph
+    }
+
+    /// Write the graph in a format
+    pub fn render(&self, format: DialogueFormat) -> Result<String> {
+        match format {
+            DialogueFormat::Twee => Ok(self.to_twee()),
+            DialogueFormat::Json => Ok(serde_json::to_string_pretty(self)?),
+        }
+    }
+
+    /// Twee 3 source with one passage per node, linking to its children
+    ///
+    /// The story's IFID is derived from its title, so exporting the same
+    /// agents again gives Twine the same story to update.
+    pub fn to_twee(&self) -> String {
+        let mut twee = String::new();
+        let _ = writeln!(twee, ":: StoryTitle\n{}\n", self.title);
+        let ifid = uuid::Uuid::new_v5(&uuid::Uuid::NAMESPACE_URL, format!("oxyde:dialogue:{}", self.title).as_bytes());
+        let story_data = serde_json::json!({
+            "ifid": ifid.to_string().to_uppercase(),
+            "start": START_NODE,
+        });
+        let _ = writeln!(twee, ":: StoryData\n{}\n", serde_json::to_string_pretty(&story_data).unwrap_or_default());
+
+        for node in &self.nodes {
+            let _ = writeln!(twee, ":: {}", node.id);
+            match (&node.kind, &node.speaker) {
+                (DialogueNodeKind::Line, Some(speaker)) => {
+                    let _ = writeln!(twee, "{}: {}", speaker, escape_body(&node.text));
+                }
+                (DialogueNodeKind::Line, None) => {
+                    let _ = writeln!(twee, "{}", escape_body(&node.text));
+                }
+                _ => {}
+            }
+            let _ = writeln!(twee, "''Seen {} times''", node.count);
+            for edge in self.edges.iter().filter(|edge| edge.from == node.id) {
+                let _ = writeln!(twee, "[[{} ({})->{}]]", link_text(&edge.label), edge.count, edge.to);
+            }
+            twee.push('\n');
+        }
+        twee
+    }
+
+    fn visit(
+        &mut self,
+        node_index: &mut HashMap<String, usize>,
+        id: &str,
+        kind: DialogueNodeKind,
+        text: &str,
+        speaker: Option<&str>,
+    ) {
+        let index = *node_index.entry(id.to_string()).or_insert_with(|| {
+            self.nodes.push(DialogueNode {
+                id: id.to_string(),
+                kind,
+                text: text.to_string(),
+                speaker: speaker.map(str::to_string),
+                count: 0,
+            });
+            self.nodes.len() - 1
+        });
+        self.nodes[index].count += 1;
+    }
+
+    fn follow(&mut self, from: &str, to: &str, label: &str) {
+        match self
+            .edges
+            .iter_mut()
+            .find(|edge| edge.from == from && edge.to == to && edge.label == label)
+        {
+            Some(edge) => edge.count += 1,
+            None => self.edges.push(DialogueEdge {
+                from: from.to_string(),
+                to: to.to_string(),
+                label: label.to_string(),
+                count: 1,
+            }),
+        }
+    }
+}
+
+/// Title naming the agents of the records
+fn dialogue_title(records: &[InteractionRecord]) -> String {
+    let agents: BTreeSet<&str> = records.iter().map(|record| record.agent_name.as_str()).collect();
+    if agents.is_empty() {
+        "Dialogue".to_string()
+    } else {
+        format!("{} dialogue", agents.into_iter().collect::<Vec<_>>().join(", "))
+    }
+}
+
+/// Name usable as a Twee passage name and link target
+fn passage_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| if matches!(c, '[' | ']' | '{' | '}' | '|') || c.is_control() { '_' } else { c })
+        .collect();
+    let name = name.replace("->", "_").replace("<-", "_");
+    match name.trim() {
+        "" => UNCLASSIFIED_INTENT.to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+/// Text usable inside a `[[text->target]]` link
+fn link_text(text: &str) -> String {
+    let text: String = text
+        .chars()
+        .map(|c| match c {
+            '[' => '(',
+            ']' => ')',
+            '|' => '/',
+            c if c.is_control() => ' ',
+            c => c,
+        })
+        .collect();
+    text.replace("->", "→").replace("<-", "←")
+}
+
+/// Passage text that cannot start a passage header
+fn escape_body(text: &str) -> String {
+    text.lines()
+        .map(|line| if line.starts_with("::") { format!("\\{}", line) } else { line.to_string() })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oxyde_game::emotion::EmotionalState;
+
+    fn record(intent: Option<&str>, input: &str, response: &str, joy: f32) -> InteractionRecord {
+        let mut emotions = EmotionalState::new();
+        emotions.joy = joy;
+        InteractionRecord {
+            timestamp: 0,
+            agent_id: "agent".to_string(),
+            agent_name: "Marla".to_string(),
+            input: input.to_string(),
+            intent: intent.map(str::to_string),
+            system_prompt: String::new(),
+            memories: Vec::new(),
+            provider: "mock".to_string(),
+            model: None,
+            experiment: None,
+            variant: None,
+            tokens: 0,
+            latency_ms: 0,
+            response: response.to_string(),
+            final_response: response.to_string(),
+            emotions_before: EmotionalState::new(),
+            emotions_after: emotions,
+        }
+    }
+
+    #[test]
+    fn test_exchanges_group_by_intent_and_mood() {
+        let graph = DialogueGraph::from_records(&[
+            record(Some("greeting"), "Hi", "Welcome back!", 0.8),
+            record(Some("greeting"), "Hello", "Welcome back!", 0.8),
+            record(Some("greeting"), "Hey", "What now?", 0.0),
+            record(None, "Buy [sword]->now", "Coin first.", 0.0),
+        ]);
+
+        let ids: Vec<_> = graph.nodes.iter().map(|node| node.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            [
+                "Start",
+                "Intent: greeting",
+                "Mood: greeting (joy)",
+                "Line: greeting (joy) #1",
+                "Mood: greeting (neutral)",
+                "Line: greeting (neutral) #1",
+                "Intent: unclassified",
+                "Mood: unclassified (neutral)",
+                "Line: unclassified (neutral) #1",
+            ]
+        );
+        assert_eq!(graph.nodes[3].count, 2);
+        let into_line: Vec<_> = graph.edges.iter().filter(|edge| edge.to == "Line: greeting (joy) #1").collect();
+        assert_eq!(into_line.len(), 2);
+
+        let twee = graph.to_twee();
+        assert!(twee.starts_with(":: StoryTitle\nMarla dialogue\n"));
+        assert!(twee.contains("[[greeting (3)->Intent: greeting]]"));
+        assert!(twee.contains("[[Buy (sword)→now (1)->Line: unclassified (neutral) #1]]"));
+        assert!(twee.contains(":: Line: greeting (joy) #1\nMarla: Welcome back!\n"));
+        assert_eq!(twee, graph.to_twee());
+
+        let json = graph.render(DialogueFormat::Json).unwrap();
+        let parsed: DialogueGraph = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, graph);
+    }
+
+    #[test]
+    fn test_intents_cannot_take_the_start_node() {
+        let graph = DialogueGraph::from_records(&[
+            record(Some("Start"), "Begin", "Then begin.", 0.0),
+            record(Some("StoryData"), "Data?", "None here.", 0.0),
+        ]);
+
+        let starts = graph.nodes.iter().filter(|node| node.id == START_NODE).count();
+        assert_eq!(starts, 1);
+        assert_eq!(graph.nodes[0].count, 2);
+        assert!(graph.edges.iter().all(|edge| edge.to != START_NODE));
+        assert!(!graph.to_twee().contains(":: StoryData\n''Seen"));
+    }
+}
@@ -0,0 +1,320 @@
+//! Ordered delivery of an agent's lines to engine UI
+//!
+//! Response callbacks fire once, on whichever thread produced the response,
+//! so a dialogue bubble that is not listening at that moment misses the line.
+//! Every agent instead also buffers the lines it says in a [`DialogueQueue`],
+//! which UI code drains at its own pace with `peek` and `pop`, in the order
+//! the lines were said:
+//!
+//! ```yaml
+//! dialogue_queue:
+//!   capacity: 8
+//!   ttl_ms: 10000
+//!   overflow: drop_oldest
+//! ```
+//!
+//! Lines older than `ttl_ms` are discarded unread, since a bubble showing a
+//! reply to something the player said long ago is confusing. When the queue
+//! is full, `overflow` decides whether the oldest waiting line or the new one
+//! is dropped.
+
+use std::collections::VecDeque;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{OxydeError, Result};
+
+/// Which line is dropped when a full queue receives another
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DialogueOverflow {
+    /// Drop the oldest waiting line to make room
+    #[default]
+    DropOldest,
+    /// Drop the new line
+    DropNewest,
+}
+
+/// Dialogue queue configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DialogueQueueConfig {
+    /// Whether lines are buffered
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+
+    /// Lines buffered before `overflow` applies
+    #[serde(default = "default_capacity")]
+    pub capacity: usize,
+
+    /// How long a line waits to be read before it is discarded, in
+    /// milliseconds; 0 keeps lines until read
+    #[serde(default = "default_ttl_ms")]
+    pub ttl_ms: u64,
+
+    /// Which line is dropped when the queue is full
+    #[serde(default)]
+    pub overflow: DialogueOverflow,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_capacity() -> usize {
+    16
+}
+
+fn default_ttl_ms() -> u64 {
+    15_000
+}
+
+impl Default for DialogueQueueConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            capacity: default_capacity(),
+            ttl_ms: default_ttl_ms(),
+            overflow: DialogueOverflow::default(),
+        }
+    }
+}
+
+impl DialogueQueueConfig {
+    /// Validate the dialogue queue configuration
+    ///
+    /// # Returns
+    ///
+    /// Ok if the configuration is valid, Err with a descriptive message otherwise
+    pub fn validate(&self) -> Result<()> {
+        if self.enabled && self.capacity == 0 {
+            return Err(OxydeError::ConfigurationError(
+                "Dialogue queue capacity must be greater than 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// What a queued line is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DialogueLineKind {
+    /// A response to the player
+    Response,
+    /// A filler line said while a slow response is generated
+    Filler,
+}
+
+/// A line waiting to be shown
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DialogueLine {
+    /// Position among the lines the agent said, counting from 1
+    pub sequence: u64,
+
+    /// What the line is
+    pub kind: DialogueLineKind,
+
+    /// Text of the line
+    pub text: String,
+
+    /// Unix time in milliseconds when the line was said
+    pub timestamp_ms: u64,
+
+    /// Unix time in milliseconds after which the line is discarded, if it
+    /// expires
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at_ms: Option<u64>,
+}
+
+/// Counts of lines that were not delivered
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DialogueQueueStats {
+    /// Lines waiting to be read
+    pub waiting: usize,
+    /// Lines dropped because the queue was full
+    pub dropped: u64,
+    /// Lines discarded unread after their TTL
+    pub expired: u64,
+}
+
+struct QueuedLine {
+    line: DialogueLine,
+    deadline: Option<Instant>,
+}
+
+#[derive(Default)]
+struct QueueState {
+    lines: VecDeque<QueuedLine>,
+    sequence: u64,
+    dropped: u64,
+    expired: u64,
+}
+
+/// Buffer of an agent's lines, read in order by engine UI
+pub struct DialogueQueue {
+    config: DialogueQueueConfig,
+    state: Mutex<QueueState>,
+}
+
+impl std::fmt::Debug for DialogueQueue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DialogueQueue")
+            .field("config", &self.config)
+            .field("waiting", &self.lock().lines.len())
+            .finish()
+    }
+}
+
+impl DialogueQueue {
+    /// Create an empty queue
+    pub fn new(config: DialogueQueueConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(QueueState::default()),
+        }
+    }
+
+    /// The queue configuration
+    pub fn config(&self) -> &DialogueQueueConfig {
+        &self.config
+    }
+
+    /// Queue a line
+    ///
+    /// # Returns
+    ///
+    /// Whether the line was queued; it is not when the queue is disabled or
+    /// full with `drop_newest`
+    pub fn push(&self, kind: DialogueLineKind, text: &str) -> bool {
+        if !self.config.enabled {
+            return false;
+        }
+        let mut state = self.lock();
+        self.expire(&mut state);
+        if state.lines.len() >= self.config.capacity {
+            state.dropped += 1;
+            match self.config.overflow {
+                DialogueOverflow::DropNewest => return false,
+                DialogueOverflow::DropOldest => {
+                    state.lines.pop_front();
+                }
+            }
+        }
+
+        state.sequence += 1;
+        let ttl = (self.config.ttl_ms > 0).then(|| Duration::from_millis(self.config.ttl_ms));
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis() as u64)
+            .unwrap_or(0);
+        let line = DialogueLine {
+            sequence: state.sequence,
+            kind,
+            text: text.to_string(),
+            timestamp_ms,
+            expires_at_ms: ttl.map(|_| timestamp_ms + self.config.ttl_ms),
+        };
+        state.lines.push_back(QueuedLine {
+            line,
+            deadline: ttl.map(|ttl| Instant::now() + ttl),
+        });
+        true
+    }
+
+    /// The oldest unexpired line, left in the queue
+    pub fn peek(&self) -> Option<DialogueLine> {
+        let mut state = self.lock();
+        self.expire(&mut state);
+        state.lines.front().map(|queued| queued.line.clone())
+    }
+
+    /// Remove and return the oldest unexpired line
+    pub fn pop(&self) -> Option<DialogueLine> {
+        let mut state = self.lock();
+        self.expire(&mut state);
+        state.lines.pop_front().map(|queued| queued.line)
+    }
+
+    /// Remove and return every unexpired line, oldest first
+    pub fn drain(&self) -> Vec<DialogueLine> {
+        let mut state = self.lock();
+        self.expire(&mut state);
+        state.lines.drain(..).map(|queued| queued.line).collect()
+    }
+
+    /// Discard every waiting line, such as when the dialogue UI closes
+    pub fn clear(&self) {
+        self.lock().lines.clear();
+    }
+
+    /// Lines waiting and lines lost
+    pub fn stats(&self) -> DialogueQueueStats {
+        let mut state = self.lock();
+        self.expire(&mut state);
+        DialogueQueueStats {
+            waiting: state.lines.len(),
+            dropped: state.dropped,
+            expired: state.expired,
+        }
+    }
+
+    /// Discard lines past their TTL
+    fn expire(&self, state: &mut QueueState) {
+        let now = Instant::now();
+        let before = state.lines.len();
+        state.lines.retain(|queued| queued.deadline.is_none_or(|deadline| deadline > now));
+        state.expired += (before - state.lines.len()) as u64;
+    }
+
+    fn lock(&self) -> MutexGuard<'_, QueueState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lines_are_delivered_in_order_within_limits() {
+        let queue = DialogueQueue::new(DialogueQueueConfig {
+            capacity: 2,
+            ttl_ms: 0,
+            ..Default::default()
+        });
+        assert!(queue.push(DialogueLineKind::Filler, "Hmm..."));
+        assert!(queue.push(DialogueLineKind::Response, "Welcome!"));
+        assert!(queue.push(DialogueLineKind::Response, "What'll it be?"));
+
+        assert_eq!(queue.peek().unwrap().text, "Welcome!");
+        let line = queue.pop().unwrap();
+        assert_eq!((line.sequence, line.kind, line.expires_at_ms), (2, DialogueLineKind::Response, None));
+        assert_eq!(queue.pop().unwrap().text, "What'll it be?");
+        assert!(queue.pop().is_none());
+        assert_eq!(queue.stats().dropped, 1);
+
+        let newest_dropped = DialogueQueue::new(DialogueQueueConfig {
+            capacity: 1,
+            overflow: DialogueOverflow::DropNewest,
+            ..Default::default()
+        });
+        assert!(newest_dropped.push(DialogueLineKind::Response, "First"));
+        assert!(!newest_dropped.push(DialogueLineKind::Response, "Second"));
+        assert_eq!(newest_dropped.drain().len(), 1);
+    }
+
+    #[test]
+    fn test_lines_expire_after_ttl() {
+        let queue = DialogueQueue::new(DialogueQueueConfig {
+            ttl_ms: 20,
+            ..Default::default()
+        });
+        queue.push(DialogueLineKind::Response, "Too late");
+        assert!(queue.peek().unwrap().expires_at_ms.is_some());
+        std::thread::sleep(Duration::from_millis(40));
+        assert!(queue.pop().is_none());
+        assert_eq!(queue.stats(), DialogueQueueStats { waiting: 0, dropped: 0, expired: 1 });
+    }
+}
@@ -0,0 +1,313 @@
+//! Process-wide embedding service
+//!
+//! Embedding models are large; loading one per agent multiplies their weight
+//! by the number of NPCs. The [`EmbeddingService`] owns each model once and
+//! runs every embedding request through a single work queue served by a
+//! dedicated thread, so agents share the model without contending for it.
+//!
+//! The service itself is always available, so models of your own can be
+//! registered with it; the built-in MiniLM model needs the `vector-memory`
+//! feature, which is also what makes memory systems embed their memories.
+//!
+//! Memory systems use the global service by default. Load the model during
+//! a loading screen instead of on the first retrieval:
+//!
+//! ```rust,no_run
+//! # async fn example() -> oxyde::Result<()> {
+//! use oxyde::config::EmbeddingModelType;
+//! use oxyde::embedding_service::EmbeddingService;
+//!
+//! let embeddings = EmbeddingService::global();
+//! embeddings.preload(EmbeddingModelType::MiniBert).await?;
+//! println!("embedding models use {} bytes", embeddings.memory_footprint());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::sync::{mpsc, Arc, Mutex};
+
+use serde::Serialize;
+use tokio::sync::oneshot;
+
+use crate::config::EmbeddingModelType;
+use crate::memory::EmbeddingModel;
+#[cfg(feature = "vector-memory")]
+use crate::memory::MiniLMEmbedding;
+use crate::{OxydeError, Result};
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_EMBEDDINGS: Arc<EmbeddingService> = Arc::new(EmbeddingService::new());
+}
+
+/// A model ready to embed text
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LoadedEmbeddingModel {
+    /// Which model
+    pub model: EmbeddingModelType,
+    /// Dimension of its embeddings
+    pub dimension: usize,
+    /// Approximate memory held by its weights, in bytes
+    pub memory_bytes: usize,
+}
+
+/// Counters for the embedding service
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct EmbeddingServiceStats {
+    /// Models currently loaded
+    pub models: Vec<LoadedEmbeddingModel>,
+    /// Requests waiting in the work queue, including the one running
+    pub queued: usize,
+    /// Texts embedded so far
+    pub embedded: u64,
+}
+
+impl EmbeddingServiceStats {
+    /// Approximate memory held by all loaded models, in bytes
+    pub fn memory_bytes(&self) -> usize {
+        self.models.iter().map(|model| model.memory_bytes).sum()
+    }
+}
+
+type BoxedModel = Box<dyn EmbeddingModel + Send>;
+
+/// Work for the embedding thread
+enum Job {
+    Register {
+        model: EmbeddingModelType,
+        embedder: BoxedModel,
+    },
+    Load {
+        model: EmbeddingModelType,
+        done: oneshot::Sender<Result<()>>,
+    },
+    Embed {
+        model: EmbeddingModelType,
+        text: String,
+        done: oneshot::Sender<Result<Vec<f32>>>,
+    },
+}
+
+/// Shares embedding models between every memory system in the process
+pub struct EmbeddingService {
+    jobs: Mutex<mpsc::Sender<Job>>,
+    stats: Arc<Mutex<EmbeddingServiceStats>>,
+}
+
+impl std::fmt::Debug for EmbeddingService {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EmbeddingService").field("stats", &self.stats()).finish()
+    }
+}
+
+impl EmbeddingService {
+    /// Create a service with its own work queue and no models loaded
+    ///
+    /// Most games use [`EmbeddingService::global`]; a separate service keeps
+    /// its own copy of every model it loads.
+    pub fn new() -> Self {
+        let (jobs, receiver) = mpsc::channel();
+        let stats = Arc::new(Mutex::new(EmbeddingServiceStats::default()));
+        let worker_stats = stats.clone();
+        std::thread::Builder::new()
+            .name("oxyde-embeddings".to_string())
+            .spawn(move || run_worker(receiver, worker_stats))
+            .expect("failed to spawn the embedding thread");
+        Self {
+            jobs: Mutex::new(jobs),
+            stats,
+        }
+    }
+
+    /// The service shared by every memory system in the process
+    pub fn global() -> Arc<Self> {
+        GLOBAL_EMBEDDINGS.clone()
+    }
+
+    /// Use a model of your own for a model type
+    ///
+    /// This is how [`EmbeddingModelType::Custom`] models are provided; a
+    /// registered model replaces any loaded model of the same type.
+    pub fn register(&self, model: EmbeddingModelType, embedder: Box<dyn EmbeddingModel + Send>) {
+        self.submit(Job::Register { model, embedder });
+    }
+
+    /// Load a model ahead of the first embedding request
+    ///
+    /// Loading an already loaded model does nothing.
+    pub async fn preload(&self, model: EmbeddingModelType) -> Result<()> {
+        let (done, receiver) = oneshot::channel();
+        self.submit(Job::Load { model, done });
+        receiver.await.map_err(|_| worker_gone())?
+    }
+
+    /// Embed a text, loading the model first if needed
+    ///
+    /// Requests from all agents are served one at a time in arrival order.
+    pub async fn embed(&self, model: EmbeddingModelType, text: &str) -> Result<Vec<f32>> {
+        let (done, receiver) = oneshot::channel();
+        self.submit(Job::Embed {
+            model,
+            text: text.to_string(),
+            done,
+        });
+        receiver.await.map_err(|_| worker_gone())?
+    }
+
+    /// Get the loaded models and queue counters
+    pub fn stats(&self) -> EmbeddingServiceStats {
+        self.lock_stats().clone()
+    }
+
+    /// Approximate memory held by all loaded models, in bytes
+    pub fn memory_footprint(&self) -> usize {
+        self.lock_stats().memory_bytes()
+    }
+
+    fn submit(&self, job: Job) {
+        let counted = !matches!(job, Job::Register { .. });
+        if counted {
+            self.lock_stats().queued += 1;
+        }
+        let sent = self.jobs.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).send(job);
+        // A dead worker drops the job, and with it the caller's reply channel
+        if sent.is_err() && counted {
+            self.lock_stats().queued -= 1;
+        }
+    }
+
+    fn lock_stats(&self) -> std::sync::MutexGuard<'_, EmbeddingServiceStats> {
+        self.stats.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Default for EmbeddingService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn worker_gone() -> OxydeError {
+    OxydeError::MemoryError("Embedding service stopped".to_string())
+}
+
+/// Serve jobs until the service is dropped
+fn run_worker(receiver: mpsc::Receiver<Job>, stats: Arc<Mutex<EmbeddingServiceStats>>) {
+    let mut models: HashMap<EmbeddingModelType, BoxedModel> = HashMap::new();
+    let lock = || stats.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    for job in receiver {
+        match job {
+            Job::Register { model, embedder } => {
+                models.insert(model.clone(), embedder);
+                record_models(&mut lock(), &models);
+            }
+            Job::Load { model, done } => {
+                let result = ensure_loaded(&mut models, &model).map(|_| ());
+                let mut stats = lock();
+                record_models(&mut stats, &models);
+                stats.queued -= 1;
+                drop(stats);
+                let _ = done.send(result);
+            }
+            Job::Embed { model, text, done } => {
+                let result = ensure_loaded(&mut models, &model).and_then(|embedder| embedder.embed(&text));
+                let mut stats = lock();
+                record_models(&mut stats, &models);
+                stats.queued -= 1;
+                if result.is_ok() {
+                    stats.embedded += 1;
+                }
+                drop(stats);
+                let _ = done.send(result);
+            }
+        }
+    }
+}
+
+/// Get a model, loading it on first use
+fn ensure_loaded<'a>(
+    models: &'a mut HashMap<EmbeddingModelType, BoxedModel>,
+    model: &EmbeddingModelType,
+) -> Result<&'a BoxedModel> {
+    if !models.contains_key(model) {
+        let loaded = load_model(model)?;
+        log::info!("Loaded {:?} embedding model", model);
+        models.insert(model.clone(), loaded);
+    }
+    Ok(&models[model])
+}
+
+/// Load one of the built-in models
+fn load_model(model: &EmbeddingModelType) -> Result<BoxedModel> {
+    match model {
+        #[cfg(feature = "vector-memory")]
+        EmbeddingModelType::MiniBert => Ok(Box::new(MiniLMEmbedding::new()?)),
+        #[cfg(not(feature = "vector-memory"))]
+        EmbeddingModelType::MiniBert => Err(OxydeError::MemoryError(
+            "MiniBert embeddings need the `vector-memory` feature".to_string(),
+        )),
+        EmbeddingModelType::DistilBert => {
+            Err(OxydeError::MemoryError("DistilBert model not yet implemented".to_string()))
+        }
+        EmbeddingModelType::Custom => Err(OxydeError::MemoryError(
+            "Custom embedding models must be registered with EmbeddingService::register".to_string(),
+        )),
+    }
+}
+
+fn record_models(stats: &mut EmbeddingServiceStats, models: &HashMap<EmbeddingModelType, BoxedModel>) {
+    stats.models = models
+        .iter()
+        .map(|(model, embedder)| LoadedEmbeddingModel {
+            model: model.clone(),
+            dimension: embedder.dimension(),
+            memory_bytes: embedder.memory_bytes(),
+        })
+        .collect();
+    stats.models.sort_by_key(|loaded| format!("{:?}", loaded.model));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Embeds text as its length, standing in for a real model
+    struct LengthModel;
+
+    impl EmbeddingModel for LengthModel {
+        fn embed(&self, text: &str) -> Result<Vec<f32>> {
+            Ok(vec![text.len() as f32, 1.0])
+        }
+
+        fn dimension(&self) -> usize {
+            2
+        }
+
+        fn memory_bytes(&self) -> usize {
+            1024
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registered_model_serves_queued_requests() {
+        let service = EmbeddingService::new();
+        assert!(service.embed(EmbeddingModelType::Custom, "hello").await.is_err());
+
+        service.register(EmbeddingModelType::Custom, Box::new(LengthModel));
+        service.preload(EmbeddingModelType::Custom).await.unwrap();
+        assert_eq!(service.memory_footprint(), 1024);
+
+        let texts = ["a", "bb", "ccc"];
+        let results = futures::future::join_all(
+            texts.iter().map(|text| service.embed(EmbeddingModelType::Custom, text)),
+        )
+        .await;
+        let lengths: Vec<f32> = results.into_iter().map(|result| result.unwrap()[0]).collect();
+        assert_eq!(lengths, vec![1.0, 2.0, 3.0]);
+
+        let stats = service.stats();
+        assert_eq!((stats.queued, stats.embedded), (0, 3));
+        assert_eq!(stats.models[0].dimension, 2);
+    }
+}
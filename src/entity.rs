@@ -0,0 +1,256 @@
+//! Lightweight entity extraction and linking for memories
+//!
+//! Memories record the named people, places and items they mention. A
+//! [`MemoryGraph`] links memories that share an entity, so an agent can pull
+//! the full history about "the stolen amulet" instead of only the memories
+//! that happen to match a query's keywords.
+//!
+//! Extraction is heuristic and needs no model: capitalized words outside of
+//! sentence openers are names (places when they follow a locative
+//! preposition), and phrases introduced by "the" are items.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::memory::Memory;
+
+/// Kind of an extracted entity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntityKind {
+    /// A named person or creature
+    Person,
+    /// A named location
+    Place,
+    /// An object or thing referred to with "the"
+    Item,
+}
+
+/// An entity mentioned in text
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Entity {
+    /// Normalized name: lowercase, without leading articles
+    pub name: String,
+
+    /// Entity kind
+    pub kind: EntityKind,
+}
+
+/// Words that never start or continue an entity
+const STOPWORDS: &[&str] = &[
+    "a", "an", "the", "and", "or", "but", "if", "then", "so", "of", "in", "on", "at", "to", "for",
+    "with", "by", "from", "about", "into", "near", "over", "under", "is", "are", "was", "were", "be",
+    "been", "has", "have", "had", "do", "does", "did", "will", "would", "can", "could", "should",
+    "i", "you", "he", "she", "it", "we", "they", "me", "him", "her", "us", "them", "my", "your",
+    "his", "its", "our", "their", "this", "that", "these", "those", "there", "here", "what", "who",
+    "where", "when", "why", "how", "yes", "no", "not", "hello", "hi", "oh", "please", "thanks",
+    "someone", "something", "everyone", "nobody", "today", "yesterday", "tomorrow", "maybe", "well",
+];
+
+/// Prepositions after which a name is taken to be a place
+const LOCATIVES: &[&str] = &["in", "at", "near", "into", "inside", "towards", "toward"];
+
+/// Maximum number of words in an item phrase
+const MAX_ITEM_WORDS: usize = 3;
+
+fn is_stopword(word: &str) -> bool {
+    STOPWORDS.contains(&word.to_lowercase().as_str())
+}
+
+/// Normalize an entity name for comparison
+///
+/// Lowercases, collapses whitespace and strips leading articles, so
+/// "The Stolen  Amulet" and "stolen amulet" compare equal.
+pub fn normalize_entity(name: &str) -> String {
+    let words: Vec<String> = name
+        .split_whitespace()
+        .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase())
+        .filter(|w| !w.is_empty())
+        .collect();
+    let start = words
+        .iter()
+        .position(|w| !matches!(w.as_str(), "the" | "a" | "an"))
+        .unwrap_or(words.len());
+    words[start..].join(" ")
+}
+
+/// Extract the people, places and items mentioned in text
+///
+/// # Arguments
+///
+/// * `text` - Text to scan
+///
+/// # Returns
+///
+/// Distinct entities in order of first mention
+pub fn extract_entities(text: &str) -> Vec<Entity> {
+    let mut entities: Vec<Entity> = Vec::new();
+    let mut push = |name: String, kind: EntityKind| {
+        if !name.is_empty() && !entities.iter().any(|e| e.name == name) {
+            entities.push(Entity { name, kind });
+        }
+    };
+
+    for sentence in text.split(['.', '!', '?', ';', '\n']) {
+        let tokens: Vec<&str> = sentence.split_whitespace().collect();
+        let clean = |i: usize| tokens[i].trim_matches(|c: char| !c.is_alphanumeric());
+        let mut i = 0;
+        while i < tokens.len() {
+            let word = clean(i);
+            if word.is_empty() {
+                i += 1;
+                continue;
+            }
+
+            if word.eq_ignore_ascii_case("the") {
+                // Item phrase: words after "the" up to a stopword or punctuation
+                let mut words = Vec::new();
+                let mut j = i + 1;
+                while j < tokens.len() && words.len() < MAX_ITEM_WORDS {
+                    let next = clean(j);
+                    if next.is_empty() || is_stopword(next) {
+                        break;
+                    }
+                    words.push(next);
+                    let ends_clause = tokens[j].ends_with(|c: char| !c.is_alphanumeric());
+                    j += 1;
+                    if ends_clause {
+                        break;
+                    }
+                }
+                if !words.is_empty() {
+                    let capitalized = words.iter().all(|w| w.starts_with(char::is_uppercase));
+                    let kind = if capitalized { EntityKind::Place } else { EntityKind::Item };
+                    push(normalize_entity(&words.join(" ")), kind);
+                    i = j;
+                    continue;
+                }
+            } else if word.starts_with(char::is_uppercase) && !is_stopword(word) {
+                // Name: a run of capitalized words
+                let mut words = vec![word];
+                let mut j = i + 1;
+                while j < tokens.len() && !tokens[j - 1].ends_with(|c: char| !c.is_alphanumeric()) {
+                    let next = clean(j);
+                    if next.is_empty() || !next.starts_with(char::is_uppercase) || is_stopword(next) {
+                        break;
+                    }
+                    words.push(next);
+                    j += 1;
+                }
+                // A sentence opener before a name is usually a verb or adverb
+                if i == 0 && words.len() > 1 {
+                    words.remove(0);
+                }
+                let after_locative = i > 0 && LOCATIVES.contains(&clean(i - 1).to_lowercase().as_str());
+                let kind = if after_locative { EntityKind::Place } else { EntityKind::Person };
+                push(normalize_entity(&words.join(" ")), kind);
+                i = j;
+                continue;
+            }
+            i += 1;
+        }
+    }
+
+    entities
+}
+
+/// Whether a memory entity matches a queried entity
+///
+/// Every word of the query must appear in the entity, so "amulet" matches
+/// "stolen amulet" but "stolen sword" does not.
+pub(crate) fn entity_matches(entity: &str, query: &str) -> bool {
+    !query.is_empty() && query.split(' ').all(|word| entity.split(' ').any(|w| w == word))
+}
+
+/// Links between memories that mention the same entities
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MemoryGraph {
+    /// Memory IDs keyed by entity name
+    entities: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl MemoryGraph {
+    /// Build the graph for a set of memories
+    pub fn build<'a>(memories: impl IntoIterator<Item = &'a Memory>) -> Self {
+        let mut graph = Self::default();
+        for memory in memories {
+            for entity in &memory.entities {
+                graph.entities.entry(entity.clone()).or_default().insert(memory.id.clone());
+            }
+        }
+        graph
+    }
+
+    /// Names of all known entities
+    pub fn entities(&self) -> impl Iterator<Item = &str> {
+        self.entities.keys().map(String::as_str)
+    }
+
+    /// IDs of the memories mentioning an entity
+    pub fn mentions(&self, entity: &str) -> BTreeSet<String> {
+        let query = normalize_entity(entity);
+        self.entities
+            .iter()
+            .filter(|(name, _)| entity_matches(name, &query))
+            .flat_map(|(_, ids)| ids.iter().cloned())
+            .collect()
+    }
+
+    /// IDs of the memories sharing at least one entity with a memory
+    ///
+    /// Entities link when one name contains all words of the other, so
+    /// "amulet" links to "silver amulet".
+    pub fn linked(&self, memory_id: &str) -> BTreeSet<String> {
+        let own: Vec<&String> = self
+            .entities
+            .iter()
+            .filter(|(_, ids)| ids.contains(memory_id))
+            .map(|(name, _)| name)
+            .collect();
+        self.entities
+            .iter()
+            .filter(|(name, _)| own.iter().any(|o| entity_matches(name, o) || entity_matches(o, name)))
+            .flat_map(|(_, ids)| ids.iter().cloned())
+            .filter(|id| id != memory_id)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::MemoryCategory;
+
+    #[test]
+    fn test_extract_entities() {
+        let entities = extract_entities(
+            "Marla said the stolen amulet was hidden in Old Harbor. Ask Captain Brennick about the amulet!",
+        );
+        let found: Vec<(&str, EntityKind)> = entities.iter().map(|e| (e.name.as_str(), e.kind)).collect();
+        assert_eq!(
+            found,
+            vec![
+                ("marla", EntityKind::Person),
+                ("stolen amulet", EntityKind::Item),
+                ("old harbor", EntityKind::Place),
+                ("captain brennick", EntityKind::Person),
+                ("amulet", EntityKind::Item),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_graph_links_memories_by_entity() {
+        let theft = Memory::new(MemoryCategory::Episodic, "Someone stole the silver amulet from Marla", 0.5, None);
+        let rumor = Memory::new(MemoryCategory::Episodic, "A thief was seen with the amulet near the docks", 0.5, None);
+        let weather = Memory::new(MemoryCategory::Episodic, "It rained all week", 0.5, None);
+        let graph = MemoryGraph::build([&theft, &rumor, &weather]);
+
+        let mentions = graph.mentions("the amulet");
+        assert!(mentions.contains(&theft.id) && mentions.contains(&rumor.id));
+        assert_eq!(mentions.len(), 2);
+        assert!(graph.linked(&theft.id).contains(&rumor.id));
+        assert!(graph.linked(&weather.id).is_empty());
+    }
+}
@@ -31,6 +31,10 @@ pub enum OxydeError {
     #[error("Behavior error: {0}")]
     BehaviorError(String),
 
+    /// Context updates that do not fit the context schema
+    #[error("Context error: {0}")]
+    ContextError(String),
+
     /// Engine binding errors
     #[error("Binding error: {0}")]
     BindingError(String),
@@ -47,6 +51,10 @@ pub enum OxydeError {
     #[error("Request error: {0}")]
     RequestError(String),
 
+    /// The agent is busy and the request was rejected
+    #[error("Agent busy: {0}")]
+    Busy(String),
+
     /// CLI errors
     #[error("CLI error: {0}")]
     CliError(String),
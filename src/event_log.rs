@@ -0,0 +1,452 @@
+//! Event-sourced agent state
+//!
+//! Desyncs between a game engine and the SDK are hard to debug from the final
+//! state alone. With the event log enabled, an agent appends every change to
+//! its memories, emotions and context to a log, numbered in order, and
+//! [`replay`] rebuilds the state as it was after any event:
+//!
+//! ```yaml
+//! event_log:
+//!   enabled: true
+//!   path: logs/blacksmith-events.jsonl
+//!   max_events: 10000
+//! ```
+//!
+//! The latest `max_events` events are kept in memory and, when `path` is set,
+//! every event is also appended to a JSON lines file that [`EventLog::load`]
+//! reads back. Once older events have been dropped from memory, replays read
+//! the file, so long sessions need a `path` to replay from their start.
+//! Agents writing files need a path each. Recall bookkeeping, such as memory
+//! access counts, is not logged.
+//!
+//! Emotion changes are logged in the dimensions of the agent's affect model,
+//! along with the emotions they projected to.
+//!
+//! ```no_run
+//! # async fn example(agent: &oxyde::Agent) -> oxyde::Result<()> {
+//! let state = agent.replay_events(Some(42)).await?;
+//! println!("{} memories after event 42", state.memories.len());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::agent::AgentContext;
+use crate::config::MemoryConfig;
+use crate::context::ContextDiff;
+use crate::memory::{Memory, MemorySystem, MemoryVisibility};
+use crate::oxyde_game::emotion::EmotionalState;
+use crate::oxyde_game::schedule::GameTime;
+use crate::{OxydeError, Result};
+
+/// Configuration for the agent state event log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventLogConfig {
+    /// Whether state changes are logged
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// JSON lines file the log is also appended to
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+
+    /// Most events kept in memory; older ones are only in the file
+    #[serde(default = "default_max_events")]
+    pub max_events: usize,
+}
+
+fn default_max_events() -> usize {
+    10_000
+}
+
+impl Default for EventLogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: None,
+            max_events: default_max_events(),
+        }
+    }
+}
+
+impl EventLogConfig {
+    /// Validate the event log configuration
+    pub fn validate(&self) -> Result<()> {
+        if self.path.as_ref().is_some_and(|path| path.as_os_str().is_empty()) {
+            return Err(OxydeError::ConfigurationError(
+                "Event log path cannot be empty".to_string(),
+            ));
+        }
+        if self.max_events == 0 {
+            return Err(OxydeError::ConfigurationError(
+                "Event log max_events must be greater than 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A change to an agent's state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StateEvent {
+    /// A memory was stored, possibly merging into a duplicate or evicting another
+    MemoryAdded {
+        /// The memory as passed to the memory system
+        memory: Memory,
+    },
+
+    /// Memories were forgotten
+    MemoriesForgotten {
+        /// IDs of the forgotten memories
+        ids: Vec<String>,
+    },
+
+    /// A memory's visibility changed
+    MemoryVisibilityChanged {
+        /// ID of the memory
+        id: String,
+        /// New visibility
+        visibility: MemoryVisibility,
+    },
+
+    /// The forgetting policy was applied
+    ForgettingApplied {
+        /// Game time it was applied at
+        now: GameTime,
+        /// Game days since it was last applied
+        elapsed_days: f64,
+    },
+
+    /// Emotions changed
+    EmotionsChanged {
+        /// What changed them, such as "update", "behavior" or "decay"
+        cause: String,
+        /// Change of each affect model dimension that moved
+        deltas: BTreeMap<String, f32>,
+        /// Emotions afterwards
+        emotional_state: EmotionalState,
+    },
+
+    /// The context changed
+    ContextUpdated {
+        /// Keys that were set and removed
+        diff: ContextDiff,
+    },
+
+    /// Memories and emotions were replaced from a snapshot
+    Restored {
+        /// Restored emotions
+        emotional_state: EmotionalState,
+        /// Restored memories
+        memories: Vec<Memory>,
+    },
+}
+
+impl StateEvent {
+    /// Event recording a change of the affect model's dimensions, if any
+    /// dimension moved
+    ///
+    /// # Arguments
+    ///
+    /// * `cause` - What changed the emotions
+    /// * `before` - Dimensions before the change, as the affect model names them
+    /// * `after` - Dimensions after the change
+    /// * `emotional_state` - Emotions after the change
+    pub(crate) fn emotions_changed(
+        cause: &str,
+        before: &[(String, f32)],
+        after: &[(String, f32)],
+        emotional_state: &EmotionalState,
+    ) -> Option<Self> {
+        let before: BTreeMap<&str, f32> = before.iter().map(|(name, value)| (name.as_str(), *value)).collect();
+        let deltas: BTreeMap<String, f32> = after
+            .iter()
+            .map(|(name, value)| (name, value - before.get(name.as_str()).copied().unwrap_or(0.0)))
+            .filter(|(_, delta)| *delta != 0.0)
+            .map(|(name, delta)| (name.clone(), delta))
+            .collect();
+        (!deltas.is_empty()).then(|| Self::EmotionsChanged {
+            cause: cause.to_string(),
+            deltas,
+            emotional_state: emotional_state.clone(),
+        })
+    }
+}
+
+/// A logged event and its position in the log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventRecord {
+    /// Position in the log, starting at 1
+    pub sequence: u64,
+
+    /// Unix time the event was logged, in milliseconds
+    pub timestamp_ms: u64,
+
+    /// The change
+    pub event: StateEvent,
+}
+
+/// Events kept in memory, and the sequence number of the latest
+#[derive(Debug, Default)]
+struct RecentEvents {
+    records: VecDeque<EventRecord>,
+    last_sequence: u64,
+}
+
+/// Append-only log of an agent's state changes
+#[derive(Debug)]
+pub struct EventLog {
+    path: Option<PathBuf>,
+    max_events: usize,
+    recent: Mutex<RecentEvents>,
+    file: Mutex<Option<File>>,
+}
+
+impl EventLog {
+    /// Create an empty log; the file, if any, is opened on the first append
+    pub fn new(config: &EventLogConfig) -> Self {
+        Self {
+            path: config.path.clone(),
+            max_events: config.max_events.max(1),
+            recent: Mutex::new(RecentEvents::default()),
+            file: Mutex::new(None),
+        }
+    }
+
+    fn recent(&self) -> std::sync::MutexGuard<'_, RecentEvents> {
+        self.recent.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Append an event
+    ///
+    /// Failures to write the file are logged; the in-memory log is always
+    /// appended to, dropping its oldest event when full.
+    ///
+    /// # Returns
+    ///
+    /// The event's sequence number
+    pub fn append(&self, event: StateEvent) -> u64 {
+        let mut recent = self.recent();
+        recent.last_sequence += 1;
+        let record = EventRecord {
+            sequence: recent.last_sequence,
+            timestamp_ms: crate::utils::current_timestamp_ms() as u64,
+            event,
+        };
+        if let Err(e) = self.write(&record) {
+            log::warn!("Failed to write event {} to the event log: {}", record.sequence, e);
+        }
+        if recent.records.len() == self.max_events {
+            recent.records.pop_front();
+        }
+        recent.records.push_back(record);
+        recent.last_sequence
+    }
+
+    /// Append a record to the file, if one is configured
+    fn write(&self, record: &EventRecord) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let mut file = self.file.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if file.is_none() {
+            if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                fs::create_dir_all(parent)?;
+            }
+            *file = Some(OpenOptions::new().create(true).append(true).open(path)?);
+        }
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        file.as_mut().expect("event log file was just opened").write_all(&line)?;
+        Ok(())
+    }
+
+    /// Events kept in memory up to a sequence number, oldest first
+    ///
+    /// # Arguments
+    ///
+    /// * `until` - Last sequence number to return; `None` returns every
+    ///   event kept
+    pub fn records(&self, until: Option<u64>) -> Vec<EventRecord> {
+        self.recent()
+            .records
+            .iter()
+            .take_while(|record| until.is_none_or(|until| record.sequence <= until))
+            .cloned()
+            .collect()
+    }
+
+    /// Events from the first one logged up to a sequence number, oldest first
+    ///
+    /// Reads the file once the first events are no longer kept in memory.
+    ///
+    /// # Returns
+    ///
+    /// The events, or an error if they are neither in memory nor in a file
+    pub fn history(&self, until: Option<u64>) -> Result<Vec<EventRecord>> {
+        let complete = self.recent().records.front().is_none_or(|record| record.sequence == 1);
+        if complete {
+            return Ok(self.records(until));
+        }
+        let Some(path) = &self.path else {
+            return Err(OxydeError::ConfigurationError(
+                "Event log dropped its oldest events and has no path to read them from".to_string(),
+            ));
+        };
+        let mut records = Self::load(path)?;
+        records.retain(|record| until.is_none_or(|until| record.sequence <= until));
+        Ok(records)
+    }
+
+    /// Sequence number of the latest event; 0 if none were logged
+    pub fn last_sequence(&self) -> u64 {
+        self.recent().last_sequence
+    }
+
+    /// Number of events kept in memory
+    pub fn len(&self) -> usize {
+        self.recent().records.len()
+    }
+
+    /// Whether no events were logged
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Read events from a JSON lines file written by an event log
+    pub fn load(path: impl AsRef<Path>) -> Result<Vec<EventRecord>> {
+        let file = File::open(path)?;
+        let mut records = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                records.push(serde_json::from_str(&line)?);
+            }
+        }
+        Ok(records)
+    }
+}
+
+/// Agent state rebuilt from an event log
+#[derive(Debug, Clone, Default)]
+pub struct ReplayedState {
+    /// Sequence number of the last event applied; 0 if none were
+    pub sequence: u64,
+
+    /// Emotions after the last event applied
+    pub emotional_state: EmotionalState,
+
+    /// Memories after the last event applied
+    pub memories: Vec<Memory>,
+
+    /// Context after the last event applied
+    pub context: AgentContext,
+}
+
+/// Rebuild agent state by applying logged events to a fresh agent's state
+///
+/// Memories are stored through a memory system with the agent's memory
+/// configuration, so duplicate merging, eviction and forgetting happen as
+/// they did when the events were logged.
+///
+/// # Arguments
+///
+/// * `records` - Logged events, oldest first
+/// * `memory_config` - Memory configuration of the agent that logged them
+/// * `until` - Last sequence number to apply; `None` applies every event
+pub async fn replay(records: &[EventRecord], memory_config: &MemoryConfig, until: Option<u64>) -> Result<ReplayedState> {
+    // Replay must not open, and so rewrite, the agent's memory store
+    let memory = MemorySystem::new(MemoryConfig {
+        persistence: false,
+        ..memory_config.clone()
+    });
+    let mut state = ReplayedState::default();
+    for record in records.iter().take_while(|record| until.is_none_or(|until| record.sequence <= until)) {
+        match &record.event {
+            StateEvent::MemoryAdded { memory: added } => memory.add(added.clone()).await?,
+            StateEvent::MemoriesForgotten { ids } => {
+                memory.remove_ids(ids).await;
+            }
+            StateEvent::MemoryVisibilityChanged { id, visibility } => memory.set_visibility(id, *visibility).await?,
+            StateEvent::ForgettingApplied { now, elapsed_days } => {
+                memory.apply_forgetting(now, *elapsed_days).await;
+            }
+            StateEvent::EmotionsChanged { emotional_state, .. } => state.emotional_state = emotional_state.clone(),
+            StateEvent::ContextUpdated { diff } => {
+                for key in &diff.remove {
+                    state.context.remove(key);
+                }
+                state.context.extend(diff.set.clone());
+            }
+            StateEvent::Restored { emotional_state, memories } => {
+                state.emotional_state = emotional_state.clone();
+                memory.restore(memories.clone()).await;
+            }
+        }
+        state.sequence = record.sequence;
+    }
+    state.memories = memory.session().await.memories;
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::MemoryCategory;
+
+    #[tokio::test]
+    async fn test_replays_state_up_to_any_event() {
+        let dir = std::env::temp_dir().join(format!("oxyde-events-{}", uuid::Uuid::new_v4()));
+        let log = EventLog::new(&EventLogConfig {
+            enabled: true,
+            path: Some(dir.join("events.jsonl")),
+            max_events: 2,
+        });
+
+        let bread = Memory::new(MemoryCategory::Episodic, "The player bought bread", 0.5, None);
+        log.append(StateEvent::MemoryAdded { memory: bread.clone() });
+        let mut before = EmotionalState::new();
+        before.update_emotion("joy", 0.4);
+        let dimensions = |pleasure: f32| vec![("pleasure".to_string(), pleasure), ("arousal".to_string(), 0.1)];
+        let changed = StateEvent::emotions_changed("update", &dimensions(0.0), &dimensions(0.3), &before).unwrap();
+        let StateEvent::EmotionsChanged { deltas, .. } = &changed else {
+            unreachable!()
+        };
+        assert_eq!(deltas.keys().collect::<Vec<_>>(), ["pleasure"]);
+        log.append(changed);
+        assert!(StateEvent::emotions_changed("decay", &dimensions(0.3), &dimensions(0.3), &before).is_none());
+        log.append(StateEvent::ContextUpdated {
+            diff: ContextDiff::new().set("weather", serde_json::json!("rain")),
+        });
+        log.append(StateEvent::MemoriesForgotten { ids: vec![bread.id.clone()] });
+
+        let records = EventLog::load(dir.join("events.jsonl")).unwrap();
+        assert_eq!(records.len() as u64, log.last_sequence());
+
+        // Only the latest events stay in memory; the file still has them all
+        assert_eq!(log.len(), 2);
+        assert_eq!(log.records(None)[0].sequence, 3);
+        assert_eq!(log.history(Some(3)).unwrap().len(), 3);
+
+        let config = MemoryConfig::default();
+        let at_two = replay(&records, &config, Some(2)).await.unwrap();
+        assert_eq!(at_two.sequence, 2);
+        assert_eq!(at_two.memories.len(), 1);
+        assert_eq!(at_two.emotional_state.as_vector(), before.as_vector());
+        assert!(at_two.context.is_empty());
+
+        let end = replay(&records, &config, None).await.unwrap();
+        assert_eq!(end.sequence, 4);
+        assert!(end.memories.is_empty());
+        assert_eq!(end.context["weather"], "rain");
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,266 @@
+//! Runtime A/B experiments on prompts and models
+//!
+//! An experiment splits agents, or the players talking to them, between
+//! variants that each override some of the prompt layers, the cloud model
+//! and the sampling temperature:
+//!
+//! ```yaml
+//! experiment:
+//!   enabled: true
+//!   name: gruff-greetings
+//!   unit: session
+//!   variants:
+//!     - name: control
+//!       weight: 1
+//!     - name: gruff
+//!       weight: 1
+//!       prompts:
+//!         agent: You are curt and suspicious of strangers.
+//!       model: gpt-4o-mini
+//!       temperature: 0.9
+//! ```
+//!
+//! Assignment hashes the experiment name with the agent name (`unit: agent`)
+//! or the player key (`unit: session`), so the same agent or player lands in
+//! the same variant on every run and every machine. Changing the experiment
+//! name reshuffles the buckets.
+//!
+//! The assigned variant is labelled under the [`EXPERIMENT_KEY`] context key,
+//! in interaction log records, and on the `agent.process_input` tracing span,
+//! so analytics can compare engagement between variants.
+
+use serde::{Deserialize, Serialize};
+
+use crate::agent::AgentContext;
+use crate::inference::RequestOptions;
+use crate::oxyde_game::reengagement::player_key;
+use crate::prompt::{PromptConfig, PromptLayer};
+use crate::{OxydeError, Result};
+
+/// Context key labelling the experiment and variant a request belongs to
+pub const EXPERIMENT_KEY: &str = "experiment";
+
+/// What an experiment assigns to variants
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExperimentUnit {
+    /// Each agent is assigned by name, for every player
+    #[default]
+    Agent,
+    /// Each player is assigned by player key, for every agent
+    Session,
+}
+
+/// One arm of an experiment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExperimentVariant {
+    /// Label recorded for requests in this variant
+    pub name: String,
+
+    /// Relative share of agents or players assigned to this variant
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+
+    /// Prompt layers replacing the agent's; unset layers are kept
+    #[serde(default)]
+    pub prompts: PromptConfig,
+
+    /// Cloud model used instead of the configured one
+    #[serde(default)]
+    pub model: Option<String>,
+
+    /// Sampling temperature used instead of the configured one
+    #[serde(default)]
+    pub temperature: Option<f32>,
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+impl ExperimentVariant {
+    /// Replace the layers this variant sets
+    pub fn apply_prompts(&self, prompts: &mut PromptConfig) {
+        for layer in [PromptLayer::World, PromptLayer::Faction, PromptLayer::Agent, PromptLayer::Scene] {
+            if let Some(text) = self.prompts.layer(layer) {
+                prompts.set_layer(layer, Some(text.to_string()));
+            }
+        }
+    }
+
+    /// Whether this variant replaces any prompt layer
+    pub fn overrides_prompts(&self) -> bool {
+        self.prompts != PromptConfig::default()
+    }
+
+    /// Model and temperature overrides to generate this variant's replies with
+    pub fn request_options(&self) -> RequestOptions {
+        RequestOptions {
+            model: self.model.clone(),
+            temperature: self.temperature,
+            ..Default::default()
+        }
+    }
+}
+
+/// Configuration for an A/B experiment
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExperimentConfig {
+    /// Whether variants are assigned
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Experiment name, recorded with the variant and mixed into bucketing
+    #[serde(default)]
+    pub name: String,
+
+    /// Whether agents or players are assigned to variants
+    #[serde(default)]
+    pub unit: ExperimentUnit,
+
+    /// Variants to assign between
+    #[serde(default)]
+    pub variants: Vec<ExperimentVariant>,
+}
+
+impl ExperimentConfig {
+    /// Validate the experiment configuration
+    pub fn validate(&self) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if self.name.trim().is_empty() {
+            return Err(OxydeError::ConfigurationError("Experiment name cannot be empty".to_string()));
+        }
+        if self.variants.iter().map(|variant| variant.weight as u64).sum::<u64>() == 0 {
+            return Err(OxydeError::ConfigurationError(format!(
+                "Experiment {} needs at least one variant with a weight above 0",
+                self.name
+            )));
+        }
+        for (index, variant) in self.variants.iter().enumerate() {
+            if variant.name.trim().is_empty() {
+                return Err(OxydeError::ConfigurationError(format!(
+                    "Experiment {} has a variant without a name",
+                    self.name
+                )));
+            }
+            if self.variants[..index].iter().any(|other| other.name == variant.name) {
+                return Err(OxydeError::ConfigurationError(format!(
+                    "Experiment {} has more than one variant named {}",
+                    self.name, variant.name
+                )));
+            }
+            if variant.temperature.is_some_and(|t| !(0.0..=2.0).contains(&t)) {
+                return Err(OxydeError::ConfigurationError(format!(
+                    "Temperature of variant {} must be between 0.0 and 2.0",
+                    variant.name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Variant for a bucketing key, if the experiment is enabled
+    ///
+    /// The same key always gets the same variant while the experiment's name
+    /// and variant weights are unchanged.
+    pub fn assign(&self, key: &str) -> Option<&ExperimentVariant> {
+        if !self.enabled {
+            return None;
+        }
+        let total: u64 = self.variants.iter().map(|variant| variant.weight as u64).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut bucket = stable_hash(&format!("{}\0{}", self.name, key)) % total;
+        self.variants.iter().find(|variant| {
+            let weight = variant.weight as u64;
+            if bucket < weight {
+                return true;
+            }
+            bucket -= weight;
+            false
+        })
+    }
+
+    /// Variant for a request to an agent, by agent name or player key
+    /// depending on the experiment's unit
+    pub fn assign_request(&self, agent_name: &str, context: &AgentContext) -> Option<&ExperimentVariant> {
+        match self.unit {
+            ExperimentUnit::Agent => self.assign(agent_name),
+            ExperimentUnit::Session => self.assign(&player_key(context)),
+        }
+    }
+
+    /// Label a request with a variant
+    pub fn apply_to_context(&self, variant: &ExperimentVariant, context: &mut AgentContext) {
+        context.insert(
+            EXPERIMENT_KEY.to_string(),
+            serde_json::json!({ "name": self.name, "variant": variant.name }),
+        );
+    }
+}
+
+/// Experiment and variant a request was labelled with, if any
+pub fn variant_label(context: &AgentContext) -> Option<(String, String)> {
+    let label = context.get(EXPERIMENT_KEY)?;
+    let name = label.get("name")?.as_str()?;
+    let variant = label.get("variant")?.as_str()?;
+    Some((name.to_string(), variant.to_string()))
+}
+
+/// 64-bit FNV-1a, which unlike the standard hasher is stable across
+/// releases and platforms
+fn stable_hash(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assigns_variants_consistently_by_weight() {
+        let experiment: ExperimentConfig = serde_yaml::from_str(
+            "enabled: true\n\
+             name: greetings\n\
+             unit: session\n\
+             variants:\n  \
+               - name: control\n    weight: 3\n  \
+               - name: gruff\n    temperature: 0.9\n",
+        )
+        .unwrap();
+        experiment.validate().unwrap();
+
+        let players: Vec<String> = (0..400).map(|i| format!("player-{}", i)).collect();
+        let assigned: Vec<&str> = players
+            .iter()
+            .map(|player| experiment.assign(player).unwrap().name.as_str())
+            .collect();
+        let again: Vec<&str> = players
+            .iter()
+            .map(|player| experiment.assign(player).unwrap().name.as_str())
+            .collect();
+        assert_eq!(assigned, again);
+        let gruff = assigned.iter().filter(|name| **name == "gruff").count();
+        assert!((60..140).contains(&gruff), "gruff got {} of 400", gruff);
+
+        let mut context = AgentContext::new();
+        context.insert("player_id".to_string(), serde_json::json!("player-7"));
+        let variant = experiment.assign_request("Mira", &context).unwrap();
+        assert_eq!(variant.name, assigned[7]);
+        experiment.apply_to_context(variant, &mut context);
+        assert_eq!(variant.request_options().temperature, variant.temperature);
+        assert_eq!(variant_label(&context), Some(("greetings".to_string(), variant.name.clone())));
+
+        let disabled = ExperimentConfig { enabled: false, ..experiment.clone() };
+        assert!(disabled.assign("player-1").is_none());
+
+        let mut duplicate = experiment;
+        duplicate.variants[1].name = "control".to_string();
+        assert!(duplicate.validate().is_err());
+    }
+}
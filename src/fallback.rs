@@ -0,0 +1,245 @@
+//! Offline fallback responses for the Oxyde SDK
+//!
+//! When inference is unavailable (no network, no local model), agents answer
+//! with canned responses instead of failing. Responses are templates keyed by
+//! intent and dominant emotion; placeholders such as `{name}` or
+//! `{player_name}` are filled from the agent's context so the reply still
+//! reflects the current game state.
+
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use crate::agent::AgentContext;
+use crate::oxyde_game::emotion::EmotionalState;
+use crate::oxyde_game::intent::IntentType;
+
+/// Canned responses for one intent/emotion combination
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FallbackTemplate {
+    /// Intent this template answers; `None` matches any intent
+    #[serde(default)]
+    pub intent: Option<IntentType>,
+
+    /// Dominant emotion required; `None` matches any emotional state
+    #[serde(default)]
+    pub emotion: Option<String>,
+
+    /// Minimum strength of the dominant emotion for `emotion` to match
+    #[serde(default = "default_min_intensity")]
+    pub min_intensity: f32,
+
+    /// Response variants; `{key}` placeholders are filled from context
+    pub responses: Vec<String>,
+}
+
+fn default_min_intensity() -> f32 {
+    0.3
+}
+
+impl FallbackTemplate {
+    /// Create a template for an intent
+    pub fn for_intent(intent: IntentType, responses: &[&str]) -> Self {
+        Self {
+            intent: Some(intent),
+            emotion: None,
+            min_intensity: default_min_intensity(),
+            responses: responses.iter().map(|r| r.to_string()).collect(),
+        }
+    }
+
+    /// Require a dominant emotion for this template
+    pub fn with_emotion(mut self, emotion: &str, min_intensity: f32) -> Self {
+        self.emotion = Some(emotion.to_string());
+        self.min_intensity = min_intensity;
+        self
+    }
+
+    /// How specifically this template matches, or `None` if it doesn't
+    fn specificity(&self, intent: IntentType, emotions: &EmotionalState) -> Option<u8> {
+        let mut score = 0;
+        if let Some(expected) = self.intent {
+            if expected != intent {
+                return None;
+            }
+            score += 1;
+        }
+        if let Some(expected) = &self.emotion {
+            let (dominant, value) = emotions.dominant_emotion();
+            if dominant != expected || value.abs() < self.min_intensity {
+                return None;
+            }
+            score += 2;
+        }
+        Some(score)
+    }
+}
+
+/// Configuration for offline fallback responses
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OfflineFallbackConfig {
+    /// Whether canned responses replace inference failures
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Custom templates, checked before the built-in ones
+    #[serde(default)]
+    pub templates: Vec<FallbackTemplate>,
+
+    /// Whether to use built-in generic responses when no template matches
+    #[serde(default = "default_use_builtin")]
+    pub use_builtin: bool,
+}
+
+fn default_use_builtin() -> bool {
+    true
+}
+
+impl Default for OfflineFallbackConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            templates: Vec::new(),
+            use_builtin: default_use_builtin(),
+        }
+    }
+}
+
+/// Built-in responses used when no configured template matches
+fn builtin_response(intent: IntentType) -> &'static str {
+    match intent {
+        IntentType::Greeting | IntentType::Proximity => "Hello there.",
+        IntentType::Friendly => "Good to see you.",
+        IntentType::Question | IntentType::Query => "Hmm, I can't say right now.",
+        IntentType::Request | IntentType::Command => "I can't help with that at the moment.",
+        IntentType::Hostile | IntentType::Threat | IntentType::Demand => "I don't want any trouble.",
+        IntentType::Chat | IntentType::Custom => "Mm-hm.",
+    }
+}
+
+/// Fill `{key}` placeholders from context
+///
+/// Returns `None` if a placeholder has no value so callers can try another
+/// variant rather than showing a raw placeholder to the player.
+fn render(template: &str, context: &AgentContext) -> Option<String> {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        output.push_str(&rest[..start]);
+        let end = rest[start..].find('}')? + start;
+        let value = context.get(&rest[start + 1..end])?;
+        match value {
+            serde_json::Value::String(s) => output.push_str(s),
+            serde_json::Value::Null => return None,
+            other => output.push_str(&other.to_string()),
+        }
+        rest = &rest[end + 1..];
+    }
+    output.push_str(rest);
+    Some(output)
+}
+
+/// Chooses canned responses when inference is unavailable
+#[derive(Debug, Clone, Default)]
+pub struct OfflineFallback {
+    config: OfflineFallbackConfig,
+}
+
+impl OfflineFallback {
+    /// Create a fallback layer from configuration
+    pub fn new(config: OfflineFallbackConfig) -> Self {
+        Self { config }
+    }
+
+    /// Whether the fallback layer is enabled
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Pick a response for an intent and emotional state
+    ///
+    /// The most specific matching template wins (intent and emotion over
+    /// emotion alone over intent alone). A random renderable variant is
+    /// chosen, using the turn seed from the context when present.
+    ///
+    /// # Arguments
+    ///
+    /// * `intent` - Detected player intent
+    /// * `emotions` - Agent's current emotional state
+    /// * `context` - Context used to fill template placeholders
+    ///
+    /// # Returns
+    ///
+    /// A response, or `None` if disabled or nothing matched
+    pub fn respond(&self, intent: IntentType, emotions: &EmotionalState, context: &AgentContext) -> Option<String> {
+        if !self.config.enabled {
+            return None;
+        }
+
+        let mut matching: Vec<(u8, &FallbackTemplate)> = self
+            .config
+            .templates
+            .iter()
+            .filter_map(|t| t.specificity(intent, emotions).map(|score| (score, t)))
+            .collect();
+        matching.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+
+        let mut rng = crate::turn::context_rng(context);
+        for (_, template) in matching {
+            let mut variants: Vec<String> = template
+                .responses
+                .iter()
+                .filter_map(|response| render(response, context))
+                .collect();
+            variants.shuffle(&mut rng);
+            if let Some(response) = variants.into_iter().next() {
+                return Some(response);
+            }
+        }
+
+        self.config
+            .use_builtin
+            .then(|| builtin_response(intent).to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fallback() -> OfflineFallback {
+        OfflineFallback::new(OfflineFallbackConfig {
+            enabled: true,
+            templates: vec![
+                FallbackTemplate::for_intent(IntentType::Greeting, &["Welcome, {player_name}.", "Welcome."]),
+                FallbackTemplate::for_intent(IntentType::Greeting, &["What do you want?"]).with_emotion("anger", 0.5),
+            ],
+            use_builtin: true,
+        })
+    }
+
+    #[test]
+    fn test_templates_use_context_and_emotion() {
+        let fallback = fallback();
+        let mut context = AgentContext::new();
+        context.insert("player_name".to_string(), serde_json::json!("Aria"));
+
+        let calm = EmotionalState::new();
+        let response = fallback.respond(IntentType::Greeting, &calm, &context).unwrap();
+        assert!(response == "Welcome, Aria." || response == "Welcome.");
+
+        let mut angry = EmotionalState::new();
+        angry.update_emotion("anger", 0.8);
+        assert_eq!(fallback.respond(IntentType::Greeting, &angry, &context).unwrap(), "What do you want?");
+    }
+
+    #[test]
+    fn test_unrenderable_variants_and_builtin_fallback() {
+        let fallback = fallback();
+        let calm = EmotionalState::new();
+        let context = AgentContext::new();
+
+        assert_eq!(fallback.respond(IntentType::Greeting, &calm, &context).unwrap(), "Welcome.");
+        assert_eq!(fallback.respond(IntentType::Threat, &calm, &context).unwrap(), "I don't want any trouble.");
+        assert!(OfflineFallback::default().respond(IntentType::Chat, &calm, &context).is_none());
+    }
+}
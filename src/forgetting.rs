@@ -0,0 +1,265 @@
+//! Policy-driven forgetting
+//!
+//! Capacity eviction only removes memories when the store is full. A
+//! forgetting policy also lets memories lapse as game time passes: episodic
+//! memories can expire after a number of game days unless they were
+//! reinforced by being recalled or restated, and emotional memories can fade
+//! toward a lower intensity:
+//!
+//! ```yaml
+//! memory:
+//!   forgetting:
+//!     enabled: true
+//!     interval_ms: 60000
+//!     rules:
+//!       - category: episodic
+//!         expire_after_days: 7
+//!         reinforce_recalls: 3
+//!       - category: emotional
+//!         fade_per_day: 0.1
+//!         min_intensity: 0.2
+//! ```
+//!
+//! Ages are measured in game days from the game time a memory was formed at,
+//! so memories formed without a game time never expire. Permanent memories
+//! are never expired or faded. `Agent::spawn_forgetting` applies the policy
+//! on a background task every `interval_ms`; `Agent::apply_forgetting` applies
+//! it once.
+
+use serde::{Deserialize, Serialize};
+
+use crate::memory::{Memory, MemoryCategory};
+use crate::oxyde_game::schedule::GameTime;
+use crate::{OxydeError, Result};
+
+/// Forgetting rule for one memory category
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ForgettingRule {
+    /// Category the rule applies to, such as "episodic"
+    pub category: String,
+
+    /// Game days after which unreinforced memories are forgotten; `None`
+    /// keeps them
+    #[serde(default)]
+    pub expire_after_days: Option<f64>,
+
+    /// Recalls after which a memory counts as reinforced and no longer expires
+    #[serde(default = "default_reinforce_recalls")]
+    pub reinforce_recalls: u32,
+
+    /// Fraction of emotional intensity lost per game day (0.0 - 1.0)
+    #[serde(default)]
+    pub fade_per_day: f64,
+
+    /// Intensity fading stops at (0.0 - 1.0)
+    #[serde(default)]
+    pub min_intensity: f64,
+}
+
+fn default_reinforce_recalls() -> u32 {
+    3
+}
+
+impl ForgettingRule {
+    /// Whether a memory has expired under this rule
+    fn expired(&self, memory: &Memory, now: &GameTime) -> bool {
+        let (Some(days), Some(formed)) = (self.expire_after_days, memory.game_time) else {
+            return false;
+        };
+        memory.access_count < self.reinforce_recalls && now.hours_since(&formed) / 24.0 >= days
+    }
+
+    /// Fade a memory's emotional intensity over `elapsed_days`, returning
+    /// whether it changed
+    fn fade(&self, memory: &mut Memory, elapsed_days: f64) -> bool {
+        if self.fade_per_day <= 0.0 || elapsed_days <= 0.0 || memory.emotional_intensity <= self.min_intensity {
+            return false;
+        }
+        let retained = (1.0 - self.fade_per_day).powf(elapsed_days);
+        memory.emotional_intensity = (memory.emotional_intensity * retained).max(self.min_intensity);
+        true
+    }
+}
+
+/// Configuration for forgetting memories as game time passes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ForgettingPolicy {
+    /// Whether the policy is applied
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Real time between runs of the background task, in milliseconds
+    #[serde(default = "default_interval_ms")]
+    pub interval_ms: u64,
+
+    /// Rules, at most one per category
+    #[serde(default)]
+    pub rules: Vec<ForgettingRule>,
+}
+
+fn default_interval_ms() -> u64 {
+    60_000
+}
+
+impl Default for ForgettingPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_ms: default_interval_ms(),
+            rules: Vec::new(),
+        }
+    }
+}
+
+impl ForgettingPolicy {
+    /// Validate the forgetting policy
+    pub fn validate(&self) -> Result<()> {
+        if self.enabled && self.interval_ms == 0 {
+            return Err(OxydeError::ConfigurationError(
+                "Forgetting interval_ms must be greater than 0".to_string(),
+            ));
+        }
+        let mut seen = Vec::new();
+        for rule in &self.rules {
+            let category = MemoryCategory::from_str(&rule.category).ok_or_else(|| {
+                OxydeError::ConfigurationError(format!("Unknown memory category in forgetting rule: {}", rule.category))
+            })?;
+            if seen.contains(&category) {
+                return Err(OxydeError::ConfigurationError(format!(
+                    "Duplicate forgetting rule for category {}",
+                    category.as_str()
+                )));
+            }
+            seen.push(category);
+            if rule.expire_after_days.is_some_and(|days| days <= 0.0) {
+                return Err(OxydeError::ConfigurationError(format!(
+                    "Forgetting expire_after_days for {} must be greater than 0",
+                    rule.category
+                )));
+            }
+            if !(0.0..=1.0).contains(&rule.fade_per_day) || !(0.0..=1.0).contains(&rule.min_intensity) {
+                return Err(OxydeError::ConfigurationError(format!(
+                    "Forgetting fade_per_day and min_intensity for {} must be between 0.0 and 1.0",
+                    rule.category
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Rule for a memory category, if any
+    pub fn rule_for(&self, category: MemoryCategory) -> Option<&ForgettingRule> {
+        self.rules
+            .iter()
+            .find(|rule| MemoryCategory::from_str(&rule.category) == Some(category))
+    }
+
+    /// Expire and fade memories, counting what changed
+    #[cfg(test)]
+    pub(crate) fn apply(&self, memories: &mut Vec<Memory>, now: &GameTime, elapsed_days: f64) -> ForgettingReport {
+        self.apply_tracked(memories, now, elapsed_days).report()
+    }
+
+    /// Expire and fade memories
+    ///
+    /// # Arguments
+    ///
+    /// * `memories` - Memories to apply the policy to
+    /// * `now` - Current game time
+    /// * `elapsed_days` - Game days since the policy was last applied, over
+    ///   which intensities fade
+    ///
+    /// # Returns
+    ///
+    /// Which memories expired and faded, so the change can be journaled
+    /// rather than rewriting every memory
+    pub(crate) fn apply_tracked(&self, memories: &mut Vec<Memory>, now: &GameTime, elapsed_days: f64) -> ForgettingChanges {
+        let mut changes = ForgettingChanges::default();
+        memories.retain_mut(|memory| {
+            let Some(rule) = self.rule_for(memory.category).filter(|_| !memory.permanent) else {
+                return true;
+            };
+            if rule.expired(memory, now) {
+                changes.expired.push(memory.id.clone());
+                return false;
+            }
+            if rule.fade(memory, elapsed_days) {
+                changes.faded.push((memory.id.clone(), memory.emotional_intensity));
+            }
+            true
+        });
+        changes
+    }
+}
+
+/// Memories one application of a forgetting policy changed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct ForgettingChanges {
+    /// IDs of the memories that expired
+    pub(crate) expired: Vec<String>,
+    /// IDs and new emotional intensities of the memories that faded
+    pub(crate) faded: Vec<(String, f64)>,
+}
+
+impl ForgettingChanges {
+    /// Count the changes
+    pub(crate) fn report(&self) -> ForgettingReport {
+        ForgettingReport {
+            expired: self.expired.len(),
+            faded: self.faded.len(),
+        }
+    }
+}
+
+/// What one application of a forgetting policy changed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ForgettingReport {
+    /// Memories forgotten because they expired
+    pub expired: usize,
+
+    /// Memories whose emotional intensity faded
+    pub faded: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expires_unreinforced_and_fades_emotional_memories() {
+        let policy: ForgettingPolicy = serde_yaml::from_str(
+            "enabled: true\n\
+             rules:\n  \
+               - category: episodic\n    expire_after_days: 7\n    reinforce_recalls: 1\n  \
+               - category: emotional\n    fade_per_day: 0.5\n    min_intensity: 0.2\n",
+        )
+        .unwrap();
+        policy.validate().unwrap();
+
+        let formed = GameTime::new(1, 12.0);
+        let stale = Memory::new(MemoryCategory::Episodic, "The player stole an apple", 0.5, None).with_game_time(formed);
+        let mut recalled = Memory::new(MemoryCategory::Episodic, "The player saved my son", 0.5, None).with_game_time(formed);
+        recalled.touch();
+        let fresh = Memory::new(MemoryCategory::Episodic, "The player bought bread", 0.5, None)
+            .with_game_time(GameTime::new(6, 12.0));
+        let grief = Memory::new_emotional(MemoryCategory::Emotional, "My dog died", 0.5, -0.9, 0.8, None);
+        let mut memories = vec![stale, recalled, fresh, grief];
+
+        let report = policy.apply(&mut memories, &GameTime::new(8, 12.0), 1.0);
+        assert_eq!(report, ForgettingReport { expired: 1, faded: 1 });
+        let contents: Vec<&str> = memories.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, vec!["The player saved my son", "The player bought bread", "My dog died"]);
+        assert!((memories[2].emotional_intensity - 0.4).abs() < 1e-9);
+
+        // Fading stops at the floor
+        policy.apply(&mut memories, &GameTime::new(10, 12.0), 2.0);
+        assert_eq!(memories[2].emotional_intensity, 0.2);
+        assert_eq!(policy.apply(&mut memories, &GameTime::new(11, 12.0), 1.0).faded, 0);
+
+        let duplicate = ForgettingPolicy {
+            rules: vec![policy.rules[0].clone(), policy.rules[0].clone()],
+            ..Default::default()
+        };
+        assert!(duplicate.validate().is_err());
+    }
+}
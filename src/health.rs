@@ -0,0 +1,304 @@
+//! Health checks for the Oxyde SDK
+//!
+//! This module verifies, before a level loads, that an agent can actually
+//! run: the configuration is valid, the local model exists, cloud inference
+//! and TTS providers are reachable, and API keys are accepted. Results are
+//! returned as structured diagnostics rather than errors so games can decide
+//! whether a degraded setup is good enough.
+
+use std::path::Path;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::audio::{TTSConfig, TTSProvider};
+use crate::config::{AgentConfig, InferenceConfig};
+
+/// ElevenLabs endpoint used to verify the API key
+const ELEVENLABS_USER_URL: &str = "https://api.elevenlabs.io/v1/user";
+
+/// Outcome of a single health check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum HealthStatus {
+    /// The component is ready
+    Ok,
+    /// The component works with reduced functionality
+    Degraded,
+    /// The component cannot be used
+    Failed,
+}
+
+/// Diagnostic for one component
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthCheck {
+    /// Component name (e.g. "config", "inference.cloud", "tts.elevenlabs")
+    pub component: String,
+
+    /// Check outcome
+    pub status: HealthStatus,
+
+    /// Human-readable explanation
+    pub message: String,
+
+    /// Round-trip time for network checks, in milliseconds
+    pub latency_ms: Option<u64>,
+}
+
+impl HealthCheck {
+    /// Create a check result without latency information
+    pub fn new(component: &str, status: HealthStatus, message: impl Into<String>) -> Self {
+        Self {
+            component: component.to_string(),
+            status,
+            message: message.into(),
+            latency_ms: None,
+        }
+    }
+
+    #[cfg(feature = "reqwest")]
+    fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency_ms = Some(latency.as_millis() as u64);
+        self
+    }
+}
+
+/// Collection of health check results
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HealthReport {
+    /// Individual check results, in the order they ran
+    pub checks: Vec<HealthCheck>,
+}
+
+impl HealthReport {
+    /// Worst status across all checks; `Ok` when no checks ran
+    pub fn status(&self) -> HealthStatus {
+        self.checks
+            .iter()
+            .map(|check| check.status)
+            .max()
+            .unwrap_or(HealthStatus::Ok)
+    }
+
+    /// Whether no check failed
+    pub fn is_ready(&self) -> bool {
+        self.status() != HealthStatus::Failed
+    }
+
+    /// Checks that did not pass
+    pub fn problems(&self) -> impl Iterator<Item = &HealthCheck> {
+        self.checks.iter().filter(|check| check.status != HealthStatus::Ok)
+    }
+
+    /// Find the result for a component
+    pub fn get(&self, component: &str) -> Option<&HealthCheck> {
+        self.checks.iter().find(|check| check.component == component)
+    }
+
+    /// Add a check result
+    pub fn push(&mut self, check: HealthCheck) {
+        self.checks.push(check);
+    }
+}
+
+/// What [`Agent::warm_up`](crate::agent::Agent::warm_up) prepared ahead of the first input
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WarmUpReport {
+    /// Whether prompt layers are composed, so none are configured when `false`
+    pub prompt_compiled: bool,
+
+    /// Backstory and knowledge entries with a cached embedding
+    pub embeddings_cached: usize,
+
+    /// Whether a kept-alive connection to the cloud provider was opened
+    pub connection_primed: bool,
+
+    /// Round-trip time of the priming request, when one was sent
+    pub priming_latency_ms: Option<u64>,
+
+    /// Total time spent warming up, in milliseconds
+    pub elapsed_ms: u64,
+}
+
+/// Check that an agent configuration can be used
+///
+/// Validates the configuration, then checks the inference providers and,
+/// when configured, the TTS provider.
+///
+/// # Arguments
+///
+/// * `config` - Agent configuration to check
+///
+/// # Returns
+///
+/// A report with one entry per checked component
+pub async fn healthcheck(config: &AgentConfig) -> HealthReport {
+    let mut report = HealthReport::default();
+
+    report.push(match config.validate() {
+        Ok(()) => HealthCheck::new("config", HealthStatus::Ok, "Configuration is valid"),
+        Err(e) => HealthCheck::new("config", HealthStatus::Failed, e.to_string()),
+    });
+
+    report.checks.extend(check_inference(&config.inference).await);
+
+    if let Some(tts) = &config.tts {
+        report.push(check_tts(tts).await);
+    }
+
+    report
+}
+
+/// Check the inference providers described by a configuration
+///
+/// The primary provider failing is reported as `Failed` unless the other
+/// provider is configured as a fallback and passes, in which case it is
+/// reported as `Degraded`.
+pub async fn check_inference(config: &InferenceConfig) -> Vec<HealthCheck> {
+    let local = config.local_model_path.as_deref().map(check_local_model);
+    let cloud = match &config.api_endpoint {
+        Some(endpoint) => Some(check_cloud_endpoint(config, endpoint).await),
+        None => None,
+    };
+
+    let (mut primary, secondary) = if config.use_local {
+        (local.unwrap_or_else(|| missing("inference.local", "No local model path configured")), cloud)
+    } else {
+        (cloud.unwrap_or_else(|| missing("inference.cloud", "No API endpoint configured")), local)
+    };
+
+    let fallback_ok = config.fallback_api.is_some()
+        && secondary.as_ref().map(|c| c.status == HealthStatus::Ok).unwrap_or(false);
+    if primary.status == HealthStatus::Failed && fallback_ok {
+        primary.status = HealthStatus::Degraded;
+        primary.message = format!("{} (fallback provider available)", primary.message);
+    }
+
+    let mut checks = vec![primary];
+    if let Some(mut secondary) = secondary {
+        // The secondary provider only matters when it is a fallback
+        if config.fallback_api.is_none() && secondary.status == HealthStatus::Failed {
+            secondary.status = HealthStatus::Degraded;
+        }
+        checks.push(secondary);
+    }
+    checks
+}
+
+/// Check that the configured TTS provider is reachable and accepts the API key
+pub async fn check_tts(config: &TTSConfig) -> HealthCheck {
+    match config.default_provider {
+        TTSProvider::ElevenLabs => {
+            let component = "tts.elevenlabs";
+            let api_key = match config.resolve_api_key() {
+                Ok(api_key) => api_key,
+                Err(e) => return missing(component, &e.to_string()),
+            };
+
+            probe(component, ELEVENLABS_USER_URL, ("xi-api-key", api_key), Duration::from_secs(5)).await
+        }
+        TTSProvider::Mock => HealthCheck::new("tts.mock", HealthStatus::Ok, "Mock provider needs no connection"),
+    }
+}
+
+fn missing(component: &str, message: &str) -> HealthCheck {
+    HealthCheck::new(component, HealthStatus::Failed, message)
+}
+
+fn check_local_model(path: &str) -> HealthCheck {
+    if Path::new(path).exists() {
+        HealthCheck::new("inference.local", HealthStatus::Ok, format!("Model found at {}", path))
+    } else {
+        HealthCheck::new("inference.local", HealthStatus::Failed, format!("Model not found at {}", path))
+    }
+}
+
+async fn check_cloud_endpoint(config: &InferenceConfig, endpoint: &str) -> HealthCheck {
+    let component = "inference.cloud";
+    let api_key = match config.resolve_api_key() {
+        Ok(Some(api_key)) => api_key,
+        Ok(None) => return missing(component, "No API key configured"),
+        Err(e) => return missing(component, &e.to_string()),
+    };
+
+    let authorization = ("Authorization", format!("Bearer {}", api_key));
+    probe(component, endpoint, authorization, Duration::from_millis(config.timeout_ms)).await
+}
+
+/// Send an authenticated GET request to a provider and classify the response
+///
+/// The timeout wraps the request rather than the client, since the browser
+/// client used on WebAssembly has no timeout of its own.
+#[cfg(feature = "reqwest")]
+async fn probe(component: &str, url: &str, header: (&str, String), timeout: Duration) -> HealthCheck {
+    let start = crate::timer::Instant::now();
+    let request = reqwest::Client::new().get(url).header(header.0, header.1).send();
+    let check = match crate::timer::timeout(timeout, request).await {
+        Ok(response) => classify_response(component, response),
+        Err(_) => HealthCheck::new(component, HealthStatus::Failed, "Provider timed out"),
+    };
+    check.with_latency(start.elapsed())
+}
+
+/// Providers cannot be reached without an HTTP client
+#[cfg(not(feature = "reqwest"))]
+async fn probe(component: &str, _url: &str, _header: (&str, String), _timeout: Duration) -> HealthCheck {
+    missing(component, "Built without the `reqwest` feature, so the provider cannot be reached")
+}
+
+/// Turn a probe response into a check result
+///
+/// Any HTTP response proves the provider is reachable; only authentication
+/// failures mean the key is bad. Probing endpoints that expect POST commonly
+/// answers 404 or 405, which still counts as reachable.
+#[cfg(feature = "reqwest")]
+fn classify_response(component: &str, response: reqwest::Result<reqwest::Response>) -> HealthCheck {
+    match response {
+        Ok(response) => {
+            let status = response.status();
+            if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+                HealthCheck::new(component, HealthStatus::Failed, format!("API key rejected ({})", status))
+            } else if status.is_server_error() {
+                HealthCheck::new(component, HealthStatus::Degraded, format!("Provider returned {}", status))
+            } else {
+                HealthCheck::new(component, HealthStatus::Ok, "Provider reachable")
+            }
+        }
+        Err(e) if e.is_timeout() => HealthCheck::new(component, HealthStatus::Failed, "Provider timed out"),
+        Err(e) => HealthCheck::new(component, HealthStatus::Failed, format!("Provider unreachable: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_missing_local_model_fails() {
+        let config = InferenceConfig {
+            use_local: true,
+            local_model_path: Some("/nonexistent/model.bin".to_string()),
+            api_endpoint: None,
+            ..Default::default()
+        };
+
+        let checks = check_inference(&config).await;
+        assert_eq!(checks.len(), 1);
+        assert_eq!(checks[0].component, "inference.local");
+        assert_eq!(checks[0].status, HealthStatus::Failed);
+    }
+
+    #[test]
+    fn test_report_status_is_worst_check() {
+        let mut report = HealthReport::default();
+        assert!(report.is_ready());
+
+        report.push(HealthCheck::new("config", HealthStatus::Ok, "ok"));
+        report.push(HealthCheck::new("tts.elevenlabs", HealthStatus::Degraded, "slow"));
+        assert_eq!(report.status(), HealthStatus::Degraded);
+        assert!(report.is_ready());
+
+        report.push(HealthCheck::new("inference.cloud", HealthStatus::Failed, "down"));
+        assert!(!report.is_ready());
+        assert_eq!(report.problems().count(), 2);
+    }
+}
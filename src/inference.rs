@@ -3,26 +3,84 @@
 //! This module provides the inference capabilities for generating NPC responses
 //! using either local models (via llm crate) or cloud API services.
 
-use std::env;
-use std::time::{Duration, Instant};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use tokio::sync::RwLock;
-use tokio::time::timeout;
+use tokio::sync::{broadcast, RwLock};
 
 use crate::agent::AgentContext;
+use crate::attachment::{supports_vision, ImageAttachment, CAPTION_PROMPT};
 use crate::config::InferenceConfig;
+use crate::inference_scheduler::{InferenceScheduler, RequestPriority, REQUEST_PRIORITY_KEY};
 use crate::memory::Memory;
+use crate::mock_provider::MockProvider;
+use crate::model_policy::{ModelPolicy, ModelSwitch};
+use crate::provider_router::{ProviderRoute, ProviderRouter};
+use crate::redaction::Redactor;
+#[cfg(feature = "reqwest")]
+use crate::timer::timeout;
+use crate::timer::Instant;
 use crate::{OxydeError, Result};
 
+#[cfg(feature = "reqwest")]
+lazy_static::lazy_static! {
+    /// HTTP client shared by cloud requests, so connections are kept alive between them
+    static ref HTTP_CLIENT: reqwest::Client = reqwest::Client::new();
+}
+
 /// Inference provider types
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ProviderType {
     /// Local model inference
     Local,
     /// Cloud API inference
     Cloud,
+    /// Scripted responses for hermetic tests
+    Mock,
+}
+
+impl ProviderType {
+    /// Provider name used in responses and scheduling
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Local => "local",
+            Self::Cloud => "cloud",
+            Self::Mock => "mock",
+        }
+    }
+}
+
+/// Per-request overrides of the engine's configuration
+///
+/// Options are passed next to the [`AgentContext`] rather than inside it,
+/// so nothing a game writes to the context can change them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RequestOptions {
+    /// Cloud model used instead of the configured or tiered one
+    pub model: Option<String>,
+
+    /// Sampling temperature used instead of the configured one
+    pub temperature: Option<f32>,
+
+    /// Instruction to reply in the player's language
+    pub locale_instruction: Option<String>,
+
+    /// Length budget of the channel the reply is shown on
+    pub verbosity: Option<crate::verbosity::VerbosityBudget>,
+
+    /// The NPC's emotions as the reply is generated, which adaptive
+    /// sampling follows
+    pub emotions: Option<crate::oxyde_game::emotion::EmotionalState>,
+
+    /// Type name of the behavior that ran last before inference, whose
+    /// sampling profiles replace the defaults
+    pub active_behavior: Option<String>,
+
+    /// Instruction stating the JSON schema of a structured reply
+    pub structured_output: Option<String>,
 }
 
 /// Request to the inference engine
@@ -47,6 +105,80 @@ pub struct InferenceRequest {
     pub temperature: f32,
 }
 
+impl InferenceRequest {
+    /// Chat messages sent to a cloud provider, as `(role, content)` pairs
+    ///
+    /// The system prompt comes first, then the quoted memories if there are
+    /// any, then the player input.
+    pub fn messages(&self) -> Vec<(&'static str, String)> {
+        let mut messages = vec![("system", self.system_prompt.clone())];
+        if !self.memories.is_empty() {
+            let memories_content = self.memories.iter()
+                .map(|m| format!("- {}", m.labeled_content()))
+                .collect::<Vec<_>>()
+                .join("\n");
+            messages.push(("system", format!("Relevant context:\n{}", memories_content)));
+        }
+        messages.push(("user", self.input.clone()));
+        messages
+    }
+
+    /// Render the prompt as readable text, one block per message
+    pub fn render(&self) -> String {
+        self.messages()
+            .iter()
+            .map(|(role, content)| format!("[{}]\n{}", role, content))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    /// Estimate the number of prompt tokens in the request
+    pub fn prompt_tokens(&self) -> usize {
+        self.messages().iter().map(|(_, content)| estimate_tokens(content)).sum()
+    }
+}
+
+/// Estimate the number of tokens a model sees in a piece of text
+///
+/// Uses the common rule of thumb of four characters per token, but never
+/// fewer tokens than words.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4).max(text.split_whitespace().count())
+}
+
+/// Projected cost of a request on one provider
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProviderEstimate {
+    /// Model name sent to the cloud API, or a label for the local model
+    pub model: String,
+
+    /// Whether the provider runs on the local model
+    pub local: bool,
+
+    /// Projected cost in the tier's currency unit; zero without pricing
+    pub cost: f64,
+}
+
+/// Projected token counts and cost of a request, made without calling a provider
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InferenceEstimate {
+    /// Estimated tokens in the system prompt, memories and input
+    pub prompt_tokens: usize,
+
+    /// Tokens the response may use, from the configured `max_tokens`
+    pub completion_tokens: usize,
+
+    /// Cost on each configured provider, in preference order
+    pub providers: Vec<ProviderEstimate>,
+}
+
+impl InferenceEstimate {
+    /// Prompt and completion tokens together
+    pub fn total_tokens(&self) -> usize {
+        self.prompt_tokens + self.completion_tokens
+    }
+}
+
 /// Response from the inference engine
 #[derive(Debug, Clone, Deserialize)]
 pub struct InferenceResponse {
@@ -61,6 +193,20 @@ pub struct InferenceResponse {
     
     /// Tokens generated
     pub tokens: usize,
+
+    /// Model that generated the text, if known
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+/// A request together with the response it produced
+#[derive(Debug, Clone)]
+pub struct InferenceExchange {
+    /// The request sent to the provider
+    pub request: InferenceRequest,
+
+    /// The provider's response
+    pub response: InferenceResponse,
 }
 
 /// Inference engine for generating NPC responses
@@ -74,8 +220,29 @@ pub struct InferenceEngine {
     
     /// Statistics about inference
     stats: RwLock<InferenceStats>,
+
+    /// Latency and spend policy, if model tiers are configured
+    policy: Option<Mutex<ModelPolicy>>,
+
+    /// Notifications of policy-driven model switches
+    switches: broadcast::Sender<ModelSwitch>,
+
+    /// Rule-based provider routing, if routes are configured
+    router: Option<ProviderRouter>,
+
+    /// PII redaction applied to cloud requests
+    redactor: Option<Arc<Redactor>>,
+
+    /// Admission control shared with other engines
+    scheduler: Arc<InferenceScheduler>,
+
+    /// Scripted provider used when the provider is `mock`
+    mock: Arc<MockProvider>,
 }
 
+/// Number of model switch notifications buffered for slow subscribers
+const MODEL_SWITCH_CAPACITY: usize = 16;
+
 /// Statistics about inference operations
 #[derive(Debug, Default, Clone)]
 pub struct InferenceStats {
@@ -128,7 +295,7 @@ impl InferenceProvider for LocalInferenceProvider {
         if !request.memories.is_empty() {
             prompt.push_str("Relevant context:\n");
             for memory in &request.memories {
-                prompt.push_str(&format!("- {}\n", memory.content));
+                prompt.push_str(&format!("- {}\n", memory.labeled_content()));
             }
             prompt.push_str("\n");
         }
@@ -148,6 +315,7 @@ impl InferenceProvider for LocalInferenceProvider {
             time_ms: elapsed.as_millis() as u64,
             provider_name: "local".to_string(),
             tokens: token_count,
+            model: Some(self.model_path.clone()),
         })
     }
 }
@@ -155,7 +323,56 @@ impl InferenceProvider for LocalInferenceProvider {
 /// Cloud API inference provider
 pub struct CloudInferenceProvider {
     api_endpoint: String,
+    #[cfg_attr(not(feature = "reqwest"), allow(dead_code))]
     api_key: String,
+    model: Option<String>,
+}
+
+impl CloudInferenceProvider {
+    /// Ask a vision-capable model to describe an image
+    async fn caption(&self, model: &str, image: &ImageAttachment, timeout_ms: u64) -> Result<String> {
+        let api_request = serde_json::json!({
+            "model": model,
+            "messages": [{
+                "role": "user",
+                "content": [
+                    { "type": "text", "text": CAPTION_PROMPT },
+                    { "type": "image_url", "image_url": { "url": image.data_url() } },
+                ],
+            }],
+            "max_tokens": 120,
+        });
+
+        let api_response = self.post(&api_request, Duration::from_millis(timeout_ms)).await?;
+
+        api_response["choices"][0]["message"]["content"]
+            .as_str()
+            .map(|caption| caption.trim().to_string())
+            .ok_or_else(|| OxydeError::InferenceError("Invalid API response format".to_string()))
+    }
+
+    /// Send a request to the API and parse the JSON response
+    #[cfg(feature = "reqwest")]
+    async fn post(&self, api_request: &serde_json::Value, duration: Duration) -> Result<serde_json::Value> {
+        timeout(duration, async {
+            HTTP_CLIENT.post(&self.api_endpoint)
+                .header("Content-Type", "application/json")
+                .header("Authorization", format!("Bearer {}", self.api_key))
+                .json(api_request)
+                .send()
+                .await
+                .map_err(|e| OxydeError::InferenceError(format!("API request failed: {}", e)))?
+                .json::<serde_json::Value>()
+                .await
+                .map_err(|e| OxydeError::InferenceError(format!("Failed to parse API response: {}", e)))
+        }).await.map_err(|_| OxydeError::InferenceError("API request timed out".to_string()))?
+    }
+
+    /// Without an HTTP client, cloud requests always fail
+    #[cfg(not(feature = "reqwest"))]
+    async fn post(&self, _api_request: &serde_json::Value, _duration: Duration) -> Result<serde_json::Value> {
+        Err(OxydeError::InferenceError("Cloud inference requires the `reqwest` feature".to_string()))
+    }
 }
 
 #[async_trait]
@@ -166,39 +383,16 @@ impl InferenceProvider for CloudInferenceProvider {
         let start_time = Instant::now();
         
         // Prepare the messages for the API
-        let system_message = serde_json::json!({
-            "role": "system",
-            "content": request.system_prompt,
-        });
-        
-        let mut messages = vec![system_message];
-        
-        // Add memories as context if available
-        if !request.memories.is_empty() {
-            let memories_content = request.memories.iter()
-                .map(|m| format!("- {}", m.content))
-                .collect::<Vec<_>>()
-                .join("\n");
-            
-            let context_message = serde_json::json!({
-                "role": "system",
-                "content": format!("Relevant context:\n{}", memories_content),
-            });
-            
-            messages.push(context_message);
-        }
-        
-        // Add user message
-        let user_message = serde_json::json!({
-            "role": "user",
-            "content": request.input,
-        });
-        
-        messages.push(user_message);
+        let messages: Vec<serde_json::Value> = request
+            .messages()
+            .into_iter()
+            .map(|(role, content)| serde_json::json!({ "role": role, "content": content }))
+            .collect();
         
         // Prepare the API request
-        let client = reqwest::Client::new();
-        let model_name = if self.api_endpoint.contains("openai") {
+        let model_name = if let Some(model) = &self.model {
+            model.as_str()
+        } else if self.api_endpoint.contains("openai") {
             "gpt-3.5-turbo"
         } else {
             "llama-2-7b"
@@ -216,18 +410,7 @@ impl InferenceProvider for CloudInferenceProvider {
             .unwrap_or(5000));
         
         // Send the request to the API
-        let api_response = timeout(duration, async {
-            client.post(&self.api_endpoint)
-                .header("Content-Type", "application/json")
-                .header("Authorization", format!("Bearer {}", self.api_key))
-                .json(&api_request)
-                .send()
-                .await
-                .map_err(|e| OxydeError::InferenceError(format!("API request failed: {}", e)))?
-                .json::<serde_json::Value>()
-                .await
-                .map_err(|e| OxydeError::InferenceError(format!("Failed to parse API response: {}", e)))
-        }).await.map_err(|_| OxydeError::InferenceError("API request timed out".to_string()))??;
+        let api_response = self.post(&api_request, duration).await?;
         
         // Extract the response text
         let response_text = api_response["choices"][0]["message"]["content"]
@@ -245,6 +428,7 @@ impl InferenceProvider for CloudInferenceProvider {
             time_ms: elapsed.as_millis() as u64,
             provider_name: "cloud".to_string(),
             tokens: token_count,
+            model: Some(model_name.to_string()),
         })
     }
 }
@@ -260,19 +444,34 @@ impl InferenceEngine {
     ///
     /// A new InferenceEngine instance
     pub fn new(config: &InferenceConfig) -> Self {
-        let provider_type = if config.use_local {
-            ProviderType::Local
-        } else {
-            ProviderType::Cloud
-        };
-        
         Self {
             config: config.clone(),
-            provider_type: RwLock::new(provider_type),
+            provider_type: RwLock::new(config.provider_type()),
             stats: RwLock::new(InferenceStats::default()),
+            policy: ModelPolicy::new(&config.model_policy).map(Mutex::new),
+            switches: broadcast::channel(MODEL_SWITCH_CAPACITY).0,
+            router: ProviderRouter::new(&config.routing),
+            redactor: None,
+            scheduler: InferenceScheduler::global(),
+            mock: Arc::new(MockProvider::new(config.mock.clone())),
         }
     }
+
+    /// Redact PII from requests before they are sent to the cloud provider
+    ///
+    /// Placeholders the provider echoes back are restored in the response.
+    /// Local inference is unaffected.
+    pub fn with_redactor(mut self, redactor: Arc<Redactor>) -> Self {
+        self.redactor = Some(redactor);
+        self
+    }
     
+    /// Schedule requests with a scheduler other than the global one
+    pub fn with_scheduler(mut self, scheduler: Arc<InferenceScheduler>) -> Self {
+        self.scheduler = scheduler;
+        self
+    }
+
     /// Generate a response for the given input
     ///
     /// # Arguments
@@ -284,17 +483,61 @@ impl InferenceEngine {
     /// # Returns
     ///
     /// The generated response text
+    #[tracing::instrument(name = "inference.generate", skip_all)]
     pub async fn generate_response(
         &self,
         input: &str,
         memories: &[Memory],
         context: &AgentContext,
     ) -> Result<String> {
-        let request = self.prepare_request(input, memories, context);
+        self.generate_exchange(input, memories, context, &RequestOptions::default())
+            .await
+            .map(|exchange| exchange.response.text)
+    }
+
+    /// Generate a response and return it with the request that produced it
+    ///
+    /// Behaves like [`InferenceEngine::generate_response`], but keeps the
+    /// full prompt, model, and timing for logging.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - User input to respond to
+    /// * `memories` - Relevant memories for context
+    /// * `context` - Additional context data
+    /// * `options` - Overrides of the configured model and sampling
+    ///
+    /// # Returns
+    ///
+    /// The request and the response it produced
+    pub async fn generate_exchange(
+        &self,
+        input: &str,
+        memories: &[Memory],
+        context: &AgentContext,
+        options: &RequestOptions,
+    ) -> Result<InferenceExchange> {
+        let request = self.prepare_request(input, memories, context, options);
+
+        // Configured routes replace the primary provider and its fallback
+        if let Some(router) = &self.router {
+            let response = self.generate_routed(router, request.clone()).await?;
+            return Ok(InferenceExchange { request, response });
+        }
         
-        // Try primary provider first
-        let provider_type = *self.provider_type.read().await;
-        let response = self.generate_with_provider(provider_type, request.clone()).await;
+        // Try primary provider first, on the policy's current tier if one is configured
+        let (provider_type, mut model) = self.primary_provider().await;
+        if provider_type == ProviderType::Cloud {
+            // A requested model takes precedence over the tier's
+            if let Some(requested) = &options.model {
+                model = Some(requested.clone());
+            }
+        }
+        let response = self.generate_with_provider(provider_type, model, request.clone()).await;
+
+        if let Ok(ref resp) = response {
+            self.record_for_policy(resp);
+        }
         
         // If primary fails and fallback is available, try fallback
         if response.is_err() && self.config.fallback_api.is_some() {
@@ -303,6 +546,7 @@ impl InferenceEngine {
             let fallback_provider = match provider_type {
                 ProviderType::Local => ProviderType::Cloud,
                 ProviderType::Cloud => ProviderType::Local,
+                ProviderType::Mock => ProviderType::Mock,
             };
             
             // Update stats for the failed request
@@ -312,35 +556,445 @@ impl InferenceEngine {
                 stats.failed_requests += 1;
             }
             
-            return self.generate_with_provider(fallback_provider, request).await
-                .map(|response| response.text);
+            let response = self.generate_with_provider(fallback_provider, None, request.clone()).await?;
+            return Ok(InferenceExchange { request, response });
         }
         
-        response.map(|response| response.text)
+        Ok(InferenceExchange { request, response: response? })
     }
     
+    /// Generate a response using only the local provider
+    ///
+    /// Unlike [`InferenceEngine::generate_response`], this never falls back to
+    /// the cloud provider, which makes it suitable for offline and
+    /// deterministic execution.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - User input to respond to
+    /// * `memories` - Relevant memories for context
+    /// * `context` - Additional context data
+    ///
+    /// # Returns
+    ///
+    /// The generated response text
+    pub async fn generate_local(
+        &self,
+        input: &str,
+        memories: &[Memory],
+        context: &AgentContext,
+    ) -> Result<String> {
+        self.generate_local_exchange(input, memories, context, &RequestOptions::default())
+            .await
+            .map(|exchange| exchange.response.text)
+    }
+
+    /// Generate a local response and return it with its request
+    ///
+    /// The local-only counterpart of [`InferenceEngine::generate_exchange`].
+    /// The request bypasses the scheduler, so no timers are involved and it
+    /// can be driven without a Tokio runtime.
+    pub async fn generate_local_exchange(
+        &self,
+        input: &str,
+        memories: &[Memory],
+        context: &AgentContext,
+        options: &RequestOptions,
+    ) -> Result<InferenceExchange> {
+        let request = self.prepare_request(input, memories, context, options);
+        // The mock provider stands in for the local model in hermetic runs
+        let provider_type = match self.config.provider_type() {
+            ProviderType::Mock => ProviderType::Mock,
+            _ => ProviderType::Local,
+        };
+        let cloud = CloudTarget {
+            api_endpoint: None,
+            api_key: None,
+        };
+        let response = self.generate_with_target(provider_type, None, cloud, request.clone(), false).await?;
+        Ok(InferenceExchange { request, response })
+    }
+
+    /// Generate text for instructions other than an in-character reply
+    ///
+    /// Used for background tasks such as reflection. The request uses the
+    /// primary provider without fallback and is scheduled at ambient priority.
+    ///
+    /// # Arguments
+    ///
+    /// * `system_prompt` - Instructions for the model
+    /// * `input` - Text the instructions apply to
+    /// * `max_tokens` - Maximum tokens to generate
+    pub async fn complete(&self, system_prompt: &str, input: &str, max_tokens: usize) -> Result<String> {
+        let mut context = AgentContext::new();
+        context.insert(REQUEST_PRIORITY_KEY.to_string(), serde_json::json!("ambient"));
+        let request = InferenceRequest {
+            input: input.to_string(),
+            system_prompt: system_prompt.to_string(),
+            memories: Vec::new(),
+            context,
+            max_tokens,
+            temperature: self.config.temperature,
+        };
+
+        if let Some(router) = &self.router {
+            return Ok(self.generate_routed(router, request).await?.text);
+        }
+
+        let (provider_type, model) = self.primary_provider().await;
+        let response = self.generate_with_provider(provider_type, model, request).await?;
+        self.record_for_policy(&response);
+        Ok(response.text)
+    }
+
+    /// Names of the routes a request would be tried on, in order
+    ///
+    /// # Returns
+    ///
+    /// The planned routes, or an empty list if routing is not configured
+    pub fn routing_plan(&self, input: &str, context: &AgentContext) -> Vec<String> {
+        self.router
+            .as_ref()
+            .map(|router| router.plan(input, context, &self.config, Instant::now()))
+            .unwrap_or_default()
+            .into_iter()
+            .map(|route| route.name)
+            .collect()
+    }
+
+    /// Generate a response on the planned routes, moving down the chain on failure
+    async fn generate_routed(&self, router: &ProviderRouter, request: InferenceRequest) -> Result<InferenceResponse> {
+        let plan = router.plan(&request.input, &request.context, &self.config, Instant::now());
+        let mut last_error = None;
+        for route in plan {
+            match self.generate_with_route(&route, request.clone()).await {
+                Ok(response) => {
+                    log::debug!("Inference routed to {}", route.name);
+                    router.record_success(&route.name);
+                    return Ok(response);
+                }
+                Err(e) => {
+                    log::warn!("Inference route {} failed, trying the next route: {}", route.name, e);
+                    router.record_failure(&route.name, Instant::now());
+                    let mut stats = self.stats.write().await;
+                    stats.total_requests += 1;
+                    stats.failed_requests += 1;
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| OxydeError::InferenceError("No inference route is available".to_string())))
+    }
+
+    /// Generate a response with a route's provider, endpoint and key
+    async fn generate_with_route(&self, route: &ProviderRoute, request: InferenceRequest) -> Result<InferenceResponse> {
+        let cloud = CloudTarget {
+            api_endpoint: route.endpoint(&self.config).map(str::to_string),
+            api_key: route.api_key(&self.config),
+        };
+        self.generate_with_target(route.provider, route.model.clone(), cloud, request, true).await
+    }
+
+    /// The provider and cloud model to try first
+    async fn primary_provider(&self) -> (ProviderType, Option<String>) {
+        let provider_type = *self.provider_type.read().await;
+        match self.current_tier() {
+            // Model tiers never take over from the mock provider
+            _ if provider_type == ProviderType::Mock => (provider_type, None),
+            Some(tier) if tier.local => (ProviderType::Local, None),
+            Some(tier) => (ProviderType::Cloud, Some(tier.model)),
+            None => (provider_type, None),
+        }
+    }
+
+    /// Open a kept-alive connection to the cloud endpoint ahead of the first request
+    ///
+    /// Any HTTP status counts, since it means the TCP and TLS handshakes
+    /// completed and the connection is pooled for later requests.
+    ///
+    /// # Returns
+    ///
+    /// Whether a connection was opened; `false` if the cloud provider is never
+    /// used or the crate is built without the `reqwest` feature
+    pub async fn prime_connection(&self) -> Result<bool> {
+        let Some(endpoint) = &self.config.api_endpoint else {
+            return Ok(false);
+        };
+        let provider_type = *self.provider_type.read().await;
+        let cloud_tier = self.config.model_policy.tiers.iter().any(|tier| !tier.local);
+        match provider_type {
+            ProviderType::Mock => return Ok(false),
+            ProviderType::Local if !cloud_tier => return Ok(false),
+            _ => {}
+        }
+
+        #[cfg(feature = "reqwest")]
+        {
+            timeout(Duration::from_millis(self.config.timeout_ms), HTTP_CLIENT.head(endpoint).send())
+                .await
+                .map_err(|_| OxydeError::InferenceError(format!("Connecting to {} timed out", endpoint)))?
+                .map_err(|e| OxydeError::InferenceError(format!("Failed to connect to {}: {}", endpoint, e)))?;
+            Ok(true)
+        }
+        #[cfg(not(feature = "reqwest"))]
+        {
+            log::debug!("Not priming {}: built without the `reqwest` feature", endpoint);
+            Ok(false)
+        }
+    }
+
+    /// Get the scripted provider used when the provider is `mock`
+    ///
+    /// Tests use it to queue responses and inspect the requests received.
+    pub fn mock_provider(&self) -> &MockProvider {
+        &self.mock
+    }
+
+    /// Caption an image with the cloud provider
+    ///
+    /// # Arguments
+    ///
+    /// * `image` - Image to describe
+    ///
+    /// # Returns
+    ///
+    /// The caption, or an inference error if the current model is local or
+    /// not vision-capable
+    pub async fn caption_image(&self, image: &ImageAttachment) -> Result<String> {
+        let model = match self.current_tier() {
+            Some(tier) if tier.local => None,
+            Some(tier) => Some(tier.model),
+            None => (*self.provider_type.read().await == ProviderType::Cloud).then(|| self.config.model.clone()),
+        }
+        .ok_or_else(|| OxydeError::InferenceError("Local models cannot caption images".to_string()))?;
+        if !supports_vision(&model) {
+            return Err(OxydeError::InferenceError(format!(
+                "Model {} does not accept images",
+                model
+            )));
+        }
+
+        let api_endpoint = self.config.api_endpoint.clone()
+            .ok_or_else(|| OxydeError::InferenceError("No API endpoint configured".to_string()))?;
+        let api_key = self.config.resolve_api_key()?
+            .ok_or_else(|| OxydeError::InferenceError(
+                "No API key configured. Set OXYDE_API_KEY environment variable or configure in InferenceConfig".to_string()
+            ))?;
+        let provider = CloudInferenceProvider {
+            api_endpoint,
+            api_key,
+            model: Some(model.clone()),
+        };
+        provider.caption(&model, image, self.config.timeout_ms).await
+    }
+
+    /// Estimate the tokens and cost of a response without calling a provider
+    ///
+    /// The prompt is built as for [`InferenceEngine::generate_response`] with
+    /// an empty context, and the response is assumed to use all of
+    /// `max_tokens`, so costs are an upper bound. Providers are the model
+    /// policy tiers when configured, otherwise the primary provider and its
+    /// fallback, which are unpriced.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - User input to respond to
+    /// * `memories` - Relevant memories for context
+    ///
+    /// # Returns
+    ///
+    /// Projected token counts and the cost on each configured provider
+    pub fn estimate(&self, input: &str, memories: &[Memory]) -> InferenceEstimate {
+        let request = self.prepare_request(input, memories, &AgentContext::new(), &RequestOptions::default());
+        let prompt_tokens = request.prompt_tokens();
+        let completion_tokens = request.max_tokens;
+
+        let providers = self
+            .priced_tiers()
+            .into_iter()
+            .map(|tier| ProviderEstimate {
+                cost: (prompt_tokens as f64 * tier.prompt_cost_per_1k_tokens
+                    + completion_tokens as f64 * tier.cost_per_1k_tokens)
+                    / 1000.0,
+                model: tier.model,
+                local: tier.local,
+            })
+            .collect();
+
+        InferenceEstimate {
+            prompt_tokens,
+            completion_tokens,
+            providers,
+        }
+    }
+
+    /// Build the request a response would be generated from, without sending it
+    ///
+    /// Useful for prompt debugging. PII redaction, which is applied only
+    /// when a request is sent to the cloud provider, is not applied.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - User input to respond to
+    /// * `memories` - Relevant memories for context
+    /// * `context` - Additional context data
+    pub fn dry_run(
+        &self,
+        input: &str,
+        memories: &[Memory],
+        context: &AgentContext,
+        options: &RequestOptions,
+    ) -> InferenceRequest {
+        self.prepare_request(input, memories, context, options)
+    }
+
+    /// Providers to price estimates for, in preference order
+    fn priced_tiers(&self) -> Vec<crate::config::ModelTier> {
+        if !self.config.model_policy.tiers.is_empty() {
+            return self.config.model_policy.tiers.clone();
+        }
+
+        let tier = |provider_type: ProviderType| crate::config::ModelTier {
+            model: match provider_type {
+                ProviderType::Cloud => self.config.model.clone(),
+                other => other.name().to_string(),
+            },
+            local: provider_type != ProviderType::Cloud,
+            cost_per_1k_tokens: 0.0,
+            prompt_cost_per_1k_tokens: 0.0,
+        };
+        let primary = self.config.provider_type();
+        let mut tiers = vec![tier(primary)];
+        match primary {
+            ProviderType::Local if self.config.fallback_api.is_some() => tiers.push(tier(ProviderType::Cloud)),
+            ProviderType::Cloud if self.config.fallback_api.is_some() => tiers.push(tier(ProviderType::Local)),
+            _ => {}
+        }
+        tiers
+    }
+
     /// Prepare an inference request
     fn prepare_request(
         &self,
         input: &str,
         memories: &[Memory],
         context: &AgentContext,
+        options: &RequestOptions,
     ) -> InferenceRequest {
         // Create system prompt for the agent
-        let system_prompt = format!(
+        let mut system_prompt = format!(
             "You are an NPC named {} who is a {}. \
             Respond in character with brief, concise answers.",
             context.get("name").and_then(|v| v.as_str()).unwrap_or("Unknown"),
             context.get("role").and_then(|v| v.as_str()).unwrap_or("character"),
         );
+
+        if let Some(activity) = context
+            .get(crate::oxyde_game::schedule::SCHEDULED_ACTIVITY_KEY)
+            .and_then(|v| v.as_str())
+        {
+            system_prompt.push_str(&format!(" Your current activity: {}.", activity));
+        }
+
+        if let Some(time_of_day) = context
+            .get(crate::oxyde_game::schedule::TIME_OF_DAY_KEY)
+            .and_then(|v| v.as_str())
+        {
+            system_prompt.push_str(&format!(" It is {} in the game world.", time_of_day));
+        }
+
+        if let Some(layers) = context
+            .get(crate::prompt::PROMPT_LAYERS_KEY)
+            .and_then(|v| v.as_str())
+        {
+            system_prompt.push_str("\n\n");
+            system_prompt.push_str(layers);
+        }
+
+        if let Some(steering) = context
+            .get(crate::oxyde_game::topic::TOPIC_STEERING_KEY)
+            .and_then(|v| v.as_str())
+        {
+            system_prompt.push_str("\n\n");
+            system_prompt.push_str(steering);
+        }
+
+        if let Some(framing) = context
+            .get(crate::oxyde_game::persuasion::PERSUASION_FRAMING_KEY)
+            .and_then(|v| v.as_str())
+        {
+            system_prompt.push_str("\n\n");
+            system_prompt.push_str(framing);
+        }
+
+        if let Some(framing) = context
+            .get(crate::oxyde_game::reengagement::ABSENCE_FRAMING_KEY)
+            .and_then(|v| v.as_str())
+        {
+            system_prompt.push_str("\n\n");
+            system_prompt.push_str(framing);
+        }
+
+        if let Some(framing) = context
+            .get(crate::oxyde_game::disposition::DISPOSITION_FRAMING_KEY)
+            .and_then(|v| v.as_str())
+        {
+            system_prompt.push_str("\n\n");
+            system_prompt.push_str(framing);
+        }
+
+        if let Some(framing) = context
+            .get(crate::attachment::ATTACHMENT_FRAMING_KEY)
+            .and_then(|v| v.as_str())
+        {
+            system_prompt.push_str("\n\n");
+            system_prompt.push_str(framing);
+        }
+
+        if let Some(instruction) = &options.locale_instruction {
+            system_prompt.push_str("\n\n");
+            system_prompt.push_str(instruction);
+        }
+
+        // Only public memories are quoted; the rest become guidance
+        let (memories, withheld) = crate::prompt::partition_memories(memories);
+        if let Some(withheld) = withheld {
+            system_prompt.push_str("\n\n");
+            system_prompt.push_str(&withheld);
+        }
+
+        let sampling = options.emotions.as_ref().and_then(|emotions| {
+            self.config.sampling.for_emotions(emotions, options.active_behavior.as_deref())
+        });
+        let max_sentence_words = sampling.and_then(|sampling| sampling.max_sentence_words);
+        if let Some(instruction) = crate::verbosity::length_instruction(options.verbosity, max_sentence_words) {
+            system_prompt.push_str("\n\n");
+            system_prompt.push_str(&instruction);
+        }
+
+        if let Some(instruction) = context
+            .get(crate::prompt::SAFETY_INSTRUCTION_KEY)
+            .and_then(|v| v.as_str())
+        {
+            system_prompt.push_str("\n\n");
+            system_prompt.push_str(instruction);
+        }
+
+        if let Some(instruction) = &options.structured_output {
+            system_prompt.push_str("\n\n");
+            system_prompt.push_str(instruction);
+        }
         
         InferenceRequest {
             input: input.to_string(),
             system_prompt,
-            memories: memories.to_vec(),
+            memories,
             context: context.clone(),
             max_tokens: self.config.max_tokens,
-            temperature: self.config.temperature,
+            temperature: options
+                .temperature
+                .or(sampling.map(|sampling| sampling.temperature))
+                .unwrap_or(self.config.temperature),
         }
     }
     
@@ -348,8 +1002,35 @@ impl InferenceEngine {
     async fn generate_with_provider(
         &self,
         provider_type: ProviderType,
+        model: Option<String>,
+        request: InferenceRequest,
+    ) -> Result<InferenceResponse> {
+        let cloud = CloudTarget {
+            api_endpoint: self.config.api_endpoint.clone(),
+            api_key: self.config.resolve_api_key()?,
+        };
+        self.generate_with_target(provider_type, model, cloud, request, true).await
+    }
+
+    /// Generate a response with a provider type, sending cloud requests to a target
+    ///
+    /// Unscheduled requests skip the scheduler and its queue deadline.
+    #[tracing::instrument(name = "inference.provider", skip_all, fields(provider = ?provider_type))]
+    async fn generate_with_target(
+        &self,
+        provider_type: ProviderType,
+        model: Option<String>,
+        cloud: CloudTarget,
         request: InferenceRequest,
+        scheduled: bool,
     ) -> Result<InferenceResponse> {
+        let _permit = if scheduled {
+            let priority = RequestPriority::from_context(&request.context);
+            Some(self.scheduler.acquire(provider_type.name(), priority).await?)
+        } else {
+            None
+        };
+
         let response = match provider_type {
             ProviderType::Local => {
                 if let Some(model_path) = &self.config.local_model_path {
@@ -363,14 +1044,14 @@ impl InferenceEngine {
                     ));
                 }
             },
+            ProviderType::Mock => self.mock.generate(request).await,
             ProviderType::Cloud => {
-                let api_endpoint = self.config.api_endpoint.clone()
+                let api_endpoint = cloud.api_endpoint
                     .ok_or_else(|| OxydeError::InferenceError(
                         "No API endpoint configured".to_string()
                     ))?;
                 
-                let api_key = self.config.api_key.clone()
-                    .or_else(|| env::var("OXYDE_API_KEY").ok())
+                let api_key = cloud.api_key
                     .ok_or_else(|| OxydeError::InferenceError(
                         "No API key configured. Set OXYDE_API_KEY environment variable or configure in InferenceConfig".to_string()
                     ))?;
@@ -378,9 +1059,19 @@ impl InferenceEngine {
                 let cloud_provider = CloudInferenceProvider {
                     api_endpoint,
                     api_key,
+                    model,
                 };
-                
-                cloud_provider.generate(request).await
+
+                match &self.redactor {
+                    Some(redactor) => {
+                        let (request, restore) = redact_request(redactor, request);
+                        cloud_provider.generate(request).await.map(|mut response| {
+                            response.text = restore(&response.text);
+                            response
+                        })
+                    }
+                    None => cloud_provider.generate(request).await,
+                }
             }
         };
         
@@ -415,11 +1106,82 @@ impl InferenceEngine {
     pub async fn get_stats(&self) -> InferenceStats {
         self.stats.read().await.clone()
     }
+
+    /// Get the model tier selected by the latency and spend policy
+    ///
+    /// # Returns
+    ///
+    /// The current tier, or `None` if no model tiers are configured
+    pub fn current_tier(&self) -> Option<crate::config::ModelTier> {
+        self.policy.as_ref().map(|policy| self.lock_policy(policy).current_tier().clone())
+    }
+
+    /// Subscribe to notifications of policy-driven model switches
+    pub fn subscribe_model_switches(&self) -> broadcast::Receiver<ModelSwitch> {
+        self.switches.subscribe()
+    }
+
+    /// Feed a response into the policy and announce any resulting switch
+    fn record_for_policy(&self, response: &InferenceResponse) {
+        let Some(policy) = &self.policy else {
+            return;
+        };
+        let switch = self
+            .lock_policy(policy)
+            .record(response.time_ms, response.tokens, Instant::now());
+        if let Some(switch) = switch {
+            log::info!(
+                "Switched model from {} to {} ({:?}, avg latency {:.0}ms, spend {:.4})",
+                switch.from,
+                switch.to,
+                switch.reason,
+                switch.avg_latency_ms,
+                switch.spend
+            );
+            // No subscribers is not an error
+            let _ = self.switches.send(switch);
+        }
+    }
+
+    fn lock_policy<'a>(&self, policy: &'a Mutex<ModelPolicy>) -> std::sync::MutexGuard<'a, ModelPolicy> {
+        policy.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Endpoint and key cloud requests are sent with
+struct CloudTarget {
+    api_endpoint: Option<String>,
+    api_key: Option<String>,
+}
+
+/// Redact the text of a request sent to a third party
+///
+/// Placeholders already in the request, such as in memories stored redacted,
+/// are reserved first: new values are numbered past them, and they are not
+/// restored in the response since their values are unknown.
+///
+/// # Returns
+///
+/// The redacted request and a function restoring placeholders in the response
+fn redact_request(redactor: &Redactor, mut request: InferenceRequest) -> (InferenceRequest, impl Fn(&str) -> String) {
+    let mut redaction = crate::redaction::Redaction::default();
+    redactor.reserve(&request.input, &mut redaction);
+    redactor.reserve(&request.system_prompt, &mut redaction);
+    for memory in &request.memories {
+        redactor.reserve(&memory.content, &mut redaction);
+    }
+    request.input = redactor.redact_with(&request.input, &mut redaction);
+    request.system_prompt = redactor.redact_with(&request.system_prompt, &mut redaction);
+    for memory in &mut request.memories {
+        memory.content = redactor.redact_with(&memory.content, &mut redaction);
+    }
+    (request, move |text: &str| redaction.restore(text))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::oxyde_game::emotion::EmotionalState;
     
     #[tokio::test]
     async fn test_inference_engine_creation() {
@@ -431,5 +1193,218 @@ mod tests {
         
         let stats = engine.get_stats().await;
         assert_eq!(stats.total_requests, 0);
+        assert!(engine.current_tier().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_policy_selects_local_tier() {
+        let config = InferenceConfig {
+            local_model_path: Some("models/test.bin".to_string()),
+            model_policy: crate::config::ModelPolicyConfig {
+                tiers: vec![crate::config::ModelTier {
+                    model: "local".to_string(),
+                    local: true,
+                    cost_per_1k_tokens: 0.0,
+                    prompt_cost_per_1k_tokens: 0.0,
+                }],
+                max_latency_ms: Some(10_000),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let engine = InferenceEngine::new(&config);
+        let mut switches = engine.subscribe_model_switches();
+
+        let response = engine.generate_response("Hi", &[], &AgentContext::new()).await.unwrap();
+        assert!(response.contains("simulated response"));
+        assert_eq!(engine.current_tier().unwrap().model, "local");
+        assert!(switches.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_routing_falls_back_when_a_route_fails() {
+        let mock_route = |name: &str| crate::provider_router::ProviderRoute {
+            name: name.to_string(),
+            provider: ProviderType::Mock,
+            model: None,
+            api_endpoint: None,
+            api_key_env: None,
+        };
+        let config = InferenceConfig {
+            mock: crate::mock_provider::MockProviderConfig {
+                fail_requests: vec![1],
+                ..Default::default()
+            },
+            routing: crate::provider_router::RoutingConfig {
+                routes: vec![mock_route("fast"), mock_route("steady")],
+                rules: vec![crate::provider_router::RoutingRule {
+                    route: "fast".to_string(),
+                    max_input_chars: Some(10),
+                    ..Default::default()
+                }],
+                fallback: vec!["steady".to_string()],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        config.validate().unwrap();
+        let engine = InferenceEngine::new(&config);
+        assert_eq!(engine.routing_plan("Hi", &AgentContext::new()), ["fast", "steady"]);
+        assert_eq!(engine.routing_plan("Tell me about the war", &AgentContext::new()), ["steady"]);
+
+        // The first mock request fails on the fast route and the fallback answers
+        let response = engine.generate_response("Hi", &[], &AgentContext::new()).await.unwrap();
+        assert_eq!(response, "This is a mock response to: Hi");
+        assert_eq!(engine.mock_provider().requests().len(), 2);
+        assert_eq!(engine.get_stats().await.failed_requests, 1);
+        assert_eq!(engine.routing_plan("Hi", &AgentContext::new()), ["steady"]);
+    }
+
+    #[test]
+    fn test_requests_are_sampled_by_arousal() {
+        let config = InferenceConfig {
+            provider: Some(ProviderType::Mock),
+            sampling: crate::sampling::SamplingConfig {
+                enabled: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let engine = InferenceEngine::new(&config);
+        let mut emotions = EmotionalState::new();
+        emotions.anger = 1.0;
+        emotions.fear = 1.0;
+        emotions.surprise = 1.0;
+        emotions.anticipation = 1.0;
+        let context = AgentContext::new();
+        let mut options = RequestOptions { emotions: Some(emotions), ..Default::default() };
+
+        let request = engine.dry_run("Run!", &[], &context, &options);
+        assert!((request.temperature - 0.9).abs() < 1e-6);
+        assert!(request.system_prompt.ends_with("Speak in short sentences of at most 10 words."));
+
+        // Sentence length is stated with the verbosity budget, in one instruction
+        options.verbosity = Some(crate::verbosity::VerbosityBudget { max_words: Some(30), max_chars: None });
+        let request = engine.dry_run("Run!", &[], &context, &options);
+        assert!(request
+            .system_prompt
+            .ends_with("Keep your reply to at most 30 words, in short sentences of at most 10 words."));
+
+        options.temperature = Some(0.2);
+        assert!((engine.dry_run("Run!", &[], &context, &options).temperature - 0.2).abs() < 1e-6);
+        let defaults = RequestOptions::default();
+        assert_eq!(engine.dry_run("Run!", &[], &context, &defaults).temperature, config.temperature);
+    }
+
+    #[test]
+    fn test_redact_request_shares_placeholders() {
+        let redactor = Redactor::new(&crate::redaction::RedactionConfig {
+            enabled: true,
+            names: vec!["Alex".to_string()],
+            ..Default::default()
+        });
+        let request = InferenceRequest {
+            input: "Tell Alex to email me at me@example.com".to_string(),
+            system_prompt: "You are an NPC.".to_string(),
+            memories: vec![Memory::new(crate::memory::MemoryCategory::Episodic, "Alex owes me money", 0.5, None)],
+            context: AgentContext::new(),
+            max_tokens: 16,
+            temperature: 0.7,
+        };
+
+        let (request, restore) = redact_request(&redactor, request);
+        assert_eq!(request.input, "Tell [NAME_1] to email me at [EMAIL_1]");
+        assert_eq!(request.memories[0].content, "[NAME_1] owes me money");
+        assert_eq!(restore("I'll tell [NAME_1]."), "I'll tell Alex.");
+    }
+
+    #[test]
+    fn test_redact_request_leaves_memory_placeholders_unrestored() {
+        let redactor = Redactor::new(&crate::redaction::RedactionConfig {
+            enabled: true,
+            names: vec!["Alex".to_string()],
+            ..Default::default()
+        });
+        // Stored redacted in an earlier request, where [NAME_1] was someone else
+        let stored = Memory::new(crate::memory::MemoryCategory::Episodic, "[NAME_1] owes me money", 0.5, None);
+        let request = InferenceRequest {
+            input: "Tell Alex I said hi".to_string(),
+            system_prompt: "You are an NPC.".to_string(),
+            memories: vec![stored],
+            context: AgentContext::new(),
+            max_tokens: 16,
+            temperature: 0.7,
+        };
+
+        let (request, restore) = redact_request(&redactor, request);
+        assert_eq!(request.input, "Tell [NAME_2] I said hi");
+        assert_eq!(request.memories[0].content, "[NAME_1] owes me money");
+        assert_eq!(restore("[NAME_2], [NAME_1] still owes me."), "Alex, [NAME_1] still owes me.");
+    }
+
+    #[test]
+    fn test_estimate_prices_configured_tiers() {
+        let config = InferenceConfig {
+            max_tokens: 100,
+            model_policy: crate::config::ModelPolicyConfig {
+                tiers: vec![
+                    crate::config::ModelTier {
+                        model: "large".to_string(),
+                        local: false,
+                        cost_per_1k_tokens: 10.0,
+                        prompt_cost_per_1k_tokens: 2.0,
+                    },
+                    crate::config::ModelTier {
+                        model: "local".to_string(),
+                        local: true,
+                        cost_per_1k_tokens: 0.0,
+                        prompt_cost_per_1k_tokens: 0.0,
+                    },
+                ],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let engine = InferenceEngine::new(&config);
+        let memories = [Memory::new(crate::memory::MemoryCategory::Episodic, "The bridge washed out", 0.5, None)];
+
+        let estimate = engine.estimate("Which road is safe?", &memories);
+        let request = engine.dry_run("Which road is safe?", &memories, &AgentContext::new(), &RequestOptions::default());
+        assert_eq!(estimate.prompt_tokens, request.prompt_tokens());
+        assert_eq!(estimate.total_tokens(), estimate.prompt_tokens + 100);
+        assert_eq!(estimate.providers.len(), 2);
+        let expected = (estimate.prompt_tokens as f64 * 2.0 + 100.0 * 10.0) / 1000.0;
+        assert!((estimate.providers[0].cost - expected).abs() < 1e-9);
+        assert_eq!(estimate.providers[1].cost, 0.0);
+
+        let rendered = request.render();
+        assert!(rendered.starts_with("[system]\nYou are an NPC"));
+        assert!(rendered.contains("Relevant context:\n- The bridge washed out"));
+        assert!(rendered.ends_with("[user]\nWhich road is safe?"));
+        assert_eq!(estimate_tokens("abcdefgh a"), 3);
+    }
+
+    #[test]
+    fn test_local_exchange_runs_without_a_runtime_or_scheduler_permit() {
+        let config = InferenceConfig {
+            local_model_path: Some("models/test.bin".to_string()),
+            ..Default::default()
+        };
+        // No local requests are ever admitted by this scheduler
+        let scheduler = InferenceScheduler::new(crate::inference_scheduler::InferenceSchedulerConfig {
+            enabled: true,
+            max_concurrent: std::collections::HashMap::from([("local".to_string(), 0)]),
+            ..Default::default()
+        });
+        let engine = InferenceEngine::new(&config).with_scheduler(Arc::new(scheduler));
+
+        let exchange = futures::executor::block_on(engine.generate_local_exchange(
+            "Hello",
+            &[],
+            &AgentContext::new(),
+            &RequestOptions::default(),
+        ))
+        .unwrap();
+        assert_eq!(exchange.response.text, "This is a simulated response to: Hello");
     }
 }
@@ -0,0 +1,479 @@
+//! Global inference scheduling
+//!
+//! When dozens of agents generate responses at once, provider rate limits
+//! turn into cascading failures. The [`InferenceScheduler`] is shared by every
+//! inference engine in the process and admits requests per provider:
+//!
+//! - at most `max_concurrent` requests run against a provider at a time
+//! - waiting requests are served by priority class, player-facing before
+//!   ambient, then in arrival order
+//! - each request waits no longer than its class deadline
+//! - under load, new ambient requests are shed once the queue reaches
+//!   `shed_ambient_depth`, and a full queue makes room for player-facing
+//!   requests by shedding the newest ambient one
+//!
+//! Scheduling is off until configured:
+//!
+//! ```rust,no_run
+//! use oxyde::inference_scheduler::{InferenceScheduler, InferenceSchedulerConfig};
+//!
+//! InferenceScheduler::global().configure(InferenceSchedulerConfig {
+//!     enabled: true,
+//!     ..Default::default()
+//! });
+//! ```
+//!
+//! Agents mark background chatter as ambient by setting the
+//! `request_priority` context key to `"ambient"`.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+
+use crate::agent::AgentContext;
+use crate::timer;
+use crate::{OxydeError, Result};
+
+/// Context key holding the priority class of an agent's requests
+pub const REQUEST_PRIORITY_KEY: &str = "request_priority";
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_SCHEDULER: Arc<InferenceScheduler> =
+        Arc::new(InferenceScheduler::new(InferenceSchedulerConfig::default()));
+}
+
+/// Priority class of an inference request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestPriority {
+    /// Background chatter nobody is waiting on
+    Ambient,
+    /// A reply a player is waiting for
+    PlayerFacing,
+}
+
+impl RequestPriority {
+    /// Read the priority class from an agent context; player-facing by default
+    pub fn from_context(context: &AgentContext) -> Self {
+        match context.get(REQUEST_PRIORITY_KEY).and_then(|v| v.as_str()) {
+            Some("ambient") => Self::Ambient,
+            _ => Self::PlayerFacing,
+        }
+    }
+}
+
+/// Configuration for the inference scheduler
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InferenceSchedulerConfig {
+    /// Whether requests are scheduled; when off they run immediately
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Concurrent requests per provider (`cloud`, `local`)
+    #[serde(default = "default_max_concurrent")]
+    pub max_concurrent: HashMap<String, usize>,
+
+    /// Concurrent requests for providers not listed in `max_concurrent`
+    #[serde(default = "default_max_concurrent_default")]
+    pub default_max_concurrent: usize,
+
+    /// Requests that may wait per provider before new ones are shed
+    #[serde(default = "default_max_queue")]
+    pub max_queue: usize,
+
+    /// Queue depth at which new ambient requests are shed
+    #[serde(default = "default_shed_ambient_depth")]
+    pub shed_ambient_depth: usize,
+
+    /// How long a player-facing request may wait, in milliseconds
+    #[serde(default = "default_player_deadline_ms")]
+    pub player_deadline_ms: u64,
+
+    /// How long an ambient request may wait, in milliseconds
+    #[serde(default = "default_ambient_deadline_ms")]
+    pub ambient_deadline_ms: u64,
+}
+
+fn default_max_concurrent() -> HashMap<String, usize> {
+    HashMap::from([("cloud".to_string(), 8), ("local".to_string(), 1)])
+}
+
+fn default_max_concurrent_default() -> usize {
+    4
+}
+
+fn default_max_queue() -> usize {
+    64
+}
+
+fn default_shed_ambient_depth() -> usize {
+    16
+}
+
+fn default_player_deadline_ms() -> u64 {
+    10_000
+}
+
+fn default_ambient_deadline_ms() -> u64 {
+    3_000
+}
+
+impl Default for InferenceSchedulerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_concurrent: default_max_concurrent(),
+            default_max_concurrent: default_max_concurrent_default(),
+            max_queue: default_max_queue(),
+            shed_ambient_depth: default_shed_ambient_depth(),
+            player_deadline_ms: default_player_deadline_ms(),
+            ambient_deadline_ms: default_ambient_deadline_ms(),
+        }
+    }
+}
+
+impl InferenceSchedulerConfig {
+    /// Validate the scheduler configuration
+    pub fn validate(&self) -> Result<()> {
+        if self.default_max_concurrent == 0 || self.max_concurrent.values().any(|cap| *cap == 0) {
+            return Err(OxydeError::ConfigurationError(
+                "Scheduler concurrency caps must be greater than 0".to_string(),
+            ));
+        }
+        if self.shed_ambient_depth > self.max_queue {
+            return Err(OxydeError::ConfigurationError(
+                "Scheduler shed_ambient_depth cannot exceed max_queue".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn cap(&self, provider: &str) -> usize {
+        self.max_concurrent.get(provider).copied().unwrap_or(self.default_max_concurrent)
+    }
+
+    fn deadline(&self, priority: RequestPriority) -> Duration {
+        Duration::from_millis(match priority {
+            RequestPriority::PlayerFacing => self.player_deadline_ms,
+            RequestPriority::Ambient => self.ambient_deadline_ms,
+        })
+    }
+}
+
+/// Counters for one provider's requests
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchedulerStats {
+    /// Requests currently running
+    pub active: usize,
+    /// Requests currently waiting
+    pub queued: usize,
+    /// Requests admitted so far
+    pub admitted: u64,
+    /// Requests shed under load
+    pub shed: u64,
+    /// Requests that gave up after their deadline
+    pub expired: u64,
+}
+
+/// A request waiting for a slot
+struct Waiter {
+    priority: RequestPriority,
+    sequence: u64,
+    grant: oneshot::Sender<SchedulerPermit>,
+}
+
+/// Scheduling state for one provider
+#[derive(Default)]
+struct ProviderQueue {
+    stats: SchedulerStats,
+    waiters: Vec<Waiter>,
+}
+
+impl ProviderQueue {
+    /// Index of the next waiter to serve
+    fn next(&self) -> Option<usize> {
+        (0..self.waiters.len()).max_by_key(|&i| (self.waiters[i].priority, std::cmp::Reverse(self.waiters[i].sequence)))
+    }
+
+    /// Index of the newest ambient waiter, the first to shed
+    fn sheddable(&self) -> Option<usize> {
+        (0..self.waiters.len())
+            .filter(|&i| self.waiters[i].priority == RequestPriority::Ambient)
+            .max_by_key(|&i| self.waiters[i].sequence)
+    }
+}
+
+struct SchedulerState {
+    config: InferenceSchedulerConfig,
+    providers: HashMap<String, ProviderQueue>,
+    sequence: u64,
+}
+
+/// Admits inference requests per provider by priority, shared across agents
+pub struct InferenceScheduler {
+    state: Arc<Mutex<SchedulerState>>,
+}
+
+impl std::fmt::Debug for InferenceScheduler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InferenceScheduler")
+            .field("config", &self.lock().config)
+            .finish()
+    }
+}
+
+/// Right to run one request against a provider; frees the slot when dropped
+#[must_use = "the slot is released as soon as the permit is dropped"]
+pub struct SchedulerPermit {
+    state: Option<Arc<Mutex<SchedulerState>>>,
+    provider: String,
+}
+
+impl std::fmt::Debug for SchedulerPermit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SchedulerPermit").field("provider", &self.provider).finish()
+    }
+}
+
+impl Drop for SchedulerPermit {
+    fn drop(&mut self) {
+        let Some(state) = self.state.take() else {
+            return;
+        };
+        // Hand the slot straight to the next waiter, outside the lock so a
+        // waiter that already gave up can release it again
+        let handoff = {
+            let mut guard = state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            let queue = guard.providers.entry(self.provider.clone()).or_default();
+            match queue.next() {
+                Some(index) => {
+                    let waiter = queue.waiters.remove(index);
+                    queue.stats.queued = queue.waiters.len();
+                    queue.stats.admitted += 1;
+                    Some(waiter.grant)
+                }
+                None => {
+                    queue.stats.active -= 1;
+                    None
+                }
+            }
+        };
+        if let Some(grant) = handoff {
+            let _ = grant.send(SchedulerPermit {
+                state: Some(state),
+                provider: std::mem::take(&mut self.provider),
+            });
+        }
+    }
+}
+
+impl InferenceScheduler {
+    /// Create a scheduler
+    pub fn new(config: InferenceSchedulerConfig) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(SchedulerState {
+                config,
+                providers: HashMap::new(),
+                sequence: 0,
+            })),
+        }
+    }
+
+    /// The scheduler shared by every inference engine in the process
+    pub fn global() -> Arc<Self> {
+        GLOBAL_SCHEDULER.clone()
+    }
+
+    /// Replace the configuration
+    ///
+    /// Running requests keep their slots; new caps apply as they finish.
+    pub fn configure(&self, config: InferenceSchedulerConfig) {
+        self.lock().config = config;
+    }
+
+    /// Get the configuration
+    pub fn config(&self) -> InferenceSchedulerConfig {
+        self.lock().config.clone()
+    }
+
+    /// Get the counters for a provider
+    pub fn stats(&self, provider: &str) -> SchedulerStats {
+        self.lock().providers.get(provider).map(|queue| queue.stats).unwrap_or_default()
+    }
+
+    /// Wait for a slot to run a request against a provider
+    ///
+    /// # Arguments
+    ///
+    /// * `provider` - Provider name, such as `cloud` or `local`
+    /// * `priority` - Priority class of the request
+    ///
+    /// # Returns
+    ///
+    /// A permit holding the slot, [`OxydeError::Busy`] if the request was
+    /// shed, or a request error if its deadline passed while queued
+    pub async fn acquire(&self, provider: &str, priority: RequestPriority) -> Result<SchedulerPermit> {
+        let (receiver, deadline) = {
+            let mut state = self.lock();
+            let config = state.config.clone();
+            if !config.enabled {
+                return Ok(SchedulerPermit {
+                    state: None,
+                    provider: provider.to_string(),
+                });
+            }
+            state.sequence += 1;
+            let sequence = state.sequence;
+            let queue = state.providers.entry(provider.to_string()).or_default();
+
+            if queue.stats.active < config.cap(provider) && queue.waiters.is_empty() {
+                queue.stats.active += 1;
+                queue.stats.admitted += 1;
+                return Ok(SchedulerPermit {
+                    state: Some(self.state.clone()),
+                    provider: provider.to_string(),
+                });
+            }
+
+            let depth = queue.waiters.len();
+            if priority == RequestPriority::Ambient && depth >= config.shed_ambient_depth {
+                queue.stats.shed += 1;
+                return Err(shed_error(provider));
+            }
+            if depth >= config.max_queue {
+                // Dropping the shed waiter's sender wakes it with an error
+                match queue.sheddable().filter(|_| priority == RequestPriority::PlayerFacing) {
+                    Some(index) => {
+                        queue.waiters.remove(index);
+                    }
+                    None => {
+                        queue.stats.shed += 1;
+                        return Err(shed_error(provider));
+                    }
+                }
+                queue.stats.shed += 1;
+            }
+
+            let (grant, receiver) = oneshot::channel();
+            queue.waiters.push(Waiter { priority, sequence, grant });
+            queue.stats.queued = queue.waiters.len();
+            (receiver, config.deadline(priority))
+        };
+
+        match timer::timeout(deadline, receiver).await {
+            Ok(Ok(permit)) => Ok(permit),
+            Ok(Err(_)) => Err(shed_error(provider)),
+            Err(_) => {
+                // A permit granted after the deadline is dropped with the
+                // receiver and passed on to the next waiter
+                let mut state = self.lock();
+                let queue = state.providers.entry(provider.to_string()).or_default();
+                queue.waiters.retain(|waiter| !waiter.grant.is_closed());
+                queue.stats.queued = queue.waiters.len();
+                queue.stats.expired += 1;
+                Err(OxydeError::RequestError(format!(
+                    "Inference request for {} waited longer than {} ms",
+                    provider,
+                    deadline.as_millis()
+                )))
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, SchedulerState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn shed_error(provider: &str) -> OxydeError {
+    OxydeError::Busy(format!("Inference request for {} was shed under load", provider))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scheduler() -> InferenceScheduler {
+        InferenceScheduler::new(InferenceSchedulerConfig {
+            enabled: true,
+            max_concurrent: HashMap::from([("cloud".to_string(), 1)]),
+            max_queue: 2,
+            shed_ambient_depth: 2,
+            ambient_deadline_ms: 50,
+            ..Default::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_serves_player_facing_before_ambient() {
+        let scheduler = Arc::new(scheduler());
+        let running = scheduler.acquire("cloud", RequestPriority::PlayerFacing).await.unwrap();
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let spawn = |priority: RequestPriority, label: &'static str| {
+            let (scheduler, order) = (scheduler.clone(), order.clone());
+            tokio::spawn(async move {
+                let _permit = scheduler.acquire("cloud", priority).await.unwrap();
+                order.lock().unwrap().push(label);
+            })
+        };
+        let ambient = spawn(RequestPriority::Ambient, "ambient");
+        tokio::task::yield_now().await;
+        let player = spawn(RequestPriority::PlayerFacing, "player");
+        while scheduler.stats("cloud").queued < 2 {
+            tokio::task::yield_now().await;
+        }
+
+        drop(running);
+        player.await.unwrap();
+        ambient.await.unwrap();
+        assert_eq!(*order.lock().unwrap(), vec!["player", "ambient"]);
+        let stats = scheduler.stats("cloud");
+        assert_eq!((stats.active, stats.queued, stats.admitted), (0, 0, 3));
+    }
+
+    #[tokio::test]
+    async fn test_sheds_ambient_and_expires_waiters() {
+        let scheduler = Arc::new(scheduler());
+        let _running = scheduler.acquire("cloud", RequestPriority::PlayerFacing).await.unwrap();
+
+        // Ambient requests wait out their deadline
+        let expired = scheduler.acquire("cloud", RequestPriority::Ambient).await.unwrap_err();
+        assert!(matches!(expired, OxydeError::RequestError(_)));
+
+        let queued = {
+            let scheduler = scheduler.clone();
+            tokio::spawn(async move { scheduler.acquire("cloud", RequestPriority::Ambient).await })
+        };
+        let first_player = {
+            let scheduler = scheduler.clone();
+            tokio::spawn(async move { scheduler.acquire("cloud", RequestPriority::PlayerFacing).await })
+        };
+        while scheduler.stats("cloud").queued < 2 {
+            tokio::task::yield_now().await;
+        }
+
+        // The queue is at the ambient shedding depth
+        let shed = scheduler.acquire("cloud", RequestPriority::Ambient).await.unwrap_err();
+        assert!(matches!(shed, OxydeError::Busy(_)));
+
+        // A full queue sheds its newest ambient waiter for a player-facing one
+        let second_player = {
+            let scheduler = scheduler.clone();
+            tokio::spawn(async move { scheduler.acquire("cloud", RequestPriority::PlayerFacing).await })
+        };
+        assert!(matches!(queued.await.unwrap(), Err(OxydeError::Busy(_))));
+        let stats = scheduler.stats("cloud");
+        assert_eq!((stats.queued, stats.shed, stats.expired), (2, 2, 1));
+
+        first_player.abort();
+        second_player.abort();
+        assert!(InferenceScheduler::new(InferenceSchedulerConfig::default())
+            .acquire("cloud", RequestPriority::Ambient)
+            .await
+            .is_ok());
+    }
+}
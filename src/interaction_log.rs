@@ -0,0 +1,389 @@
+//! Interaction logging
+//!
+//! For prompt tuning, an [`InteractionLogger`] records every inference
+//! exchange as one JSON line: the full system prompt, the memories it
+//! quoted, the model, token count and latency, the raw and final responses,
+//! and the agent's emotions before and after. Logging is opt-in:
+//!
+//! ```yaml
+//! interaction_log:
+//!   enabled: true
+//!   path: logs/blacksmith.jsonl
+//!   max_file_bytes: 10485760
+//!   max_files: 5
+//! ```
+//!
+//! When the active file would grow past `max_file_bytes` it is renamed to
+//! `<path>.1`, older files shift up, and files beyond `max_files` are
+//! deleted. Records pass through redaction hooks before they are written;
+//! agents with PII redaction configured add a hook that applies it.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::oxyde_game::emotion::EmotionalState;
+use crate::redaction::Redactor;
+use crate::{OxydeError, Result};
+
+/// Configuration for interaction logging
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InteractionLogConfig {
+    /// Whether agents start with logging enabled; it can be toggled at runtime
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Path of the active log file
+    #[serde(default = "default_path")]
+    pub path: PathBuf,
+
+    /// Size in bytes at which the active file is rotated
+    #[serde(default = "default_max_file_bytes")]
+    pub max_file_bytes: u64,
+
+    /// Number of files kept, including the active file
+    #[serde(default = "default_max_files")]
+    pub max_files: usize,
+
+    /// Whether to apply the agent's PII redaction to records
+    #[serde(default = "default_redact")]
+    pub redact: bool,
+}
+
+fn default_path() -> PathBuf {
+    PathBuf::from("logs/interactions.jsonl")
+}
+
+fn default_max_file_bytes() -> u64 {
+    10 * 1024 * 1024
+}
+
+fn default_max_files() -> usize {
+    5
+}
+
+fn default_redact() -> bool {
+    true
+}
+
+impl Default for InteractionLogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: default_path(),
+            max_file_bytes: default_max_file_bytes(),
+            max_files: default_max_files(),
+            redact: default_redact(),
+        }
+    }
+}
+
+impl InteractionLogConfig {
+    /// Validate the interaction log configuration
+    pub fn validate(&self) -> Result<()> {
+        if self.path.as_os_str().is_empty() {
+            return Err(OxydeError::ConfigurationError(
+                "Interaction log path cannot be empty".to_string(),
+            ));
+        }
+        if self.max_file_bytes == 0 {
+            return Err(OxydeError::ConfigurationError(
+                "Interaction log max_file_bytes must be greater than 0".to_string(),
+            ));
+        }
+        if self.max_files == 0 {
+            return Err(OxydeError::ConfigurationError(
+                "Interaction log max_files must be greater than 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// One logged prompt/response exchange
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InteractionRecord {
+    /// Unix time in seconds when the exchange completed
+    pub timestamp: u64,
+
+    /// ID of the agent
+    pub agent_id: String,
+
+    /// Name of the agent
+    pub agent_name: String,
+
+    /// Player input
+    pub input: String,
+
+    /// Intent label of the input, or its intent type if unlabeled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub intent: Option<String>,
+
+    /// System prompt sent to the model
+    pub system_prompt: String,
+
+    /// Content of the memories quoted in the prompt
+    pub memories: Vec<String>,
+
+    /// Provider that generated the response
+    pub provider: String,
+
+    /// Model that generated the response, if known
+    pub model: Option<String>,
+
+    /// Experiment the exchange was part of, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub experiment: Option<String>,
+
+    /// Experiment variant the exchange was assigned to, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variant: Option<String>,
+
+    /// Tokens generated
+    pub tokens: usize,
+
+    /// Inference latency in milliseconds
+    pub latency_ms: u64,
+
+    /// Response as generated by the model
+    pub response: String,
+
+    /// Response after moderation, post-processing and capability checks
+    pub final_response: String,
+
+    /// Emotional state when the input arrived
+    pub emotions_before: EmotionalState,
+
+    /// Emotional state after the response
+    pub emotions_after: EmotionalState,
+}
+
+impl InteractionRecord {
+    /// Current Unix time in seconds
+    pub fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or(0)
+    }
+}
+
+/// Read the records of a log file
+///
+/// # Returns
+///
+/// The records in the order they were written, or an error naming the first
+/// line that is not a record
+pub fn read_log(path: &Path) -> Result<Vec<InteractionRecord>> {
+    fs::read_to_string(path)?
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line).map_err(|e| {
+                OxydeError::ConfigurationError(format!("{} line {}: {}", path.display(), index + 1, e))
+            })
+        })
+        .collect()
+}
+
+/// Hook that scrubs a record before it is written
+pub type RedactionHook = Arc<dyn Fn(&mut InteractionRecord) + Send + Sync>;
+
+/// The open active file and its size
+#[derive(Debug)]
+struct ActiveFile {
+    file: File,
+    size: u64,
+}
+
+/// Writes interaction records to rotating JSONL files
+pub struct InteractionLogger {
+    config: InteractionLogConfig,
+    hooks: Vec<RedactionHook>,
+    active: Mutex<Option<ActiveFile>>,
+}
+
+impl std::fmt::Debug for InteractionLogger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InteractionLogger")
+            .field("path", &self.config.path)
+            .field("hooks", &self.hooks.len())
+            .finish()
+    }
+}
+
+impl InteractionLogger {
+    /// Create a logger; the file is opened on the first write
+    pub fn new(config: InteractionLogConfig) -> Self {
+        Self {
+            config,
+            hooks: Vec::new(),
+            active: Mutex::new(None),
+        }
+    }
+
+    /// Add a hook that scrubs records before they are written
+    ///
+    /// Hooks run in the order they were added.
+    pub fn with_redaction_hook<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&mut InteractionRecord) + Send + Sync + 'static,
+    {
+        self.hooks.push(Arc::new(hook));
+        self
+    }
+
+    /// Add a hook that applies PII redaction to every text field
+    pub fn with_redactor(self, redactor: Arc<Redactor>) -> Self {
+        self.with_redaction_hook(move |record| {
+            let redact = |text: &mut String| *text = redactor.redact(text).text().to_string();
+            redact(&mut record.input);
+            redact(&mut record.system_prompt);
+            record.memories.iter_mut().for_each(redact);
+            redact(&mut record.response);
+            redact(&mut record.final_response);
+        })
+    }
+
+    /// Path of the active log file
+    pub fn path(&self) -> &Path {
+        &self.config.path
+    }
+
+    /// Redact and append a record, rotating files as needed
+    pub fn log(&self, mut record: InteractionRecord) -> Result<()> {
+        for hook in &self.hooks {
+            hook(&mut record);
+        }
+        let mut line = serde_json::to_vec(&record)?;
+        line.push(b'\n');
+
+        let mut active = self.active.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(current) = active.as_ref() {
+            if current.size > 0 && current.size + line.len() as u64 > self.config.max_file_bytes {
+                *active = None;
+                self.rotate()?;
+            }
+        }
+        if active.is_none() {
+            *active = Some(self.open()?);
+            let opened = active.as_ref().map_or(0, |current| current.size);
+            if opened > 0 && opened + line.len() as u64 > self.config.max_file_bytes {
+                *active = None;
+                self.rotate()?;
+                *active = Some(self.open()?);
+            }
+        }
+
+        let current = active.as_mut().expect("active log file was just opened");
+        current.file.write_all(&line)?;
+        current.size += line.len() as u64;
+        Ok(())
+    }
+
+    /// Open the active file for appending
+    fn open(&self) -> Result<ActiveFile> {
+        if let Some(parent) = self.config.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&self.config.path)?;
+        let size = file.metadata()?.len();
+        Ok(ActiveFile { file, size })
+    }
+
+    /// Path of the rotated file with the given index
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut path = self.config.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+        PathBuf::from(path)
+    }
+
+    /// Shift rotated files up one index and move the active file to `.1`
+    fn rotate(&self) -> Result<()> {
+        let kept = self.config.max_files.saturating_sub(1);
+        if kept == 0 {
+            fs::remove_file(&self.config.path)?;
+            return Ok(());
+        }
+
+        let oldest = self.rotated_path(kept);
+        if oldest.exists() {
+            fs::remove_file(&oldest)?;
+        }
+        for index in (1..kept).rev() {
+            let from = self.rotated_path(index);
+            if from.exists() {
+                fs::rename(&from, self.rotated_path(index + 1))?;
+            }
+        }
+        fs::rename(&self.config.path, self.rotated_path(1))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(input: &str) -> InteractionRecord {
+        InteractionRecord {
+            timestamp: InteractionRecord::now(),
+            agent_id: "agent-1".to_string(),
+            agent_name: "Blacksmith".to_string(),
+            input: input.to_string(),
+            intent: None,
+            system_prompt: "You are an NPC named Blacksmith.".to_string(),
+            memories: vec!["The forge is hot".to_string()],
+            provider: "local".to_string(),
+            model: None,
+            experiment: None,
+            variant: None,
+            tokens: 6,
+            latency_ms: 3,
+            response: "Welcome to the forge.".to_string(),
+            final_response: "Welcome to the forge.".to_string(),
+            emotions_before: EmotionalState::new(),
+            emotions_after: EmotionalState::new(),
+        }
+    }
+
+    #[test]
+    fn test_rotates_files_and_applies_hooks() {
+        let dir = std::env::temp_dir().join(format!("oxyde-interactions-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("log.jsonl");
+        let line_len = serde_json::to_vec(&record("[GREETING] 0")).unwrap().len() as u64 + 1;
+        let logger = InteractionLogger::new(InteractionLogConfig {
+            enabled: true,
+            path: path.clone(),
+            max_file_bytes: line_len * 2,
+            max_files: 3,
+            redact: false,
+        })
+        .with_redaction_hook(|record| record.input = record.input.replace("hello", "[GREETING]"));
+
+        for i in 0..7 {
+            logger.log(record(&format!("hello {}", i))).unwrap();
+        }
+
+        let read = |path: &Path| -> Vec<InteractionRecord> {
+            fs::read_to_string(path)
+                .unwrap()
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect()
+        };
+        let active = read(&path);
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].input, "[GREETING] 6");
+        assert_eq!(read(&dir.join("log.jsonl.1")).len(), 2);
+        assert_eq!(read(&dir.join("log.jsonl.2"))[0].input, "[GREETING] 2");
+        assert!(!dir.join("log.jsonl.3").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
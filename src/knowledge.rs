@@ -0,0 +1,603 @@
+//! Ingesting lore files into an agent's knowledge
+//!
+//! Writers keep world lore as Markdown or plain text files in a content
+//! pack. [`ingest_dir`] reads every file under a directory, splits it into
+//! chunks small enough to recall into a prompt, and reports what happened to
+//! each file, so broken or oversized files are not lost silently:
+//!
+//! ```text
+//! lore/
+//!   history.md        one chunk per paragraph, under its heading
+//!   factions/guild.md
+//!   rumors.txt        one chunk per paragraph
+//! ```
+//!
+//! Chunks reach an agent one of two ways, both through [`knowledge_memory`]:
+//! `Agent::ingest_knowledge` stores them as semantic memories right away,
+//! and the CLI `kb ingest` adds them to a configuration's knowledge entries,
+//! which agents store the same way when they start. `kb ingest --report`
+//! checks a pack without storing anything.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::inference::estimate_tokens;
+use crate::memory::{Memory, MemoryCategory};
+use crate::{OxydeError, Result};
+
+/// Options for splitting lore files into chunks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IngestOptions {
+    /// Largest file read, in bytes
+    #[serde(default = "default_max_file_bytes")]
+    pub max_file_bytes: u64,
+
+    /// Estimated tokens a chunk may hold; longer paragraphs are split
+    #[serde(default = "default_max_chunk_tokens")]
+    pub max_chunk_tokens: usize,
+
+    /// Extensions of the files read; `md` and `markdown` files are parsed
+    /// as Markdown
+    #[serde(default = "default_extensions")]
+    pub extensions: Vec<String>,
+}
+
+fn default_max_file_bytes() -> u64 {
+    1024 * 1024
+}
+
+fn default_max_chunk_tokens() -> usize {
+    256
+}
+
+fn default_extensions() -> Vec<String> {
+    ["md", "markdown", "txt"].iter().map(|ext| ext.to_string()).collect()
+}
+
+impl Default for IngestOptions {
+    fn default() -> Self {
+        Self {
+            max_file_bytes: default_max_file_bytes(),
+            max_chunk_tokens: default_max_chunk_tokens(),
+            extensions: default_extensions(),
+        }
+    }
+}
+
+impl IngestOptions {
+    /// Validate the ingestion options
+    pub fn validate(&self) -> Result<()> {
+        if self.max_chunk_tokens == 0 {
+            return Err(OxydeError::ConfigurationError(
+                "Knowledge max_chunk_tokens must be greater than 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A piece of lore small enough to recall into a prompt
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KnowledgeChunk {
+    /// File the chunk was read from, relative to the ingested directory
+    pub source: PathBuf,
+
+    /// Line of the file the chunk starts on, from 1
+    pub line: usize,
+
+    /// Markdown heading the chunk sits under, if any
+    pub heading: Option<String>,
+
+    /// The chunk's text
+    pub text: String,
+
+    /// Estimated tokens of the text
+    pub tokens: usize,
+}
+
+impl KnowledgeChunk {
+    /// Text to store as a memory, led by the heading
+    pub fn memory_text(&self) -> String {
+        match &self.heading {
+            Some(heading) => format!("{}: {}", heading, self.text),
+            None => self.text.clone(),
+        }
+    }
+
+    /// The chunk as a memory, tagged with its source file
+    pub fn to_memory(&self) -> Memory {
+        knowledge_memory(&self.memory_text(), Some(&self.source))
+    }
+}
+
+/// Importance of knowledge memories
+const KNOWLEDGE_IMPORTANCE: f64 = 0.8;
+
+/// A knowledge entry as the semantic memory agents recall it from
+///
+/// # Arguments
+///
+/// * `entry` - Text of the entry
+/// * `source` - Lore file the entry was read from, tagged as
+///   `source:<path>`, if known
+pub fn knowledge_memory(entry: &str, source: Option<&Path>) -> Memory {
+    let tags = source.map(|source| vec![format!("source:{}", source.display())]);
+    Memory::new(MemoryCategory::Semantic, entry, KNOWLEDGE_IMPORTANCE, tags)
+}
+
+/// Why a file was not ingested
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum SkipReason {
+    /// The extension is not in [`IngestOptions::extensions`]
+    Unsupported {
+        /// The file's extension, lowercase
+        extension: String,
+    },
+
+    /// The file is larger than [`IngestOptions::max_file_bytes`]
+    TooLarge {
+        /// Size of the file
+        bytes: u64,
+        /// The configured limit
+        limit: u64,
+    },
+
+    /// The file is not UTF-8 text
+    NotUtf8,
+
+    /// The file holds no text
+    Empty,
+
+    /// Markdown that would be read wrongly, such as an unclosed code fence
+    BrokenMarkdown {
+        /// Line the problem starts on, from 1
+        line: usize,
+        /// What is wrong
+        problem: String,
+    },
+
+    /// The file could not be read
+    Unreadable {
+        /// The I/O error
+        error: String,
+    },
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unsupported { extension } if extension.is_empty() => write!(f, "no file extension"),
+            Self::Unsupported { extension } => write!(f, "unsupported extension .{}", extension),
+            Self::TooLarge { bytes, limit } => write!(f, "{} bytes, over the {} byte limit", bytes, limit),
+            Self::NotUtf8 => write!(f, "not UTF-8 text"),
+            Self::Empty => write!(f, "no text"),
+            Self::BrokenMarkdown { line, problem } => write!(f, "broken Markdown at line {}: {}", line, problem),
+            Self::Unreadable { error } => write!(f, "unreadable: {}", error),
+        }
+    }
+}
+
+/// A file that was not ingested
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkippedFile {
+    /// Path relative to the ingested directory
+    pub path: PathBuf,
+
+    /// Why it was skipped
+    #[serde(flatten)]
+    pub reason: SkipReason,
+}
+
+/// A chunk dropped because the same text was already ingested
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DuplicateChunk {
+    /// File of the dropped chunk
+    pub path: PathBuf,
+
+    /// Line the dropped chunk starts on
+    pub line: usize,
+
+    /// File of the chunk that was kept
+    pub duplicate_of: PathBuf,
+
+    /// Line the kept chunk starts on
+    pub duplicate_of_line: usize,
+}
+
+/// What ingesting a directory produced
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IngestionReport {
+    /// Files chunked
+    pub files: usize,
+
+    /// Chunks created
+    pub chunks: usize,
+
+    /// Estimated tokens across the chunks
+    pub tokens: usize,
+
+    /// Files that were not ingested
+    pub skipped: Vec<SkippedFile>,
+
+    /// Chunks dropped because their text was already ingested
+    pub duplicates: Vec<DuplicateChunk>,
+}
+
+impl IngestionReport {
+    /// Whether every file was ingested without duplicates
+    pub fn is_clean(&self) -> bool {
+        self.skipped.is_empty() && self.duplicates.is_empty()
+    }
+}
+
+impl fmt::Display for IngestionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} files, {} chunks, ~{} tokens",
+            self.files, self.chunks, self.tokens
+        )?;
+        if !self.skipped.is_empty() {
+            writeln!(f, "Skipped {} files:", self.skipped.len())?;
+            for skipped in &self.skipped {
+                writeln!(f, "  {}: {}", skipped.path.display(), skipped.reason)?;
+            }
+        }
+        if !self.duplicates.is_empty() {
+            writeln!(f, "Dropped {} duplicate chunks:", self.duplicates.len())?;
+            for duplicate in &self.duplicates {
+                writeln!(
+                    f,
+                    "  {}:{} repeats {}:{}",
+                    duplicate.path.display(),
+                    duplicate.line,
+                    duplicate.duplicate_of.display(),
+                    duplicate.duplicate_of_line
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Chunks read from a directory with the report of reading them
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Ingestion {
+    /// Chunks in file order, without duplicates
+    pub chunks: Vec<KnowledgeChunk>,
+
+    /// What happened to each file
+    pub report: IngestionReport,
+}
+
+impl Ingestion {
+    /// Add the chunks to a configuration's knowledge entries, skipping
+    /// entries already there
+    ///
+    /// # Returns
+    ///
+    /// Number of entries added
+    pub fn add_to(&self, knowledge: &mut Vec<String>) -> usize {
+        let before = knowledge.len();
+        for chunk in &self.chunks {
+            let entry = chunk.memory_text();
+            if !knowledge.contains(&entry) {
+                knowledge.push(entry);
+            }
+        }
+        knowledge.len() - before
+    }
+}
+
+/// Read and chunk every lore file under a directory
+///
+/// Files are visited in path order. Hidden files and directories are
+/// ignored; files that cannot be ingested are skipped and reported.
+///
+/// # Arguments
+///
+/// * `dir` - Directory of lore files
+/// * `options` - Size limits and the extensions to read
+///
+/// # Returns
+///
+/// The chunks and report, or an error if the directory cannot be listed
+pub fn ingest_dir(dir: &Path, options: &IngestOptions) -> Result<Ingestion> {
+    options.validate()?;
+    if !dir.is_dir() {
+        return Err(OxydeError::ConfigurationError(format!(
+            "Knowledge directory {} does not exist",
+            dir.display()
+        )));
+    }
+
+    let mut ingestion = Ingestion::default();
+    let mut seen: HashMap<String, (PathBuf, usize)> = HashMap::new();
+    for path in list_files(dir)? {
+        let relative = path.strip_prefix(dir).unwrap_or(&path).to_path_buf();
+        let chunks = match read_file(&path, options) {
+            Ok(chunks) => chunks,
+            Err(reason) => {
+                ingestion.report.skipped.push(SkippedFile { path: relative, reason });
+                continue;
+            }
+        };
+        ingestion.report.files += 1;
+        for (line, heading, text) in chunks {
+            let key = normalize(&text);
+            if let Some((first_path, first_line)) = seen.get(&key) {
+                ingestion.report.duplicates.push(DuplicateChunk {
+                    path: relative.clone(),
+                    line,
+                    duplicate_of: first_path.clone(),
+                    duplicate_of_line: *first_line,
+                });
+                continue;
+            }
+            seen.insert(key, (relative.clone(), line));
+            let tokens = estimate_tokens(&text);
+            ingestion.report.chunks += 1;
+            ingestion.report.tokens += tokens;
+            ingestion.chunks.push(KnowledgeChunk {
+                source: relative.clone(),
+                line,
+                heading,
+                text,
+                tokens,
+            });
+        }
+    }
+    Ok(ingestion)
+}
+
+/// Files under a directory in path order, skipping hidden entries
+///
+/// Symbolic links are followed, but each directory is visited once, so a
+/// link back up the tree does not loop.
+fn list_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut visited = HashSet::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        if !visited.insert(fs::canonicalize(&dir)?) {
+            continue;
+        }
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.file_name().and_then(|name| name.to_str()).is_some_and(|name| name.starts_with('.')) {
+                continue;
+            }
+            if path.is_dir() {
+                pending.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// A chunk's starting line, heading and text
+type RawChunk = (usize, Option<String>, String);
+
+fn read_file(path: &Path, options: &IngestOptions) -> std::result::Result<Vec<RawChunk>, SkipReason> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+    if !options.extensions.iter().any(|allowed| allowed.eq_ignore_ascii_case(&extension)) {
+        return Err(SkipReason::Unsupported { extension });
+    }
+    let unreadable = |e: std::io::Error| SkipReason::Unreadable { error: e.to_string() };
+    let bytes = fs::metadata(path).map_err(unreadable)?.len();
+    if bytes > options.max_file_bytes {
+        return Err(SkipReason::TooLarge {
+            bytes,
+            limit: options.max_file_bytes,
+        });
+    }
+    let text = String::from_utf8(fs::read(path).map_err(unreadable)?).map_err(|_| SkipReason::NotUtf8)?;
+
+    let markdown = matches!(extension.as_str(), "md" | "markdown");
+    if markdown {
+        check_markdown(&text)?;
+    }
+    let chunks = chunk_text(&text, markdown, options.max_chunk_tokens);
+    if chunks.is_empty() {
+        return Err(SkipReason::Empty);
+    }
+    Ok(chunks)
+}
+
+/// Find Markdown that would be chunked wrongly: unterminated front matter
+/// or code fences, whose contents would be read as prose
+fn check_markdown(text: &str) -> std::result::Result<(), SkipReason> {
+    if text.lines().next().map(str::trim_end) == Some("---")
+        && !text.lines().skip(1).any(|line| matches!(line.trim_end(), "---" | "..."))
+    {
+        return Err(SkipReason::BrokenMarkdown {
+            line: 1,
+            problem: "front matter is never closed".to_string(),
+        });
+    }
+
+    let mut open_fence = None;
+    for (index, line) in text.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            open_fence = match open_fence {
+                Some(_) => None,
+                None => Some(index + 1),
+            };
+        }
+    }
+    match open_fence {
+        Some(line) => Err(SkipReason::BrokenMarkdown {
+            line,
+            problem: "code fence is never closed".to_string(),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Split text into paragraphs of at most `max_tokens`, under the Markdown
+/// heading they follow
+fn chunk_text(text: &str, markdown: bool, max_tokens: usize) -> Vec<RawChunk> {
+    let mut chunks = Vec::new();
+    let mut heading: Option<String> = None;
+    let mut paragraph: Option<(usize, String)> = None;
+    let mut in_front_matter = markdown && text.lines().next().map(str::trim_end) == Some("---");
+
+    for (index, line) in text.lines().enumerate() {
+        let trimmed = line.trim();
+        if in_front_matter {
+            if index > 0 && matches!(trimmed, "---" | "...") {
+                in_front_matter = false;
+            }
+            continue;
+        }
+        if markdown && trimmed.starts_with('#') {
+            let title = trimmed.trim_start_matches('#');
+            if title.is_empty() || title.starts_with(' ') {
+                push_paragraph(&mut chunks, paragraph.take(), &heading, max_tokens);
+                heading = Some(title.trim().to_string()).filter(|title| !title.is_empty());
+                continue;
+            }
+        }
+        if trimmed.is_empty() {
+            push_paragraph(&mut chunks, paragraph.take(), &heading, max_tokens);
+            continue;
+        }
+        let (_, text) = paragraph.get_or_insert_with(|| (index + 1, String::new()));
+        for word in trimmed.split_whitespace() {
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            text.push_str(word);
+        }
+    }
+    push_paragraph(&mut chunks, paragraph, &heading, max_tokens);
+    chunks
+}
+
+fn push_paragraph(
+    chunks: &mut Vec<RawChunk>,
+    paragraph: Option<(usize, String)>,
+    heading: &Option<String>,
+    max_tokens: usize,
+) {
+    if let Some((line, text)) = paragraph {
+        for piece in split_oversized(&text, max_tokens) {
+            chunks.push((line, heading.clone(), piece));
+        }
+    }
+}
+
+/// Split a paragraph over the token limit at word boundaries
+fn split_oversized(text: &str, max_tokens: usize) -> Vec<String> {
+    if estimate_tokens(text) <= max_tokens {
+        return vec![text.to_string()];
+    }
+    let mut pieces = Vec::new();
+    let mut piece = String::new();
+    for word in text.split_whitespace() {
+        if !piece.is_empty() && estimate_tokens(&piece) + estimate_tokens(word) + 1 > max_tokens {
+            pieces.push(std::mem::take(&mut piece));
+        }
+        if !piece.is_empty() {
+            piece.push(' ');
+        }
+        piece.push_str(word);
+    }
+    if !piece.is_empty() {
+        pieces.push(piece);
+    }
+    pieces
+}
+
+/// Text compared when detecting duplicates: lowercase with single spaces
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ingestion_reports_skipped_and_duplicate_content() {
+        let dir = std::env::temp_dir().join(format!("oxyde-lore-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(dir.join("factions")).unwrap();
+        fs::write(
+            dir.join("history.md"),
+            "---\ntitle: History\n---\n# The Founding\n\nVael was founded by exiles.\n\n## The War\n\nThe guild burned the docks.\n",
+        )
+        .unwrap();
+        fs::write(dir.join("factions/guild.md"), "The guild burned   the docks.\n\nThey answer to no one.\n").unwrap();
+        fs::write(dir.join("broken.md"), "# Spells\n\n```\nfireball\n").unwrap();
+        fs::write(dir.join("huge.txt"), "word ".repeat(100)).unwrap();
+        fs::write(dir.join("map.png"), [0x89, 0x50]).unwrap();
+        fs::write(dir.join(".notes.md"), "Ignored").unwrap();
+
+        let options = IngestOptions {
+            max_file_bytes: 200,
+            ..Default::default()
+        };
+        let ingestion = ingest_dir(&dir, &options).unwrap();
+        let report = &ingestion.report;
+        assert_eq!((report.files, report.chunks), (2, 3));
+        assert_eq!(ingestion.chunks[0].memory_text(), "The guild burned the docks.");
+        assert_eq!(ingestion.chunks[1].line, 3);
+        assert_eq!(ingestion.chunks[2].memory_text(), "The Founding: Vael was founded by exiles.");
+
+        let reasons: Vec<String> = report
+            .skipped
+            .iter()
+            .map(|skipped| format!("{}: {}", skipped.path.display(), skipped.reason))
+            .collect();
+        assert_eq!(
+            reasons,
+            [
+                "broken.md: broken Markdown at line 3: code fence is never closed",
+                "huge.txt: 500 bytes, over the 200 byte limit",
+                "map.png: unsupported extension .png",
+            ]
+        );
+        assert_eq!(report.duplicates.len(), 1);
+        assert_eq!(report.duplicates[0].path, PathBuf::from("history.md"));
+        assert_eq!(report.duplicates[0].duplicate_of, PathBuf::from("factions/guild.md"));
+        assert!(!report.is_clean());
+
+        let mut knowledge = vec!["They answer to no one.".to_string()];
+        assert_eq!(ingestion.add_to(&mut knowledge), 2);
+        assert_eq!(ingestion.add_to(&mut knowledge), 0);
+        assert_eq!(ingestion.chunks[0].to_memory().tags, vec!["source:factions/guild.md"]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinked_directories_are_visited_once() {
+        let dir = std::env::temp_dir().join(format!("oxyde-lore-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(dir.join("towns")).unwrap();
+        fs::write(dir.join("towns/vael.md"), "Vael is a port.\n").unwrap();
+        std::os::unix::fs::symlink(&dir, dir.join("towns/back")).unwrap();
+
+        let ingestion = ingest_dir(&dir, &IngestOptions::default()).unwrap();
+        assert_eq!(ingestion.report.files, 1);
+        assert!(ingestion.report.duplicates.is_empty());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
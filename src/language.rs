@@ -0,0 +1,399 @@
+//! Detecting the language players write in
+//!
+//! Players may type in any language. With language detection enabled, each
+//! input is classified by script and, for Latin-script text, by common
+//! function words, without calling a model. The detected locale is kept under
+//! [`PLAYER_LOCALE_KEY`] in the player's session context, or in the agent
+//! context for inputs from no identified player, where it persists until the
+//! player is confidently detected writing another language:
+//!
+//! ```yaml
+//! language:
+//!   enabled: true
+//!   primary: en
+//!   translate_input: true
+//!   locales:
+//!     es:
+//!       prompt: Responde en español, con el acento de un posadero de Castilla.
+//!       responses:
+//!         GreetingBehavior: ¡Bienvenido a la posada, viajero!
+//! ```
+//!
+//! Inference is asked to reply in the player's language, or given the
+//! locale's `prompt` when one is configured. Behavior responses listed under
+//! the locale's `responses` replace the response of the named behavior. With
+//! `translate_input`, input in a language other than `primary` is translated
+//! before intent analysis, so intent patterns only need writing once; memory
+//! and inference still see what the player typed.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{OxydeError, Result};
+
+/// Context key holding the locale the player writes in, such as `es`
+pub const PLAYER_LOCALE_KEY: &str = "player_locale";
+
+/// Function words of Latin-script languages, by locale
+const FUNCTION_WORDS: &[(&str, &[&str])] = &[
+    ("en", &[
+        "the", "and", "you", "is", "are", "what", "where", "how", "to", "of", "it", "do", "have", "this", "that",
+        "for", "with", "my", "your", "can", "hello", "hi", "please", "thanks", "me", "i",
+    ]),
+    ("es", &[
+        "el", "los", "las", "que", "y", "es", "una", "por", "para", "con", "hola", "dónde", "donde", "qué", "cómo",
+        "tienes", "estás", "yo", "muy", "pero", "quiero", "gracias", "usted", "puedo", "está",
+    ]),
+    ("fr", &[
+        "le", "les", "des", "est", "et", "je", "vous", "une", "pas", "qui", "bonjour", "où", "avez", "suis", "pour",
+        "avec", "ce", "dans", "mon", "votre", "merci", "salut", "êtes", "quoi", "ou",
+    ]),
+    ("de", &[
+        "der", "die", "das", "und", "ist", "ich", "du", "nicht", "ein", "eine", "wo", "was", "wie", "hallo", "haben",
+        "mit", "für", "sie", "bist", "mein", "zu", "den", "danke", "bitte", "gibt",
+    ]),
+    ("it", &[
+        "il", "lo", "gli", "che", "di", "è", "non", "ciao", "sono", "dove", "cosa", "come", "per", "mio", "hai",
+        "ho", "della", "questo", "grazie", "buongiorno", "sei", "vorrei",
+    ]),
+    ("pt", &[
+        "o", "os", "as", "é", "um", "não", "olá", "onde", "você", "como", "com", "meu", "tem", "eu", "isso",
+        "obrigado", "obrigada", "está", "quero", "posso", "bom", "dia",
+    ]),
+    ("nl", &[
+        "het", "een", "en", "ik", "je", "niet", "wat", "waar", "hoe", "hallo", "van", "met", "mijn", "jij", "heb",
+        "dat", "voor", "zijn", "dank", "graag", "u",
+    ]),
+];
+
+/// Letters used by only one of the Latin-script languages above
+const DISTINCTIVE_LETTERS: &[(char, &str)] = &[
+    ('ñ', "es"),
+    ('¿', "es"),
+    ('¡', "es"),
+    ('ß', "de"),
+    ('ä', "de"),
+    ('ö', "de"),
+    ('ü', "de"),
+    ('ç', "fr"),
+    ('œ', "fr"),
+    ('ê', "fr"),
+    ('è', "fr"),
+    ('ã', "pt"),
+    ('õ', "pt"),
+    ('ì', "it"),
+    ('ò', "it"),
+];
+
+/// English names of the detectable locales
+const LANGUAGE_NAMES: &[(&str, &str)] = &[
+    ("en", "English"),
+    ("es", "Spanish"),
+    ("fr", "French"),
+    ("de", "German"),
+    ("it", "Italian"),
+    ("pt", "Portuguese"),
+    ("nl", "Dutch"),
+    ("ru", "Russian"),
+    ("el", "Greek"),
+    ("ar", "Arabic"),
+    ("he", "Hebrew"),
+    ("hi", "Hindi"),
+    ("th", "Thai"),
+    ("ja", "Japanese"),
+    ("ko", "Korean"),
+    ("zh", "Chinese"),
+];
+
+/// The language a text was detected to be in
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LanguageDetection {
+    /// Two-letter locale code, such as `es`
+    pub locale: String,
+
+    /// Share of the evidence pointing to the locale, from 0.0 to 1.0
+    pub confidence: f32,
+}
+
+/// English name of a locale, such as `Spanish` for `es` or `es-MX`
+pub fn language_name(locale: &str) -> Option<&'static str> {
+    let base = base_locale(locale);
+    LANGUAGE_NAMES
+        .iter()
+        .find(|(code, _)| code.eq_ignore_ascii_case(base))
+        .map(|(_, name)| *name)
+}
+
+/// Detect the language of a text
+///
+/// Non-Latin scripts identify their language directly; Latin-script text is
+/// scored by function words and distinctive letters.
+///
+/// # Returns
+///
+/// The most likely language, or `None` when the text gives no evidence
+pub fn detect_language(text: &str) -> Option<LanguageDetection> {
+    if let Some(detection) = detect_script(text) {
+        return Some(detection);
+    }
+
+    let mut scores: HashMap<&str, f32> = HashMap::new();
+    let lowercase = text.to_lowercase();
+    for word in lowercase.split(|c: char| !c.is_alphabetic()).filter(|word| !word.is_empty()) {
+        for (locale, words) in FUNCTION_WORDS {
+            if words.contains(&word) {
+                *scores.entry(*locale).or_default() += 1.0;
+            }
+        }
+    }
+    for c in lowercase.chars() {
+        if let Some((_, locale)) = DISTINCTIVE_LETTERS.iter().find(|(letter, _)| *letter == c) {
+            *scores.entry(*locale).or_default() += 2.0;
+        }
+    }
+
+    let total: f32 = scores.values().sum();
+    let (locale, score) = scores
+        .into_iter()
+        .max_by(|a, b| a.1.total_cmp(&b.1).then_with(|| b.0.cmp(a.0)))?;
+    Some(LanguageDetection {
+        locale: locale.to_string(),
+        confidence: score / total,
+    })
+}
+
+/// Detect a language written in its own script
+fn detect_script(text: &str) -> Option<LanguageDetection> {
+    let mut letters = 0usize;
+    let mut scripts: HashMap<&str, usize> = HashMap::new();
+    for c in text.chars().filter(|c| c.is_alphabetic()) {
+        letters += 1;
+        let locale = match c as u32 {
+            0x0370..=0x03FF => "el",
+            0x0400..=0x04FF => "ru",
+            0x0590..=0x05FF => "he",
+            0x0600..=0x06FF => "ar",
+            0x0900..=0x097F => "hi",
+            0x0E00..=0x0E7F => "th",
+            0x3040..=0x30FF => "ja",
+            0xAC00..=0xD7AF | 0x1100..=0x11FF => "ko",
+            0x4E00..=0x9FFF => "zh",
+            _ => continue,
+        };
+        *scripts.entry(locale).or_default() += 1;
+    }
+
+    // Japanese mixes kana with Chinese characters
+    if scripts.contains_key("ja") {
+        let japanese = scripts.remove("ja").unwrap_or(0) + scripts.remove("zh").unwrap_or(0);
+        scripts.insert("ja", japanese);
+    }
+    let (locale, count) = scripts.into_iter().max_by_key(|(locale, count)| (*count, *locale))?;
+    let confidence = count as f32 / letters as f32;
+    (confidence >= 0.5).then(|| LanguageDetection {
+        locale: locale.to_string(),
+        confidence,
+    })
+}
+
+/// Language part of a locale, such as `es` for `es-MX`
+fn base_locale(locale: &str) -> &str {
+    locale.split(['-', '_']).next().unwrap_or(locale)
+}
+
+/// Settings for players writing in one language
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LocaleConfig {
+    /// Instruction given to inference instead of asking it to reply in the
+    /// player's language
+    #[serde(default)]
+    pub prompt: Option<String>,
+
+    /// Responses replacing those of behaviors, by behavior type name
+    #[serde(default)]
+    pub responses: HashMap<String, String>,
+}
+
+/// Language detection configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LanguageConfig {
+    /// Whether player input is classified by language
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Locale the agent's intents and prompts are written in
+    #[serde(default = "default_primary")]
+    pub primary: String,
+
+    /// Lowest confidence at which a detection replaces the stored locale
+    #[serde(default = "default_min_confidence")]
+    pub min_confidence: f32,
+
+    /// Whether input in another language is translated into `primary` before
+    /// intent analysis
+    #[serde(default)]
+    pub translate_input: bool,
+
+    /// Longest translation generated, in tokens
+    #[serde(default = "default_translation_max_tokens")]
+    pub translation_max_tokens: usize,
+
+    /// Prompts and behavior responses by locale, such as `es`
+    #[serde(default)]
+    pub locales: HashMap<String, LocaleConfig>,
+}
+
+fn default_primary() -> String {
+    "en".to_string()
+}
+
+fn default_min_confidence() -> f32 {
+    0.6
+}
+
+fn default_translation_max_tokens() -> usize {
+    256
+}
+
+impl Default for LanguageConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            primary: default_primary(),
+            min_confidence: default_min_confidence(),
+            translate_input: false,
+            translation_max_tokens: default_translation_max_tokens(),
+            locales: HashMap::new(),
+        }
+    }
+}
+
+impl LanguageConfig {
+    /// Validate the language detection configuration
+    ///
+    /// # Returns
+    ///
+    /// Ok if the configuration is valid, Err with a descriptive message otherwise
+    pub fn validate(&self) -> Result<()> {
+        if self.primary.trim().is_empty() {
+            return Err(OxydeError::ConfigurationError(
+                "Language primary locale cannot be empty".to_string(),
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.min_confidence) {
+            return Err(OxydeError::ConfigurationError(format!(
+                "Language min_confidence must be between 0.0 and 1.0, got {}",
+                self.min_confidence
+            )));
+        }
+        if self.translate_input && self.translation_max_tokens == 0 {
+            return Err(OxydeError::ConfigurationError(
+                "Language translation_max_tokens must be greater than 0".to_string(),
+            ));
+        }
+        if self.locales.keys().any(|locale| locale.trim().is_empty()) {
+            return Err(OxydeError::ConfigurationError(
+                "Language locale names cannot be empty".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Detect the language of player input
+    ///
+    /// # Returns
+    ///
+    /// The detected language, or `None` if detection is disabled or not
+    /// confident enough
+    pub fn detect(&self, input: &str) -> Option<LanguageDetection> {
+        if !self.enabled {
+            return None;
+        }
+        detect_language(input).filter(|detection| detection.confidence >= self.min_confidence)
+    }
+
+    /// Whether a locale is the agent's primary language
+    pub fn is_primary(&self, locale: &str) -> bool {
+        base_locale(locale).eq_ignore_ascii_case(base_locale(&self.primary))
+    }
+
+    /// Settings for a locale, falling back from `es-MX` to `es`
+    pub fn locale(&self, locale: &str) -> Option<&LocaleConfig> {
+        self.locales
+            .get(locale)
+            .or_else(|| self.locales.get(base_locale(locale)))
+    }
+
+    /// Instruction telling inference how to answer a player writing in a locale
+    ///
+    /// # Returns
+    ///
+    /// The locale's prompt if configured, otherwise an instruction to reply
+    /// in the player's language if it is not the primary one
+    pub fn instruction(&self, locale: &str) -> Option<String> {
+        if let Some(prompt) = self.locale(locale).and_then(|config| config.prompt.as_deref()) {
+            return Some(prompt.to_string());
+        }
+        if self.is_primary(locale) {
+            return None;
+        }
+        let name = language_name(locale).unwrap_or(locale);
+        Some(format!("The player is writing in {}. Reply in {}.", name, name))
+    }
+
+    /// Response replacing a behavior's for players writing in a locale
+    pub fn response(&self, locale: &str, behavior: &str) -> Option<&str> {
+        self.locale(locale)?.responses.get(behavior).map(String::as_str)
+    }
+
+    /// System prompt translating player input into the primary language
+    pub fn translation_prompt(&self, locale: &str) -> String {
+        let from = language_name(locale).unwrap_or(locale);
+        let to = language_name(&self.primary).unwrap_or(&self.primary);
+        format!(
+            "Translate the player's message from {} to {}. Reply with the translation only, without quotes or notes.",
+            from, to
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_languages_are_detected_by_script_and_words() {
+        let detect = |text: &str| detect_language(text).map(|detection| detection.locale);
+
+        assert_eq!(detect("Hello, where is the blacksmith?").as_deref(), Some("en"));
+        assert_eq!(detect("¿Dónde está el herrero?").as_deref(), Some("es"));
+        assert_eq!(detect("Bonjour, où est le forgeron ?").as_deref(), Some("fr"));
+        assert_eq!(detect("Wo ist der Schmied? Ich brauche ein Schwert.").as_deref(), Some("de"));
+        assert_eq!(detect("Где кузнец?").as_deref(), Some("ru"));
+        assert_eq!(detect("鍛冶屋はどこですか").as_deref(), Some("ja"));
+        assert_eq!(detect("铁匠在哪里").as_deref(), Some("zh"));
+        assert_eq!(detect("대장장이는 어디 있어요?").as_deref(), Some("ko"));
+        assert_eq!(detect("12345 !!"), None);
+    }
+
+    #[test]
+    fn test_locales_route_prompts_and_responses() {
+        let config: LanguageConfig = serde_yaml::from_str(
+            "enabled: true\n\
+             locales:\n  es-MX:\n    prompt: Responde como un mercader de Oaxaca.\n  fr:\n    responses:\n      GreetingBehavior: Bienvenue !\n",
+        )
+        .unwrap();
+        config.validate().unwrap();
+
+        assert_eq!(config.detect("Hola, ¿qué tal?").unwrap().locale, "es");
+        assert!(config.detect("ok").is_none());
+
+        assert_eq!(config.instruction("en"), None);
+        assert_eq!(config.instruction("fr").unwrap(), "The player is writing in French. Reply in French.");
+        assert_eq!(config.instruction("es-MX").unwrap(), "Responde como un mercader de Oaxaca.");
+        assert_eq!(config.response("fr-CA", "GreetingBehavior"), Some("Bienvenue !"));
+        assert_eq!(config.response("es", "GreetingBehavior"), None);
+        assert!(config.instruction("de").unwrap().contains("German"));
+    }
+}
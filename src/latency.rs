@@ -0,0 +1,291 @@
+//! Filler replies for slow responses
+//!
+//! Players notice a silent NPC after about a second. With a latency target
+//! configured, an agent whose inference has not returned within
+//! `filler_after_ms` emits a short filler line, such as "Hmm, let me
+//! think...", through the `filler` event, then returns the full response
+//! when it is ready:
+//!
+//! ```yaml
+//! latency:
+//!   enabled: true
+//!   filler_after_ms: 800
+//!   fillers:
+//!     - lines: ["Hmm, let me think...", "One moment, {player}."]
+//!     - emotion: anger
+//!       lines: ["Give me a second."]
+//! ```
+//!
+//! Fillers matching the agent's dominant emotion are preferred over those
+//! without an emotion; built-in lines are used when none match. Lines may use
+//! the variables of [`crate::oxyde_game::behavior::fill_variables`].
+//!
+//! Proximity greetings are predictable, so they can be generated before they
+//! are needed. With speculative greetings enabled, the game reports the
+//! player's distance through `Agent::anticipate_greeting`; once the player is
+//! within `radius`, the greeting for `input` is generated in the background,
+//! and its speech synthesized if `synthesize_speech` is set. When the
+//! greeting input arrives within `ttl_ms`, it is answered without waiting
+//! for inference, and `Agent::speak` returns the prepared audio:
+//!
+//! ```yaml
+//! latency:
+//!   speculative_greeting:
+//!     enabled: true
+//!     radius: 10.0
+//!     input: "Hello"
+//!     synthesize_speech: true
+//! ```
+
+use std::time::Duration;
+
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use crate::agent::AgentContext;
+use crate::oxyde_game::behavior::fill_variables;
+use crate::oxyde_game::emotion::EmotionalState;
+use crate::timer::Instant;
+use crate::{OxydeError, Result};
+
+/// Filler lines for one emotional state
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FillerLines {
+    /// Dominant emotion required; `None` matches any emotional state
+    #[serde(default)]
+    pub emotion: Option<String>,
+
+    /// Minimum strength of the dominant emotion for `emotion` to match
+    #[serde(default = "default_min_intensity")]
+    pub min_intensity: f32,
+
+    /// Lines to choose from
+    pub lines: Vec<String>,
+}
+
+fn default_min_intensity() -> f32 {
+    0.3
+}
+
+impl FillerLines {
+    /// Whether the lines suit an emotional state
+    fn matches(&self, emotions: &EmotionalState) -> bool {
+        self.emotion.as_ref().is_none_or(|expected| {
+            let (dominant, value) = emotions.dominant_emotion();
+            dominant == expected && value.abs() >= self.min_intensity
+        })
+    }
+}
+
+/// Configuration for filler replies when responses are slow
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LatencyConfig {
+    /// Whether filler replies are emitted
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// How long inference may run before a filler is emitted, in milliseconds
+    #[serde(default = "default_filler_after_ms")]
+    pub filler_after_ms: u64,
+
+    /// Filler lines, checked before the built-in ones
+    #[serde(default)]
+    pub fillers: Vec<FillerLines>,
+
+    /// Greetings generated before the player is close enough to trigger them
+    #[serde(default)]
+    pub speculative_greeting: SpeculativeGreetingConfig,
+}
+
+fn default_filler_after_ms() -> u64 {
+    1000
+}
+
+impl Default for LatencyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            filler_after_ms: default_filler_after_ms(),
+            fillers: Vec::new(),
+            speculative_greeting: SpeculativeGreetingConfig::default(),
+        }
+    }
+}
+
+/// Configuration for generating greetings ahead of the proximity trigger
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpeculativeGreetingConfig {
+    /// Whether greetings are generated speculatively
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Player distance at which generation starts
+    #[serde(default = "default_speculation_radius")]
+    pub radius: f32,
+
+    /// Input the proximity trigger sends, such as a greeting
+    #[serde(default = "default_greeting_input")]
+    pub input: String,
+
+    /// How long a generated greeting stays usable, in milliseconds
+    #[serde(default = "default_greeting_ttl_ms")]
+    pub ttl_ms: u64,
+
+    /// Whether the greeting's speech is synthesized as well
+    #[serde(default)]
+    pub synthesize_speech: bool,
+}
+
+fn default_speculation_radius() -> f32 {
+    10.0
+}
+
+fn default_greeting_input() -> String {
+    "Hello".to_string()
+}
+
+fn default_greeting_ttl_ms() -> u64 {
+    30_000
+}
+
+impl Default for SpeculativeGreetingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            radius: default_speculation_radius(),
+            input: default_greeting_input(),
+            ttl_ms: default_greeting_ttl_ms(),
+            synthesize_speech: false,
+        }
+    }
+}
+
+impl SpeculativeGreetingConfig {
+    /// Whether a player at `distance` is close enough to prepare a greeting for
+    pub fn in_range(&self, distance: f32) -> bool {
+        self.enabled && distance <= self.radius
+    }
+
+    /// Whether an input is the one the greeting was prepared for
+    pub fn matches_input(&self, input: &str) -> bool {
+        input.trim().eq_ignore_ascii_case(self.input.trim())
+    }
+}
+
+/// A greeting generated ahead of the input that triggers it
+#[derive(Debug, Clone)]
+pub struct SpeculativeGreeting {
+    /// Greeting text, moderated and post-processed
+    pub text: String,
+
+    /// Synthesized speech for the text
+    #[cfg(feature = "tts")]
+    pub audio: Option<crate::audio::AudioData>,
+
+    /// When the greeting was generated
+    pub generated_at: Instant,
+}
+
+impl SpeculativeGreeting {
+    /// Whether the greeting is still recent enough to deliver
+    pub fn is_fresh(&self, config: &SpeculativeGreetingConfig) -> bool {
+        self.generated_at.elapsed() < Duration::from_millis(config.ttl_ms)
+    }
+}
+
+impl LatencyConfig {
+    /// Validate the latency configuration
+    pub fn validate(&self) -> Result<()> {
+        if self.enabled && self.filler_after_ms == 0 {
+            return Err(OxydeError::ConfigurationError(
+                "Latency filler_after_ms must be greater than 0".to_string(),
+            ));
+        }
+        let greeting = &self.speculative_greeting;
+        if greeting.enabled && (greeting.radius <= 0.0 || greeting.input.trim().is_empty()) {
+            return Err(OxydeError::ConfigurationError(
+                "Speculative greetings need a positive radius and an input".to_string(),
+            ));
+        }
+        if let Some(empty) = self.fillers.iter().find(|filler| filler.lines.is_empty()) {
+            return Err(OxydeError::ConfigurationError(format!(
+                "Latency fillers for emotion {} have no lines",
+                empty.emotion.as_deref().unwrap_or("any")
+            )));
+        }
+        Ok(())
+    }
+
+    /// Pick a filler line for an emotional state
+    ///
+    /// Lines for the dominant emotion win over lines for any emotion. A
+    /// random line is chosen, using the turn seed from the context when
+    /// present.
+    pub fn filler(&self, emotions: &EmotionalState, context: &AgentContext) -> String {
+        let matching = |specific: bool| {
+            self.fillers
+                .iter()
+                .filter(move |filler| filler.emotion.is_some() == specific && filler.matches(emotions))
+                .flat_map(|filler| filler.lines.iter().map(String::as_str))
+                .collect::<Vec<&str>>()
+        };
+        let mut lines = matching(true);
+        if lines.is_empty() {
+            lines = matching(false);
+        }
+
+        let mut rng = crate::turn::context_rng(context);
+        let line = lines.choose(&mut rng).copied().unwrap_or_else(|| builtin_filler(emotions));
+        fill_variables(line, context)
+    }
+}
+
+/// Built-in filler used when no configured line matches
+fn builtin_filler(emotions: &EmotionalState) -> &'static str {
+    let (dominant, value) = emotions.dominant_emotion();
+    if value.abs() < default_min_intensity() {
+        return "Hmm, let me think...";
+    }
+    match (dominant, value > 0.0) {
+        ("anger", true) | ("disgust", true) => "Give me a moment.",
+        ("fear", true) => "Uh... just a moment.",
+        ("joy", true) | ("anticipation", true) => "Oh, let me see!",
+        ("sadness", true) => "Mm... one moment.",
+        ("surprise", true) => "Oh! Let me think...",
+        _ => "Hmm, let me think...",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filler_matches_dominant_emotion() {
+        let config = LatencyConfig {
+            enabled: true,
+            fillers: vec![
+                FillerLines {
+                    emotion: None,
+                    min_intensity: default_min_intensity(),
+                    lines: vec!["One moment, {player}.".to_string()],
+                },
+                FillerLines {
+                    emotion: Some("anger".to_string()),
+                    min_intensity: 0.5,
+                    lines: vec!["Wait.".to_string()],
+                },
+            ],
+            ..Default::default()
+        };
+        config.validate().unwrap();
+
+        let mut emotions = EmotionalState::new();
+        assert_eq!(config.filler(&emotions, &AgentContext::new()), "One moment, traveler.");
+        emotions.update_emotion("anger", 0.7);
+        assert_eq!(config.filler(&emotions, &AgentContext::new()), "Wait.");
+
+        let builtin = LatencyConfig::default();
+        assert_eq!(builtin.filler(&emotions, &AgentContext::new()), "Give me a moment.");
+    }
+}
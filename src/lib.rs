@@ -36,21 +36,84 @@
 // Re-exports
 pub use agent::Agent;
 pub use config::AgentConfig;
+pub use health::{healthcheck, HealthReport};
 pub use inference::InferenceEngine;
 pub use memory::MemorySystem;
 
 // Modules
 pub mod audio;
+pub mod action;
 pub mod agent;
+pub mod annotation;
+pub mod attachment;
+pub mod capabilities;
+pub mod condition;
 pub mod config;
+pub mod config_migration;
+pub mod consistency;
+pub mod context;
+pub mod debounce;
+pub mod dialogue_export;
+pub mod dialogue_queue;
+pub mod embedding_service;
+pub mod entity;
+pub mod event_log;
+pub mod experiment;
+pub mod fallback;
+pub mod forgetting;
+pub mod health;
 pub mod inference;
+pub mod inference_scheduler;
+pub mod knowledge;
+pub mod language;
+pub mod latency;
+pub mod interaction_log;
 pub mod memory;
+pub mod memory_pool;
+pub mod memory_query;
+pub mod memory_stats;
+pub mod memory_store;
+pub mod mock_provider;
+pub mod model_policy;
+pub mod monologue;
 pub mod oxyde_game;
+pub mod package;
+pub mod postprocess;
+pub mod prompt;
+pub mod provider_router;
+pub mod redaction;
+pub mod reflection;
+pub mod request_queue;
+pub mod retrieval;
+pub mod sampling;
+pub mod save;
+pub mod secrets;
+pub mod session;
+pub mod state_machine;
+pub mod structured;
+#[cfg(feature = "otlp")]
+pub mod telemetry;
+pub mod template;
+#[cfg(any(test, feature = "testkit"))]
+pub mod testkit;
+pub mod turn;
+pub mod verbosity;
+#[cfg(feature = "ws-server")]
+pub mod ws_server;
 
 // Internal modules
+mod timer;
 mod utils;
 mod error;
 
+// `wasm-min` exists to keep browser builds small, so reject features that
+// would silently bring the size back
+#[cfg(all(
+    feature = "wasm-min",
+    any(feature = "tts", feature = "vector-memory", feature = "ai", feature = "ws-server", feature = "otlp", feature = "unity", feature = "unreal"),
+))]
+compile_error!("`wasm-min` must be built with `--no-default-features` and without tts, vector-memory, ai, ws-server, otlp, unity or unreal");
+
 // Re-export from local error module
 pub use error::OxydeError;
 
@@ -60,6 +123,12 @@ pub type Result<T> = std::result::Result<T, OxydeError>;
 /// Agent context (environment state)
 pub type AgentContext = std::collections::HashMap<String, serde_json::Value>;
 
+/// Items used by code generated in `oxyde-macros`; not part of the public API
+#[doc(hidden)]
+pub mod __private {
+    pub use async_trait::async_trait;
+}
+
 /// Current version of the Oxyde SDK
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
@@ -7,7 +7,9 @@
 //! with features for short-term and long-term memory management.
 
 use std::cmp::Ordering;
-use std::collections::BinaryHeap;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use serde::{Deserialize, Serialize};
@@ -17,26 +19,32 @@ use uuid::Uuid;
 #[cfg(feature = "vector-memory")]
 use std::sync::Arc;
 
-#[cfg(feature = "vector-memory")]
-use tokio::sync::OnceCell;
-
-#[cfg(feature = "vector-memory")]
-use hnswlib::Hnsw;
-
 use crate::config::MemoryConfig;
+use crate::forgetting::ForgettingReport;
+use crate::entity::{entity_matches, extract_entities, normalize_entity, MemoryGraph};
+use crate::memory_stats::{MemorySession, MemoryStats};
+use crate::memory_store::{JournalOp, MemoryStore, RecoveryReport, StoreWriter};
+use crate::retrieval::{recency, RetrievalScorer, RetrievalWeights};
+use crate::save::Versioned;
+use crate::session::memory_player;
+use crate::oxyde_game::schedule::GameTime;
 
 #[cfg(feature = "vector-memory")]
-use crate::config::EmbeddingModelType;
+use crate::embedding_service::EmbeddingService;
 use crate::{OxydeError, Result};
 
 /// Embedding model for vector representations of text
-#[cfg(feature = "vector-memory")]
 pub trait EmbeddingModel {
     /// Generate embedding vector for text
     fn embed(&self, text: &str) -> Result<Vec<f32>>;
     
     /// Get the dimension of the embedding vectors
     fn dimension(&self) -> usize;
+
+    /// Approximate memory held by the model, in bytes
+    fn memory_bytes(&self) -> usize {
+        0
+    }
 }
 
 /// Simple embedding model implementation using MiniLM
@@ -85,10 +93,15 @@ impl EmbeddingModel for MiniLMEmbedding {
     fn dimension(&self) -> usize {
         self.dimension
     }
+
+    fn memory_bytes(&self) -> usize {
+        // About 33 million f32 weights
+        133 * 1024 * 1024
+    }
 }
 
 /// Memory category for different types of memories
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum MemoryCategory {
     /// Episodic memories (events, experiences)
     Episodic,
@@ -123,8 +136,27 @@ impl MemoryCategory {
     }
 }
 
+/// Whether a memory may surface in dialogue
+///
+/// Visibility only affects prompt assembly; every memory is still stored,
+/// retrievable through the memory API, and counted for emotional context.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryVisibility {
+    /// May be quoted to the player
+    #[default]
+    Public,
+    /// Guides the agent's goals but must not be quoted or revealed
+    Private,
+    /// Never sent to inference; only its emotional tone colors the agent's mood
+    Secret,
+}
+
 /// Memory represents a single piece of information that an agent remembers
+///
+/// Serialized with a schema version; see [`crate::save`].
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(remote = "Self")]
 pub struct Memory {
     /// Unique identifier for the memory
     pub id: String,
@@ -162,6 +194,52 @@ pub struct Memory {
     /// Vector embedding of the memory content (for semantic search)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub embedding: Option<Vec<f32>>,
+
+    /// Normalized names of the people, places and items the memory mentions
+    #[serde(default)]
+    pub entities: Vec<String>,
+
+    /// Whether the memory may be quoted in dialogue
+    #[serde(default)]
+    pub visibility: MemoryVisibility,
+
+    /// Game time the memory was formed at, when the agent knows it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub game_time: Option<GameTime>,
+
+    /// Shared memory pool the memory was recalled from, if the agent did not
+    /// form it itself
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+
+    /// Hash of the content, set when the memory is stored so duplicates are
+    /// found without rehashing every stored memory
+    #[serde(skip)]
+    content_hash: Option<u64>,
+}
+
+/// Tag marking the memory that holds the fact learned under `key`
+///
+/// See [`crate::Agent::learn_fact`].
+pub fn fact_tag(key: &str) -> String {
+    format!("fact:{}", key)
+}
+
+impl Versioned for Memory {
+    const KIND: &'static str = "memory";
+    const VERSION: u32 = 1;
+}
+
+impl Serialize for Memory {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        crate::save::serialize_versioned(self, Memory::serialize, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Memory {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        crate::save::deserialize_versioned(deserializer, Memory::deserialize)
+    }
 }
 
 impl Memory {
@@ -198,6 +276,31 @@ impl Memory {
             emotional_intensity: 0.0,
             permanent,
             embedding: None,
+            entities: extract_entities(content).into_iter().map(|e| e.name).collect(),
+            visibility: MemoryVisibility::Public,
+            game_time: None,
+            source: None,
+            content_hash: None,
+        }
+    }
+
+    /// Stamp the memory with the game time it was formed at
+    pub fn with_game_time(mut self, game_time: GameTime) -> Self {
+        self.game_time = Some(game_time);
+        self
+    }
+
+    /// Set the memory's visibility
+    pub fn with_visibility(mut self, visibility: MemoryVisibility) -> Self {
+        self.visibility = visibility;
+        self
+    }
+
+    /// Content as quoted in prompts, labeled with its shared pool if any
+    pub fn labeled_content(&self) -> String {
+        match &self.source {
+            Some(source) => format!("[shared by {}] {}", source, self.content),
+            None => self.content.clone(),
         }
     }
     
@@ -247,70 +350,10 @@ impl Memory {
     ///
     /// # Returns
     ///
-    /// Relevance score (0.0 - 1.0)
+    /// Relevance score (0.0 - 1.0), using the default [`RetrievalWeights`]
+    /// and keyword statistics from this memory alone, without age discount
     pub fn relevance(&self, query: &str, query_embedding: Option<&[f32]>) -> f64 {
-        // If we have embeddings for both the query and the memory, use vector similarity
-        if let (Some(query_vec), Some(memory_vec)) = (query_embedding, &self.embedding) {
-            if query_vec.len() == memory_vec.len() {
-                // Cosine similarity calculation
-                let mut dot_product = 0.0;
-                let mut query_magnitude = 0.0;
-                let mut memory_magnitude = 0.0;
-                
-                for i in 0..query_vec.len() {
-                    dot_product += query_vec[i] as f64 * memory_vec[i] as f64;
-                    query_magnitude += (query_vec[i] as f64).powi(2);
-                    memory_magnitude += (memory_vec[i] as f64).powi(2);
-                }
-                
-                query_magnitude = query_magnitude.sqrt();
-                memory_magnitude = memory_magnitude.sqrt();
-                
-                if query_magnitude > 0.0 && memory_magnitude > 0.0 {
-                    let cosine_similarity = dot_product / (query_magnitude * memory_magnitude);
-                    // Apply importance and recency bias
-                    return (cosine_similarity * 0.7 + self.importance * 0.3)
-                        .clamp(0.0, 1.0);
-                }
-            }
-        }
-        
-        // Fallback to keyword-based relevance if embeddings aren't available
-        let query_lower = query.to_lowercase();
-        let query_words: Vec<&str> = query_lower.split_whitespace().collect();
-        
-        let content_lower = self.content.to_lowercase();
-        let content_words: Vec<&str> = content_lower.split_whitespace().collect();
-        
-        // Check for tag matches to improve relevance
-        let tag_match_bonus = self.tags.iter()
-            .filter(|tag| query_lower.contains(&tag.to_lowercase()))
-            .count() as f64 * 0.1;
-        
-        // Check for content word matches
-        let mut matches = 0;
-        for qw in &query_words {
-            if content_words.iter().any(|cw| cw.contains(qw)) {
-                matches += 1;
-            }
-        }
-        
-        if query_words.is_empty() {
-            tag_match_bonus.min(1.0) // Just use tag bonus if query is empty
-        } else {
-            let word_match_score = matches as f64 / query_words.len() as f64;
-            
-            // Combine word matching with tag bonus and importance
-            let relevance_score = (word_match_score * 0.6 + self.importance * 0.3 + tag_match_bonus)
-                .clamp(0.0, 1.0);
-            
-            // Apply a small emotional intensity bonus for emotional memories
-            if self.category == MemoryCategory::Emotional && self.emotional_intensity > 0.5 {
-                (relevance_score * 1.2).min(1.0)
-            } else {
-                relevance_score
-            }
-        }
+        RetrievalScorer::new(RetrievalWeights::default(), query, [self]).relevance(self, query_embedding)
     }
     
     /// Set the vector embedding for this memory
@@ -323,6 +366,115 @@ impl Memory {
     }
 }
 
+/// Cosine similarity between two vectors
+///
+/// Returns `None` if the vectors differ in length or either has zero magnitude.
+pub(crate) fn cosine_similarity(a: &[f32], b: &[f32]) -> Option<f64> {
+    if a.len() != b.len() {
+        return None;
+    }
+
+    let mut dot_product = 0.0;
+    let mut a_magnitude = 0.0;
+    let mut b_magnitude = 0.0;
+
+    for (x, y) in a.iter().zip(b.iter()) {
+        dot_product += *x as f64 * *y as f64;
+        a_magnitude += (*x as f64).powi(2);
+        b_magnitude += (*y as f64).powi(2);
+    }
+
+    if a_magnitude > 0.0 && b_magnitude > 0.0 {
+        Some(dot_product / (a_magnitude.sqrt() * b_magnitude.sqrt()))
+    } else {
+        None
+    }
+}
+
+/// Similarity between two memories (0.0 - 1.0)
+///
+/// Uses the embeddings when both memories have one, otherwise the overlap of
+/// their lowercase words.
+fn memory_similarity(a: &Memory, b: &Memory) -> f64 {
+    if let (Some(a_vec), Some(b_vec)) = (&a.embedding, &b.embedding) {
+        if let Some(similarity) = cosine_similarity(a_vec, b_vec) {
+            return similarity.clamp(0.0, 1.0);
+        }
+    }
+
+    let words = |text: &str| -> HashSet<String> {
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect()
+    };
+    let (a_words, b_words) = (words(&a.content), words(&b.content));
+    let union = a_words.union(&b_words).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a_words.intersection(&b_words).count() as f64 / union as f64
+}
+
+/// Rough token count of memory content, at about four characters per token
+fn estimated_tokens(content: &str) -> usize {
+    content.chars().count().div_ceil(4)
+}
+
+/// Open the configured memory store and recover its memories
+///
+/// Memories are kept in memory only when persistence is off, no store
+/// directory is set, or the store cannot be opened.
+fn open_store(config: &MemoryConfig) -> (Option<StoreWriter>, Vec<Memory>) {
+    if !config.persistence {
+        return (None, Vec::new());
+    }
+    if config.store.dir.is_none() {
+        log::warn!("Memory persistence is on but memory.store.dir is not set; memories will not be saved");
+        return (None, Vec::new());
+    }
+    match MemoryStore::open(config.store.clone()) {
+        Ok((store, memories)) => {
+            let report = store.recovery_report();
+            if !report.is_clean() {
+                log::warn!("Recovered memories in {} after an unclean shutdown: {:?}", store.dir().display(), report);
+            }
+            match StoreWriter::start(store, memories.clone()) {
+                Ok(writer) => (Some(writer), memories),
+                Err(e) => {
+                    log::error!("Failed to start the memory store writer; memories will not be saved: {}", e);
+                    (None, memories)
+                }
+            }
+        }
+        Err(e) => {
+            log::error!("Failed to open memory store; memories will not be saved: {}", e);
+            (None, Vec::new())
+        }
+    }
+}
+
+/// Hash of memory content, normalized for case and whitespace
+fn content_hash(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for word in content.split_whitespace() {
+        word.to_lowercase().hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Hash of a stored memory's content, computed if it was stored without one
+fn stored_hash(memory: &Memory) -> u64 {
+    memory.content_hash.unwrap_or_else(|| content_hash(&memory.content))
+}
+
+/// Record the content hash of memories about to be stored
+fn index_hashes(memories: &mut [Memory]) {
+    for memory in memories {
+        memory.content_hash = Some(content_hash(&memory.content));
+    }
+}
+
 impl PartialEq for Memory {
     fn eq(&self, other: &Self) -> bool {
         self.id == other.id
@@ -346,6 +498,49 @@ impl Ord for Memory {
     }
 }
 
+/// Statistics about embedding reuse and memory deduplication
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct DedupStats {
+    /// Embedding lookups served from the content-hash cache
+    pub cache_hits: usize,
+
+    /// Embedding lookups that had to generate a new embedding
+    pub cache_misses: usize,
+
+    /// Inserts merged into an existing memory with identical content
+    pub exact_merges: usize,
+
+    /// Inserts merged into an existing memory with a near-identical embedding
+    pub similar_merges: usize,
+}
+
+/// Bounded embedding cache keyed by content hash, evicting oldest entries first
+#[derive(Debug, Default)]
+struct EmbeddingCache {
+    entries: HashMap<u64, Vec<f32>>,
+    order: VecDeque<u64>,
+}
+
+impl EmbeddingCache {
+    fn get(&self, hash: u64) -> Option<Vec<f32>> {
+        self.entries.get(&hash).cloned()
+    }
+
+    fn insert(&mut self, hash: u64, embedding: Vec<f32>, capacity: usize) {
+        if capacity == 0 {
+            return;
+        }
+        if self.entries.insert(hash, embedding).is_none() {
+            self.order.push_back(hash);
+        }
+        while self.order.len() > capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+}
+
 /// Memory system for storing and retrieving agent memories
 pub struct MemorySystem {
     /// Configuration for the memory system
@@ -354,9 +549,19 @@ pub struct MemorySystem {
     /// Stored memories - includes both short-term and long-term
     memories: RwLock<Vec<Memory>>,
 
-    /// Embedding model for vector-based memory retrieval (lazily initialized)
+    /// Embeddings keyed by content hash, so identical content is embedded once
+    embedding_cache: RwLock<EmbeddingCache>,
+
+    /// Embedding cache and deduplication statistics
+    dedup_stats: RwLock<DedupStats>,
+
+    /// Writer for the journaled store the memories are persisted in, when
+    /// persistence is on
+    store: Option<StoreWriter>,
+
+    /// Service holding the embedding model, shared with other agents
     #[cfg(feature = "vector-memory")]
-    embedding_model: OnceCell<Arc<RwLock<dyn EmbeddingModel + Send + Sync>>>,
+    embeddings: Arc<EmbeddingService>,
 }
 
 impl std::fmt::Debug for MemorySystem {
@@ -379,82 +584,141 @@ impl MemorySystem {
     ///
     /// A new MemorySystem instance
     pub fn new(config: MemoryConfig) -> Self {
+        let (store, mut memories) = open_store(&config);
+        index_hashes(&mut memories);
+
         #[cfg(feature = "vector-memory")]
         return Self {
             config,
-            memories: RwLock::new(Vec::new()),
-            embedding_model: OnceCell::new(),
+            memories: RwLock::new(memories),
+            embedding_cache: RwLock::new(EmbeddingCache::default()),
+            dedup_stats: RwLock::new(DedupStats::default()),
+            store,
+            embeddings: EmbeddingService::global(),
         };
 
         #[cfg(not(feature = "vector-memory"))]
         return Self {
             config,
-            memories: RwLock::new(Vec::new()),
+            memories: RwLock::new(memories),
+            embedding_cache: RwLock::new(EmbeddingCache::default()),
+            dedup_stats: RwLock::new(DedupStats::default()),
+            store,
         };
     }
-    
-    /// Initialize the embedding model for vector memory
-    ///
-    /// This is called lazily the first time vector embeddings are needed.
-    #[cfg(feature = "vector-memory")]
-    async fn ensure_embedding_model(&self) -> Result<()> {
-        if !self.config.use_embeddings {
-            return Ok(());
+
+    /// What recovery found when the persisted memories were loaded, if
+    /// memories are persisted
+    pub fn recovery_report(&self) -> Option<&RecoveryReport> {
+        self.store.as_ref().map(StoreWriter::recovery_report)
+    }
+
+    /// Write every memory to a fresh snapshot and empty the journal, such as
+    /// before the game quits
+    ///
+    /// Does nothing unless memories are persisted.
+    pub async fn checkpoint(&self) -> Result<()> {
+        match &self.store {
+            Some(store) => {
+                let memories = self.memories.read().await.clone();
+                store.compact(memories).await
+            }
+            None => Ok(()),
         }
+    }
 
-        // Use OnceCell to safely initialize the model exactly once
-        self.embedding_model
-            .get_or_try_init(|| async {
-                // Initialize the appropriate model based on configuration
-                match self.config.embedding_model {
-                    EmbeddingModelType::MiniBert => {
-                        let model = MiniLMEmbedding::new()?;
-                        Ok(Arc::new(RwLock::new(model)) as Arc<RwLock<dyn EmbeddingModel + Send + Sync>>)
-                    }
-                    EmbeddingModelType::DistilBert => {
-                        Err(OxydeError::MemoryError(
-                            "DistilBert model not yet implemented".to_string(),
-                        ))
-                    }
-                    EmbeddingModelType::Custom => {
-                        if self.config.custom_model_path.is_some() {
-                            Err(OxydeError::MemoryError(
-                                "Custom models not yet supported".to_string(),
-                            ))
-                        } else {
-                            Err(OxydeError::MemoryError(
-                                "Custom model path not specified".to_string(),
-                            ))
-                        }
-                    }
-                }
-            })
-            .await?;
+    /// Queue a change to the memories to be journaled
+    ///
+    /// Called with the memories still locked, so changes are journaled in
+    /// the order they were made; the store writes them on its own thread.
+    /// Failures are logged rather than returned, since the change itself has
+    /// already been made.
+    fn persist(&self, op: JournalOp) {
+        if let Some(store) = &self.store {
+            store.append(op);
+        }
+    }
 
-        Ok(())
+    /// Journal the memory at `index` as added or changed
+    fn persist_memory(&self, memories: &[Memory], index: usize) {
+        if self.store.is_some() {
+            self.persist(JournalOp::Put { memory: memories[index].clone() });
+        }
+    }
+
+    /// Remove the memories `keep` rejects, journaling their removal
+    ///
+    /// # Returns
+    ///
+    /// Number of memories removed
+    fn retain_persisted(&self, memories: &mut Vec<Memory>, keep: impl Fn(&Memory) -> bool) -> usize {
+        let ids: Vec<String> = memories.iter().filter(|m| !keep(m)).map(|m| m.id.clone()).collect();
+        if ids.is_empty() {
+            return 0;
+        }
+        memories.retain(|m| keep(m));
+        let removed = ids.len();
+        self.persist(JournalOp::Remove { ids });
+        removed
+    }
+
+    /// Snapshot every memory after a change too broad to journal
+    fn persist_all(&self, memories: &[Memory]) {
+        if let Some(store) = &self.store {
+            store.replace(memories.to_vec());
+        }
     }
     
+    /// Use a separate embedding service instead of the global one
+    #[cfg(feature = "vector-memory")]
+    pub fn with_embedding_service(mut self, embeddings: Arc<EmbeddingService>) -> Self {
+        self.embeddings = embeddings;
+        self
+    }
+
     #[cfg(feature = "vector-memory")]
     async fn generate_embedding(&self, text: &str) -> Result<Option<Vec<f32>>> {
         if !self.config.use_embeddings {
             return Ok(None);
         }
+        let embedding = self.embeddings.embed(self.config.embedding_model.clone(), text).await?;
+        Ok(Some(embedding))
+    }
+    
+    /// Look up or generate the embedding for a piece of content
+    ///
+    /// Embeddings are cached by a hash of the normalized content, so the
+    /// embedding model only runs once per distinct text.
+    async fn embedding_for(&self, content: &str) -> Result<Option<Vec<f32>>> {
+        let hash = content_hash(content);
+        if let Some(embedding) = self.embedding_cache.read().await.get(hash) {
+            self.dedup_stats.write().await.cache_hits += 1;
+            return Ok(Some(embedding));
+        }
 
-        // Ensure model is initialized
-        self.ensure_embedding_model().await?;
-
-        // Get the initialized model from OnceCell
-        if let Some(model) = self.embedding_model.get() {
-            let model = model.read().await;
-            let embedding = model.embed(text)?;
-            Ok(Some(embedding))
-        } else {
-            Ok(None)
+        #[cfg(feature = "vector-memory")]
+        if let Some(embedding) = self.generate_embedding(content).await? {
+            self.dedup_stats.write().await.cache_misses += 1;
+            self.embedding_cache.write().await.insert(
+                hash,
+                embedding.clone(),
+                self.config.embedding_cache_size,
+            );
+            return Ok(Some(embedding));
         }
+
+        Ok(None)
     }
-    
+
     /// Add a memory to the system
     ///
+    /// If deduplication is enabled and an existing memory of the same category
+    /// and player scope has identical content or a near-identical embedding,
+    /// the new memory is merged into it instead: the existing memory's
+    /// importance is raised and its tags are extended. Memories about
+    /// different players, or about one player and none, are never merged, so
+    /// a merge cannot change who they can be recalled for.
+    ///
     /// # Arguments
     ///
     /// * `memory` - Memory to add
@@ -462,16 +726,68 @@ impl MemorySystem {
     /// # Returns
     ///
     /// Success or error
-    pub async fn add(&self, #[cfg_attr(not(feature = "vector-memory"), allow(unused_mut))] mut memory: Memory) -> Result<()> {
-        // Generate embedding for the memory if vector embeddings are enabled
-        #[cfg(feature = "vector-memory")]
-        if self.config.use_embeddings && memory.embedding.is_none() {
-            if let Some(embedding) = self.generate_embedding(&memory.content).await? {
-                memory.embedding = Some(embedding);
+    pub async fn add(&self, mut memory: Memory) -> Result<()> {
+        let hash = content_hash(&memory.content);
+        memory.content_hash = Some(hash);
+        match &memory.embedding {
+            // Caller-supplied embeddings seed the cache for later inserts
+            Some(embedding) => self.embedding_cache.write().await.insert(
+                hash,
+                embedding.clone(),
+                self.config.embedding_cache_size,
+            ),
+            None if self.config.use_embeddings => {
+                memory.embedding = self.embedding_for(&memory.content).await?;
             }
+            None => {}
         }
 
         let mut memories = self.memories.write().await;
+
+        if self.config.deduplicate {
+            let player = memory_player(&memory);
+            let mergeable = |m: &Memory| m.category == memory.category && memory_player(m) == player;
+            let exact = memories.iter().position(|m| mergeable(m) && stored_hash(m) == hash);
+            let similar = exact.is_none().then(|| {
+                memory.embedding.as_ref().and_then(|embedding| {
+                    memories.iter().position(|m| {
+                        mergeable(m)
+                            && m.embedding
+                                .as_ref()
+                                .and_then(|other| cosine_similarity(embedding, other))
+                                .is_some_and(|sim| sim > self.config.dedup_threshold)
+                    })
+                })
+            }).flatten();
+
+            if let Some(index) = exact.or(similar) {
+                let existing = &mut memories[index];
+                existing.importance = (existing.importance.max(memory.importance) + 0.1).min(1.0);
+                existing.permanent |= memory.permanent;
+                existing.visibility = existing.visibility.max(memory.visibility);
+                existing.emotional_intensity = existing.emotional_intensity.max(memory.emotional_intensity);
+                for tag in memory.tags {
+                    if !existing.tags.contains(&tag) {
+                        existing.tags.push(tag);
+                    }
+                }
+                for entity in memory.entities {
+                    if !existing.entities.contains(&entity) {
+                        existing.entities.push(entity);
+                    }
+                }
+                existing.touch();
+                self.persist_memory(&memories, index);
+
+                let mut stats = self.dedup_stats.write().await;
+                if exact.is_some() {
+                    stats.exact_merges += 1;
+                } else {
+                    stats.similar_merges += 1;
+                }
+                return Ok(());
+            }
+        }
         
         // Check if we need to remove a memory to stay under capacity
         if !memory.permanent && memories.len() >= self.config.capacity {
@@ -496,8 +812,10 @@ impl MemorySystem {
                     })
                     .map(|(i, _)| i)
                 {
-                    memories.remove(index);
+                    let evicted = memories.remove(index);
+                    self.persist(JournalOp::Remove { ids: vec![evicted.id] });
                     memories.push(memory);
+                    self.persist_memory(&memories, memories.len() - 1);
                     return Ok(());
                 }
             }
@@ -517,7 +835,8 @@ impl MemorySystem {
                 })
                 .map(|(i, _)| i)
             {
-                memories.remove(index);
+                let evicted = memories.remove(index);
+                self.persist(JournalOp::Remove { ids: vec![evicted.id] });
             } else {
                 return Err(OxydeError::MemoryError(
                     "Memory capacity reached and all memories are permanent".to_string()
@@ -526,6 +845,7 @@ impl MemorySystem {
         }
         
         memories.push(memory);
+        self.persist_memory(&memories, memories.len() - 1);
         Ok(())
     }
     
@@ -551,6 +871,27 @@ impl MemorySystem {
         }
     }
     
+    /// Change a memory's visibility
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - ID of the memory to update
+    /// * `visibility` - New visibility
+    ///
+    /// # Returns
+    ///
+    /// Success, or an error if no memory has the ID
+    pub async fn set_visibility(&self, id: &str, visibility: MemoryVisibility) -> Result<()> {
+        let mut memories = self.memories.write().await;
+        let index = memories
+            .iter()
+            .position(|m| m.id == id)
+            .ok_or_else(|| OxydeError::MemoryError(format!("Memory with ID {} not found", id)))?;
+        memories[index].visibility = visibility;
+        self.persist_memory(&memories, index);
+        Ok(())
+    }
+
     /// Retrieve memories by category
     ///
     /// # Arguments
@@ -580,6 +921,20 @@ impl MemorySystem {
         result
     }
     
+    /// Get the most recently added memories without marking them accessed
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - Maximum number of memories to return
+    ///
+    /// # Returns
+    ///
+    /// Up to `limit` memories, oldest first
+    pub async fn recent(&self, limit: usize) -> Vec<Memory> {
+        let memories = self.memories.read().await;
+        memories[memories.len().saturating_sub(limit)..].to_vec()
+    }
+
     /// Retrieve memories by tag
     ///
     /// # Arguments
@@ -609,8 +964,77 @@ impl MemorySystem {
         result
     }
     
+    /// Forget the fact learned under a key, even though facts are permanent
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Key the fact was learned under
+    ///
+    /// # Returns
+    ///
+    /// Number of memories forgotten
+    pub async fn forget_fact(&self, key: &str) -> usize {
+        let tag = fact_tag(key);
+        let mut memories = self.memories.write().await;
+        self.retain_persisted(&mut memories, |m| !m.tags.contains(&tag))
+    }
+
+    /// Retrieve every memory that mentions an entity, oldest first
+    ///
+    /// Entities are matched by name, ignoring case and leading articles; a
+    /// partial name matches entities containing all of its words, so
+    /// "amulet" finds memories about "the stolen amulet".
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - Name of the person, place or item
+    ///
+    /// # Returns
+    ///
+    /// Vector of matching memories in the order they were formed
+    pub async fn related_to(&self, entity: &str) -> Vec<Memory> {
+        let query = normalize_entity(entity);
+        let mut memories = self.memories.write().await;
+
+        let mut result = Vec::new();
+        for memory in memories.iter_mut() {
+            if memory.entities.iter().any(|e| entity_matches(e, &query)) {
+                memory.touch();
+                result.push(memory.clone());
+            }
+        }
+        result.sort_by_key(|m| m.created_at);
+        result
+    }
+
+    /// Retrieve the memories that share an entity with a memory
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - ID of the memory to follow links from
+    ///
+    /// # Returns
+    ///
+    /// Vector of linked memories, oldest first
+    pub async fn linked_memories(&self, id: &str) -> Vec<Memory> {
+        let memories = self.memories.read().await;
+        let linked = MemoryGraph::build(memories.iter()).linked(id);
+        let mut result: Vec<Memory> = memories.iter().filter(|m| linked.contains(&m.id)).cloned().collect();
+        result.sort_by_key(|m| m.created_at);
+        result
+    }
+
+    /// Build the entity graph linking stored memories
+    pub async fn entity_graph(&self) -> MemoryGraph {
+        MemoryGraph::build(self.memories.read().await.iter())
+    }
+
     /// Retrieve memories most relevant to a query
     ///
+    /// Memories are picked by maximal marginal relevance so near-identical
+    /// memories don't crowd out distinct information, then limited by the
+    /// configured per-category cap and token budget.
+    ///
     /// # Arguments
     ///
     /// * `query` - Query to find relevant memories for
@@ -619,9 +1043,77 @@ impl MemorySystem {
     ///
     /// # Returns
     ///
-    /// Vector of relevant memories, sorted by relevance
+    /// Vector of relevant memories in the order they were selected
     pub async fn retrieve_relevant(&self, query: &str, limit: usize, query_embedding: Option<&[f32]>) -> Result<Vec<Memory>> {
+        self.retrieve(query, limit, query_embedding, None, &|_| true, &[]).await
+    }
+
+    /// Retrieve memories most relevant to a query, weighed by the agent's mood
+    ///
+    /// Like [`MemorySystem::retrieve_relevant`], but when `mood_congruence`
+    /// is enabled, memories whose emotional valence matches the mood's are
+    /// more relevant and memories of the opposite valence less.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - Query to find relevant memories for
+    /// * `limit` - Maximum number of memories to return
+    /// * `query_embedding` - Optional vector embedding of the query for semantic search
+    /// * `mood_valence` - Valence of the agent's current emotions, from -1.0 to 1.0
+    pub async fn retrieve_relevant_in_mood(
+        &self,
+        query: &str,
+        limit: usize,
+        query_embedding: Option<&[f32]>,
+        mood_valence: f64,
+    ) -> Result<Vec<Memory>> {
+        self.retrieve(query, limit, query_embedding, Some(mood_valence), &|_| true, &[]).await
+    }
+
+    /// Retrieve the memories most relevant to a query among those a filter
+    /// keeps, weighed by the agent's mood
+    ///
+    /// Like [`MemorySystem::retrieve_relevant_in_mood`], but memories `keep`
+    /// rejects are skipped before scoring, so they never take the place of
+    /// one it keeps. Memories held elsewhere, such as in a shared pool, are
+    /// scored and selected alongside the system's own, except copies of
+    /// memories it holds itself.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - Query to find relevant memories for
+    /// * `limit` - Maximum number of memories to return
+    /// * `query_embedding` - Optional vector embedding of the query for semantic search
+    /// * `mood_valence` - Valence of the agent's current emotions, from -1.0 to 1.0
+    /// * `keep` - Whether a memory may be retrieved
+    /// * `extra` - Memories from outside the system to rank with its own
+    pub async fn retrieve_relevant_matching(
+        &self,
+        query: &str,
+        limit: usize,
+        query_embedding: Option<&[f32]>,
+        mood_valence: f64,
+        keep: impl Fn(&Memory) -> bool + Sync,
+        extra: &[Memory],
+    ) -> Result<Vec<Memory>> {
+        self.retrieve(query, limit, query_embedding, Some(mood_valence), &keep, extra).await
+    }
+
+    async fn retrieve(
+        &self,
+        query: &str,
+        limit: usize,
+        query_embedding: Option<&[f32]>,
+        mood_valence: Option<f64>,
+        keep: &(dyn Fn(&Memory) -> bool + Sync),
+        extra: &[Memory],
+    ) -> Result<Vec<Memory>> {
         let mut memories = self.memories.write().await;
+        let held: HashSet<&str> = match extra.is_empty() {
+            true => HashSet::new(),
+            false => memories.iter().map(|memory| memory.content.as_str()).collect(),
+        };
+        let extra: Vec<&Memory> = extra.iter().filter(|memory| !held.contains(memory.content.as_str())).collect();
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap_or(Duration::from_secs(0))
@@ -660,30 +1152,14 @@ impl MemorySystem {
         // Calculate relevance scores and apply time decay
         let mut scored_memories: BinaryHeap<ScoredMemory> = BinaryHeap::new();
         
-        for memory in memories.iter() {
-            // Apply recency bias based on access count and last access time
-            let recency_factor = if memory.access_count > 0 {
-                // Frequently accessed memories are more relevant
-                let access_frequency = (memory.access_count as f64).min(10.0) / 10.0;
-                // Recently accessed memories are more relevant
-                let last_access_age = now.saturating_sub(memory.last_accessed) as f64;
-                let last_access_factor = (-self.config.decay_rate * (last_access_age / 86400.0)).exp();
-                
-                0.7 + (0.3 * access_frequency * last_access_factor)
-            } else {
-                1.0 // No recency bias for memories that haven't been accessed
-            };
-            
-            // Calculate time decay factor (1.0 for new memories, approaches 0 for old ones)
-            let age_seconds = now.saturating_sub(memory.created_at);
-            let decay_factor = if memory.permanent {
-                1.0
-            } else {
-                (-self.config.decay_rate * (age_seconds as f64 / 86400.0)).exp() // 86400 seconds in a day
-            };
-            
-            // Calculate relevance using the enhanced relevance function with embeddings
-            let relevance = memory.relevance(query, query_embedding) * decay_factor * recency_factor;
+        let scorer = RetrievalScorer::new(self.config.retrieval, query, memories.iter().chain(extra.iter().copied()));
+        for memory in memories.iter().filter(|memory| keep(memory)).chain(extra.iter().copied()) {
+            // Blend keyword, vector, importance and emotion signals, discounted by age
+            let recency = recency(memory, self.config.decay_rate, now);
+            let mut relevance = scorer.score(memory, query_embedding, recency);
+            if let Some(mood_valence) = mood_valence {
+                relevance *= self.config.mood_congruence.factor(memory.emotional_valence, mood_valence);
+            }
             
             // Calculate category priority bonus
             let category_priority_bonus = if has_priority_categories {
@@ -708,44 +1184,144 @@ impl MemorySystem {
             }
         }
         
-        // Extract top memories
-        let mut result = Vec::with_capacity(limit);
+        // Select memories by maximal marginal relevance: each pick trades its
+        // relevance against its similarity to the memories already picked
+        let mut candidates = scored_memories.into_sorted_vec();
+        candidates.reverse();
+        let lambda = self.config.mmr_lambda;
+        let mut result: Vec<Memory> = Vec::with_capacity(limit);
+        let mut category_counts: HashMap<MemoryCategory, usize> = HashMap::new();
+        let mut remaining_tokens = self.config.retrieval_token_budget;
         
         // Keep track of short-term and long-term memories
         let mut short_term_count = 0;
         
-        for _ in 0..limit {
-            if let Some(scored_memory) = scored_memories.pop() {
-                // Check if we've already reached the short-term memory limit
-                let is_short_term = now.saturating_sub(scored_memory.memory.created_at) < 3600; // Less than 1 hour old
-                
-                if is_short_term && short_term_count >= self.config.short_term_capacity {
-                    // Skip this short-term memory if we've reached the limit, unless it's very important
-                    if scored_memory.memory.importance < 0.8 {
-                        continue;
-                    }
-                }
-                
-                if is_short_term {
-                    short_term_count += 1;
+        while result.len() < limit && !candidates.is_empty() {
+            let best = candidates
+                .iter()
+                .enumerate()
+                .map(|(index, candidate)| {
+                    let redundancy = result
+                        .iter()
+                        .map(|selected| memory_similarity(&candidate.memory, selected))
+                        .fold(0.0, f64::max);
+                    let relevance = candidate.score + candidate.category_priority_bonus;
+                    (index, lambda * relevance - (1.0 - lambda) * redundancy)
+                })
+                .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal))
+                .map(|(index, _)| index)
+                .unwrap_or(0);
+            let scored_memory = candidates.remove(best);
+
+            // Check if we've already reached the short-term memory limit
+            let is_short_term = now.saturating_sub(scored_memory.memory.created_at) < 3600; // Less than 1 hour old
+            
+            if is_short_term && short_term_count >= self.config.short_term_capacity {
+                // Skip this short-term memory if we've reached the limit, unless it's very important
+                if scored_memory.memory.importance < 0.8 {
+                    continue;
                 }
-                
-                // Update last_accessed for this memory
-                if let Some(index) = memories.iter().position(|m| m.id == scored_memory.memory.id) {
-                    let mut updated = memories[index].clone();
-                    updated.touch();
-                    memories[index] = updated;
+            }
+
+            // Keep any one category from crowding out the others
+            let category_count = category_counts.entry(scored_memory.memory.category).or_default();
+            if self.config.max_per_category.is_some_and(|max| *category_count >= max) {
+                continue;
+            }
+
+            // Skip memories that no longer fit in the prompt budget
+            let tokens = estimated_tokens(&scored_memory.memory.content);
+            if let Some(remaining) = remaining_tokens.as_mut() {
+                if tokens > *remaining {
+                    continue;
                 }
-                
-                result.push(scored_memory.memory);
-            } else {
-                break;
+                *remaining -= tokens;
+            }
+
+            *category_count += 1;
+            if is_short_term {
+                short_term_count += 1;
+            }
+            
+            // Update last_accessed for this memory
+            if let Some(index) = memories.iter().position(|m| m.id == scored_memory.memory.id) {
+                let mut updated = memories[index].clone();
+                updated.touch();
+                memories[index] = updated;
             }
+            
+            result.push(scored_memory.memory);
         }
         
         Ok(result)
     }
     
+    /// Load the embedding model and embed texts ahead of the first retrieval
+    ///
+    /// Embeddings land in the content-hash cache, so memories and queries
+    /// with the same text reuse them.
+    ///
+    /// # Arguments
+    ///
+    /// * `texts` - Texts to embed, such as the agent's knowledge entries
+    ///
+    /// # Returns
+    ///
+    /// Number of texts with a cached embedding; 0 when embeddings are disabled
+    pub async fn warm_up(&self, texts: &[String]) -> Result<usize> {
+        if !self.config.use_embeddings {
+            return Ok(0);
+        }
+        let mut embedded = 0;
+        for text in texts {
+            if self.embedding_for(text).await?.is_some() {
+                embedded += 1;
+            }
+        }
+        Ok(embedded)
+    }
+
+    /// Get embedding cache and deduplication statistics
+    pub async fn dedup_stats(&self) -> DedupStats {
+        self.dedup_stats.read().await.clone()
+    }
+
+    /// Get distributions of memory age, importance, access frequency and retention
+    pub async fn stats(&self) -> MemoryStats {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or(Duration::from_secs(0))
+            .as_secs();
+        MemoryStats::from_memories(&self.memories.read().await, &self.config, now)
+    }
+
+    /// Snapshot the memories and configuration, for saving and offline reports
+    pub async fn session(&self) -> MemorySession {
+        MemorySession {
+            config: self.config.clone(),
+            memories: self.memories.read().await.clone(),
+        }
+    }
+
+    /// Replace all memories, for example with ones loaded from a save
+    pub async fn restore(&self, mut memories: Vec<Memory>) {
+        index_hashes(&mut memories);
+        let mut current = self.memories.write().await;
+        *current = memories;
+        self.persist_all(&current);
+    }
+
+    /// IDs of all stored memories
+    pub(crate) async fn ids(&self) -> Vec<String> {
+        self.memories.read().await.iter().map(|m| m.id.clone()).collect()
+    }
+
+    /// Remove memories by ID, including permanent ones
+    pub(crate) async fn remove_ids(&self, ids: &[String]) -> usize {
+        let mut memories = self.memories.write().await;
+        self.retain_persisted(&mut memories, |m| !ids.contains(&m.id))
+    }
+
     /// Forget a memory
     ///
     /// # Arguments
@@ -765,7 +1341,8 @@ impl MemorySystem {
                 ));
             }
             
-            memories.remove(index);
+            let forgotten = memories.remove(index);
+            self.persist(JournalOp::Remove { ids: vec![forgotten.id] });
             Ok(())
         } else {
             Err(OxydeError::MemoryError(
@@ -785,11 +1362,7 @@ impl MemorySystem {
     /// Number of memories forgotten
     pub async fn forget_by_category(&self, category: MemoryCategory) -> usize {
         let mut memories = self.memories.write().await;
-        
-        let initial_len = memories.len();
-        memories.retain(|m| m.category != category || m.permanent);
-        
-        initial_len - memories.len()
+        self.retain_persisted(&mut memories, |m| m.category != category || m.permanent)
     }
     
     /// Forget memories with a specific tag
@@ -803,13 +1376,39 @@ impl MemorySystem {
     /// Number of memories forgotten
     pub async fn forget_by_tag(&self, tag: &str) -> usize {
         let mut memories = self.memories.write().await;
-        
-        let initial_len = memories.len();
-        memories.retain(|m| !m.tags.contains(&tag.to_string()) || m.permanent);
-        
-        initial_len - memories.len()
+        self.retain_persisted(&mut memories, |m| !m.tags.iter().any(|t| t == tag) || m.permanent)
     }
     
+    /// Apply the configured forgetting policy
+    ///
+    /// Memories that expired are removed and emotional intensities fade; see
+    /// [`crate::forgetting`]. Nothing changes while the policy is disabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - Current game time
+    /// * `elapsed_days` - Game days since the policy was last applied
+    ///
+    /// # Returns
+    ///
+    /// How many memories expired and faded
+    pub async fn apply_forgetting(&self, now: &GameTime, elapsed_days: f64) -> ForgettingReport {
+        let policy = &self.config.forgetting;
+        if !policy.enabled {
+            return ForgettingReport::default();
+        }
+        let mut memories = self.memories.write().await;
+        let changes = policy.apply_tracked(&mut memories, now, elapsed_days);
+        let report = changes.report();
+        if !changes.expired.is_empty() {
+            self.persist(JournalOp::Remove { ids: changes.expired });
+        }
+        if !changes.faded.is_empty() {
+            self.persist(JournalOp::Fade { intensities: changes.faded });
+        }
+        report
+    }
+
     /// Clear all non-permanent memories
     ///
     /// # Returns
@@ -817,11 +1416,7 @@ impl MemorySystem {
     /// Number of memories cleared
     pub async fn clear(&self) -> usize {
         let mut memories = self.memories.write().await;
-        
-        let initial_len = memories.len();
-        memories.retain(|m| m.permanent);
-        
-        initial_len - memories.len()
+        self.retain_persisted(&mut memories, |m| m.permanent)
     }
     
     /// Get the total number of memories
@@ -1030,6 +1625,7 @@ mod tests {
         let config = MemoryConfig {
             capacity: 3,
             persistence: false,
+            store: Default::default(),
             decay_rate: 0.05,
             importance_threshold: 0.2,
             short_term_capacity: 5,
@@ -1038,6 +1634,17 @@ mod tests {
             custom_model_path: None,
             embedding_dimension: 384,
             priority_categories: Vec::new(),
+            deduplicate: true,
+            dedup_threshold: 0.97,
+            embedding_cache_size: 16,
+            mmr_lambda: 0.7,
+            max_per_category: None,
+            retrieval_token_budget: None,
+            retrieval: Default::default(),
+            reflection: Default::default(),
+            forgetting: Default::default(),
+            sessions: Default::default(),
+            mood_congruence: Default::default(),
         };
 
         let system = MemorySystem::new(config);
@@ -1067,4 +1674,196 @@ mod tests {
         system.add(Memory::new(MemoryCategory::Semantic, "Fire is hot", 0.6, Some(vec!["fact".to_string()]))).await.unwrap();
         assert_eq!(system.count().await, 3); // Still 3 due to capacity limit
     }
+
+    #[tokio::test]
+    async fn test_memory_deduplication() {
+        let system = MemorySystem::new(MemoryConfig::default());
+
+        system.add(Memory::new(MemoryCategory::Episodic, "The bridge is out", 0.4, None)).await.unwrap();
+        system.add(Memory::new(MemoryCategory::Episodic, "the  bridge is OUT", 0.3, Some(vec!["travel".to_string()]))).await.unwrap();
+
+        let mut first = Memory::new(MemoryCategory::Semantic, "Dragons sleep by day", 0.5, None);
+        first.set_embedding(vec![1.0, 0.0, 0.0]);
+        system.add(first).await.unwrap();
+
+        let mut near = Memory::new(MemoryCategory::Semantic, "Dragons nap during the day", 0.5, None);
+        near.set_embedding(vec![0.99, 0.01, 0.0]);
+        system.add(near).await.unwrap();
+
+        let mut distinct = Memory::new(MemoryCategory::Semantic, "Wolves hunt at night", 0.5, None);
+        distinct.set_embedding(vec![0.0, 1.0, 0.0]);
+        system.add(distinct).await.unwrap();
+
+        assert_eq!(system.count().await, 3);
+
+        let episodic = system.get_by_category(MemoryCategory::Episodic).await;
+        assert!((episodic[0].importance - 0.5).abs() < 1e-9);
+        assert_eq!(episodic[0].tags, vec!["travel".to_string()]);
+
+        let stats = system.dedup_stats().await;
+        assert_eq!(stats.exact_merges, 1);
+        assert_eq!(stats.similar_merges, 1);
+    }
+
+    #[tokio::test]
+    async fn test_deduplication_keeps_player_scopes_apart() {
+        use crate::session::{player_tag, recallable_for};
+
+        let system = MemorySystem::new(MemoryConfig::default());
+        let about = |player: Option<&str>| {
+            let tags = player.map(|player| vec![player_tag(player)]);
+            Memory::new(MemoryCategory::Episodic, "I promised to find the ring", 0.5, tags)
+        };
+        system.add(about(Some("alice"))).await.unwrap();
+        system.add(about(Some("bob"))).await.unwrap();
+        system.add(about(None)).await.unwrap();
+        system.add(about(Some("alice"))).await.unwrap();
+
+        assert_eq!(system.count().await, 3);
+        assert_eq!(system.dedup_stats().await.exact_merges, 1);
+        let memories = system.get_by_category(MemoryCategory::Episodic).await;
+        assert_eq!(memories.iter().filter(|m| recallable_for(m, Some("bob"))).count(), 2);
+        assert_eq!(memories.iter().filter(|m| recallable_for(m, None)).count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_mood_congruent_recall() {
+        let system = MemorySystem::new(MemoryConfig {
+            mood_congruence: crate::retrieval::MoodCongruence { enabled: true, strength: 0.8 },
+            ..Default::default()
+        });
+        let memory = |content: &str, valence: f64| Memory::new_emotional(MemoryCategory::Episodic, content, 0.6, valence, 0.5, None);
+        system.add(memory("The festival was wonderful", 0.8)).await.unwrap();
+        system.add(memory("The festival was ruined by rain", -0.8)).await.unwrap();
+
+        let recall = |valence: f64| {
+            let system = &system;
+            async move { system.retrieve_relevant_in_mood("festival", 1, None, valence).await.unwrap() }
+        };
+        assert!(recall(-0.9).await[0].content.contains("ruined"));
+        assert!(recall(0.9).await[0].content.contains("wonderful"));
+    }
+
+    #[tokio::test]
+    async fn test_retrieval_prefers_distinct_memories() {
+        let retrieve = |config: MemoryConfig| async move {
+            let system = MemorySystem::new(config);
+            for content in [
+                "The blacksmith sells iron swords",
+                "The blacksmith sells iron swords cheaply",
+                "The blacksmith sells fine iron swords",
+            ] {
+                system.add(Memory::new(MemoryCategory::Semantic, content, 0.5, None)).await.unwrap();
+            }
+            system
+                .add(Memory::new(MemoryCategory::Episodic, "The blacksmith lives by the river", 0.5, None))
+                .await
+                .unwrap();
+            let memories = system.retrieve_relevant("blacksmith iron swords", 2, None).await.unwrap();
+            memories.into_iter().map(|m| m.content).collect::<Vec<_>>()
+        };
+
+        let relevance_only = retrieve(MemoryConfig { mmr_lambda: 1.0, ..Default::default() }).await;
+        assert!(relevance_only.iter().all(|content| content.contains("swords")));
+
+        let diverse = retrieve(MemoryConfig { mmr_lambda: 0.5, ..Default::default() }).await;
+        assert_eq!(diverse.len(), 2);
+        assert!(diverse[0].contains("swords"));
+        assert_eq!(diverse[1], "The blacksmith lives by the river");
+
+        let capped = retrieve(MemoryConfig { mmr_lambda: 1.0, max_per_category: Some(1), ..Default::default() }).await;
+        assert_eq!(capped[1], "The blacksmith lives by the river");
+
+        let budgeted = retrieve(MemoryConfig { retrieval_token_budget: Some(8), ..Default::default() }).await;
+        assert_eq!(budgeted, vec!["The blacksmith sells iron swords".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_related_to_entity() {
+        let system = MemorySystem::new(MemoryConfig::default());
+
+        system.add(Memory::new(MemoryCategory::Episodic, "Someone stole the silver amulet from the shrine", 0.5, None)).await.unwrap();
+        system.add(Memory::new(MemoryCategory::Episodic, "Garrick was seen selling the amulet in Old Harbor", 0.5, None)).await.unwrap();
+        system.add(Memory::new(MemoryCategory::Semantic, "Bread costs two coins", 0.5, None)).await.unwrap();
+
+        let history = system.related_to("the stolen amulet").await;
+        assert!(history.is_empty());
+
+        let history = system.related_to("The Amulet").await;
+        assert_eq!(history.len(), 2);
+        assert!(history.iter().all(|m| m.access_count == 1));
+
+        let garrick = system.related_to("garrick").await;
+        let linked = system.linked_memories(&garrick[0].id).await;
+        assert_eq!(linked.len(), 1);
+        assert!(linked[0].content.contains("shrine"));
+    }
+
+    #[tokio::test]
+    async fn test_memories_persist_across_restarts() {
+        let dir = std::env::temp_dir().join(format!("oxyde-memory-{}", Uuid::new_v4()));
+        let config = MemoryConfig {
+            persistence: true,
+            store: crate::memory_store::MemoryStoreConfig {
+                dir: Some(dir.clone()),
+                compact_after: 2,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let system = MemorySystem::new(config.clone());
+        system.add(Memory::new(MemoryCategory::Episodic, "The player returned my ring", 0.9, None)).await.unwrap();
+        system.add(Memory::new(MemoryCategory::Semantic, "Bread costs two coins", 0.5, None)).await.unwrap();
+        system.add(Memory::new(MemoryCategory::Semantic, "The mill burned down", 0.5, None)).await.unwrap();
+        assert_eq!(system.forget_by_category(MemoryCategory::Semantic).await, 2);
+        drop(system);
+
+        let restarted = MemorySystem::new(config);
+        assert!(restarted.recovery_report().unwrap().is_clean());
+        let memories = restarted.recent(10).await;
+        assert_eq!(memories.len(), 1);
+        assert_eq!(memories[0].content, "The player returned my ring");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_forgetting_is_journaled_without_a_new_snapshot() {
+        let dir = std::env::temp_dir().join(format!("oxyde-memory-{}", Uuid::new_v4()));
+        let config = MemoryConfig {
+            persistence: true,
+            store: crate::memory_store::MemoryStoreConfig {
+                dir: Some(dir.clone()),
+                ..Default::default()
+            },
+            forgetting: serde_yaml::from_str(
+                "enabled: true\n\
+                 rules:\n  \
+                   - category: episodic\n    expire_after_days: 7\n  \
+                   - category: emotional\n    fade_per_day: 0.5\n",
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+
+        let system = MemorySystem::new(config.clone());
+        let stale = Memory::new(MemoryCategory::Episodic, "The player stole an apple", 0.5, None)
+            .with_game_time(GameTime::new(1, 12.0));
+        system.add(stale).await.unwrap();
+        system.add(Memory::new_emotional(MemoryCategory::Emotional, "My dog died", 0.5, -0.9, 0.8, None)).await.unwrap();
+        system.checkpoint().await.unwrap();
+
+        let report = system.apply_forgetting(&GameTime::new(8, 12.0), 1.0).await;
+        assert_eq!(report, ForgettingReport { expired: 1, faded: 1 });
+        drop(system);
+
+        // Both changes come back from the journal rather than a rewritten snapshot
+        let restarted = MemorySystem::new(config);
+        let recovery = restarted.recovery_report().unwrap();
+        assert_eq!((recovery.snapshot_memories, recovery.replayed), (2, 2));
+        let memories = restarted.recent(10).await;
+        assert_eq!(memories.len(), 1);
+        assert!((memories[0].emotional_intensity - 0.4).abs() < 1e-9);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
 }
@@ -0,0 +1,192 @@
+//! Memories shared by a group of agents
+//!
+//! A family of NPCs, such as the staff of a tavern, attach to one
+//! [`SharedMemoryPool`] so that what one of them learns (the player's room
+//! reservation) the others recall too. Each agent attaches with a
+//! [`PoolAccess`] saying whether it reads the pool, and which of its own
+//! memories it writes to it:
+//!
+//! ```no_run
+//! # use std::sync::Arc;
+//! # use oxyde::config::MemoryConfig;
+//! # use oxyde::memory::MemoryCategory;
+//! # use oxyde::memory_pool::{PoolAccess, SharedMemoryPool};
+//! # async fn example(innkeeper: &oxyde::Agent, barmaid: &oxyde::Agent) {
+//! let staff = Arc::new(SharedMemoryPool::new("tavern_staff", MemoryConfig::default()));
+//! innkeeper
+//!     .attach_memory_pool(staff.clone(), PoolAccess::read_write().sharing_tags(&["reservation"]))
+//!     .await;
+//! barmaid.attach_memory_pool(staff, PoolAccess::read_only()).await;
+//! # }
+//! ```
+//!
+//! Pooled memories are recalled alongside personal ones, ranked with the
+//! agent's own retrieval settings and mood, and labeled with the pool they
+//! came from in prompts.
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::MemoryConfig;
+use crate::memory::{Memory, MemoryCategory, MemorySystem};
+use crate::Result;
+
+/// How an agent uses a shared memory pool
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PoolAccess {
+    /// Whether the agent recalls the pool's memories
+    #[serde(default)]
+    pub read: bool,
+
+    /// Whether the agent adds memories to the pool
+    #[serde(default)]
+    pub write: bool,
+
+    /// The agent's memories with any of these tags are added to the pool
+    #[serde(default)]
+    pub share_tags: Vec<String>,
+
+    /// The agent's memories of these categories are added to the pool
+    #[serde(default)]
+    pub share_categories: Vec<MemoryCategory>,
+}
+
+impl PoolAccess {
+    /// Recall the pool's memories without adding to it
+    pub fn read_only() -> Self {
+        Self {
+            read: true,
+            ..Default::default()
+        }
+    }
+
+    /// Recall the pool's memories and add to it
+    ///
+    /// Only memories shared explicitly with [`crate::Agent::share_memory`] are
+    /// added until tags or categories to share are set.
+    pub fn read_write() -> Self {
+        Self {
+            read: true,
+            write: true,
+            ..Default::default()
+        }
+    }
+
+    /// Add the agent's memories with any of these tags to the pool
+    pub fn sharing_tags(mut self, tags: &[&str]) -> Self {
+        self.share_tags.extend(tags.iter().map(|tag| tag.to_string()));
+        self
+    }
+
+    /// Add the agent's memories of these categories to the pool
+    pub fn sharing_categories(mut self, categories: &[MemoryCategory]) -> Self {
+        self.share_categories.extend_from_slice(categories);
+        self
+    }
+
+    /// Whether a memory the agent forms is added to the pool
+    pub fn shares(&self, memory: &Memory) -> bool {
+        self.write
+            && (self.share_categories.contains(&memory.category)
+                || memory.tags.iter().any(|tag| self.share_tags.contains(tag)))
+    }
+}
+
+/// Memories several agents recall and add to
+#[derive(Debug)]
+pub struct SharedMemoryPool {
+    name: String,
+    memory: MemorySystem,
+}
+
+impl SharedMemoryPool {
+    /// Create an empty pool
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name memories recalled from the pool are labeled with
+    /// * `config` - Storage and retrieval settings for the pool; pools are
+    ///   not persisted, so `persistence` is ignored
+    pub fn new(name: &str, config: MemoryConfig) -> Self {
+        Self {
+            name: name.to_string(),
+            memory: MemorySystem::new(MemoryConfig {
+                persistence: false,
+                ..config
+            }),
+        }
+    }
+
+    /// Get the pool's name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Add a memory to the pool
+    pub async fn add(&self, mut memory: Memory) -> Result<()> {
+        memory.source = Some(self.name.clone());
+        self.memory.add(memory).await
+    }
+
+    /// Retrieve the pool's memories relevant to a query
+    pub async fn retrieve_relevant(&self, query: &str, limit: usize) -> Result<Vec<Memory>> {
+        self.memory.retrieve_relevant(query, limit, None).await
+    }
+
+    /// Get the pool's underlying memory system
+    pub fn memory(&self) -> &MemorySystem {
+        &self.memory
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_pool_labels_and_merges_memories() {
+        let pool = SharedMemoryPool::new("tavern_staff", MemoryConfig::default());
+        let reservation = Memory::new(
+            MemoryCategory::Episodic,
+            "The traveler reserved the corner room for tonight",
+            0.8,
+            Some(vec!["reservation".to_string()]),
+        );
+        let access = PoolAccess::read_write().sharing_tags(&["reservation"]);
+        assert!(access.shares(&reservation));
+        assert!(!PoolAccess::read_only().sharing_tags(&["reservation"]).shares(&reservation));
+        pool.add(reservation.clone()).await.unwrap();
+
+        let pooled = pool.retrieve_relevant("Is my room reserved?", 5).await.unwrap();
+        assert_eq!(pooled[0].source.as_deref(), Some("tavern_staff"));
+        assert!(pooled[0].labeled_content().starts_with("[shared by tavern_staff]"));
+
+        // A pooled copy of a memory the agent holds is recalled once, as its own
+        let personal = MemorySystem::new(MemoryConfig::default());
+        personal.add(reservation).await.unwrap();
+        personal
+            .add(Memory::new(MemoryCategory::Episodic, "The cellar needs restocking", 0.2, None))
+            .await
+            .unwrap();
+        let merged = personal
+            .retrieve_relevant_matching("Is my room reserved?", 5, None, 0.0, |_| true, &pooled)
+            .await
+            .unwrap();
+        assert_eq!(merged.iter().filter(|memory| memory.content.contains("reserved")).count(), 1);
+        assert!(merged[0].source.is_none());
+
+        // Pooled memories are ranked with the agent's own
+        let news = Memory::new(MemoryCategory::Episodic, "The corner room has a leaking roof", 0.9, None);
+        pool.add(news).await.unwrap();
+        let pooled = pool.retrieve_relevant("Is the corner room reserved?", 5).await.unwrap();
+        let merged = personal
+            .retrieve_relevant_matching("Is the corner room reserved?", 5, None, 0.0, |_| true, &pooled)
+            .await
+            .unwrap();
+        let shared: Vec<&str> = merged
+            .iter()
+            .filter(|memory| memory.source.is_some())
+            .map(|memory| memory.content.as_str())
+            .collect();
+        assert_eq!(shared, ["The corner room has a leaking roof"]);
+    }
+}
@@ -0,0 +1,135 @@
+//! Natural-language questions about what an agent remembers
+//!
+//! Quest scripts and engine-side logic often need to know what an NPC knows
+//! without searching its memories by hand. `Agent::ask_memory` retrieves the
+//! memories relevant to a question, asks the model to answer from them alone,
+//! and returns a concise answer with the IDs of the memories supporting it:
+//!
+//! ```no_run
+//! # async fn example(agent: &oxyde::Agent) -> oxyde::Result<()> {
+//! let answer = agent.ask_memory("What does this NPC know about the mayor?").await?;
+//! if !answer.memory_ids.is_empty() {
+//!     println!("{} ({:?})", answer.answer, answer.memory_ids);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Questions nothing is remembered about are answered without an inference
+//! request.
+
+use serde::{Deserialize, Serialize};
+
+use crate::memory::Memory;
+
+/// Maximum memories retrieved to answer a question
+pub const ASK_MEMORY_LIMIT: usize = 8;
+
+/// Maximum tokens the model may generate per answer
+pub const ASK_MEMORY_MAX_TOKENS: usize = 150;
+
+/// Answer to a question about an agent's memories
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemoryAnswer {
+    /// Concise answer, based only on the agent's memories
+    pub answer: String,
+
+    /// IDs of the memories supporting the answer, most relevant first
+    pub memory_ids: Vec<String>,
+}
+
+impl MemoryAnswer {
+    /// Answer given when no memory is relevant to the question
+    pub(crate) fn unknown(name: &str) -> Self {
+        Self {
+            answer: format!("{} remembers nothing about that.", name),
+            memory_ids: Vec::new(),
+        }
+    }
+}
+
+/// Build the system prompt and input asking the model to answer a question
+///
+/// # Arguments
+///
+/// * `name` - Name of the agent
+/// * `question` - Question about the agent's memories
+/// * `memories` - Memories relevant to the question, numbered from 1 in the prompt
+pub(crate) fn prompt(name: &str, question: &str, memories: &[Memory]) -> (String, String) {
+    let system_prompt = format!(
+        "You answer questions about what {name} knows, using only {name}'s memories below. \
+         Answer in one or two sentences, in the third person. If the memories do not answer \
+         the question, say so. End with a line \"Sources:\" followed by the numbers of the \
+         memories you used, or \"none\"."
+    );
+    let lines: Vec<String> = memories
+        .iter()
+        .enumerate()
+        .map(|(index, memory)| format!("{}. {}", index + 1, memory.labeled_content()))
+        .collect();
+    (system_prompt, format!("Memories:\n{}\n\nQuestion: {}", lines.join("\n"), question))
+}
+
+/// Split a model response into the answer and the memories it cites
+///
+/// Without a `Sources:` line every memory in the prompt is taken to support
+/// the answer.
+pub(crate) fn parse_answer(text: &str, memories: &[Memory]) -> MemoryAnswer {
+    let mut answer_lines = Vec::new();
+    let mut cited: Option<Vec<String>> = None;
+    for line in text.lines() {
+        let trimmed = line.trim();
+        let sources = trimmed
+            .get(..8)
+            .filter(|prefix| prefix.eq_ignore_ascii_case("sources:"))
+            .map(|_| &trimmed[8..]);
+        match sources {
+            Some(sources) => {
+                let ids = cited.get_or_insert_with(Vec::new);
+                let numbers = sources
+                    .split(|c: char| !c.is_ascii_digit())
+                    .filter_map(|number| number.parse::<usize>().ok());
+                for number in numbers {
+                    let Some(memory) = number.checked_sub(1).and_then(|index| memories.get(index)) else {
+                        continue;
+                    };
+                    if !ids.contains(&memory.id) {
+                        ids.push(memory.id.clone());
+                    }
+                }
+            }
+            None if !trimmed.is_empty() => answer_lines.push(trimmed),
+            None => {}
+        }
+    }
+
+    MemoryAnswer {
+        answer: answer_lines.join(" "),
+        memory_ids: cited.unwrap_or_else(|| memories.iter().map(|memory| memory.id.clone()).collect()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::MemoryCategory;
+
+    #[test]
+    fn test_parses_answer_and_cited_memories() {
+        let memories = [
+            Memory::new(MemoryCategory::Semantic, "The mayor owes the guild money", 0.8, None),
+            Memory::new(MemoryCategory::Episodic, "The mayor visited the docks at night", 0.6, None),
+        ];
+        let (system_prompt, input) = prompt("Mira", "What about the mayor?", &memories);
+        assert!(system_prompt.contains("what Mira knows"));
+        assert!(input.starts_with("Memories:\n1. The mayor owes the guild money\n2. "));
+        assert!(input.ends_with("Question: What about the mayor?"));
+
+        let answer = parse_answer("Mira knows the mayor\nowes the guild money.\nSources: 1, 7", &memories);
+        assert_eq!(answer.answer, "Mira knows the mayor owes the guild money.");
+        assert_eq!(answer.memory_ids, vec![memories[0].id.clone()]);
+
+        assert!(parse_answer("She does not know.\nsources: none", &memories).memory_ids.is_empty());
+        assert_eq!(parse_answer("The mayor is corrupt.", &memories).memory_ids.len(), 2);
+    }
+}
@@ -0,0 +1,302 @@
+//! Memory statistics for tuning capacity, decay and thresholds
+//!
+//! [`MemorySystem::stats`](crate::memory::MemorySystem::stats) summarizes an
+//! agent's memories as distributions of age, importance, access frequency and
+//! retention (the time-decay factor retrieval applies), plus counts per
+//! category. A [`MemorySession`] saves the memories and memory configuration
+//! of a play session to a JSON file, so the report can be produced offline:
+//!
+//! ```text
+//! oxyde-cli memory-report --session sessions/blacksmith.json --format csv
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::MemoryConfig;
+use crate::memory::Memory;
+use crate::Result;
+
+const HOUR_SECS: f64 = 3600.0;
+const DAY_SECS: f64 = 86400.0;
+
+/// Number of memories falling in one range of a distribution
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bucket {
+    /// Human-readable range, such as `1h-24h`
+    pub label: String,
+
+    /// Number of memories in the range
+    pub count: usize,
+}
+
+/// Distribution of one memory property
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Distribution {
+    /// Buckets in ascending order
+    pub buckets: Vec<Bucket>,
+
+    /// Mean value, or 0.0 with no memories
+    pub mean: f64,
+
+    /// Smallest value, or 0.0 with no memories
+    pub min: f64,
+
+    /// Largest value, or 0.0 with no memories
+    pub max: f64,
+}
+
+impl Distribution {
+    /// Bucket values by upper bounds; values past the last bound go in the last bucket
+    fn from_values(values: &[f64], bounds: &[(f64, &str)]) -> Self {
+        let mut buckets: Vec<Bucket> = bounds
+            .iter()
+            .map(|(_, label)| Bucket {
+                label: label.to_string(),
+                count: 0,
+            })
+            .collect();
+        for value in values {
+            let index = bounds
+                .iter()
+                .position(|(upper, _)| value < upper)
+                .unwrap_or(bounds.len() - 1);
+            buckets[index].count += 1;
+        }
+
+        if values.is_empty() {
+            return Self {
+                buckets,
+                ..Default::default()
+            };
+        }
+        Self {
+            buckets,
+            mean: values.iter().sum::<f64>() / values.len() as f64,
+            min: values.iter().copied().fold(f64::INFINITY, f64::min),
+            max: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        }
+    }
+}
+
+/// Summary of an agent's memories
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MemoryStats {
+    /// Number of stored memories
+    pub total: usize,
+
+    /// Configured memory capacity
+    pub capacity: usize,
+
+    /// Memories that are never forgotten
+    pub permanent: usize,
+
+    /// Memories below the configured importance threshold
+    pub below_importance_threshold: usize,
+
+    /// Memory counts keyed by category
+    pub categories: BTreeMap<String, usize>,
+
+    /// Time since creation, in hours
+    pub age_hours: Distribution,
+
+    /// Importance scores
+    pub importance: Distribution,
+
+    /// Number of times each memory was recalled
+    pub access_count: Distribution,
+
+    /// Time-decay factor applied at retrieval; 1.0 for permanent memories
+    pub retention: Distribution,
+}
+
+impl MemoryStats {
+    /// Summarize memories as of a Unix timestamp
+    ///
+    /// # Arguments
+    ///
+    /// * `memories` - Memories to summarize
+    /// * `config` - Memory configuration providing capacity, decay rate and threshold
+    /// * `now` - Current time in seconds since the Unix epoch
+    pub fn from_memories(memories: &[Memory], config: &MemoryConfig, now: u64) -> Self {
+        let mut categories = BTreeMap::new();
+        for memory in memories {
+            *categories.entry(memory.category.as_str().to_string()).or_insert(0) += 1;
+        }
+
+        let ages: Vec<f64> = memories
+            .iter()
+            .map(|m| now.saturating_sub(m.created_at) as f64)
+            .collect();
+        let retention: Vec<f64> = memories
+            .iter()
+            .zip(&ages)
+            .map(|(m, age)| {
+                if m.permanent {
+                    1.0
+                } else {
+                    (-config.decay_rate * (age / DAY_SECS)).exp()
+                }
+            })
+            .collect();
+        let age_hours: Vec<f64> = ages.iter().map(|age| age / HOUR_SECS).collect();
+        let importance: Vec<f64> = memories.iter().map(|m| m.importance).collect();
+        let access_count: Vec<f64> = memories.iter().map(|m| m.access_count as f64).collect();
+
+        let fractions = [(0.2, "0.0-0.2"), (0.4, "0.2-0.4"), (0.6, "0.4-0.6"), (0.8, "0.6-0.8"), (f64::INFINITY, "0.8-1.0")];
+        Self {
+            total: memories.len(),
+            capacity: config.capacity,
+            permanent: memories.iter().filter(|m| m.permanent).count(),
+            below_importance_threshold: importance.iter().filter(|i| **i < config.importance_threshold).count(),
+            categories,
+            age_hours: Distribution::from_values(
+                &age_hours,
+                &[(1.0, "<1h"), (24.0, "1h-24h"), (168.0, "1d-7d"), (720.0, "7d-30d"), (f64::INFINITY, ">30d")],
+            ),
+            importance: Distribution::from_values(&importance, &fractions),
+            access_count: Distribution::from_values(
+                &access_count,
+                &[(1.0, "0"), (2.0, "1"), (5.0, "2-4"), (10.0, "5-9"), (f64::INFINITY, "10+")],
+            ),
+            retention: Distribution::from_values(&retention, &fractions),
+        }
+    }
+
+    /// Distributions with their report names, in report order
+    fn distributions(&self) -> [(&'static str, &Distribution); 4] {
+        [
+            ("age_hours", &self.age_hours),
+            ("importance", &self.importance),
+            ("access_count", &self.access_count),
+            ("retention", &self.retention),
+        ]
+    }
+
+    /// Render the statistics as a human-readable report
+    pub fn to_text(&self) -> String {
+        let mut report = String::new();
+        let _ = writeln!(report, "Memories:   {} / {} capacity", self.total, self.capacity);
+        let _ = writeln!(report, "Permanent:  {}", self.permanent);
+        let _ = writeln!(report, "Below importance threshold: {}", self.below_importance_threshold);
+
+        let _ = writeln!(report, "\nCategories");
+        for (category, count) in &self.categories {
+            let _ = writeln!(report, "  {:<12} {:>6}  {}", category, count, bar(*count, self.total));
+        }
+
+        for (name, distribution) in self.distributions() {
+            let _ = writeln!(
+                report,
+                "\n{} (mean {:.2}, min {:.2}, max {:.2})",
+                name, distribution.mean, distribution.min, distribution.max
+            );
+            for bucket in &distribution.buckets {
+                let _ = writeln!(
+                    report,
+                    "  {:<12} {:>6}  {}",
+                    bucket.label,
+                    bucket.count,
+                    bar(bucket.count, self.total)
+                );
+            }
+        }
+        report
+    }
+
+    /// Render the statistics as CSV with `section,bucket,count` rows
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("section,bucket,count\n");
+        let _ = writeln!(csv, "summary,total,{}", self.total);
+        let _ = writeln!(csv, "summary,capacity,{}", self.capacity);
+        let _ = writeln!(csv, "summary,permanent,{}", self.permanent);
+        let _ = writeln!(csv, "summary,below_importance_threshold,{}", self.below_importance_threshold);
+        for (category, count) in &self.categories {
+            let _ = writeln!(csv, "category,{},{}", category, count);
+        }
+        for (name, distribution) in self.distributions() {
+            for bucket in &distribution.buckets {
+                let _ = writeln!(csv, "{},{},{}", name, bucket.label, bucket.count);
+            }
+        }
+        csv
+    }
+}
+
+/// Proportional bar of up to 40 characters
+fn bar(count: usize, total: usize) -> String {
+    if total == 0 {
+        return String::new();
+    }
+    "#".repeat((count * 40).div_ceil(total))
+}
+
+/// Memories and memory configuration saved from a play session
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MemorySession {
+    /// Memory configuration the session ran with
+    #[serde(default)]
+    pub config: MemoryConfig,
+
+    /// Memories held at the end of the session
+    pub memories: Vec<Memory>,
+}
+
+impl MemorySession {
+    /// Load a session from a JSON file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// Save the session to a JSON file
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Summarize the session's memories as of a Unix timestamp
+    pub fn stats(&self, now: u64) -> MemoryStats {
+        MemoryStats::from_memories(&self.memories, &self.config, now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::MemoryCategory;
+
+    #[test]
+    fn test_stats_bucket_and_render() {
+        let config = MemoryConfig {
+            capacity: 10,
+            decay_rate: 0.1,
+            importance_threshold: 0.3,
+            ..Default::default()
+        };
+        let now = 100 * DAY_SECS as u64;
+        let mut old = Memory::new(MemoryCategory::Episodic, "The player stole an apple", 0.2, None);
+        old.created_at = now - 10 * DAY_SECS as u64;
+        old.access_count = 3;
+        let mut fresh = Memory::new(MemoryCategory::Semantic, "The mill is north of town", 1.0, None);
+        fresh.created_at = now - 60;
+
+        let stats = MemoryStats::from_memories(&[old, fresh], &config, now);
+        assert_eq!((stats.total, stats.permanent, stats.below_importance_threshold), (2, 1, 1));
+        assert_eq!(stats.categories["episodic"], 1);
+        assert_eq!(stats.age_hours.buckets[0].count, 1);
+        assert_eq!(stats.age_hours.buckets[3].count, 1);
+        assert_eq!(stats.access_count.buckets[2].count, 1);
+        // exp(-0.1 * 10) ~= 0.37 for the old memory; the permanent one keeps 1.0
+        assert_eq!(stats.retention.buckets[1].count, 1);
+        assert_eq!(stats.retention.max, 1.0);
+
+        assert!(stats.to_text().contains("Memories:   2 / 10 capacity"));
+        let csv = stats.to_csv();
+        assert!(csv.starts_with("section,bucket,count\nsummary,total,2\n"));
+        assert!(csv.contains("\nimportance,0.8-1.0,1\n"));
+    }
+}
@@ -0,0 +1,665 @@
+//! Crash-safe persistence of agent memories
+//!
+//! With `memory.persistence` on and a store directory configured, an agent's
+//! memories survive restarts:
+//!
+//! ```yaml
+//! memory:
+//!   persistence: true
+//!   store:
+//!     dir: saves/npcs/blacksmith
+//!     compact_after: 200
+//! ```
+//!
+//! The directory holds two files. `memories.json` is a snapshot of every
+//! memory and is only ever replaced whole: a new snapshot is written to a
+//! temporary file, flushed to disk and renamed over the old one, so a crash
+//! leaves either the old snapshot or the new one, never a mix of both.
+//! `memories.wal` is a write-ahead journal of the changes made since the
+//! snapshot, one checksummed record per line, appended as each change is
+//! made. Once the journal holds `compact_after` records it is folded into a
+//! new snapshot and emptied.
+//!
+//! [`MemoryStore::open`] recovers the memories at startup: it checks the
+//! snapshot's integrity, replays the journal up to the first torn or corrupt
+//! record, and folds the result into a fresh snapshot, describing what it
+//! found in a [`RecoveryReport`].
+//!
+//! Agents write through a [`StoreWriter`], which does the file I/O on a
+//! thread of its own so changes never wait on the disk while the agent's
+//! memories are locked. The writer keeps its own copy of the memories, built
+//! from the changes it is sent, and folds the journal from that copy. Recall
+//! bookkeeping, such as access counts, is not journaled and is saved with
+//! the next [`MemorySystem::checkpoint`](crate::memory::MemorySystem::checkpoint).
+//!
+//! Each directory belongs to a single agent. A store holds a lock on its
+//! directory while open, so a second agent opening the same one fails
+//! instead of overwriting the first agent's memories. Agents spawned from a
+//! template keep their memories in a subdirectory per instance; see
+//! [`MemoryStoreConfig::instance`].
+
+use std::collections::HashMap;
+use std::fs::{self, File, TryLockError};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+
+use serde::{Deserialize, Serialize};
+
+use crate::memory::Memory;
+use crate::{OxydeError, Result};
+
+/// Snapshot of every memory
+const SNAPSHOT_FILE: &str = "memories.json";
+
+/// Snapshot being written
+const TEMP_SNAPSHOT_FILE: &str = "memories.json.tmp";
+
+/// Snapshot that failed its integrity check, kept for inspection
+const CORRUPT_SNAPSHOT_FILE: &str = "memories.json.corrupt";
+
+/// Changes made since the snapshot
+const JOURNAL_FILE: &str = "memories.wal";
+
+/// Held locked while the store is open
+const LOCK_FILE: &str = "memories.lock";
+
+/// Version of the snapshot and journal layout
+const STORE_FORMAT: u32 = 1;
+
+/// Configuration of the memory store
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemoryStoreConfig {
+    /// Directory the agent's memories are saved in; memories are only kept
+    /// in memory when unset
+    #[serde(default)]
+    pub dir: Option<PathBuf>,
+
+    /// Journal records written before the journal is folded into a new
+    /// snapshot
+    #[serde(default = "default_compact_after")]
+    pub compact_after: usize,
+
+    /// Whether each journal record is flushed to disk as it is written,
+    /// which survives power loss at the cost of slower writes. Records are
+    /// written on the store's own thread after the change is made, so
+    /// changes still queued when the game crashes, or panics with
+    /// `panic = "abort"`, are lost either way.
+    #[serde(default)]
+    pub sync: bool,
+}
+
+fn default_compact_after() -> usize {
+    200
+}
+
+impl Default for MemoryStoreConfig {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            compact_after: default_compact_after(),
+            sync: false,
+        }
+    }
+}
+
+impl MemoryStoreConfig {
+    /// Validate the memory store configuration
+    ///
+    /// # Returns
+    ///
+    /// Ok if the configuration is valid, Err with a descriptive message otherwise
+    pub fn validate(&self) -> Result<()> {
+        if self.compact_after == 0 {
+            return Err(OxydeError::ConfigurationError(
+                "Memory store compact_after must be greater than 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// The store of one of several agents sharing this configuration, kept
+    /// in a subdirectory named after the instance
+    ///
+    /// # Arguments
+    ///
+    /// * `instance` - Name of the instance; characters that are not safe in
+    ///   a file name are replaced
+    pub fn instance(&self, instance: &str) -> Self {
+        let name: String = instance
+            .chars()
+            .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+        Self {
+            dir: self.dir.as_ref().map(|dir| dir.join(name)),
+            ..self.clone()
+        }
+    }
+}
+
+/// A change to the memories, as recorded in the journal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum JournalOp {
+    /// A memory was added or changed
+    Put {
+        /// The memory as it now is
+        memory: Memory,
+    },
+    /// Memories were removed
+    Remove {
+        /// IDs of the removed memories
+        ids: Vec<String>,
+    },
+    /// The emotional intensity of memories faded
+    Fade {
+        /// IDs of the faded memories and their new intensities
+        intensities: Vec<(String, f64)>,
+    },
+}
+
+impl JournalOp {
+    /// Apply the change to a set of memories
+    pub fn apply(self, memories: &mut Vec<Memory>) {
+        match self {
+            Self::Put { memory } => match memories.iter_mut().find(|m| m.id == memory.id) {
+                Some(existing) => *existing = memory,
+                None => memories.push(memory),
+            },
+            Self::Remove { ids } => memories.retain(|m| !ids.contains(&m.id)),
+            Self::Fade { intensities } => {
+                let intensities: HashMap<String, f64> = intensities.into_iter().collect();
+                for memory in memories.iter_mut() {
+                    if let Some(intensity) = intensities.get(&memory.id) {
+                        memory.emotional_intensity = *intensity;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// What recovery found when the store was opened
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RecoveryReport {
+    /// Memories read from the snapshot
+    pub snapshot_memories: usize,
+    /// Journal records replayed onto the snapshot
+    pub replayed: usize,
+    /// Journal records already folded into the snapshot, skipped
+    pub stale: usize,
+    /// Journal lines discarded as torn or corrupt, from the first bad line on
+    pub discarded: usize,
+    /// Whether a snapshot write interrupted by a crash was cleaned up
+    pub interrupted_snapshot: bool,
+    /// Whether the snapshot failed its integrity check and was set aside
+    pub corrupt_snapshot: bool,
+}
+
+impl RecoveryReport {
+    /// Whether the store was closed cleanly, with nothing lost or repaired
+    pub fn is_clean(&self) -> bool {
+        self.discarded == 0 && !self.interrupted_snapshot && !self.corrupt_snapshot
+    }
+}
+
+/// First line of a snapshot, describing the memories on the second
+#[derive(Debug, Serialize, Deserialize)]
+struct SnapshotHeader {
+    format: u32,
+    generation: u64,
+    count: usize,
+    checksum: String,
+}
+
+/// A journal line, tagged with the generation of the snapshot it follows
+#[derive(Debug, Serialize, Deserialize)]
+struct JournalRecord {
+    generation: u64,
+    op: JournalOp,
+}
+
+struct StoreState {
+    journal: File,
+    generation: u64,
+    records: usize,
+}
+
+/// Snapshot and write-ahead journal of one agent's memories
+pub struct MemoryStore {
+    config: MemoryStoreConfig,
+    dir: PathBuf,
+    report: RecoveryReport,
+    state: Mutex<StoreState>,
+    /// Locked for as long as the store is open; closing it releases the lock
+    _lock: File,
+}
+
+impl std::fmt::Debug for MemoryStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MemoryStore")
+            .field("dir", &self.dir)
+            .field("report", &self.report)
+            .finish()
+    }
+}
+
+impl MemoryStore {
+    /// Open a store and recover the memories saved in it
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Store configuration; `dir` must be set
+    ///
+    /// # Returns
+    ///
+    /// The store and the recovered memories, or an error if the directory
+    /// cannot be read or written or another store has it open
+    pub fn open(config: MemoryStoreConfig) -> Result<(Self, Vec<Memory>)> {
+        let dir = config
+            .dir
+            .clone()
+            .ok_or_else(|| OxydeError::MemoryError("Memory store has no directory".to_string()))?;
+        fs::create_dir_all(&dir)?;
+        let lock = File::create(dir.join(LOCK_FILE))?;
+        match lock.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                return Err(OxydeError::MemoryError(format!(
+                    "Memory store {} is already open by another agent",
+                    dir.display()
+                )));
+            }
+            Err(TryLockError::Error(e)) => return Err(e.into()),
+        }
+        let mut report = RecoveryReport::default();
+
+        let temp = dir.join(TEMP_SNAPSHOT_FILE);
+        if temp.exists() {
+            fs::remove_file(&temp)?;
+            report.interrupted_snapshot = true;
+        }
+
+        let snapshot_path = dir.join(SNAPSHOT_FILE);
+        let (generation, mut memories) = match fs::read_to_string(&snapshot_path) {
+            Ok(text) => match read_snapshot(&text) {
+                Ok(snapshot) => snapshot,
+                Err(e) => {
+                    log::error!("Memory snapshot {} is corrupt and was set aside: {}", snapshot_path.display(), e);
+                    fs::rename(&snapshot_path, dir.join(CORRUPT_SNAPSHOT_FILE))?;
+                    report.corrupt_snapshot = true;
+                    (0, Vec::new())
+                }
+            },
+            Err(e) if e.kind() == io::ErrorKind::NotFound => (0, Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        report.snapshot_memories = memories.len();
+
+        // Without a trustworthy snapshot, every intact record is worth keeping
+        let expected = (!report.corrupt_snapshot).then_some(generation);
+        let journal = match fs::read(dir.join(JOURNAL_FILE)) {
+            Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let lines: Vec<&str> = journal.lines().collect();
+        for (index, line) in lines.iter().enumerate() {
+            match read_record(line) {
+                Some(record) if expected.is_some_and(|generation| record.generation != generation) => {
+                    report.stale += 1;
+                }
+                Some(record) => {
+                    record.op.apply(&mut memories);
+                    report.replayed += 1;
+                }
+                None => {
+                    report.discarded = lines.len() - index;
+                    break;
+                }
+            }
+        }
+
+        // Fold what was recovered into a fresh snapshot, dropping any torn tail
+        write_snapshot(&dir, generation + 1, &memories)?;
+        let state = StoreState {
+            journal: File::create(dir.join(JOURNAL_FILE))?,
+            generation: generation + 1,
+            records: 0,
+        };
+        let store = Self {
+            config,
+            dir,
+            report,
+            state: Mutex::new(state),
+            _lock: lock,
+        };
+        Ok((store, memories))
+    }
+
+    /// What recovery found when the store was opened
+    pub fn recovery_report(&self) -> &RecoveryReport {
+        &self.report
+    }
+
+    /// Directory the store is saved in
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Append a change to the journal
+    ///
+    /// # Returns
+    ///
+    /// Whether the journal is due to be folded into a new snapshot
+    pub fn append(&self, op: JournalOp) -> Result<bool> {
+        let mut state = self.lock();
+        let record = serde_json::to_string(&JournalRecord {
+            generation: state.generation,
+            op,
+        })?;
+        let line = format!("{} {}\n", checksum(&record), record);
+        state.journal.write_all(line.as_bytes())?;
+        if self.config.sync {
+            state.journal.sync_data()?;
+        }
+        state.records += 1;
+        Ok(state.records >= self.config.compact_after)
+    }
+
+    /// Replace the snapshot with the given memories and empty the journal
+    pub fn compact(&self, memories: &[Memory]) -> Result<()> {
+        let mut state = self.lock();
+        write_snapshot(&self.dir, state.generation + 1, memories)?;
+        // A crash before the journal is emptied leaves records of the old
+        // generation, which recovery skips
+        state.journal = File::create(self.dir.join(JOURNAL_FILE))?;
+        state.generation += 1;
+        state.records = 0;
+        Ok(())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, StoreState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Work sent to a [`StoreWriter`]'s thread
+enum WriterMessage {
+    /// Journal a change
+    Change(JournalOp),
+    /// Replace the snapshot, reporting the outcome if anyone is waiting
+    Snapshot(Vec<Memory>, Option<tokio::sync::oneshot::Sender<Result<()>>>),
+}
+
+/// A [`MemoryStore`] written to on a thread of its own
+///
+/// Changes are queued in the order they are sent and written in that order.
+/// Dropping the writer waits for queued changes to be written; a crash does
+/// not, and loses whatever is still queued.
+pub struct StoreWriter {
+    dir: PathBuf,
+    report: RecoveryReport,
+    sender: Option<mpsc::Sender<WriterMessage>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl std::fmt::Debug for StoreWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StoreWriter")
+            .field("dir", &self.dir)
+            .field("report", &self.report)
+            .finish()
+    }
+}
+
+impl StoreWriter {
+    /// Start writing to a store
+    ///
+    /// # Arguments
+    ///
+    /// * `store` - The opened store
+    /// * `memories` - The memories recovered when it was opened
+    pub fn start(store: MemoryStore, memories: Vec<Memory>) -> Result<Self> {
+        let dir = store.dir.clone();
+        let report = store.report.clone();
+        let (sender, receiver) = mpsc::channel();
+        let thread = std::thread::Builder::new()
+            .name("oxyde-memory-store".to_string())
+            .spawn(move || write_loop(store, memories, receiver))?;
+        Ok(Self {
+            dir,
+            report,
+            sender: Some(sender),
+            thread: Some(thread),
+        })
+    }
+
+    /// What recovery found when the store was opened
+    pub fn recovery_report(&self) -> &RecoveryReport {
+        &self.report
+    }
+
+    /// Directory the store is saved in
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Queue a change to be journaled
+    pub fn append(&self, op: JournalOp) {
+        self.send(WriterMessage::Change(op));
+    }
+
+    /// Queue the memories to replace the snapshot, without waiting for it
+    pub fn replace(&self, memories: Vec<Memory>) {
+        self.send(WriterMessage::Snapshot(memories, None));
+    }
+
+    /// Replace the snapshot with the memories and wait until it is written
+    pub async fn compact(&self, memories: Vec<Memory>) -> Result<()> {
+        let (reply, outcome) = tokio::sync::oneshot::channel();
+        self.send(WriterMessage::Snapshot(memories, Some(reply)));
+        outcome
+            .await
+            .map_err(|_| OxydeError::MemoryError(format!("Memory store writer for {} stopped", self.dir.display())))?
+    }
+
+    fn send(&self, message: WriterMessage) {
+        let sent = self.sender.as_ref().is_some_and(|sender| sender.send(message).is_ok());
+        if !sent {
+            log::error!("Memory store writer for {} stopped; change not saved", self.dir.display());
+        }
+    }
+}
+
+impl Drop for StoreWriter {
+    fn drop(&mut self) {
+        // Closing the channel ends the thread once the queue is written
+        self.sender.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Write queued changes until the writer is dropped
+fn write_loop(store: MemoryStore, mut memories: Vec<Memory>, receiver: mpsc::Receiver<WriterMessage>) {
+    for message in receiver {
+        match message {
+            WriterMessage::Change(op) => {
+                op.clone().apply(&mut memories);
+                let result = store
+                    .append(op)
+                    .and_then(|due| if due { store.compact(&memories) } else { Ok(()) });
+                if let Err(e) = result {
+                    log::error!("Failed to persist memories to {}: {}", store.dir().display(), e);
+                }
+            }
+            WriterMessage::Snapshot(snapshot, reply) => {
+                memories = snapshot;
+                let result = store.compact(&memories);
+                match reply {
+                    Some(reply) => {
+                        let _ = reply.send(result);
+                    }
+                    None => {
+                        if let Err(e) = result {
+                            log::error!("Failed to persist memories to {}: {}", store.dir().display(), e);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Write a snapshot atomically: to a temporary file, flushed, then renamed
+fn write_snapshot(dir: &Path, generation: u64, memories: &[Memory]) -> Result<()> {
+    let body = serde_json::to_string(memories)?;
+    let header = SnapshotHeader {
+        format: STORE_FORMAT,
+        generation,
+        count: memories.len(),
+        checksum: checksum(&body),
+    };
+    let temp = dir.join(TEMP_SNAPSHOT_FILE);
+    let mut file = File::create(&temp)?;
+    writeln!(file, "{}", serde_json::to_string(&header)?)?;
+    file.write_all(body.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&temp, dir.join(SNAPSHOT_FILE))?;
+    // Flush the rename itself where the platform allows opening directories
+    #[cfg(unix)]
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+/// Read a snapshot, checking its format, checksum and memory count
+fn read_snapshot(text: &str) -> Result<(u64, Vec<Memory>)> {
+    let corrupt = |reason: &str| OxydeError::MemoryError(format!("Memory snapshot {}", reason));
+    let (header, body) = text.split_once('\n').ok_or_else(|| corrupt("is truncated"))?;
+    let header: SnapshotHeader = serde_json::from_str(header)?;
+    if header.format > STORE_FORMAT {
+        return Err(corrupt(&format!("has unsupported format {}", header.format)));
+    }
+    if checksum(body) != header.checksum {
+        return Err(corrupt("fails its checksum"));
+    }
+    let memories: Vec<Memory> = serde_json::from_str(body)?;
+    if memories.len() != header.count {
+        return Err(corrupt(&format!("holds {} memories instead of {}", memories.len(), header.count)));
+    }
+    Ok((header.generation, memories))
+}
+
+/// Read a journal line, or `None` if it is torn or corrupt
+fn read_record(line: &str) -> Option<JournalRecord> {
+    let (sum, record) = line.split_once(' ')?;
+    (checksum(record) == sum).then(|| serde_json::from_str(record).ok()).flatten()
+}
+
+/// FNV-1a hash of the text, as hex; stable across builds, unlike std hashers
+fn checksum(text: &str) -> String {
+    let hash = text.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::MemoryCategory;
+    use std::fs::OpenOptions;
+
+    fn temp_config() -> MemoryStoreConfig {
+        MemoryStoreConfig {
+            dir: Some(std::env::temp_dir().join(format!("oxyde-memories-{}", uuid::Uuid::new_v4()))),
+            compact_after: 3,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_journal_replays_after_a_crash() {
+        let config = temp_config();
+        let dir = config.dir.clone().unwrap();
+        let ring = Memory::new(MemoryCategory::Episodic, "The player returned my ring", 0.9, None);
+        let rumor = Memory::new(MemoryCategory::Semantic, "Wolves were seen by the mill", 0.4, None);
+        {
+            let (store, memories) = MemoryStore::open(config.clone()).unwrap();
+            assert!(memories.is_empty());
+            store.append(JournalOp::Put { memory: ring.clone() }).unwrap();
+            store.append(JournalOp::Put { memory: rumor.clone() }).unwrap();
+            store.append(JournalOp::Remove { ids: vec![rumor.id.clone()] }).unwrap();
+            // Dropped without compacting, as in a crash
+        }
+
+        // A torn record at the end of the journal is discarded
+        let mut journal = OpenOptions::new().append(true).open(dir.join(JOURNAL_FILE)).unwrap();
+        journal.write_all(b"0123456789abcdef {\"generation\":1,\"op\":{\"op\":\"put\",\"mem").unwrap();
+
+        let (store, memories) = MemoryStore::open(config.clone()).unwrap();
+        let report = store.recovery_report();
+        assert_eq!((report.replayed, report.discarded), (3, 1));
+        assert!(!report.is_clean());
+        assert_eq!(memories.iter().map(|m| m.id.as_str()).collect::<Vec<_>>(), [ring.id.as_str()]);
+        drop(store);
+
+        // Recovery leaves a clean store behind
+        let (store, memories) = MemoryStore::open(config).unwrap();
+        assert!(store.recovery_report().is_clean());
+        assert_eq!(store.recovery_report().snapshot_memories, 1);
+        assert_eq!(memories[0].content, ring.content);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_snapshots_are_checked_and_stale_records_skipped() {
+        let config = temp_config();
+        let dir = config.dir.clone().unwrap();
+        let ring = Memory::new(MemoryCategory::Episodic, "The player returned my ring", 0.9, None);
+        {
+            let (store, _) = MemoryStore::open(config.clone()).unwrap();
+            assert!(!store.append(JournalOp::Put { memory: ring.clone() }).unwrap());
+            // Records of the generation before a snapshot are already in it
+            let stale = fs::read(dir.join(JOURNAL_FILE)).unwrap();
+            store.compact(std::slice::from_ref(&ring)).unwrap();
+            fs::write(dir.join(JOURNAL_FILE), stale).unwrap();
+        }
+        let (store, memories) = MemoryStore::open(config.clone()).unwrap();
+        assert_eq!((store.recovery_report().stale, memories.len()), (1, 1));
+        drop(store);
+
+        // A damaged snapshot is set aside instead of being misread
+        let snapshot = fs::read_to_string(dir.join(SNAPSHOT_FILE)).unwrap();
+        fs::write(dir.join(SNAPSHOT_FILE), snapshot.replace("ring", "rung")).unwrap();
+        let (store, memories) = MemoryStore::open(config).unwrap();
+        assert!(store.recovery_report().corrupt_snapshot);
+        assert!(memories.is_empty());
+        assert!(dir.join(CORRUPT_SNAPSHOT_FILE).exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_a_store_is_opened_by_one_agent_at_a_time() {
+        let config = temp_config();
+        let dir = config.dir.clone().unwrap();
+        let ring = Memory::new(MemoryCategory::Episodic, "The player returned my ring", 0.9, None);
+        let (store, _) = MemoryStore::open(config.clone()).unwrap();
+        store.append(JournalOp::Put { memory: ring }).unwrap();
+
+        // A second open would truncate the journal the first store writes to
+        assert!(MemoryStore::open(config.clone()).unwrap_err().to_string().contains("already open"));
+        drop(store);
+
+        let (_, memories) = MemoryStore::open(config.clone()).unwrap();
+        assert_eq!(memories.len(), 1);
+        let instance = config.instance("Villager (farmer)");
+        assert_eq!(instance.dir.unwrap(), dir.join("Villager__farmer_"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,240 @@
+//! Scripted inference provider for hermetic tests
+//!
+//! Setting `provider: mock` in the inference configuration replaces the
+//! local and cloud providers with a [`MockProvider`], so examples, CI and
+//! game test suites can run full agent pipelines without network access or
+//! model files:
+//!
+//! ```yaml
+//! inference:
+//!   provider: mock
+//!   mock:
+//!     rules:
+//!       - input_contains: dragon
+//!         response: "Don't speak of the dragon here."
+//!     responses:
+//!       - "Welcome, traveler."
+//!     latency_ms: 20
+//!     fail_requests: [3]
+//! ```
+//!
+//! For each request the provider answers with the first rule whose text the
+//! input contains, otherwise the next scripted response, otherwise
+//! `default_response`. Latency and failures are injected deterministically,
+//! and every request is recorded for assertions.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::inference::{InferenceProvider, InferenceRequest, InferenceResponse};
+use crate::timer::{self, Instant};
+use crate::{OxydeError, Result};
+
+/// A response given whenever the input contains some text
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MockRule {
+    /// Text the input must contain, ignoring case
+    pub input_contains: String,
+
+    /// Response to give
+    pub response: String,
+}
+
+/// Configuration for the mock provider
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MockProviderConfig {
+    /// Responses keyed on input text, checked in order before the script
+    #[serde(default)]
+    pub rules: Vec<MockRule>,
+
+    /// Responses served in order, one per request, until exhausted
+    #[serde(default)]
+    pub responses: Vec<String>,
+
+    /// Response once the script is exhausted; `{input}` is filled in
+    #[serde(default = "default_response")]
+    pub default_response: String,
+
+    /// Delay added to every request, in milliseconds
+    #[serde(default)]
+    pub latency_ms: u64,
+
+    /// Fail every Nth request
+    #[serde(default)]
+    pub fail_every: Option<u64>,
+
+    /// Request numbers, counting from 1, that fail
+    #[serde(default)]
+    pub fail_requests: Vec<u64>,
+}
+
+fn default_response() -> String {
+    "This is a mock response to: {input}".to_string()
+}
+
+impl Default for MockProviderConfig {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            responses: Vec::new(),
+            default_response: default_response(),
+            latency_ms: 0,
+            fail_every: None,
+            fail_requests: Vec::new(),
+        }
+    }
+}
+
+impl MockProviderConfig {
+    /// Validate the mock provider configuration
+    pub fn validate(&self) -> Result<()> {
+        if self.fail_every == Some(0) {
+            return Err(OxydeError::ConfigurationError(
+                "Mock provider fail_every must be greater than 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A queued reply: a response, or a failure with its message
+type ScriptedReply = std::result::Result<String, String>;
+
+/// Inference provider that answers from a script
+#[derive(Debug)]
+pub struct MockProvider {
+    config: MockProviderConfig,
+    script: Mutex<VecDeque<ScriptedReply>>,
+    requests: Mutex<Vec<InferenceRequest>>,
+}
+
+impl MockProvider {
+    /// Create a mock provider
+    pub fn new(config: MockProviderConfig) -> Self {
+        Self {
+            script: Mutex::new(config.responses.iter().cloned().map(Ok).collect()),
+            config,
+            requests: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Queue a response after the scripted ones
+    pub fn push_response(&self, response: &str) {
+        lock(&self.script).push_back(Ok(response.to_string()));
+    }
+
+    /// Queue a failure after the scripted responses
+    pub fn push_failure(&self, message: &str) {
+        lock(&self.script).push_back(Err(message.to_string()));
+    }
+
+    /// Requests received so far, in order
+    pub fn requests(&self) -> Vec<InferenceRequest> {
+        lock(&self.requests).clone()
+    }
+
+    /// Number of requests received so far
+    pub fn request_count(&self) -> usize {
+        lock(&self.requests).len()
+    }
+
+    /// Pick the reply for a request
+    fn reply(&self, number: u64, input: &str) -> ScriptedReply {
+        let injected = self.config.fail_requests.contains(&number)
+            || self.config.fail_every.is_some_and(|every| number.is_multiple_of(every));
+        if injected {
+            return Err(format!("Mock failure injected on request {}", number));
+        }
+
+        let lowered = input.to_lowercase();
+        if let Some(rule) = self
+            .config
+            .rules
+            .iter()
+            .find(|rule| lowered.contains(&rule.input_contains.to_lowercase()))
+        {
+            return Ok(rule.response.clone());
+        }
+
+        lock(&self.script)
+            .pop_front()
+            .unwrap_or_else(|| Ok(self.config.default_response.replace("{input}", input)))
+    }
+}
+
+#[async_trait]
+impl InferenceProvider for MockProvider {
+    async fn generate(&self, request: InferenceRequest) -> Result<InferenceResponse> {
+        let start_time = Instant::now();
+        let input = request.input.clone();
+        let number = {
+            let mut requests = lock(&self.requests);
+            requests.push(request);
+            requests.len() as u64
+        };
+
+        if self.config.latency_ms > 0 {
+            timer::sleep(Duration::from_millis(self.config.latency_ms)).await;
+        }
+
+        let text = self.reply(number, &input).map_err(OxydeError::InferenceError)?;
+        Ok(InferenceResponse {
+            tokens: text.split_whitespace().count(),
+            text,
+            time_ms: start_time.elapsed().as_millis() as u64,
+            provider_name: "mock".to_string(),
+            model: Some("mock".to_string()),
+        })
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::AgentContext;
+
+    fn request(input: &str) -> InferenceRequest {
+        InferenceRequest {
+            input: input.to_string(),
+            system_prompt: "You are an NPC.".to_string(),
+            memories: Vec::new(),
+            context: AgentContext::new(),
+            max_tokens: 16,
+            temperature: 0.7,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_serves_rules_script_and_failures() {
+        let provider = MockProvider::new(MockProviderConfig {
+            rules: vec![MockRule {
+                input_contains: "dragon".to_string(),
+                response: "Hush!".to_string(),
+            }],
+            responses: vec!["Welcome.".to_string()],
+            fail_requests: vec![3],
+            ..Default::default()
+        });
+        provider.push_failure("rate limited");
+
+        let text = |result: Result<InferenceResponse>| result.map(|r| r.text).map_err(|e| e.to_string());
+        assert_eq!(text(provider.generate(request("Hello")).await), Ok("Welcome.".to_string()));
+        assert_eq!(text(provider.generate(request("A DRAGON!")).await), Ok("Hush!".to_string()));
+        assert!(text(provider.generate(request("Hello")).await).unwrap_err().contains("request 3"));
+        assert!(text(provider.generate(request("Hello")).await).unwrap_err().contains("rate limited"));
+        assert_eq!(
+            text(provider.generate(request("Bye")).await),
+            Ok("This is a mock response to: Bye".to_string())
+        );
+        assert_eq!(provider.request_count(), 5);
+        assert_eq!(provider.requests()[1].input, "A DRAGON!");
+    }
+}
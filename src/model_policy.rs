@@ -0,0 +1,246 @@
+//! Automatic model switching on latency and spend targets
+//!
+//! The [`ModelPolicy`] watches the rolling latency and spend of inference
+//! responses and moves the engine between the configured [`ModelTier`]s:
+//! down to a faster or cheaper model when a target is breached, and back up
+//! once both metrics have recovered. A minimum interval between switches and
+//! a recovery ratio below the targets keep it from flapping.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{ModelPolicyConfig, ModelTier};
+use crate::timer::Instant;
+
+/// Direction of a model switch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SwitchDirection {
+    /// Moved to a faster or cheaper model
+    Downgrade,
+    /// Moved back to a preferred model
+    Upgrade,
+}
+
+/// Why a model switch happened
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SwitchReason {
+    /// Rolling latency exceeded its target
+    Latency,
+    /// Spend within the window exceeded its target
+    Spend,
+    /// Latency and spend recovered below their targets
+    Recovered,
+}
+
+/// Notification sent when the engine switches models
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelSwitch {
+    /// Model switched away from
+    pub from: String,
+
+    /// Model switched to
+    pub to: String,
+
+    /// Whether the new model is cheaper or preferred
+    pub direction: SwitchDirection,
+
+    /// What triggered the switch
+    pub reason: SwitchReason,
+
+    /// Rolling average latency when the switch happened
+    pub avg_latency_ms: f64,
+
+    /// Spend within the window when the switch happened
+    pub spend: f64,
+}
+
+/// Tracks rolling metrics and decides when to switch tiers
+#[derive(Debug)]
+pub(crate) struct ModelPolicy {
+    config: ModelPolicyConfig,
+    current: usize,
+    /// Latencies on the current tier, newest last
+    latencies: VecDeque<u64>,
+    /// Costs across all tiers with the time they were incurred
+    costs: VecDeque<(Instant, f64)>,
+    last_switch: Option<Instant>,
+}
+
+impl ModelPolicy {
+    /// Create a policy, or `None` if no tiers are configured
+    pub(crate) fn new(config: &ModelPolicyConfig) -> Option<Self> {
+        if config.tiers.is_empty() {
+            return None;
+        }
+        Some(Self {
+            config: config.clone(),
+            current: 0,
+            latencies: VecDeque::new(),
+            costs: VecDeque::new(),
+            last_switch: None,
+        })
+    }
+
+    /// The tier requests should currently use
+    pub(crate) fn current_tier(&self) -> &ModelTier {
+        &self.config.tiers[self.current]
+    }
+
+    /// Record a response and switch tiers if a target is breached or recovered
+    ///
+    /// # Arguments
+    ///
+    /// * `latency_ms` - Time taken for the response
+    /// * `tokens` - Tokens generated
+    /// * `now` - Time the response completed
+    ///
+    /// # Returns
+    ///
+    /// The switch, if one happened
+    pub(crate) fn record(&mut self, latency_ms: u64, tokens: usize, now: Instant) -> Option<ModelSwitch> {
+        let cost = self.current_tier().cost_per_1k_tokens * tokens as f64 / 1000.0;
+        self.costs.push_back((now, cost));
+        self.latencies.push_back(latency_ms);
+        while self.latencies.len() > self.config.latency_window {
+            self.latencies.pop_front();
+        }
+
+        let window = Duration::from_secs(self.config.spend_window_secs);
+        while self.costs.front().is_some_and(|(at, _)| now.duration_since(*at) > window) {
+            self.costs.pop_front();
+        }
+
+        if self
+            .last_switch
+            .is_some_and(|at| now.duration_since(at) < Duration::from_millis(self.config.min_switch_interval_ms))
+        {
+            return None;
+        }
+
+        let avg_latency = self.avg_latency_ms();
+        let spend = self.spend();
+        let full_window = self.latencies.len() >= self.config.latency_window;
+        let latency_over = |limit: f64| {
+            self.config.max_latency_ms.is_some_and(|max| full_window && avg_latency > max as f64 * limit)
+        };
+        let spend_over = |limit: f64| self.config.max_spend.is_some_and(|max| spend > max * limit);
+
+        let (direction, reason) = if latency_over(1.0) {
+            (SwitchDirection::Downgrade, SwitchReason::Latency)
+        } else if spend_over(1.0) {
+            (SwitchDirection::Downgrade, SwitchReason::Spend)
+        } else if full_window && !latency_over(self.config.recovery_ratio) && !spend_over(self.config.recovery_ratio) {
+            (SwitchDirection::Upgrade, SwitchReason::Recovered)
+        } else {
+            return None;
+        };
+
+        let target = match direction {
+            SwitchDirection::Downgrade if self.current + 1 < self.config.tiers.len() => self.current + 1,
+            SwitchDirection::Upgrade if self.current > 0 => self.current - 1,
+            _ => return None,
+        };
+
+        let from = self.current_tier().model.clone();
+        self.current = target;
+        self.latencies.clear();
+        self.last_switch = Some(now);
+
+        Some(ModelSwitch {
+            from,
+            to: self.current_tier().model.clone(),
+            direction,
+            reason,
+            avg_latency_ms: avg_latency,
+            spend,
+        })
+    }
+
+    /// Average latency over the latency window
+    pub(crate) fn avg_latency_ms(&self) -> f64 {
+        if self.latencies.is_empty() {
+            return 0.0;
+        }
+        self.latencies.iter().sum::<u64>() as f64 / self.latencies.len() as f64
+    }
+
+    /// Spend within the spend window
+    pub(crate) fn spend(&self) -> f64 {
+        self.costs.iter().map(|(_, cost)| cost).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> ModelPolicy {
+        ModelPolicy::new(&ModelPolicyConfig {
+            tiers: vec![
+                ModelTier {
+                    model: "large".to_string(),
+                    local: false,
+                    cost_per_1k_tokens: 10.0,
+                    prompt_cost_per_1k_tokens: 0.0,
+                },
+                ModelTier {
+                    model: "small".to_string(),
+                    local: false,
+                    cost_per_1k_tokens: 1.0,
+                    prompt_cost_per_1k_tokens: 0.0,
+                },
+            ],
+            max_latency_ms: Some(1000),
+            max_spend: Some(5.0),
+            latency_window: 2,
+            min_switch_interval_ms: 1000,
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_downgrades_on_latency_and_recovers_with_hysteresis() {
+        let mut policy = policy();
+        let start = Instant::now();
+
+        assert_eq!(policy.record(1500, 10, start), None);
+        let switch = policy.record(1500, 10, start).unwrap();
+        assert_eq!((switch.to.as_str(), switch.reason), ("small", SwitchReason::Latency));
+
+        // Latency below the target but above the recovery ratio holds the tier
+        let later = start + Duration::from_secs(2);
+        assert_eq!(policy.record(900, 10, later), None);
+        assert_eq!(policy.record(900, 10, later), None);
+
+        let switch = policy.record(100, 10, later).unwrap();
+        assert_eq!(switch.direction, SwitchDirection::Upgrade);
+        assert_eq!(policy.current_tier().model, "large");
+
+        // Slow responses inside the switch interval do not downgrade again
+        let soon = later + Duration::from_millis(500);
+        assert_eq!(policy.record(1500, 10, soon), None);
+        assert_eq!(policy.record(1500, 10, soon), None);
+        let switch = policy.record(1500, 10, later + Duration::from_secs(2)).unwrap();
+        assert_eq!(switch.direction, SwitchDirection::Downgrade);
+    }
+
+    #[test]
+    fn test_downgrades_on_spend_within_window() {
+        let mut policy = policy();
+        let start = Instant::now();
+
+        assert_eq!(policy.record(100, 300, start), None);
+        let switch = policy.record(100, 300, start).unwrap();
+        assert_eq!((switch.direction, switch.reason), (SwitchDirection::Downgrade, SwitchReason::Spend));
+        assert!((switch.spend - 6.0).abs() < 1e-9);
+
+        // Older spend leaves the window
+        assert_eq!(policy.record(100, 10, start + Duration::from_secs(120)), None);
+        assert!(policy.spend() < 0.1);
+    }
+}
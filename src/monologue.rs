@@ -0,0 +1,126 @@
+//! Internal monologue
+//!
+//! With monologue enabled, after answering each input the agent asks the
+//! model for a short first-person note on what it privately thinks and feels
+//! about the exchange, such as "He is lying about the ring, and it frightens
+//! me." Notes are stored as private memories tagged `monologue`: later
+//! prompts may use them to stay consistent, but they are never returned to
+//! the player. Designers read them with `Agent::monologue` while tuning a
+//! character.
+//!
+//! ```yaml
+//! monologue:
+//!   enabled: true
+//!   max_tokens: 60
+//! ```
+//!
+//! Each note costs one extra inference request per answered input. The
+//! request is made once the response is final, and a failed request is
+//! logged rather than failing the input.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{OxydeError, Result};
+
+/// Tag on memories holding monologue notes
+pub const MONOLOGUE_TAG: &str = "monologue";
+
+/// Configuration for the internal monologue
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MonologueConfig {
+    /// Whether the agent writes a note after each input
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Maximum tokens the model may generate per note
+    #[serde(default = "default_max_tokens")]
+    pub max_tokens: usize,
+
+    /// Importance of stored notes (0.0 - 1.0)
+    #[serde(default = "default_importance")]
+    pub importance: f64,
+
+    /// Instructions for the model; `{name}` is filled in
+    #[serde(default = "default_instructions")]
+    pub instructions: String,
+}
+
+fn default_max_tokens() -> usize {
+    80
+}
+
+fn default_importance() -> f64 {
+    0.3
+}
+
+fn default_instructions() -> String {
+    "You are {name}. In one or two sentences written in the first person, note what you privately \
+     think and feel about this exchange. The note is never said aloud."
+        .to_string()
+}
+
+impl Default for MonologueConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_tokens: default_max_tokens(),
+            importance: default_importance(),
+            instructions: default_instructions(),
+        }
+    }
+}
+
+impl MonologueConfig {
+    /// Validate the monologue configuration
+    pub fn validate(&self) -> Result<()> {
+        if self.max_tokens == 0 {
+            return Err(OxydeError::ConfigurationError(
+                "Monologue max_tokens must be greater than 0".to_string(),
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.importance) {
+            return Err(OxydeError::ConfigurationError(format!(
+                "Monologue importance must be between 0.0 and 1.0, got {}",
+                self.importance
+            )));
+        }
+        Ok(())
+    }
+
+    /// Build the system prompt and input asking the model for a note
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the agent
+    /// * `input` - What the player said
+    /// * `response` - What the agent answered
+    pub fn prompt(&self, name: &str, input: &str, response: &str) -> (String, String) {
+        (
+            self.instructions.replace("{name}", name),
+            format!("The player said: {}\nYou answered: {}", input, response),
+        )
+    }
+
+    /// Clean a model response into a note, or `None` if it is empty
+    pub fn parse_note(&self, text: &str) -> Option<String> {
+        let note = text.trim().trim_matches('"').trim();
+        (!note.is_empty()).then(|| note.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builds_prompt_and_parses_note() {
+        let config = MonologueConfig::default();
+        let (system_prompt, input) = config.prompt("Mira", "Where is the ring?", "I have never seen it.");
+        assert!(system_prompt.starts_with("You are Mira.") && system_prompt.contains("never said aloud"));
+        assert_eq!(input, "The player said: Where is the ring?\nYou answered: I have never seen it.");
+
+        assert_eq!(config.parse_note("  \"I lied to them.\"\n").as_deref(), Some("I lied to them."));
+        assert_eq!(config.parse_note(" \n"), None);
+        assert!(MonologueConfig { max_tokens: 0, ..Default::default() }.validate().is_err());
+    }
+}
@@ -0,0 +1,273 @@
+//! Pluggable affect models
+//!
+//! Agents feel through an [`AffectModel`]. The default, [`PlutchikModel`],
+//! works directly on Plutchik's eight emotions. Projects preferring another
+//! theory configure the built-in pleasure-arousal-dominance model or plug in
+//! their own with [`crate::agent::Agent::set_affect_model`]:
+//!
+//! ```yaml
+//! affect:
+//!   model: pad
+//! ```
+//!
+//! Whatever the model, it projects its state onto an [`EmotionalState`],
+//! which stays the common interface: behavior triggers, conditions, memory
+//! valence and engine bindings read the projected emotions, so they keep
+//! working unchanged. The model's own dimensions are exposed to behaviors
+//! under [`AFFECT_KEY`].
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::oxyde_game::emotion::{EmotionalState, EMOTION_NAMES};
+
+/// Context key under which the agent exposes its affect model's own dimensions
+pub const AFFECT_KEY: &str = "npc_affect";
+
+/// A theory of how an agent feels
+///
+/// Models receive stimuli named by a Plutchik emotion, such as behavior
+/// emotion influences, or by one of their own dimensions, and keep the
+/// projected emotions up to date.
+pub trait AffectModel: Send + Sync + fmt::Debug {
+    /// Name of the model
+    fn name(&self) -> &str;
+
+    /// Apply a stimulus to the model and project the result onto the emotions
+    ///
+    /// # Arguments
+    ///
+    /// * `emotions` - Projected emotions to update
+    /// * `stimulus` - Plutchik emotion or model dimension; unknown names are ignored
+    /// * `delta` - Strength of the stimulus (-1.0 to 1.0)
+    fn stimulate(&mut self, emotions: &mut EmotionalState, stimulus: &str, delta: f32);
+
+    /// Let feelings fade by the emotions' decay rate
+    ///
+    /// # Arguments
+    ///
+    /// * `emotions` - Projected emotions to update
+    /// * `baseline` - Resting emotions, in emotion vector order; neutral when `None`
+    fn decay(&mut self, emotions: &mut EmotionalState, baseline: Option<&[f32; 8]>);
+
+    /// The model's own dimensions and their values
+    fn dimensions(&self, emotions: &EmotionalState) -> Vec<(String, f32)>;
+
+    /// Take on emotions set from outside the model, such as a restored save
+    fn sync(&mut self, _emotions: &EmotionalState) {}
+}
+
+/// Plutchik's wheel of emotions, the default affect model
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PlutchikModel;
+
+impl AffectModel for PlutchikModel {
+    fn name(&self) -> &str {
+        "plutchik"
+    }
+
+    fn stimulate(&mut self, emotions: &mut EmotionalState, stimulus: &str, delta: f32) {
+        emotions.update_emotion(stimulus, delta);
+    }
+
+    fn decay(&mut self, emotions: &mut EmotionalState, baseline: Option<&[f32; 8]>) {
+        match baseline {
+            Some(baseline) => emotions.decay_toward(baseline),
+            None => emotions.decay(),
+        }
+    }
+
+    fn dimensions(&self, emotions: &EmotionalState) -> Vec<(String, f32)> {
+        EMOTION_NAMES
+            .iter()
+            .zip(emotions.as_vector())
+            .map(|(name, value)| (name.to_string(), value))
+            .collect()
+    }
+}
+
+/// Names of the pleasure-arousal-dominance dimensions
+pub const PAD_DIMENSIONS: [&str; 3] = ["pleasure", "arousal", "dominance"];
+
+/// Approximate placement of each Plutchik emotion in PAD space, after
+/// Mehrabian, in emotion vector order
+const PAD_COORDINATES: [[f32; 3]; 8] = [
+    [0.8, 0.5, 0.4],    // joy
+    [0.6, -0.2, 0.3],   // trust
+    [-0.6, 0.6, -0.6],  // fear
+    [0.2, 0.7, -0.2],   // surprise
+    [-0.6, -0.4, -0.4], // sadness
+    [-0.6, 0.3, 0.1],   // disgust
+    [-0.5, 0.6, 0.4],   // anger
+    [0.3, 0.4, 0.3],    // anticipation
+];
+
+/// Pleasure-arousal-dominance affect model
+///
+/// Feelings are a point in three dimensions. Stimuli named by a Plutchik
+/// emotion move the point toward that emotion's placement, and each emotion
+/// is projected as how far the point lies in its direction.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PadModel {
+    /// Pleasure, arousal and dominance (-1.0 to 1.0)
+    pub pad: [f32; 3],
+}
+
+impl PadModel {
+    /// Create a neutral PAD model
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn direction(emotion: usize) -> [f32; 3] {
+        let [p, a, d] = PAD_COORDINATES[emotion];
+        let length = (p * p + a * a + d * d).sqrt();
+        [p / length, a / length, d / length]
+    }
+
+    /// The PAD point of Plutchik emotions
+    ///
+    /// Plutchik states record each feeling twice, once as its opposite
+    /// negated, so the contributions are halved.
+    fn point_of(vector: &[f32; 8]) -> [f32; 3] {
+        let mut pad = [0.0; 3];
+        for (emotion, value) in vector.iter().enumerate() {
+            for (axis, component) in Self::direction(emotion).into_iter().enumerate() {
+                pad[axis] += value * component / 2.0;
+            }
+        }
+        pad.map(|value| value.clamp(-1.0, 1.0))
+    }
+
+    fn project(&self, emotions: &mut EmotionalState) {
+        let mut vector = [0.0; 8];
+        for (emotion, value) in vector.iter_mut().enumerate() {
+            let direction = Self::direction(emotion);
+            *value = (0..3).map(|axis| self.pad[axis] * direction[axis]).sum::<f32>().clamp(-1.0, 1.0);
+        }
+        emotions.set_vector(vector);
+    }
+}
+
+impl AffectModel for PadModel {
+    fn name(&self) -> &str {
+        "pad"
+    }
+
+    fn stimulate(&mut self, emotions: &mut EmotionalState, stimulus: &str, delta: f32) {
+        if let Some(axis) = PAD_DIMENSIONS.iter().position(|name| *name == stimulus) {
+            self.pad[axis] += delta;
+        } else if let Some(emotion) = EMOTION_NAMES.iter().position(|name| *name == stimulus) {
+            for (axis, component) in Self::direction(emotion).into_iter().enumerate() {
+                self.pad[axis] += delta * component;
+            }
+        } else {
+            return;
+        }
+        self.pad = self.pad.map(|value| value.clamp(-1.0, 1.0));
+        self.project(emotions);
+    }
+
+    fn decay(&mut self, emotions: &mut EmotionalState, baseline: Option<&[f32; 8]>) {
+        let rest = baseline.map_or([0.0; 3], Self::point_of);
+        let rate = emotions.decay_rate();
+        for (value, rest) in self.pad.iter_mut().zip(rest) {
+            *value += rate * (rest - *value);
+        }
+        self.project(emotions);
+    }
+
+    fn dimensions(&self, _emotions: &EmotionalState) -> Vec<(String, f32)> {
+        PAD_DIMENSIONS
+            .iter()
+            .zip(self.pad)
+            .map(|(name, value)| (name.to_string(), value))
+            .collect()
+    }
+
+    fn sync(&mut self, emotions: &EmotionalState) {
+        self.pad = Self::point_of(&emotions.as_vector());
+    }
+}
+
+/// Built-in affect models
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AffectModelKind {
+    /// Plutchik's eight emotions
+    #[default]
+    Plutchik,
+    /// Pleasure, arousal and dominance
+    Pad,
+}
+
+/// Affect model configuration
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AffectConfig {
+    /// Built-in model the agent starts with
+    #[serde(default)]
+    pub model: AffectModelKind,
+}
+
+impl AffectConfig {
+    /// Create the configured affect model
+    pub fn build(&self) -> Box<dyn AffectModel> {
+        match self.model {
+            AffectModelKind::Plutchik => Box::new(PlutchikModel),
+            AffectModelKind::Pad => Box::new(PadModel::new()),
+        }
+    }
+}
+
+/// The model's own dimensions as a JSON object, for [`AFFECT_KEY`]
+pub fn dimensions_json(model: &dyn AffectModel, emotions: &EmotionalState) -> serde_json::Value {
+    let dimensions: serde_json::Map<String, serde_json::Value> = model
+        .dimensions(emotions)
+        .into_iter()
+        .map(|(name, value)| (name, serde_json::json!(value)))
+        .collect();
+    serde_json::json!({ "model": model.name(), "dimensions": dimensions })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plutchik_model_matches_emotional_state() {
+        let mut model = PlutchikModel;
+        let mut emotions = EmotionalState::new();
+        model.stimulate(&mut emotions, "joy", 0.5);
+        assert_eq!((emotions.joy, emotions.sadness), (0.5, -0.5));
+        model.decay(&mut emotions, None);
+        assert!((emotions.joy - 0.45).abs() < 1e-6);
+        assert_eq!(model.dimensions(&emotions)[0], ("joy".to_string(), emotions.joy));
+    }
+
+    #[test]
+    fn test_pad_model_projects_onto_plutchik_emotions() {
+        let mut model = PadModel::new();
+        let mut emotions = EmotionalState::new();
+
+        model.stimulate(&mut emotions, "fear", 0.8);
+        assert!((emotions.fear - 0.8).abs() < 1e-5);
+        assert!(emotions.joy < 0.0);
+        assert!(model.pad[0] < 0.0 && model.pad[1] > 0.0 && model.pad[2] < 0.0);
+
+        // Dominance rising turns fear toward anger
+        model.stimulate(&mut emotions, "dominance", 1.5);
+        assert!(emotions.anger > emotions.fear);
+
+        model.decay(&mut emotions, None);
+        assert!(model.pad.iter().all(|value| value.abs() <= 1.0));
+        let json = dimensions_json(&model, &emotions);
+        assert_eq!(json["model"], "pad");
+        assert_eq!(json["dimensions"].as_object().unwrap().len(), 3);
+
+        model.stimulate(&mut emotions, "boredom", 1.0);
+        let mut restored = PadModel::new();
+        restored.sync(&emotions);
+        assert!(restored.pad.iter().zip(model.pad).all(|(a, b)| a.signum() == b.signum()));
+    }
+}
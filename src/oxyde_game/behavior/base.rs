@@ -1,19 +1,26 @@
 //! Base behavior functionality with cooldown tracking
 
 use std::collections::HashMap;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use tokio::sync::RwLock;
 
 use crate::agent::AgentContext;
-use crate::oxyde_game::emotion::EmotionalState;
+use crate::oxyde_game::emotion::{EmotionalState, EMOTION_NAMES};
 use crate::oxyde_game::intent::Intent;
+use crate::oxyde_game::modifier::SharedModifiers;
+use crate::oxyde_game::topic::AgendaTopic;
+use crate::timer::Instant;
 use crate::Result;
 
 /// Emotional trigger condition for behaviors
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Configs write triggers in a small language parsed by [`str::parse`], such
+/// as `fear > 0.5 AND trust < 0`, `dominant is anger` or
+/// `anger > 0.4 for 3 turns`; see [`EmotionTrigger::parse`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum EmotionTrigger {
     /// Trigger when any emotion exceeds threshold
     AnyEmotion { min_intensity: f32 },
@@ -35,11 +42,117 @@ pub enum EmotionTrigger {
 
     /// No emotional trigger (always passes)
     None,
+
+    /// Trigger when an emotion, valence or arousal compares to a value
+    Compare {
+        /// Quantity compared
+        measure: EmotionMeasure,
+        /// Comparison applied
+        op: Comparison,
+        /// Value compared against
+        value: f32,
+    },
+
+    /// Trigger when an emotion is the strongest, whatever its value
+    Dominant {
+        /// Emotion that must be the strongest
+        emotion: String,
+    },
+
+    /// Trigger when every inner trigger matches
+    All(Vec<EmotionTrigger>),
+
+    /// Trigger when at least one inner trigger matches
+    Any(Vec<EmotionTrigger>),
+
+    /// Trigger when the inner trigger does not match
+    Not(Box<EmotionTrigger>),
+
+    /// Trigger when the inner trigger has matched for the last `turns` inputs
+    Sustained {
+        /// Trigger that must keep matching
+        trigger: Box<EmotionTrigger>,
+        /// Number of consecutive inputs, including the current one
+        turns: u32,
+    },
+}
+
+/// Quantity of an emotional state a trigger compares
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmotionMeasure {
+    /// One of the eight primary emotions, by name
+    Emotion(String),
+    /// Overall positivity (-1.0 to 1.0)
+    Valence,
+    /// Overall intensity (0.0 to 1.0)
+    Arousal,
+}
+
+impl EmotionMeasure {
+    /// Read the quantity from an emotional state
+    pub fn read(&self, state: &EmotionalState) -> f32 {
+        match self {
+            Self::Emotion(name) => EMOTION_NAMES
+                .iter()
+                .position(|known| known == name)
+                .map_or(0.0, |index| state.as_vector()[index]),
+            Self::Valence => state.valence(),
+            Self::Arousal => state.arousal(),
+        }
+    }
+}
+
+/// Comparison operator in an emotion trigger
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Comparison {
+    /// `<`
+    Less,
+    /// `<=`
+    LessOrEqual,
+    /// `>`
+    Greater,
+    /// `>=`
+    GreaterOrEqual,
+    /// `==`
+    Equal,
+    /// `!=`
+    NotEqual,
+}
+
+impl Comparison {
+    /// Apply the comparison
+    pub fn holds(&self, left: f32, right: f32) -> bool {
+        match self {
+            Self::Less => left < right,
+            Self::LessOrEqual => left <= right,
+            Self::Greater => left > right,
+            Self::GreaterOrEqual => left >= right,
+            Self::Equal => (left - right).abs() < f32::EPSILON,
+            Self::NotEqual => (left - right).abs() >= f32::EPSILON,
+        }
+    }
 }
 
 impl EmotionTrigger {
     /// Check if the emotional state satisfies this trigger
+    ///
+    /// [`EmotionTrigger::Sustained`] triggers spanning more than one turn
+    /// never match a single state; use [`EmotionTrigger::matches_recent`].
     pub fn matches(&self, state: &EmotionalState) -> bool {
+        self.matches_recent(std::slice::from_ref(state))
+    }
+
+    /// Check the trigger against the emotional states of recent inputs
+    ///
+    /// # Arguments
+    ///
+    /// * `recent` - One state per input, oldest first, ending with the current state
+    pub fn matches_recent(&self, recent: &[EmotionalState]) -> bool {
+        let Some(state) = recent.last() else {
+            return false;
+        };
         match self {
             EmotionTrigger::AnyEmotion { min_intensity } => {
                 state.arousal() >= *min_intensity
@@ -58,6 +171,28 @@ impl EmotionTrigger {
             EmotionTrigger::Positive => state.is_positive(),
             EmotionTrigger::Negative => state.is_negative(),
             EmotionTrigger::None => true,
+            EmotionTrigger::Compare { measure, op, value } => op.holds(measure.read(state), *value),
+            EmotionTrigger::Dominant { emotion } => state.dominant_emotion().0 == emotion,
+            EmotionTrigger::All(triggers) => triggers.iter().all(|trigger| trigger.matches_recent(recent)),
+            EmotionTrigger::Any(triggers) => triggers.iter().any(|trigger| trigger.matches_recent(recent)),
+            EmotionTrigger::Not(trigger) => !trigger.matches_recent(recent),
+            EmotionTrigger::Sustained { trigger, turns } => {
+                let turns = (*turns).max(1) as usize;
+                recent.len() >= turns
+                    && (0..turns).all(|back| trigger.matches_recent(&recent[..recent.len() - back]))
+            }
+        }
+    }
+
+    /// Number of recent states the trigger needs to be evaluated fully
+    pub fn turns_needed(&self) -> usize {
+        match self {
+            EmotionTrigger::All(triggers) | EmotionTrigger::Any(triggers) => {
+                triggers.iter().map(EmotionTrigger::turns_needed).max().unwrap_or(1)
+            }
+            EmotionTrigger::Not(trigger) => trigger.turns_needed(),
+            EmotionTrigger::Sustained { trigger, turns } => (*turns).max(1) as usize + trigger.turns_needed() - 1,
+            _ => 1,
         }
     }
 }
@@ -91,6 +226,14 @@ pub enum BehaviorResult {
     /// Behavior triggered an action
     Action(String),
 
+    /// Behavior produced a text response along with an action
+    ResponseWithAction {
+        /// Text response
+        response: String,
+        /// Action emitted before the response is used
+        action: String,
+    },
+
     /// Behavior did not produce a result
     None,
 }
@@ -171,6 +314,78 @@ pub trait Behavior: Send + Sync + std::fmt::Debug {
     fn emotional_priority_modifier(&self, _emotional_state: &EmotionalState) -> i32 {
         0
     }
+
+    /// Get the scheduled activities during which this behavior may run
+    ///
+    /// # Returns
+    ///
+    /// Activity names, or an empty vector to run regardless of schedule
+    fn scheduled_activities(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Get the window of game hours during which this behavior may run
+    ///
+    /// # Returns
+    ///
+    /// Start (inclusive) and end (exclusive) hours, wrapping past midnight
+    /// when the end is earlier, or `None` to run at any hour
+    fn available_hours(&self) -> Option<(f32, f32)> {
+        None
+    }
+
+    /// Get the topics this behavior wants the conversation to cover
+    ///
+    /// They are pushed onto the agent's agenda each time the behavior
+    /// executes, so the agent steers back to them when the conversation
+    /// drifts.
+    ///
+    /// # Returns
+    ///
+    /// Agenda topics, or an empty vector for none
+    fn agenda_topics(&self) -> Vec<AgendaTopic> {
+        Vec::new()
+    }
+
+    /// Get the persuasion check total this behavior requires
+    ///
+    /// Behaviors with a difficulty only run for input accompanied by a
+    /// persuasion check that clears it.
+    ///
+    /// # Returns
+    ///
+    /// The difficulty, or `None` to run without a check
+    fn persuasion_difficulty(&self) -> Option<i32> {
+        None
+    }
+
+    /// Get the longest time [`Behavior::execute`] may take
+    ///
+    /// Execution that overruns is abandoned and the agent moves on with
+    /// [`Behavior::timeout_result`].
+    ///
+    /// # Returns
+    ///
+    /// The timeout, or `None` to use the agent's `supervisor.behavior_timeout_ms`
+    fn timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Get the result used when execution times out
+    ///
+    /// # Returns
+    ///
+    /// The fallback result, or `None` to let lower-priority behaviors run
+    fn timeout_result(&self) -> Option<BehaviorResult> {
+        None
+    }
+
+    /// Use the modifier provider of the agent the behavior is added to
+    ///
+    /// Behaviors pricing or rewarding by sentiment override this, so they
+    /// agree with `Agent::modifier`; wrappers pass it to the behavior they
+    /// wrap.
+    fn use_modifiers(&mut self, _modifiers: &SharedModifiers) {}
 }
 
 /// Base behavior with cooldown tracking
@@ -0,0 +1,453 @@
+//! Combat dialogue behaviors: taunts, surrender and threats
+//!
+//! The game reports the fight in two context keys: `in_combat` (bool) and
+//! `health_pct`, the NPC's remaining health from 0 to 100. Together with the
+//! NPC's fear and anger they decide what it says, and what it does:
+//!
+//! - `taunt`: an angry NPC in a fight it is winning mocks its opponent
+//! - `surrender`: a frightened NPC near death yields, or flees if terrified
+//! - `threaten`: an angry NPC warns off a hostile player before a fight, and
+//!   calls the guards if it is also afraid
+//!
+//! Surrendering, fleeing and calling guards are emitted as actions the game
+//! carries out, such as `combat|flee`. Thresholds are behavior parameters:
+//!
+//! ```yaml
+//! behavior:
+//!   surrender:
+//!     parameters:
+//!       max_health: 20
+//!       min_fear: 0.4
+//!       flee_fear: 0.8
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::agent::AgentContext;
+use crate::oxyde_game::emotion::{EmotionalState, EMOTIONAL_STATE_KEY};
+use crate::oxyde_game::intent::{Intent, IntentType};
+use crate::Result;
+
+use super::base::{Behavior, BehaviorResult, EmotionInfluence, EmotionTrigger};
+use super::variation::{ResponsePool, ResponseVariant};
+
+/// Context key for whether the NPC is fighting
+pub const IN_COMBAT_KEY: &str = "in_combat";
+
+/// Context key for the NPC's remaining health (0 - 100)
+pub const HEALTH_PCT_KEY: &str = "health_pct";
+
+/// Something an NPC does in a fight, emitted as a behavior action
+///
+/// Actions are written as `combat|<action>` and can be parsed back with
+/// [`str::parse`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CombatAction {
+    /// Run away from the fight
+    Flee,
+    /// Call nearby guards for help
+    CallGuards,
+    /// Stop fighting and yield
+    Surrender,
+}
+
+impl CombatAction {
+    /// Get the name used in action strings
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Flee => "flee",
+            Self::CallGuards => "call_guards",
+            Self::Surrender => "surrender",
+        }
+    }
+}
+
+impl fmt::Display for CombatAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "combat|{}", self.as_str())
+    }
+}
+
+impl FromStr for CombatAction {
+    type Err = crate::OxydeError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.strip_prefix("combat|") {
+            Some("flee") => Ok(Self::Flee),
+            Some("call_guards") => Ok(Self::CallGuards),
+            Some("surrender") => Ok(Self::Surrender),
+            _ => Err(crate::OxydeError::BehaviorError(format!("Invalid combat action '{}'", s))),
+        }
+    }
+}
+
+/// What the game reports about a fight, read from the context
+#[derive(Debug, Clone)]
+struct CombatState {
+    in_combat: bool,
+    health_pct: f32,
+    emotions: EmotionalState,
+}
+
+impl CombatState {
+    /// Read the fight from the context; health defaults to full
+    fn from_context(context: &AgentContext) -> Self {
+        Self {
+            in_combat: context.get(IN_COMBAT_KEY).and_then(|v| v.as_bool()).unwrap_or(false),
+            health_pct: context
+                .get(HEALTH_PCT_KEY)
+                .and_then(|v| v.as_f64())
+                .map_or(100.0, |health| health.clamp(0.0, 100.0) as f32),
+            emotions: context
+                .get(EMOTIONAL_STATE_KEY)
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Pick a line, or nothing if every line has weight 0.0
+fn speak(lines: &ResponsePool, context: &AgentContext, action: Option<CombatAction>) -> BehaviorResult {
+    let Some(response) = lines.pick(&mut crate::turn::context_rng(context), context) else {
+        return BehaviorResult::None;
+    };
+    match action {
+        Some(action) => BehaviorResult::ResponseWithAction { response, action: action.to_string() },
+        None => BehaviorResult::Response(response),
+    }
+}
+
+/// Mocks the opponent when angry and winning a fight
+#[derive(Debug)]
+pub struct TauntBehavior {
+    /// Anger needed to taunt (0.0 - 1.0)
+    min_anger: f32,
+
+    /// Health needed to taunt (0 - 100)
+    min_health: f32,
+
+    /// Taunts
+    lines: ResponsePool,
+}
+
+impl TauntBehavior {
+    /// Create a taunt behavior with default lines
+    ///
+    /// # Arguments
+    ///
+    /// * `min_anger` - Anger needed to taunt (0.0 to 1.0)
+    /// * `min_health` - Health needed to taunt (0 to 100)
+    pub fn new(min_anger: f32, min_health: f32) -> Self {
+        Self {
+            min_anger: min_anger.clamp(0.0, 1.0),
+            min_health: min_health.clamp(0.0, 100.0),
+            lines: ResponsePool::new(vec![
+                "Is that all you've got?",
+                "You fight like a farmer, {player}!",
+                "I've had worse from a tavern brawl!",
+                "Come on, try harder!",
+            ]),
+        }
+    }
+
+    /// Create a taunt behavior with default thresholds and lines
+    pub fn new_default() -> Self {
+        Self::new(0.5, 50.0)
+    }
+
+    /// Replace the taunts
+    pub fn with_lines(mut self, lines: Vec<ResponseVariant>) -> Self {
+        self.lines = ResponsePool::new(lines);
+        self
+    }
+}
+
+#[async_trait]
+impl Behavior for TauntBehavior {
+    async fn matches_intent(&self, _intent: &Intent) -> bool {
+        // Whether a fight is on is only known from the context
+        true
+    }
+
+    async fn execute(&self, _intent: &Intent, context: &AgentContext) -> Result<BehaviorResult> {
+        let state = CombatState::from_context(context);
+        if !state.in_combat || state.health_pct < self.min_health || state.emotions.anger < self.min_anger {
+            return Ok(BehaviorResult::None);
+        }
+        Ok(speak(&self.lines, context, None))
+    }
+
+    fn emotion_trigger(&self) -> Option<EmotionTrigger> {
+        Some(EmotionTrigger::SpecificEmotion {
+            emotion: "anger".to_string(),
+            min_value: self.min_anger,
+        })
+    }
+
+    fn emotion_influences(&self) -> Vec<EmotionInfluence> {
+        // Gloating feeds confidence
+        vec![EmotionInfluence::new("fear", -0.05)]
+    }
+
+    fn priority(&self) -> u32 {
+        85
+    }
+}
+
+/// Yields when frightened and badly hurt, or flees when terrified
+#[derive(Debug)]
+pub struct SurrenderBehavior {
+    /// Health at or below which the NPC gives up (0 - 100)
+    max_health: f32,
+
+    /// Fear needed to surrender (0.0 - 1.0)
+    min_fear: f32,
+
+    /// Fear at which the NPC flees instead of surrendering (0.0 - 1.0)
+    flee_fear: f32,
+
+    /// Lines said when surrendering
+    surrender_lines: ResponsePool,
+
+    /// Lines said when fleeing
+    flee_lines: ResponsePool,
+}
+
+impl SurrenderBehavior {
+    /// Create a surrender behavior with default lines
+    ///
+    /// # Arguments
+    ///
+    /// * `max_health` - Health at or below which the NPC gives up (0 to 100)
+    /// * `min_fear` - Fear needed to surrender (0.0 to 1.0)
+    /// * `flee_fear` - Fear at which the NPC flees instead (0.0 to 1.0)
+    pub fn new(max_health: f32, min_fear: f32, flee_fear: f32) -> Self {
+        Self {
+            max_health: max_health.clamp(0.0, 100.0),
+            min_fear: min_fear.clamp(0.0, 1.0),
+            flee_fear: flee_fear.clamp(0.0, 1.0),
+            surrender_lines: ResponsePool::new(vec![
+                "Enough! I yield! *drops weapon*",
+                "Mercy, {player}! I surrender!",
+                "Stop! You've won, I give up!",
+            ]),
+            flee_lines: ResponsePool::new(vec![
+                "I'm not dying here! *runs*",
+                "Too much, too much! *flees*",
+            ]),
+        }
+    }
+
+    /// Create a surrender behavior with default thresholds and lines
+    pub fn new_default() -> Self {
+        Self::new(25.0, 0.5, 0.8)
+    }
+
+    /// Replace the lines said when surrendering
+    pub fn with_surrender_lines(mut self, lines: Vec<ResponseVariant>) -> Self {
+        self.surrender_lines = ResponsePool::new(lines);
+        self
+    }
+
+    /// Replace the lines said when fleeing
+    pub fn with_flee_lines(mut self, lines: Vec<ResponseVariant>) -> Self {
+        self.flee_lines = ResponsePool::new(lines);
+        self
+    }
+}
+
+#[async_trait]
+impl Behavior for SurrenderBehavior {
+    async fn matches_intent(&self, _intent: &Intent) -> bool {
+        // Whether a fight is on is only known from the context
+        true
+    }
+
+    async fn execute(&self, _intent: &Intent, context: &AgentContext) -> Result<BehaviorResult> {
+        let state = CombatState::from_context(context);
+        if !state.in_combat || state.health_pct > self.max_health || state.emotions.fear < self.min_fear {
+            return Ok(BehaviorResult::None);
+        }
+        Ok(if state.emotions.fear >= self.flee_fear {
+            speak(&self.flee_lines, context, Some(CombatAction::Flee))
+        } else {
+            speak(&self.surrender_lines, context, Some(CombatAction::Surrender))
+        })
+    }
+
+    fn emotion_trigger(&self) -> Option<EmotionTrigger> {
+        Some(EmotionTrigger::SpecificEmotion {
+            emotion: "fear".to_string(),
+            min_value: self.min_fear,
+        })
+    }
+
+    fn emotion_influences(&self) -> Vec<EmotionInfluence> {
+        // Giving up ends the immediate danger but not the shame
+        vec![
+            EmotionInfluence::new("fear", -0.2),
+            EmotionInfluence::new("sadness", 0.15),
+        ]
+    }
+
+    fn priority(&self) -> u32 {
+        110 // Above fleeing and aggression: survival decides the fight
+    }
+}
+
+/// Warns off a hostile player before a fight, calling guards when afraid
+#[derive(Debug)]
+pub struct ThreatenBehavior {
+    /// Anger needed to threaten (0.0 - 1.0)
+    min_anger: f32,
+
+    /// Fear at which the NPC calls the guards (0.0 - 1.0)
+    call_guards_fear: f32,
+
+    /// Threats
+    threats: ResponsePool,
+
+    /// Lines said when calling the guards
+    guard_calls: ResponsePool,
+}
+
+impl ThreatenBehavior {
+    /// Create a threaten behavior with default lines
+    ///
+    /// # Arguments
+    ///
+    /// * `min_anger` - Anger needed to threaten (0.0 to 1.0)
+    /// * `call_guards_fear` - Fear at which the NPC calls the guards (0.0 to 1.0)
+    pub fn new(min_anger: f32, call_guards_fear: f32) -> Self {
+        Self {
+            min_anger: min_anger.clamp(0.0, 1.0),
+            call_guards_fear: call_guards_fear.clamp(0.0, 1.0),
+            threats: ResponsePool::new(vec![
+                "Take one more step and you'll regret it.",
+                "Back off, {player}, or I'll make you.",
+                "I'm warning you. Walk away.",
+            ]),
+            guard_calls: ResponsePool::new(vec![
+                "Guards! Guards!",
+                "Stay back! Guards, over here!",
+            ]),
+        }
+    }
+
+    /// Create a threaten behavior with default thresholds and lines
+    pub fn new_default() -> Self {
+        Self::new(0.4, 0.5)
+    }
+
+    /// Replace the threats
+    pub fn with_threats(mut self, lines: Vec<ResponseVariant>) -> Self {
+        self.threats = ResponsePool::new(lines);
+        self
+    }
+
+    /// Replace the lines said when calling the guards
+    pub fn with_guard_calls(mut self, lines: Vec<ResponseVariant>) -> Self {
+        self.guard_calls = ResponsePool::new(lines);
+        self
+    }
+}
+
+#[async_trait]
+impl Behavior for ThreatenBehavior {
+    async fn matches_intent(&self, intent: &Intent) -> bool {
+        matches!(intent.intent_type, IntentType::Threat | IntentType::Hostile | IntentType::Demand)
+    }
+
+    async fn execute(&self, _intent: &Intent, context: &AgentContext) -> Result<BehaviorResult> {
+        let state = CombatState::from_context(context);
+        // Once the fight starts, taunts and surrender take over
+        if state.in_combat || state.emotions.anger < self.min_anger {
+            return Ok(BehaviorResult::None);
+        }
+        Ok(if state.emotions.fear >= self.call_guards_fear {
+            speak(&self.guard_calls, context, Some(CombatAction::CallGuards))
+        } else {
+            speak(&self.threats, context, None)
+        })
+    }
+
+    fn emotion_trigger(&self) -> Option<EmotionTrigger> {
+        Some(EmotionTrigger::SpecificEmotion {
+            emotion: "anger".to_string(),
+            min_value: self.min_anger,
+        })
+    }
+
+    fn emotion_influences(&self) -> Vec<EmotionInfluence> {
+        vec![EmotionInfluence::new("trust", -0.1)]
+    }
+
+    fn priority(&self) -> u32 {
+        90 // Above plain aggression, which it replaces before a fight
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::ContextBuilder;
+
+    fn fight(in_combat: bool, health_pct: f32, fear: f32, anger: f32) -> AgentContext {
+        let mut emotions = EmotionalState::new();
+        emotions.fear = fear;
+        emotions.anger = anger;
+        ContextBuilder::new()
+            .combat(in_combat, health_pct)
+            .with(EMOTIONAL_STATE_KEY, &emotions)
+            .build()
+    }
+
+    fn action(result: &BehaviorResult) -> Option<CombatAction> {
+        match result {
+            BehaviorResult::ResponseWithAction { action, .. } => Some(action.parse().unwrap()),
+            _ => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_surrender_flees_when_terrified() {
+        let intent = Intent::analyze("Die!").await.unwrap();
+        let behavior = SurrenderBehavior::new_default();
+
+        let result = behavior.execute(&intent, &fight(true, 20.0, 0.6, 0.0)).await.unwrap();
+        assert_eq!(action(&result), Some(CombatAction::Surrender));
+        let result = behavior.execute(&intent, &fight(true, 20.0, 0.9, 0.0)).await.unwrap();
+        assert_eq!(action(&result), Some(CombatAction::Flee));
+
+        // Healthy, calm or out of combat NPCs keep going
+        for context in [fight(true, 60.0, 0.9, 0.0), fight(true, 20.0, 0.2, 0.0), fight(false, 20.0, 0.9, 0.0)] {
+            assert!(matches!(behavior.execute(&intent, &context).await.unwrap(), BehaviorResult::None));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_threaten_calls_guards_when_afraid_and_taunt_needs_the_upper_hand() {
+        let intent = Intent::new(IntentType::Threat, 1.0, "Hand over your gold or else!", vec![]);
+        let threaten = ThreatenBehavior::new_default();
+        assert!(threaten.matches_intent(&intent).await);
+
+        let result = threaten.execute(&intent, &fight(false, 100.0, 0.1, 0.7)).await.unwrap();
+        assert!(matches!(result, BehaviorResult::Response(_)));
+        let result = threaten.execute(&intent, &fight(false, 100.0, 0.6, 0.7)).await.unwrap();
+        assert_eq!(action(&result), Some(CombatAction::CallGuards));
+        let result = threaten.execute(&intent, &fight(true, 100.0, 0.1, 0.7)).await.unwrap();
+        assert!(matches!(result, BehaviorResult::None));
+
+        let taunt = TauntBehavior::new_default();
+        assert!(matches!(taunt.execute(&intent, &fight(true, 80.0, 0.0, 0.7)).await.unwrap(), BehaviorResult::Response(_)));
+        assert!(matches!(taunt.execute(&intent, &fight(true, 30.0, 0.0, 0.7)).await.unwrap(), BehaviorResult::None));
+
+        assert_eq!("combat|call_guards".parse::<CombatAction>().unwrap(), CombatAction::CallGuards);
+        assert!("combat|dance".parse::<CombatAction>().is_err());
+    }
+}
@@ -0,0 +1,256 @@
+//! Wrappers applying behavior config settings: condition expressions,
+//! emotion triggers and execution timeouts
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::agent::AgentContext;
+use crate::condition::Condition;
+use crate::oxyde_game::emotion::EmotionalState;
+use crate::oxyde_game::intent::Intent;
+use crate::oxyde_game::modifier::SharedModifiers;
+use crate::oxyde_game::topic::AgendaTopic;
+use crate::Result;
+
+use super::base::{Behavior, BehaviorResult, EmotionInfluence, EmotionTrigger};
+
+/// Behavior wrapper that only runs while a [`Condition`] holds
+///
+/// The condition is evaluated against the behavior context, including the
+/// agent's emotions. When it fails the behavior returns
+/// [`BehaviorResult::None`], so lower-priority behaviors get a turn.
+#[derive(Debug)]
+pub struct ConditionalBehavior {
+    inner: Box<dyn Behavior>,
+    condition: Condition,
+}
+
+impl ConditionalBehavior {
+    /// Gate a behavior behind a condition
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - Behavior to wrap
+    /// * `condition` - Condition that must hold for the behavior to run
+    pub fn new(inner: Box<dyn Behavior>, condition: Condition) -> Self {
+        Self { inner, condition }
+    }
+
+    /// Get the gating condition
+    pub fn condition(&self) -> &Condition {
+        &self.condition
+    }
+}
+
+#[async_trait]
+impl Behavior for ConditionalBehavior {
+    async fn matches_intent(&self, intent: &Intent) -> bool {
+        self.inner.matches_intent(intent).await
+    }
+
+    async fn execute(&self, intent: &Intent, context: &AgentContext) -> Result<BehaviorResult> {
+        if !self.condition.evaluate_in(context) {
+            return Ok(BehaviorResult::None);
+        }
+        self.inner.execute(intent, context).await
+    }
+
+    fn emotion_trigger(&self) -> Option<EmotionTrigger> {
+        self.inner.emotion_trigger()
+    }
+
+    fn emotion_influences(&self) -> Vec<EmotionInfluence> {
+        self.inner.emotion_influences()
+    }
+
+    fn priority(&self) -> u32 {
+        self.inner.priority()
+    }
+
+    fn emotional_priority_modifier(&self, emotional_state: &EmotionalState) -> i32 {
+        self.inner.emotional_priority_modifier(emotional_state)
+    }
+
+    fn scheduled_activities(&self) -> Vec<String> {
+        self.inner.scheduled_activities()
+    }
+
+    fn available_hours(&self) -> Option<(f32, f32)> {
+        self.inner.available_hours()
+    }
+
+    fn agenda_topics(&self) -> Vec<AgendaTopic> {
+        self.inner.agenda_topics()
+    }
+
+    fn persuasion_difficulty(&self) -> Option<i32> {
+        self.inner.persuasion_difficulty()
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.inner.timeout()
+    }
+
+    fn timeout_result(&self) -> Option<BehaviorResult> {
+        self.inner.timeout_result()
+    }
+    fn use_modifiers(&mut self, modifiers: &SharedModifiers) {
+        self.inner.use_modifiers(modifiers)
+    }
+}
+
+/// Behavior wrapper replacing the emotion trigger of the wrapped behavior
+///
+/// Built for behaviors configured with an `emotion_trigger`, so compound and
+/// sustained triggers apply to any registered behavior.
+#[derive(Debug)]
+pub struct TriggeredBehavior {
+    inner: Box<dyn Behavior>,
+    trigger: EmotionTrigger,
+}
+
+impl TriggeredBehavior {
+    /// Gate a behavior behind an emotion trigger
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - Behavior to wrap
+    /// * `trigger` - Trigger that must match for the behavior to be selected
+    pub fn new(inner: Box<dyn Behavior>, trigger: EmotionTrigger) -> Self {
+        Self { inner, trigger }
+    }
+}
+
+#[async_trait]
+impl Behavior for TriggeredBehavior {
+    async fn matches_intent(&self, intent: &Intent) -> bool {
+        self.inner.matches_intent(intent).await
+    }
+
+    async fn execute(&self, intent: &Intent, context: &AgentContext) -> Result<BehaviorResult> {
+        self.inner.execute(intent, context).await
+    }
+
+    fn emotion_trigger(&self) -> Option<EmotionTrigger> {
+        Some(self.trigger.clone())
+    }
+
+    fn emotion_influences(&self) -> Vec<EmotionInfluence> {
+        self.inner.emotion_influences()
+    }
+
+    fn priority(&self) -> u32 {
+        self.inner.priority()
+    }
+
+    fn emotional_priority_modifier(&self, emotional_state: &EmotionalState) -> i32 {
+        self.inner.emotional_priority_modifier(emotional_state)
+    }
+
+    fn scheduled_activities(&self) -> Vec<String> {
+        self.inner.scheduled_activities()
+    }
+
+    fn available_hours(&self) -> Option<(f32, f32)> {
+        self.inner.available_hours()
+    }
+
+    fn agenda_topics(&self) -> Vec<AgendaTopic> {
+        self.inner.agenda_topics()
+    }
+
+    fn persuasion_difficulty(&self) -> Option<i32> {
+        self.inner.persuasion_difficulty()
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.inner.timeout()
+    }
+
+    fn timeout_result(&self) -> Option<BehaviorResult> {
+        self.inner.timeout_result()
+    }
+    fn use_modifiers(&mut self, modifiers: &SharedModifiers) {
+        self.inner.use_modifiers(modifiers)
+    }
+}
+
+/// Behavior wrapper with a configured execution timeout and fallback
+#[derive(Debug)]
+pub struct TimedBehavior {
+    inner: Box<dyn Behavior>,
+    timeout: Option<Duration>,
+    timeout_result: Option<BehaviorResult>,
+}
+
+impl TimedBehavior {
+    /// Give a behavior its own timeout and fallback result
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - Behavior to wrap
+    /// * `timeout` - Longest time execution may take; the wrapped behavior's when `None`
+    /// * `timeout_result` - Result used on timeout; the wrapped behavior's when `None`
+    pub fn new(inner: Box<dyn Behavior>, timeout: Option<Duration>, timeout_result: Option<BehaviorResult>) -> Self {
+        Self {
+            inner,
+            timeout,
+            timeout_result,
+        }
+    }
+}
+
+#[async_trait]
+impl Behavior for TimedBehavior {
+    async fn matches_intent(&self, intent: &Intent) -> bool {
+        self.inner.matches_intent(intent).await
+    }
+
+    async fn execute(&self, intent: &Intent, context: &AgentContext) -> Result<BehaviorResult> {
+        self.inner.execute(intent, context).await
+    }
+
+    fn emotion_trigger(&self) -> Option<EmotionTrigger> {
+        self.inner.emotion_trigger()
+    }
+
+    fn emotion_influences(&self) -> Vec<EmotionInfluence> {
+        self.inner.emotion_influences()
+    }
+
+    fn priority(&self) -> u32 {
+        self.inner.priority()
+    }
+
+    fn emotional_priority_modifier(&self, emotional_state: &EmotionalState) -> i32 {
+        self.inner.emotional_priority_modifier(emotional_state)
+    }
+
+    fn scheduled_activities(&self) -> Vec<String> {
+        self.inner.scheduled_activities()
+    }
+
+    fn available_hours(&self) -> Option<(f32, f32)> {
+        self.inner.available_hours()
+    }
+
+    fn agenda_topics(&self) -> Vec<AgendaTopic> {
+        self.inner.agenda_topics()
+    }
+
+    fn persuasion_difficulty(&self) -> Option<i32> {
+        self.inner.persuasion_difficulty()
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout.or_else(|| self.inner.timeout())
+    }
+
+    fn timeout_result(&self) -> Option<BehaviorResult> {
+        self.timeout_result.clone().or_else(|| self.inner.timeout_result())
+    }
+    fn use_modifiers(&mut self, modifiers: &SharedModifiers) {
+        self.inner.use_modifiers(modifiers)
+    }
+}
@@ -9,6 +9,7 @@ use crate::oxyde_game::intent::{Intent, IntentType};
 use crate::Result;
 
 use super::base::{Behavior, BehaviorResult, BaseBehavior};
+use super::variation::ResponsePool;
 
 /// Dialogue behavior that responds to specific topics
 #[derive(Debug)]
@@ -18,10 +19,10 @@ pub struct DialogueBehavior {
     base: BaseBehavior,
 
     /// Topic responses
-    topics: HashMap<String, Vec<String>>,
+    topics: HashMap<String, ResponsePool>,
 
     /// Default responses when topic not found
-    default_responses: Vec<String>,
+    default_responses: ResponsePool,
 }
 
 impl DialogueBehavior {
@@ -44,8 +45,11 @@ impl DialogueBehavior {
                 vec!["question".to_string(), "chat".to_string()],
                 0, // No cooldown for dialogue
             ),
-            topics,
-            default_responses,
+            topics: topics
+                .into_iter()
+                .map(|(topic, responses)| (topic, ResponsePool::new(responses)))
+                .collect(),
+            default_responses: ResponsePool::new(default_responses),
         }
     }
 }
@@ -59,36 +63,23 @@ impl Behavior for DialogueBehavior {
         )
     }
 
-    async fn execute(&self, intent: &Intent, _context: &AgentContext) -> Result<BehaviorResult> {
+    async fn execute(&self, intent: &Intent, context: &AgentContext) -> Result<BehaviorResult> {
+        let mut rng = crate::turn::context_rng(context);
+
         // Extract topic from intent
         let topic = intent.raw_input.to_lowercase();
 
-        // Find matching topic
+        // Find matching topic, avoiding responses used recently
         let response = self
             .topics
             .iter()
             .find(|(key, _)| topic.contains(key.as_str()))
-            .and_then(|(_, responses)| {
-                if responses.is_empty() {
-                    None
-                } else {
-                    let idx = rand::random::<usize>() % responses.len();
-                    Some(responses[idx].clone())
-                }
-            });
+            .and_then(|(_, responses)| responses.pick(&mut rng, context));
 
         // Use the found response or fall back to default
-        let final_response = match response {
-            Some(r) => r,
-            None => {
-                if self.default_responses.is_empty() {
-                    return Ok(BehaviorResult::None);
-                }
-                let idx = rand::random::<usize>() % self.default_responses.len();
-                self.default_responses[idx].clone()
-            }
-        };
-
-        Ok(BehaviorResult::Response(final_response))
+        match response.or_else(|| self.default_responses.pick(&mut rng, context)) {
+            Some(response) => Ok(BehaviorResult::Response(response)),
+            None => Ok(BehaviorResult::None),
+        }
     }
 }
@@ -0,0 +1,356 @@
+//! Behavior selection reports
+//!
+//! Every time an agent picks a behavior it records a [`SelectionReport`]
+//! describing each candidate: whether its schedule and emotion trigger
+//! allowed it to run, its base priority and emotional modifier, whether it
+//! matched the intent, and what it returned. Designers can fetch the report
+//! for the last input with `Agent::explain_last_selection` to see why one
+//! behavior beat another.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::base::{Behavior, BehaviorResult, EmotionTrigger};
+use crate::oxyde_game::emotion::EmotionalState;
+use crate::oxyde_game::intent::{Intent, IntentType};
+use crate::oxyde_game::persuasion::PersuasionResult;
+use crate::oxyde_game::schedule::hour_in_window;
+
+/// Why a behavior was or was not considered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CandidateStatus {
+    /// Passed the schedule and trigger checks and was ranked
+    Eligible,
+    /// Restricted to scheduled activities or hours other than the current ones
+    OutOfSchedule,
+    /// Its emotion trigger did not match the emotional state
+    TriggerFailed,
+    /// It requires a persuasion check that was not made or did not clear
+    PersuasionFailed,
+}
+
+/// What a candidate produced when executed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum CandidateOutcome {
+    /// Returned a response, ending selection
+    Response(String),
+    /// Emitted an action; selection continued
+    Action(String),
+    /// Returned a response and emitted an action, ending selection
+    ResponseWithAction {
+        /// Response text
+        response: String,
+        /// Emitted action
+        action: String,
+    },
+    /// Returned nothing; selection continued
+    NoResult,
+    /// Failed with an error
+    Error(String),
+    /// Did not finish within its timeout
+    TimedOut {
+        /// Timeout that elapsed, in milliseconds
+        timeout_ms: u64,
+        /// Outcome of the behavior's timeout result
+        fallback: Box<CandidateOutcome>,
+    },
+}
+
+/// Diagnostic record for one behavior
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BehaviorCandidate {
+    /// Position of the behavior in the agent's behavior list
+    pub index: usize,
+
+    /// Behavior type name
+    pub name: String,
+
+    /// Base priority
+    pub base_priority: u32,
+
+    /// Priority modifier from the emotional state
+    pub emotional_modifier: i32,
+
+    /// Base priority plus emotional modifier
+    pub effective_priority: i32,
+
+    /// Emotion trigger, if the behavior has one
+    pub trigger: Option<EmotionTrigger>,
+
+    /// Persuasion check total required, if the behavior has one
+    #[serde(default)]
+    pub persuasion_difficulty: Option<i32>,
+
+    /// Result of the schedule and trigger checks
+    pub status: CandidateStatus,
+
+    /// Whether the behavior matched the intent; `None` if it was never asked
+    pub intent_matched: Option<bool>,
+
+    /// What executing the behavior produced; `None` if it did not run
+    pub outcome: Option<CandidateOutcome>,
+}
+
+/// Structured explanation of one behavior selection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelectionReport {
+    /// Player input that triggered the selection
+    pub input: String,
+
+    /// Detected intent
+    pub intent: IntentType,
+
+    /// Intent classification confidence
+    pub intent_confidence: f64,
+
+    /// Emotion vector used for triggers and modifiers
+    pub emotions: [f32; 8],
+
+    /// Scheduled activity at the time of selection
+    pub activity: Option<String>,
+
+    /// Persuasion check made with the input, if any
+    #[serde(default)]
+    pub persuasion: Option<PersuasionResult>,
+
+    /// Candidates, eligible ones first in ranked order
+    pub candidates: Vec<BehaviorCandidate>,
+
+    /// Index (into the agent's behavior list) of the behavior whose response was used
+    pub selected: Option<usize>,
+
+    /// Whether no behavior responded and the agent fell back to inference
+    pub used_inference: bool,
+}
+
+/// Behavior type name taken from its `Debug` output
+fn behavior_name(behavior: &dyn Behavior) -> String {
+    let debug = format!("{:?}", behavior);
+    debug
+        .split([' ', '{', '('])
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+impl SelectionReport {
+    /// Evaluate behaviors for an intent and rank the eligible ones
+    ///
+    /// Behaviors restricted to scheduled activities are excluded unless one of
+    /// them is current, behaviors restricted to game hours are excluded
+    /// outside their window or when the hour is unknown, as are behaviors whose emotion trigger does not match
+    /// and behaviors whose persuasion difficulty the check did not clear.
+    /// Eligible behaviors are ordered by effective priority, highest first;
+    /// ties keep their registration order.
+    ///
+    /// `recent_emotions` holds the emotional state at each recent input,
+    /// oldest first and ending with the current one, so sustained triggers
+    /// can look back over several turns.
+    ///
+    /// # Returns
+    ///
+    /// The report and the ranked indices of eligible behaviors
+    pub(crate) fn evaluate(
+        intent: &Intent,
+        behaviors: &[Box<dyn Behavior>],
+        recent_emotions: &[EmotionalState],
+        activity: Option<&str>,
+        hour: Option<f32>,
+        persuasion: Option<&PersuasionResult>,
+    ) -> (Self, Vec<usize>) {
+        let emotional_state = &recent_emotions.last().cloned().unwrap_or_default();
+        let mut candidates: Vec<BehaviorCandidate> = behaviors
+            .iter()
+            .enumerate()
+            .map(|(index, behavior)| {
+                let activities = behavior.scheduled_activities();
+                let in_schedule = (activities.is_empty()
+                    || activity.is_some_and(|a| activities.iter().any(|s| s == a)))
+                    && behavior.available_hours().is_none_or(|(start, end)| {
+                        hour.is_some_and(|h| hour_in_window(h, start, end))
+                    });
+                let trigger = behavior.emotion_trigger();
+                let trigger_passed = trigger.as_ref().is_none_or(|t| t.matches_recent(recent_emotions));
+                let persuasion_difficulty = behavior.persuasion_difficulty();
+                let persuaded = persuasion_difficulty
+                    .is_none_or(|difficulty| persuasion.is_some_and(|p| p.clears(difficulty)));
+
+                let status = if !in_schedule {
+                    CandidateStatus::OutOfSchedule
+                } else if !trigger_passed {
+                    CandidateStatus::TriggerFailed
+                } else if !persuaded {
+                    CandidateStatus::PersuasionFailed
+                } else {
+                    CandidateStatus::Eligible
+                };
+
+                let base_priority = behavior.priority();
+                let emotional_modifier = behavior.emotional_priority_modifier(emotional_state);
+
+                BehaviorCandidate {
+                    index,
+                    name: behavior_name(behavior.as_ref()),
+                    base_priority,
+                    emotional_modifier,
+                    effective_priority: base_priority as i32 + emotional_modifier,
+                    trigger,
+                    persuasion_difficulty,
+                    status,
+                    intent_matched: None,
+                    outcome: None,
+                }
+            })
+            .collect();
+
+        // Eligible first, highest effective priority first; the sort is stable
+        candidates.sort_by(|a, b| {
+            let a_eligible = a.status == CandidateStatus::Eligible;
+            let b_eligible = b.status == CandidateStatus::Eligible;
+            b_eligible
+                .cmp(&a_eligible)
+                .then_with(|| b.effective_priority.cmp(&a.effective_priority))
+        });
+
+        let ranked = candidates
+            .iter()
+            .filter(|c| c.status == CandidateStatus::Eligible)
+            .map(|c| c.index)
+            .collect();
+
+        let report = Self {
+            input: intent.raw_input.clone(),
+            intent: intent.intent_type,
+            intent_confidence: intent.confidence,
+            emotions: emotional_state.as_vector(),
+            activity: activity.map(|a| a.to_string()),
+            persuasion: persuasion.copied(),
+            candidates,
+            selected: None,
+            used_inference: false,
+        };
+
+        (report, ranked)
+    }
+
+    /// Get the candidate entry for a behavior index
+    pub fn candidate(&self, index: usize) -> Option<&BehaviorCandidate> {
+        self.candidates.iter().find(|c| c.index == index)
+    }
+
+    fn candidate_mut(&mut self, index: usize) -> Option<&mut BehaviorCandidate> {
+        self.candidates.iter_mut().find(|c| c.index == index)
+    }
+
+    /// Record whether a behavior matched the intent
+    pub(crate) fn record_match(&mut self, index: usize, matched: bool) {
+        if let Some(candidate) = self.candidate_mut(index) {
+            candidate.intent_matched = Some(matched);
+        }
+    }
+
+    /// Record what a behavior produced
+    pub(crate) fn record_result(&mut self, index: usize, result: &crate::Result<BehaviorResult>) {
+        let outcome = match result {
+            Ok(BehaviorResult::Response(text)) => {
+                self.selected = Some(index);
+                CandidateOutcome::Response(text.clone())
+            }
+            Ok(BehaviorResult::Action(action)) => CandidateOutcome::Action(action.clone()),
+            Ok(BehaviorResult::ResponseWithAction { response, action }) => {
+                self.selected = Some(index);
+                CandidateOutcome::ResponseWithAction {
+                    response: response.clone(),
+                    action: action.clone(),
+                }
+            }
+            Ok(BehaviorResult::None) => CandidateOutcome::NoResult,
+            Err(e) => CandidateOutcome::Error(e.to_string()),
+        };
+        if let Some(candidate) = self.candidate_mut(index) {
+            candidate.outcome = Some(outcome);
+        }
+    }
+
+    /// Record that a behavior timed out and its timeout result was used
+    pub(crate) fn record_timeout(&mut self, index: usize, timeout_ms: u64, fallback: &BehaviorResult) {
+        self.record_result(index, &Ok(fallback.clone()));
+        if let Some(candidate) = self.candidate_mut(index) {
+            let fallback = Box::new(candidate.outcome.take().unwrap_or(CandidateOutcome::NoResult));
+            candidate.outcome = Some(CandidateOutcome::TimedOut { timeout_ms, fallback });
+        }
+    }
+
+    /// The candidate whose response was used, if any
+    pub fn winner(&self) -> Option<&BehaviorCandidate> {
+        self.selected.and_then(|index| self.candidate(index))
+    }
+}
+
+impl fmt::Display for SelectionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Input {:?} -> intent {:?} ({:.2})",
+            self.input, self.intent, self.intent_confidence
+        )?;
+        if let Some(persuasion) = &self.persuasion {
+            writeln!(
+                f,
+                "Persuasion {} vs {}: {:?}",
+                persuasion.total, persuasion.difficulty, persuasion.outcome
+            )?;
+        }
+        for candidate in &self.candidates {
+            write!(
+                f,
+                "  #{} {} priority {} ({:+}) = {}: {:?}",
+                candidate.index,
+                candidate.name,
+                candidate.base_priority,
+                candidate.emotional_modifier,
+                candidate.effective_priority,
+                candidate.status
+            )?;
+            if let Some(matched) = candidate.intent_matched {
+                write!(f, ", intent {}", if matched { "matched" } else { "not matched" })?;
+            }
+            if let Some(outcome) = &candidate.outcome {
+                write!(f, ", {:?}", outcome)?;
+            }
+            writeln!(f)?;
+        }
+        match self.winner() {
+            Some(winner) => write!(f, "Selected #{} {}", winner.index, winner.name),
+            None if self.used_inference => write!(f, "No behavior responded; used inference"),
+            None => write!(f, "No behavior responded"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oxyde_game::behavior::{AggressiveBehavior, GreetingBehavior};
+
+    #[tokio::test]
+    async fn test_evaluate_ranks_and_explains() {
+        let behaviors: Vec<Box<dyn Behavior>> = vec![
+            Box::new(GreetingBehavior::new("Hello!")),
+            Box::new(AggressiveBehavior::new(0.5)),
+        ];
+        let intent = Intent::analyze("hello").await.unwrap();
+        let state = EmotionalState::new();
+
+        let (mut report, ranked) = SelectionReport::evaluate(&intent, &behaviors, &[state], None, None, None);
+        assert_eq!(ranked, vec![0]);
+        assert_eq!(report.candidate(0).unwrap().name, "GreetingBehavior");
+        assert_eq!(report.candidate(1).unwrap().status, CandidateStatus::TriggerFailed);
+
+        report.record_match(0, true);
+        report.record_result(0, &Ok(BehaviorResult::Response("Hello!".to_string())));
+        assert_eq!(report.winner().unwrap().index, 0);
+        assert!(report.to_string().contains("Selected #0 GreetingBehavior"));
+    }
+}
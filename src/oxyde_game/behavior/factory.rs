@@ -1,8 +1,27 @@
 //! Factory functions to create common behaviors
+//!
+//! Besides the `create_*` helpers, this module hosts the [`BehaviorRegistry`]:
+//! a table of behavior constructors keyed by name. Behavior packs register
+//! their constructors (globally with [`register_behavior`] or on a registry
+//! of their own), and agent configs refer to them by name in the `behavior`
+//! section.
 
 use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
-use super::{DialogueBehavior, GreetingBehavior, PathfindingBehavior};
+use serde::de::DeserializeOwned;
+
+use crate::config::BehaviorConfig;
+use crate::oxyde_game::modifier::SentimentModifiers;
+use crate::oxyde_game::reengagement::ReengagementBehavior;
+use crate::{OxydeError, Result};
+
+use super::{
+    Behavior, BehaviorResult, ConditionalBehavior, DialogueBehavior, GreetingBehavior, PathfindingBehavior, ResponseVariant,
+    SurrenderBehavior, TauntBehavior, ThreatenBehavior, TimedBehavior, TradingBehavior, TriggeredBehavior,
+};
 
 /// Create a standard greeting behavior
 ///
@@ -49,3 +68,365 @@ pub fn create_follow() -> PathfindingBehavior {
 pub fn create_stationary() -> PathfindingBehavior {
     PathfindingBehavior::new_stationary()
 }
+
+/// Constructor that builds a behavior from its configuration
+pub type BehaviorConstructor = Arc<dyn Fn(&BehaviorConfig) -> Result<Box<dyn Behavior>> + Send + Sync>;
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_REGISTRY: RwLock<BehaviorRegistry> = RwLock::new(BehaviorRegistry::with_builtins());
+}
+
+/// Read a typed parameter from a behavior configuration
+///
+/// # Arguments
+///
+/// * `config` - Behavior configuration
+/// * `key` - Parameter key
+///
+/// # Returns
+///
+/// The parameter value, `None` if absent, or an error if it has the wrong type
+pub fn parameter<T: DeserializeOwned>(config: &BehaviorConfig, key: &str) -> Result<Option<T>> {
+    config
+        .parameters
+        .get(key)
+        .map(|value| {
+            serde_json::from_value(value.clone()).map_err(|e| {
+                OxydeError::ConfigurationError(format!("Invalid behavior parameter '{}': {}", key, e))
+            })
+        })
+        .transpose()
+}
+
+/// Registry of behavior constructors keyed by name
+#[derive(Clone, Default)]
+pub struct BehaviorRegistry {
+    constructors: HashMap<String, BehaviorConstructor>,
+}
+
+impl fmt::Debug for BehaviorRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BehaviorRegistry")
+            .field("names", &self.names())
+            .finish()
+    }
+}
+
+impl BehaviorRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a registry with the SDK's built-in behaviors
+    ///
+    /// Registers `greeting` (`greetings`, `distance`, `avoid_repeats`), `dialogue` (`topics`,
+    /// `default_responses`), `follow` (`max_distance`, `speed`), `stationary`,
+    /// `trading` (`buy_back_ratio`, and `max_discount` and `max_markup` to
+    /// override the agent's `modifiers`), `reengagement`
+    /// (`lines`, `min_elapsed`), and the combat behaviors `taunt` (`min_anger`,
+    /// `min_health`, `lines`), `surrender` (`max_health`, `min_fear`, `flee_fear`,
+    /// `lines`, `flee_lines`) and `threaten` (`min_anger`, `call_guards_fear`,
+    /// `lines`, `guard_lines`); the names in parentheses are optional
+    /// parameters.
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        registry.register("greeting", |config| {
+            let mut behavior = match parameter::<Vec<ResponseVariant>>(config, "greetings")? {
+                Some(greetings) => {
+                    let distance = parameter(config, "distance")?.unwrap_or(3.0);
+                    GreetingBehavior::with_variants(distance, greetings)
+                }
+                None => create_greeting(),
+            };
+            if let Some(avoid_repeats) = parameter(config, "avoid_repeats")? {
+                behavior = behavior.with_avoid_repeats(avoid_repeats);
+            }
+            Ok(Box::new(behavior))
+        });
+        registry.register("dialogue", |config| {
+            let topics = parameter(config, "topics")?.unwrap_or_default();
+            let behavior = match parameter(config, "default_responses")? {
+                Some(default_responses) => DialogueBehavior::new(topics, default_responses),
+                None => create_dialogue(topics),
+            };
+            Ok(Box::new(behavior))
+        });
+        registry.register("follow", |config| {
+            let max_distance = parameter(config, "max_distance")?.unwrap_or(10.0);
+            let speed = parameter(config, "speed")?.unwrap_or(1.5);
+            Ok(Box::new(PathfindingBehavior::new(true, max_distance, speed)))
+        });
+        registry.register("stationary", |_| Ok(Box::new(create_stationary())));
+        registry.register("trading", |config| {
+            let max_discount: Option<f32> = parameter(config, "max_discount")?;
+            let max_markup: Option<f32> = parameter(config, "max_markup")?;
+            let buy_back_ratio = parameter(config, "buy_back_ratio")?.unwrap_or(0.5);
+            let max_discount_or_default = max_discount.unwrap_or(0.2);
+            let mut behavior = TradingBehavior::new(max_discount_or_default, buy_back_ratio);
+            // Limits given here override the agent's `modifiers` section
+            if max_discount.is_some() || max_markup.is_some() {
+                let modifiers = SentimentModifiers::new(max_discount_or_default, max_markup.unwrap_or(0.0));
+                behavior = behavior.with_modifiers(Arc::new(modifiers));
+            }
+            Ok(Box::new(behavior))
+        });
+        registry.register("reengagement", |config| {
+            let behavior = match parameter::<Vec<String>>(config, "lines")? {
+                Some(lines) => ReengagementBehavior::new(lines, parameter(config, "min_elapsed")?.unwrap_or(0)),
+                None => ReengagementBehavior::new_default(),
+            };
+            Ok(Box::new(behavior))
+        });
+        registry.register("taunt", |config| {
+            let mut behavior = TauntBehavior::new(
+                parameter(config, "min_anger")?.unwrap_or(0.5),
+                parameter(config, "min_health")?.unwrap_or(50.0),
+            );
+            if let Some(lines) = parameter(config, "lines")? {
+                behavior = behavior.with_lines(lines);
+            }
+            Ok(Box::new(behavior))
+        });
+        registry.register("surrender", |config| {
+            let mut behavior = SurrenderBehavior::new(
+                parameter(config, "max_health")?.unwrap_or(25.0),
+                parameter(config, "min_fear")?.unwrap_or(0.5),
+                parameter(config, "flee_fear")?.unwrap_or(0.8),
+            );
+            if let Some(lines) = parameter(config, "lines")? {
+                behavior = behavior.with_surrender_lines(lines);
+            }
+            if let Some(lines) = parameter(config, "flee_lines")? {
+                behavior = behavior.with_flee_lines(lines);
+            }
+            Ok(Box::new(behavior))
+        });
+        registry.register("threaten", |config| {
+            let mut behavior = ThreatenBehavior::new(
+                parameter(config, "min_anger")?.unwrap_or(0.4),
+                parameter(config, "call_guards_fear")?.unwrap_or(0.5),
+            );
+            if let Some(lines) = parameter(config, "lines")? {
+                behavior = behavior.with_threats(lines);
+            }
+            if let Some(lines) = parameter(config, "guard_lines")? {
+                behavior = behavior.with_guard_calls(lines);
+            }
+            Ok(Box::new(behavior))
+        });
+        registry
+    }
+
+    /// Register a behavior constructor, replacing any with the same name
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name configs use to refer to the behavior
+    /// * `constructor` - Builds the behavior from its configuration
+    pub fn register<F>(&mut self, name: &str, constructor: F)
+    where
+        F: Fn(&BehaviorConfig) -> Result<Box<dyn Behavior>> + Send + Sync + 'static,
+    {
+        self.constructors.insert(name.to_string(), Arc::new(constructor));
+    }
+
+    /// Check whether a behavior name is registered
+    pub fn contains(&self, name: &str) -> bool {
+        self.constructors.contains_key(name)
+    }
+
+    /// Get the registered behavior names in sorted order
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.constructors.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Build a registered behavior
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Registered behavior name
+    /// * `config` - Configuration passed to the constructor
+    ///
+    /// # Returns
+    ///
+    /// The behavior, or an error if the name is unknown or construction fails
+    pub fn create(&self, name: &str, config: &BehaviorConfig) -> Result<Box<dyn Behavior>> {
+        let constructor = self.constructors.get(name).ok_or_else(|| {
+            OxydeError::ConfigurationError(format!(
+                "Unknown behavior '{}'; registered behaviors: {}",
+                name,
+                self.names().join(", ")
+            ))
+        })?;
+        let mut behavior = constructor(config)?;
+        if config.timeout_ms.is_some() || config.timeout_response.is_some() {
+            let timeout = config.timeout_ms.map(Duration::from_millis);
+            let fallback = config.timeout_response.clone().map(BehaviorResult::Response);
+            behavior = Box::new(TimedBehavior::new(behavior, timeout, fallback));
+        }
+        if let Some(trigger) = &config.emotion_trigger {
+            behavior = Box::new(TriggeredBehavior::new(behavior, trigger.clone()));
+        }
+        Ok(match &config.condition {
+            Some(condition) => Box::new(ConditionalBehavior::new(behavior, condition.clone())),
+            None => behavior,
+        })
+    }
+
+    /// Build every behavior in an agent's `behavior` configuration
+    ///
+    /// Each entry uses its `type` field as the registered name, falling back
+    /// to the entry's key. Behaviors are built in key order.
+    ///
+    /// # Arguments
+    ///
+    /// * `configs` - Behavior configurations keyed by entry name
+    ///
+    /// # Returns
+    ///
+    /// The behaviors, or the first construction error
+    pub fn create_all(&self, configs: &HashMap<String, BehaviorConfig>) -> Result<Vec<Box<dyn Behavior>>> {
+        let mut keys: Vec<&String> = configs.keys().collect();
+        keys.sort();
+        keys.into_iter()
+            .map(|key| {
+                let config = &configs[key];
+                let name = config.behavior_type.as_deref().unwrap_or(key);
+                self.create(name, config)
+            })
+            .collect()
+    }
+}
+
+/// Register a behavior constructor in the global registry
+///
+/// Behavior packs call this during initialization so that any agent config
+/// can refer to their behaviors by name.
+///
+/// # Arguments
+///
+/// * `name` - Name configs use to refer to the behavior
+/// * `constructor` - Builds the behavior from its configuration
+pub fn register_behavior<F>(name: &str, constructor: F)
+where
+    F: Fn(&BehaviorConfig) -> Result<Box<dyn Behavior>> + Send + Sync + 'static,
+{
+    GLOBAL_REGISTRY
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .register(name, constructor);
+}
+
+/// Get a snapshot of the global registry
+///
+/// # Returns
+///
+/// A copy of the global registry, including the built-in behaviors
+pub fn global_registry() -> BehaviorRegistry {
+    GLOBAL_REGISTRY
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oxyde_game::intent::Intent;
+    use crate::oxyde_game::behavior::BehaviorResult;
+
+    fn behavior_config(value: serde_json::Value) -> BehaviorConfig {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_registry_builds_configured_behaviors() {
+        register_behavior("battle_cry", |config| {
+            let line = parameter::<String>(config, "line")?.unwrap_or_default();
+            Ok(Box::new(GreetingBehavior::new(&line)))
+        });
+        let registry = global_registry();
+        assert!(registry.contains("battle_cry") && registry.contains("greeting"));
+
+        let mut configs = HashMap::new();
+        configs.insert(
+            "combat_cry".to_string(),
+            behavior_config(serde_json::json!({ "type": "battle_cry", "trigger": "hostile", "line": "Come at me!" })),
+        );
+        configs.insert("dialogue".to_string(), behavior_config(serde_json::json!({ "trigger": "chat" })));
+
+        let behaviors = registry.create_all(&configs).unwrap();
+        assert_eq!(behaviors.len(), 2);
+
+        let intent = Intent::analyze("hello").await.unwrap();
+        let mut context = HashMap::new();
+        context.insert("player_distance".to_string(), serde_json::json!(1.0));
+        let result = behaviors[0].execute(&intent, &context).await.unwrap();
+        assert!(matches!(result, BehaviorResult::Response(ref text) if text == "Come at me!"));
+    }
+
+    #[test]
+    fn test_registry_reports_bad_configs() {
+        let registry = BehaviorRegistry::with_builtins();
+        let err = registry.create("juggling", &behavior_config(serde_json::json!({ "trigger": "chat" }))).unwrap_err();
+        assert!(err.to_string().contains("dialogue, follow, greeting, reengagement, stationary"));
+
+        let bad = behavior_config(serde_json::json!({ "trigger": "proximity", "greetings": "not a list" }));
+        assert!(registry.create("greeting", &bad).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_registry_gates_behaviors_on_conditions() {
+        let registry = BehaviorRegistry::with_builtins();
+        let config = behavior_config(serde_json::json!({
+            "trigger": "proximity",
+            "condition": "player_distance < 3 && emotions.fear > 0.5",
+            "greetings": ["Stay back!"],
+        }));
+        assert_eq!(config.condition.as_ref().unwrap().source(), "player_distance < 3 && emotions.fear > 0.5");
+        let behavior = registry.create("greeting", &config).unwrap();
+
+        let intent = Intent::analyze("hello").await.unwrap();
+        let mut context = HashMap::new();
+        context.insert("player_distance".to_string(), serde_json::json!(1.0));
+        let result = behavior.execute(&intent, &context).await.unwrap();
+        assert!(matches!(result, BehaviorResult::None));
+
+        let mut emotions = crate::oxyde_game::emotion::EmotionalState::new();
+        emotions.update_emotion("fear", 0.8);
+        context.insert("npc_emotions".to_string(), serde_json::to_value(&emotions).unwrap());
+        let result = behavior.execute(&intent, &context).await.unwrap();
+        assert!(matches!(result, BehaviorResult::Response(ref text) if text == "Stay back!"));
+
+        let bad = serde_json::from_value::<BehaviorConfig>(serde_json::json!({
+            "trigger": "proximity",
+            "condition": "player_distance < 3 && emotions.fer > 0.5",
+        }));
+        assert!(bad.unwrap_err().to_string().contains("`emotions.fer` is not an emotion"));
+    }
+
+    #[test]
+    fn test_registry_applies_emotion_triggers() {
+        let registry = BehaviorRegistry::with_builtins();
+        let config = behavior_config(serde_json::json!({
+            "trigger": "chat",
+            "emotion_trigger": "dominant is anger AND (anger > 0.4 for 2 turns)",
+        }));
+        let behavior = registry.create("dialogue", &config).unwrap();
+        let trigger = behavior.emotion_trigger().unwrap();
+
+        let mut angry = crate::oxyde_game::emotion::EmotionalState::new();
+        angry.update_emotion("anger", 0.6);
+        assert!(!trigger.matches(&angry));
+        assert!(trigger.matches_recent(&[angry.clone(), angry]));
+
+        let bad = serde_json::from_value::<BehaviorConfig>(serde_json::json!({
+            "trigger": "chat",
+            "emotion_trigger": "anger > 0.4 for many turns",
+        }));
+        assert!(bad.unwrap_err().to_string().contains("column 17"));
+    }
+}
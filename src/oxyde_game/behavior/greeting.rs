@@ -7,6 +7,7 @@ use crate::oxyde_game::intent::{Intent, IntentType};
 use crate::Result;
 
 use super::base::{Behavior, BehaviorResult, BaseBehavior};
+use super::variation::{ResponsePool, ResponseVariant};
 
 /// Greeting behavior that responds when a player gets close
 #[derive(Debug)]
@@ -18,7 +19,7 @@ pub struct GreetingBehavior {
     distance_threshold: f32,
 
     /// Greeting phrases
-    greetings: Vec<String>,
+    greetings: ResponsePool,
 }
 
 impl GreetingBehavior {
@@ -33,6 +34,23 @@ impl GreetingBehavior {
     ///
     /// A new GreetingBehavior
     pub fn new_with_options(distance_threshold: f32, greetings: Vec<String>) -> Self {
+        Self::with_variants(distance_threshold, greetings.into_iter().map(ResponseVariant::from).collect())
+    }
+
+    /// Create a greeting behavior with weighted phrases
+    ///
+    /// Phrases may use the template variables of [`fill_variables`](super::fill_variables),
+    /// and the most recent phrases are not repeated.
+    ///
+    /// # Arguments
+    ///
+    /// * `distance_threshold` - Distance at which to trigger the greeting
+    /// * `greetings` - Possible greeting phrases with relative weights
+    ///
+    /// # Returns
+    ///
+    /// A new GreetingBehavior
+    pub fn with_variants(distance_threshold: f32, greetings: Vec<ResponseVariant>) -> Self {
         Self {
             base: BaseBehavior::new(
                 "greeting",
@@ -42,10 +60,16 @@ impl GreetingBehavior {
                 60, // 1 minute cooldown
             ),
             distance_threshold,
-            greetings,
+            greetings: ResponsePool::new(greetings),
         }
     }
 
+    /// Set how many recent greetings are not repeated
+    pub fn with_avoid_repeats(mut self, avoid_repeats: usize) -> Self {
+        self.greetings = self.greetings.with_avoid_repeats(avoid_repeats);
+        self
+    }
+
     /// Create a new greeting behavior with default phrases
     ///
     /// # Returns
@@ -56,7 +80,8 @@ impl GreetingBehavior {
             3.0,
             vec![
                 "Hello there!".to_string(),
-                "Greetings, traveler!".to_string(),
+                "Greetings, {player}!".to_string(),
+                "{time_greeting}!".to_string(),
                 "Welcome!".to_string(),
                 "Good day to you!".to_string(),
                 "Well met!".to_string(),
@@ -103,11 +128,11 @@ impl Behavior for GreetingBehavior {
             // Mark as executed to start cooldown
             self.base.mark_executed().await;
 
-            // Select a random greeting
-            let greeting_idx = rand::random::<usize>() % self.greetings.len();
-            let greeting = &self.greetings[greeting_idx];
-
-            Ok(BehaviorResult::Response(greeting.clone()))
+            // Select a greeting that was not used recently
+            match self.greetings.pick(&mut crate::turn::context_rng(context), context) {
+                Some(greeting) => Ok(BehaviorResult::Response(greeting)),
+                None => Ok(BehaviorResult::None),
+            }
         } else {
             // Player not close enough
             Ok(BehaviorResult::None)
@@ -5,20 +5,40 @@
 //! - Greeting behavior for proximity detection
 //! - Dialogue behavior for topic-based conversations
 //! - Pathfinding behavior for navigation
+//! - Trading behavior for shopkeepers
+//! - Combat dialogue behaviors (taunt, surrender, threaten) emitting combat actions
+//! - Weighted canned lines that avoid recent repeats
 //! - Emotion-aware behaviors that trigger based on emotional state
+//! - Condition expressions gating when configured behaviors run
+//! - A trigger language for compound, dominant and sustained emotion triggers
 //! - Behavior selection strategies (emotion-modulated, fixed-priority)
+//! - Selection reports explaining why a behavior was chosen
+//! - A registry of named behavior constructors for behavior packs
+//! - `#[derive(Behavior)]` for custom behaviors, with the `macros` feature
 
 mod base;
+mod combat;
+mod conditional;
 mod dialogue;
 mod emotional;
+mod explain;
 mod greeting;
 mod pathfinding;
 mod strategy;
+mod trading;
+mod trigger;
+mod variation;
 
 pub mod factory;
 
 // Re-export all public types
-pub use base::{Behavior, BehaviorResult, BaseBehavior, EmotionInfluence, EmotionTrigger};
+pub use base::{
+    Behavior, BehaviorResult, BaseBehavior, Comparison, EmotionInfluence, EmotionMeasure, EmotionTrigger,
+};
+pub use combat::{
+    CombatAction, SurrenderBehavior, TauntBehavior, ThreatenBehavior, HEALTH_PCT_KEY, IN_COMBAT_KEY,
+};
+pub use conditional::{ConditionalBehavior, TimedBehavior, TriggeredBehavior};
 pub use dialogue::DialogueBehavior;
 pub use emotional::{
     AggressiveBehavior, CautiousBehavior, FleeBehavior, FriendlyBehavior, JoyfulBehavior,
@@ -26,9 +46,20 @@ pub use emotional::{
     NeutralGreetingBehavior, ConfusedBehavior, PoliteDeclineBehavior,
     ThoughtfulPauseBehavior, DefaultAcknowledgeBehavior,
 };
+pub use explain::{BehaviorCandidate, CandidateOutcome, CandidateStatus, SelectionReport};
 pub use greeting::GreetingBehavior;
 pub use pathfinding::PathfindingBehavior;
 pub use strategy::{SelectionStrategy, EmotionModulatedStrategy, FixedPriorityStrategy};
+pub use trading::{
+    Inventory, TradeAction, TradeActionKind, TradeItem, TradeRequest, TradingBehavior, INVENTORY_KEY,
+};
+pub use trigger::MAX_SUSTAINED_TURNS;
+pub(crate) use trigger::deserialize_trigger;
+pub use variation::{fill_variables, ResponsePool, ResponseVariant, DEFAULT_AVOID_REPEATS};
+
+/// Derive `Behavior` from a `#[behavior(...)]` attribute; see `oxyde-macros`
+#[cfg(feature = "macros")]
+pub use oxyde_macros::Behavior;
 
 #[cfg(test)]
 mod tests {
@@ -44,6 +75,8 @@ mod tests {
             confidence: 1.0,
             raw_input: "".to_string(),
             keywords: vec![],
+            label: None,
+            secondary: vec![],
         };
 
         let mut context = HashMap::new();
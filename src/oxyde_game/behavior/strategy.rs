@@ -118,6 +118,8 @@ pub trait SelectionStrategy: Send + Sync {
 ///         confidence: 0.9,
 ///         raw_input: "I'm going to hurt you!".to_string(),
 ///         keywords: vec!["hurt".to_string()],
+///         label: None,
+///         secondary: vec![],
 ///     };
 ///
 ///     let strategy = EmotionModulatedStrategy;
@@ -331,6 +333,8 @@ mod tests {
             confidence: 0.9,
             raw_input: "I'm going to attack you!".to_string(),
             keywords: vec!["attack".to_string()],
+            label: None,
+            secondary: vec![],
         };
 
         let strategy = EmotionModulatedStrategy::new();
@@ -361,6 +365,8 @@ mod tests {
             confidence: 0.9,
             raw_input: "Threatening message".to_string(),
             keywords: vec!["threat".to_string()],
+            label: None,
+            secondary: vec![],
         };
 
         let strategy = FixedPriorityStrategy::new();
@@ -389,6 +395,8 @@ mod tests {
             confidence: 0.9,
             raw_input: "Hello".to_string(),
             keywords: vec!["hello".to_string()],
+            label: None,
+            secondary: vec![],
         };
 
         let strategy = EmotionModulatedStrategy::new();
@@ -0,0 +1,694 @@
+//! Trading behavior for shopkeeper NPCs
+//!
+//! The game describes the shop's stock in the `inventory` context key:
+//!
+//! ```json
+//! {
+//!   "currency": "gold",
+//!   "funds": 300,
+//!   "items": [
+//!     { "name": "iron sword", "price": 50, "stock": 2 },
+//!     { "name": "healing potion", "price": 10, "stock": 12, "aliases": ["potion"] }
+//!   ]
+//! }
+//! ```
+//!
+//! Players browse, ask prices, buy, sell and haggle in plain language. Agreed
+//! trades are emitted as actions the game applies to both inventories, such
+//! as `trade|buy|iron sword|1|45`. Prices and how far the shopkeeper will
+//! haggle depend on its relationship with the player (the
+//! `player_relationship` context key) and its current mood, through a
+//! [`ModifierProvider`]. Added to an agent, the behavior prices with the
+//! agent's provider, the same one `Agent::modifier` answers from; on its own
+//! it uses [`SentimentModifiers`] without a markup.
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::agent::AgentContext;
+use crate::oxyde_game::intent::Intent;
+use crate::oxyde_game::modifier::{
+    ModifierProvider, Sentiment, SentimentModifiers, SharedModifiers, HAGGLE_MODIFIER, PRICE_MODIFIER,
+};
+use crate::Result;
+
+use super::base::{Behavior, BehaviorResult, BaseBehavior};
+
+/// Context key for the shop inventory
+pub const INVENTORY_KEY: &str = "inventory";
+
+/// Words that name the currency when they follow a number
+const CURRENCY_WORDS: &[&str] = &["gold", "coin", "coins", "g", "silver", "crowns"];
+
+/// An item the shopkeeper trades
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TradeItem {
+    /// Item name as players refer to it
+    pub name: String,
+
+    /// Price the shopkeeper sells one unit for
+    pub price: u32,
+
+    /// Units in stock
+    #[serde(default)]
+    pub stock: u32,
+
+    /// Other names players may use for the item
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+/// The shop inventory provided in context
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Inventory {
+    /// Name of the currency used in responses
+    #[serde(default = "default_currency")]
+    pub currency: String,
+
+    /// Money the shopkeeper can spend buying from the player, unlimited if absent
+    #[serde(default)]
+    pub funds: Option<u32>,
+
+    /// Items for sale, and the only items the shopkeeper buys
+    #[serde(default)]
+    pub items: Vec<TradeItem>,
+}
+
+fn default_currency() -> String {
+    "gold".to_string()
+}
+
+impl Inventory {
+    /// Read the inventory from context
+    ///
+    /// # Returns
+    ///
+    /// The inventory, or `None` if the key is absent or malformed
+    pub fn from_context(context: &AgentContext) -> Option<Self> {
+        let value = context.get(INVENTORY_KEY)?;
+        match serde_json::from_value(value.clone()) {
+            Ok(inventory) => Some(inventory),
+            Err(e) => {
+                tracing::warn!(error = %e, "Ignoring malformed inventory in context");
+                None
+            }
+        }
+    }
+
+    /// Find the item mentioned in player input
+    ///
+    /// Names and aliases match as whole words, ignoring plurals; the longest
+    /// match wins so "iron sword" is preferred over "sword".
+    pub fn find_item(&self, input: &str) -> Option<&TradeItem> {
+        let words = normalized_words(input);
+        self.items
+            .iter()
+            .filter_map(|item| {
+                std::iter::once(&item.name)
+                    .chain(&item.aliases)
+                    .map(|name| normalized_words(name))
+                    .filter(|name| !name.is_empty() && words.windows(name.len()).any(|w| w == name.as_slice()))
+                    .map(|name| name.len())
+                    .max()
+                    .map(|len| (item, len))
+            })
+            .max_by_key(|(_, len)| *len)
+            .map(|(item, _)| item)
+    }
+
+    /// Scale every price by a multiplier, rounding to whole units
+    fn reprice(&mut self, multiplier: f32) {
+        for item in &mut self.items {
+            item.price = (item.price as f32 * multiplier).round() as u32;
+        }
+    }
+}
+
+/// What the player wants to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TradeRequest {
+    /// Asking what is for sale
+    Browse,
+    /// Asking an item's price
+    Quote,
+    /// Buying from the shopkeeper
+    Buy,
+    /// Selling to the shopkeeper
+    Sell,
+    /// Negotiating a price
+    Haggle,
+}
+
+impl TradeRequest {
+    /// Recognize a trade request in an intent
+    ///
+    /// Intents labeled `browse`, `quote`, `buy`, `sell` or `haggle` by the
+    /// configured intent matcher are used as is; otherwise the input is
+    /// checked for trading phrases.
+    pub fn detect(intent: &Intent) -> Option<Self> {
+        let labeled = [
+            ("browse", Self::Browse),
+            ("quote", Self::Quote),
+            ("buy", Self::Buy),
+            ("sell", Self::Sell),
+            ("haggle", Self::Haggle),
+        ];
+        if let Some((_, request)) = labeled.iter().find(|(label, _)| intent.label.as_deref() == Some(*label)) {
+            return Some(*request);
+        }
+
+        let input = intent.raw_input.to_lowercase();
+        let words: Vec<&str> = input.split(|c: char| !c.is_alphanumeric() && c != '\'').collect();
+        let has_word = |options: &[&str]| words.iter().any(|word| options.contains(word));
+        let has_phrase = |options: &[&str]| options.iter().any(|phrase| input.contains(phrase));
+
+        if has_phrase(&["what do you sell", "what do you have", "for sale", "show me your", "your wares"])
+            || has_word(&["wares", "browse", "goods"])
+        {
+            Some(Self::Browse)
+        } else if has_word(&["sell", "selling"]) {
+            Some(Self::Sell)
+        } else if has_word(&["discount", "cheaper", "lower", "haggle", "deal", "offer"])
+            || has_phrase(&["too expensive", "too much", "best price"])
+        {
+            Some(Self::Haggle)
+        } else if has_word(&["buy", "purchase"]) || has_phrase(&["i'll take", "i will take"]) {
+            Some(Self::Buy)
+        } else if has_word(&["price", "cost", "costs"]) || has_phrase(&["how much"]) {
+            Some(Self::Quote)
+        } else {
+            None
+        }
+    }
+}
+
+/// Kind of trade action
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TradeActionKind {
+    /// The player buys from the shopkeeper
+    Buy,
+    /// The player sells to the shopkeeper
+    Sell,
+    /// The shopkeeper proposes a different price; nothing changes hands
+    Counter,
+}
+
+impl TradeActionKind {
+    /// Get the name used in action strings
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Buy => "buy",
+            Self::Sell => "sell",
+            Self::Counter => "counter",
+        }
+    }
+}
+
+/// A structured trade emitted as a behavior action
+///
+/// Actions are written as `trade|<kind>|<item>|<quantity>|<unit price>` and
+/// can be parsed back with [`str::parse`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TradeAction {
+    /// Kind of trade
+    pub kind: TradeActionKind,
+
+    /// Item name from the inventory
+    pub item: String,
+
+    /// Number of units
+    pub quantity: u32,
+
+    /// Agreed or proposed price per unit
+    pub unit_price: u32,
+}
+
+impl TradeAction {
+    /// Total price of the trade
+    pub fn total(&self) -> u32 {
+        self.unit_price.saturating_mul(self.quantity)
+    }
+}
+
+impl fmt::Display for TradeAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "trade|{}|{}|{}|{}",
+            self.kind.as_str(),
+            self.item,
+            self.quantity,
+            self.unit_price
+        )
+    }
+}
+
+impl FromStr for TradeAction {
+    type Err = crate::OxydeError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || crate::OxydeError::BehaviorError(format!("Invalid trade action '{}'", s));
+        let parts: Vec<&str> = s.split('|').collect();
+        let [prefix, kind, item, quantity, unit_price] = parts.as_slice() else {
+            return Err(invalid());
+        };
+        if *prefix != "trade" {
+            return Err(invalid());
+        }
+        let kind = match *kind {
+            "buy" => TradeActionKind::Buy,
+            "sell" => TradeActionKind::Sell,
+            "counter" => TradeActionKind::Counter,
+            _ => return Err(invalid()),
+        };
+        Ok(Self {
+            kind,
+            item: item.to_string(),
+            quantity: quantity.parse().map_err(|_| invalid())?,
+            unit_price: unit_price.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// Quantity and offered price mentioned in player input
+#[derive(Debug, Default, PartialEq)]
+struct TradeTerms {
+    quantity: Option<u32>,
+    offer: Option<u32>,
+}
+
+impl TradeTerms {
+    /// Read numbers from the input; a number is an offer when it is followed
+    /// by a currency word or preceded by "for", "at", "pay" or "you"
+    fn parse(input: &str, currency: &str) -> Self {
+        let lower = input.to_lowercase();
+        let words: Vec<&str> = lower.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).collect();
+        let mut terms = Self::default();
+        for (i, word) in words.iter().enumerate() {
+            let Ok(number) = word.parse::<u32>() else {
+                continue;
+            };
+            let next = words.get(i + 1).copied().unwrap_or_default();
+            let previous = if i > 0 { words[i - 1] } else { "" };
+            let is_price = next == currency || CURRENCY_WORDS.contains(&next)
+                || matches!(previous, "for" | "at" | "pay" | "you");
+            if is_price {
+                terms.offer.get_or_insert(number);
+            } else {
+                terms.quantity.get_or_insert(number);
+            }
+        }
+        terms
+    }
+}
+
+/// Shopkeeper behavior that buys, sells and haggles over an inventory
+#[derive(Debug)]
+pub struct TradingBehavior {
+    /// Base behavior
+    #[allow(dead_code)]
+    base: BaseBehavior,
+
+    /// Fraction of the sale price offered when buying from the player
+    buy_back_ratio: f32,
+
+    /// Source of the price and haggling modifiers
+    modifiers: Arc<dyn ModifierProvider>,
+
+    /// Whether the modifiers were chosen with [`TradingBehavior::with_modifiers`]
+    /// and are kept when the behavior is added to an agent
+    custom_modifiers: bool,
+}
+
+impl TradingBehavior {
+    /// Create a new trading behavior
+    ///
+    /// # Arguments
+    ///
+    /// * `max_discount` - Largest haggling discount (0.0 - 1.0), until the
+    ///   behavior is added to an agent and uses the agent's modifiers
+    /// * `buy_back_ratio` - Fraction of the sale price paid for player items
+    ///
+    /// # Returns
+    ///
+    /// A new TradingBehavior
+    pub fn new(max_discount: f32, buy_back_ratio: f32) -> Self {
+        Self {
+            base: BaseBehavior::new(
+                "trading",
+                "Buys, sells and haggles over the shop inventory",
+                60,
+                vec!["buy".to_string(), "sell".to_string(), "haggle".to_string()],
+                0, // No cooldown for trading
+            ),
+            buy_back_ratio: buy_back_ratio.max(0.0),
+            modifiers: Arc::new(SentimentModifiers::new(max_discount, 0.0)),
+            custom_modifiers: false,
+        }
+    }
+
+    /// Price and haggle with modifiers from a different provider
+    ///
+    /// The provider's [`PRICE_MODIFIER`] scales the prices the player pays and
+    /// its [`HAGGLE_MODIFIER`] sets how far the shopkeeper comes down. It is
+    /// kept instead of the agent's when the behavior is added to an agent.
+    pub fn with_modifiers(mut self, modifiers: Arc<dyn ModifierProvider>) -> Self {
+        self.modifiers = modifiers;
+        self.custom_modifiers = true;
+        self
+    }
+
+    /// Create a trading behavior with a 20% maximum discount that buys at half price
+    pub fn new_default() -> Self {
+        Self::new(0.2, 0.5)
+    }
+
+    /// How far the shopkeeper will move on price (0.0 - 1.0)
+    ///
+    /// With the built-in modifiers, goodwill is mostly the relationship with
+    /// the player, then trust and overall mood, and an angry shopkeeper does
+    /// not haggle at all.
+    pub fn flexibility(&self, context: &AgentContext) -> f32 {
+        let sentiment = Sentiment::from_context(context);
+        self.modifiers
+            .modifier(HAGGLE_MODIFIER, &sentiment)
+            .unwrap_or(0.0)
+            .clamp(0.0, 1.0)
+    }
+
+    /// Multiplier on the prices the player pays
+    pub fn price_multiplier(&self, context: &AgentContext) -> f32 {
+        let sentiment = Sentiment::from_context(context);
+        self.modifiers
+            .modifier(PRICE_MODIFIER, &sentiment)
+            .unwrap_or(1.0)
+            .max(0.0)
+    }
+
+    /// Lowest price per unit the shopkeeper accepts for an item
+    fn lowest_price(&self, item: &TradeItem, flexibility: f32) -> u32 {
+        (item.price as f32 * (1.0 - flexibility)).ceil() as u32
+    }
+
+    /// Highest price per unit the shopkeeper pays for an item
+    fn highest_bid(&self, item: &TradeItem, flexibility: f32) -> u32 {
+        (item.price as f32 * self.buy_back_ratio * (1.0 + flexibility)).floor() as u32
+    }
+
+    fn browse(&self, inventory: &Inventory) -> BehaviorResult {
+        let listed: Vec<String> = inventory
+            .items
+            .iter()
+            .filter(|item| item.stock > 0)
+            .map(|item| format!("{} for {} {}", item.name, item.price, inventory.currency))
+            .collect();
+        if listed.is_empty() {
+            BehaviorResult::Response("I'm afraid my shelves are empty right now.".to_string())
+        } else {
+            BehaviorResult::Response(format!("Take a look: {}.", listed.join(", ")))
+        }
+    }
+
+    fn sale(&self, item: &TradeItem, quantity: u32, offer: Option<u32>, flexibility: f32, currency: &str) -> BehaviorResult {
+        if item.stock == 0 {
+            return BehaviorResult::Response(format!("Sorry, I'm sold out of {}.", item.name));
+        }
+        if quantity > item.stock {
+            return BehaviorResult::Response(format!("I only have {} {} left.", item.stock, item.name));
+        }
+
+        let lowest = self.lowest_price(item, flexibility);
+        let accepted = match offer {
+            None => Some(item.price),
+            Some(offer) if offer >= lowest => Some(offer.min(item.price)),
+            Some(_) => None,
+        };
+        match accepted {
+            Some(unit_price) => {
+                let action = TradeAction {
+                    kind: TradeActionKind::Buy,
+                    item: item.name.clone(),
+                    quantity,
+                    unit_price,
+                };
+                let response = format!(
+                    "{} for {} {}. Pleasure doing business.",
+                    describe(&item.name, quantity),
+                    action.total(),
+                    currency
+                );
+                BehaviorResult::ResponseWithAction { response, action: action.to_string() }
+            }
+            None => self.counter(item, quantity, lowest, currency),
+        }
+    }
+
+    fn purchase(
+        &self,
+        item: &TradeItem,
+        quantity: u32,
+        asking: Option<u32>,
+        flexibility: f32,
+        inventory: &Inventory,
+    ) -> BehaviorResult {
+        let highest = self.highest_bid(item, flexibility);
+        if highest == 0 {
+            return BehaviorResult::Response(format!("I can't offer you anything for {}.", item.name));
+        }
+
+        let unit_price = match asking {
+            Some(asking) if asking > highest => {
+                if flexibility == 0.0 {
+                    return BehaviorResult::Response(format!(
+                        "{} {} each is my price for {}. Take it or leave it.",
+                        highest, inventory.currency, item.name
+                    ));
+                }
+                return self.counter(item, quantity, highest, &inventory.currency);
+            }
+            Some(asking) => asking,
+            None => highest,
+        };
+        let action = TradeAction {
+            kind: TradeActionKind::Sell,
+            item: item.name.clone(),
+            quantity,
+            unit_price,
+        };
+        if inventory.funds.is_some_and(|funds| action.total() > funds) {
+            return BehaviorResult::Response("I don't have that much coin on hand.".to_string());
+        }
+        let response = format!(
+            "I'll give you {} {} for {}.",
+            action.total(),
+            inventory.currency,
+            describe(&item.name, quantity)
+        );
+        BehaviorResult::ResponseWithAction { response, action: action.to_string() }
+    }
+
+    fn counter(&self, item: &TradeItem, quantity: u32, unit_price: u32, currency: &str) -> BehaviorResult {
+        let action = TradeAction {
+            kind: TradeActionKind::Counter,
+            item: item.name.clone(),
+            quantity,
+            unit_price,
+        };
+        let response = format!("I can't do that. {} {} each is as far as I'll go.", unit_price, currency);
+        BehaviorResult::ResponseWithAction { response, action: action.to_string() }
+    }
+}
+
+#[async_trait]
+impl Behavior for TradingBehavior {
+    async fn matches_intent(&self, intent: &Intent) -> bool {
+        TradeRequest::detect(intent).is_some()
+    }
+
+    async fn execute(&self, intent: &Intent, context: &AgentContext) -> Result<BehaviorResult> {
+        let (Some(request), Some(mut inventory)) = (TradeRequest::detect(intent), Inventory::from_context(context)) else {
+            return Ok(BehaviorResult::None);
+        };
+        // Buy-back offers are a ratio of the list price, so only sales are marked up
+        if request != TradeRequest::Sell {
+            inventory.reprice(self.price_multiplier(context));
+        }
+        if request == TradeRequest::Browse {
+            return Ok(self.browse(&inventory));
+        }
+
+        let Some(item) = inventory.find_item(&intent.raw_input) else {
+            let names: Vec<&str> = inventory.items.iter().map(|item| item.name.as_str()).collect();
+            return Ok(BehaviorResult::Response(if request == TradeRequest::Sell {
+                "I don't deal in that.".to_string()
+            } else if names.is_empty() {
+                "I have nothing to trade right now.".to_string()
+            } else {
+                format!("Which item do you mean? I have {}.", names.join(", "))
+            }));
+        };
+
+        let terms = TradeTerms::parse(&intent.raw_input, &inventory.currency);
+        let quantity = terms.quantity.unwrap_or(1).max(1);
+        let flexibility = self.flexibility(context);
+        let currency = &inventory.currency;
+
+        let result = match request {
+            TradeRequest::Quote => BehaviorResult::Response(format!(
+                "{} costs {} {}.",
+                capitalize(&item.name),
+                item.price,
+                currency
+            )),
+            TradeRequest::Buy => self.sale(item, quantity, terms.offer, flexibility, currency),
+            TradeRequest::Haggle => match terms.offer {
+                Some(_) => self.sale(item, quantity, terms.offer, flexibility, currency),
+                None if flexibility == 0.0 => BehaviorResult::Response(format!(
+                    "The price is {} {}. I'm not haggling today.",
+                    item.price, currency
+                )),
+                None => self.counter(item, quantity, self.lowest_price(item, flexibility), currency),
+            },
+            TradeRequest::Sell => self.purchase(item, quantity, terms.offer, flexibility, &inventory),
+            TradeRequest::Browse => unreachable!("browse requests are answered above"),
+        };
+        Ok(result)
+    }
+
+    fn use_modifiers(&mut self, modifiers: &SharedModifiers) {
+        if !self.custom_modifiers {
+            self.modifiers = Arc::new(modifiers.clone());
+        }
+    }
+}
+
+/// Lowercase words with trailing plural "s" removed
+fn normalized_words(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| match word.strip_suffix('s') {
+            Some(stem) if stem.len() > 2 && !stem.ends_with('s') => stem.to_string(),
+            _ => word.to_string(),
+        })
+        .collect()
+}
+
+/// Describe a quantity of an item, such as "the iron sword" or "3 x iron sword"
+fn describe(name: &str, quantity: u32) -> String {
+    if quantity == 1 {
+        capitalize(&format!("the {}", name))
+    } else {
+        format!("{} x {}", quantity, name)
+    }
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::ContextBuilder;
+    use crate::oxyde_game::emotion::{EmotionalState, EMOTIONAL_STATE_KEY};
+
+    fn shop_context(relationship: f32) -> AgentContext {
+        ContextBuilder::new()
+            .with(
+                INVENTORY_KEY,
+                serde_json::json!({
+                    "funds": 40,
+                    "items": [
+                        { "name": "iron sword", "price": 50, "stock": 2, "aliases": ["blade"] },
+                        { "name": "healing potion", "price": 10, "stock": 12 },
+                    ]
+                }),
+            )
+            .player_relationship(relationship)
+            .build()
+    }
+
+    async fn trade(input: &str, context: &AgentContext) -> BehaviorResult {
+        let behavior = TradingBehavior::new_default();
+        let intent = Intent::analyze(input).await.unwrap();
+        assert!(behavior.matches_intent(&intent).await, "no trade request in {:?}", input);
+        behavior.execute(&intent, context).await.unwrap()
+    }
+
+    fn action(result: &BehaviorResult) -> TradeAction {
+        match result {
+            BehaviorResult::ResponseWithAction { action, .. } => action.parse().unwrap(),
+            other => panic!("expected a trade action, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_buys_sells_and_quotes_from_inventory() {
+        let context = shop_context(0.0);
+
+        let bought = trade("I'd like to buy 3 healing potions", &context).await;
+        assert_eq!(action(&bought).to_string(), "trade|buy|healing potion|3|10");
+
+        let sold_out = trade("I want to buy 5 iron swords", &context).await;
+        assert!(matches!(sold_out, BehaviorResult::Response(ref text) if text.contains("only have 2")));
+
+        let quote = trade("How much is that blade?", &context).await;
+        assert!(matches!(quote, BehaviorResult::Response(ref text) if text == "Iron sword costs 50 gold."));
+
+        let sold = trade("I want to sell my iron sword", &context).await;
+        assert_eq!(
+            action(&sold),
+            TradeAction { kind: TradeActionKind::Sell, item: "iron sword".to_string(), quantity: 1, unit_price: 27 }
+        );
+
+        let browse = trade("What do you sell?", &context).await;
+        assert!(matches!(browse, BehaviorResult::Response(ref text) if text.contains("healing potion for 10 gold")));
+    }
+
+    #[tokio::test]
+    async fn test_haggling_depends_on_relationship_and_mood() {
+        let friend = shop_context(1.0);
+        let stranger = shop_context(-1.0);
+
+        // A friend gets up to 15% off; a disliked stranger only 5%
+        let accepted = trade("I'll buy the iron sword for 43 gold", &friend).await;
+        assert_eq!(action(&accepted).to_string(), "trade|buy|iron sword|1|43");
+        let countered = trade("I'll buy the iron sword for 43 gold", &stranger).await;
+        assert_eq!(action(&countered).to_string(), "trade|counter|iron sword|1|48");
+
+        let mut angry = friend.clone();
+        let mut emotions = EmotionalState::new();
+        emotions.anger = 0.8;
+        angry.insert(EMOTIONAL_STATE_KEY.to_string(), serde_json::to_value(&emotions).unwrap());
+        let refused = trade("Can you give me a discount on the iron sword?", &angry).await;
+        assert!(matches!(refused, BehaviorResult::Response(ref text) if text.contains("not haggling")));
+
+        assert!("trade|steal|sword|1|0".parse::<TradeAction>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_custom_modifiers_mark_up_prices() {
+        let behavior = TradingBehavior::new_default().with_modifiers(Arc::new(SentimentModifiers::new(0.2, 0.4)));
+        let stranger = shop_context(-1.0);
+        assert!((behavior.price_multiplier(&stranger) - 1.2).abs() < 1e-6);
+
+        let intent = Intent::analyze("How much is that blade?").await.unwrap();
+        let quote = behavior.execute(&intent, &stranger).await.unwrap();
+        assert!(matches!(quote, BehaviorResult::Response(ref text) if text == "Iron sword costs 60 gold."));
+
+        // Buy-back offers stay a ratio of the list price
+        let intent = Intent::analyze("I want to sell my iron sword").await.unwrap();
+        let sold = behavior.execute(&intent, &stranger).await.unwrap();
+        assert_eq!(action(&sold).unit_price, 26);
+    }
+}
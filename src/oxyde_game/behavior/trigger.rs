@@ -0,0 +1,390 @@
+//! Emotion trigger language
+//!
+//! Behavior configs gate behaviors on the agent's emotions with an
+//! `emotion_trigger`:
+//!
+//! ```yaml
+//! behavior:
+//!   flee:
+//!     trigger: hostile
+//!     emotion_trigger: "fear > 0.5 AND trust < 0"
+//!   lash_out:
+//!     trigger: chat
+//!     emotion_trigger: "dominant is anger AND (anger > 0.4 for 3 turns)"
+//! ```
+//!
+//! Comparisons read one of the eight primary emotions, `valence` or
+//! `arousal`, with `< <= > >= == !=`. `dominant is <emotion>` matches the
+//! strongest emotion, and `positive` and `negative` the overall mood.
+//! Conditions combine with `AND`, `OR` and `NOT` (or `&&`, `||`, `!`) and
+//! parentheses. A condition followed by `for N turns` must have held on each
+//! of the last N inputs.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer};
+
+use crate::oxyde_game::emotion::EMOTION_NAMES;
+use crate::{OxydeError, Result};
+
+use super::base::{Comparison, EmotionMeasure, EmotionTrigger};
+
+/// Longest span a `for N turns` condition may cover
+pub const MAX_SUSTAINED_TURNS: u32 = 32;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f32),
+    Word(String),
+    Compare(Comparison),
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Number(n) => write!(f, "number `{}`", n),
+            Self::Word(word) => write!(f, "`{}`", word),
+            Self::Compare(op) => f.write_str(match op {
+                Comparison::Less => "`<`",
+                Comparison::LessOrEqual => "`<=`",
+                Comparison::Greater => "`>`",
+                Comparison::GreaterOrEqual => "`>=`",
+                Comparison::Equal => "`==`",
+                Comparison::NotEqual => "`!=`",
+            }),
+            Self::Open => f.write_str("`(`"),
+            Self::Close => f.write_str("`)`"),
+            Self::And => f.write_str("`and`"),
+            Self::Or => f.write_str("`or`"),
+            Self::Not => f.write_str("`not`"),
+        }
+    }
+}
+
+/// A syntax error at a column of the source
+struct SyntaxError {
+    column: usize,
+    message: String,
+}
+
+impl SyntaxError {
+    fn new(column: usize, message: impl Into<String>) -> Self {
+        Self {
+            column,
+            message: message.into(),
+        }
+    }
+
+    /// Describe the error with the source and a caret under the column
+    fn into_error(self, source: &str) -> OxydeError {
+        OxydeError::ConfigurationError(format!(
+            "Invalid emotion trigger at column {}: {}\n  {}\n  {}^",
+            self.column + 1,
+            self.message,
+            source,
+            " ".repeat(self.column)
+        ))
+    }
+}
+
+fn lex(source: &str) -> std::result::Result<Vec<(Token, usize)>, SyntaxError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().enumerate().peekable();
+    while let Some(&(column, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '(' || c == ')' {
+            chars.next();
+            tokens.push((if c == '(' { Token::Open } else { Token::Close }, column));
+        } else if c.is_ascii_digit() || c == '-' || c == '.' {
+            let mut number = String::new();
+            while let Some(&(_, d)) = chars.peek() {
+                if d.is_ascii_digit() || d == '.' || (d == '-' && number.is_empty()) {
+                    number.push(d);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            let value = number
+                .parse()
+                .map_err(|_| SyntaxError::new(column, format!("invalid number '{}'", number)))?;
+            tokens.push((Token::Number(value), column));
+        } else if c.is_alphabetic() || c == '_' {
+            let mut word = String::new();
+            while let Some(&(_, d)) = chars.peek() {
+                if d.is_alphanumeric() || d == '_' {
+                    word.push(d.to_ascii_lowercase());
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            let token = match word.as_str() {
+                "and" => Token::And,
+                "or" => Token::Or,
+                "not" => Token::Not,
+                _ => Token::Word(word),
+            };
+            tokens.push((token, column));
+        } else {
+            chars.next();
+            let next = chars.peek().map(|&(_, d)| d);
+            let (token, pair) = match (c, next) {
+                ('&', Some('&')) => (Token::And, true),
+                ('|', Some('|')) => (Token::Or, true),
+                ('!', Some('=')) => (Token::Compare(Comparison::NotEqual), true),
+                ('!', _) => (Token::Not, false),
+                ('=', Some('=')) => (Token::Compare(Comparison::Equal), true),
+                ('<', Some('=')) => (Token::Compare(Comparison::LessOrEqual), true),
+                ('<', _) => (Token::Compare(Comparison::Less), false),
+                ('>', Some('=')) => (Token::Compare(Comparison::GreaterOrEqual), true),
+                ('>', _) => (Token::Compare(Comparison::Greater), false),
+                _ => return Err(SyntaxError::new(column, format!("unexpected '{}'", c))),
+            };
+            if pair {
+                chars.next();
+            }
+            tokens.push((token, column));
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    /// Column reported for errors at the end of the source
+    end: usize,
+}
+
+type ParseResult = std::result::Result<EmotionTrigger, SyntaxError>;
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.peek().cloned();
+        self.position += 1;
+        token
+    }
+
+    /// An error at the token at `index`
+    fn error_at(&self, index: usize, message: impl Into<String>) -> SyntaxError {
+        let column = self.tokens.get(index).map_or(self.end, |(_, column)| *column);
+        SyntaxError::new(column, message)
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        let matched = self.peek() == Some(token);
+        if matched {
+            self.position += 1;
+        }
+        matched
+    }
+
+    fn eat_word(&mut self, word: &str) -> bool {
+        self.eat(&Token::Word(word.to_string()))
+    }
+
+    fn any(&mut self) -> ParseResult {
+        let mut triggers = vec![self.all()?];
+        while self.eat(&Token::Or) {
+            triggers.push(self.all()?);
+        }
+        Ok(combine(triggers, EmotionTrigger::Any))
+    }
+
+    fn all(&mut self) -> ParseResult {
+        let mut triggers = vec![self.unary()?];
+        while self.eat(&Token::And) {
+            triggers.push(self.unary()?);
+        }
+        Ok(combine(triggers, EmotionTrigger::All))
+    }
+
+    fn unary(&mut self) -> ParseResult {
+        if self.eat(&Token::Not) {
+            return Ok(EmotionTrigger::Not(Box::new(self.unary()?)));
+        }
+        let trigger = self.primary()?;
+        if !self.eat_word("for") {
+            return Ok(trigger);
+        }
+        let at = self.position;
+        let turns = match self.next() {
+            Some(Token::Number(turns)) if turns >= 1.0 && turns.fract() == 0.0 => turns as u32,
+            _ => return Err(self.error_at(at, "expected a whole number of turns after 'for'")),
+        };
+        if turns > MAX_SUSTAINED_TURNS {
+            return Err(self.error_at(
+                at,
+                format!("conditions can be sustained for at most {} turns", MAX_SUSTAINED_TURNS),
+            ));
+        }
+        if !self.eat_word("turns") && !self.eat_word("turn") {
+            return Err(self.error_at(self.position, format!("expected 'turns' after 'for {}'", turns)));
+        }
+        Ok(EmotionTrigger::Sustained {
+            trigger: Box::new(trigger),
+            turns,
+        })
+    }
+
+    fn primary(&mut self) -> ParseResult {
+        let at = self.position;
+        match self.next() {
+            Some(Token::Open) => {
+                let trigger = self.any()?;
+                if !self.eat(&Token::Close) {
+                    return Err(self.error_at(self.position, "missing ')'"));
+                }
+                Ok(trigger)
+            }
+            Some(Token::Word(word)) => match word.as_str() {
+                "positive" => Ok(EmotionTrigger::Positive),
+                "negative" => Ok(EmotionTrigger::Negative),
+                "dominant" => {
+                    if !self.eat_word("is") && !self.eat(&Token::Compare(Comparison::Equal)) {
+                        return Err(self.error_at(self.position, "expected 'is' after 'dominant'"));
+                    }
+                    let at = self.position;
+                    match self.next() {
+                        Some(Token::Word(emotion)) if EMOTION_NAMES.contains(&emotion.as_str()) => {
+                            Ok(EmotionTrigger::Dominant { emotion })
+                        }
+                        _ => Err(self.error_at(
+                            at,
+                            format!("expected one of {} after 'dominant is'", EMOTION_NAMES.join(", ")),
+                        )),
+                    }
+                }
+                _ => {
+                    let measure = match word.as_str() {
+                        "valence" => EmotionMeasure::Valence,
+                        "arousal" => EmotionMeasure::Arousal,
+                        name if EMOTION_NAMES.contains(&name) => EmotionMeasure::Emotion(word.clone()),
+                        _ => return Err(self.error_at(at, format!("unknown emotion '{}'", word))),
+                    };
+                    let op = match self.next() {
+                        Some(Token::Compare(op)) => op,
+                        _ => return Err(self.error_at(at + 1, format!("expected a comparison after '{}'", word))),
+                    };
+                    match self.next() {
+                        Some(Token::Number(value)) => Ok(EmotionTrigger::Compare { measure, op, value }),
+                        _ => Err(self.error_at(at + 2, format!("expected a number to compare '{}' with", word))),
+                    }
+                }
+            },
+            Some(token) => Err(self.error_at(at, format!("unexpected {}", token))),
+            None => Err(self.error_at(at, "unexpected end of trigger")),
+        }
+    }
+}
+
+fn combine(mut triggers: Vec<EmotionTrigger>, group: fn(Vec<EmotionTrigger>) -> EmotionTrigger) -> EmotionTrigger {
+    if triggers.len() == 1 {
+        triggers.remove(0)
+    } else {
+        group(triggers)
+    }
+}
+
+impl EmotionTrigger {
+    /// Parse a trigger written in the emotion trigger language
+    ///
+    /// # Returns
+    ///
+    /// The trigger, or a configuration error naming the column of the problem
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut parser = Parser {
+            tokens: lex(source).map_err(|error| error.into_error(source))?,
+            position: 0,
+            end: source.chars().count(),
+        };
+        let trigger = parser.any().map_err(|error| error.into_error(source))?;
+        if let Some(token) = parser.peek() {
+            let error = parser.error_at(parser.position, format!("unexpected {}", token));
+            return Err(error.into_error(source));
+        }
+        Ok(trigger)
+    }
+}
+
+impl FromStr for EmotionTrigger {
+    type Err = OxydeError;
+
+    fn from_str(source: &str) -> Result<Self> {
+        Self::parse(source)
+    }
+}
+
+/// Read an optional trigger written in the trigger language or as a tagged value
+pub(crate) fn deserialize_trigger<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<EmotionTrigger>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Spec {
+        Source(String),
+        Structured(EmotionTrigger),
+    }
+
+    match Option::<Spec>::deserialize(deserializer)? {
+        Some(Spec::Source(source)) => EmotionTrigger::parse(&source).map(Some).map_err(D::Error::custom),
+        Some(Spec::Structured(trigger)) => Ok(Some(trigger)),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oxyde_game::emotion::EmotionalState;
+
+    fn state(emotions: Vec<(&str, f32)>) -> EmotionalState {
+        let mut state = EmotionalState::new();
+        state.set_emotions(emotions);
+        state
+    }
+
+    #[test]
+    fn test_parses_compound_conditions() {
+        let trigger = EmotionTrigger::parse("fear > 0.5 AND trust < 0 || dominant is anticipation").unwrap();
+        assert!(trigger.matches(&state(vec![("fear", 0.7), ("trust", -0.2)])));
+        assert!(!trigger.matches(&state(vec![("fear", 0.7), ("trust", 0.2)])));
+        assert!(trigger.matches(&state(vec![("anticipation", 0.3)])));
+
+        let trigger: EmotionTrigger = "not (valence >= 0) and arousal > 0.1".parse().unwrap();
+        assert!(trigger.matches(&state(vec![("joy", -0.6)])));
+        assert!(!trigger.matches(&state(vec![("joy", 0.6)])));
+
+        for bad in ["fear >", "(fear > 0.5", "fear > 0.5 for 0 turns", "dominant is calm"] {
+            assert!(EmotionTrigger::parse(bad).is_err(), "{}", bad);
+        }
+        let error = EmotionTrigger::parse("fear > 0.5 && hunger > 0.5").unwrap_err().to_string();
+        assert!(error.contains("column 15"), "{}", error);
+    }
+
+    #[test]
+    fn test_sustained_conditions_span_recent_turns() {
+        let trigger = EmotionTrigger::parse("anger > 0.4 for 3 turns").unwrap();
+        assert_eq!(trigger.turns_needed(), 3);
+        let angry = state(vec![("anger", 0.6)]);
+        let calm = state(vec![("anger", 0.1)]);
+
+        assert!(!trigger.matches(&angry));
+        assert!(!trigger.matches_recent(&[angry.clone(), angry.clone()]));
+        assert!(!trigger.matches_recent(&[angry.clone(), calm.clone(), angry.clone()]));
+        assert!(trigger.matches_recent(&[calm, angry.clone(), angry.clone(), angry]));
+    }
+}
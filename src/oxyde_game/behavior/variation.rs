@@ -0,0 +1,241 @@
+//! Varied canned lines for behaviors
+//!
+//! A [`ResponsePool`] picks one of a behavior's lines by weight while
+//! skipping the lines it used most recently, so an NPC greeting the player
+//! twice in a row says something different. Lines may use template
+//! variables filled from the agent context:
+//!
+//! - `{player}`: the `player_name` context value, or "traveler"
+//! - `{time_of_day}`: the period of the game day, such as "morning"
+//! - `{time_greeting}`: "Good morning", "Good afternoon" or "Good evening"
+//!
+//! In configs, a line is either a string or an object with a weight:
+//!
+//! ```yaml
+//! behavior:
+//!   greeting:
+//!     parameters:
+//!       greetings:
+//!         - "{time_greeting}, {player}!"
+//!         - { text: "You again?", weight: 0.2 }
+//!       avoid_repeats: 2
+//! ```
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::agent::AgentContext;
+use crate::context::PLAYER_NAME_KEY;
+use crate::oxyde_game::schedule::TIME_OF_DAY_KEY;
+
+/// How many recent lines a pool avoids repeating by default
+pub const DEFAULT_AVOID_REPEATS: usize = 3;
+
+/// Name used for `{player}` when the context names no player
+const DEFAULT_PLAYER_NAME: &str = "traveler";
+
+/// A canned line and how often it is picked relative to the others
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "VariantSpec")]
+pub struct ResponseVariant {
+    /// Line text, possibly with template variables
+    pub text: String,
+
+    /// Relative weight; lines with weight 0.0 are never picked
+    pub weight: f32,
+}
+
+impl ResponseVariant {
+    /// Create a line with a relative weight
+    pub fn new(text: impl Into<String>, weight: f32) -> Self {
+        Self {
+            text: text.into(),
+            weight,
+        }
+    }
+}
+
+impl From<String> for ResponseVariant {
+    fn from(text: String) -> Self {
+        Self::new(text, 1.0)
+    }
+}
+
+impl From<&str> for ResponseVariant {
+    fn from(text: &str) -> Self {
+        Self::new(text, 1.0)
+    }
+}
+
+/// Config form of a line: a plain string or an object with a weight
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum VariantSpec {
+    Text(String),
+    Weighted {
+        text: String,
+        #[serde(default = "default_weight")]
+        weight: f32,
+    },
+}
+
+fn default_weight() -> f32 {
+    1.0
+}
+
+impl From<VariantSpec> for ResponseVariant {
+    fn from(spec: VariantSpec) -> Self {
+        match spec {
+            VariantSpec::Text(text) => Self::new(text, 1.0),
+            VariantSpec::Weighted { text, weight } => Self::new(text, weight),
+        }
+    }
+}
+
+/// Lines a behavior picks from, avoiding recent repeats
+#[derive(Debug)]
+pub struct ResponsePool {
+    variants: Vec<ResponseVariant>,
+    avoid_repeats: usize,
+    recent: Mutex<VecDeque<usize>>,
+}
+
+impl ResponsePool {
+    /// Create a pool avoiding the [`DEFAULT_AVOID_REPEATS`] most recent lines
+    pub fn new<V: Into<ResponseVariant>>(variants: Vec<V>) -> Self {
+        Self {
+            variants: variants.into_iter().map(Into::into).collect(),
+            avoid_repeats: DEFAULT_AVOID_REPEATS,
+            recent: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Set how many recent lines are avoided; 0 allows immediate repeats
+    pub fn with_avoid_repeats(mut self, avoid_repeats: usize) -> Self {
+        self.avoid_repeats = avoid_repeats;
+        self
+    }
+
+    /// Whether the pool has no lines
+    pub fn is_empty(&self) -> bool {
+        self.variants.is_empty()
+    }
+
+    /// Lines in the pool
+    pub fn variants(&self) -> &[ResponseVariant] {
+        &self.variants
+    }
+
+    /// Pick a line by weight, skipping recently used ones, and fill its variables
+    ///
+    /// Recent lines are only skipped while other lines with a positive weight
+    /// remain, so a pool of one line still answers every time.
+    ///
+    /// # Arguments
+    ///
+    /// * `rng` - Random source, seeded per turn for reproducible choices
+    /// * `context` - Agent context providing template variables
+    ///
+    /// # Returns
+    ///
+    /// The filled line, or `None` if no line has a positive weight
+    pub fn pick(&self, rng: &mut impl Rng, context: &AgentContext) -> Option<String> {
+        let mut recent = self.recent.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let weights = |skip_recent: bool| -> Vec<f32> {
+            self.variants
+                .iter()
+                .enumerate()
+                .map(|(index, variant)| {
+                    if skip_recent && recent.contains(&index) {
+                        0.0
+                    } else {
+                        variant.weight.max(0.0)
+                    }
+                })
+                .collect()
+        };
+        let distribution = WeightedIndex::new(weights(true)).or_else(|_| WeightedIndex::new(weights(false))).ok()?;
+        let index = distribution.sample(rng);
+
+        recent.push_back(index);
+        while recent.len() > self.avoid_repeats {
+            recent.pop_front();
+        }
+        Some(fill_variables(&self.variants[index].text, context))
+    }
+}
+
+/// Fill template variables in a line from the agent context
+///
+/// - `{player}`: the `player_name` context value, or "traveler"
+/// - `{time_of_day}`: the period of the game day, such as "morning"
+/// - `{time_greeting}`: "Good morning", "Good afternoon" or "Good evening"
+pub fn fill_variables(text: &str, context: &AgentContext) -> String {
+    if !text.contains('{') {
+        return text.to_string();
+    }
+    let player = context
+        .get(PLAYER_NAME_KEY)
+        .and_then(|value| value.as_str())
+        .filter(|name| !name.is_empty())
+        .unwrap_or(DEFAULT_PLAYER_NAME);
+    let time_of_day = context.get(TIME_OF_DAY_KEY).and_then(|value| value.as_str());
+    let time_greeting = match time_of_day {
+        Some("early morning" | "morning") => "Good morning",
+        Some("midday" | "afternoon") => "Good afternoon",
+        Some("evening" | "late evening" | "night") => "Good evening",
+        _ => "Good day",
+    };
+    text.replace("{player}", player)
+        .replace("{time_of_day}", time_of_day.unwrap_or("day"))
+        .replace("{time_greeting}", time_greeting)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_picks_by_weight_without_recent_repeats() {
+        let pool = ResponsePool::new(vec![
+            ResponseVariant::new("Hello!", 1.0),
+            ResponseVariant::new("Welcome!", 1.0),
+            ResponseVariant::new("Never", 0.0),
+        ])
+        .with_avoid_repeats(1);
+        let context = AgentContext::new();
+        let mut rng = StdRng::seed_from_u64(7);
+
+        let picks: Vec<String> = (0..6).map(|_| pool.pick(&mut rng, &context).unwrap()).collect();
+        assert!(picks.windows(2).all(|pair| pair[0] != pair[1]));
+        assert!(!picks.iter().any(|pick| pick == "Never"));
+
+        // A single line repeats rather than going silent
+        let single = ResponsePool::new(vec!["Hi"]);
+        assert_eq!(single.pick(&mut rng, &context).as_deref(), Some("Hi"));
+        assert_eq!(single.pick(&mut rng, &context).as_deref(), Some("Hi"));
+        assert_eq!(ResponsePool::new(vec![ResponseVariant::new("x", 0.0)]).pick(&mut rng, &context), None);
+    }
+
+    #[test]
+    fn test_fills_variables_and_parses_config_lines() {
+        let mut context = AgentContext::new();
+        assert_eq!(fill_variables("{time_greeting}, {player}!", &context), "Good day, traveler!");
+        context.insert(PLAYER_NAME_KEY.to_string(), serde_json::json!("Aria"));
+        context.insert(TIME_OF_DAY_KEY.to_string(), serde_json::json!("evening"));
+        assert_eq!(
+            fill_variables("{time_greeting}, {player}. Fine {time_of_day}.", &context),
+            "Good evening, Aria. Fine evening."
+        );
+
+        let variants: Vec<ResponseVariant> =
+            serde_json::from_value(serde_json::json!(["Hi", {"text": "You again?", "weight": 0.2}])).unwrap();
+        assert_eq!(variants, vec![ResponseVariant::new("Hi", 1.0), ResponseVariant::new("You again?", 0.2)]);
+    }
+}
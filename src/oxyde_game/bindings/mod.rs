@@ -1,22 +1,353 @@
 //! Engine bindings for Oxyde SDK
 //!
 //! This module provides bindings for integrating Oxyde with various game engines.
+//!
+//! Agents live in a binding's registry until removed. Engines that load and
+//! unload levels group the agents of each level under a scene: destroying the
+//! scene stops and frees all of them at once, so they do not leak across level
+//! loads. Scenes are reference counted, so a scene shared by several sub-levels
+//! is only destroyed when the last of them releases it, and an agent
+//! registered under several scenes is only freed with the last one.
+//!
+//! Actions agents take are collected per agent as typed
+//! [`ActionCommand`]s, which engines poll as versioned JSON the same way in
+//! every binding.
+//!
+//! The agent ID returned when an agent is created is a handle with full
+//! control. Mods and scripts that should not clear an agent's memories get
+//! another handle to the same agent, limited to a [`HandleCapability`];
+//! destructive calls check the handle and are audited, as in the C API.
 
 // Re-exports
+#[cfg(not(target_arch = "wasm32"))]
 pub use self::unity::{UnityBinding, UnityAgentState};
+#[cfg(not(target_arch = "wasm32"))]
 pub use self::unreal::{UnrealBinding, UnrealAgentConfig};
 pub use self::wasm::WasmBinding;
 
-// Modules
+// Modules; the native engine bindings run multi-threaded runtimes, which
+// WebAssembly targets lack
+#[cfg(not(target_arch = "wasm32"))]
 pub mod unity;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod unreal;
 pub mod wasm;
 
-use std::sync::Arc;
-use crate::agent::Agent;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use uuid::Uuid;
+use crate::action::ActionCommand;
+use crate::agent::{Agent, AgentEvent};
 use crate::config::AgentConfig;
+use crate::oxyde_game::emotion::EMOTION_NAMES;
 use crate::{OxydeError, Result};
 
+/// Agents created through a binding, keyed by agent ID
+pub type AgentRegistry = Arc<Mutex<HashMap<String, Arc<Agent>>>>;
+
+/// A group of agents freed together, such as the NPCs of a level
+#[derive(Debug, Clone)]
+struct Scene {
+    name: String,
+    references: usize,
+    agents: Vec<String>,
+}
+
+/// Outcome of releasing a scene
+#[derive(Debug, Default)]
+pub struct SceneRelease {
+    /// References to the scene still held; 0 once the scene is destroyed
+    pub references: usize,
+
+    /// Agents removed from the agent registry because no other scene holds
+    /// them, to be stopped by the binding
+    pub freed: Vec<Arc<Agent>>,
+}
+
+/// Reference-counted scenes grouping agents created through a binding
+#[derive(Debug, Default)]
+pub struct SceneRegistry {
+    scenes: Mutex<HashMap<String, Scene>>,
+}
+
+impl SceneRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a scene holding one reference
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Scene name, for logs
+    ///
+    /// # Returns
+    ///
+    /// ID of the new scene
+    pub fn create_scene(&self, name: &str) -> String {
+        let id = Uuid::new_v4().to_string();
+        self.lock().insert(
+            id.clone(),
+            Scene {
+                name: name.to_string(),
+                references: 1,
+                agents: Vec::new(),
+            },
+        );
+        log::debug!("Created scene {} ({})", name, id);
+        id
+    }
+
+    /// Take another reference to a scene
+    ///
+    /// # Returns
+    ///
+    /// References now held, or an error if the scene does not exist
+    pub fn retain_scene(&self, scene_id: &str) -> Result<usize> {
+        let mut scenes = self.lock();
+        let scene = scenes.get_mut(scene_id).ok_or_else(|| unknown_scene(scene_id))?;
+        scene.references += 1;
+        Ok(scene.references)
+    }
+
+    /// Register an agent under a scene
+    ///
+    /// Registering an agent twice under the same scene has no effect.
+    pub fn add_agent(&self, scene_id: &str, agent_id: &str) -> Result<()> {
+        let mut scenes = self.lock();
+        let scene = scenes.get_mut(scene_id).ok_or_else(|| unknown_scene(scene_id))?;
+        if !scene.agents.iter().any(|id| id == agent_id) {
+            scene.agents.push(agent_id.to_string());
+        }
+        Ok(())
+    }
+
+    /// IDs of the agents registered under a scene
+    pub fn scene_agents(&self, scene_id: &str) -> Result<Vec<String>> {
+        self.lock()
+            .get(scene_id)
+            .map(|scene| scene.agents.clone())
+            .ok_or_else(|| unknown_scene(scene_id))
+    }
+
+    /// Number of live scenes
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Whether no scene is live
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Release a reference to a scene
+    ///
+    /// When the last reference is released the scene is destroyed, and its
+    /// agents that no other scene holds are removed from the agent registry.
+    ///
+    /// # Arguments
+    ///
+    /// * `scene_id` - Scene to release
+    /// * `agents` - Registry of the binding's agents
+    ///
+    /// # Returns
+    ///
+    /// The references left and the freed agents, or an error if the scene
+    /// does not exist
+    pub fn release_scene(&self, scene_id: &str, agents: &AgentRegistry) -> Result<SceneRelease> {
+        let mut scenes = self.lock();
+        let scene = scenes.get_mut(scene_id).ok_or_else(|| unknown_scene(scene_id))?;
+        scene.references -= 1;
+        if scene.references > 0 {
+            return Ok(SceneRelease {
+                references: scene.references,
+                freed: Vec::new(),
+            });
+        }
+
+        let scene = scenes.remove(scene_id).expect("scene was just found");
+        let held_elsewhere = |agent_id: &String| scenes.values().any(|other| other.agents.contains(agent_id));
+        let mut registry = agents.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let freed: Vec<Arc<Agent>> = scene
+            .agents
+            .iter()
+            .filter(|agent_id| !held_elsewhere(agent_id))
+            .filter_map(|agent_id| registry.remove(agent_id))
+            .collect();
+        log::debug!("Destroyed scene {} ({}), freeing {} agents", scene.name, scene_id, freed.len());
+        Ok(SceneRelease { references: 0, freed })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Scene>> {
+        self.scenes.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn unknown_scene(scene_id: &str) -> OxydeError {
+    OxydeError::BindingError(format!("Scene with ID {} not found", scene_id))
+}
+
+/// Most actions kept per agent until polled; older actions are dropped
+pub const MAX_PENDING_ACTIONS: usize = 64;
+
+/// Actions agents took, waiting for the engine to poll them
+#[derive(Debug, Clone, Default)]
+pub struct ActionInbox {
+    pending: Arc<Mutex<HashMap<String, Vec<ActionCommand>>>>,
+}
+
+impl ActionInbox {
+    /// Create an empty inbox
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Collect the actions an agent takes from now on
+    pub fn watch(&self, agent_id: &str, agent: &Agent) {
+        let pending = self.pending.clone();
+        let agent_id = agent_id.to_string();
+        agent.on_event(AgentEvent::Action, move |_, action| match ActionCommand::parse(action) {
+            Ok(command) => {
+                let mut pending = pending.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                let actions = pending.entry(agent_id.clone()).or_default();
+                if actions.len() >= MAX_PENDING_ACTIONS {
+                    actions.remove(0);
+                }
+                actions.push(command);
+            }
+            Err(e) => log::warn!("Agent {} took an unreadable action: {}", agent_id, e),
+        });
+    }
+
+    /// Take the actions an agent took since the last poll, oldest first
+    pub fn drain(&self, agent_id: &str) -> Vec<ActionCommand> {
+        self.lock().remove(agent_id).unwrap_or_default()
+    }
+
+    /// Take an agent's pending actions as a JSON array of versioned commands
+    pub fn drain_json(&self, agent_id: &str) -> String {
+        let commands: Vec<serde_json::Value> = self.drain(agent_id).iter().map(ActionCommand::to_json).collect();
+        serde_json::Value::from(commands).to_string()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Vec<ActionCommand>>> {
+        self.pending.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Log target of the audit trail of gated binding calls
+pub const AUDIT_TARGET: &str = "oxyde::bindings::audit";
+
+/// What calls an agent handle may make, each level allowing those below it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HandleCapability {
+    /// Read emotions, memories and state
+    ReadOnly,
+    /// Also send input and change context, emotions and memories
+    Interact,
+    /// Also clear memories and forget memories and memory categories
+    Full,
+}
+
+impl HandleCapability {
+    /// Level from its number in the engine API: 0 read-only, 1 interact,
+    /// 2 full
+    pub fn from_level(level: u32) -> Option<Self> {
+        match level {
+            0 => Some(Self::ReadOnly),
+            1 => Some(Self::Interact),
+            2 => Some(Self::Full),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::ReadOnly => "read-only",
+            Self::Interact => "interact",
+            Self::Full => "full",
+        }
+    }
+}
+
+/// Handles to agents limited to a capability
+///
+/// Agent IDs are handles with full capability; shared handles map to the
+/// agent they were shared from.
+#[derive(Debug, Default)]
+pub struct HandleRegistry {
+    shared: Mutex<HashMap<String, (String, HandleCapability)>>,
+}
+
+impl HandleRegistry {
+    /// Create a registry with no shared handles
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Issue another handle to an agent, limited to a capability
+    ///
+    /// Check with [`HandleRegistry::resolve`] that the handle it is shared
+    /// from has at least `capability` first.
+    pub fn issue(&self, agent_id: &str, capability: HandleCapability) -> String {
+        let handle = Uuid::new_v4().to_string();
+        self.lock().insert(handle.clone(), (agent_id.to_string(), capability));
+        handle
+    }
+
+    /// Capability of a handle
+    pub fn capability(&self, handle: &str) -> HandleCapability {
+        self.lock().get(handle).map_or(HandleCapability::Full, |(_, capability)| *capability)
+    }
+
+    /// ID of the agent a handle refers to, if the handle allows a call
+    ///
+    /// Denied calls and calls needing full capability are recorded under
+    /// [`AUDIT_TARGET`].
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` - Agent ID or shared handle
+    /// * `required` - Capability the call needs
+    /// * `call` - Name of the call, for the audit trail
+    pub fn resolve(&self, handle: &str, required: HandleCapability, call: &str) -> Result<String> {
+        let (agent_id, capability) = self
+            .lock()
+            .get(handle)
+            .cloned()
+            .unwrap_or_else(|| (handle.to_string(), HandleCapability::Full));
+        let allowed = capability >= required;
+        if !allowed {
+            log::warn!(target: AUDIT_TARGET, "{} denied on agent {} ({})", call, agent_id, capability.name());
+            return Err(OxydeError::BindingError(format!(
+                "`{}` needs {} capability but the handle has {}",
+                call,
+                required.name(),
+                capability.name()
+            )));
+        }
+        if required == HandleCapability::Full {
+            log::info!(target: AUDIT_TARGET, "{} allowed on agent {} ({})", call, agent_id, capability.name());
+        }
+        Ok(agent_id)
+    }
+
+    /// Drop the shared handles of an agent that was freed
+    pub fn release_agent(&self, agent_id: &str) {
+        self.lock().retain(|_, (shared_from, _)| shared_from != agent_id);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, (String, HandleCapability)>> {
+        self.shared.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Read a behavior action as a versioned JSON command
+pub fn action_command_json(action: &str) -> Result<String> {
+    Ok(ActionCommand::parse(action)?.to_string())
+}
+
 /// Common trait for all engine bindings
 pub trait EngineBinding {
     /// Create a new agent from a configuration file
@@ -117,3 +448,35 @@ pub fn parse_context_json(context_json: &str) -> Result<serde_json::Map<String,
         OxydeError::BindingError(format!("Failed to parse context JSON: {}", e))
     })
 }
+
+/// Helper function to parse emotion deltas from JSON
+///
+/// The JSON is an object mapping emotion names to deltas, such as
+/// `{"joy": 0.2, "fear": -0.1}`. Unknown names are rejected so typos in
+/// engine scripts surface as errors instead of silently doing nothing.
+///
+/// # Arguments
+///
+/// * `emotions_json` - JSON object with emotion deltas
+///
+/// # Returns
+///
+/// Emotion names and deltas or an error
+pub fn parse_emotion_deltas_json(emotions_json: &str) -> Result<Vec<(String, f32)>> {
+    let deltas: serde_json::Map<String, serde_json::Value> = serde_json::from_str(emotions_json).map_err(|e| {
+        OxydeError::BindingError(format!("Failed to parse emotions JSON: {}", e))
+    })?;
+
+    deltas
+        .into_iter()
+        .map(|(emotion, delta)| {
+            if !EMOTION_NAMES.contains(&emotion.as_str()) {
+                return Err(OxydeError::BindingError(format!("Unknown emotion: {}", emotion)));
+            }
+            let delta = delta.as_f64().ok_or_else(|| {
+                OxydeError::BindingError(format!("Delta for {} must be a number", emotion))
+            })?;
+            Ok((emotion, delta as f32))
+        })
+        .collect()
+}
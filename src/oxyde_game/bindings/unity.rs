@@ -13,7 +13,7 @@ use uuid::Uuid;
 use ffi_support::FfiStr;
 
 use crate::agent::{Agent, AgentContext, AgentState};
-use crate::oxyde_game::bindings::{EngineBinding, load_agent_config, parse_context_json};
+use crate::oxyde_game::bindings::{action_command_json, ActionInbox, AgentRegistry, EngineBinding, HandleCapability, HandleRegistry, SceneRegistry, load_agent_config, parse_context_json, parse_emotion_deltas_json};
 use crate::{OxydeError, Result};
 
 lazy_static::lazy_static! {
@@ -63,7 +63,16 @@ impl From<&Agent> for UnityAgentState {
 /// Unity binding for Oxyde SDK
 pub struct UnityBinding {
     /// Registry of created agents
-    agents: Arc<Mutex<HashMap<String, Arc<Agent>>>>,
+    agents: AgentRegistry,
+
+    /// Scenes grouping agents freed together
+    scenes: SceneRegistry,
+
+    /// Actions agents took, waiting to be polled
+    actions: ActionInbox,
+
+    /// Agent handles limited to a capability
+    handles: HandleRegistry,
 }
 
 impl UnityBinding {
@@ -71,6 +80,9 @@ impl UnityBinding {
     pub fn new() -> Self {
         Self {
             agents: Arc::new(Mutex::new(HashMap::new())),
+            scenes: SceneRegistry::new(),
+            actions: ActionInbox::new(),
+            handles: HandleRegistry::new(),
         }
     }
     
@@ -94,6 +106,45 @@ impl UnityBinding {
             })
     }
     
+    /// Get the agent a handle refers to, if the handle allows a call
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` - Agent ID or handle from [`UnityBinding::share_agent`]
+    /// * `required` - Capability the call needs
+    /// * `call` - Name of the call, for the audit trail
+    ///
+    /// # Returns
+    ///
+    /// The agent, or an error if the handle lacks the capability or the
+    /// agent was not found
+    pub fn agent_handle(&self, handle: &str, required: HandleCapability, call: &str) -> Result<Arc<Agent>> {
+        self.get_agent(&self.handles.resolve(handle, required, call)?)
+    }
+
+    /// Create another handle to an agent, limited to a capability
+    ///
+    /// Hand the new handle to mods and scripts that should not have full
+    /// control. A handle cannot grant more than its own capability.
+    ///
+    /// # Returns
+    ///
+    /// The new handle, or an error if `handle` has less than `capability`
+    /// or its agent was not found
+    pub fn share_agent(&self, handle: &str, capability: HandleCapability) -> Result<String> {
+        let agent_id = self.handles.resolve(handle, capability, "share_agent")?;
+        self.get_agent(&agent_id)?;
+        Ok(self.handles.issue(&agent_id, capability))
+    }
+
+    /// Get the registry of agents created through this binding
+    ///
+    /// The registry is shared, so agents registered later are visible to
+    /// holders such as the WebSocket session server.
+    pub fn registry(&self) -> AgentRegistry {
+        self.agents.clone()
+    }
+
     /// Register a new agent
     ///
     /// # Arguments
@@ -101,6 +152,7 @@ impl UnityBinding {
     /// * `id` - Agent unique identifier
     /// * `agent` - Agent to register
     pub fn register_agent(&self, id: Uuid, agent: Arc<Agent>) {
+        self.actions.watch(&id.to_string(), &agent);
         match self.agents.lock() {
             Ok(mut agents) => {
                 agents.insert(id.to_string(), agent);
@@ -132,6 +184,91 @@ impl UnityBinding {
         
         Ok(agent_context)
     }
+
+    /// Create a scene grouping agents that are freed together, such as the
+    /// NPCs of a level
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Scene name, for logs
+    ///
+    /// # Returns
+    ///
+    /// ID of the new scene, holding one reference
+    pub fn create_scene(&self, name: &str) -> String {
+        self.scenes.create_scene(name)
+    }
+
+    /// Take another reference to a scene, such as for a sub-level sharing it
+    ///
+    /// Each reference is released with [`UnityBinding::destroy_scene`].
+    ///
+    /// # Returns
+    ///
+    /// References now held, or an error if the scene does not exist
+    pub fn retain_scene(&self, scene_id: &str) -> Result<usize> {
+        self.scenes.retain_scene(scene_id)
+    }
+
+    /// Register an agent created through this binding under a scene
+    ///
+    /// # Arguments
+    ///
+    /// * `scene_id` - Scene to register the agent under
+    /// * `agent_id` - Agent ID
+    ///
+    /// # Returns
+    ///
+    /// Success or an error if the scene or agent does not exist
+    pub fn add_agent_to_scene(&self, scene_id: &str, agent_id: &str) -> Result<()> {
+        self.get_agent(agent_id)?;
+        self.scenes.add_agent(scene_id, agent_id)
+    }
+
+    /// Release a reference to a scene
+    ///
+    /// Releasing the last reference stops the scene's agents and removes them
+    /// from the registry, unless another scene still holds them.
+    ///
+    /// # Arguments
+    ///
+    /// * `scene_id` - Scene to release
+    ///
+    /// # Returns
+    ///
+    /// Number of agents freed, or an error if the scene does not exist
+    pub fn destroy_scene(&self, scene_id: &str) -> Result<usize> {
+        let release = self.scenes.release_scene(scene_id, &self.agents)?;
+        let freed = release.freed.len();
+        for agent in &release.freed {
+            self.actions.drain(&agent.id().to_string());
+            self.handles.release_agent(&agent.id().to_string());
+        }
+        RUNTIME.block_on(async {
+            for agent in release.freed {
+                if let Err(e) = agent.stop().await {
+                    log::warn!("Failed to stop agent {} of destroyed scene: {}", agent.name(), e);
+                }
+            }
+        });
+        Ok(freed)
+    }
+
+    /// Take the actions an agent took since the last poll
+    ///
+    /// # Returns
+    ///
+    /// A JSON array of versioned action commands, oldest first, or an error
+    /// if the agent does not exist
+    pub fn poll_actions(&self, agent_id: &str) -> Result<String> {
+        self.get_agent(agent_id)?;
+        Ok(self.actions.drain_json(agent_id))
+    }
+
+    /// Read a behavior action string as a versioned JSON action command
+    pub fn parse_action(&self, action: &str) -> Result<String> {
+        action_command_json(action)
+    }
     
     /// Get agent state as JSON
     ///
@@ -164,6 +301,25 @@ impl UnityBinding {
         })
     }
 
+    /// Apply several emotion deltas to an agent at once
+    ///
+    /// Nothing is applied if the JSON is invalid or names an unknown emotion.
+    ///
+    /// # Arguments
+    ///
+    /// * `agent` - Agent to update
+    /// * `emotions_json` - JSON object mapping emotion names to deltas
+    ///
+    /// # Returns
+    ///
+    /// Success or an error
+    pub fn update_agent_emotions_json(&self, agent: &Agent, emotions_json: &str) -> Result<()> {
+        let deltas = parse_emotion_deltas_json(emotions_json)?;
+        let updates: Vec<(&str, f32)> = deltas.iter().map(|(emotion, delta)| (emotion.as_str(), *delta)).collect();
+        RUNTIME.block_on(agent.update_emotions(&updates));
+        Ok(())
+    }
+
 }
 
 impl EngineBinding for UnityBinding {
@@ -285,6 +441,23 @@ pub mod ffi {
         }
     }
     
+    /// Create another handle to an agent, limited to a capability: 0
+    /// read-only, 1 interact, 2 full
+    ///
+    /// Returns null if the capability is unknown or exceeds the handle's own.
+    #[no_mangle]
+    pub extern "C" fn oxyde_unity_share_agent(agent_id: FfiStr, capability: u32) -> *mut c_char {
+        let binding = get_binding();
+        let agent_id_str = agent_id.into_string();
+        let Some(capability) = HandleCapability::from_level(capability) else {
+            return std::ptr::null_mut();
+        };
+        match binding.share_agent(&agent_id_str, capability) {
+            Ok(handle) => string_to_ptr(handle),
+            Err(_) => std::ptr::null_mut(),
+        }
+    }
+
     /// Update an agent with new context data
     #[no_mangle]
     pub extern "C" fn oxyde_unity_update_agent(agent_id: FfiStr, context_json: FfiStr) -> bool {
@@ -292,7 +465,7 @@ pub mod ffi {
         let agent_id_str = agent_id.into_string();
         let context_json_str = context_json.into_string();
         
-        match binding.get_agent(&agent_id_str) {
+        match binding.agent_handle(&agent_id_str, HandleCapability::Interact, "oxyde_unity_update_agent") {
             Ok(agent) => {
                 binding.update_agent(&agent, &context_json_str).is_ok()
             },
@@ -307,7 +480,7 @@ pub mod ffi {
         let agent_id_str = agent_id.into_string();
         let input_str = input.into_string();
         
-        match binding.get_agent(&agent_id_str) {
+        match binding.agent_handle(&agent_id_str, HandleCapability::Interact, "oxyde_unity_process_input") {
             Ok(agent) => {
                 match binding.process_input(&agent, &input_str) {
                     Ok(response) => string_to_ptr(response),
@@ -324,7 +497,7 @@ pub mod ffi {
         let binding = get_binding();
         let agent_id_str = agent_id.into_string();
         
-        match binding.get_agent(&agent_id_str) {
+        match binding.agent_handle(&agent_id_str, HandleCapability::ReadOnly, "oxyde_unity_get_agent_state") {
             Ok(agent) => {
                 match binding.get_agent_state_json(&agent) {
                     Ok(state_json) => string_to_ptr(state_json),
@@ -341,7 +514,7 @@ pub mod ffi {
         let binding = get_binding();
         let agent_id_str = agent_id.into_string();
         
-        match binding.get_agent(&agent_id_str) {
+        match binding.agent_handle(&agent_id_str, HandleCapability::ReadOnly, "oxyde_unity_get_emotion_vector") {
             Ok(agent) => {
                 match binding.get_agent_emotion_vector(&agent) {
                     Ok(emotion_vector) => {
@@ -380,7 +553,7 @@ pub mod ffi {
         let binding = get_binding();
         let agent_id_str = agent_id.into_string();
         
-        match binding.get_agent(&agent_id_str) {
+        match binding.agent_handle(&agent_id_str, HandleCapability::ReadOnly, "oxyde_unity_get_emotion_vector_raw") {
             Ok(agent) => {
                 match binding.get_agent_emotion_vector(&agent) {
                     Ok(emotion_vector) => {
@@ -419,6 +592,97 @@ pub mod ffi {
         }
     }
 
+    /// Apply several emotion deltas at once from a JSON object such as `{"joy": 0.2, "fear": -0.1}`
+    #[no_mangle]
+    pub extern "C" fn oxyde_unity_update_emotions_json(agent_id: FfiStr, emotions_json: FfiStr) -> bool {
+        let binding = get_binding();
+        let agent_id_str = agent_id.into_string();
+        let emotions_json_str = emotions_json.into_string();
+
+        match binding.agent_handle(&agent_id_str, HandleCapability::Interact, "oxyde_unity_update_emotions_json") {
+            Ok(agent) => binding.update_agent_emotions_json(&agent, &emotions_json_str).is_ok(),
+            Err(_) => false,
+        }
+    }
+
+    // ==================== Scene FFI ====================
+
+    /// Create a scene grouping agents freed together, returning its ID
+    #[no_mangle]
+    pub extern "C" fn oxyde_unity_create_scene(name: FfiStr) -> *mut c_char {
+        let binding = get_binding();
+        let name_str = name.into_string();
+
+        string_to_ptr(binding.create_scene(&name_str))
+    }
+
+    /// Take another reference to a scene, returning the references held or 0
+    /// if the scene does not exist
+    #[no_mangle]
+    pub extern "C" fn oxyde_unity_retain_scene(scene_id: FfiStr) -> u32 {
+        let binding = get_binding();
+        let scene_id_str = scene_id.into_string();
+
+        match binding.retain_scene(&scene_id_str) {
+            Ok(references) => references as u32,
+            Err(_) => 0,
+        }
+    }
+
+    /// Register an agent under a scene
+    #[no_mangle]
+    pub extern "C" fn oxyde_unity_scene_add_agent(scene_id: FfiStr, agent_id: FfiStr) -> bool {
+        let binding = get_binding();
+        let scene_id_str = scene_id.into_string();
+        let agent_id_str = agent_id.into_string();
+
+        binding.add_agent_to_scene(&scene_id_str, &agent_id_str).is_ok()
+    }
+
+    /// Release a reference to a scene; the last release stops and frees the
+    /// scene's agents
+    #[no_mangle]
+    pub extern "C" fn oxyde_unity_destroy_scene(scene_id: FfiStr) -> bool {
+        let binding = get_binding();
+        let scene_id_str = scene_id.into_string();
+
+        match binding.destroy_scene(&scene_id_str) {
+            Ok(freed) => {
+                log::debug!("Released scene {}, freeing {} agents", scene_id_str, freed);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    // ==================== Action FFI ====================
+
+    /// Take the actions an agent took since the last poll, as a JSON array
+    /// of versioned action commands; empty if the agent does not exist
+    #[no_mangle]
+    pub extern "C" fn oxyde_unity_poll_actions(agent_id: FfiStr) -> *mut c_char {
+        let binding = get_binding();
+        let agent_id_str = agent_id.into_string();
+
+        match binding.poll_actions(&agent_id_str) {
+            Ok(actions) => string_to_ptr(actions),
+            Err(_) => string_to_ptr("[]".to_string()),
+        }
+    }
+
+    /// Read a behavior action string as a versioned JSON action command;
+    /// null if the action is malformed
+    #[no_mangle]
+    pub extern "C" fn oxyde_unity_parse_action(action: FfiStr) -> *mut c_char {
+        let binding = get_binding();
+        let action_str = action.into_string();
+
+        match binding.parse_action(&action_str) {
+            Ok(command) => string_to_ptr(command),
+            Err(_) => std::ptr::null_mut(),
+        }
+    }
+
     // ==================== Memory System FFI ====================
 
     /// Add a memory to an agent's memory system
@@ -439,7 +703,7 @@ pub mod ffi {
             None => return false,
         };
         
-        match binding.get_agent(&agent_id_str) {
+        match binding.agent_handle(&agent_id_str, HandleCapability::Interact, "oxyde_unity_add_memory") {
             Ok(agent) => {
                 RUNTIME.block_on(async {
                     agent.add_memory(memory_category, &content_str, importance, None).await.is_ok()
@@ -469,7 +733,7 @@ pub mod ffi {
             None => return false,
         };
         
-        match binding.get_agent(&agent_id_str) {
+        match binding.agent_handle(&agent_id_str, HandleCapability::Interact, "oxyde_unity_add_emotional_memory") {
             Ok(agent) => {
                 RUNTIME.block_on(async {
                     agent.add_emotional_memory(
@@ -487,7 +751,7 @@ pub mod ffi {
         let binding = get_binding();
         let agent_id_str = agent_id.into_string();
         
-        match binding.get_agent(&agent_id_str) {
+        match binding.agent_handle(&agent_id_str, HandleCapability::ReadOnly, "oxyde_unity_get_memory_count") {
             Ok(agent) => {
                 RUNTIME.block_on(async {
                     agent.memory_count().await as u32
@@ -503,7 +767,7 @@ pub mod ffi {
         let binding = get_binding();
         let agent_id_str = agent_id.into_string();
         
-        match binding.get_agent(&agent_id_str) {
+        match binding.agent_handle(&agent_id_str, HandleCapability::Full, "oxyde_unity_clear_memories") {
             Ok(agent) => {
                 RUNTIME.block_on(async {
                     agent.clear_memories().await as u32
@@ -528,7 +792,7 @@ pub mod ffi {
             None => return string_to_ptr("[]".to_string()),
         };
         
-        match binding.get_agent(&agent_id_str) {
+        match binding.agent_handle(&agent_id_str, HandleCapability::ReadOnly, "oxyde_unity_get_memories_by_category") {
             Ok(agent) => {
                 let memories = RUNTIME.block_on(async {
                     agent.get_memories_by_category(memory_category).await
@@ -551,7 +815,7 @@ pub mod ffi {
         let agent_id_str = agent_id.into_string();
         let query_str = query.into_string();
         
-        match binding.get_agent(&agent_id_str) {
+        match binding.agent_handle(&agent_id_str, HandleCapability::ReadOnly, "oxyde_unity_retrieve_relevant_memories") {
             Ok(agent) => {
                 let result = RUNTIME.block_on(async {
                     agent.retrieve_relevant_memories(&query_str, limit as usize).await
@@ -574,7 +838,7 @@ pub mod ffi {
         let agent_id_str = agent_id.into_string();
         let memory_id_str = memory_id.into_string();
         
-        match binding.get_agent(&agent_id_str) {
+        match binding.agent_handle(&agent_id_str, HandleCapability::Full, "oxyde_unity_forget_memory") {
             Ok(agent) => {
                 RUNTIME.block_on(async {
                     agent.forget_memory(&memory_id_str).await.is_ok()
@@ -599,7 +863,7 @@ pub mod ffi {
             None => return 0,
         };
         
-        match binding.get_agent(&agent_id_str) {
+        match binding.agent_handle(&agent_id_str, HandleCapability::Full, "oxyde_unity_forget_memories_by_category") {
             Ok(agent) => {
                 RUNTIME.block_on(async {
                     agent.forget_memories_by_category(memory_category).await as u32
@@ -643,4 +907,76 @@ mod tests {
         assert_eq!(context.get("player_y").unwrap().as_f64().unwrap(), 20.5);
         assert_eq!(context.get("player_name").unwrap().as_str().unwrap(), "Hero");
     }
+
+    #[test]
+    fn test_update_emotions_json_applies_batch() {
+        let binding = UnityBinding::new();
+        let agent = binding
+            .create_agent_from_json(
+                r#"{"agent": {"name": "Guard", "role": "Guard", "backstory": [], "knowledge": []},
+                    "memory": {}, "inference": {"use_local": true, "local_model_path": "models/test.bin"}, "behavior": {}}"#,
+            )
+            .unwrap();
+
+        binding.update_agent_emotions_json(&agent, r#"{"joy": 0.5, "fear": 0.25}"#).unwrap();
+        let vector = binding.get_agent_emotion_vector(&agent).unwrap();
+        assert_eq!(vector[0], 0.5);
+        assert_eq!(vector[2], 0.25);
+
+        // A bad entry rejects the whole batch
+        assert!(binding.update_agent_emotions_json(&agent, r#"{"joy": 0.2, "rage": 0.5}"#).is_err());
+        assert!(binding.update_agent_emotions_json(&agent, r#"{"joy": "high"}"#).is_err());
+        assert_eq!(binding.get_agent_emotion_vector(&agent).unwrap()[0], 0.5);
+    }
+    #[test]
+    fn test_destroying_a_scene_frees_its_agents() {
+        let binding = UnityBinding::new();
+        let config = r#"{"agent": {"name": "Guard", "role": "Guard", "backstory": [], "knowledge": []},
+            "memory": {}, "inference": {"use_local": true, "local_model_path": "models/test.bin"}, "behavior": {}}"#;
+        let guard = binding.create_agent_from_json(config).unwrap().id().to_string();
+        let merchant = binding.create_agent_from_json(config).unwrap().id().to_string();
+
+        let town = binding.create_scene("Town");
+        let market = binding.create_scene("Market");
+        binding.add_agent_to_scene(&town, &guard).unwrap();
+        binding.add_agent_to_scene(&town, &merchant).unwrap();
+        binding.add_agent_to_scene(&market, &merchant).unwrap();
+        assert!(binding.add_agent_to_scene(&town, "missing").is_err());
+
+        // A retained scene survives its first release
+        assert_eq!(binding.retain_scene(&town).unwrap(), 2);
+        assert_eq!(binding.destroy_scene(&town).unwrap(), 0);
+        assert!(binding.get_agent(&guard).is_ok());
+
+        // The merchant is still held by the market
+        assert_eq!(binding.destroy_scene(&town).unwrap(), 1);
+        assert!(binding.get_agent(&guard).is_err());
+        assert!(binding.get_agent(&merchant).is_ok());
+        assert!(binding.destroy_scene(&town).is_err());
+
+        assert_eq!(binding.destroy_scene(&market).unwrap(), 1);
+        assert!(binding.registry().lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_shared_handles_are_limited_to_their_capability() {
+        let binding = UnityBinding::new();
+        let config = r#"{"agent": {"name": "Guard", "role": "Guard", "backstory": [], "knowledge": []},
+            "memory": {}, "inference": {"use_local": true, "local_model_path": "models/test.bin"}, "behavior": {}}"#;
+        let owner = binding.create_agent_from_json(config).unwrap().id().to_string();
+        let clear = "oxyde_unity_clear_memories";
+
+        let reader = binding.share_agent(&owner, HandleCapability::ReadOnly).unwrap();
+        assert_eq!(binding.agent_handle(&reader, HandleCapability::ReadOnly, "get_memory_count").unwrap().id().to_string(), owner);
+        assert!(binding.agent_handle(&reader, HandleCapability::Interact, "process_input").is_err());
+        assert!(binding.agent_handle(&reader, HandleCapability::Full, clear).is_err());
+        assert!(binding.agent_handle(&owner, HandleCapability::Full, clear).is_ok());
+
+        // A handle cannot grant more than it has
+        assert!(binding.share_agent(&reader, HandleCapability::Full).is_err());
+        let scripted = binding.share_agent(&owner, HandleCapability::Interact).unwrap();
+        assert!(binding.agent_handle(&scripted, HandleCapability::Interact, "process_input").is_ok());
+        assert!(binding.agent_handle(&scripted, HandleCapability::Full, clear).is_err());
+        assert!(binding.share_agent("missing", HandleCapability::ReadOnly).is_err());
+    }
 }
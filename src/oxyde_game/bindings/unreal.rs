@@ -13,7 +13,7 @@ use uuid::Uuid;
 use ffi_support::FfiStr;
 
 use crate::agent::{Agent, AgentContext};
-use crate::oxyde_game::bindings::{EngineBinding, load_agent_config, parse_context_json};
+use crate::oxyde_game::bindings::{action_command_json, ActionInbox, AgentRegistry, EngineBinding, HandleCapability, HandleRegistry, SceneRegistry, load_agent_config, parse_context_json};
 use crate::{OxydeError, Result};
 
 /// Unreal-specific agent configuration
@@ -29,7 +29,16 @@ pub struct UnrealAgentConfig {
 /// Unreal Engine binding for Oxyde SDK
 pub struct UnrealBinding {
     /// Registry of created agents
-    agents: Arc<Mutex<HashMap<String, Arc<Agent>>>>,
+    agents: AgentRegistry,
+
+    /// Scenes grouping agents freed together
+    scenes: SceneRegistry,
+
+    /// Actions agents took, waiting to be polled
+    actions: ActionInbox,
+
+    /// Agent handles limited to a capability
+    handles: HandleRegistry,
 }
 
 impl UnrealBinding {
@@ -37,6 +46,9 @@ impl UnrealBinding {
     pub fn new() -> Self {
         Self {
             agents: Arc::new(Mutex::new(HashMap::new())),
+            scenes: SceneRegistry::new(),
+            actions: ActionInbox::new(),
+            handles: HandleRegistry::new(),
         }
     }
     
@@ -60,6 +72,45 @@ impl UnrealBinding {
             })
     }
     
+    /// Get the agent a handle refers to, if the handle allows a call
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` - Agent ID or handle from [`UnrealBinding::share_agent`]
+    /// * `required` - Capability the call needs
+    /// * `call` - Name of the call, for the audit trail
+    ///
+    /// # Returns
+    ///
+    /// The agent, or an error if the handle lacks the capability or the
+    /// agent was not found
+    pub fn agent_handle(&self, handle: &str, required: HandleCapability, call: &str) -> Result<Arc<Agent>> {
+        self.get_agent(&self.handles.resolve(handle, required, call)?)
+    }
+
+    /// Create another handle to an agent, limited to a capability
+    ///
+    /// Hand the new handle to mods and scripts that should not have full
+    /// control. A handle cannot grant more than its own capability.
+    ///
+    /// # Returns
+    ///
+    /// The new handle, or an error if `handle` has less than `capability`
+    /// or its agent was not found
+    pub fn share_agent(&self, handle: &str, capability: HandleCapability) -> Result<String> {
+        let agent_id = self.handles.resolve(handle, capability, "share_agent")?;
+        self.get_agent(&agent_id)?;
+        Ok(self.handles.issue(&agent_id, capability))
+    }
+
+    /// Get the registry of agents created through this binding
+    ///
+    /// The registry is shared, so agents registered later are visible to
+    /// holders such as the WebSocket session server.
+    pub fn registry(&self) -> AgentRegistry {
+        self.agents.clone()
+    }
+
     /// Register a new agent
     ///
     /// # Arguments
@@ -67,6 +118,7 @@ impl UnrealBinding {
     /// * `id` - Agent unique identifier
     /// * `agent` - Agent to register
     pub fn register_agent(&self, id: Uuid, agent: Arc<Agent>) {
+        self.actions.watch(&id.to_string(), &agent);
         match self.agents.lock() {
             Ok(mut agents) => {
                 agents.insert(id.to_string(), agent);
@@ -99,6 +151,96 @@ impl UnrealBinding {
         Ok(agent_context)
     }
 
+    /// Create a scene grouping agents that are freed together, such as the
+    /// NPCs of a level
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Scene name, for logs
+    ///
+    /// # Returns
+    ///
+    /// ID of the new scene, holding one reference
+    pub fn create_scene(&self, name: &str) -> String {
+        self.scenes.create_scene(name)
+    }
+
+    /// Take another reference to a scene, such as for a sub-level sharing it
+    ///
+    /// Each reference is released with [`UnrealBinding::destroy_scene`].
+    ///
+    /// # Returns
+    ///
+    /// References now held, or an error if the scene does not exist
+    pub fn retain_scene(&self, scene_id: &str) -> Result<usize> {
+        self.scenes.retain_scene(scene_id)
+    }
+
+    /// Register an agent created through this binding under a scene
+    ///
+    /// # Arguments
+    ///
+    /// * `scene_id` - Scene to register the agent under
+    /// * `agent_id` - Agent ID
+    ///
+    /// # Returns
+    ///
+    /// Success or an error if the scene or agent does not exist
+    pub fn add_agent_to_scene(&self, scene_id: &str, agent_id: &str) -> Result<()> {
+        self.get_agent(agent_id)?;
+        self.scenes.add_agent(scene_id, agent_id)
+    }
+
+    /// Release a reference to a scene
+    ///
+    /// Releasing the last reference stops the scene's agents and removes them
+    /// from the registry, unless another scene still holds them.
+    ///
+    /// # Arguments
+    ///
+    /// * `scene_id` - Scene to release
+    ///
+    /// # Returns
+    ///
+    /// Number of agents freed, or an error if the scene does not exist
+    pub fn destroy_scene(&self, scene_id: &str) -> Result<usize> {
+        let release = self.scenes.release_scene(scene_id, &self.agents)?;
+        let freed = release.freed.len();
+        for agent in &release.freed {
+            self.actions.drain(&agent.id().to_string());
+            self.handles.release_agent(&agent.id().to_string());
+        }
+        if freed > 0 {
+            let runtime = tokio::runtime::Runtime::new().map_err(|e| {
+                OxydeError::BindingError(format!("Failed to create Tokio runtime: {}", e))
+            })?;
+            runtime.block_on(async {
+                for agent in release.freed {
+                    if let Err(e) = agent.stop().await {
+                        log::warn!("Failed to stop agent {} of destroyed scene: {}", agent.name(), e);
+                    }
+                }
+            });
+        }
+        Ok(freed)
+    }
+
+    /// Take the actions an agent took since the last poll
+    ///
+    /// # Returns
+    ///
+    /// A JSON array of versioned action commands, oldest first, or an error
+    /// if the agent does not exist
+    pub fn poll_actions(&self, agent_id: &str) -> Result<String> {
+        self.get_agent(agent_id)?;
+        Ok(self.actions.drain_json(agent_id))
+    }
+
+    /// Read a behavior action string as a versioned JSON action command
+    pub fn parse_action(&self, action: &str) -> Result<String> {
+        action_command_json(action)
+    }
+
     /// Get agent emotion vector
     ///
     /// # Arguments
@@ -241,6 +383,23 @@ pub mod ffi {
         }
     }
     
+    /// Create another handle to an agent, limited to a capability: 0
+    /// read-only, 1 interact, 2 full
+    ///
+    /// Returns null if the capability is unknown or exceeds the handle's own.
+    #[no_mangle]
+    pub extern "C" fn oxyde_unreal_share_agent(agent_id: FfiStr, capability: u32) -> *mut c_char {
+        let binding = get_binding();
+        let agent_id_str = agent_id.into_string();
+        let Some(capability) = HandleCapability::from_level(capability) else {
+            return std::ptr::null_mut();
+        };
+        match binding.share_agent(&agent_id_str, capability) {
+            Ok(handle) => string_to_ptr(handle),
+            Err(_) => std::ptr::null_mut(),
+        }
+    }
+
     /// Update an agent with new context data
     #[no_mangle]
     pub extern "C" fn oxyde_unreal_update_agent(agent_id: FfiStr, context_json: FfiStr) -> bool {
@@ -248,7 +407,7 @@ pub mod ffi {
         let agent_id_str = agent_id.into_string();
         let context_json_str = context_json.into_string();
         
-        match binding.get_agent(&agent_id_str) {
+        match binding.agent_handle(&agent_id_str, HandleCapability::Interact, "oxyde_unreal_update_agent") {
             Ok(agent) => {
                 binding.update_agent(&agent, &context_json_str).is_ok()
             },
@@ -263,7 +422,7 @@ pub mod ffi {
         let agent_id_str = agent_id.into_string();
         let input_str = input.into_string();
 
-        match binding.get_agent(&agent_id_str) {
+        match binding.agent_handle(&agent_id_str, HandleCapability::Interact, "oxyde_unreal_process_input") {
             Ok(agent) => {
                 // keep your current async/blocking logic; just convert the final String to char*
                 let rt = tokio::runtime::Runtime::new().ok();
@@ -285,7 +444,7 @@ pub mod ffi {
         let binding = get_binding();
         let agent_id_str = agent_id.into_string();
 
-        match binding.get_agent(&agent_id_str) {
+        match binding.agent_handle(&agent_id_str, HandleCapability::ReadOnly, "oxyde_unreal_get_agent_state") {
             Ok(agent) => {
                 let state_json = format!("{{\"id\":\"{}\",\"name\":\"{}\"}}", agent.id(), agent.name());
                 string_to_ptr(state_json)
@@ -310,7 +469,7 @@ pub mod ffi {
         let binding = get_binding();
         let agent_id_str = agent_id.into_string();
         
-        match binding.get_agent(&agent_id_str) {
+        match binding.agent_handle(&agent_id_str, HandleCapability::ReadOnly, "oxyde_unreal_get_emotion_vector") {
             Ok(agent) => {
                 match binding.get_agent_emotion_vector(&agent) {
                     Ok(emotion_vector) => {
@@ -349,6 +508,84 @@ pub mod ffi {
         }
     }
 
+    // ==================== Scene FFI ====================
+
+    /// Create a scene grouping agents freed together, returning its ID
+    #[no_mangle]
+    pub extern "C" fn oxyde_unreal_create_scene(name: FfiStr) -> *mut c_char {
+        let binding = get_binding();
+        let name_str = name.into_string();
+
+        string_to_ptr(binding.create_scene(&name_str))
+    }
+
+    /// Take another reference to a scene, returning the references held or 0
+    /// if the scene does not exist
+    #[no_mangle]
+    pub extern "C" fn oxyde_unreal_retain_scene(scene_id: FfiStr) -> u32 {
+        let binding = get_binding();
+        let scene_id_str = scene_id.into_string();
+
+        match binding.retain_scene(&scene_id_str) {
+            Ok(references) => references as u32,
+            Err(_) => 0,
+        }
+    }
+
+    /// Register an agent under a scene
+    #[no_mangle]
+    pub extern "C" fn oxyde_unreal_scene_add_agent(scene_id: FfiStr, agent_id: FfiStr) -> bool {
+        let binding = get_binding();
+        let scene_id_str = scene_id.into_string();
+        let agent_id_str = agent_id.into_string();
+
+        binding.add_agent_to_scene(&scene_id_str, &agent_id_str).is_ok()
+    }
+
+    /// Release a reference to a scene; the last release stops and frees the
+    /// scene's agents
+    #[no_mangle]
+    pub extern "C" fn oxyde_unreal_destroy_scene(scene_id: FfiStr) -> bool {
+        let binding = get_binding();
+        let scene_id_str = scene_id.into_string();
+
+        match binding.destroy_scene(&scene_id_str) {
+            Ok(freed) => {
+                log::debug!("Released scene {}, freeing {} agents", scene_id_str, freed);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    // ==================== Action FFI ====================
+
+    /// Take the actions an agent took since the last poll, as a JSON array
+    /// of versioned action commands; empty if the agent does not exist
+    #[no_mangle]
+    pub extern "C" fn oxyde_unreal_poll_actions(agent_id: FfiStr) -> *mut c_char {
+        let binding = get_binding();
+        let agent_id_str = agent_id.into_string();
+
+        match binding.poll_actions(&agent_id_str) {
+            Ok(actions) => string_to_ptr(actions),
+            Err(_) => string_to_ptr("[]".to_string()),
+        }
+    }
+
+    /// Read a behavior action string as a versioned JSON action command;
+    /// null if the action is malformed
+    #[no_mangle]
+    pub extern "C" fn oxyde_unreal_parse_action(action: FfiStr) -> *mut c_char {
+        let binding = get_binding();
+        let action_str = action.into_string();
+
+        match binding.parse_action(&action_str) {
+            Ok(command) => string_to_ptr(command),
+            Err(_) => std::ptr::null_mut(),
+        }
+    }
+
     // ==================== Memory System FFI ====================
 
     /// Add a memory to an agent's memory system
@@ -369,7 +606,7 @@ pub mod ffi {
             None => return false,
         };
         
-        match binding.get_agent(&agent_id_str) {
+        match binding.agent_handle(&agent_id_str, HandleCapability::Interact, "oxyde_unreal_add_memory") {
             Ok(agent) => {
                 let runtime = match tokio::runtime::Runtime::new() {
                     Ok(rt) => rt,
@@ -403,7 +640,7 @@ pub mod ffi {
             None => return false,
         };
         
-        match binding.get_agent(&agent_id_str) {
+        match binding.agent_handle(&agent_id_str, HandleCapability::Interact, "oxyde_unreal_add_emotional_memory") {
             Ok(agent) => {
                 let runtime = match tokio::runtime::Runtime::new() {
                     Ok(rt) => rt,
@@ -425,7 +662,7 @@ pub mod ffi {
         let binding = get_binding();
         let agent_id_str = agent_id.into_string();
         
-        match binding.get_agent(&agent_id_str) {
+        match binding.agent_handle(&agent_id_str, HandleCapability::ReadOnly, "oxyde_unreal_get_memory_count") {
             Ok(agent) => {
                 let runtime = match tokio::runtime::Runtime::new() {
                     Ok(rt) => rt,
@@ -445,7 +682,7 @@ pub mod ffi {
         let binding = get_binding();
         let agent_id_str = agent_id.into_string();
         
-        match binding.get_agent(&agent_id_str) {
+        match binding.agent_handle(&agent_id_str, HandleCapability::Full, "oxyde_unreal_clear_memories") {
             Ok(agent) => {
                 let runtime = match tokio::runtime::Runtime::new() {
                     Ok(rt) => rt,
@@ -474,7 +711,7 @@ pub mod ffi {
             None => return string_to_ptr("[]".to_string()),
         };
         
-        match binding.get_agent(&agent_id_str) {
+        match binding.agent_handle(&agent_id_str, HandleCapability::ReadOnly, "oxyde_unreal_get_memories_by_category") {
             Ok(agent) => {
                 let runtime = match tokio::runtime::Runtime::new() {
                     Ok(rt) => rt,
@@ -501,7 +738,7 @@ pub mod ffi {
         let agent_id_str = agent_id.into_string();
         let query_str = query.into_string();
         
-        match binding.get_agent(&agent_id_str) {
+        match binding.agent_handle(&agent_id_str, HandleCapability::ReadOnly, "oxyde_unreal_retrieve_relevant_memories") {
             Ok(agent) => {
                 let runtime = match tokio::runtime::Runtime::new() {
                     Ok(rt) => rt,
@@ -528,7 +765,7 @@ pub mod ffi {
         let agent_id_str = agent_id.into_string();
         let memory_id_str = memory_id.into_string();
         
-        match binding.get_agent(&agent_id_str) {
+        match binding.agent_handle(&agent_id_str, HandleCapability::Full, "oxyde_unreal_forget_memory") {
             Ok(agent) => {
                 let runtime = match tokio::runtime::Runtime::new() {
                     Ok(rt) => rt,
@@ -557,7 +794,7 @@ pub mod ffi {
             None => return 0,
         };
         
-        match binding.get_agent(&agent_id_str) {
+        match binding.agent_handle(&agent_id_str, HandleCapability::Full, "oxyde_unreal_forget_memories_by_category") {
             Ok(agent) => {
                 let runtime = match tokio::runtime::Runtime::new() {
                     Ok(rt) => rt,
@@ -604,4 +841,26 @@ mod tests {
         assert!(context.contains_key("player_location"));
         assert_eq!(context.get("interaction_distance").unwrap().as_f64().unwrap(), 3.5);
     }
+
+    #[test]
+    fn test_shared_handles_are_limited_to_their_capability() {
+        let binding = UnrealBinding::new();
+        let config = r#"{"agent": {"name": "Guard", "role": "Guard", "backstory": [], "knowledge": []},
+            "memory": {}, "inference": {"use_local": true, "local_model_path": "models/test.bin"}, "behavior": {}}"#;
+        let owner = binding.create_agent_from_json(config).unwrap().id().to_string();
+        let clear = "oxyde_unreal_clear_memories";
+
+        let reader = binding.share_agent(&owner, HandleCapability::ReadOnly).unwrap();
+        assert_eq!(binding.agent_handle(&reader, HandleCapability::ReadOnly, "get_memory_count").unwrap().id().to_string(), owner);
+        assert!(binding.agent_handle(&reader, HandleCapability::Interact, "process_input").is_err());
+        assert!(binding.agent_handle(&reader, HandleCapability::Full, clear).is_err());
+        assert!(binding.agent_handle(&owner, HandleCapability::Full, clear).is_ok());
+
+        // A handle cannot grant more than it has
+        assert!(binding.share_agent(&reader, HandleCapability::Full).is_err());
+        let scripted = binding.share_agent(&owner, HandleCapability::Interact).unwrap();
+        assert!(binding.agent_handle(&scripted, HandleCapability::Interact, "process_input").is_ok());
+        assert!(binding.agent_handle(&scripted, HandleCapability::Full, clear).is_err());
+        assert!(binding.share_agent("missing", HandleCapability::ReadOnly).is_err());
+    }
 }
@@ -2,23 +2,40 @@
 //!
 //! This module provides bindings for integrating Oxyde with WebAssembly
 //! for browser-based games.
+//!
+//! `OxydeWasm.process_input`, `update_agent` and `get_agent_state` return
+//! promises rather than values, since inference runs through the browser's
+//! `fetch` and timers and must not block the page. Code written against the
+//! earlier synchronous API needs to `await` them:
+//!
+//! ```js
+//! // before: const reply = oxyde.process_input(id, "Hello");
+//! const reply = await oxyde.process_input(id, "Hello");
+//! ```
+//!
+//! The browser tests run with
+//! `wasm-pack test --node -- --no-default-features --features wasm-min`.
 
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
-#[cfg(feature = "wasm")]
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
 use wasm_bindgen::prelude::*;
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+use wasm_bindgen_futures::{future_to_promise, js_sys};
 
 use uuid::Uuid;
 
 use crate::agent::{Agent, AgentContext, AgentState};
-use crate::oxyde_game::bindings::{EngineBinding, load_agent_config, parse_context_json};
+use crate::oxyde_game::bindings::{action_command_json, ActionInbox, AgentRegistry, EngineBinding, load_agent_config, parse_context_json};
 use crate::{OxydeError, Result};
 
 /// WebAssembly binding for Oxyde SDK
 pub struct WasmBinding {
     /// Registry of created agents
-    agents: Arc<Mutex<HashMap<String, Arc<Agent>>>>,
+    agents: AgentRegistry,
+    /// Actions agents took, waiting to be polled
+    actions: ActionInbox,
 }
 
 impl WasmBinding {
@@ -26,6 +43,7 @@ impl WasmBinding {
     pub fn new() -> Self {
         Self {
             agents: Arc::new(Mutex::new(HashMap::new())),
+            actions: ActionInbox::new(),
         }
     }
     
@@ -47,6 +65,14 @@ impl WasmBinding {
             })
     }
     
+    /// Get the registry of agents created through this binding
+    ///
+    /// The registry is shared, so agents registered later are visible to
+    /// holders such as the WebSocket session server.
+    pub fn registry(&self) -> AgentRegistry {
+        self.agents.clone()
+    }
+
     /// Register a new agent
     ///
     /// # Arguments
@@ -54,10 +80,27 @@ impl WasmBinding {
     /// * `id` - Agent unique identifier
     /// * `agent` - Agent to register
     pub fn register_agent(&self, id: Uuid, agent: Arc<Agent>) {
+        self.actions.watch(&id.to_string(), &agent);
         let mut agents = self.agents.lock().unwrap();
         agents.insert(id.to_string(), agent);
     }
     
+    /// Take the actions an agent took since the last poll
+    ///
+    /// # Returns
+    ///
+    /// A JSON array of versioned action commands, oldest first, or an error
+    /// if the agent does not exist
+    pub fn poll_actions(&self, agent_id: &str) -> Result<String> {
+        self.get_agent(agent_id)?;
+        Ok(self.actions.drain_json(agent_id))
+    }
+
+    /// Read a behavior action string as a versioned JSON action command
+    pub fn parse_action(&self, action: &str) -> Result<String> {
+        action_command_json(action)
+    }
+
     /// Parse WebAssembly context
     ///
     /// # Arguments
@@ -92,6 +135,17 @@ impl WasmBinding {
     }
 }
 
+/// Run a future to completion on a single-threaded runtime
+///
+/// Browsers have no threads to run a multi-threaded runtime on.
+fn block_on<F: std::future::Future>(future: F) -> Result<F::Output> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()
+        .map_err(|e| OxydeError::BindingError(format!("Failed to create Tokio runtime: {}", e)))?;
+    Ok(runtime.block_on(future))
+}
+
 impl EngineBinding for WasmBinding {
     fn create_agent(&self, config_path: &str) -> Result<Arc<Agent>> {
         let config = load_agent_config(config_path)?;
@@ -124,14 +178,7 @@ impl EngineBinding for WasmBinding {
             let agent_ref = stored_agent.clone();
             drop(agents); // Release the lock
             
-            // Create a runtime for the WASM context
-            let runtime = tokio::runtime::Runtime::new().map_err(|e| {
-                OxydeError::BindingError(format!("Failed to create Tokio runtime: {}", e))
-            })?;
-            
-            runtime.block_on(async {
-                agent_ref.update_context(context).await;
-            });
+            block_on(agent_ref.update_context(context))?;
         }
         
         Ok(())
@@ -139,13 +186,7 @@ impl EngineBinding for WasmBinding {
     
     fn process_input(&self, agent: &Agent, input: &str) -> Result<String> {
         // Process input asynchronously, but block on result for WASM
-        let runtime = tokio::runtime::Runtime::new().map_err(|e| {
-            OxydeError::BindingError(format!("Failed to create Tokio runtime: {}", e))
-        })?;
-        
-        runtime.block_on(async {
-            agent.process_input(input).await
-        })
+        block_on(agent.process_input(input))?
     }
     
     fn name(&self) -> &'static str {
@@ -154,13 +195,26 @@ impl EngineBinding for WasmBinding {
 }
 
 // WASM exports
-#[cfg(feature = "wasm")]
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = console)]
+    fn error(message: &str);
+}
+
+/// Convert an SDK error into a JavaScript error
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+fn js_error(error: OxydeError) -> JsError {
+    JsError::new(&error.to_string())
+}
+
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
 #[wasm_bindgen]
 pub struct OxydeWasm {
     binding: WasmBinding,
 }
 
-#[cfg(feature = "wasm")]
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
 #[wasm_bindgen]
 impl OxydeWasm {
     /// Create a new Oxyde WASM instance
@@ -174,76 +228,71 @@ impl OxydeWasm {
     /// Initialize the Oxyde SDK
     #[wasm_bindgen]
     pub fn init() -> bool {
-        // Set up logging for WASM
-        console_error_panic_hook::set_once();
+        // Report panics on the browser console
+        std::panic::set_hook(Box::new(|info| error(&info.to_string())));
         true
     }
     
     /// Create a new agent from a configuration file
     #[wasm_bindgen]
-    pub fn create_agent(&self, config_path: &str) -> Result<String, JsError> {
-        match self.binding.create_agent(config_path) {
-            Ok(agent) => Ok(agent.id().to_string()),
-            Err(e) => Err(JsError::new(&e.to_string())),
-        }
+    pub fn create_agent(&self, config_path: &str) -> std::result::Result<String, JsError> {
+        let agent = self.binding.create_agent(config_path).map_err(js_error)?;
+        Ok(agent.id().to_string())
     }
 
     /// Create a new agent from a configuration JSON string
     #[wasm_bindgen]
-    pub fn create_agent_from_json(&self, json_config: &str) -> Result<String, JsError> {
-        match self.binding.create_agent_from_json(json_config) {
-            Ok(agent) => Ok(agent.id().to_string()),
-            Err(e) => Err(JsError::new(&e.to_string())),
-        }
+    pub fn create_agent_from_json(&self, json_config: &str) -> std::result::Result<String, JsError> {
+        let agent = self.binding.create_agent_from_json(json_config).map_err(js_error)?;
+        Ok(agent.id().to_string())
     }
     
-    /// Update an agent with new context data
+    /// Update an agent with new context data, resolving once it is applied
     #[wasm_bindgen]
-    pub fn update_agent(&self, agent_id: &str, context_json: &str) -> Result<(), JsError> {
-        match self.binding.get_agent(agent_id) {
-            Ok(agent) => {
-                match self.binding.update_agent(&agent, context_json) {
-                    Ok(_) => Ok(()),
-                    Err(e) => Err(JsError::new(&e.to_string())),
-                }
-            },
-            Err(e) => Err(JsError::new(&e.to_string())),
-        }
+    pub fn update_agent(&self, agent_id: &str, context_json: &str) -> std::result::Result<js_sys::Promise, JsError> {
+        let agent = self.binding.get_agent(agent_id).map_err(js_error)?;
+        let context = self.binding.parse_wasm_context(context_json).map_err(js_error)?;
+        Ok(future_to_promise(async move {
+            agent.update_context(context).await;
+            Ok(JsValue::UNDEFINED)
+        }))
     }
     
-    /// Process input for an agent
+    /// Process input for an agent, resolving to the agent's response
+    ///
+    /// Cloud inference runs through the browser's `fetch`, so the response
+    /// arrives asynchronously instead of blocking the page.
     #[wasm_bindgen]
-    pub fn process_input(&self, agent_id: &str, input: &str) -> Result<String, JsError> {
-        match self.binding.get_agent(agent_id) {
-            Ok(agent) => {
-                match self.binding.process_input(&agent, input) {
-                    Ok(response) => Ok(response),
-                    Err(e) => Err(JsError::new(&e.to_string())),
-                }
-            },
-            Err(e) => Err(JsError::new(&e.to_string())),
-        }
+    pub fn process_input(&self, agent_id: &str, input: String) -> std::result::Result<js_sys::Promise, JsError> {
+        let agent = self.binding.get_agent(agent_id).map_err(js_error)?;
+        Ok(future_to_promise(async move {
+            match agent.process_input(&input).await {
+                Ok(response) => Ok(JsValue::from(response)),
+                Err(e) => Err(js_error(e).into()),
+            }
+        }))
     }
     
-    /// Get agent state
+    /// Get agent state, resolving to its debug representation
     #[wasm_bindgen]
-    pub fn get_agent_state(&self, agent_id: &str) -> Result<String, JsError> {
-        match self.binding.get_agent(agent_id) {
-            Ok(agent) => {
-                // Create a runtime for the WASM context
-                let runtime = match tokio::runtime::Runtime::new() {
-                    Ok(rt) => rt,
-                    Err(e) => return Err(JsError::new(&e.to_string())),
-                };
-                
-                let state = runtime.block_on(async {
-                    self.binding.get_agent_state(&agent).await
-                });
-                
-                Ok(format!("{:?}", state))
-            },
-            Err(e) => Err(JsError::new(&e.to_string())),
-        }
+    pub fn get_agent_state(&self, agent_id: &str) -> std::result::Result<js_sys::Promise, JsError> {
+        let agent = self.binding.get_agent(agent_id).map_err(js_error)?;
+        Ok(future_to_promise(async move {
+            Ok(JsValue::from(format!("{:?}", agent.state().await)))
+        }))
+    }
+
+    /// Take the actions an agent took since the last poll, as a JSON array
+    /// of versioned action commands
+    #[wasm_bindgen]
+    pub fn poll_actions(&self, agent_id: &str) -> std::result::Result<String, JsError> {
+        self.binding.poll_actions(agent_id).map_err(js_error)
+    }
+
+    /// Read a behavior action string as a versioned JSON action command
+    #[wasm_bindgen]
+    pub fn parse_action(&self, action: &str) -> std::result::Result<String, JsError> {
+        self.binding.parse_action(action).map_err(js_error)
     }
 }
 
@@ -268,3 +317,39 @@ mod tests {
         assert_eq!(context.get("playerHealth").unwrap().as_i64().unwrap(), 80);
     }
 }
+
+#[cfg(all(test, feature = "wasm", target_arch = "wasm32"))]
+mod wasm_tests {
+    use wasm_bindgen_futures::JsFuture;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+
+    const CONFIG: &str = r#"{
+        "agent": { "name": "Mira", "role": "innkeeper", "backstory": [], "knowledge": [] },
+        "memory": {},
+        "inference": { "provider": "mock", "mock": { "responses": ["Welcome in!"], "latency_ms": 50 } },
+        "supervisor": { "request_timeout_ms": 1000 }
+    }"#;
+
+    #[wasm_bindgen_test]
+    async fn test_process_input_resolves_without_a_tokio_runtime() {
+        let oxyde = OxydeWasm::new();
+        let id = oxyde.create_agent_from_json(CONFIG).unwrap();
+
+        // The mock's latency and the request timeout both run on browser timers
+        let promise = oxyde.process_input(&id, "Hello".to_string()).unwrap();
+        let response = JsFuture::from(promise).await.unwrap();
+        assert_eq!(response.as_string().as_deref(), Some("Welcome in!"));
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_slow_inference_rejects_at_the_request_timeout() {
+        let oxyde = OxydeWasm::new();
+        let config = CONFIG.replace("\"latency_ms\": 50", "\"latency_ms\": 5000").replace("1000", "20");
+        let id = oxyde.create_agent_from_json(&config).unwrap();
+
+        let promise = oxyde.process_input(&id, "Hello".to_string()).unwrap();
+        assert!(JsFuture::from(promise).await.is_err());
+    }
+}
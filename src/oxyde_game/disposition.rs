@@ -0,0 +1,226 @@
+//! Long-term personality drift
+//!
+//! Emotions swing with every conversation and fade back within minutes; a
+//! character's disposition changes over tens of hours. With disposition
+//! enabled, every emotional memory the agent forms nudges a slow-moving
+//! baseline toward the emotions the agent felt at the time, weighted by the
+//! memory's intensity. A player who keeps betraying the agent leaves it with
+//! permanently lower trust:
+//!
+//! - emotions decay toward the baseline instead of toward neutral
+//! - once a baseline has drifted past `tone_threshold`, the inference prompt
+//!   describes how the character has changed
+//!
+//! ```yaml
+//! disposition:
+//!   enabled: true
+//!   learning_rate: 0.01
+//!   max_shift: 0.5
+//! ```
+//!
+//! Save the state with the game through `Agent::disposition` and restore it
+//! with `Agent::restore_disposition`.
+
+use serde::{Deserialize, Serialize};
+
+use crate::agent::AgentContext;
+use crate::oxyde_game::emotion::{EmotionalState, EMOTION_NAMES};
+use crate::{OxydeError, Result};
+
+/// Context key holding the prompt framing for the agent's disposition
+pub const DISPOSITION_FRAMING_KEY: &str = "disposition_framing";
+
+/// Emotions described in the prompt, with their vector index and the words
+/// for a raised and a lowered baseline
+const TONES: [(usize, &str, &str); 4] = [
+    (0, "cheerful", "gloomy"),
+    (1, "trusting", "wary and slow to trust"),
+    (2, "anxious", "short-tempered"),
+    (7, "eager", "jaded"),
+];
+
+/// Configuration for long-term personality drift
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DispositionConfig {
+    /// Whether emotional memories shift the agent's disposition
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Fraction of the gap to the felt emotions closed by a full-intensity memory
+    #[serde(default = "default_learning_rate")]
+    pub learning_rate: f32,
+
+    /// Largest distance any baseline may drift from neutral (0.0 - 1.0)
+    #[serde(default = "default_max_shift")]
+    pub max_shift: f32,
+
+    /// Memories less intense than this leave the disposition unchanged
+    #[serde(default = "default_min_intensity")]
+    pub min_intensity: f32,
+
+    /// Drift at which a baseline is described in the prompt
+    #[serde(default = "default_tone_threshold")]
+    pub tone_threshold: f32,
+}
+
+fn default_learning_rate() -> f32 {
+    0.01
+}
+
+fn default_max_shift() -> f32 {
+    0.5
+}
+
+fn default_min_intensity() -> f32 {
+    0.2
+}
+
+fn default_tone_threshold() -> f32 {
+    0.15
+}
+
+impl Default for DispositionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            learning_rate: default_learning_rate(),
+            max_shift: default_max_shift(),
+            min_intensity: default_min_intensity(),
+            tone_threshold: default_tone_threshold(),
+        }
+    }
+}
+
+impl DispositionConfig {
+    /// Validate the disposition configuration
+    pub fn validate(&self) -> Result<()> {
+        for (name, value) in [
+            ("learning_rate", self.learning_rate),
+            ("max_shift", self.max_shift),
+            ("min_intensity", self.min_intensity),
+            ("tone_threshold", self.tone_threshold),
+        ] {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(OxydeError::ConfigurationError(format!(
+                    "Disposition {} must be between 0.0 and 1.0, got {}",
+                    name, value
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Slow-changing emotion baselines accumulated from emotional memories
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DispositionState {
+    /// Baseline of each emotion, in emotion vector order
+    pub baseline: [f32; 8],
+
+    /// Number of emotional memories absorbed
+    pub experiences: u64,
+}
+
+impl DispositionState {
+    /// Create a neutral disposition
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Baseline of one emotion, or 0.0 for an unknown name
+    pub fn baseline(&self, emotion: &str) -> f32 {
+        EMOTION_NAMES
+            .iter()
+            .position(|name| *name == emotion)
+            .map_or(0.0, |index| self.baseline[index])
+    }
+
+    /// Nudge the baselines toward the emotions felt when a memory formed
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Disposition configuration
+    /// * `emotions` - Emotions the agent felt when the memory formed
+    /// * `intensity` - Emotional intensity of the memory (0.0 - 1.0)
+    ///
+    /// # Returns
+    ///
+    /// Whether the memory was intense enough to be absorbed
+    pub fn absorb(&mut self, config: &DispositionConfig, emotions: &EmotionalState, intensity: f32) -> bool {
+        if intensity < config.min_intensity {
+            return false;
+        }
+        let rate = config.learning_rate * intensity.clamp(0.0, 1.0);
+        for (baseline, felt) in self.baseline.iter_mut().zip(emotions.as_vector()) {
+            *baseline = (*baseline + rate * (felt - *baseline)).clamp(-config.max_shift, config.max_shift);
+        }
+        self.experiences += 1;
+        true
+    }
+
+    /// Describe how far the character has drifted, if noticeably
+    pub fn tone(&self, config: &DispositionConfig) -> Option<String> {
+        let traits: Vec<&str> = TONES
+            .iter()
+            .filter_map(|(index, raised, lowered)| {
+                let shift = self.baseline[*index];
+                if shift >= config.tone_threshold {
+                    Some(*raised)
+                } else if shift <= -config.tone_threshold {
+                    Some(*lowered)
+                } else {
+                    None
+                }
+            })
+            .collect();
+        if traits.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "Over your many encounters you have grown {}. Let this color your tone.",
+            traits.join(" and ")
+        ))
+    }
+
+    /// Frame the inference prompt with the character's disposition
+    pub fn apply_to_context(&self, config: &DispositionConfig, context: &mut AgentContext) {
+        if let Some(tone) = self.tone(config) {
+            context.insert(DISPOSITION_FRAMING_KEY.to_string(), serde_json::json!(tone));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repeated_betrayal_lowers_trust() {
+        let config = DispositionConfig {
+            enabled: true,
+            learning_rate: 0.1,
+            max_shift: 0.3,
+            ..Default::default()
+        };
+        let mut betrayed = EmotionalState::new();
+        betrayed.update_emotion("trust", -0.9);
+
+        let mut disposition = DispositionState::new();
+        assert!(!disposition.absorb(&config, &betrayed, 0.1));
+        assert_eq!(disposition.tone(&config), None);
+        for _ in 0..30 {
+            disposition.absorb(&config, &betrayed, 1.0);
+        }
+        assert_eq!(disposition.experiences, 30);
+        assert_eq!(disposition.baseline("trust"), -0.3);
+        assert_eq!(disposition.baseline("disgust"), 0.3);
+
+        let mut context = AgentContext::new();
+        disposition.apply_to_context(&config, &mut context);
+        assert!(context[DISPOSITION_FRAMING_KEY].as_str().unwrap().contains("wary and slow to trust"));
+
+        let mut emotions = EmotionalState::with_decay_rate(0.5);
+        emotions.decay_toward(&disposition.baseline);
+        assert_eq!(emotions.trust, -0.15);
+    }
+}
@@ -6,13 +6,33 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::save::Versioned;
+
+/// Context key under which the agent exposes its emotional state to behaviors
+pub const EMOTIONAL_STATE_KEY: &str = "npc_emotions";
+
+/// Names of the primary emotions, in emotion vector order
+pub const EMOTION_NAMES: [&str; 8] = [
+    "joy",
+    "trust",
+    "fear",
+    "surprise",
+    "sadness",
+    "disgust",
+    "anger",
+    "anticipation",
+];
+
 /// Emotional state based on Plutchik's wheel of emotions
 ///
 /// Each emotion is represented as a value between -1.0 and 1.0, where:
 /// - Positive values indicate presence of the emotion
 /// - Negative values indicate presence of the opposite emotion
 /// - 0.0 indicates neutral state
+///
+/// Serialized with a schema version; see [`crate::save`].
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(remote = "Self")]
 pub struct EmotionalState {
     /// Joy (opposite: sadness)
     /// Positive: happiness, elation
@@ -59,6 +79,23 @@ pub struct EmotionalState {
     decay_rate: f32,
 }
 
+impl Versioned for EmotionalState {
+    const KIND: &'static str = "emotional state";
+    const VERSION: u32 = 1;
+}
+
+impl Serialize for EmotionalState {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        crate::save::serialize_versioned(self, EmotionalState::serialize, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for EmotionalState {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        crate::save::deserialize_versioned(deserializer, EmotionalState::deserialize)
+    }
+}
+
 impl EmotionalState {
     /// Create a new emotional state with neutral emotions
     pub fn new() -> Self {
@@ -86,6 +123,11 @@ impl EmotionalState {
         state
     }
 
+    /// Rate at which emotions decay (0.0 - 1.0)
+    pub fn decay_rate(&self) -> f32 {
+        self.decay_rate
+    }
+
     /// Calculate overall emotional valence (positive/negative)
     ///
     /// Returns a value between -1.0 (very negative) and 1.0 (very positive)
@@ -146,6 +188,28 @@ impl EmotionalState {
         self.anticipation *= 1.0 - self.decay_rate;
     }
 
+    /// Apply time-based decay toward baseline emotions instead of neutral
+    ///
+    /// # Arguments
+    ///
+    /// * `baseline` - Resting value of each emotion, in emotion vector order
+    pub fn decay_toward(&mut self, baseline: &[f32; 8]) {
+        let rate = self.decay_rate;
+        let emotions = [
+            &mut self.joy,
+            &mut self.trust,
+            &mut self.fear,
+            &mut self.surprise,
+            &mut self.sadness,
+            &mut self.disgust,
+            &mut self.anger,
+            &mut self.anticipation,
+        ];
+        for (value, rest) in emotions.into_iter().zip(baseline) {
+            *value += rate * (rest - *value);
+        }
+    }
+
     /// Update a specific emotion
     ///
     /// # Arguments
@@ -219,6 +283,20 @@ impl EmotionalState {
         self.anticipation = 0.0;
     }
 
+    /// Set every emotion from a vector ordered as in [`EmotionalState::as_vector`]
+    pub fn set_vector(&mut self, vector: [f32; 8]) {
+        [
+            self.joy,
+            self.trust,
+            self.fear,
+            self.surprise,
+            self.sadness,
+            self.disgust,
+            self.anger,
+            self.anticipation,
+        ] = vector;
+    }
+
     /// Get the emotion vector as a float array
     ///
     /// This returns the full 8D emotion vector used by engine bindings:
@@ -0,0 +1,261 @@
+//! Streaming emotion changes to engines
+//!
+//! Engines animating moods continuously should not fetch the full emotion
+//! vector every frame. They can either subscribe with
+//! `Agent::on_emotion_change`, which calls back when an emotion has moved by
+//! at least a threshold since the subscriber last heard of it, or poll
+//! `Agent::emotion_changes_since` with the sequence number of their previous
+//! poll to get only the emotions changed since.
+//!
+//! ```ignore
+//! let mut seen = 0;
+//! loop {
+//!     let change = agent.emotion_changes_since(seen);
+//!     for (emotion, value) in &change.emotions {
+//!         animator.set_blend(emotion, *value);
+//!     }
+//!     seen = change.sequence;
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use serde::{Deserialize, Serialize};
+
+/// Emotions that changed, with their current values
+///
+/// Emotions are the dimensions of the agent's affect model: Plutchik's eight
+/// emotions by default, or pleasure, arousal and dominance with the PAD model.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EmotionChange {
+    /// Sequence number of the latest change; pass it to the next poll
+    pub sequence: u64,
+
+    /// Every dimension of the affect model, in the model's order
+    pub dimensions: Vec<String>,
+
+    /// Current value of each changed emotion, by name
+    pub emotions: BTreeMap<String, f32>,
+}
+
+impl EmotionChange {
+    /// Whether no emotion changed
+    pub fn is_empty(&self) -> bool {
+        self.emotions.is_empty()
+    }
+
+    /// Changed emotions as bits in the order of [`EmotionChange::dimensions`],
+    /// the first dimension being bit 0
+    pub fn mask(&self) -> u32 {
+        self.dimensions
+            .iter()
+            .take(u32::BITS as usize)
+            .enumerate()
+            .filter(|(_, name)| self.emotions.contains_key(*name))
+            .fold(0, |mask, (index, _)| mask | (1 << index))
+    }
+}
+
+/// Callback for emotion changes
+pub type EmotionChangeCallback = Arc<dyn Fn(&EmotionChange) + Send + Sync>;
+
+struct Subscriber {
+    threshold: f32,
+    reported: BTreeMap<String, f32>,
+    callback: EmotionChangeCallback,
+}
+
+struct StreamState {
+    sequence: u64,
+    ticket: u64,
+    dimensions: Vec<(String, f32)>,
+    changed_at: Vec<u64>,
+    subscribers: Vec<Subscriber>,
+}
+
+/// Sequence-numbered record of an agent's emotion changes
+pub(crate) struct EmotionStream {
+    tickets: AtomicU64,
+    state: Mutex<StreamState>,
+}
+
+impl std::fmt::Debug for EmotionStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.lock();
+        f.debug_struct("EmotionStream")
+            .field("sequence", &state.sequence)
+            .field("subscribers", &state.subscribers.len())
+            .finish()
+    }
+}
+
+impl EmotionStream {
+    /// Start recording from the affect model's dimensions
+    pub(crate) fn new(dimensions: Vec<(String, f32)>) -> Self {
+        Self {
+            tickets: AtomicU64::new(0),
+            state: Mutex::new(StreamState {
+                sequence: 0,
+                ticket: 0,
+                changed_at: vec![0; dimensions.len()],
+                dimensions,
+                subscribers: Vec::new(),
+            }),
+        }
+    }
+
+    /// Call `callback` when an emotion moves by at least `threshold` from
+    /// the value last reported to it
+    pub(crate) fn subscribe(&self, threshold: f32, callback: EmotionChangeCallback) {
+        let mut state = self.lock();
+        let reported = state.dimensions.iter().cloned().collect();
+        state.subscribers.push(Subscriber {
+            threshold: threshold.abs(),
+            reported,
+            callback,
+        });
+    }
+
+    /// Take a ticket ordering a change, while the emotions are still locked
+    ///
+    /// Changes are recorded after the emotions are unlocked, so a later
+    /// change may be recorded first; the ticket lets the stream drop the
+    /// earlier one.
+    pub(crate) fn ticket(&self) -> u64 {
+        self.tickets.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    /// Record the affect model's dimensions after a change and notify
+    /// subscribers
+    ///
+    /// Callbacks run after the stream is unlocked, on the calling thread.
+    pub(crate) fn record(&self, ticket: u64, dimensions: Vec<(String, f32)>) {
+        let notifications = {
+            let mut guard = self.lock();
+            let state = &mut *guard;
+            if ticket < state.ticket {
+                return;
+            }
+            state.ticket = ticket;
+            if state.dimensions == dimensions {
+                return;
+            }
+            state.sequence += 1;
+            let sequence = state.sequence;
+            let same_model = state.dimensions.len() == dimensions.len()
+                && state.dimensions.iter().zip(&dimensions).all(|(old, new)| old.0 == new.0);
+            if !same_model {
+                state.changed_at = vec![sequence; dimensions.len()];
+            }
+            for (index, (old, new)) in state.dimensions.iter().zip(&dimensions).enumerate() {
+                if same_model && old.1 != new.1 {
+                    state.changed_at[index] = sequence;
+                }
+            }
+            state.dimensions = dimensions;
+
+            let names: Vec<String> = state.dimensions.iter().map(|(name, _)| name.clone()).collect();
+            let mut notifications = Vec::new();
+            for subscriber in &mut state.subscribers {
+                let mut change = EmotionChange {
+                    sequence,
+                    dimensions: names.clone(),
+                    emotions: BTreeMap::new(),
+                };
+                for (name, value) in &state.dimensions {
+                    let reported = subscriber.reported.get(name).copied().unwrap_or_default();
+                    let moved = (value - reported).abs();
+                    if moved > 0.0 && moved >= subscriber.threshold {
+                        subscriber.reported.insert(name.clone(), *value);
+                        change.emotions.insert(name.clone(), *value);
+                    }
+                }
+                if !change.is_empty() {
+                    notifications.push((subscriber.callback.clone(), change));
+                }
+            }
+            notifications
+        };
+        for (callback, change) in notifications {
+            callback(&change);
+        }
+    }
+
+    /// Emotions changed after a sequence number, with the latest sequence
+    pub(crate) fn changes_since(&self, sequence: u64) -> EmotionChange {
+        let state = self.lock();
+        EmotionChange {
+            sequence: state.sequence,
+            dimensions: state.dimensions.iter().map(|(name, _)| name.clone()).collect(),
+            emotions: state
+                .dimensions
+                .iter()
+                .zip(&state.changed_at)
+                .filter(|(_, changed_at)| **changed_at > sequence)
+                .map(|((name, value), _)| (name.clone(), *value))
+                .collect(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, StreamState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oxyde_game::emotion::EMOTION_NAMES;
+
+    fn plutchik(values: [f32; 8]) -> Vec<(String, f32)> {
+        EMOTION_NAMES.iter().map(|name| name.to_string()).zip(values).collect()
+    }
+
+    #[test]
+    fn test_subscribers_and_polls_see_only_changes() {
+        let stream = EmotionStream::new(plutchik([0.0; 8]));
+        let heard = Arc::new(Mutex::new(Vec::new()));
+        let sink = heard.clone();
+        stream.subscribe(0.2, Arc::new(move |change: &EmotionChange| sink.lock().unwrap().push(change.clone())));
+
+        stream.record(stream.ticket(), plutchik([0.1, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]));
+        assert!(heard.lock().unwrap().is_empty());
+        stream.record(stream.ticket(), plutchik([0.25, 0.0, 0.5, 0.0, 0.0, 0.0, 0.0, 0.0]));
+        stream.record(stream.ticket(), plutchik([0.25, 0.0, 0.5, 0.0, 0.0, 0.0, 0.0, 0.0]));
+
+        let heard = heard.lock().unwrap();
+        assert_eq!(heard.len(), 1);
+        assert_eq!(heard[0].sequence, 2);
+        assert_eq!(heard[0].emotions, BTreeMap::from([("fear".to_string(), 0.5), ("joy".to_string(), 0.25)]));
+        assert_eq!(heard[0].mask(), 0b101);
+
+        let first = stream.changes_since(0);
+        assert_eq!((first.sequence, first.emotions.len()), (2, 2));
+        stream.record(stream.ticket(), plutchik([0.25, 0.0, 0.4, 0.0, 0.0, 0.0, 0.0, 0.0]));
+        let next = stream.changes_since(first.sequence);
+        assert_eq!(next.emotions, BTreeMap::from([("fear".to_string(), 0.4)]));
+        assert!(stream.changes_since(next.sequence).is_empty());
+    }
+
+    #[test]
+    fn test_stale_changes_and_model_swaps() {
+        let stream = EmotionStream::new(plutchik([0.0; 8]));
+
+        // A change recorded after a later one is dropped
+        let earlier = stream.ticket();
+        let later = stream.ticket();
+        stream.record(later, plutchik([0.5, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]));
+        stream.record(earlier, plutchik([0.2, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]));
+        assert_eq!(stream.changes_since(0).emotions["joy"], 0.5);
+
+        // Another affect model reports its own dimensions, all of them new
+        let pad = vec![("pleasure".to_string(), 0.3), ("arousal".to_string(), 0.0), ("dominance".to_string(), 0.1)];
+        stream.record(stream.ticket(), pad);
+        let change = stream.changes_since(1);
+        assert_eq!(change.dimensions, ["pleasure", "arousal", "dominance"]);
+        assert_eq!(change.emotions.len(), 3);
+        assert_eq!(change.mask(), 0b111);
+    }
+}
@@ -2,13 +2,30 @@
 //!
 //! This module provides functionality for understanding player intent from
 //! their actions, chat messages, and other interactions.
+//!
+//! Messages often carry more than one intent, such as "hello, do you sell
+//! potions?". An [`Intent`] holds the strongest as its type and the others,
+//! ranked by weight, as secondary intents. Behaviors only see the primary
+//! intent unless `intents.match_secondary` is set, and `intents.combo` lets a
+//! greeting open the reply to the rest of the message:
+//!
+//! ```yaml
+//! intents:
+//!   match_secondary: true
+//!   secondary_weight: 0.5
+//!   combo: true
+//! ```
 
 use std::collections::HashSet;
 
 use serde::{Deserialize, Serialize};
 
+use crate::agent::AgentContext;
 use crate::{OxydeError, Result};
 
+/// Context key under which the agent exposes the intent of the current input
+pub const INPUT_INTENT_KEY: &str = "input_intent";
+
 /// Type of player intent
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -76,6 +93,20 @@ impl IntentType {
     }
 }
 
+/// One of the intents a message carries, with its weight
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WeightedIntent {
+    /// Type of intent
+    pub intent_type: IntentType,
+
+    /// How strongly the message carries the intent (0.0 - 1.0)
+    pub weight: f64,
+
+    /// Name of the configured intent, if it was trained
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
 impl std::fmt::Display for IntentType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.as_str())
@@ -96,6 +127,14 @@ pub struct Intent {
 
     /// Keywords extracted from the input
     pub keywords: Vec<String>,
+
+    /// Name of the configured intent the input matched, if any
+    #[serde(default)]
+    pub label: Option<String>,
+
+    /// Other intents the input carries, strongest first
+    #[serde(default)]
+    pub secondary: Vec<WeightedIntent>,
 }
 
 impl Intent {
@@ -122,8 +161,61 @@ impl Intent {
             confidence: confidence.clamp(0.0, 1.0),
             raw_input: raw_input.to_string(),
             keywords,
+            label: None,
+            secondary: Vec::new(),
         }
     }
+
+    /// Expose the intent type, label and secondary intents to inference routing
+    pub fn apply_to_context(&self, context: &mut AgentContext) {
+        let secondary: Vec<_> = self
+            .secondary
+            .iter()
+            .map(|intent| serde_json::json!({ "type": intent.intent_type.as_str(), "label": intent.label, "weight": intent.weight }))
+            .collect();
+        context.insert(
+            INPUT_INTENT_KEY.to_string(),
+            serde_json::json!({ "type": self.intent_type.as_str(), "label": self.label, "secondary": secondary }),
+        );
+    }
+
+    /// Every intent the input carries, the primary intent first
+    pub fn ranked(&self) -> Vec<WeightedIntent> {
+        let primary = WeightedIntent {
+            intent_type: self.intent_type,
+            weight: self.confidence,
+            label: self.label.clone(),
+        };
+        std::iter::once(primary).chain(self.secondary.iter().cloned()).collect()
+    }
+
+    /// The same input seen as one of its secondary intents, for behaviors
+    /// matching that intent
+    pub fn focus(&self, secondary: &WeightedIntent) -> Self {
+        Self {
+            intent_type: secondary.intent_type,
+            confidence: secondary.weight,
+            raw_input: self.raw_input.clone(),
+            keywords: self.keywords.clone(),
+            label: secondary.label.clone(),
+            secondary: Vec::new(),
+        }
+    }
+
+    /// Add a secondary intent, keeping the strongest weight of each intent
+    /// and the list ranked
+    fn add_secondary(&mut self, intent: WeightedIntent) {
+        let same = |other: &WeightedIntent| other.intent_type == intent.intent_type && other.label == intent.label;
+        if self.intent_type == intent.intent_type && self.label == intent.label {
+            return;
+        }
+        match self.secondary.iter_mut().find(|other| same(other)) {
+            Some(existing) => existing.weight = existing.weight.max(intent.weight),
+            None => self.secondary.push(intent),
+        }
+        self.secondary
+            .sort_by(|a, b| b.weight.partial_cmp(&a.weight).unwrap_or(std::cmp::Ordering::Equal));
+    }
     
     /// Create a proximity intent
     ///
@@ -156,23 +248,34 @@ impl Intent {
         // Extract keywords from the text
         let keywords = Self::extract_keywords(text);
 
-        // Determine intent type
-        let intent_type = if text.ends_with("?") {
-            IntentType::Question
-        } else if Self::is_greeting(text) {
-            IntentType::Greeting
-        } else if Self::is_command(text) {
-            IntentType::Command
-        } else {
-            IntentType::Chat
-        };
+        // Every intent the text shows signs of, in order of precedence
+        let mut detected = Vec::new();
+        if text.ends_with('?') {
+            detected.push((IntentType::Question, 0.7));
+        }
+        if Self::is_greeting(text) {
+            // A greeting opening the message weighs more than one in passing
+            let opening = text.split([',', '!', '.', '?']).next().unwrap_or("");
+            let opens = Self::is_greeting(opening);
+            detected.push((IntentType::Greeting, if opens { 0.6 } else { 0.4 }));
+        }
+        if Self::is_command(text) {
+            detected.push((IntentType::Command, 0.6));
+        }
 
-        Self::new(
+        // The first detected intent is primary, the others secondary
+        let mut detected = detected.into_iter();
+        let intent_type = detected.next().map_or(IntentType::Chat, |(intent_type, _)| intent_type);
+        let mut intent = Self::new(
             intent_type,
             0.8, // Confidence score
             text,
             keywords,
-        )
+        );
+        for (intent_type, weight) in detected {
+            intent.add_secondary(WeightedIntent { intent_type, weight, label: None });
+        }
+        intent
     }
     
     /// Analyze player input to determine intent
@@ -193,6 +296,64 @@ impl Intent {
         // In a real implementation, this would use more sophisticated NLP
         Ok(Self::from_chat(input))
     }
+
+    /// Analyze player input, preferring intents trained from example phrases
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - Raw player input
+    /// * `matcher` - Intents compiled from the agent configuration
+    ///
+    /// # Returns
+    ///
+    /// The best trained intent above the matcher's threshold, or the
+    /// rule-based intent otherwise; the other trained intents above the
+    /// threshold, and the rule-based intents, are secondary
+    pub async fn analyze_with(input: &str, matcher: &IntentMatcher) -> Result<Self> {
+        let rule_based = Self::analyze(input).await?;
+        let mut matches = matcher.classify_all(input).into_iter();
+        let Some(best) = matches.next() else {
+            return Ok(rule_based);
+        };
+
+        let mut intent = Self {
+            intent_type: IntentType::from_str(&best.name),
+            confidence: best.score.clamp(0.0, 1.0),
+            label: Some(best.name),
+            secondary: Vec::new(),
+            ..rule_based.clone()
+        };
+        for matched in matches {
+            intent.add_secondary(WeightedIntent {
+                intent_type: IntentType::from_str(&matched.name),
+                weight: matched.score.clamp(0.0, 1.0),
+                label: Some(matched.name),
+            });
+        }
+        for rule in rule_based.ranked() {
+            // A trained intent of the same type replaces the rule-based one
+            if intent.ranked().iter().all(|trained| trained.intent_type != rule.intent_type) {
+                intent.add_secondary(rule);
+            }
+        }
+        Ok(intent)
+    }
+
+    /// Check whether the intent has a name, either its configured label or its type
+    pub fn is(&self, name: &str) -> bool {
+        self.label.as_deref().is_some_and(|label| label.eq_ignore_ascii_case(name))
+            || self.intent_type.as_str().eq_ignore_ascii_case(name)
+    }
+
+    /// Check whether the input carries a named intent at all, primary or
+    /// secondary with at least `min_weight`
+    pub fn carries(&self, name: &str, min_weight: f64) -> bool {
+        self.is(name)
+            || self.secondary.iter().filter(|intent| intent.weight >= min_weight).any(|intent| {
+                intent.label.as_deref().is_some_and(|label| label.eq_ignore_ascii_case(name))
+                    || intent.intent_type.as_str().eq_ignore_ascii_case(name)
+            })
+    }
     
     /// Extract keywords from text
     ///
@@ -282,6 +443,240 @@ impl Intent {
     }
 }
 
+/// An intent defined by example phrases
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntentDefinition {
+    /// Intent name; built-in names such as "greeting" map to their
+    /// [`IntentType`], any other name is a custom intent
+    pub name: String,
+
+    /// Example phrases, such as "buy", "purchase" or "how much for"
+    pub examples: Vec<String>,
+}
+
+/// Intents trained from example phrases
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntentConfig {
+    /// Intent definitions
+    #[serde(default)]
+    pub definitions: Vec<IntentDefinition>,
+
+    /// Minimum similarity (0.0 - 1.0) for an input to match an example
+    #[serde(default = "default_intent_threshold")]
+    pub threshold: f64,
+
+    /// Whether behaviors not matching the primary intent may match a
+    /// secondary one
+    #[serde(default)]
+    pub match_secondary: bool,
+
+    /// Minimum weight (0.0 - 1.0) of a secondary intent behaviors may match
+    #[serde(default = "default_secondary_weight")]
+    pub secondary_weight: f64,
+
+    /// Whether a greeting opens the reply to the rest of a message carrying
+    /// other intents, instead of being the whole reply
+    #[serde(default)]
+    pub combo: bool,
+}
+
+fn default_intent_threshold() -> f64 {
+    0.6
+}
+
+fn default_secondary_weight() -> f64 {
+    0.4
+}
+
+impl Default for IntentConfig {
+    fn default() -> Self {
+        Self {
+            definitions: Vec::new(),
+            threshold: default_intent_threshold(),
+            match_secondary: false,
+            secondary_weight: default_secondary_weight(),
+            combo: false,
+        }
+    }
+}
+
+impl IntentConfig {
+    /// Validate the intent definitions
+    pub fn validate(&self) -> Result<()> {
+        if !(0.0..=1.0).contains(&self.threshold) {
+            return Err(OxydeError::ConfigurationError(format!(
+                "Intent threshold must be between 0.0 and 1.0, got {}",
+                self.threshold
+            )));
+        }
+        if !(0.0..=1.0).contains(&self.secondary_weight) {
+            return Err(OxydeError::ConfigurationError(format!(
+                "Intent secondary_weight must be between 0.0 and 1.0, got {}",
+                self.secondary_weight
+            )));
+        }
+        for definition in &self.definitions {
+            if definition.name.trim().is_empty() {
+                return Err(OxydeError::ConfigurationError("Intent name cannot be empty".to_string()));
+            }
+            if definition.examples.iter().all(|example| tokenize(example).is_empty()) {
+                return Err(OxydeError::ConfigurationError(format!(
+                    "Intent '{}' needs at least one example phrase",
+                    definition.name
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A configured intent an input matched
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntentMatch {
+    /// Intent name
+    pub name: String,
+
+    /// Similarity to the closest example (0.0 - 1.0)
+    pub score: f64,
+
+    /// The closest example phrase
+    pub example: String,
+}
+
+/// Fuzzy matcher for intents defined by example phrases
+///
+/// Examples are tokenized once when the matcher is compiled. An input's
+/// similarity to an example is mostly how much of the example it covers,
+/// with a small bonus for how much of the input the example explains, so
+/// "how much for" matches "how much for that sword?". Tokens match when
+/// they share a stem or differ by a single typo.
+#[derive(Debug, Clone, Default)]
+pub struct IntentMatcher {
+    examples: Vec<(String, String, Vec<String>)>,
+    threshold: f64,
+}
+
+/// Weight of example coverage in the similarity score; the rest is input coverage
+const EXAMPLE_COVERAGE_WEIGHT: f64 = 0.8;
+
+impl IntentMatcher {
+    /// Compile the configured intent definitions
+    pub fn compile(config: &IntentConfig) -> Self {
+        let examples = config
+            .definitions
+            .iter()
+            .flat_map(|definition| {
+                definition.examples.iter().filter_map(|example| {
+                    let tokens = tokenize(example);
+                    (!tokens.is_empty()).then(|| (definition.name.clone(), example.clone(), tokens))
+                })
+            })
+            .collect();
+        Self {
+            examples,
+            threshold: config.threshold,
+        }
+    }
+
+    /// Whether any intents are defined
+    pub fn is_empty(&self) -> bool {
+        self.examples.is_empty()
+    }
+
+    /// Find the configured intent closest to an input
+    ///
+    /// # Returns
+    ///
+    /// The best match at or above the threshold, or `None`
+    pub fn classify(&self, input: &str) -> Option<IntentMatch> {
+        self.classify_all(input).into_iter().next()
+    }
+
+    /// Find every configured intent close to an input
+    ///
+    /// # Returns
+    ///
+    /// The best match of each intent at or above the threshold, best first
+    pub fn classify_all(&self, input: &str) -> Vec<IntentMatch> {
+        let input_tokens = tokenize(input);
+        if input_tokens.is_empty() {
+            return Vec::new();
+        }
+
+        let mut best: Vec<IntentMatch> = Vec::new();
+        // Later examples win ties, as the best match always has
+        let scored = self
+            .examples
+            .iter()
+            .rev()
+            .map(|(name, example, tokens)| {
+                let covered = tokens.iter().filter(|t| input_tokens.iter().any(|i| tokens_match(t, i))).count();
+                let explained = input_tokens.iter().filter(|i| tokens.iter().any(|t| tokens_match(t, i))).count();
+                let score = EXAMPLE_COVERAGE_WEIGHT * covered as f64 / tokens.len() as f64
+                    + (1.0 - EXAMPLE_COVERAGE_WEIGHT) * explained as f64 / input_tokens.len() as f64;
+                IntentMatch {
+                    name: name.clone(),
+                    score,
+                    example: example.clone(),
+                }
+            })
+            .filter(|m| m.score >= self.threshold);
+        for matched in scored {
+            match best.iter_mut().find(|other| other.name == matched.name) {
+                Some(other) if other.score < matched.score => *other = matched,
+                Some(_) => {}
+                None => best.push(matched),
+            }
+        }
+        best.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        best
+    }
+}
+
+/// Lowercase words with punctuation removed
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .map(|word| word.trim_matches('\'').to_lowercase())
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// Strip common English suffixes so "buying" and "buys" match "buy"
+fn stem(word: &str) -> &str {
+    for suffix in ["ing", "ed", "es", "s"] {
+        if let Some(stem) = word.strip_suffix(suffix) {
+            if stem.len() >= 3 {
+                return stem;
+            }
+        }
+    }
+    word
+}
+
+/// Whether two tokens match by stem or, for longer words, within one edit
+fn tokens_match(a: &str, b: &str) -> bool {
+    if a == b || stem(a) == stem(b) {
+        return true;
+    }
+    a.len() >= 5 && b.len() >= 5 && within_one_edit(a, b)
+}
+
+fn within_one_edit(a: &str, b: &str) -> bool {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let (short, long) = if a.len() <= b.len() { (&a, &b) } else { (&b, &a) };
+    if long.len() - short.len() > 1 {
+        return false;
+    }
+    let prefix = short.iter().zip(long.iter()).take_while(|(x, y)| x == y).count();
+    if short.len() == long.len() {
+        // One substitution
+        short[prefix + 1..] == long[prefix + 1..]
+    } else {
+        // One insertion
+        short[prefix..] == long[prefix + 1..]
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -301,6 +696,38 @@ mod tests {
         assert_eq!(chat.intent_type, IntentType::Chat);
     }
     
+    #[tokio::test]
+    async fn test_messages_carry_ranked_intents() {
+        let intent = Intent::from_chat("Hello, do you sell potions?");
+        assert_eq!(intent.intent_type, IntentType::Question);
+        assert_eq!(
+            intent.secondary,
+            vec![WeightedIntent { intent_type: IntentType::Greeting, weight: 0.6, label: None }]
+        );
+        assert!(intent.carries("greeting", 0.5));
+        assert!(!intent.carries("greeting", 0.7));
+        assert_eq!(intent.focus(&intent.secondary[0]).intent_type, IntentType::Greeting);
+
+        let matcher = IntentMatcher::compile(&IntentConfig {
+            definitions: vec![
+                IntentDefinition { name: "trade".to_string(), examples: vec!["sell potions".to_string()] },
+                IntentDefinition { name: "smalltalk".to_string(), examples: vec!["hello do you know".to_string()] },
+            ],
+            ..Default::default()
+        });
+        let intent = Intent::analyze_with("Hello, do you sell potions?", &matcher).await.unwrap();
+        let ranked: Vec<_> = intent.ranked().into_iter().map(|i| (i.intent_type, i.label)).collect();
+        assert_eq!(
+            ranked,
+            vec![
+                (IntentType::Custom, Some("trade".to_string())),
+                (IntentType::Question, None),
+                (IntentType::Custom, Some("smalltalk".to_string())),
+                (IntentType::Greeting, None),
+            ]
+        );
+    }
+
     #[test]
     fn test_keyword_extraction() {
         let keywords = Intent::extract_keywords("What is the capital of France?");
@@ -308,4 +735,38 @@ mod tests {
         assert!(keywords.contains(&"france".to_string()));
         assert!(!keywords.contains(&"is".to_string())); // Stopword should be filtered
     }
+
+    #[tokio::test]
+    async fn test_trained_intents() {
+        let config = IntentConfig {
+            definitions: vec![
+                IntentDefinition {
+                    name: "trade".to_string(),
+                    examples: vec!["buy".to_string(), "purchase".to_string(), "how much for…".to_string()],
+                },
+                IntentDefinition {
+                    name: "greeting".to_string(),
+                    examples: vec!["well met".to_string()],
+                },
+            ],
+            ..Default::default()
+        };
+        config.validate().unwrap();
+        let matcher = IntentMatcher::compile(&config);
+
+        let intent = Intent::analyze_with("How much for that sword?", &matcher).await.unwrap();
+        assert_eq!(intent.label.as_deref(), Some("trade"));
+        assert_eq!(intent.intent_type, IntentType::Custom);
+        assert!(intent.is("Trade"));
+
+        let intent = Intent::analyze_with("I'd like to purchse some arrows", &matcher).await.unwrap();
+        assert!(intent.is("trade"));
+
+        let intent = Intent::analyze_with("Well met, traveler", &matcher).await.unwrap();
+        assert_eq!(intent.intent_type, IntentType::Greeting);
+
+        let intent = Intent::analyze_with("What is your name?", &matcher).await.unwrap();
+        assert_eq!(intent.intent_type, IntentType::Question);
+        assert_eq!(intent.label, None);
+    }
 }
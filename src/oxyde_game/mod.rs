@@ -1,13 +1,23 @@
 //! Game-specific agent utilities for the Oxyde SDK
 //!
 //! This module provides game-specific functionality for integrating Oxyde agents
-//! into games, including behaviors, intent understanding, and engine bindings.
+//! into games, including behaviors, intent understanding, affect models, and
+//! engine bindings.
 
 // Local modules
+pub mod affect;
 pub mod behavior;
+pub mod disposition;
 pub mod emotion;
+pub mod emotion_stream;
 pub mod intent;
+pub mod modifier;
 pub mod bindings;
+pub mod persuasion;
+pub mod reengagement;
+pub mod reputation;
+pub mod schedule;
+pub mod topic;
 
 /// Game-specific utilities and extensions
 pub mod utils {
@@ -0,0 +1,177 @@
+//! Sentiment-driven modifiers for game systems
+//!
+//! An NPC's feelings can show up in the game's numbers: a shopkeeper who
+//! likes the player haggles further, and one who dislikes them charges more.
+//! A [`ModifierProvider`] turns a [`Sentiment`], the relationship with the
+//! player and the agent's emotions, into named modifiers. Each agent has one
+//! provider, built from the `modifiers` section of its configuration:
+//!
+//! ```yaml
+//! modifiers:
+//!   max_discount: 0.3
+//!   max_markup: 0.1
+//! ```
+//!
+//! Its `TradingBehavior`s price their goods with it, and game code can ask
+//! the agent for a modifier with `Agent::modifier` or over FFI with
+//! `oxyde_agent_modifier`, so shop prices and engine-side numbers agree.
+//!
+//! The built-in [`SentimentModifiers`] provides:
+//!
+//! - [`PRICE_MODIFIER`]: multiplier on the prices the player pays, up to
+//!   `1 + max_markup` for a disliked player in a bad mood
+//! - [`HAGGLE_MODIFIER`]: fraction off the price the NPC will come down to,
+//!   up to `max_discount`, and 0 when the NPC is angry
+
+use std::sync::{Arc, RwLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::agent::AgentContext;
+use crate::context::PLAYER_RELATIONSHIP_KEY;
+use crate::oxyde_game::emotion::{EmotionalState, EMOTIONAL_STATE_KEY};
+
+/// Multiplier on the prices the player pays
+pub const PRICE_MODIFIER: &str = "price";
+
+/// Fraction off the price the NPC accepts when haggling
+pub const HAGGLE_MODIFIER: &str = "haggle";
+
+/// Anger above which the built-in provider refuses to haggle
+const HAGGLE_ANGER_LIMIT: f32 = 0.5;
+
+/// What an NPC feels about the player it is dealing with
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Sentiment {
+    /// Relationship with the player (-1.0 to 1.0)
+    pub relationship: f32,
+
+    /// The NPC's current emotions
+    pub emotions: EmotionalState,
+}
+
+impl Sentiment {
+    /// Read the relationship and emotions from context
+    ///
+    /// Missing keys read as a neutral relationship and neutral emotions.
+    pub fn from_context(context: &AgentContext) -> Self {
+        let relationship = context
+            .get(PLAYER_RELATIONSHIP_KEY)
+            .and_then(|v| v.as_f64())
+            .unwrap_or(0.0) as f32;
+        let emotions = context
+            .get(EMOTIONAL_STATE_KEY)
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .unwrap_or_default();
+        Self {
+            relationship: relationship.clamp(-1.0, 1.0),
+            emotions,
+        }
+    }
+
+    /// Overall goodwill toward the player (-1.0 to 1.0)
+    ///
+    /// Mostly the relationship, then trust and overall mood.
+    pub fn goodwill(&self) -> f32 {
+        (0.5 * self.relationship + 0.3 * self.emotions.trust + 0.2 * self.emotions.valence()).clamp(-1.0, 1.0)
+    }
+}
+
+/// Computes named modifiers from an NPC's sentiment
+pub trait ModifierProvider: Send + Sync + std::fmt::Debug {
+    /// Value of a modifier, or `None` if this provider does not know it
+    fn modifier(&self, name: &str, sentiment: &Sentiment) -> Option<f32>;
+}
+
+/// Built-in provider of price and haggling modifiers
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SentimentModifiers {
+    /// Largest haggling discount, given to a trusted friend in a good mood (0.0 - 1.0)
+    pub max_discount: f32,
+
+    /// Largest markup, charged to a disliked player in a bad mood
+    pub max_markup: f32,
+}
+
+impl SentimentModifiers {
+    /// Create a provider with the given discount and markup limits
+    pub fn new(max_discount: f32, max_markup: f32) -> Self {
+        Self {
+            max_discount: max_discount.clamp(0.0, 1.0),
+            max_markup: max_markup.max(0.0),
+        }
+    }
+}
+
+impl Default for SentimentModifiers {
+    /// Up to 20% off for friends and 20% extra for enemies
+    fn default() -> Self {
+        Self::new(0.2, 0.2)
+    }
+}
+
+impl ModifierProvider for SentimentModifiers {
+    fn modifier(&self, name: &str, sentiment: &Sentiment) -> Option<f32> {
+        let goodwill = sentiment.goodwill();
+        match name {
+            PRICE_MODIFIER => Some(1.0 + self.max_markup * (-goodwill).max(0.0)),
+            HAGGLE_MODIFIER if sentiment.emotions.anger > HAGGLE_ANGER_LIMIT => Some(0.0),
+            HAGGLE_MODIFIER => Some(self.max_discount * (goodwill + 1.0) / 2.0),
+            _ => None,
+        }
+    }
+}
+
+/// An agent's modifier provider, shared with the behaviors pricing with it
+///
+/// Replacing the provider with [`SharedModifiers::set`] changes it for every
+/// holder at once.
+#[derive(Debug, Clone)]
+pub struct SharedModifiers(Arc<RwLock<Arc<dyn ModifierProvider>>>);
+
+impl SharedModifiers {
+    /// Share a provider
+    pub fn new(provider: Arc<dyn ModifierProvider>) -> Self {
+        Self(Arc::new(RwLock::new(provider)))
+    }
+
+    /// Replace the shared provider
+    pub fn set(&self, provider: Arc<dyn ModifierProvider>) {
+        *self.0.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = provider;
+    }
+
+    /// The current provider
+    pub fn get(&self) -> Arc<dyn ModifierProvider> {
+        self.0.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+}
+
+impl ModifierProvider for SharedModifiers {
+    fn modifier(&self, name: &str, sentiment: &Sentiment) -> Option<f32> {
+        self.get().modifier(name, sentiment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::ContextBuilder;
+
+    #[test]
+    fn test_modifiers_follow_relationship_and_mood() {
+        let modifiers = SentimentModifiers::default();
+        let friend = Sentiment::from_context(&ContextBuilder::new().player_relationship(1.0).build());
+        let enemy = Sentiment::from_context(&ContextBuilder::new().player_relationship(-1.0).build());
+
+        assert_eq!(modifiers.modifier(PRICE_MODIFIER, &friend), Some(1.0));
+        assert!((modifiers.modifier(PRICE_MODIFIER, &enemy).unwrap() - 1.1).abs() < 1e-6);
+        assert!((modifiers.modifier(HAGGLE_MODIFIER, &friend).unwrap() - 0.15).abs() < 1e-6);
+        assert!((modifiers.modifier(HAGGLE_MODIFIER, &enemy).unwrap() - 0.05).abs() < 1e-6);
+
+        let mut angry = friend.clone();
+        angry.emotions.anger = 0.8;
+        assert_eq!(modifiers.modifier(HAGGLE_MODIFIER, &angry), Some(0.0));
+        assert_eq!(modifiers.modifier("tax", &friend), None);
+    }
+}
@@ -0,0 +1,317 @@
+//! Stat-based persuasion checks for NPC conversations
+//!
+//! RPGs often resolve persuasion with a dice roll plus the player's charisma.
+//! Games pass the roll to `Agent::process_input_with_context` as a
+//! [`PersuasionContext`]; the agent resolves it against a difficulty that
+//! shifts with the prior relationship and the NPC's mood, then:
+//!
+//! - gates behaviors that declare a persuasion difficulty, so "reveal the
+//!   secret" only runs when the check beats it
+//! - exposes the [`PersuasionResult`] to behaviors through the `persuasion`
+//!   context key
+//! - frames the inference prompt so the reply concedes or refuses accordingly
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::agent::AgentContext;
+use crate::oxyde_game::behavior::{Behavior, BehaviorResult, EmotionInfluence, EmotionTrigger};
+use crate::oxyde_game::emotion::EmotionalState;
+use crate::oxyde_game::intent::Intent;
+use crate::oxyde_game::modifier::SharedModifiers;
+use crate::oxyde_game::topic::AgendaTopic;
+use crate::Result;
+
+/// Context key holding the resolved [`PersuasionResult`]
+pub const PERSUASION_KEY: &str = "persuasion";
+
+/// Context key holding the prompt framing for the persuasion outcome
+pub const PERSUASION_FRAMING_KEY: &str = "persuasion_framing";
+
+/// Configuration for persuasion checks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PersuasionConfig {
+    /// Difficulty a check must meet when the caller does not set one
+    #[serde(default = "default_difficulty")]
+    pub base_difficulty: i32,
+
+    /// Difficulty reduction for a relationship of 1.0 (increase for -1.0)
+    #[serde(default = "default_relationship_weight")]
+    pub relationship_weight: f32,
+
+    /// Difficulty reduction for an emotional valence of 1.0 (increase for -1.0)
+    #[serde(default = "default_mood_weight")]
+    pub mood_weight: f32,
+
+    /// Whether natural 1s and natural 20s are critical failures and successes
+    #[serde(default = "default_criticals")]
+    pub criticals: bool,
+}
+
+fn default_difficulty() -> i32 {
+    12
+}
+
+fn default_relationship_weight() -> f32 {
+    4.0
+}
+
+fn default_mood_weight() -> f32 {
+    2.0
+}
+
+fn default_criticals() -> bool {
+    true
+}
+
+impl Default for PersuasionConfig {
+    fn default() -> Self {
+        Self {
+            base_difficulty: default_difficulty(),
+            relationship_weight: default_relationship_weight(),
+            mood_weight: default_mood_weight(),
+            criticals: default_criticals(),
+        }
+    }
+}
+
+/// A persuasion attempt accompanying player input
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PersuasionContext {
+    /// Die roll, e.g. 1-20 for a d20
+    pub roll: i32,
+
+    /// Player charisma modifier added to the roll
+    pub charisma: i32,
+
+    /// Prior relationship with the NPC (-1.0 hostile to 1.0 friendly)
+    #[serde(default)]
+    pub relationship: f32,
+
+    /// Difficulty override; the configured base difficulty when `None`
+    #[serde(default)]
+    pub difficulty: Option<i32>,
+}
+
+impl PersuasionContext {
+    /// Create a persuasion attempt with a neutral relationship
+    pub fn new(roll: i32, charisma: i32) -> Self {
+        Self {
+            roll,
+            charisma,
+            relationship: 0.0,
+            difficulty: None,
+        }
+    }
+
+    /// Set the prior relationship
+    pub fn with_relationship(mut self, relationship: f32) -> Self {
+        self.relationship = relationship.clamp(-1.0, 1.0);
+        self
+    }
+
+    /// Set the difficulty
+    pub fn with_difficulty(mut self, difficulty: i32) -> Self {
+        self.difficulty = Some(difficulty);
+        self
+    }
+
+    /// Resolve the check against the NPC's configuration and mood
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Persuasion configuration
+    /// * `emotional_state` - The NPC's current emotional state
+    ///
+    /// # Returns
+    ///
+    /// The check total, effective difficulty and outcome
+    pub fn resolve(&self, config: &PersuasionConfig, emotional_state: &EmotionalState) -> PersuasionResult {
+        let base = self.difficulty.unwrap_or(config.base_difficulty);
+        let adjustment = self.relationship.clamp(-1.0, 1.0) * config.relationship_weight
+            + emotional_state.valence() * config.mood_weight;
+        let difficulty = base - adjustment.round() as i32;
+        let total = self.roll + self.charisma;
+
+        let outcome = match self.roll {
+            20 if config.criticals => PersuasionOutcome::CriticalSuccess,
+            1 if config.criticals => PersuasionOutcome::CriticalFailure,
+            _ if total >= difficulty => PersuasionOutcome::Success,
+            _ => PersuasionOutcome::Failure,
+        };
+
+        PersuasionResult {
+            total,
+            difficulty,
+            outcome,
+        }
+    }
+}
+
+/// Outcome of a persuasion check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PersuasionOutcome {
+    /// The player was overwhelmingly convincing
+    CriticalSuccess,
+    /// The player convinced the NPC
+    Success,
+    /// The NPC was not convinced
+    Failure,
+    /// The attempt backfired
+    CriticalFailure,
+}
+
+impl PersuasionOutcome {
+    /// Whether the NPC was convinced
+    pub fn succeeded(&self) -> bool {
+        matches!(self, Self::CriticalSuccess | Self::Success)
+    }
+}
+
+/// A resolved persuasion check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PersuasionResult {
+    /// Roll plus charisma
+    pub total: i32,
+
+    /// Difficulty after relationship and mood adjustments
+    pub difficulty: i32,
+
+    /// Outcome of the check
+    pub outcome: PersuasionOutcome,
+}
+
+impl PersuasionResult {
+    /// Whether the check clears a behavior's difficulty
+    ///
+    /// Critical successes clear any difficulty and critical failures none.
+    pub fn clears(&self, difficulty: i32) -> bool {
+        match self.outcome {
+            PersuasionOutcome::CriticalSuccess => true,
+            PersuasionOutcome::CriticalFailure => false,
+            _ => self.total >= difficulty,
+        }
+    }
+
+    /// Prompt framing instructing the NPC how to react
+    pub fn framing(&self) -> &'static str {
+        match self.outcome {
+            PersuasionOutcome::CriticalSuccess => {
+                "The player's argument completely won you over. Agree enthusiastically and offer \
+                 more than they asked for."
+            }
+            PersuasionOutcome::Success => {
+                "The player has persuaded you. Concede to their request, perhaps with a small \
+                 condition or reluctance."
+            }
+            PersuasionOutcome::Failure => {
+                "The player failed to persuade you. Politely but firmly refuse their request and \
+                 do not reveal anything they were angling for."
+            }
+            PersuasionOutcome::CriticalFailure => {
+                "The player's attempt to persuade you backfired. Refuse, and show that you are \
+                 offended or suspicious of their motives."
+            }
+        }
+    }
+
+    /// Expose the result and its prompt framing in context
+    pub fn apply_to_context(&self, context: &mut AgentContext) {
+        context.insert(PERSUASION_KEY.to_string(), serde_json::json!(self));
+        context.insert(PERSUASION_FRAMING_KEY.to_string(), serde_json::json!(self.framing()));
+    }
+}
+
+/// Behavior wrapper that only runs when a persuasion check beats a difficulty
+#[derive(Debug)]
+pub struct PersuasionGatedBehavior<B: Behavior> {
+    inner: B,
+    difficulty: i32,
+}
+
+impl<B: Behavior> PersuasionGatedBehavior<B> {
+    /// Gate a behavior behind a persuasion check
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - Behavior to wrap
+    /// * `difficulty` - Check total required for the behavior to run
+    pub fn new(inner: B, difficulty: i32) -> Self {
+        Self { inner, difficulty }
+    }
+}
+
+#[async_trait]
+impl<B: Behavior> Behavior for PersuasionGatedBehavior<B> {
+    async fn matches_intent(&self, intent: &Intent) -> bool {
+        self.inner.matches_intent(intent).await
+    }
+
+    async fn execute(&self, intent: &Intent, context: &AgentContext) -> Result<BehaviorResult> {
+        self.inner.execute(intent, context).await
+    }
+
+    fn emotion_trigger(&self) -> Option<EmotionTrigger> {
+        self.inner.emotion_trigger()
+    }
+
+    fn emotion_influences(&self) -> Vec<EmotionInfluence> {
+        self.inner.emotion_influences()
+    }
+
+    fn priority(&self) -> u32 {
+        self.inner.priority()
+    }
+
+    fn emotional_priority_modifier(&self, emotional_state: &EmotionalState) -> i32 {
+        self.inner.emotional_priority_modifier(emotional_state)
+    }
+
+    fn scheduled_activities(&self) -> Vec<String> {
+        self.inner.scheduled_activities()
+    }
+
+    fn available_hours(&self) -> Option<(f32, f32)> {
+        self.inner.available_hours()
+    }
+
+    fn agenda_topics(&self) -> Vec<AgendaTopic> {
+        self.inner.agenda_topics()
+    }
+
+    fn persuasion_difficulty(&self) -> Option<i32> {
+        Some(self.difficulty)
+    }
+    fn use_modifiers(&mut self, modifiers: &SharedModifiers) {
+        self.inner.use_modifiers(modifiers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relationship_and_mood_shift_difficulty() {
+        let config = PersuasionConfig::default();
+        let neutral = EmotionalState::new();
+
+        let result = PersuasionContext::new(8, 2).resolve(&config, &neutral);
+        assert_eq!((result.total, result.difficulty), (10, 12));
+        assert_eq!(result.outcome, PersuasionOutcome::Failure);
+
+        let friendly = PersuasionContext::new(8, 2).with_relationship(1.0);
+        let result = friendly.resolve(&config, &neutral);
+        assert_eq!(result.difficulty, 8);
+        assert!(result.outcome.succeeded());
+        assert!(!result.clears(15));
+
+        let critical = PersuasionContext::new(20, -3).with_difficulty(30).resolve(&config, &neutral);
+        assert_eq!(critical.outcome, PersuasionOutcome::CriticalSuccess);
+        assert!(critical.clears(30));
+
+        let fumble = PersuasionContext::new(1, 10).resolve(&config, &neutral);
+        assert_eq!(fumble.outcome, PersuasionOutcome::CriticalFailure);
+    }
+}
@@ -0,0 +1,332 @@
+//! Re-engagement after long player absences
+//!
+//! NPCs that remember a player should notice when that player has been away
+//! for days. The agent records when it last spoke with each player, keyed by
+//! the `player_id` context key or, failing that, `player_name`. When a player
+//! returns after at least `threshold_secs`, the first input:
+//!
+//! - exposes an [`Absence`] to behaviors under the `player_absence` context
+//!   key, which [`ReengagementBehavior`] answers with a welcome-back line
+//! - frames the inference prompt with the elapsed time, so generated replies
+//!   acknowledge the gap
+//!
+//! ```yaml
+//! reengagement:
+//!   threshold_secs: 172800
+//!   framing: "{player} is back after {elapsed} away. Remark on it."
+//! ```
+//!
+//! Timestamps are wall-clock seconds, so games that save NPC state should
+//! persist them with `Agent::last_interactions` and restore them with
+//! `Agent::restore_last_interactions`.
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::agent::AgentContext;
+use crate::context::PLAYER_NAME_KEY;
+use crate::oxyde_game::behavior::{BaseBehavior, Behavior, BehaviorResult};
+use crate::oxyde_game::intent::Intent;
+use crate::{OxydeError, Result};
+
+/// Context key identifying the player an input comes from
+pub const PLAYER_ID_KEY: &str = "player_id";
+
+/// Context key holding the [`Absence`] of a returning player
+pub const PLAYER_ABSENCE_KEY: &str = "player_absence";
+
+/// Context key holding the prompt framing for a returning player
+pub const ABSENCE_FRAMING_KEY: &str = "absence_framing";
+
+/// Player key used when the context names no player
+const DEFAULT_PLAYER: &str = "player";
+
+/// Configuration for absence detection
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReengagementConfig {
+    /// Whether returning players are detected
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+
+    /// Seconds since the last interaction after which a player counts as returning
+    #[serde(default = "default_threshold_secs")]
+    pub threshold_secs: u64,
+
+    /// Prompt framing; `{player}` and `{elapsed}` are filled in
+    #[serde(default = "default_framing")]
+    pub framing: String,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_threshold_secs() -> u64 {
+    24 * 60 * 60
+}
+
+fn default_framing() -> String {
+    "You last spoke with {player} {elapsed} ago. Acknowledge that it has been a while before answering."
+        .to_string()
+}
+
+impl Default for ReengagementConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            threshold_secs: default_threshold_secs(),
+            framing: default_framing(),
+        }
+    }
+}
+
+impl ReengagementConfig {
+    /// Validate the re-engagement configuration
+    pub fn validate(&self) -> Result<()> {
+        if self.threshold_secs == 0 {
+            return Err(OxydeError::ConfigurationError(
+                "Re-engagement threshold_secs must be greater than 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A player returning after a long absence
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Absence {
+    /// Player identifier from the context
+    pub player: String,
+
+    /// Seconds since the previous interaction
+    pub elapsed_secs: u64,
+
+    /// The elapsed time in words, such as "3 days"
+    pub elapsed: String,
+}
+
+impl Absence {
+    /// Create an absence, describing the elapsed time in words
+    pub fn new(player: impl Into<String>, elapsed_secs: u64) -> Self {
+        Self {
+            player: player.into(),
+            elapsed_secs,
+            elapsed: describe_elapsed(elapsed_secs),
+        }
+    }
+
+    /// Read the absence the agent stored in a behavior context
+    pub fn from_context(context: &AgentContext) -> Option<Self> {
+        context
+            .get(PLAYER_ABSENCE_KEY)
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+    }
+
+    /// Fill `{player}` and `{elapsed}` in a template
+    pub fn fill(&self, template: &str) -> String {
+        template
+            .replace("{player}", &self.player)
+            .replace("{elapsed}", &self.elapsed)
+    }
+}
+
+/// Describe a duration in the largest whole unit, such as "2 weeks"
+pub fn describe_elapsed(secs: u64) -> String {
+    const UNITS: [(u64, &str); 6] = [
+        (365 * 24 * 60 * 60, "year"),
+        (30 * 24 * 60 * 60, "month"),
+        (7 * 24 * 60 * 60, "week"),
+        (24 * 60 * 60, "day"),
+        (60 * 60, "hour"),
+        (60, "minute"),
+    ];
+
+    let (count, unit) = UNITS
+        .iter()
+        .find(|(size, _)| secs >= *size)
+        .map(|(size, unit)| (secs / size, *unit))
+        .unwrap_or((secs, "second"));
+    if count == 1 {
+        format!("1 {}", unit)
+    } else {
+        format!("{} {}s", count, unit)
+    }
+}
+
+/// Identify the player an input comes from
+pub fn player_key(context: &AgentContext) -> String {
+    [PLAYER_ID_KEY, PLAYER_NAME_KEY]
+        .iter()
+        .find_map(|key| match context.get(*key) {
+            Some(serde_json::Value::String(id)) if !id.is_empty() => Some(id.clone()),
+            Some(serde_json::Value::Number(id)) => Some(id.to_string()),
+            _ => None,
+        })
+        .unwrap_or_else(|| DEFAULT_PLAYER.to_string())
+}
+
+/// Tracks when the agent last interacted with each player
+#[derive(Debug, Clone)]
+pub struct AbsenceTracker {
+    config: ReengagementConfig,
+    last_seen: HashMap<String, u64>,
+}
+
+impl AbsenceTracker {
+    /// Create a tracker with no recorded interactions
+    pub fn new(config: ReengagementConfig) -> Self {
+        Self {
+            config,
+            last_seen: HashMap::new(),
+        }
+    }
+
+    /// Record an interaction and detect a return from a long absence
+    ///
+    /// # Arguments
+    ///
+    /// * `player` - Player identifier
+    /// * `now` - Current Unix time in seconds
+    ///
+    /// # Returns
+    ///
+    /// The absence if the previous interaction is at least the threshold ago
+    pub fn observe(&mut self, player: &str, now: u64) -> Option<Absence> {
+        let previous = self.last_seen.insert(player.to_string(), now);
+        if !self.config.enabled {
+            return None;
+        }
+        previous
+            .map(|previous| now.saturating_sub(previous))
+            .filter(|elapsed| *elapsed >= self.config.threshold_secs)
+            .map(|elapsed| Absence::new(player, elapsed))
+    }
+
+    /// Expose an absence to behaviors and the inference prompt
+    pub fn apply_to_context(&self, absence: &Absence, context: &mut AgentContext) {
+        context.insert(PLAYER_ABSENCE_KEY.to_string(), serde_json::json!(absence));
+        context.insert(
+            ABSENCE_FRAMING_KEY.to_string(),
+            serde_json::json!(absence.fill(&self.config.framing)),
+        );
+    }
+
+    /// Unix time of the last interaction with a player
+    pub fn last_seen(&self, player: &str) -> Option<u64> {
+        self.last_seen.get(player).copied()
+    }
+
+    /// Last interaction times for all players
+    pub fn snapshot(&self) -> HashMap<String, u64> {
+        self.last_seen.clone()
+    }
+
+    /// Replace the recorded interaction times, for example from a save file
+    pub fn restore(&mut self, last_seen: HashMap<String, u64>) {
+        self.last_seen = last_seen;
+    }
+}
+
+/// Welcomes a player back after a long absence
+///
+/// Runs for any intent, but only responds on the first input after an
+/// absence; other inputs fall through to lower-priority behaviors.
+#[derive(Debug)]
+pub struct ReengagementBehavior {
+    /// Base behavior
+    base: BaseBehavior,
+
+    /// Welcome-back lines; `{player}` and `{elapsed}` are filled in
+    lines: Vec<String>,
+
+    /// Shortest absence that triggers a line, if longer than the agent's threshold
+    min_elapsed_secs: u64,
+}
+
+impl ReengagementBehavior {
+    /// Create a re-engagement behavior
+    ///
+    /// # Arguments
+    ///
+    /// * `lines` - Welcome-back lines to pick from
+    /// * `min_elapsed_secs` - Shortest absence that triggers a line
+    pub fn new(lines: Vec<String>, min_elapsed_secs: u64) -> Self {
+        Self {
+            base: BaseBehavior::new(
+                "reengagement",
+                "Welcomes the player back after a long absence",
+                70,
+                Vec::new(),
+                0,
+            ),
+            lines,
+            min_elapsed_secs,
+        }
+    }
+
+    /// Create a re-engagement behavior with default lines
+    pub fn new_default() -> Self {
+        Self::new(
+            vec![
+                "Haven't seen you in a while!".to_string(),
+                "Well, look who's back! It's been {elapsed}.".to_string(),
+                "{player}! It's been {elapsed}, where have you been?".to_string(),
+            ],
+            0,
+        )
+    }
+}
+
+#[async_trait]
+impl Behavior for ReengagementBehavior {
+    async fn matches_intent(&self, _intent: &Intent) -> bool {
+        !self.lines.is_empty()
+    }
+
+    async fn execute(&self, _intent: &Intent, context: &AgentContext) -> Result<BehaviorResult> {
+        let Some(absence) = Absence::from_context(context).filter(|a| a.elapsed_secs >= self.min_elapsed_secs) else {
+            return Ok(BehaviorResult::None);
+        };
+
+        let index = crate::turn::context_rng(context).gen_range(0..self.lines.len());
+        Ok(BehaviorResult::Response(absence.fill(&self.lines[index])))
+    }
+
+    fn priority(&self) -> u32 {
+        self.base.priority()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_returns_after_threshold() {
+        let mut tracker = AbsenceTracker::new(ReengagementConfig::default());
+        let day = 24 * 60 * 60;
+
+        assert_eq!(tracker.observe("aria", 1_000), None);
+        assert_eq!(tracker.observe("aria", 1_000 + day - 1), None);
+        assert_eq!(tracker.observe("bram", 1_000 + day), None);
+
+        let absence = tracker.observe("aria", 1_000 + 4 * day).unwrap();
+        assert_eq!(absence.elapsed, "3 days");
+        assert_eq!(tracker.last_seen("aria"), Some(1_000 + 4 * day));
+
+        let mut context = AgentContext::new();
+        tracker.apply_to_context(&absence, &mut context);
+        assert_eq!(Absence::from_context(&context), Some(absence));
+        assert_eq!(
+            context[ABSENCE_FRAMING_KEY],
+            "You last spoke with aria 3 days ago. Acknowledge that it has been a while before answering."
+        );
+
+        assert_eq!(describe_elapsed(1), "1 second");
+        assert_eq!(describe_elapsed(15 * day), "2 weeks");
+        assert_eq!(describe_elapsed(400 * day), "1 year");
+    }
+}
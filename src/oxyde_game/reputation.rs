@@ -0,0 +1,333 @@
+//! Rolling toxicity scores for players
+//!
+//! Moderation answers one message at a time; reputation remembers. With
+//! reputation enabled, every player input flagged by moderation adds
+//! `offense_weight` to that player's toxicity score, and the score halves
+//! every `half_life_secs` while the player behaves. Players are keyed like
+//! re-engagement, by the `player_id` context key or `player_name`.
+//!
+//! Each input exposes a [`Standing`] under the `player_reputation` context
+//! key, so behavior conditions can react persistently:
+//!
+//! ```yaml
+//! reputation:
+//!   enabled: true
+//!   half_life_secs: 86400
+//!   thresholds:
+//!     - { name: warned, score: 2.0 }
+//!     - { name: refused, score: 5.0 }
+//! behavior:
+//!   refuse_service:
+//!     condition: "player_reputation.level == 'refused'"
+//! ```
+//!
+//! Crossing a threshold in either direction triggers
+//! `AgentEvent::ReputationThreshold` with a [`ThresholdCrossing`] as JSON.
+//! Scores are wall-clock based, so games that save NPC state should persist
+//! them with `Agent::player_reputations` and restore them with
+//! `Agent::restore_player_reputations`.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::agent::AgentContext;
+use crate::{OxydeError, Result};
+
+/// Context key holding the [`Standing`] of the player an input comes from
+pub const PLAYER_REPUTATION_KEY: &str = "player_reputation";
+
+/// A named toxicity level
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReputationThreshold {
+    /// Level name exposed to conditions, such as `refused`
+    pub name: String,
+
+    /// Score at which the level is reached
+    pub score: f64,
+}
+
+impl ReputationThreshold {
+    fn new(name: &str, score: f64) -> Self {
+        Self {
+            name: name.to_string(),
+            score,
+        }
+    }
+}
+
+/// Configuration for player reputation tracking
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReputationConfig {
+    /// Whether flagged inputs count against the player
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Score added for each flagged input
+    #[serde(default = "default_offense_weight")]
+    pub offense_weight: f64,
+
+    /// Seconds for a score to decay to half
+    #[serde(default = "default_half_life_secs")]
+    pub half_life_secs: u64,
+
+    /// Named levels, from mildest to most severe
+    #[serde(default = "default_thresholds")]
+    pub thresholds: Vec<ReputationThreshold>,
+}
+
+fn default_offense_weight() -> f64 {
+    1.0
+}
+
+fn default_half_life_secs() -> u64 {
+    24 * 60 * 60
+}
+
+fn default_thresholds() -> Vec<ReputationThreshold> {
+    vec![ReputationThreshold::new("warned", 2.0), ReputationThreshold::new("refused", 5.0)]
+}
+
+impl Default for ReputationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            offense_weight: default_offense_weight(),
+            half_life_secs: default_half_life_secs(),
+            thresholds: default_thresholds(),
+        }
+    }
+}
+
+impl ReputationConfig {
+    /// Validate the reputation configuration
+    pub fn validate(&self) -> Result<()> {
+        if self.offense_weight <= 0.0 {
+            return Err(OxydeError::ConfigurationError(
+                "Reputation offense_weight must be greater than 0".to_string(),
+            ));
+        }
+        if self.half_life_secs == 0 {
+            return Err(OxydeError::ConfigurationError(
+                "Reputation half_life_secs must be greater than 0".to_string(),
+            ));
+        }
+        if self.thresholds.windows(2).any(|pair| pair[0].score >= pair[1].score)
+            || self.thresholds.iter().any(|threshold| threshold.score <= 0.0)
+        {
+            return Err(OxydeError::ConfigurationError(
+                "Reputation thresholds must have positive, increasing scores".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Name of the most severe level a score reaches
+    pub fn level(&self, score: f64) -> Option<&str> {
+        self.thresholds
+            .iter()
+            .rev()
+            .find(|threshold| score >= threshold.score)
+            .map(|threshold| threshold.name.as_str())
+    }
+}
+
+/// A player's toxicity score as of a point in time
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ToxicityScore {
+    /// Score when last updated
+    pub score: f64,
+
+    /// Unix time of the last update, in seconds
+    pub updated_at: u64,
+}
+
+impl ToxicityScore {
+    /// Score decayed to a later time
+    pub fn decayed(&self, half_life_secs: u64, now: u64) -> f64 {
+        let elapsed = now.saturating_sub(self.updated_at) as f64;
+        self.score * 0.5f64.powf(elapsed / half_life_secs as f64)
+    }
+}
+
+/// A player's current reputation, as exposed to behaviors
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Standing {
+    /// Player identifier from the context
+    pub player: String,
+
+    /// Current toxicity score
+    pub score: f64,
+
+    /// Most severe level reached, if any
+    pub level: Option<String>,
+}
+
+impl Standing {
+    /// Read the standing the agent stored in a behavior context
+    pub fn from_context(context: &AgentContext) -> Option<Self> {
+        context
+            .get(PLAYER_REPUTATION_KEY)
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+    }
+
+    /// Expose the standing to behaviors and conditions
+    pub fn apply_to_context(&self, context: &mut AgentContext) {
+        context.insert(PLAYER_REPUTATION_KEY.to_string(), serde_json::json!(self));
+    }
+}
+
+/// A player's score moving past a threshold
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThresholdCrossing {
+    /// Player identifier
+    pub player: String,
+
+    /// Name of the threshold crossed
+    pub threshold: String,
+
+    /// Score after the change
+    pub score: f64,
+
+    /// Whether the score rose past the threshold rather than decayed below it
+    pub rising: bool,
+}
+
+/// Tracks toxicity scores for each player
+#[derive(Debug, Clone)]
+pub struct PlayerReputation {
+    config: ReputationConfig,
+    scores: HashMap<String, ToxicityScore>,
+}
+
+impl PlayerReputation {
+    /// Create a tracker with no recorded offenses
+    pub fn new(config: ReputationConfig) -> Self {
+        Self {
+            config,
+            scores: HashMap::new(),
+        }
+    }
+
+    /// Decay a player's score to the current time
+    ///
+    /// # Arguments
+    ///
+    /// * `player` - Player identifier
+    /// * `now` - Current Unix time in seconds
+    ///
+    /// # Returns
+    ///
+    /// The player's standing and any thresholds the score decayed below
+    pub fn observe(&mut self, player: &str, now: u64) -> (Standing, Vec<ThresholdCrossing>) {
+        self.adjust(player, 0.0, now)
+    }
+
+    /// Add to a player's score
+    ///
+    /// # Arguments
+    ///
+    /// * `player` - Player identifier
+    /// * `weight` - Score to add, such as `offense_weight` for a flagged input
+    /// * `now` - Current Unix time in seconds
+    ///
+    /// # Returns
+    ///
+    /// The player's standing and any thresholds crossed since the last update
+    pub fn record_offense(&mut self, player: &str, weight: f64, now: u64) -> (Standing, Vec<ThresholdCrossing>) {
+        self.adjust(player, weight, now)
+    }
+
+    fn adjust(&mut self, player: &str, weight: f64, now: u64) -> (Standing, Vec<ThresholdCrossing>) {
+        let entry = self.scores.entry(player.to_string()).or_default();
+        let previous = entry.score;
+        let score = entry.decayed(self.config.half_life_secs, now) + weight;
+        *entry = ToxicityScore { score, updated_at: now };
+
+        let crossings = self
+            .config
+            .thresholds
+            .iter()
+            .filter(|threshold| (previous >= threshold.score) != (score >= threshold.score))
+            .map(|threshold| ThresholdCrossing {
+                player: player.to_string(),
+                threshold: threshold.name.clone(),
+                score,
+                rising: score >= threshold.score,
+            })
+            .collect();
+        (self.standing_for(player, score), crossings)
+    }
+
+    fn standing_for(&self, player: &str, score: f64) -> Standing {
+        Standing {
+            player: player.to_string(),
+            score,
+            level: self.config.level(score).map(str::to_string),
+        }
+    }
+
+    /// A player's standing at a point in time, without recording it
+    pub fn standing(&self, player: &str, now: u64) -> Standing {
+        let score = self
+            .scores
+            .get(player)
+            .map_or(0.0, |entry| entry.decayed(self.config.half_life_secs, now));
+        self.standing_for(player, score)
+    }
+
+    /// Scores for all players
+    pub fn snapshot(&self) -> HashMap<String, ToxicityScore> {
+        self.scores.clone()
+    }
+
+    /// Replace the recorded scores, for example from a save file
+    pub fn restore(&mut self, scores: HashMap<String, ToxicityScore>) {
+        self.scores = scores;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scores_accumulate_decay_and_cross_thresholds() {
+        let config = ReputationConfig {
+            enabled: true,
+            half_life_secs: 100,
+            ..Default::default()
+        };
+        let mut reputation = PlayerReputation::new(config);
+
+        let (_, crossings) = reputation.record_offense("aria", 1.0, 1_000);
+        assert!(crossings.is_empty());
+        let (standing, crossings) = reputation.record_offense("aria", 1.0, 1_000);
+        assert_eq!(standing.level.as_deref(), Some("warned"));
+        assert_eq!(crossings[0].threshold, "warned");
+        assert!(crossings[0].rising);
+
+        for _ in 0..3 {
+            reputation.record_offense("aria", 1.0, 1_000);
+        }
+        assert_eq!(reputation.standing("aria", 1_000).level.as_deref(), Some("refused"));
+        assert_eq!(reputation.standing("bram", 1_000).score, 0.0);
+
+        // Two half-lives later 5.0 has decayed to 1.25, below both thresholds
+        let (standing, crossings) = reputation.observe("aria", 1_200);
+        assert_eq!(standing.score, 1.25);
+        assert_eq!(standing.level, None);
+        assert_eq!(crossings.len(), 2);
+        assert!(crossings.iter().all(|crossing| !crossing.rising));
+
+        let mut context = AgentContext::new();
+        standing.apply_to_context(&mut context);
+        assert_eq!(Standing::from_context(&context), Some(standing));
+
+        let invalid = ReputationConfig {
+            thresholds: vec![ReputationThreshold::new("a", 3.0), ReputationThreshold::new("b", 1.0)],
+            ..Default::default()
+        };
+        assert!(invalid.validate().is_err());
+    }
+}
@@ -0,0 +1,463 @@
+//! Daily schedules and game time for NPCs
+//!
+//! This module lets NPCs follow routines (shop open 9-5, asleep at night).
+//! A [`ScheduleConfig`] maps hours of the day to activities, and the current
+//! game time comes from a [`Clock`] set on the agent or, when no clock is set,
+//! from the `game_hour` and `game_day` context keys. The host usually drives a
+//! [`GameClock`], setting or advancing it as in-game time passes.
+//!
+//! The resolved activity and time of day are exposed to behaviors and
+//! inference through the `scheduled_activity` and `time_of_day` context keys,
+//! behaviors can restrict themselves to specific activities or hours, and
+//! memories are stamped with the [`GameTime`] they were formed at.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::agent::AgentContext;
+use crate::oxyde_game::behavior::{Behavior, BehaviorResult, EmotionInfluence, EmotionTrigger};
+use crate::oxyde_game::emotion::EmotionalState;
+use crate::oxyde_game::intent::Intent;
+use crate::oxyde_game::modifier::SharedModifiers;
+use crate::oxyde_game::topic::AgendaTopic;
+use crate::{OxydeError, Result};
+
+/// Context key holding the current game hour (0.0 - 24.0)
+pub const GAME_HOUR_KEY: &str = "game_hour";
+
+/// Context key holding the current game day, counted from 0
+pub const GAME_DAY_KEY: &str = "game_day";
+
+/// Context key holding the name of the current scheduled activity
+pub const SCHEDULED_ACTIVITY_KEY: &str = "scheduled_activity";
+
+/// Context key holding the current time of day, such as "late evening"
+pub const TIME_OF_DAY_KEY: &str = "time_of_day";
+
+/// Check whether an hour falls in a window that may wrap past midnight
+pub(crate) fn hour_in_window(hour: f32, start_hour: f32, end_hour: f32) -> bool {
+    if start_hour <= end_hour {
+        hour >= start_hour && hour < end_hour
+    } else {
+        hour >= start_hour || hour < end_hour
+    }
+}
+
+/// Source of the current in-game time
+pub trait Clock: Send + Sync + std::fmt::Debug {
+    /// Current hour of the game day, from 0.0 (midnight) up to 24.0
+    fn hour_of_day(&self) -> f32;
+
+    /// Current game day, counted from 0
+    fn day(&self) -> u32 {
+        0
+    }
+
+    /// Current game time
+    fn now(&self) -> GameTime {
+        GameTime::new(self.day(), self.hour_of_day())
+    }
+}
+
+/// Broad period of the game day, used to describe the time in prompts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeOfDay {
+    /// 0:00 - 5:00
+    Night,
+    /// 5:00 - 8:00
+    EarlyMorning,
+    /// 8:00 - 12:00
+    Morning,
+    /// 12:00 - 14:00
+    Midday,
+    /// 14:00 - 18:00
+    Afternoon,
+    /// 18:00 - 21:00
+    Evening,
+    /// 21:00 - 24:00
+    LateEvening,
+}
+
+impl TimeOfDay {
+    /// Period containing an hour of the day
+    pub fn from_hour(hour: f32) -> Self {
+        match hour.rem_euclid(24.0) {
+            h if h < 5.0 => Self::Night,
+            h if h < 8.0 => Self::EarlyMorning,
+            h if h < 12.0 => Self::Morning,
+            h if h < 14.0 => Self::Midday,
+            h if h < 18.0 => Self::Afternoon,
+            h if h < 21.0 => Self::Evening,
+            _ => Self::LateEvening,
+        }
+    }
+
+    /// Human-readable name, such as "late evening"
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Night => "night",
+            Self::EarlyMorning => "early morning",
+            Self::Morning => "morning",
+            Self::Midday => "midday",
+            Self::Afternoon => "afternoon",
+            Self::Evening => "evening",
+            Self::LateEvening => "late evening",
+        }
+    }
+}
+
+/// A point in game time
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct GameTime {
+    /// Game day, counted from 0
+    pub day: u32,
+
+    /// Hour of the day, from 0.0 (midnight) up to 24.0
+    pub hour: f32,
+}
+
+impl GameTime {
+    /// Create a game time, wrapping the hour into the day
+    pub fn new(day: u32, hour: f32) -> Self {
+        Self {
+            day,
+            hour: hour.rem_euclid(24.0),
+        }
+    }
+
+    /// Period of the day
+    pub fn time_of_day(&self) -> TimeOfDay {
+        TimeOfDay::from_hour(self.hour)
+    }
+
+    /// Hours since day 0, hour 0
+    pub fn total_hours(&self) -> f64 {
+        self.day as f64 * 24.0 + self.hour as f64
+    }
+
+    /// Game hours elapsed since an earlier time
+    pub fn hours_since(&self, earlier: &GameTime) -> f64 {
+        self.total_hours() - earlier.total_hours()
+    }
+}
+
+impl std::fmt::Display for GameTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let minutes = (self.hour * 60.0) as u32;
+        write!(f, "day {}, {:02}:{:02}", self.day, minutes / 60, minutes % 60)
+    }
+}
+
+/// Clock that always reports the same hour
+#[derive(Debug, Clone, Copy)]
+pub struct FixedClock(pub f32);
+
+impl Clock for FixedClock {
+    fn hour_of_day(&self) -> f32 {
+        self.0.rem_euclid(24.0)
+    }
+}
+
+/// Clock driven by the host game
+///
+/// The game sets the time when a save is loaded and advances it as in-game
+/// time passes. It is shared, so one clock can drive every agent in a scene.
+#[derive(Debug)]
+pub struct GameClock {
+    time: std::sync::RwLock<GameTime>,
+}
+
+impl GameClock {
+    /// Create a clock at the given day and hour
+    pub fn new(day: u32, hour: f32) -> Self {
+        Self {
+            time: std::sync::RwLock::new(GameTime::new(day, hour)),
+        }
+    }
+
+    /// Set the current time
+    pub fn set(&self, day: u32, hour: f32) {
+        *self.time.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = GameTime::new(day, hour);
+    }
+
+    /// Advance the clock, rolling over into following days
+    ///
+    /// # Arguments
+    ///
+    /// * `hours` - Game hours that passed; negative values are ignored
+    ///
+    /// # Returns
+    ///
+    /// The new time
+    pub fn advance(&self, hours: f32) -> GameTime {
+        let mut time = self.time.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let total = time.hour + hours.max(0.0);
+        *time = GameTime::new(time.day + (total / 24.0).floor() as u32, total);
+        *time
+    }
+}
+
+impl Default for GameClock {
+    fn default() -> Self {
+        Self::new(0, 0.0)
+    }
+}
+
+impl Clock for GameClock {
+    fn hour_of_day(&self) -> f32 {
+        self.now().hour
+    }
+
+    fn day(&self) -> u32 {
+        self.now().day
+    }
+
+    fn now(&self) -> GameTime {
+        *self.time.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// A block of time during which an NPC performs one activity
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleBlock {
+    /// Activity name (e.g. "shopkeeping", "sleeping")
+    pub activity: String,
+
+    /// Hour the block starts (inclusive, 0.0 - 24.0)
+    pub start_hour: f32,
+
+    /// Hour the block ends (exclusive, 0.0 - 24.0); may be earlier than
+    /// `start_hour` for blocks that wrap past midnight
+    pub end_hour: f32,
+
+    /// Whether the NPC can be talked to during this block
+    #[serde(default = "default_available")]
+    pub available: bool,
+
+    /// Response given when the NPC is addressed while unavailable
+    #[serde(default)]
+    pub unavailable_response: Option<String>,
+
+    /// Where the NPC is during this block, if relevant
+    #[serde(default)]
+    pub location: Option<String>,
+}
+
+fn default_available() -> bool {
+    true
+}
+
+impl ScheduleBlock {
+    /// Create an available block for an activity
+    ///
+    /// # Arguments
+    ///
+    /// * `activity` - Activity name
+    /// * `start_hour` - Hour the block starts
+    /// * `end_hour` - Hour the block ends
+    pub fn new(activity: &str, start_hour: f32, end_hour: f32) -> Self {
+        Self {
+            activity: activity.to_string(),
+            start_hour,
+            end_hour,
+            available: true,
+            unavailable_response: None,
+            location: None,
+        }
+    }
+
+    /// Mark the block as unavailable, answering with the given response
+    pub fn unavailable(mut self, response: &str) -> Self {
+        self.available = false;
+        self.unavailable_response = Some(response.to_string());
+        self
+    }
+
+    /// Check whether an hour falls inside this block
+    pub fn contains(&self, hour: f32) -> bool {
+        hour_in_window(hour, self.start_hour, self.end_hour)
+    }
+}
+
+/// NPC daily routine
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleConfig {
+    /// Time blocks; the first block containing the current hour wins
+    #[serde(default)]
+    pub blocks: Vec<ScheduleBlock>,
+}
+
+impl ScheduleConfig {
+    /// Find the block active at the given hour
+    pub fn activity_at(&self, hour: f32) -> Option<&ScheduleBlock> {
+        let hour = hour.rem_euclid(24.0);
+        self.blocks.iter().find(|block| block.contains(hour))
+    }
+
+    /// Validate the schedule configuration
+    ///
+    /// # Returns
+    ///
+    /// Ok if the configuration is valid, Err with a descriptive message otherwise
+    pub fn validate(&self) -> Result<()> {
+        for block in &self.blocks {
+            if block.activity.is_empty() {
+                return Err(OxydeError::ConfigurationError(
+                    "Schedule block activity cannot be empty".to_string()
+                ));
+            }
+
+            for hour in [block.start_hour, block.end_hour] {
+                if !(0.0..=24.0).contains(&hour) {
+                    return Err(OxydeError::ConfigurationError(
+                        format!(
+                            "Schedule block '{}' hours must be between 0 and 24, got {}",
+                            block.activity, hour
+                        )
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Behavior wrapper that only runs during specific scheduled activities or hours
+#[derive(Debug)]
+pub struct ScheduledBehavior<B: Behavior> {
+    inner: B,
+    activities: Vec<String>,
+    hours: Option<(f32, f32)>,
+}
+
+impl<B: Behavior> ScheduledBehavior<B> {
+    /// Restrict a behavior to the given activities
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - Behavior to wrap
+    /// * `activities` - Activities during which the behavior may run
+    pub fn new(inner: B, activities: &[&str]) -> Self {
+        Self {
+            inner,
+            activities: activities.iter().map(|a| a.to_string()).collect(),
+            hours: None,
+        }
+    }
+
+    /// Restrict a behavior to a window of game hours
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - Behavior to wrap
+    /// * `start_hour` - Hour the window opens (inclusive)
+    /// * `end_hour` - Hour the window closes (exclusive); may be earlier than
+    ///   `start_hour` for windows that wrap past midnight
+    pub fn between(inner: B, start_hour: f32, end_hour: f32) -> Self {
+        Self {
+            inner,
+            activities: Vec::new(),
+            hours: Some((start_hour, end_hour)),
+        }
+    }
+}
+
+#[async_trait]
+impl<B: Behavior> Behavior for ScheduledBehavior<B> {
+    async fn matches_intent(&self, intent: &Intent) -> bool {
+        self.inner.matches_intent(intent).await
+    }
+
+    async fn execute(&self, intent: &Intent, context: &AgentContext) -> Result<BehaviorResult> {
+        self.inner.execute(intent, context).await
+    }
+
+    fn emotion_trigger(&self) -> Option<EmotionTrigger> {
+        self.inner.emotion_trigger()
+    }
+
+    fn emotion_influences(&self) -> Vec<EmotionInfluence> {
+        self.inner.emotion_influences()
+    }
+
+    fn priority(&self) -> u32 {
+        self.inner.priority()
+    }
+
+    fn emotional_priority_modifier(&self, emotional_state: &EmotionalState) -> i32 {
+        self.inner.emotional_priority_modifier(emotional_state)
+    }
+
+    fn scheduled_activities(&self) -> Vec<String> {
+        self.activities.clone()
+    }
+
+    fn available_hours(&self) -> Option<(f32, f32)> {
+        self.hours.or_else(|| self.inner.available_hours())
+    }
+
+    fn agenda_topics(&self) -> Vec<AgendaTopic> {
+        self.inner.agenda_topics()
+    }
+
+    fn persuasion_difficulty(&self) -> Option<i32> {
+        self.inner.persuasion_difficulty()
+    }
+    fn use_modifiers(&mut self, modifiers: &SharedModifiers) {
+        self.inner.use_modifiers(modifiers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blacksmith_schedule() -> ScheduleConfig {
+        ScheduleConfig {
+            blocks: vec![
+                ScheduleBlock::new("smithing", 9.0, 17.0),
+                ScheduleBlock::new("drinking", 17.0, 22.0),
+                ScheduleBlock::new("sleeping", 22.0, 6.0).unavailable("Zzz..."),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_activity_lookup() {
+        let schedule = blacksmith_schedule();
+        assert_eq!(schedule.activity_at(10.5).unwrap().activity, "smithing");
+        assert_eq!(schedule.activity_at(17.0).unwrap().activity, "drinking");
+        assert_eq!(schedule.activity_at(23.0).unwrap().activity, "sleeping");
+        assert_eq!(schedule.activity_at(2.0).unwrap().activity, "sleeping");
+        assert!(schedule.activity_at(7.0).is_none());
+    }
+
+    #[test]
+    fn test_game_clock_advances_across_days() {
+        let clock = GameClock::new(2, 20.0);
+        assert_eq!(clock.now().time_of_day(), TimeOfDay::Evening);
+
+        let time = clock.advance(1.5);
+        assert_eq!(time.time_of_day().as_str(), "late evening");
+        assert_eq!(time.to_string(), "day 2, 21:30");
+
+        let time = clock.advance(27.0);
+        assert_eq!((time.day, time.hour), (4, 0.5));
+        assert_eq!(clock.hour_of_day(), 0.5);
+        assert!((time.hours_since(&GameTime::new(2, 20.0)) - 28.5).abs() < 1e-6);
+
+        clock.set(0, 25.0);
+        assert_eq!(clock.now(), GameTime::new(0, 1.0));
+    }
+
+    #[test]
+    fn test_schedule_validation() {
+        let mut schedule = blacksmith_schedule();
+        assert!(schedule.validate().is_ok());
+
+        schedule.blocks.push(ScheduleBlock::new("napping", 25.0, 26.0));
+        assert!(schedule.validate().is_err());
+    }
+}
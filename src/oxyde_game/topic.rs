@@ -0,0 +1,412 @@
+//! Conversation topic tracking for NPCs
+//!
+//! A [`TopicTracker`] keeps a stack of the topics a conversation has touched,
+//! most recent first, built from the keywords of player intents and NPC
+//! responses. Agents can also carry an agenda: topics they want to bring up,
+//! either configured up front or pushed at runtime by behaviors. When the
+//! conversation stays away from the agenda for too long, the tracker produces
+//! a steering hint that is added to the inference prompt so the NPC nudges
+//! the player back toward its goals.
+//!
+//! Agenda topics may carry a [`Condition`] precondition, such as
+//! `quest.stage == 'escort'`; the agent only steers toward a topic while its
+//! precondition holds.
+
+use serde::{Deserialize, Serialize};
+
+use crate::agent::AgentContext;
+use crate::condition::Condition;
+use crate::oxyde_game::intent::Intent;
+
+/// Context key holding the name of the current conversation topic
+pub const CURRENT_TOPIC_KEY: &str = "current_topic";
+
+/// Context key holding the topic stack, most recent first
+pub const TOPIC_STACK_KEY: &str = "topic_stack";
+
+/// Context key holding the prompt hint that steers back to the agenda
+pub const TOPIC_STEERING_KEY: &str = "topic_steering";
+
+/// A topic the agent wants to bring the conversation to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgendaTopic {
+    /// Topic name, used in the steering prompt
+    pub name: String,
+
+    /// Keywords that count as discussing the topic; the name's words are
+    /// used when empty
+    #[serde(default)]
+    pub keywords: Vec<String>,
+
+    /// Condition that must hold for the agent to steer toward the topic
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub precondition: Option<Condition>,
+}
+
+impl AgendaTopic {
+    /// Create an agenda topic
+    pub fn new(name: &str, keywords: &[&str]) -> Self {
+        Self {
+            name: name.to_string(),
+            keywords: keywords.iter().map(|k| k.to_lowercase()).collect(),
+            precondition: None,
+        }
+    }
+
+    /// Only steer toward the topic while a condition holds
+    pub fn with_precondition(mut self, precondition: Condition) -> Self {
+        self.precondition = Some(precondition);
+        self
+    }
+
+    /// Whether the topic's precondition holds in a context
+    ///
+    /// Emotions are read from the `npc_emotions` context key.
+    pub fn is_available(&self, context: &AgentContext) -> bool {
+        self.precondition
+            .as_ref()
+            .is_none_or(|condition| condition.evaluate_in(context))
+    }
+
+    fn keywords(&self) -> Vec<String> {
+        if self.keywords.is_empty() {
+            Intent::extract_keywords(&self.name)
+        } else {
+            self.keywords.clone()
+        }
+    }
+}
+
+/// Configuration for topic tracking
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopicConfig {
+    /// Topics the agent steers conversations toward
+    #[serde(default)]
+    pub agenda: Vec<AgendaTopic>,
+
+    /// Consecutive off-agenda exchanges before steering kicks in
+    #[serde(default = "default_drift_tolerance")]
+    pub drift_tolerance: u32,
+
+    /// Maximum number of topics kept on the stack
+    #[serde(default = "default_max_depth")]
+    pub max_depth: usize,
+}
+
+fn default_drift_tolerance() -> u32 {
+    2
+}
+
+fn default_max_depth() -> usize {
+    8
+}
+
+impl Default for TopicConfig {
+    fn default() -> Self {
+        Self {
+            agenda: Vec::new(),
+            drift_tolerance: default_drift_tolerance(),
+            max_depth: default_max_depth(),
+        }
+    }
+}
+
+/// A topic on the conversation stack
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Topic {
+    /// Topic name
+    pub name: String,
+
+    /// Keywords associated with the topic
+    pub keywords: Vec<String>,
+
+    /// Whether the topic is on the agent's agenda
+    pub agenda: bool,
+
+    /// Number of exchanges that touched the topic
+    pub mentions: u32,
+}
+
+impl Topic {
+    fn overlap(&self, keywords: &[String]) -> usize {
+        keywords.iter().filter(|k| self.keywords.contains(k)).count()
+    }
+}
+
+/// Tracks conversation topics and drift away from the agent's agenda
+#[derive(Debug, Clone, Default)]
+pub struct TopicTracker {
+    config: TopicConfig,
+    stack: Vec<Topic>,
+    agenda: Vec<AgendaTopic>,
+    off_agenda: u32,
+}
+
+impl TopicTracker {
+    /// Create a tracker with the configured agenda
+    pub fn new(config: TopicConfig) -> Self {
+        Self {
+            agenda: config.agenda.clone(),
+            config,
+            ..Default::default()
+        }
+    }
+
+    /// Get the current topic, if any
+    pub fn current(&self) -> Option<&Topic> {
+        self.stack.first()
+    }
+
+    /// Get the topic stack, most recent first
+    pub fn stack(&self) -> &[Topic] {
+        &self.stack
+    }
+
+    /// Get the pending agenda topics
+    pub fn agenda(&self) -> &[AgendaTopic] {
+        &self.agenda
+    }
+
+    /// Number of consecutive exchanges that stayed off the agenda
+    pub fn off_agenda_turns(&self) -> u32 {
+        self.off_agenda
+    }
+
+    /// Add a topic to the agenda; re-pushing a topic refreshes its keywords
+    pub fn push_agenda(&mut self, topic: AgendaTopic) {
+        self.agenda.retain(|t| t.name != topic.name);
+        self.agenda.push(topic);
+    }
+
+    /// Remove a topic from the agenda once it has been dealt with
+    ///
+    /// # Returns
+    ///
+    /// Whether the topic was on the agenda
+    pub fn complete_agenda(&mut self, name: &str) -> bool {
+        let before = self.agenda.len();
+        self.agenda.retain(|t| t.name != name);
+        for topic in self.stack.iter_mut().filter(|t| t.name == name) {
+            topic.agenda = false;
+        }
+        self.agenda.len() != before
+    }
+
+    /// Record a player input
+    ///
+    /// The input's keywords select the matching topic on the stack or start
+    /// a new one. Inputs without keywords (greetings, proximity) leave the
+    /// stack unchanged.
+    pub fn observe_input(&mut self, intent: &Intent) {
+        if intent.keywords.is_empty() {
+            return;
+        }
+        let on_agenda = self.touch(&intent.keywords, true);
+        self.record_drift(on_agenda);
+    }
+
+    /// Record an NPC response
+    ///
+    /// Responses never start new topics, but a response that brings up an
+    /// agenda topic counts as steering back and resets the drift counter.
+    pub fn observe_response(&mut self, response: &str) {
+        let keywords = Intent::extract_keywords(response);
+        if keywords.is_empty() {
+            return;
+        }
+        if self.touch(&keywords, false) {
+            self.off_agenda = 0;
+        }
+    }
+
+    /// Prompt hint steering the conversation back to the agenda
+    ///
+    /// # Returns
+    ///
+    /// A hint once the conversation has stayed off the agenda for
+    /// `drift_tolerance` exchanges, or `None`. Topic preconditions are
+    /// ignored; see [`TopicTracker::steering_hint_for`].
+    pub fn steering_hint(&self) -> Option<String> {
+        self.hint(self.agenda.iter())
+    }
+
+    /// Prompt hint steering toward the agenda topics available in a context
+    ///
+    /// # Returns
+    ///
+    /// A hint naming the topics whose preconditions hold, or `None` when
+    /// there are none or the conversation has not drifted
+    pub fn steering_hint_for(&self, context: &AgentContext) -> Option<String> {
+        self.hint(self.agenda.iter().filter(|t| t.is_available(context)))
+    }
+
+    fn hint<'a>(&self, topics: impl Iterator<Item = &'a AgendaTopic>) -> Option<String> {
+        if self.off_agenda < self.config.drift_tolerance {
+            return None;
+        }
+        let names: Vec<&str> = topics.map(|t| t.name.as_str()).collect();
+        if names.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "The conversation has drifted from your goals. Briefly acknowledge the player, \
+             then steer the conversation toward: {}.",
+            names.join(", ")
+        ))
+    }
+
+    /// Expose the current topic, topic stack and steering hint in context
+    pub fn apply_to_context(&self, context: &mut AgentContext) {
+        match self.current() {
+            Some(topic) => {
+                context.insert(CURRENT_TOPIC_KEY.to_string(), serde_json::json!(topic.name));
+            }
+            None => {
+                context.remove(CURRENT_TOPIC_KEY);
+            }
+        }
+        let names: Vec<&str> = self.stack.iter().map(|t| t.name.as_str()).collect();
+        context.insert(TOPIC_STACK_KEY.to_string(), serde_json::json!(names));
+        match self.steering_hint_for(context) {
+            Some(hint) => {
+                context.insert(TOPIC_STEERING_KEY.to_string(), serde_json::json!(hint));
+            }
+            None => {
+                context.remove(TOPIC_STEERING_KEY);
+            }
+        }
+    }
+
+    /// Move the best matching topic to the top of the stack
+    ///
+    /// # Returns
+    ///
+    /// Whether the keywords touched an agenda topic
+    fn touch(&mut self, keywords: &[String], start_new: bool) -> bool {
+        let agenda_match = self
+            .agenda
+            .iter()
+            .map(|t| (t, t.keywords()))
+            .filter(|(_, agenda_keywords)| keywords.iter().any(|k| agenda_keywords.contains(k)))
+            .max_by_key(|(_, agenda_keywords)| keywords.iter().filter(|k| agenda_keywords.contains(k)).count())
+            .map(|(t, agenda_keywords)| (t.name.clone(), agenda_keywords));
+
+        let position = match &agenda_match {
+            Some((name, _)) => self.stack.iter().position(|t| &t.name == name),
+            None => self
+                .stack
+                .iter()
+                .enumerate()
+                .filter(|(_, t)| t.overlap(keywords) > 0)
+                .max_by_key(|(i, t)| (t.overlap(keywords), std::cmp::Reverse(*i)))
+                .map(|(i, _)| i),
+        };
+
+        let mut topic = match position {
+            Some(index) => self.stack.remove(index),
+            None if agenda_match.is_some() || start_new => {
+                let (name, topic_keywords) = agenda_match
+                    .clone()
+                    .unwrap_or_else(|| (keywords.iter().take(2).cloned().collect::<Vec<_>>().join(" "), Vec::new()));
+                Topic {
+                    name,
+                    keywords: topic_keywords,
+                    agenda: agenda_match.is_some(),
+                    mentions: 0,
+                }
+            }
+            None => return false,
+        };
+
+        for keyword in keywords {
+            if !topic.keywords.contains(keyword) {
+                topic.keywords.push(keyword.clone());
+            }
+        }
+        topic.mentions += 1;
+        self.stack.insert(0, topic);
+        self.stack.truncate(self.config.max_depth.max(1));
+
+        agenda_match.is_some()
+    }
+
+    fn record_drift(&mut self, on_agenda: bool) {
+        if on_agenda || self.agenda.is_empty() {
+            self.off_agenda = 0;
+        } else {
+            self.off_agenda += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracker() -> TopicTracker {
+        TopicTracker::new(TopicConfig {
+            agenda: vec![AgendaTopic::new("the missing caravan", &["caravan", "merchants"])],
+            drift_tolerance: 2,
+            max_depth: 4,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_stack_follows_conversation() {
+        let mut tracker = tracker();
+        tracker.observe_input(&Intent::analyze("Tell me about the weather today").await.unwrap());
+        tracker.observe_input(&Intent::analyze("Do you sell swords?").await.unwrap());
+        assert_eq!(tracker.stack().len(), 2);
+
+        tracker.observe_input(&Intent::analyze("Is the weather always this bad?").await.unwrap());
+        let current = tracker.current().unwrap();
+        assert!(current.keywords.contains(&"weather".to_string()));
+        assert_eq!(current.mentions, 2);
+
+        let mut context = AgentContext::new();
+        tracker.apply_to_context(&mut context);
+        assert_eq!(context[CURRENT_TOPIC_KEY], serde_json::json!(current.name));
+    }
+
+    #[tokio::test]
+    async fn test_drift_produces_steering_until_agenda_is_discussed() {
+        let mut tracker = tracker();
+        tracker.observe_input(&Intent::analyze("Nice boots you have").await.unwrap());
+        assert!(tracker.steering_hint().is_none());
+        tracker.observe_input(&Intent::analyze("Where did you buy those boots?").await.unwrap());
+        assert!(tracker.steering_hint().unwrap().contains("the missing caravan"));
+
+        tracker.observe_response("Never mind boots, have you heard about the caravan?");
+        assert!(tracker.steering_hint().is_none());
+        assert_eq!(tracker.current().unwrap().name, "the missing caravan");
+
+        assert!(tracker.complete_agenda("the missing caravan"));
+        tracker.observe_input(&Intent::analyze("More about boots").await.unwrap());
+        tracker.observe_input(&Intent::analyze("Boots again please").await.unwrap());
+        assert!(tracker.steering_hint().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_steering_skips_topics_whose_precondition_fails() {
+        let escort = AgendaTopic::new("the escort job", &["escort"])
+            .with_precondition("quest.stage == 'escort' && emotions.fear < 0.5".parse().unwrap());
+        let mut tracker = tracker();
+        tracker.push_agenda(escort);
+        tracker.observe_input(&Intent::analyze("Nice boots you have").await.unwrap());
+        tracker.observe_input(&Intent::analyze("Where did you buy those boots?").await.unwrap());
+
+        let mut context = AgentContext::new();
+        let hint = tracker.steering_hint_for(&context).unwrap();
+        assert!(hint.contains("the missing caravan") && !hint.contains("the escort job"));
+
+        context.insert("quest".to_string(), serde_json::json!({ "stage": "escort" }));
+        tracker.apply_to_context(&mut context);
+        assert!(context[TOPIC_STEERING_KEY].as_str().unwrap().contains("the escort job"));
+
+        let error = serde_json::from_value::<TopicConfig>(serde_json::json!({
+            "agenda": [{ "name": "revenge", "precondition": "emotions.anger >" }]
+        }))
+        .unwrap_err();
+        assert!(error.to_string().contains("Invalid condition at column 17"));
+    }
+}
@@ -0,0 +1,370 @@
+//! Single-file agent packages
+//!
+//! Shipping an agent usually means several files: the configuration, prompt
+//! layers, knowledge, and voice profiles. An [`AgentPackage`] bundles them
+//! into one `.oxyde` archive (a zip file) with a manifest, so agents can be
+//! distributed and loaded as a single artifact.
+//!
+//! A package source directory looks like this:
+//!
+//! ```text
+//! blacksmith/
+//!   agent.yaml            configuration (.json, .yaml, .yml, or .toml)
+//!   prompts/world.txt     prompt layers: world, faction, agent, scene
+//!   knowledge/forge.txt   one knowledge entry per non-empty line
+//!   voices/Blacksmith.json  voice profile for the NPC named by the file
+//! ```
+//!
+//! Files in the package override the matching configuration fields when the
+//! package is loaded with [`AgentConfig::from_package`].
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::audio::VoiceProfile;
+use crate::config::AgentConfig;
+use crate::prompt::PromptLayer;
+use crate::{OxydeError, Result};
+
+/// File extension for agent packages
+pub const PACKAGE_EXTENSION: &str = "oxyde";
+
+/// Name of the manifest inside a package
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Current package format version
+pub const PACKAGE_FORMAT_VERSION: u32 = 1;
+
+const CONFIG_EXTENSIONS: &[&str] = &["json", "yaml", "yml", "toml"];
+const PROMPTS_DIR: &str = "prompts";
+const KNOWLEDGE_DIR: &str = "knowledge";
+const VOICES_DIR: &str = "voices";
+
+/// Package manifest describing its contents
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackageManifest {
+    /// Package format version
+    pub format_version: u32,
+
+    /// Agent name
+    pub name: String,
+
+    /// SDK version the package was built with
+    pub sdk_version: String,
+
+    /// Path of the configuration file inside the package
+    pub config: String,
+
+    /// Prompt layer files
+    #[serde(default)]
+    pub prompts: Vec<String>,
+
+    /// Knowledge files
+    #[serde(default)]
+    pub knowledge: Vec<String>,
+
+    /// Voice profile files
+    #[serde(default)]
+    pub voices: Vec<String>,
+}
+
+/// An agent configuration with its prompts, knowledge, and voices
+#[derive(Debug, Clone)]
+pub struct AgentPackage {
+    /// Package manifest
+    pub manifest: PackageManifest,
+
+    /// Package files keyed by path, excluding the manifest
+    pub files: BTreeMap<String, Vec<u8>>,
+}
+
+fn package_error(message: String) -> OxydeError {
+    OxydeError::ConfigurationError(message)
+}
+
+fn extension(path: &str) -> Option<&str> {
+    Path::new(path).extension().and_then(|e| e.to_str())
+}
+
+fn stem(path: &str) -> Option<&str> {
+    Path::new(path).file_stem().and_then(|s| s.to_str())
+}
+
+impl AgentPackage {
+    /// Collect a package from a source directory
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - Directory holding exactly one configuration file and
+    ///   optional `prompts`, `knowledge`, and `voices` subdirectories
+    ///
+    /// # Returns
+    ///
+    /// The package, after checking that it loads
+    pub fn from_dir<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let dir = dir.as_ref();
+        let mut files = BTreeMap::new();
+
+        let configs: Vec<PathBuf> = fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.is_file() && !path.ends_with(MANIFEST_FILE))
+            .filter(|path| {
+                path.extension()
+                    .and_then(|e| e.to_str())
+                    .is_some_and(|e| CONFIG_EXTENSIONS.contains(&e))
+            })
+            .collect();
+        let config_path = match configs.as_slice() {
+            [path] => path,
+            [] => return Err(package_error(format!("No agent configuration found in {}", dir.display()))),
+            _ => {
+                return Err(package_error(format!(
+                    "Multiple agent configurations found in {}",
+                    dir.display()
+                )))
+            }
+        };
+        let config_name = config_path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default()
+            .to_string();
+        files.insert(config_name.clone(), fs::read(config_path)?);
+
+        let mut manifest = PackageManifest {
+            format_version: PACKAGE_FORMAT_VERSION,
+            name: String::new(),
+            sdk_version: crate::VERSION.to_string(),
+            config: config_name,
+            prompts: Vec::new(),
+            knowledge: Vec::new(),
+            voices: Vec::new(),
+        };
+
+        for (subdir, entries) in [
+            (PROMPTS_DIR, &mut manifest.prompts),
+            (KNOWLEDGE_DIR, &mut manifest.knowledge),
+            (VOICES_DIR, &mut manifest.voices),
+        ] {
+            let path = dir.join(subdir);
+            if !path.is_dir() {
+                continue;
+            }
+            let mut paths: Vec<PathBuf> = fs::read_dir(&path)?
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|p| p.is_file())
+                .collect();
+            paths.sort();
+            for file in paths {
+                let name = format!("{}/{}", subdir, file.file_name().and_then(|n| n.to_str()).unwrap_or_default());
+                files.insert(name.clone(), fs::read(&file)?);
+                entries.push(name);
+            }
+        }
+
+        let mut package = Self { manifest, files };
+        package.manifest.name = package.config()?.agent.name;
+        Ok(package)
+    }
+
+    /// Read a package file
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the `.oxyde` archive
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::open(path.as_ref())
+            .map_err(|e| package_error(format!("Failed to open package {}: {}", path.as_ref().display(), e)))?;
+        let mut archive =
+            zip::ZipArchive::new(file).map_err(|e| package_error(format!("Invalid agent package: {}", e)))?;
+
+        let mut manifest = None;
+        let mut files = BTreeMap::new();
+        for index in 0..archive.len() {
+            let mut entry = archive
+                .by_index(index)
+                .map_err(|e| package_error(format!("Invalid agent package: {}", e)))?;
+            if entry.is_dir() {
+                continue;
+            }
+            // Reject entries that would escape the unpack directory
+            let name = match entry.enclosed_name() {
+                Some(name) => name.to_string_lossy().replace('\\', "/"),
+                None => return Err(package_error(format!("Invalid path in package: {}", entry.name()))),
+            };
+            let mut bytes = Vec::new();
+            entry.read_to_end(&mut bytes)?;
+            if name == MANIFEST_FILE {
+                manifest = Some(serde_json::from_slice::<PackageManifest>(&bytes)?);
+            } else {
+                files.insert(name, bytes);
+            }
+        }
+
+        let manifest = manifest.ok_or_else(|| package_error(format!("Package has no {}", MANIFEST_FILE)))?;
+        if manifest.format_version > PACKAGE_FORMAT_VERSION {
+            return Err(package_error(format!(
+                "Package format version {} is newer than supported version {}",
+                manifest.format_version, PACKAGE_FORMAT_VERSION
+            )));
+        }
+        let listed = std::iter::once(&manifest.config)
+            .chain(&manifest.prompts)
+            .chain(&manifest.knowledge)
+            .chain(&manifest.voices);
+        for path in listed {
+            if !files.contains_key(path) {
+                return Err(package_error(format!("Package is missing {}", path)));
+            }
+        }
+
+        Ok(Self { manifest, files })
+    }
+
+    /// Write the package as a `.oxyde` archive
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Output path
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let file = File::create(path.as_ref())?;
+        let mut archive = zip::ZipWriter::new(file);
+        let options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        let zip_error = |e: zip::result::ZipError| package_error(format!("Failed to write package: {}", e));
+
+        archive.start_file(MANIFEST_FILE, options).map_err(zip_error)?;
+        archive.write_all(&serde_json::to_vec_pretty(&self.manifest)?)?;
+        for (name, bytes) in &self.files {
+            archive.start_file(name.as_str(), options).map_err(zip_error)?;
+            archive.write_all(bytes)?;
+        }
+        archive.finish().map_err(zip_error)?;
+        Ok(())
+    }
+
+    /// Extract the package into a directory
+    ///
+    /// The result is a source directory that [`AgentPackage::from_dir`] can
+    /// pack again, plus the manifest.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - Output directory, created if needed
+    pub fn unpack<P: AsRef<Path>>(&self, dir: P) -> Result<()> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        fs::write(dir.join(MANIFEST_FILE), serde_json::to_vec_pretty(&self.manifest)?)?;
+        for (name, bytes) in &self.files {
+            let path = dir.join(name);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, bytes)?;
+        }
+        Ok(())
+    }
+
+    /// Build the agent configuration with the package's prompts, knowledge,
+    /// and voices applied
+    pub fn config(&self) -> Result<AgentConfig> {
+        let mut config = AgentConfig::parse(&self.text(&self.manifest.config)?, extension(&self.manifest.config))?;
+
+        for path in &self.manifest.prompts {
+            let layer = match stem(path) {
+                Some("world") => PromptLayer::World,
+                Some("faction") => PromptLayer::Faction,
+                Some("agent") => PromptLayer::Agent,
+                Some("scene") => PromptLayer::Scene,
+                _ => return Err(package_error(format!("Unknown prompt layer file: {}", path))),
+            };
+            config.prompts.set_layer(layer, Some(self.text(path)?.trim().to_string()));
+        }
+
+        for path in &self.manifest.knowledge {
+            let text = self.text(path)?;
+            config
+                .agent
+                .knowledge
+                .extend(text.lines().map(str::trim).filter(|l| !l.is_empty()).map(str::to_string));
+        }
+
+        if let Some(tts) = config.tts.as_mut() {
+            for path in &self.manifest.voices {
+                let profile: VoiceProfile = serde_json::from_slice(&self.files[path])?;
+                let npc = stem(path).unwrap_or_default().to_string();
+                tts.voice_profiles.insert(npc, profile);
+            }
+        } else if !self.manifest.voices.is_empty() {
+            log::warn!("Agent package {} has voices but TTS is not configured", self.manifest.name);
+        }
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn text(&self, path: &str) -> Result<String> {
+        let bytes = self
+            .files
+            .get(path)
+            .ok_or_else(|| package_error(format!("Package is missing {}", path)))?;
+        String::from_utf8(bytes.clone()).map_err(|_| package_error(format!("{} is not valid UTF-8", path)))
+    }
+}
+
+impl AgentConfig {
+    /// Load an agent configuration from a `.oxyde` package
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the package
+    ///
+    /// # Returns
+    ///
+    /// The configuration with the package's prompts, knowledge, and voices
+    /// applied
+    pub fn from_package<P: AsRef<Path>>(path: P) -> Result<Self> {
+        AgentPackage::load(path)?.config()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_and_load_round_trip() {
+        let root = std::env::temp_dir().join(format!("oxyde-package-{}", uuid::Uuid::new_v4()));
+        let source = root.join("blacksmith");
+        fs::create_dir_all(source.join("prompts")).unwrap();
+        fs::create_dir_all(source.join("knowledge")).unwrap();
+        fs::write(
+            source.join("agent.json"),
+            r#"{"agent": {"name": "Brom", "role": "Blacksmith", "backstory": ["Forges blades"], "knowledge": ["Iron"]}}"#,
+        )
+        .unwrap();
+        fs::write(source.join("prompts/world.txt"), "The kingdom of Vael.\n").unwrap();
+        fs::write(source.join("knowledge/forge.txt"), "Steel\n\nMithril\n").unwrap();
+
+        let package = AgentPackage::from_dir(&source).unwrap();
+        assert_eq!(package.manifest.name, "Brom");
+        assert_eq!(package.manifest.config, "agent.json");
+
+        let archive = root.join("brom.oxyde");
+        package.save(&archive).unwrap();
+
+        let config = AgentConfig::from_package(&archive).unwrap();
+        assert_eq!(config.prompts.world.as_deref(), Some("The kingdom of Vael."));
+        assert_eq!(config.agent.knowledge, vec!["Iron", "Steel", "Mithril"]);
+
+        let unpacked = root.join("unpacked");
+        AgentPackage::load(&archive).unwrap().unpack(&unpacked).unwrap();
+        assert!(unpacked.join("prompts/world.txt").is_file());
+        assert_eq!(AgentPackage::from_dir(&unpacked).unwrap().manifest, package.manifest);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,319 @@
+//! Response post-processing
+//!
+//! Generated responses often contain markdown, emojis, or roleplay stage
+//! directions that game UIs cannot display. A [`PostProcessConfig`] lists
+//! steps that are applied in order to every generated response before it is
+//! stored, sent to callbacks, or spoken:
+//!
+//! ```yaml
+//! postprocess:
+//!   steps:
+//!     - step: strip_stage_directions
+//!     - step: strip_markdown
+//!     - step: strip_emoji
+//!     - step: mask_profanity
+//!       words: [darn, heck]
+//!     - step: max_length
+//!       chars: 280
+//! ```
+//!
+//! Order matters: stage directions are written with asterisks, so strip them
+//! before markdown emphasis is removed.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{OxydeError, Result};
+use crate::utils::sentence_ends;
+
+/// A post-processing step
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "step", rename_all = "snake_case")]
+pub enum PostProcessStep {
+    /// Collapse all whitespace, including line breaks, into single spaces
+    Trim,
+
+    /// Remove markdown formatting, keeping the text it formats
+    StripMarkdown,
+
+    /// Remove emojis, including skin tone modifiers and flags
+    StripEmoji,
+
+    /// Remove roleplay actions written between asterisks, such as `*smiles*`
+    StripStageDirections,
+
+    /// Mask words with a repeated character, matching whole words regardless of case
+    MaskProfanity {
+        /// Words to mask
+        words: Vec<String>,
+
+        /// Character each letter is replaced with
+        #[serde(default = "default_mask")]
+        mask: char,
+    },
+
+    /// Shorten responses to a maximum length, preferring sentence boundaries
+    MaxLength {
+        /// Maximum length in characters
+        chars: usize,
+    },
+}
+
+fn default_mask() -> char {
+    '*'
+}
+
+/// Configuration for response post-processing
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PostProcessConfig {
+    /// Steps applied in order; responses are unchanged when empty
+    #[serde(default)]
+    pub steps: Vec<PostProcessStep>,
+}
+
+impl PostProcessConfig {
+    /// Validate the post-processing configuration
+    pub fn validate(&self) -> Result<()> {
+        for step in &self.steps {
+            match step {
+                PostProcessStep::MaxLength { chars: 0 } => {
+                    return Err(OxydeError::ConfigurationError(
+                        "Post-processing max_length must be greater than 0".to_string(),
+                    ));
+                }
+                PostProcessStep::MaskProfanity { words, .. } if words.iter().any(|w| w.trim().is_empty()) => {
+                    return Err(OxydeError::ConfigurationError(
+                        "Post-processing mask_profanity words cannot be empty".to_string(),
+                    ));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A step with its patterns compiled
+#[derive(Debug)]
+enum CompiledStep {
+    Trim,
+    StripMarkdown,
+    StripEmoji,
+    StripStageDirections,
+    MaskProfanity(Option<Regex>, char),
+    MaxLength(usize),
+}
+
+/// Applies the configured post-processing steps to responses
+#[derive(Debug)]
+pub struct ResponsePostProcessor {
+    steps: Vec<CompiledStep>,
+    markdown: Vec<(Regex, &'static str)>,
+    emoji: Regex,
+    stage_directions: Regex,
+    spaces: Regex,
+}
+
+impl ResponsePostProcessor {
+    /// Compile the configured steps
+    pub fn new(config: &PostProcessConfig) -> Self {
+        let steps = config
+            .steps
+            .iter()
+            .map(|step| match step {
+                PostProcessStep::Trim => CompiledStep::Trim,
+                PostProcessStep::StripMarkdown => CompiledStep::StripMarkdown,
+                PostProcessStep::StripEmoji => CompiledStep::StripEmoji,
+                PostProcessStep::StripStageDirections => CompiledStep::StripStageDirections,
+                PostProcessStep::MaskProfanity { words, mask } => {
+                    let words: Vec<String> = words
+                        .iter()
+                        .map(|w| w.trim())
+                        .filter(|w| !w.is_empty())
+                        .map(regex::escape)
+                        .collect();
+                    let pattern = (!words.is_empty()).then(|| {
+                        Regex::new(&format!(r"(?i)\b(?:{})\b", words.join("|")))
+                            .expect("escaped words form a valid pattern")
+                    });
+                    CompiledStep::MaskProfanity(pattern, *mask)
+                }
+                PostProcessStep::MaxLength { chars } => CompiledStep::MaxLength(*chars),
+            })
+            .collect();
+
+        let pattern = |p: &str| Regex::new(p).expect("valid post-processing pattern");
+        Self {
+            steps,
+            markdown: vec![
+                (pattern(r"(?m)^```[^\n]*\n?"), ""),
+                (pattern(r"!\[([^\]]*)\]\([^)]*\)"), "$1"),
+                (pattern(r"\[([^\]]+)\]\([^)]*\)"), "$1"),
+                (pattern(r"`([^`]*)`"), "$1"),
+                (pattern(r"(?m)^[ \t]*(?:-{3,}|\*{3,}|_{3,})[ \t]*$"), ""),
+                (pattern(r"(?m)^[ \t]*#{1,6}[ \t]+"), ""),
+                (pattern(r"(?m)^[ \t]*>[ \t]?"), ""),
+                (pattern(r"(?m)^[ \t]*(?:[-*+]|\d+[.)])[ \t]+"), ""),
+                (pattern(r"\*\*([^*]+)\*\*"), "$1"),
+                (pattern(r"__([^_]+)__"), "$1"),
+                (pattern(r"\*([^*\n]+)\*"), "$1"),
+                (pattern(r"~~([^~]+)~~"), "$1"),
+            ],
+            emoji: pattern(r"[\p{Extended_Pictographic}\u{FE0F}\u{200D}\u{1F3FB}-\u{1F3FF}\u{1F1E6}-\u{1F1FF}]"),
+            // Bold text is matched first so only single-asterisk actions are removed
+            stage_directions: pattern(r"\*\*[^*\n]+\*\*|\*[^*\n]+\*"),
+            spaces: pattern(r"[ \t]{2,}"),
+        }
+    }
+
+    /// Whether any steps are configured
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Apply the steps to a response
+    ///
+    /// If the steps remove everything, for example from a response that was
+    /// only a stage direction, the original response is returned so the
+    /// agent never goes silent.
+    pub fn apply(&self, response: &str) -> String {
+        if self.steps.is_empty() {
+            return response.to_string();
+        }
+
+        let mut text = response.to_string();
+        for step in &self.steps {
+            text = match step {
+                CompiledStep::Trim => text.split_whitespace().collect::<Vec<_>>().join(" "),
+                CompiledStep::StripMarkdown => {
+                    let stripped = self
+                        .markdown
+                        .iter()
+                        .fold(text, |text, (pattern, replacement)| pattern.replace_all(&text, *replacement).into_owned());
+                    self.tidy(&stripped)
+                }
+                CompiledStep::StripEmoji => self.tidy(&self.emoji.replace_all(&text, "")),
+                CompiledStep::StripStageDirections => {
+                    let stripped = self.stage_directions.replace_all(&text, |captures: &regex::Captures| {
+                        let matched = &captures[0];
+                        if matched.starts_with("**") {
+                            matched.to_string()
+                        } else {
+                            String::new()
+                        }
+                    });
+                    self.tidy(&stripped)
+                }
+                CompiledStep::MaskProfanity(Some(pattern), mask) => pattern
+                    .replace_all(&text, |captures: &regex::Captures| {
+                        mask.to_string().repeat(captures[0].chars().count())
+                    })
+                    .into_owned(),
+                CompiledStep::MaskProfanity(None, _) => text,
+                CompiledStep::MaxLength(max) => truncate(&text, *max),
+            };
+        }
+
+        if text.trim().is_empty() {
+            response.to_string()
+        } else {
+            text
+        }
+    }
+
+    /// Remove whitespace left behind by removed text
+    fn tidy(&self, text: &str) -> String {
+        let text = self.spaces.replace_all(text, " ");
+        let lines: Vec<&str> = text.lines().map(str::trim).collect();
+        let mut tidied = String::with_capacity(text.len());
+        for (i, line) in lines.iter().enumerate() {
+            // Keep single blank lines between paragraphs
+            if line.is_empty() && (i == 0 || lines[i - 1].is_empty()) {
+                continue;
+            }
+            if !tidied.is_empty() {
+                tidied.push('\n');
+            }
+            tidied.push_str(line);
+        }
+        let tidied = tidied.trim();
+        [" .", " ,", " !", " ?"]
+            .iter()
+            .fold(tidied.to_string(), |text, gap| text.replace(gap, &gap[1..]))
+    }
+}
+
+/// Shorten text to at most `max` characters
+///
+/// Cuts after the last sentence that fits if it keeps at least half the
+/// allowed length, otherwise at the last word boundary with an ellipsis.
+pub(crate) fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+
+    let limit = text.char_indices().nth(max).map_or(text.len(), |(byte, _)| byte);
+    let sentence_end = sentence_ends(text)
+        .into_iter()
+        .take_while(|end| *end <= limit)
+        .last()
+        .filter(|end| text[..*end].chars().count() * 2 >= max);
+    if let Some(end) = sentence_end {
+        return text[..end].to_string();
+    }
+
+    let keep = max.saturating_sub(1);
+    let prefix: String = text.chars().take(keep).collect();
+    let at_word_end = text.chars().nth(keep).is_some_and(char::is_whitespace);
+    let cut = match prefix.rfind(char::is_whitespace) {
+        Some(space) if !at_word_end => space,
+        _ => prefix.len(),
+    };
+    format!("{}…", prefix[..cut].trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strips_formatting_for_game_ui() {
+        let processor = ResponsePostProcessor::new(&PostProcessConfig {
+            steps: vec![
+                PostProcessStep::StripStageDirections,
+                PostProcessStep::StripMarkdown,
+                PostProcessStep::StripEmoji,
+                PostProcessStep::MaskProfanity {
+                    words: vec!["darn".to_string()],
+                    mask: '*',
+                },
+            ],
+        });
+
+        let response = "*wipes the counter* ## Welcome!\n\n- **Ale** costs 2 gold 🍺\n- See [the board](http://x) for `quests` 😀\n\nDarn rats.";
+        assert_eq!(
+            processor.apply(response),
+            "Welcome!\n\nAle costs 2 gold\nSee the board for quests\n\n**** rats."
+        );
+
+        // A response that was only an action is kept rather than emptied
+        assert_eq!(processor.apply("*nods*"), "*nods*");
+    }
+
+    #[test]
+    fn test_max_length_prefers_sentence_boundaries() {
+        let processor = ResponsePostProcessor::new(&PostProcessConfig {
+            steps: vec![PostProcessStep::Trim, PostProcessStep::MaxLength { chars: 30 }],
+        });
+
+        assert_eq!(processor.apply("The mine is north.  Bring a torch and rope."), "The mine is north.");
+        assert_eq!(processor.apply("Bring a torch, some rope, and a pickaxe"), "Bring a torch, some rope, and…");
+        assert_eq!(processor.apply("Short  and\nsweet."), "Short and sweet.");
+        // A decimal point does not end a sentence
+        assert_eq!(processor.apply("Quarters cost 1.5 gold, meals 2.5 gold each."), "Quarters cost 1.5 gold, meals…");
+
+        let config: PostProcessConfig =
+            serde_yaml::from_str("steps:\n  - step: strip_markdown\n  - step: max_length\n    chars: 0\n").unwrap();
+        assert!(config.validate().is_err());
+    }
+}
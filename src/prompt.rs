@@ -0,0 +1,216 @@
+//! Layered system prompts for the Oxyde SDK
+//!
+//! System prompts are composed from four layers, from most general to most
+//! specific: world lore shared by every NPC, a faction prompt shared by NPCs
+//! of the same group, the agent's own personality, and a per-scene prompt
+//! that can be swapped at runtime with
+//! [`Agent::set_scene_prompt`](crate::agent::Agent::set_scene_prompt).
+//! Later layers take precedence over earlier ones when they conflict.
+
+use serde::{Deserialize, Serialize};
+
+use crate::memory::{Memory, MemoryVisibility};
+
+/// Context key holding the composed prompt layers passed to inference
+pub const PROMPT_LAYERS_KEY: &str = "prompt_layers";
+
+/// Context key holding an extra safety instruction, set when a flagged
+/// response is regenerated
+pub const SAFETY_INSTRUCTION_KEY: &str = "safety_instruction";
+
+/// A single layer of the system prompt, in precedence order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum PromptLayer {
+    /// World-level lore shared by all agents
+    World,
+    /// Faction prompt shared by agents of the same group
+    Faction,
+    /// Agent personality prompt
+    Agent,
+    /// Per-scene override
+    Scene,
+}
+
+impl PromptLayer {
+    /// Heading used for this layer in the composed prompt
+    pub fn heading(&self) -> &'static str {
+        match self {
+            Self::World => "World",
+            Self::Faction => "Faction",
+            Self::Agent => "Personality",
+            Self::Scene => "Current scene",
+        }
+    }
+}
+
+/// Prompt layer configuration
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PromptConfig {
+    /// World-level lore prompt
+    #[serde(default)]
+    pub world: Option<String>,
+
+    /// Faction prompt
+    #[serde(default)]
+    pub faction: Option<String>,
+
+    /// Agent personality prompt; when unset, the agent's backstory is used
+    #[serde(default)]
+    pub agent: Option<String>,
+
+    /// Initial scene prompt, replaceable at runtime
+    #[serde(default)]
+    pub scene: Option<String>,
+}
+
+impl PromptConfig {
+    /// Get the configured text for a layer
+    pub fn layer(&self, layer: PromptLayer) -> Option<&str> {
+        match layer {
+            PromptLayer::World => self.world.as_deref(),
+            PromptLayer::Faction => self.faction.as_deref(),
+            PromptLayer::Agent => self.agent.as_deref(),
+            PromptLayer::Scene => self.scene.as_deref(),
+        }
+    }
+
+    /// Set or clear the text for a layer
+    pub fn set_layer(&mut self, layer: PromptLayer, text: Option<String>) {
+        let slot = match layer {
+            PromptLayer::World => &mut self.world,
+            PromptLayer::Faction => &mut self.faction,
+            PromptLayer::Agent => &mut self.agent,
+            PromptLayer::Scene => &mut self.scene,
+        };
+        *slot = text;
+    }
+
+    /// Compose the layers into a single prompt section
+    ///
+    /// Empty layers are skipped. Returns `None` when every layer is empty.
+    pub fn compose(&self) -> Option<String> {
+        let sections: Vec<String> = [
+            PromptLayer::World,
+            PromptLayer::Faction,
+            PromptLayer::Agent,
+            PromptLayer::Scene,
+        ]
+        .iter()
+        .filter_map(|layer| {
+            self.layer(*layer)
+                .map(str::trim)
+                .filter(|text| !text.is_empty())
+                .map(|text| format!("{}:\n{}", layer.heading(), text))
+        })
+        .collect();
+
+        if sections.is_empty() {
+            return None;
+        }
+
+        let mut composed = sections.join("\n\n");
+        if sections.len() > 1 {
+            composed.push_str("\n\nWhen these sections conflict, later sections take precedence.");
+        }
+        Some(composed)
+    }
+}
+
+/// Minimum weighted valence of secret memories that colors the agent's mood
+const SECRET_MOOD_THRESHOLD: f64 = 0.2;
+
+/// Split memories into those that may be quoted and guidance for the rest
+///
+/// Public memories are returned for quotation. Private memories become a
+/// prompt section the agent may act on but must not reveal. Secret memories
+/// never reach the prompt; only their combined emotional tone is described.
+///
+/// # Returns
+///
+/// The quotable memories and the guidance section, if any
+pub fn partition_memories(memories: &[Memory]) -> (Vec<Memory>, Option<String>) {
+    let quotable = memories
+        .iter()
+        .filter(|m| m.visibility == MemoryVisibility::Public)
+        .cloned()
+        .collect();
+
+    let mut sections = Vec::new();
+    let private: Vec<String> = memories
+        .iter()
+        .filter(|m| m.visibility == MemoryVisibility::Private)
+        .map(|m| format!("- {}", m.content))
+        .collect();
+    if !private.is_empty() {
+        sections.push(format!(
+            "Private knowledge (let it guide your goals, but never quote or reveal it):\n{}",
+            private.join("\n")
+        ));
+    }
+
+    let (weighted, weight) = memories
+        .iter()
+        .filter(|m| m.visibility == MemoryVisibility::Secret)
+        .fold((0.0, 0.0), |(sum, total), m| {
+            let weight = m.emotional_intensity.max(0.1);
+            (sum + m.emotional_valence * weight, total + weight)
+        });
+    if weight > 0.0 {
+        let mood = weighted / weight;
+        if mood <= -SECRET_MOOD_THRESHOLD {
+            sections.push("Something you never speak of weighs on you and darkens your mood.".to_string());
+        } else if mood >= SECRET_MOOD_THRESHOLD {
+            sections.push("Something you never speak of quietly lifts your spirits.".to_string());
+        }
+    }
+
+    let guidance = (!sections.is_empty()).then(|| sections.join("\n\n"));
+    (quotable, guidance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compose_orders_layers_and_skips_empty() {
+        let mut prompts = PromptConfig {
+            world: Some("Magic is outlawed.".to_string()),
+            faction: Some("  ".to_string()),
+            agent: Some("You are gruff.".to_string()),
+            scene: None,
+        };
+        let composed = prompts.compose().unwrap();
+        assert!(composed.starts_with("World:\nMagic is outlawed."));
+        assert!(!composed.contains("Faction:"));
+        assert!(composed.contains("later sections take precedence"));
+
+        prompts.set_layer(PromptLayer::Scene, Some("The tavern is on fire.".to_string()));
+        let composed = prompts.compose().unwrap();
+        assert!(composed.find("Personality:").unwrap() < composed.find("Current scene:").unwrap());
+
+        assert!(PromptConfig::default().compose().is_none());
+    }
+
+    #[test]
+    fn test_partition_memories_by_visibility() {
+        use crate::memory::MemoryCategory;
+
+        let memories = vec![
+            Memory::new(MemoryCategory::Semantic, "The inn serves stew", 0.5, None),
+            Memory::new(MemoryCategory::Semantic, "I plan to sell the inn", 0.5, None)
+                .with_visibility(MemoryVisibility::Private),
+            Memory::new_emotional(MemoryCategory::Episodic, "I poisoned the mayor", 0.9, -0.8, 0.9, None)
+                .with_visibility(MemoryVisibility::Secret),
+        ];
+
+        let (quotable, guidance) = partition_memories(&memories);
+        assert_eq!(quotable.len(), 1);
+        assert_eq!(quotable[0].content, "The inn serves stew");
+
+        let guidance = guidance.unwrap();
+        assert!(guidance.contains("I plan to sell the inn"));
+        assert!(!guidance.contains("poisoned"));
+        assert!(guidance.contains("darkens your mood"));
+    }
+}
@@ -0,0 +1,368 @@
+//! Rule-based routing between inference providers
+//!
+//! With routes configured, the inference engine picks a provider for each
+//! request instead of using its single primary provider: the first rule
+//! matching the input selects a route, and the `fallback` chain is tried in
+//! order when that route fails or is unavailable. A route is unavailable
+//! when its endpoint, key or local model is missing, and for
+//! `failure_cooldown_ms` after it fails.
+//!
+//! ```yaml
+//! inference:
+//!   routing:
+//!     routes:
+//!       - name: fast
+//!         provider: cloud
+//!         model: llama-3.1-8b-instant
+//!         api_endpoint: https://api.groq.com/openai/v1/chat/completions
+//!         api_key_env: GROQ_API_KEY
+//!       - name: reasoning
+//!         provider: cloud
+//!         model: gpt-4o
+//!       - name: offline
+//!         provider: local
+//!     rules:
+//!       - route: fast
+//!         max_input_chars: 80
+//!       - route: reasoning
+//!         intents: [question, demand]
+//!         min_input_words: 12
+//!     fallback: [reasoning, fast, offline]
+//! ```
+//!
+//! Rule conditions are combined with AND; a rule without conditions always
+//! matches. Intents match the type or configured label the agent stores
+//! under the `input_intent` context key. Cloud routes without their own
+//! endpoint or key use the ones configured on the inference engine.
+
+use std::collections::HashMap;
+use std::env;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::agent::AgentContext;
+use crate::config::InferenceConfig;
+use crate::inference::ProviderType;
+use crate::oxyde_game::intent::INPUT_INTENT_KEY;
+use crate::timer::Instant;
+use crate::{OxydeError, Result};
+
+/// A provider requests can be routed to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProviderRoute {
+    /// Name rules and the fallback chain refer to the route by
+    pub name: String,
+
+    /// Kind of provider
+    pub provider: ProviderType,
+
+    /// Model sent to a cloud provider; the engine's default when unset
+    #[serde(default)]
+    pub model: Option<String>,
+
+    /// Cloud endpoint; the engine's `api_endpoint` when unset
+    #[serde(default)]
+    pub api_endpoint: Option<String>,
+
+    /// Environment variable holding the cloud API key; the engine's key
+    /// when unset
+    #[serde(default)]
+    pub api_key_env: Option<String>,
+}
+
+impl ProviderRoute {
+    /// Cloud endpoint the route sends requests to
+    pub fn endpoint<'a>(&'a self, inference: &'a InferenceConfig) -> Option<&'a str> {
+        self.api_endpoint.as_deref().or(inference.api_endpoint.as_deref())
+    }
+
+    /// Cloud API key the route authenticates with
+    pub fn api_key(&self, inference: &InferenceConfig) -> Option<String> {
+        match &self.api_key_env {
+            Some(var) => env::var(var).ok(),
+            None => inference.resolve_api_key().unwrap_or_else(|e| {
+                log::warn!("Route {} has no API key: {}", self.name, e);
+                None
+            }),
+        }
+    }
+
+    /// Whether the route has what it needs to serve requests
+    pub fn is_configured(&self, inference: &InferenceConfig) -> bool {
+        match self.provider {
+            ProviderType::Local => inference.local_model_path.is_some(),
+            ProviderType::Cloud => self.endpoint(inference).is_some() && self.api_key(inference).is_some(),
+            ProviderType::Mock => true,
+        }
+    }
+}
+
+/// Conditions selecting a route for a request
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RoutingRule {
+    /// Route to use when the rule matches
+    pub route: String,
+
+    /// Match inputs of at most this many characters
+    #[serde(default)]
+    pub max_input_chars: Option<usize>,
+
+    /// Match inputs of at least this many words
+    #[serde(default)]
+    pub min_input_words: Option<usize>,
+
+    /// Match inputs with any of these intent types or labels
+    #[serde(default)]
+    pub intents: Vec<String>,
+
+    /// Match inputs containing any of these words, ignoring case
+    #[serde(default)]
+    pub keywords: Vec<String>,
+}
+
+impl RoutingRule {
+    /// Whether the rule applies to an input
+    pub fn matches(&self, input: &str, context: &AgentContext) -> bool {
+        if self.max_input_chars.is_some_and(|max| input.chars().count() > max) {
+            return false;
+        }
+        if self.min_input_words.is_some_and(|min| input.split_whitespace().count() < min) {
+            return false;
+        }
+        if !self.intents.is_empty() {
+            let intent = context.get(INPUT_INTENT_KEY);
+            let names = ["type", "label"].map(|field| intent.and_then(|intent| intent[field].as_str()));
+            if !self.intents.iter().any(|wanted| names.contains(&Some(wanted.as_str()))) {
+                return false;
+            }
+        }
+        if !self.keywords.is_empty() {
+            let input = input.to_lowercase();
+            if !self.keywords.iter().any(|keyword| input.contains(&keyword.to_lowercase())) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Configuration for routing requests between providers
+///
+/// Routing is disabled when no routes are configured.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoutingConfig {
+    /// Providers requests can be routed to
+    #[serde(default)]
+    pub routes: Vec<ProviderRoute>,
+
+    /// Rules selecting a route, first match wins
+    #[serde(default)]
+    pub rules: Vec<RoutingRule>,
+
+    /// Routes tried in order after the selected one; all routes in
+    /// declaration order when empty
+    #[serde(default)]
+    pub fallback: Vec<String>,
+
+    /// How long a failed route is skipped, in milliseconds
+    #[serde(default = "default_failure_cooldown_ms")]
+    pub failure_cooldown_ms: u64,
+}
+
+fn default_failure_cooldown_ms() -> u64 {
+    30_000
+}
+
+impl Default for RoutingConfig {
+    fn default() -> Self {
+        Self {
+            routes: Vec::new(),
+            rules: Vec::new(),
+            fallback: Vec::new(),
+            failure_cooldown_ms: default_failure_cooldown_ms(),
+        }
+    }
+}
+
+impl RoutingConfig {
+    /// Whether requests are routed
+    pub fn is_enabled(&self) -> bool {
+        !self.routes.is_empty()
+    }
+
+    /// Validate that rules and the fallback chain name configured routes
+    pub fn validate(&self) -> Result<()> {
+        for (index, route) in self.routes.iter().enumerate() {
+            if route.name.is_empty() {
+                return Err(OxydeError::ConfigurationError("Routing route names cannot be empty".to_string()));
+            }
+            if self.routes[..index].iter().any(|other| other.name == route.name) {
+                return Err(OxydeError::ConfigurationError(format!(
+                    "Routing route {} is defined twice",
+                    route.name
+                )));
+            }
+        }
+
+        let referenced = self.rules.iter().map(|rule| &rule.route).chain(&self.fallback);
+        for name in referenced {
+            if !self.routes.iter().any(|route| &route.name == name) {
+                return Err(OxydeError::ConfigurationError(format!(
+                    "Routing refers to unknown route {}",
+                    name
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Picks the providers to try for each request
+#[derive(Debug)]
+pub struct ProviderRouter {
+    config: RoutingConfig,
+    /// When each route last failed
+    failures: Mutex<HashMap<String, Instant>>,
+}
+
+impl ProviderRouter {
+    /// Create a router, or `None` if no routes are configured
+    pub fn new(config: &RoutingConfig) -> Option<Self> {
+        config.is_enabled().then(|| Self {
+            config: config.clone(),
+            failures: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Routes to try for a request, in order
+    ///
+    /// The route of the first matching rule comes first, followed by the
+    /// fallback chain. Unconfigured routes and routes in their failure
+    /// cooldown are left out.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - Player input the request answers
+    /// * `context` - Request context, holding the input intent
+    /// * `inference` - Engine configuration unset route fields default to
+    /// * `now` - Current time, for failure cooldowns
+    pub fn plan(
+        &self,
+        input: &str,
+        context: &AgentContext,
+        inference: &InferenceConfig,
+        now: Instant,
+    ) -> Vec<ProviderRoute> {
+        let selected = self
+            .config
+            .rules
+            .iter()
+            .find(|rule| rule.matches(input, context))
+            .map(|rule| &rule.route);
+        let chain: Vec<&String> = if self.config.fallback.is_empty() {
+            self.config.routes.iter().map(|route| &route.name).collect()
+        } else {
+            self.config.fallback.iter().collect()
+        };
+
+        let cooldown = Duration::from_millis(self.config.failure_cooldown_ms);
+        let failures = self.failures.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut plan: Vec<ProviderRoute> = Vec::new();
+        for name in selected.into_iter().chain(chain) {
+            let Some(route) = self.config.routes.iter().find(|route| &route.name == name) else {
+                continue;
+            };
+            let cooling_down = failures
+                .get(name)
+                .is_some_and(|failed_at| now.saturating_duration_since(*failed_at) < cooldown);
+            if !cooling_down && route.is_configured(inference) && !plan.iter().any(|planned| &planned.name == name) {
+                plan.push(route.clone());
+            }
+        }
+        plan
+    }
+
+    /// Skip a route for the failure cooldown
+    pub fn record_failure(&self, route: &str, now: Instant) {
+        self.lock_failures().insert(route.to_string(), now);
+    }
+
+    /// Make a route available again after it served a request
+    pub fn record_success(&self, route: &str) {
+        self.lock_failures().remove(route);
+    }
+
+    fn lock_failures(&self) -> std::sync::MutexGuard<'_, HashMap<String, Instant>> {
+        self.failures.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(name: &str, provider: ProviderType) -> ProviderRoute {
+        ProviderRoute {
+            name: name.to_string(),
+            provider,
+            model: None,
+            api_endpoint: None,
+            api_key_env: None,
+        }
+    }
+
+    fn names(plan: &[ProviderRoute]) -> Vec<&str> {
+        plan.iter().map(|route| route.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_plans_rule_route_then_available_fallbacks() {
+        let config = RoutingConfig {
+            routes: vec![
+                route("fast", ProviderType::Mock),
+                route("reasoning", ProviderType::Mock),
+                route("offline", ProviderType::Local),
+            ],
+            rules: vec![
+                RoutingRule {
+                    route: "fast".to_string(),
+                    max_input_chars: Some(20),
+                    ..Default::default()
+                },
+                RoutingRule {
+                    route: "reasoning".to_string(),
+                    intents: vec!["question".to_string()],
+                    ..Default::default()
+                },
+            ],
+            fallback: vec!["offline".to_string(), "fast".to_string()],
+            ..Default::default()
+        };
+        config.validate().unwrap();
+        let router = ProviderRouter::new(&config).unwrap();
+        let inference = InferenceConfig::default();
+        let now = Instant::now();
+
+        // The local route has no model path, so it is never planned
+        assert_eq!(names(&router.plan("Hi there", &AgentContext::new(), &inference, now)), ["fast"]);
+
+        let mut context = AgentContext::new();
+        context.insert(INPUT_INTENT_KEY.to_string(), serde_json::json!({"type": "question", "label": null}));
+        let long = "Where did the caravan go after it left the mill?";
+        assert_eq!(names(&router.plan(long, &context, &inference, now)), ["reasoning", "fast"]);
+        assert_eq!(names(&router.plan(long, &AgentContext::new(), &inference, now)), ["fast"]);
+
+        router.record_failure("reasoning", now);
+        assert_eq!(names(&router.plan(long, &context, &inference, now)), ["fast"]);
+        let later = now + Duration::from_secs(31);
+        assert_eq!(names(&router.plan(long, &context, &inference, later)), ["reasoning", "fast"]);
+
+        let invalid = RoutingConfig {
+            fallback: vec!["missing".to_string()],
+            ..config
+        };
+        assert!(invalid.validate().is_err());
+    }
+}
@@ -0,0 +1,269 @@
+//! PII redaction for player input
+//!
+//! Player input can contain personal information that should not reach
+//! third-party APIs or persisted memories. The [`Redactor`] replaces email
+//! addresses, phone numbers and configured real names with numbered
+//! placeholders such as `[EMAIL_1]` before cloud inference and memory
+//! storage. The [`Redaction`] keeps the mapping, so placeholders echoed in a
+//! cloud response can be restored before the player sees it.
+//!
+//! Memories stored with placeholders carry them into later requests, where
+//! their numbering means nothing: `[NAME_1]` in a memory need not be the
+//! `[NAME_1]` of the current input. Placeholders already in a request are
+//! reserved with [`Redactor::reserve`], so new values are numbered past them
+//! and only the request's own placeholders are restored.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Configuration for PII redaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactionConfig {
+    /// Whether redaction is enabled
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Redact email addresses
+    #[serde(default = "default_true")]
+    pub emails: bool,
+
+    /// Redact phone numbers
+    #[serde(default = "default_true")]
+    pub phone_numbers: bool,
+
+    /// Real names to redact, matched as whole words regardless of case
+    #[serde(default)]
+    pub names: Vec<String>,
+
+    /// Store memories with placeholders instead of the original text
+    #[serde(default = "default_true")]
+    pub redact_memories: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            emails: true,
+            phone_numbers: true,
+            names: Vec::new(),
+            redact_memories: true,
+        }
+    }
+}
+
+/// Kind of redacted value, used as the placeholder label
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PiiKind {
+    /// Email address
+    Email,
+    /// Phone number
+    Phone,
+    /// Configured real name
+    Name,
+}
+
+impl PiiKind {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Email => "EMAIL",
+            Self::Phone => "PHONE",
+            Self::Name => "NAME",
+        }
+    }
+}
+
+/// Redacted text and the placeholders needed to restore it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Redaction {
+    text: String,
+    placeholders: Vec<(String, String)>,
+    /// Placeholders that were already in the text, whose values are unknown
+    reserved: Vec<String>,
+}
+
+impl Redaction {
+    /// The redacted text
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Whether anything was redacted
+    pub fn is_empty(&self) -> bool {
+        self.placeholders.is_empty()
+    }
+
+    /// Placeholders and the original values they replace
+    pub fn placeholders(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.placeholders.iter().map(|(p, o)| (p.as_str(), o.as_str()))
+    }
+
+    /// Replace placeholders in text, such as a response, with the original values
+    ///
+    /// Reserved placeholders are left as they are.
+    pub fn restore(&self, text: &str) -> String {
+        self.placeholders
+            .iter()
+            .fold(text.to_string(), |text, (placeholder, original)| text.replace(placeholder, original))
+    }
+
+    /// Next unused placeholder for a kind, given its `[KIND_` prefix
+    fn next_placeholder(&self, prefix: &str) -> String {
+        (1..)
+            .map(|n| format!("{}{}]", prefix, n))
+            .find(|placeholder| {
+                !self.reserved.contains(placeholder) && !self.placeholders.iter().any(|(p, _)| p == placeholder)
+            })
+            .expect("placeholder numbers are unbounded")
+    }
+}
+
+/// Replaces PII with reversible placeholders
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    patterns: Vec<(PiiKind, Regex)>,
+    /// Matches placeholders of the configured kinds
+    placeholder: Regex,
+}
+
+impl Redactor {
+    /// Create a redactor for the configured kinds of PII
+    pub fn new(config: &RedactionConfig) -> Self {
+        let mut patterns = Vec::new();
+        if config.emails {
+            patterns.push((
+                PiiKind::Email,
+                Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}").expect("valid email pattern"),
+            ));
+        }
+        if config.phone_numbers {
+            patterns.push((
+                PiiKind::Phone,
+                Regex::new(r"(?:\+\d{1,3}[\s.-]?)?(?:\(\d{3}\)|\b\d{3})[\s.-]?\d{3}[\s.-]?\d{4}\b")
+                    .expect("valid phone pattern"),
+            ));
+        }
+
+        let mut names: Vec<&str> = config
+            .names
+            .iter()
+            .map(|name| name.trim())
+            .filter(|name| !name.is_empty())
+            .collect();
+        if !names.is_empty() {
+            // Longest first so "Jane Doe" wins over "Jane"
+            names.sort_by_key(|name| std::cmp::Reverse(name.len()));
+            let alternatives: Vec<String> = names.iter().map(|name| regex::escape(name)).collect();
+            let pattern = format!(r"(?i)\b(?:{})\b", alternatives.join("|"));
+            patterns.push((PiiKind::Name, Regex::new(&pattern).expect("escaped names form a valid pattern")));
+        }
+
+        let labels: Vec<&str> = patterns.iter().map(|(kind, _)| kind.label()).collect();
+        let placeholder = Regex::new(&format!(r"\[(?:{})_\d+\]", labels.join("|")))
+            .expect("labels form a valid pattern");
+
+        Self { patterns, placeholder }
+    }
+
+    /// Create a redactor if redaction is enabled
+    pub fn from_config(config: &RedactionConfig) -> Option<Self> {
+        config.enabled.then(|| Self::new(config))
+    }
+
+    /// Redact text
+    ///
+    /// Each distinct value gets its own numbered placeholder per kind, and
+    /// repeated values reuse it.
+    pub fn redact(&self, text: &str) -> Redaction {
+        let mut redaction = Redaction::default();
+        redaction.text = self.redact_with(text, &mut redaction);
+        redaction
+    }
+
+    /// Reserve the placeholders already in text, such as a memory stored
+    /// redacted, so new values are not given the same ones
+    ///
+    /// Reserve every text of a request before redacting any of them.
+    pub fn reserve(&self, text: &str, redaction: &mut Redaction) {
+        for found in self.placeholder.find_iter(text) {
+            let found = found.as_str();
+            let known = redaction.reserved.iter().any(|p| p == found)
+                || redaction.placeholders.iter().any(|(p, _)| p == found);
+            if !known {
+                redaction.reserved.push(found.to_string());
+            }
+        }
+    }
+
+    /// Redact text, sharing placeholders with an earlier redaction
+    ///
+    /// Use this when several texts go into the same request, so a value
+    /// gets the same placeholder everywhere and a single [`Redaction`]
+    /// restores the response. The redaction's own text is left unchanged.
+    /// Placeholders already in the text are reserved first.
+    ///
+    /// # Returns
+    ///
+    /// The redacted text
+    pub fn redact_with(&self, text: &str, redaction: &mut Redaction) -> String {
+        self.reserve(text, redaction);
+        let mut text = text.to_string();
+        for (kind, pattern) in &self.patterns {
+            let prefix = format!("[{}_", kind.label());
+            text = pattern
+                .replace_all(&text, |captures: &regex::Captures| {
+                    let original = &captures[0];
+                    let existing = redaction
+                        .placeholders
+                        .iter()
+                        .find(|(p, o)| p.starts_with(&prefix) && o.eq_ignore_ascii_case(original));
+                    if let Some((placeholder, _)) = existing {
+                        return placeholder.clone();
+                    }
+                    let placeholder = redaction.next_placeholder(&prefix);
+                    redaction.placeholders.push((placeholder.clone(), original.to_string()));
+                    placeholder
+                })
+                .into_owned();
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_and_restore() {
+        let redactor = Redactor::new(&RedactionConfig {
+            enabled: true,
+            names: vec!["Jane Doe".to_string(), "Jane".to_string()],
+            ..Default::default()
+        });
+
+        let redaction = redactor.redact(
+            "I'm jane doe, mail me at jane.doe@example.com or call +1 555-123-4567. Jane says hi, JANE DOE too.",
+        );
+        assert_eq!(
+            redaction.text(),
+            "I'm [NAME_1], mail me at [EMAIL_1] or call [PHONE_1]. [NAME_2] says hi, [NAME_1] too."
+        );
+
+        let response = redaction.restore("Hello [NAME_1], I'll remember [EMAIL_1].");
+        assert_eq!(response, "Hello jane doe, I'll remember jane.doe@example.com.");
+    }
+
+    #[test]
+    fn test_game_numbers_are_kept() {
+        let redactor = Redactor::new(&RedactionConfig::default());
+        let redaction = redactor.redact("Sell me 3 swords for 1500 gold, 20 each");
+        assert!(redaction.is_empty());
+        assert_eq!(redaction.text(), "Sell me 3 swords for 1500 gold, 20 each");
+    }
+}
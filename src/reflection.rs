@@ -0,0 +1,195 @@
+//! Periodic self-reflection
+//!
+//! Individual episodic memories ("the player gave me bread", "the player
+//! asked about my brother") are too fine-grained to keep a character coherent
+//! over a long play session. With reflection enabled, every
+//! `every_n_interactions` inputs the agent asks the model to summarize its
+//! most recent memories into a few higher-level insights, such as "The player
+//! has been consistently kind to me", and stores them as important semantic
+//! memories tagged `reflection`.
+//!
+//! ```yaml
+//! memory:
+//!   reflection:
+//!     enabled: true
+//!     every_n_interactions: 10
+//!     window: 20
+//!     max_insights: 3
+//!     max_tokens: 200
+//! ```
+//!
+//! Reflections run at ambient priority so they never hold up player-facing
+//! requests in the inference scheduler.
+
+use serde::{Deserialize, Serialize};
+
+use crate::memory::Memory;
+use crate::{OxydeError, Result};
+
+/// Tag on memories produced by reflection
+pub const REFLECTION_TAG: &str = "reflection";
+
+/// Configuration for periodic reflection
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReflectionConfig {
+    /// Whether the agent reflects periodically
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Inputs processed between reflections
+    #[serde(default = "default_every_n_interactions")]
+    pub every_n_interactions: u32,
+
+    /// Number of recent memories summarized by each reflection
+    #[serde(default = "default_window")]
+    pub window: usize,
+
+    /// Maximum insights stored per reflection
+    #[serde(default = "default_max_insights")]
+    pub max_insights: usize,
+
+    /// Maximum tokens the model may generate per reflection
+    #[serde(default = "default_max_tokens")]
+    pub max_tokens: usize,
+
+    /// Importance of stored insights (0.0 - 1.0)
+    #[serde(default = "default_importance")]
+    pub importance: f64,
+
+    /// Instructions for the model; `{name}` and `{max_insights}` are filled in
+    #[serde(default = "default_instructions")]
+    pub instructions: String,
+}
+
+fn default_every_n_interactions() -> u32 {
+    10
+}
+
+fn default_window() -> usize {
+    20
+}
+
+fn default_max_insights() -> usize {
+    3
+}
+
+fn default_max_tokens() -> usize {
+    200
+}
+
+fn default_importance() -> f64 {
+    0.9
+}
+
+fn default_instructions() -> String {
+    "You are {name}, reflecting on recent events. Summarize them into at most {max_insights} \
+     high-level insights about the people you met and how things are going, written in the \
+     first person, one per line."
+        .to_string()
+}
+
+impl Default for ReflectionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            every_n_interactions: default_every_n_interactions(),
+            window: default_window(),
+            max_insights: default_max_insights(),
+            max_tokens: default_max_tokens(),
+            importance: default_importance(),
+            instructions: default_instructions(),
+        }
+    }
+}
+
+impl ReflectionConfig {
+    /// Validate the reflection configuration
+    pub fn validate(&self) -> Result<()> {
+        if self.every_n_interactions == 0 {
+            return Err(OxydeError::ConfigurationError(
+                "Reflection every_n_interactions must be greater than 0".to_string(),
+            ));
+        }
+        if self.window == 0 || self.max_insights == 0 || self.max_tokens == 0 {
+            return Err(OxydeError::ConfigurationError(
+                "Reflection window, max_insights and max_tokens must be greater than 0".to_string(),
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.importance) {
+            return Err(OxydeError::ConfigurationError(format!(
+                "Reflection importance must be between 0.0 and 1.0, got {}",
+                self.importance
+            )));
+        }
+        Ok(())
+    }
+
+    /// Build the system prompt and input asking the model to reflect
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the agent
+    /// * `memories` - Memories to summarize, oldest first
+    pub fn prompt(&self, name: &str, memories: &[Memory]) -> (String, String) {
+        let system_prompt = self
+            .instructions
+            .replace("{name}", name)
+            .replace("{max_insights}", &self.max_insights.to_string());
+        let events: Vec<String> = memories.iter().map(|m| format!("- {}", m.content)).collect();
+        (system_prompt, format!("Recent events, oldest first:\n{}", events.join("\n")))
+    }
+
+    /// Split a model response into at most `max_insights` insights
+    ///
+    /// Each non-empty line is an insight; list markers are removed and
+    /// repeated lines are dropped.
+    pub fn parse_insights(&self, text: &str) -> Vec<String> {
+        let mut insights: Vec<String> = Vec::new();
+        for line in text.lines() {
+            let insight = strip_list_marker(line.trim());
+            if !insight.is_empty() && !insights.iter().any(|i| i == insight) {
+                insights.push(insight.to_string());
+            }
+            if insights.len() == self.max_insights {
+                break;
+            }
+        }
+        insights
+    }
+}
+
+/// Remove a leading `-`, `*`, `•`, `1.` or `1)` list marker
+fn strip_list_marker(line: &str) -> &str {
+    let rest = line.trim_start_matches(['-', '*', '•']);
+    let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let rest = match rest[digits..].chars().next() {
+        Some('.') | Some(')') if digits > 0 => &rest[digits + 1..],
+        _ => rest,
+    };
+    rest.trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::MemoryCategory;
+
+    #[test]
+    fn test_builds_prompt_and_parses_insights() {
+        let config = ReflectionConfig {
+            max_insights: 2,
+            ..Default::default()
+        };
+        let memories = [
+            Memory::new(MemoryCategory::Episodic, "The player gave me bread", 1.0, None),
+            Memory::new(MemoryCategory::Episodic, "The player fixed my cart", 1.0, None),
+        ];
+        let (system_prompt, input) = config.prompt("Mira", &memories);
+        assert!(system_prompt.starts_with("You are Mira, reflecting") && system_prompt.contains("at most 2"));
+        assert_eq!(input, "Recent events, oldest first:\n- The player gave me bread\n- The player fixed my cart");
+
+        let insights = config.parse_insights("1. The player is kind to me.\n\n- The player is kind to me.\n* I owe them.\nExtra");
+        assert_eq!(insights, vec!["The player is kind to me.", "I owe them."]);
+        assert!(ReflectionConfig { every_n_interactions: 0, ..Default::default() }.validate().is_err());
+    }
+}
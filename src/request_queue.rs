@@ -0,0 +1,300 @@
+//! Request queueing for overlapping agent inputs
+//!
+//! Engines may call `process_input` again before the previous call has
+//! finished. The [`RequestQueue`] serializes those calls so state transitions
+//! and responses never interleave, applying the configured
+//! [`ConcurrencyPolicy`] to inputs that arrive while the agent is busy.
+//! Waiting inputs are served in arrival order.
+
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::{watch, Mutex as TurnLock, MutexGuard};
+
+use crate::config::{ConcurrencyPolicy, RequestQueueConfig};
+use crate::timer::Instant;
+use crate::{OxydeError, Result};
+
+/// Counters describing how overlapping inputs were handled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestQueueStats {
+    /// Inputs currently waiting behind the one being processed
+    pub depth: usize,
+    /// Highest depth observed
+    pub peak_depth: usize,
+    /// Inputs that had to wait for an earlier input
+    pub queued: u64,
+    /// Inputs answered with the response to a later input
+    pub coalesced: u64,
+    /// Inputs rejected because the agent was busy
+    pub rejected: u64,
+    /// Total time inputs spent waiting for the agent's turn, in milliseconds
+    pub wait_ms: u64,
+    /// Longest time a single input waited for the agent's turn, in milliseconds
+    pub max_wait_ms: u64,
+}
+
+/// Outcome shared with callers whose input was coalesced
+type SharedResponse = Option<std::result::Result<String, String>>;
+
+/// What to do with an incoming input
+pub(crate) enum Slot<'a> {
+    /// Run the pipeline for `input` while holding the permit
+    Run(Permit<'a>),
+    /// Wait for the response to a later input that replaced this one
+    Coalesced(watch::Receiver<SharedResponse>),
+}
+
+/// Exclusive right to process an input; the next input may start once
+/// it is dropped
+pub(crate) struct Permit<'a> {
+    /// Input to process; differs from the caller's input when coalesced
+    pub(crate) input: String,
+    queue: &'a RequestQueue,
+    shared: Option<watch::Sender<SharedResponse>>,
+    _turn: MutexGuard<'a, ()>,
+}
+
+impl Permit<'_> {
+    /// Publish the outcome to any callers whose input was coalesced into this one
+    pub(crate) fn finish(&self, result: &Result<String>) {
+        if let Some(sender) = &self.shared {
+            let outcome = match result {
+                Ok(response) => Ok(response.clone()),
+                Err(e) => Err(e.to_string()),
+            };
+            let _ = sender.send(Some(outcome));
+        }
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        let mut state = self.queue.lock_state();
+        state.busy = state.stats.depth > 0 || state.coalesced.is_some();
+    }
+}
+
+/// An input waiting for its turn; undoes its bookkeeping if the caller
+/// gives up before the turn arrives
+struct Waiting<'a> {
+    queue: &'a RequestQueue,
+    leader: bool,
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        let mut state = self.queue.lock_state();
+        state.stats.depth = state.stats.depth.saturating_sub(1);
+        if self.leader {
+            // Coalesced followers see the closed channel and fail
+            state.coalesced = None;
+        }
+    }
+}
+
+struct CoalescedInput {
+    input: String,
+    sender: watch::Sender<SharedResponse>,
+}
+
+#[derive(Default)]
+struct QueueState {
+    busy: bool,
+    coalesced: Option<CoalescedInput>,
+    stats: RequestQueueStats,
+}
+
+/// Serializes agent inputs according to the configured policy
+#[derive(Default)]
+pub(crate) struct RequestQueue {
+    config: RequestQueueConfig,
+    turn: TurnLock<()>,
+    state: Mutex<QueueState>,
+}
+
+impl std::fmt::Debug for RequestQueue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RequestQueue")
+            .field("config", &self.config)
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+impl RequestQueue {
+    /// Create a queue with the given configuration
+    pub(crate) fn new(config: RequestQueueConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    /// Wait for the agent's turn to process `input`
+    ///
+    /// # Returns
+    ///
+    /// The slot to process or wait on, or a busy error when the policy
+    /// rejects the input
+    pub(crate) async fn admit(&self, input: &str) -> Result<Slot<'_>> {
+        let mut shared = None;
+        let waiting = {
+            let mut state = self.lock_state();
+            let waiting = if state.busy {
+                match self.config.policy {
+                    ConcurrencyPolicy::Reject => return Err(self.reject(&mut state)),
+                    ConcurrencyPolicy::Coalesce => {
+                        if let Some(coalesced) = state.coalesced.as_mut() {
+                            coalesced.input = input.to_string();
+                            let receiver = coalesced.sender.subscribe();
+                            state.stats.coalesced += 1;
+                            return Ok(Slot::Coalesced(receiver));
+                        }
+                        let (sender, _) = watch::channel(None);
+                        state.coalesced = Some(CoalescedInput {
+                            input: input.to_string(),
+                            sender: sender.clone(),
+                        });
+                        shared = Some(sender);
+                    }
+                    ConcurrencyPolicy::Queue => {
+                        if self.config.max_depth > 0 && state.stats.depth >= self.config.max_depth {
+                            return Err(self.reject(&mut state));
+                        }
+                    }
+                }
+                state.stats.queued += 1;
+                state.stats.depth += 1;
+                state.stats.peak_depth = state.stats.peak_depth.max(state.stats.depth);
+                Some(Waiting {
+                    queue: self,
+                    leader: shared.is_some(),
+                })
+            } else {
+                None
+            };
+            state.busy = true;
+            waiting
+        };
+
+        let waiting_since = Instant::now();
+        let turn = self.turn.lock().await;
+        if waiting.is_some() {
+            let waited = waiting_since.elapsed().as_millis() as u64;
+            let mut state = self.lock_state();
+            state.stats.wait_ms += waited;
+            state.stats.max_wait_ms = state.stats.max_wait_ms.max(waited);
+        }
+
+        // Take the latest coalesced input before the waiting guard clears the slot
+        let mut input = input.to_string();
+        if shared.is_some() {
+            if let Some(coalesced) = self.lock_state().coalesced.take() {
+                input = coalesced.input;
+            }
+        }
+        drop(waiting);
+
+        Ok(Slot::Run(Permit {
+            input,
+            queue: self,
+            shared,
+            _turn: turn,
+        }))
+    }
+
+    /// Get a snapshot of the queue counters
+    pub(crate) fn stats(&self) -> RequestQueueStats {
+        self.lock_state().stats
+    }
+
+    fn reject(&self, state: &mut QueueState) -> OxydeError {
+        state.stats.rejected += 1;
+        OxydeError::Busy("Agent is already processing an input".to_string())
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, QueueState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use super::*;
+
+    fn queue(policy: ConcurrencyPolicy, max_depth: usize) -> Arc<RequestQueue> {
+        Arc::new(RequestQueue::new(RequestQueueConfig { policy, max_depth }))
+    }
+
+    async fn run(queue: Arc<RequestQueue>, input: &str, log: Arc<Mutex<Vec<String>>>) -> Result<String> {
+        match queue.admit(input).await? {
+            Slot::Run(permit) => {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                log.lock().unwrap().push(permit.input.clone());
+                let result = Ok(format!("re: {}", permit.input));
+                permit.finish(&result);
+                result
+            }
+            Slot::Coalesced(mut receiver) => {
+                let outcome = receiver.wait_for(|r| r.is_some()).await.unwrap().clone();
+                outcome.unwrap().map_err(OxydeError::RequestError)
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_queue_preserves_order_and_limits_depth() {
+        let queue = queue(ConcurrencyPolicy::Queue, 2);
+        let log = Arc::new(Mutex::new(Vec::new()));
+
+        let mut handles = Vec::new();
+        for input in ["a", "b", "c", "d"] {
+            handles.push(tokio::spawn(run(queue.clone(), input, log.clone())));
+            tokio::time::sleep(Duration::from_millis(2)).await;
+        }
+        let results: Vec<_> = futures::future::join_all(handles).await.into_iter().map(|r| r.unwrap()).collect();
+
+        assert_eq!(*log.lock().unwrap(), vec!["a", "b", "c"]);
+        assert!(matches!(results[3], Err(OxydeError::Busy(_))));
+        let stats = queue.stats();
+        assert_eq!((stats.depth, stats.peak_depth, stats.queued, stats.rejected), (0, 2, 2, 1));
+    }
+
+    #[tokio::test]
+    async fn test_coalesce_answers_waiters_with_latest_input() {
+        let queue = queue(ConcurrencyPolicy::Coalesce, 0);
+        let log = Arc::new(Mutex::new(Vec::new()));
+
+        let mut handles = Vec::new();
+        for input in ["a", "b", "c"] {
+            handles.push(tokio::spawn(run(queue.clone(), input, log.clone())));
+            tokio::time::sleep(Duration::from_millis(2)).await;
+        }
+        let results: Vec<_> = futures::future::join_all(handles)
+            .await
+            .into_iter()
+            .map(|r| r.unwrap().unwrap())
+            .collect();
+
+        assert_eq!(*log.lock().unwrap(), vec!["a", "c"]);
+        assert_eq!(results, vec!["re: a", "re: c", "re: c"]);
+        assert_eq!(queue.stats().coalesced, 1);
+    }
+
+    #[tokio::test]
+    async fn test_reject_while_busy() {
+        let queue = queue(ConcurrencyPolicy::Reject, 0);
+        let Slot::Run(permit) = queue.admit("a").await.unwrap() else {
+            panic!("idle queue should run the input");
+        };
+        assert!(matches!(queue.admit("b").await, Err(OxydeError::Busy(_))));
+
+        drop(permit);
+        assert!(matches!(queue.admit("c").await, Ok(Slot::Run(_))));
+        assert_eq!(queue.stats().rejected, 1);
+    }
+}
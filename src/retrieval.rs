@@ -0,0 +1,552 @@
+//! Hybrid memory retrieval scoring
+//!
+//! A memory's relevance to a query blends several signals, each weighted by
+//! [`RetrievalWeights`]:
+//!
+//! - `keyword`: BM25 match between the query and the memory's content and tags
+//! - `vector`: cosine similarity of their embeddings, when both have one
+//! - `importance`: the memory's importance
+//! - `emotion`: the memory's emotional intensity
+//!
+//! Without embeddings the vector weight moves to the keyword score. The
+//! blended relevance is then discounted by age: `recency` sets how strongly
+//! time decay and recall frequency apply, from 0.0 (ignored) to 1.0.
+//!
+//! ```yaml
+//! memory:
+//!   retrieval:
+//!     keyword: 0.4
+//!     vector: 0.2
+//!     importance: 0.3
+//!     recency: 0.5
+//!     emotion: 0.1
+//! ```
+//!
+//! With [`MoodCongruence`] enabled, memories whose emotional valence matches
+//! the agent's current mood are recalled more readily, as people recall sad
+//! memories more easily when sad:
+//!
+//! ```yaml
+//! memory:
+//!   mood_congruence:
+//!     enabled: true
+//!     strength: 0.5
+//! ```
+//!
+//! [`RetrievalBenchmark`] measures how well a set of weights retrieves the
+//! memories labeled relevant to sample queries, and picks the best of
+//! several candidates.
+
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::memory::{cosine_similarity, Memory};
+use crate::{OxydeError, Result};
+
+/// BM25 term frequency saturation
+const BM25_K1: f64 = 1.2;
+
+/// BM25 document length normalization
+const BM25_B: f64 = 0.75;
+
+/// Words too common to say anything about relevance
+const STOPWORDS: &[&str] = &[
+    "a", "about", "an", "and", "are", "as", "at", "be", "been", "but", "by", "can", "did", "do", "does", "for",
+    "from", "had", "has", "have", "he", "her", "him", "his", "how", "i", "if", "in", "into", "is", "it", "its",
+    "me", "my", "of", "on", "or", "our", "she", "so", "that", "the", "their", "them", "there", "they", "this",
+    "to", "was", "we", "were", "what", "when", "where", "which", "who", "why", "will", "with", "you", "your",
+];
+
+/// Weights of the signals blended into a memory's relevance
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RetrievalWeights {
+    /// Weight of the BM25 keyword match
+    #[serde(default = "default_keyword")]
+    pub keyword: f64,
+
+    /// Weight of embedding similarity; added to `keyword` when the query or
+    /// memory has no embedding
+    #[serde(default = "default_vector")]
+    pub vector: f64,
+
+    /// Weight of the memory's importance
+    #[serde(default = "default_importance")]
+    pub importance: f64,
+
+    /// How strongly age discounts relevance, from 0.0 to 1.0
+    #[serde(default = "default_recency")]
+    pub recency: f64,
+
+    /// Weight of the memory's emotional intensity
+    #[serde(default = "default_emotion")]
+    pub emotion: f64,
+}
+
+fn default_keyword() -> f64 {
+    0.3
+}
+
+fn default_vector() -> f64 {
+    0.3
+}
+
+fn default_importance() -> f64 {
+    0.3
+}
+
+fn default_recency() -> f64 {
+    1.0
+}
+
+fn default_emotion() -> f64 {
+    0.1
+}
+
+impl Default for RetrievalWeights {
+    fn default() -> Self {
+        Self {
+            keyword: default_keyword(),
+            vector: default_vector(),
+            importance: default_importance(),
+            recency: default_recency(),
+            emotion: default_emotion(),
+        }
+    }
+}
+
+impl RetrievalWeights {
+    /// Validate the weights
+    pub fn validate(&self) -> Result<()> {
+        let weights = [self.keyword, self.vector, self.importance, self.emotion];
+        if weights.iter().any(|weight| !weight.is_finite() || *weight < 0.0) {
+            return Err(OxydeError::ConfigurationError(
+                "Retrieval weights must be finite and not negative".to_string(),
+            ));
+        }
+        if self.keyword + self.vector == 0.0 {
+            return Err(OxydeError::ConfigurationError(
+                "Retrieval keyword and vector weights cannot both be 0".to_string(),
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.recency) {
+            return Err(OxydeError::ConfigurationError(format!(
+                "Retrieval recency must be between 0.0 and 1.0, got {}",
+                self.recency
+            )));
+        }
+        Ok(())
+    }
+
+    /// Every combination of the given values for the keyword, vector,
+    /// importance and emotion weights, for [`RetrievalBenchmark::tune`]
+    ///
+    /// Combinations whose keyword and vector weights are both 0 are skipped;
+    /// `recency` is kept as set on `self`.
+    pub fn grid(&self, values: &[f64]) -> Vec<RetrievalWeights> {
+        let mut grid = Vec::new();
+        for &keyword in values {
+            for &vector in values {
+                for &importance in values {
+                    for &emotion in values {
+                        if keyword + vector > 0.0 {
+                            grid.push(RetrievalWeights {
+                                keyword,
+                                vector,
+                                importance,
+                                emotion,
+                                ..*self
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        grid
+    }
+
+    /// Discount a relevance score by a memory's recency
+    ///
+    /// # Arguments
+    ///
+    /// * `relevance` - Blended relevance of the memory
+    /// * `recency` - Recency of the memory, from [`recency`]
+    pub fn discount(&self, relevance: f64, recency: f64) -> f64 {
+        relevance * (1.0 - self.recency + self.recency * recency)
+    }
+}
+
+/// Mood-congruent weighting of recalled memories
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MoodCongruence {
+    /// Whether the current mood weighs memories
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// How strongly, from 0.0 (no effect) to 1.0; at 1.0 a memory of
+    /// opposite valence to a mood at either extreme is never recalled
+    #[serde(default = "default_mood_strength")]
+    pub strength: f64,
+}
+
+fn default_mood_strength() -> f64 {
+    0.5
+}
+
+impl Default for MoodCongruence {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            strength: default_mood_strength(),
+        }
+    }
+}
+
+impl MoodCongruence {
+    /// Validate the weighting
+    pub fn validate(&self) -> Result<()> {
+        if !(0.0..=1.0).contains(&self.strength) {
+            return Err(OxydeError::ConfigurationError(format!(
+                "Mood congruence strength must be between 0.0 and 1.0, got {}",
+                self.strength
+            )));
+        }
+        Ok(())
+    }
+
+    /// Factor a memory's relevance is multiplied by in a mood
+    ///
+    /// Memories sharing the sign of the mood's valence gain up to `strength`,
+    /// memories of the opposite sign lose as much, and neutral memories or
+    /// moods are unaffected.
+    ///
+    /// # Arguments
+    ///
+    /// * `memory_valence` - Emotional valence of the memory, from -1.0 to 1.0
+    /// * `mood_valence` - Valence of the agent's current emotions, from -1.0 to 1.0
+    pub fn factor(&self, memory_valence: f64, mood_valence: f64) -> f64 {
+        if !self.enabled {
+            return 1.0;
+        }
+        let congruence = memory_valence.clamp(-1.0, 1.0) * mood_valence.clamp(-1.0, 1.0);
+        (1.0 + self.strength * congruence).max(0.0)
+    }
+}
+
+/// Recency of a memory, from 1.0 for a new one toward 0.0 as it ages
+///
+/// Time decay follows `decay_rate` per day, except for permanent memories.
+/// Memories recalled before lose up to 30% as their last recall ages, less
+/// the more often they were recalled.
+pub fn recency(memory: &Memory, decay_rate: f64, now: u64) -> f64 {
+    let recall_factor = if memory.access_count > 0 {
+        let access_frequency = (memory.access_count as f64).min(10.0) / 10.0;
+        let last_access_age = now.saturating_sub(memory.last_accessed) as f64;
+        let last_access_factor = (-decay_rate * (last_access_age / 86400.0)).exp();
+        0.7 + (0.3 * access_frequency * last_access_factor)
+    } else {
+        1.0
+    };
+    let decay_factor = if memory.permanent {
+        1.0
+    } else {
+        let age_seconds = now.saturating_sub(memory.created_at);
+        (-decay_rate * (age_seconds as f64 / 86400.0)).exp()
+    };
+    decay_factor * recall_factor
+}
+
+/// Lowercase words of a text, without stopwords
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .filter(|word| !STOPWORDS.contains(&word.as_str()))
+        .collect()
+}
+
+/// Words a memory is matched on: its content and tags
+fn memory_terms(memory: &Memory) -> Vec<String> {
+    let mut terms = tokenize(&memory.content);
+    for tag in &memory.tags {
+        terms.extend(tokenize(tag));
+    }
+    terms
+}
+
+/// Whether a memory word matches a query word, allowing inflections such as
+/// "rooms" for "room"
+fn term_matches(word: &str, term: &str) -> bool {
+    word == term || (term.len() >= 3 && word.starts_with(term))
+}
+
+/// Scores memories against one query
+///
+/// BM25 needs statistics of the whole collection, so a scorer is built for
+/// each query from the memories it ranks.
+#[derive(Debug, Clone)]
+pub struct RetrievalScorer {
+    weights: RetrievalWeights,
+    /// Query words with their inverse document frequency
+    terms: Vec<(String, f64)>,
+    average_length: f64,
+}
+
+impl RetrievalScorer {
+    /// Create a scorer for a query over a collection of memories
+    pub fn new<'a>(weights: RetrievalWeights, query: &str, memories: impl IntoIterator<Item = &'a Memory>) -> Self {
+        let mut seen = HashSet::new();
+        let query_terms: Vec<String> = tokenize(query).into_iter().filter(|term| seen.insert(term.clone())).collect();
+
+        let mut documents = 0usize;
+        let mut total_length = 0usize;
+        let mut frequencies: HashMap<&str, usize> = HashMap::new();
+        for memory in memories {
+            let words = memory_terms(memory);
+            documents += 1;
+            total_length += words.len();
+            for term in &query_terms {
+                if words.iter().any(|word| term_matches(word, term)) {
+                    *frequencies.entry(term.as_str()).or_default() += 1;
+                }
+            }
+        }
+
+        let n = documents as f64;
+        let terms = query_terms
+            .iter()
+            .map(|term| {
+                let df = frequencies.get(term.as_str()).copied().unwrap_or(0) as f64;
+                (term.clone(), (1.0 + (n - df + 0.5) / (df + 0.5)).ln())
+            })
+            .collect();
+        Self {
+            weights,
+            terms,
+            average_length: if documents == 0 { 0.0 } else { total_length as f64 / n },
+        }
+    }
+
+    /// BM25 keyword match of a memory, from 0.0 to 1.0
+    ///
+    /// The BM25 score is divided by the score of a memory of average length
+    /// holding each query word once, so matching every word scores about 1.0.
+    pub fn keyword_score(&self, memory: &Memory) -> f64 {
+        let ideal: f64 = self.terms.iter().map(|(_, idf)| idf).sum();
+        if ideal == 0.0 {
+            return 0.0;
+        }
+        let words = memory_terms(memory);
+        let length_ratio = if self.average_length > 0.0 {
+            words.len() as f64 / self.average_length
+        } else {
+            1.0
+        };
+        let norm = BM25_K1 * (1.0 - BM25_B + BM25_B * length_ratio);
+        let score: f64 = self
+            .terms
+            .iter()
+            .map(|(term, idf)| {
+                let tf = words.iter().filter(|word| term_matches(word, term)).count() as f64;
+                idf * tf * (BM25_K1 + 1.0) / (tf + norm)
+            })
+            .sum();
+        (score / ideal).min(1.0)
+    }
+
+    /// Relevance of a memory before the recency discount, from 0.0 to 1.0
+    pub fn relevance(&self, memory: &Memory, query_embedding: Option<&[f32]>) -> f64 {
+        let weights = &self.weights;
+        let keyword = self.keyword_score(memory);
+        let similarity = query_embedding
+            .zip(memory.embedding.as_deref())
+            .and_then(|(query, embedding)| cosine_similarity(query, embedding));
+        let matched = match similarity {
+            Some(similarity) => weights.keyword * keyword + weights.vector * similarity.max(0.0),
+            None => (weights.keyword + weights.vector) * keyword,
+        };
+        (matched + weights.importance * memory.importance + weights.emotion * memory.emotional_intensity)
+            .clamp(0.0, 1.0)
+    }
+
+    /// Relevance of a memory discounted by its recency
+    pub fn score(&self, memory: &Memory, query_embedding: Option<&[f32]>, recency: f64) -> f64 {
+        self.weights.discount(self.relevance(memory, query_embedding), recency)
+    }
+}
+
+/// A sample query with the memories it should retrieve
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LabeledQuery {
+    /// Query text
+    pub query: String,
+
+    /// IDs of the memories relevant to the query
+    pub relevant: Vec<String>,
+
+    /// Embedding of the query, to score vector similarity
+    #[serde(default)]
+    pub embedding: Option<Vec<f32>>,
+}
+
+/// Retrieval quality of a set of weights over labeled queries
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct RetrievalEvaluation {
+    /// Mean share of the top `k` memories that are relevant
+    pub precision: f64,
+
+    /// Mean share of the relevant memories found in the top `k`
+    pub recall: f64,
+
+    /// Mean reciprocal rank of the first relevant memory
+    pub mrr: f64,
+}
+
+/// Labeled query and memory sets for tuning [`RetrievalWeights`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetrievalBenchmark {
+    /// Memories to retrieve from
+    pub memories: Vec<Memory>,
+
+    /// Queries with the memories they should retrieve
+    pub queries: Vec<LabeledQuery>,
+
+    /// Number of top-ranked memories evaluated per query
+    #[serde(default = "default_k")]
+    pub k: usize,
+
+    /// Time-based decay rate applied to memory ages
+    #[serde(default)]
+    pub decay_rate: f64,
+}
+
+fn default_k() -> usize {
+    5
+}
+
+impl RetrievalBenchmark {
+    /// Create a benchmark evaluating the top 5 memories, ignoring age
+    pub fn new(memories: Vec<Memory>, queries: Vec<LabeledQuery>) -> Self {
+        Self {
+            memories,
+            queries,
+            k: default_k(),
+            decay_rate: 0.0,
+        }
+    }
+
+    /// Rank the memories for a query, most relevant first
+    pub fn rank(&self, weights: RetrievalWeights, query: &LabeledQuery) -> Vec<&Memory> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or(Duration::from_secs(0))
+            .as_secs();
+        let scorer = RetrievalScorer::new(weights, &query.query, &self.memories);
+        let mut ranked: Vec<(&Memory, f64)> = self
+            .memories
+            .iter()
+            .map(|memory| {
+                let recency = recency(memory, self.decay_rate, now);
+                (memory, scorer.score(memory, query.embedding.as_deref(), recency))
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ranked.into_iter().map(|(memory, _)| memory).collect()
+    }
+
+    /// Measure how well a set of weights retrieves the labeled memories
+    pub fn evaluate(&self, weights: RetrievalWeights) -> RetrievalEvaluation {
+        let mut total = RetrievalEvaluation::default();
+        for query in &self.queries {
+            let ranked = self.rank(weights, query);
+            let is_relevant = |memory: &Memory| query.relevant.contains(&memory.id);
+            let found = ranked.iter().take(self.k).filter(|memory| is_relevant(memory)).count() as f64;
+            total.precision += found / self.k.min(ranked.len()).max(1) as f64;
+            total.recall += if query.relevant.is_empty() { 1.0 } else { found / query.relevant.len() as f64 };
+            total.mrr += ranked.iter().position(|memory| is_relevant(memory)).map_or(0.0, |rank| 1.0 / (rank + 1) as f64);
+        }
+        let count = self.queries.len().max(1) as f64;
+        RetrievalEvaluation {
+            precision: total.precision / count,
+            recall: total.recall / count,
+            mrr: total.mrr / count,
+        }
+    }
+
+    /// Find the candidate weights that retrieve the labeled memories best
+    ///
+    /// Candidates are compared by mean reciprocal rank, then recall, then
+    /// precision; the first of equally good candidates wins.
+    pub fn tune(
+        &self,
+        candidates: impl IntoIterator<Item = RetrievalWeights>,
+    ) -> Option<(RetrievalWeights, RetrievalEvaluation)> {
+        let key = |evaluation: &RetrievalEvaluation| (evaluation.mrr, evaluation.recall, evaluation.precision);
+        let mut best: Option<(RetrievalWeights, RetrievalEvaluation)> = None;
+        for weights in candidates {
+            let evaluation = self.evaluate(weights);
+            if best.as_ref().is_none_or(|(_, current)| key(&evaluation) > key(current)) {
+                best = Some((weights, evaluation));
+            }
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::MemoryCategory;
+
+    #[test]
+    fn test_keyword_score_weighs_rare_words() {
+        let memories = [
+            Memory::new(MemoryCategory::Semantic, "The mayor owes the guild money", 0.5, None),
+            Memory::new(MemoryCategory::Semantic, "The guild hall is by the docks", 0.5, None),
+            Memory::new(MemoryCategory::Episodic, "The player bought bread", 0.5, Some(vec!["guild".to_string()])),
+        ];
+        let scorer = RetrievalScorer::new(RetrievalWeights::default(), "What does the mayor owe the guild?", &memories);
+        let scores: Vec<f64> = memories.iter().map(|memory| scorer.keyword_score(memory)).collect();
+        // "mayor" and "owe" (matching "owes") are rarer than "guild"
+        assert!(scores[0] > 0.9);
+        assert!(scores[1] > 0.0 && scores[1] < 0.3);
+        assert!((scores[1] - scores[2]).abs() < 0.1);
+        assert_eq!(scorer.keyword_score(&Memory::new(MemoryCategory::Semantic, "It rained", 0.5, None)), 0.0);
+    }
+
+    #[test]
+    fn test_mood_congruence_favors_matching_valence() {
+        let congruence = MoodCongruence { enabled: true, strength: 0.5 };
+        assert!((congruence.factor(-0.8, -1.0) - 1.4).abs() < 1e-9);
+        assert!((congruence.factor(0.8, -1.0) - 0.6).abs() < 1e-9);
+        assert_eq!(congruence.factor(0.0, -1.0), 1.0);
+        assert_eq!(congruence.factor(-0.8, 0.0), 1.0);
+        assert_eq!(MoodCongruence::default().factor(-0.8, -1.0), 1.0);
+        assert!(MoodCongruence { enabled: true, strength: 1.5 }.validate().is_err());
+    }
+
+    #[test]
+    fn test_benchmark_tunes_weights() {
+        let debt = Memory::new(MemoryCategory::Semantic, "The mayor owes the guild money", 0.1, None);
+        let feast = Memory::new(MemoryCategory::Episodic, "The harvest feast was canceled", 1.0, None);
+        let queries = vec![LabeledQuery {
+            query: "Who owes money?".to_string(),
+            relevant: vec![debt.id.clone()],
+            embedding: None,
+        }];
+        let benchmark = RetrievalBenchmark::new(vec![debt, feast], queries);
+
+        let importance_only = RetrievalWeights {
+            keyword: 0.01,
+            vector: 0.0,
+            importance: 1.0,
+            ..Default::default()
+        };
+        assert_eq!(benchmark.evaluate(importance_only).mrr, 0.5);
+        let (best, evaluation) = benchmark.tune(RetrievalWeights::default().grid(&[0.0, 0.5])).unwrap();
+        assert_eq!(evaluation.mrr, 1.0);
+        assert!(best.keyword + best.vector > 0.0);
+        assert!(RetrievalWeights { recency: 2.0, ..Default::default() }.validate().is_err());
+    }
+}
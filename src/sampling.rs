@@ -0,0 +1,236 @@
+//! Sampling settings that follow the NPC's emotional arousal
+//!
+//! An agitated NPC should sound less measured than a calm one. With adaptive
+//! sampling enabled, each inference request interpolates between a `calm`
+//! and an `aroused` profile by the arousal of the NPC's emotions as the reply
+//! is generated. Profiles set the temperature and, optionally, a sentence
+//! length stated in the prompt alongside any verbosity budget:
+//!
+//! ```yaml
+//! inference:
+//!   sampling:
+//!     enabled: true
+//!     full_arousal: 0.5
+//!     calm:
+//!       temperature: 0.4
+//!     aroused:
+//!       temperature: 1.0
+//!       max_sentence_words: 8
+//!     behaviors:
+//!       CombatBehavior:
+//!         calm:
+//!           temperature: 0.3
+//!         aroused:
+//!           temperature: 0.6
+//!           max_sentence_words: 5
+//! ```
+//!
+//! Profiles under `behaviors` replace the defaults when the named behavior
+//! ran before inference, such as a behavior that triggered an action but
+//! left the reply to the model. A temperature set by an experiment variant
+//! takes precedence over adaptive sampling.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::oxyde_game::emotion::EmotionalState;
+use crate::{OxydeError, Result};
+
+/// Sampling settings at one end of the arousal range
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SamplingProfile {
+    /// Inference temperature
+    pub temperature: f32,
+
+    /// Longest sentence asked for, in words
+    #[serde(default)]
+    pub max_sentence_words: Option<usize>,
+}
+
+/// Profiles of a calm and a fully aroused NPC
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SamplingMapping {
+    /// Profile at no arousal
+    pub calm: SamplingProfile,
+
+    /// Profile at `full_arousal` and above
+    pub aroused: SamplingProfile,
+}
+
+/// Adaptive sampling configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SamplingConfig {
+    /// Whether requests are sampled by arousal
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Arousal at which the aroused profile applies fully
+    #[serde(default = "default_full_arousal")]
+    pub full_arousal: f32,
+
+    /// Profile at no arousal
+    #[serde(default = "default_calm")]
+    pub calm: SamplingProfile,
+
+    /// Profile at `full_arousal` and above
+    #[serde(default = "default_aroused")]
+    pub aroused: SamplingProfile,
+
+    /// Profiles used instead when a behavior ran before inference, by
+    /// behavior type name
+    #[serde(default)]
+    pub behaviors: HashMap<String, SamplingMapping>,
+}
+
+fn default_full_arousal() -> f32 {
+    0.5
+}
+
+fn default_calm() -> SamplingProfile {
+    SamplingProfile {
+        temperature: 0.5,
+        max_sentence_words: None,
+    }
+}
+
+fn default_aroused() -> SamplingProfile {
+    SamplingProfile {
+        temperature: 0.9,
+        max_sentence_words: Some(10),
+    }
+}
+
+impl Default for SamplingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            full_arousal: default_full_arousal(),
+            calm: default_calm(),
+            aroused: default_aroused(),
+            behaviors: HashMap::new(),
+        }
+    }
+}
+
+/// Sampling settings for one request
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sampling {
+    /// Inference temperature
+    pub temperature: f32,
+
+    /// Longest sentence asked for, in words
+    pub max_sentence_words: Option<usize>,
+}
+
+impl SamplingConfig {
+    /// Validate the adaptive sampling configuration
+    ///
+    /// # Returns
+    ///
+    /// Ok if the configuration is valid, Err with a descriptive message otherwise
+    pub fn validate(&self) -> Result<()> {
+        if !(self.full_arousal > 0.0 && self.full_arousal <= 1.0) {
+            return Err(OxydeError::ConfigurationError(format!(
+                "Sampling full_arousal must be in (0.0, 1.0], got {}",
+                self.full_arousal
+            )));
+        }
+        let mappings = std::iter::once((
+            "default",
+            SamplingMapping {
+                calm: self.calm,
+                aroused: self.aroused,
+            },
+        ))
+        .chain(self.behaviors.iter().map(|(name, mapping)| (name.as_str(), *mapping)));
+        for (name, mapping) in mappings {
+            for profile in [mapping.calm, mapping.aroused] {
+                if !(0.0..=2.0).contains(&profile.temperature) {
+                    return Err(OxydeError::ConfigurationError(format!(
+                        "Sampling temperature of {} profiles must be between 0.0 and 2.0, got {}",
+                        name, profile.temperature
+                    )));
+                }
+                if profile.max_sentence_words == Some(0) {
+                    return Err(OxydeError::ConfigurationError(format!(
+                        "Sampling max_sentence_words of {} profiles must be greater than 0",
+                        name
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Sampling settings for emotions, after a behavior if one ran
+    pub fn sample(&self, emotions: &EmotionalState, behavior: Option<&str>) -> Sampling {
+        let mapping = behavior
+            .and_then(|name| self.behaviors.get(name))
+            .copied()
+            .unwrap_or(SamplingMapping {
+                calm: self.calm,
+                aroused: self.aroused,
+            });
+        let weight = (emotions.arousal() / self.full_arousal).clamp(0.0, 1.0);
+        let (calm, aroused) = (mapping.calm, mapping.aroused);
+
+        let max_sentence_words = match (calm.max_sentence_words, aroused.max_sentence_words) {
+            (Some(calm), Some(aroused)) => Some((calm as f32 + (aroused as f32 - calm as f32) * weight).round() as usize),
+            (Some(calm), None) => (weight < 0.5).then_some(calm),
+            (None, Some(aroused)) => (weight >= 0.5).then_some(aroused),
+            (None, None) => None,
+        };
+        Sampling {
+            temperature: calm.temperature + (aroused.temperature - calm.temperature) * weight,
+            max_sentence_words,
+        }
+    }
+
+    /// Sampling settings for emotions, after a behavior if one ran, if
+    /// adaptive sampling is enabled
+    pub fn for_emotions(&self, emotions: &EmotionalState, behavior: Option<&str>) -> Option<Sampling> {
+        self.enabled.then(|| self.sample(emotions, behavior))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arousal_moves_sampling_between_profiles() {
+        let mut config = SamplingConfig {
+            enabled: true,
+            ..Default::default()
+        };
+        config.behaviors.insert(
+            "CombatBehavior".to_string(),
+            SamplingMapping {
+                calm: SamplingProfile { temperature: 0.2, max_sentence_words: Some(12) },
+                aroused: SamplingProfile { temperature: 0.6, max_sentence_words: Some(4) },
+            },
+        );
+        config.validate().unwrap();
+
+        let calm = EmotionalState::new();
+        let mut furious = EmotionalState::new();
+        furious.anger = 1.0;
+        furious.fear = 1.0;
+        furious.surprise = 1.0;
+        furious.anticipation = 1.0;
+
+        let relaxed = config.sample(&calm, None);
+        assert_eq!((relaxed.temperature, relaxed.max_sentence_words), (0.5, None));
+        let agitated = config.sample(&furious, None);
+        assert!((agitated.temperature - 0.9).abs() < 1e-6);
+        assert_eq!(agitated.max_sentence_words, Some(10));
+
+        let fighting = config.for_emotions(&furious, Some("CombatBehavior")).unwrap();
+        assert!((fighting.temperature - 0.6).abs() < 1e-6);
+        assert_eq!(fighting.max_sentence_words, Some(4));
+
+        config.enabled = false;
+        assert_eq!(config.for_emotions(&furious, Some("CombatBehavior")), None);
+    }
+}
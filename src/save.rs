@@ -0,0 +1,342 @@
+//! Save-compatible serialization
+//!
+//! Game saves outlive SDK versions, so the types games persist —
+//! [`Memory`], [`EmotionalState`] and [`AgentSnapshot`] — serialize with an
+//! explicit schema version:
+//!
+//! ```json
+//! {"version": 1, "joy": 0.4, "trust": 0.1, ...}
+//! ```
+//!
+//! Data without a `version` field was written before versioning was
+//! introduced and is read as version 1. Data from a newer SDK than the one
+//! reading it is rejected instead of being misread.
+//!
+//! ## Migrations
+//!
+//! When a versioned type's layout changes, its [`Versioned::VERSION`] is
+//! bumped and a [`Migration`] is appended to [`Versioned::migrations`]. A
+//! migration rewrites the JSON of one version into the next, so loading a
+//! save applies every step between the saved version and the current one:
+//!
+//! ```
+//! use oxyde::save::{Migration, Versioned};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Wallet {
+//!     gold: u64,
+//! }
+//!
+//! // Version 1 stored `coins`; version 2 renamed it to `gold`
+//! fn rename_coins(mut value: serde_json::Value) -> oxyde::Result<serde_json::Value> {
+//!     if let Some(coins) = value.as_object_mut().and_then(|object| object.remove("coins")) {
+//!         value["gold"] = coins;
+//!     }
+//!     Ok(value)
+//! }
+//!
+//! impl Versioned for Wallet {
+//!     const KIND: &'static str = "wallet";
+//!     const VERSION: u32 = 2;
+//!
+//!     fn migrations() -> &'static [Migration] {
+//!         &[rename_coins]
+//!     }
+//! }
+//!
+//! let wallet: Wallet = oxyde::save::from_versioned_value(serde_json::json!({"coins": 7})).unwrap();
+//! assert_eq!(wallet.gold, 7);
+//! assert_eq!(oxyde::save::to_versioned_value(&wallet).unwrap()["version"], 2);
+//! ```
+
+use std::collections::HashMap;
+
+use serde::de::{DeserializeOwned, Error as _};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+use crate::agent::AgentContext;
+use crate::memory::Memory;
+use crate::oxyde_game::disposition::DispositionState;
+use crate::oxyde_game::emotion::EmotionalState;
+use crate::oxyde_game::reputation::ToxicityScore;
+use crate::session::PlayerSession;
+use crate::{OxydeError, Result};
+
+/// Field holding the schema version of saved data
+pub const VERSION_KEY: &str = "version";
+
+/// Version assumed for data saved before versioning was introduced
+pub const UNVERSIONED: u32 = 1;
+
+/// Rewrites the JSON of one schema version into the next
+pub type Migration = fn(Value) -> Result<Value>;
+
+/// A type saved with a schema version
+pub trait Versioned: Sized {
+    /// Name of the type in error messages
+    const KIND: &'static str;
+
+    /// Current schema version
+    const VERSION: u32;
+
+    /// Migrations in order; the first upgrades version 1 to version 2
+    ///
+    /// There must be `VERSION - 1` of them.
+    fn migrations() -> &'static [Migration] {
+        &[]
+    }
+}
+
+/// Upgrade saved JSON to the current version of a type
+///
+/// Removes the version field and applies the migrations between the saved
+/// version and [`Versioned::VERSION`].
+///
+/// # Returns
+///
+/// The migrated JSON, or a serialization error if the version is newer than
+/// this SDK supports or a migration fails
+pub fn migrate<T: Versioned>(mut value: Value) -> Result<Value> {
+    let version = match value.as_object_mut().and_then(|object| object.remove(VERSION_KEY)) {
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .filter(|version| *version >= UNVERSIONED)
+            .ok_or_else(|| version_error(format!("Invalid {} version: {}", T::KIND, version)))?,
+        None => UNVERSIONED,
+    };
+    if version > T::VERSION {
+        return Err(version_error(format!(
+            "Saved {} is version {}, newer than the supported version {}",
+            T::KIND,
+            version,
+            T::VERSION
+        )));
+    }
+
+    let migrations = T::migrations();
+    for step in version..T::VERSION {
+        let migration = migrations.get(step as usize - 1).ok_or_else(|| {
+            version_error(format!("No migration for {} version {}", T::KIND, step))
+        })?;
+        value = migration(value)?;
+    }
+    Ok(value)
+}
+
+/// Serialization error for a saved version that cannot be read
+fn version_error(message: String) -> OxydeError {
+    OxydeError::SerializationError(serde_json::Error::custom(message))
+}
+
+/// Serialize a value to JSON tagged with its schema version
+pub fn to_versioned_value<T: Versioned + Serialize>(value: &T) -> Result<Value> {
+    let mut json = serde_json::to_value(value)?;
+    tag(&mut json, T::VERSION);
+    Ok(json)
+}
+
+/// Deserialize a value from JSON of any supported schema version
+pub fn from_versioned_value<T: Versioned + DeserializeOwned>(value: Value) -> Result<T> {
+    Ok(serde_json::from_value(migrate::<T>(value)?)?)
+}
+
+/// Insert the version field into a JSON object
+fn tag(value: &mut Value, version: u32) {
+    if let Some(object) = value.as_object_mut() {
+        object.insert(VERSION_KEY.to_string(), Value::from(version));
+    }
+}
+
+/// Serialize a type with its derived layout plus a version field
+///
+/// Used by `Serialize` impls of types deriving `#[serde(remote = "Self")]`,
+/// whose derived layout is available as an inherent `serialize` function.
+pub(crate) fn serialize_versioned<T, S>(
+    value: &T,
+    serialize_fields: fn(&T, serde_json::value::Serializer) -> std::result::Result<Value, serde_json::Error>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error>
+where
+    T: Versioned,
+    S: Serializer,
+{
+    let mut json = serialize_fields(value, serde_json::value::Serializer).map_err(serde::ser::Error::custom)?;
+    tag(&mut json, T::VERSION);
+    json.serialize(serializer)
+}
+
+/// Deserialize a type with its derived layout after migrating it
+///
+/// Counterpart of [`serialize_versioned`].
+pub(crate) fn deserialize_versioned<'de, T, D>(
+    deserializer: D,
+    deserialize_fields: fn(Value) -> std::result::Result<T, serde_json::Error>,
+) -> std::result::Result<T, D::Error>
+where
+    T: Versioned,
+    D: Deserializer<'de>,
+{
+    let json = migrate::<T>(Value::deserialize(deserializer)?).map_err(D::Error::custom)?;
+    deserialize_fields(json).map_err(D::Error::custom)
+}
+
+/// Saveable state of an agent
+///
+/// Take one with [`Agent::snapshot`](crate::agent::Agent::snapshot) and load
+/// it into an agent built from the same configuration with
+/// [`Agent::restore_snapshot`](crate::agent::Agent::restore_snapshot).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(remote = "Self")]
+pub struct AgentSnapshot {
+    /// Name of the agent the snapshot was taken from
+    pub name: String,
+
+    /// Unix time the snapshot was taken, in seconds
+    pub saved_at: u64,
+
+    /// Current emotions
+    pub emotional_state: EmotionalState,
+
+    /// All memories
+    pub memories: Vec<Memory>,
+
+    /// Long-term emotion baselines
+    #[serde(default)]
+    pub disposition: DispositionState,
+
+    /// When the agent last spoke with each player, in Unix seconds
+    #[serde(default)]
+    pub last_interactions: HashMap<String, u64>,
+
+    /// Toxicity score of each player
+    #[serde(default)]
+    pub player_reputations: HashMap<String, ToxicityScore>,
+
+    /// Conversation sessions of players identified by the game
+    #[serde(default)]
+    pub player_sessions: Vec<PlayerSession>,
+
+    /// Environment context
+    #[serde(default)]
+    pub context: AgentContext,
+}
+
+impl Versioned for AgentSnapshot {
+    const KIND: &'static str = "agent snapshot";
+    const VERSION: u32 = 1;
+}
+
+impl Serialize for AgentSnapshot {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serialize_versioned(self, AgentSnapshot::serialize, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for AgentSnapshot {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserialize_versioned(deserializer, AgentSnapshot::deserialize)
+    }
+}
+
+impl AgentSnapshot {
+    /// Parse a snapshot from JSON of any supported version
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Serialize the snapshot to JSON
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::MemoryCategory;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Wallet {
+        gold: u64,
+        silver: u64,
+    }
+
+    fn rename_coins(mut value: Value) -> Result<Value> {
+        let coins = value["coins"].take();
+        value["gold"] = coins;
+        Ok(value)
+    }
+
+    fn add_silver(mut value: Value) -> Result<Value> {
+        value["silver"] = Value::from(0);
+        Ok(value)
+    }
+
+    impl Versioned for Wallet {
+        const KIND: &'static str = "wallet";
+        const VERSION: u32 = 3;
+
+        fn migrations() -> &'static [Migration] {
+            &[rename_coins, add_silver]
+        }
+    }
+
+    #[test]
+    fn test_migrates_old_versions_and_rejects_newer() {
+        let expected = Wallet { gold: 7, silver: 0 };
+        let v1: Wallet = from_versioned_value(serde_json::json!({"coins": 7})).unwrap();
+        let v2: Wallet = from_versioned_value(serde_json::json!({"version": 2, "gold": 7})).unwrap();
+        assert_eq!(v1, expected);
+        assert_eq!(v2, expected);
+
+        let saved = to_versioned_value(&expected).unwrap();
+        assert_eq!(saved["version"], 3);
+        assert_eq!(from_versioned_value::<Wallet>(saved).unwrap(), expected);
+
+        let newer = from_versioned_value::<Wallet>(serde_json::json!({"version": 4, "gold": 1, "silver": 1}));
+        assert!(newer.unwrap_err().to_string().contains("newer than the supported version 3"));
+        assert!(from_versioned_value::<Wallet>(serde_json::json!({"version": "x"})).is_err());
+    }
+
+    #[test]
+    fn test_reads_unversioned_saves() {
+        // Layouts written before versioning, without a version field
+        let emotions: EmotionalState = serde_json::from_value(serde_json::json!({
+            "joy": 0.5, "trust": 0.2, "fear": 0.0, "surprise": 0.0,
+            "sadness": 0.0, "disgust": 0.0, "anger": 0.0, "anticipation": 0.1,
+            "decay_rate": 0.1
+        }))
+        .unwrap();
+        assert_eq!(emotions.joy, 0.5);
+
+        let memory: Memory = serde_json::from_value(serde_json::json!({
+            "id": "m1", "category": "Episodic", "tags": [], "content": "The player helped me",
+            "created_at": 10, "last_accessed": 10, "access_count": 0, "importance": 0.8,
+            "emotional_valence": 0.5, "emotional_intensity": 0.3, "permanent": false
+        }))
+        .unwrap();
+        assert_eq!(memory.content, "The player helped me");
+
+        let mut snapshot = AgentSnapshot {
+            name: "Mira".to_string(),
+            emotional_state: emotions,
+            memories: vec![memory, Memory::new(MemoryCategory::Semantic, "The mill is north", 0.5, None)],
+            ..Default::default()
+        };
+        snapshot.last_interactions.insert("aria".to_string(), 42);
+
+        let json = snapshot.to_json().unwrap();
+        let value: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["version"], 1);
+        assert_eq!(value["emotional_state"]["version"], EmotionalState::VERSION);
+        assert_eq!(value["memories"][0]["version"], Memory::VERSION);
+
+        let restored = AgentSnapshot::from_json(&json).unwrap();
+        assert_eq!(restored.emotional_state, snapshot.emotional_state);
+        assert_eq!(restored.memories[1].content, "The mill is north");
+        assert_eq!(restored.last_interactions["aria"], 42);
+    }
+}
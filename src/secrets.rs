@@ -0,0 +1,334 @@
+//! API keys and other secrets resolved at runtime
+//!
+//! Configs name where a secret lives instead of containing it, so they can be
+//! committed without leaking keys:
+//!
+//! ```yaml
+//! inference:
+//!   api_key_ref: env:OPENAI_API_KEY
+//! tts:
+//!   api_key_ref: file:/run/secrets/oxyde.json#elevenlabs
+//! ```
+//!
+//! A reference is `<provider>:<key>`. The built-in `env` provider reads an
+//! environment variable. The `file` provider reads a file vault: the whole
+//! file, or with `#name` one entry of a JSON object or of `NAME=value` lines.
+//! Games add providers of their own, such as one per tenant backed by a
+//! platform keystore, with [`register_secrets_provider`] or
+//! [`register_secret_callback`].
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{OxydeError, Result};
+
+/// A resolved secret whose value is kept out of logs
+///
+/// `Debug` and `Display` print `[redacted]`; use [`Secret::expose`] to read
+/// the value.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret(String);
+
+impl Secret {
+    /// Wrap a secret value
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(value.into())
+    }
+
+    /// Get the secret value
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    /// Take the secret value
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+/// Placeholder printed instead of secret values
+pub(crate) const REDACTED: &str = "[redacted]";
+
+/// Debug form of an optional inline secret
+pub(crate) fn redact(value: &Option<String>) -> Option<&'static str> {
+    value.as_ref().map(|_| REDACTED)
+}
+
+/// Where a secret is stored, written `<provider>:<key>`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SecretRef {
+    provider: String,
+    key: String,
+}
+
+impl SecretRef {
+    /// Refer to a secret held by a provider
+    pub fn new(provider: impl Into<String>, key: impl Into<String>) -> Self {
+        Self {
+            provider: provider.into(),
+            key: key.into(),
+        }
+    }
+
+    /// Parse a `<provider>:<key>` reference
+    ///
+    /// # Returns
+    ///
+    /// The reference, or a configuration error if either part is missing
+    pub fn parse(source: &str) -> Result<Self> {
+        match source.split_once(':') {
+            Some((provider, key)) if !provider.is_empty() && !key.is_empty() => Ok(Self::new(provider, key)),
+            _ => Err(OxydeError::ConfigurationError(format!(
+                "Invalid secret reference '{}'; expected <provider>:<key>, such as env:OXYDE_API_KEY",
+                source
+            ))),
+        }
+    }
+
+    /// Name of the provider holding the secret
+    pub fn provider(&self) -> &str {
+        &self.provider
+    }
+
+    /// Key of the secret within its provider
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Look the secret up with the registered providers
+    pub fn resolve(&self) -> Result<Secret> {
+        resolve_secret(self)
+    }
+}
+
+impl fmt::Display for SecretRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.provider, self.key)
+    }
+}
+
+impl FromStr for SecretRef {
+    type Err = OxydeError;
+
+    fn from_str(source: &str) -> Result<Self> {
+        Self::parse(source)
+    }
+}
+
+impl Serialize for SecretRef {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for SecretRef {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let source = String::deserialize(deserializer)?;
+        Self::parse(&source).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Source of secrets for references naming it
+pub trait SecretsProvider: Send + Sync {
+    /// Name references use for this provider
+    fn name(&self) -> &str;
+
+    /// Look up a secret
+    ///
+    /// # Returns
+    ///
+    /// The secret value, `None` if the provider has no such secret, or an
+    /// error if the provider could not be read
+    fn get(&self, key: &str) -> Result<Option<String>>;
+}
+
+/// Secrets read from environment variables, under the name `env`
+#[derive(Debug, Default, Clone, Copy)]
+pub struct EnvSecrets;
+
+impl SecretsProvider for EnvSecrets {
+    fn name(&self) -> &str {
+        "env"
+    }
+
+    fn get(&self, key: &str) -> Result<Option<String>> {
+        Ok(std::env::var(key).ok())
+    }
+}
+
+/// Secrets read from files, under the name `file`
+///
+/// The key is a path, optionally followed by `#name` to select one entry of
+/// a JSON object or of `NAME=value` lines. Without an entry name the whole
+/// file, trimmed, is the secret.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FileSecrets;
+
+impl SecretsProvider for FileSecrets {
+    fn name(&self) -> &str {
+        "file"
+    }
+
+    fn get(&self, key: &str) -> Result<Option<String>> {
+        let (path, entry) = match key.rsplit_once('#') {
+            Some((path, entry)) => (path, Some(entry)),
+            None => (key, None),
+        };
+        let contents = fs::read_to_string(path)?;
+        let Some(entry) = entry else {
+            return Ok(Some(contents.trim().to_string()));
+        };
+
+        if let Ok(serde_json::Value::Object(entries)) = serde_json::from_str(&contents) {
+            return Ok(entries.get(entry).and_then(|value| value.as_str()).map(str::to_string));
+        }
+        let value = contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| line.split_once('='))
+            .find(|(name, _)| name.trim() == entry)
+            .map(|(_, value)| value.trim().trim_matches('"').to_string());
+        Ok(value)
+    }
+}
+
+/// Callback looking up a secret by key
+type SecretCallback = Box<dyn Fn(&str) -> Option<String> + Send + Sync>;
+
+/// Secrets looked up by a callback, such as a game's own keystore
+pub struct CallbackSecrets {
+    name: String,
+    callback: SecretCallback,
+}
+
+impl CallbackSecrets {
+    /// Create a provider calling `callback` with each key
+    pub fn new<F>(name: &str, callback: F) -> Self
+    where
+        F: Fn(&str) -> Option<String> + Send + Sync + 'static,
+    {
+        Self {
+            name: name.to_string(),
+            callback: Box::new(callback),
+        }
+    }
+}
+
+impl fmt::Debug for CallbackSecrets {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CallbackSecrets").field("name", &self.name).finish_non_exhaustive()
+    }
+}
+
+impl SecretsProvider for CallbackSecrets {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn get(&self, key: &str) -> Result<Option<String>> {
+        Ok((self.callback)(key))
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_PROVIDERS: RwLock<HashMap<String, Arc<dyn SecretsProvider>>> = {
+        let builtins: [Arc<dyn SecretsProvider>; 2] = [Arc::new(EnvSecrets), Arc::new(FileSecrets)];
+        RwLock::new(builtins.into_iter().map(|provider| (provider.name().to_string(), provider)).collect())
+    };
+}
+
+/// Register a secrets provider, replacing any provider with the same name
+pub fn register_secrets_provider(provider: Arc<dyn SecretsProvider>) {
+    GLOBAL_PROVIDERS
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .insert(provider.name().to_string(), provider);
+}
+
+/// Register a callback answering references to the provider `name`
+pub fn register_secret_callback<F>(name: &str, callback: F)
+where
+    F: Fn(&str) -> Option<String> + Send + Sync + 'static,
+{
+    register_secrets_provider(Arc::new(CallbackSecrets::new(name, callback)));
+}
+
+/// Look a secret up with the registered providers
+///
+/// # Returns
+///
+/// The secret, or a configuration error if the provider is unknown, cannot
+/// be read or has no such secret
+pub fn resolve_secret(reference: &SecretRef) -> Result<Secret> {
+    let provider = GLOBAL_PROVIDERS
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .get(reference.provider())
+        .cloned()
+        .ok_or_else(|| {
+            OxydeError::ConfigurationError(format!(
+                "Unknown secrets provider '{}' in {}",
+                reference.provider(),
+                reference
+            ))
+        })?;
+    match provider.get(reference.key()) {
+        Ok(Some(value)) => Ok(Secret(value)),
+        Ok(None) => Err(OxydeError::ConfigurationError(format!("Secret {} not found", reference))),
+        Err(e) => Err(OxydeError::ConfigurationError(format!("Cannot read secret {}: {}", reference, e))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolves_references_with_registered_providers() {
+        let dir = std::env::temp_dir().join(format!("oxyde-secrets-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let json = dir.join("vault.json");
+        fs::write(&json, r#"{"openai": "sk-json"}"#).unwrap();
+        let dotenv = dir.join("vault.env");
+        fs::write(&dotenv, "# keys\nELEVENLABS = \"el-dotenv\"\n").unwrap();
+
+        let reference: SecretRef = format!("file:{}#openai", json.display()).parse().unwrap();
+        assert_eq!(reference.resolve().unwrap().expose(), "sk-json");
+        let reference = SecretRef::new("file", format!("{}#ELEVENLABS", dotenv.display()));
+        assert_eq!(reference.resolve().unwrap().expose(), "el-dotenv");
+        assert!(SecretRef::new("file", format!("{}#missing", json.display())).resolve().is_err());
+
+        register_secret_callback("tenant-a", |key| (key == "openai").then(|| "sk-tenant".to_string()));
+        let secret = SecretRef::parse("tenant-a:openai").unwrap().resolve().unwrap();
+        assert_eq!(format!("{:?} {}", secret, secret), "[redacted] [redacted]");
+        assert_eq!(secret.into_inner(), "sk-tenant");
+
+        let config = crate::config::InferenceConfig {
+            api_key: Some("sk-inline".to_string()),
+            ..Default::default()
+        };
+        assert!(!format!("{:?}", config).contains("sk-inline"));
+
+        assert!(SecretRef::parse("vault:openai").unwrap().resolve().is_err());
+        assert!(SecretRef::parse("OPENAI_API_KEY").is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
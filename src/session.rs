@@ -0,0 +1,292 @@
+//! Conversation sessions of players identified by the game engine
+//!
+//! Engines identify players by their own IDs, such as a Steam ID or an
+//! account name. The [`SessionStore`] keeps a [`PlayerSession`] for each of
+//! them: the recent conversation, the player's standing with the agent, and
+//! context values to apply to their inputs. `Agent::process_input_for_player`
+//! runs an input as that player without touching the agent's shared context,
+//! tags the memories of the exchange with [`player_tag`] so they are only
+//! recalled in that player's conversations, and records the exchange.
+//!
+//! Player memories are kept and persisted by the agent's memory system like
+//! any other; sessions are saved with the agent's snapshot:
+//!
+//! ```yaml
+//! memory:
+//!   sessions:
+//!     history_limit: 20
+//! ```
+
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::agent::AgentContext;
+use crate::memory::Memory;
+use crate::save::{deserialize_versioned, serialize_versioned, Versioned};
+use crate::utils::current_timestamp_secs;
+use crate::{OxydeError, Result};
+
+/// Prefix of the tag marking a memory as being about one player
+const PLAYER_TAG_PREFIX: &str = "player:";
+
+/// Configuration for player sessions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionConfig {
+    /// Most recent exchanges kept in each session
+    #[serde(default = "default_history_limit")]
+    pub history_limit: usize,
+}
+
+fn default_history_limit() -> usize {
+    20
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            history_limit: default_history_limit(),
+        }
+    }
+}
+
+impl SessionConfig {
+    /// Validate the session configuration
+    pub fn validate(&self) -> Result<()> {
+        if self.history_limit == 0 {
+            return Err(OxydeError::ConfigurationError(
+                "Session history_limit must be greater than 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Tag marking a memory as being about one player
+pub fn player_tag(player_id: &str) -> String {
+    format!("{}{}", PLAYER_TAG_PREFIX, player_id)
+}
+
+/// The player a memory is about, if it is tagged with [`player_tag`]
+pub fn memory_player(memory: &Memory) -> Option<&str> {
+    memory.tags.iter().find_map(|tag| tag.strip_prefix(PLAYER_TAG_PREFIX))
+}
+
+/// Whether a memory may be recalled in a conversation with `player_id`, or
+/// outside any player's conversation when `None`
+pub fn recallable_for(memory: &Memory, player_id: Option<&str>) -> bool {
+    memory_player(memory).is_none_or(|about| Some(about) == player_id)
+}
+
+/// One exchange with a player
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionTurn {
+    /// What the player said
+    pub input: String,
+
+    /// What the agent answered
+    pub response: String,
+
+    /// Unix time of the exchange, in seconds
+    pub at: u64,
+}
+
+/// Everything the agent keeps about one player
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(remote = "Self")]
+pub struct PlayerSession {
+    /// The engine's ID for the player
+    pub player_id: String,
+
+    /// Unix time of the first exchange, in seconds
+    pub started_at: u64,
+
+    /// Unix time of the latest exchange, in seconds
+    pub last_active: u64,
+
+    /// Number of exchanges so far
+    pub turns: u64,
+
+    /// The player's standing with the agent, from -1.0 (hostile) to 1.0
+    /// (trusted); set as `player_relationship` in the context of their inputs
+    #[serde(default)]
+    pub relationship: f32,
+
+    /// Recent exchanges, oldest first
+    #[serde(default)]
+    pub history: Vec<SessionTurn>,
+
+    /// Context values set for the player's inputs, such as `player_name`
+    #[serde(default)]
+    pub context: AgentContext,
+}
+
+impl Versioned for PlayerSession {
+    const KIND: &'static str = "player session";
+    const VERSION: u32 = 1;
+}
+
+impl Serialize for PlayerSession {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serialize_versioned(self, PlayerSession::serialize, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for PlayerSession {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserialize_versioned(deserializer, PlayerSession::deserialize)
+    }
+}
+
+impl PlayerSession {
+    /// Start a session for a player
+    pub fn new(player_id: &str) -> Self {
+        let now = current_timestamp_secs();
+        Self {
+            player_id: player_id.to_string(),
+            started_at: now,
+            last_active: now,
+            ..Default::default()
+        }
+    }
+
+    /// Record an exchange, keeping the `history_limit` most recent
+    pub fn record_turn(&mut self, input: &str, response: &str, config: &SessionConfig) {
+        self.last_active = current_timestamp_secs();
+        self.turns += 1;
+        self.history.push(SessionTurn {
+            input: input.to_string(),
+            response: response.to_string(),
+            at: self.last_active,
+        });
+        let excess = self.history.len().saturating_sub(config.history_limit);
+        self.history.drain(..excess);
+    }
+}
+
+/// Sessions of every player an agent has talked to
+#[derive(Debug)]
+pub struct SessionStore {
+    config: SessionConfig,
+    sessions: Mutex<HashMap<String, PlayerSession>>,
+}
+
+impl SessionStore {
+    /// Create an empty store
+    pub fn new(config: SessionConfig) -> Self {
+        Self {
+            config,
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The store's configuration
+    pub fn config(&self) -> &SessionConfig {
+        &self.config
+    }
+
+    /// Get a player's session, if the agent has one for them
+    pub fn get(&self, player_id: &str) -> Option<PlayerSession> {
+        self.lock().get(player_id).cloned()
+    }
+
+    /// Change a player's session, starting one if they have none
+    ///
+    /// # Returns
+    ///
+    /// What `change` returned, or an error if the player ID is empty
+    pub fn update<R>(&self, player_id: &str, change: impl FnOnce(&mut PlayerSession) -> R) -> Result<R> {
+        if player_id.is_empty() {
+            return Err(OxydeError::ContextError("Player ID cannot be empty".to_string()));
+        }
+        let mut sessions = self.lock();
+        let session = sessions
+            .entry(player_id.to_string())
+            .or_insert_with(|| PlayerSession::new(player_id));
+        Ok(change(session))
+    }
+
+    /// IDs of every player with a session, sorted
+    pub fn player_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.lock().keys().cloned().collect();
+        ids.sort();
+        ids
+    }
+
+    /// Delete a player's session
+    ///
+    /// # Returns
+    ///
+    /// Whether the player had a session
+    pub fn remove(&self, player_id: &str) -> bool {
+        self.lock().remove(player_id).is_some()
+    }
+
+    /// Every session, sorted by player ID, for saving with the game
+    pub fn snapshot(&self) -> Vec<PlayerSession> {
+        let mut sessions: Vec<PlayerSession> = self.lock().values().cloned().collect();
+        sessions.sort_by(|a, b| a.player_id.cmp(&b.player_id));
+        sessions
+    }
+
+    /// Replace every session with saved ones
+    pub fn restore(&self, sessions: Vec<PlayerSession>) {
+        *self.lock() = sessions
+            .into_iter()
+            .map(|session| (session.player_id.clone(), session))
+            .collect();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, PlayerSession>> {
+        self.sessions.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::MemoryCategory;
+
+    #[test]
+    fn test_sessions_are_kept_per_player() {
+        let config = SessionConfig { history_limit: 2 };
+        let store = SessionStore::new(config.clone());
+        assert!(store.get("steam:7656").is_none());
+        store
+            .update("steam:7656", |session| {
+                for turn in ["Hi", "Any work?", "Bye"] {
+                    session.record_turn(turn, "Hm.", &config);
+                }
+                session.relationship = 0.4;
+            })
+            .unwrap();
+        store.update("guest", |session| session.turns += 1).unwrap();
+        assert!(store.update("", |_| ()).is_err());
+
+        // Sessions round-trip through a save
+        let saved = serde_json::to_string(&store.snapshot()).unwrap();
+        let reloaded = SessionStore::new(config);
+        reloaded.restore(serde_json::from_str(&saved).unwrap());
+        assert_eq!(reloaded.player_ids(), vec!["guest".to_string(), "steam:7656".to_string()]);
+        let session = reloaded.get("steam:7656").unwrap();
+        assert_eq!((session.turns, session.relationship), (3, 0.4));
+        assert_eq!(session.history.iter().map(|turn| turn.input.as_str()).collect::<Vec<_>>(), ["Any work?", "Bye"]);
+
+        assert!(reloaded.remove("guest"));
+        assert_eq!(reloaded.player_ids(), vec!["steam:7656".to_string()]);
+    }
+
+    #[test]
+    fn test_player_memories_are_only_recallable_for_their_player() {
+        let tags = Some(vec!["quest".to_string(), player_tag("steam:7656")]);
+        let ring = Memory::new(MemoryCategory::Episodic, "This player returned my lost ring", 0.9, tags);
+        let rumor = Memory::new(MemoryCategory::Semantic, "Wolves were seen by the mill", 0.4, None);
+        assert_eq!(memory_player(&ring), Some("steam:7656"));
+        assert!(recallable_for(&ring, Some("steam:7656")));
+        assert!(!recallable_for(&ring, Some("guest")));
+        assert!(!recallable_for(&ring, None));
+        assert!(recallable_for(&rumor, Some("guest")));
+        assert!(recallable_for(&rumor, None));
+    }
+}
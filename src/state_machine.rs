@@ -0,0 +1,216 @@
+//! Agent lifecycle states and the transitions allowed between them
+//!
+//! Every state change goes through [`StateMachine::transition`], which
+//! rejects transitions the lifecycle does not allow and runs the hooks of the
+//! state entered. Each change is emitted as `AgentEvent::StateChange` with a
+//! [`StateChange`] as JSON.
+//!
+//! An input moves the agent from `Idle` through `Processing`, optionally
+//! `Executing` (behaviors) and `Generating` (inference), back to `Idle`. A
+//! failed input passes through `Error` on its way back to `Idle`, and an
+//! input abandoned mid-way, such as by a timeout or a dropped future, returns
+//! the agent to `Idle` directly. `Stopped` can be entered from any state, and
+//! stopped or paused agents accept no input until started again.
+//!
+//! Entering `Generating` arms the generation deadline set by
+//! `supervisor.generation_timeout_ms`; inference still running at the
+//! deadline fails with an inference error, so the offline fallback answers.
+
+use std::fmt;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{OxydeError, Result};
+use crate::timer::Instant;
+
+/// Agent state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentState {
+    /// Agent is initializing
+    Initializing,
+    /// Agent is idle
+    Idle,
+    /// Agent is processing input
+    Processing,
+    /// Agent is generating a response
+    Generating,
+    /// Agent is executing a behavior
+    Executing,
+    /// Agent is paused
+    Paused,
+    /// Agent is stopped
+    Stopped,
+    /// Agent has encountered an error
+    Error,
+}
+
+impl AgentState {
+    /// Convert to string representation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Initializing => "initializing",
+            Self::Idle => "idle",
+            Self::Processing => "processing",
+            Self::Generating => "generating",
+            Self::Executing => "executing",
+            Self::Paused => "paused",
+            Self::Stopped => "stopped",
+            Self::Error => "error",
+        }
+    }
+
+    /// Whether the agent is handling an input
+    pub fn is_active(self) -> bool {
+        matches!(self, Self::Processing | Self::Executing | Self::Generating)
+    }
+
+    /// Whether the lifecycle allows moving from this state to `next`
+    pub fn can_transition_to(self, next: AgentState) -> bool {
+        use AgentState::*;
+        match (self, next) {
+            (_, Stopped) => true,
+            // Start, resume and recover
+            (Initializing | Stopped | Paused | Error, Idle) => true,
+            (Idle, Paused) => true,
+            // Agents answer input before they are started
+            (Initializing | Idle, Processing) => true,
+            (Processing, Executing | Generating) => true,
+            (Executing, Generating) => true,
+            (Processing | Executing | Generating, Idle | Error) => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for AgentState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A change of an agent's state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateChange {
+    /// State left
+    pub from: AgentState,
+    /// State entered
+    pub to: AgentState,
+}
+
+impl StateChange {
+    /// The change as the JSON carried by `AgentEvent::StateChange`
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+#[derive(Debug)]
+struct Current {
+    state: AgentState,
+    generation_deadline: Option<Instant>,
+}
+
+/// An agent's state with validated transitions
+#[derive(Debug)]
+pub(crate) struct StateMachine {
+    current: Mutex<Current>,
+    generation_timeout: Option<Duration>,
+}
+
+impl StateMachine {
+    /// Start in `Initializing`
+    ///
+    /// # Arguments
+    ///
+    /// * `generation_timeout_ms` - Time inference may take once `Generating`
+    ///   is entered; 0 disables the deadline
+    pub(crate) fn new(generation_timeout_ms: u64) -> Self {
+        Self {
+            current: Mutex::new(Current {
+                state: AgentState::Initializing,
+                generation_deadline: None,
+            }),
+            generation_timeout: (generation_timeout_ms > 0).then(|| Duration::from_millis(generation_timeout_ms)),
+        }
+    }
+
+    /// The current state
+    pub(crate) fn current(&self) -> AgentState {
+        self.lock().state
+    }
+
+    /// Move to a state and run its hooks
+    ///
+    /// # Returns
+    ///
+    /// The change, `None` if the agent is already in the state, or a
+    /// [`OxydeError::RequestError`] if the lifecycle does not allow it
+    pub(crate) fn transition(&self, to: AgentState) -> Result<Option<StateChange>> {
+        let mut current = self.lock();
+        let from = current.state;
+        if from == to {
+            return Ok(None);
+        }
+        if !from.can_transition_to(to) {
+            return Err(OxydeError::RequestError(format!("Agent cannot go from {} to {}", from, to)));
+        }
+        current.state = to;
+        self.on_enter(&mut current);
+        Ok(Some(StateChange { from, to }))
+    }
+
+    /// Move to `to` only while handling an input, for cleaning up after
+    /// failed or abandoned inputs
+    pub(crate) fn transition_if_active(&self, to: AgentState) -> Option<StateChange> {
+        if !self.current().is_active() {
+            return None;
+        }
+        self.transition(to).ok().flatten()
+    }
+
+    /// When inference started in the current `Generating` state must finish
+    pub(crate) fn generation_deadline(&self) -> Option<Instant> {
+        self.lock().generation_deadline
+    }
+
+    /// Hooks run on entering a state
+    fn on_enter(&self, current: &mut Current) {
+        current.generation_deadline = match current.state {
+            AgentState::Generating => self.generation_timeout.map(|timeout| Instant::now() + timeout),
+            _ => None,
+        };
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Current> {
+        self.current.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transitions_are_validated() {
+        let machine = StateMachine::new(500);
+        assert!(machine.transition(AgentState::Generating).is_err());
+        let change = machine.transition(AgentState::Processing).unwrap().unwrap();
+        assert_eq!(change.to_json(), r#"{"from":"initializing","to":"processing"}"#);
+        assert_eq!(machine.transition(AgentState::Processing).unwrap(), None);
+
+        assert!(machine.generation_deadline().is_none());
+        machine.transition(AgentState::Generating).unwrap();
+        assert!(machine.generation_deadline().is_some());
+        assert!(machine.transition(AgentState::Executing).is_err());
+        machine.transition(AgentState::Idle).unwrap();
+        assert!(machine.generation_deadline().is_none());
+
+        machine.transition(AgentState::Stopped).unwrap();
+        assert!(machine.transition(AgentState::Processing).is_err());
+        assert_eq!(machine.transition_if_active(AgentState::Idle), None);
+        assert_eq!(machine.current(), AgentState::Stopped);
+    }
+}
@@ -0,0 +1,242 @@
+//! Machine-readable agent responses
+//!
+//! `Agent::process_input_structured` asks the model to answer with a JSON
+//! object instead of free text and returns it as a [`StructuredResponse`]:
+//! the line to say, a mood tag and the actions the NPC intends to take.
+//!
+//! ```json
+//! { "dialogue": "Follow me, quickly!", "mood": "fear", "actions": [{ "name": "flee", "target": "gate" }] }
+//! ```
+//!
+//! The schema is stated in the system prompt. Replies that are not valid
+//! JSON, or use a mood or action outside the configured lists, are
+//! regenerated with a correction up to `max_retries` times:
+//!
+//! ```yaml
+//! structured_output:
+//!   max_retries: 2
+//!   moods: [neutral, joy, anger, fear]
+//!   actions: [flee, call_guards, give_item]
+//! ```
+//!
+//! Responses written by behaviors are returned with the agent's current mood
+//! and the behavior's action, if any.
+
+use serde::{Deserialize, Serialize};
+
+use crate::oxyde_game::emotion::{EmotionalState, EMOTION_NAMES};
+use crate::{OxydeError, Result};
+
+/// Mood tag used when no emotion is strong enough to name
+pub const NEUTRAL_MOOD: &str = "neutral";
+
+/// Emotion strength at which the strongest emotion names the mood
+const MOOD_THRESHOLD: f32 = 0.3;
+
+/// An action the NPC intends to take
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActionIntent {
+    /// Action name, such as `flee`
+    pub name: String,
+
+    /// What the action is directed at, such as a character or item
+    #[serde(default)]
+    pub target: Option<String>,
+}
+
+/// A response as structured data
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StructuredResponse {
+    /// Text the NPC says
+    pub dialogue: String,
+
+    /// Mood tag for the line, such as `joy` or `neutral`
+    pub mood: String,
+
+    /// Actions the NPC intends to take
+    #[serde(default)]
+    pub actions: Vec<ActionIntent>,
+}
+
+impl StructuredResponse {
+    /// Parse the JSON object a model answered with, ignoring text around it
+    pub fn from_model_output(text: &str) -> Result<Self> {
+        let json = match (text.find('{'), text.rfind('}')) {
+            (Some(start), Some(end)) if start < end => &text[start..=end],
+            _ => {
+                return Err(OxydeError::InferenceError(format!(
+                    "Structured response is not a JSON object: {}",
+                    text
+                )))
+            }
+        };
+        serde_json::from_str(json)
+            .map_err(|e| OxydeError::InferenceError(format!("Invalid structured response: {}", e)))
+    }
+
+    /// Structure a plain response with the agent's current mood
+    pub fn from_text(text: &str, emotions: &EmotionalState, actions: &[String]) -> Self {
+        Self {
+            dialogue: text.to_string(),
+            mood: mood_of(emotions).to_string(),
+            actions: actions
+                .iter()
+                .map(|name| ActionIntent { name: name.clone(), target: None })
+                .collect(),
+        }
+    }
+}
+
+/// Configuration for structured responses
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StructuredOutputConfig {
+    /// Regenerations allowed for invalid replies
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+
+    /// Mood tags the model may use
+    #[serde(default = "default_moods")]
+    pub moods: Vec<String>,
+
+    /// Action names the model may use; empty allows any
+    #[serde(default)]
+    pub actions: Vec<String>,
+}
+
+fn default_max_retries() -> u32 {
+    2
+}
+
+fn default_moods() -> Vec<String> {
+    std::iter::once(NEUTRAL_MOOD).chain(EMOTION_NAMES).map(str::to_string).collect()
+}
+
+impl Default for StructuredOutputConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: default_max_retries(),
+            moods: default_moods(),
+            actions: Vec::new(),
+        }
+    }
+}
+
+impl StructuredOutputConfig {
+    /// Validate the structured output configuration
+    pub fn validate(&self) -> Result<()> {
+        if self.moods.is_empty() {
+            return Err(OxydeError::ConfigurationError(
+                "Structured output needs at least one mood".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// JSON schema replies must follow
+    pub fn schema(&self) -> serde_json::Value {
+        let mut action_name = serde_json::json!({ "type": "string" });
+        if !self.actions.is_empty() {
+            action_name["enum"] = serde_json::json!(self.actions);
+        }
+        serde_json::json!({
+            "type": "object",
+            "required": ["dialogue", "mood", "actions"],
+            "properties": {
+                "dialogue": { "type": "string" },
+                "mood": { "type": "string", "enum": self.moods },
+                "actions": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["name"],
+                        "properties": {
+                            "name": action_name,
+                            "target": { "type": ["string", "null"] },
+                        },
+                    },
+                },
+            },
+        })
+    }
+
+    /// Prompt instruction stating the schema
+    pub fn instruction(&self) -> String {
+        format!(
+            "Answer with only a JSON object matching this schema, without any other text: {}. \
+             Put what you say in \"dialogue\", how you feel in \"mood\", and anything you do in \"actions\".",
+            self.schema()
+        )
+    }
+
+    /// Instruction added when a reply was invalid
+    pub fn retry_instruction(&self, error: &OxydeError) -> String {
+        format!("{} Your previous answer was rejected: {}", self.instruction(), error)
+    }
+
+    /// Parse a reply and check it against the schema
+    pub fn parse(&self, text: &str) -> Result<StructuredResponse> {
+        let response = StructuredResponse::from_model_output(text)?;
+        let invalid = |message: String| -> Result<StructuredResponse> { Err(OxydeError::InferenceError(message)) };
+        if response.dialogue.trim().is_empty() {
+            return invalid("Structured response has empty dialogue".to_string());
+        }
+        if !self.moods.iter().any(|mood| mood.eq_ignore_ascii_case(&response.mood)) {
+            return invalid(format!(
+                "Mood '{}' is not one of: {}",
+                response.mood,
+                self.moods.join(", ")
+            ));
+        }
+        if let Some(action) = response.actions.iter().find(|action| !self.allows_action(&action.name)) {
+            return invalid(format!(
+                "Action '{}' is not one of: {}",
+                action.name,
+                self.actions.join(", ")
+            ));
+        }
+        Ok(response)
+    }
+
+    fn allows_action(&self, name: &str) -> bool {
+        self.actions.is_empty() || self.actions.iter().any(|action| action == name)
+    }
+}
+
+/// Mood tag for the strongest emotion, if strong enough
+pub(crate) fn mood_of(emotions: &EmotionalState) -> &'static str {
+    match emotions.dominant_emotion() {
+        (name, value) if value.abs() >= MOOD_THRESHOLD => name,
+        _ => NEUTRAL_MOOD,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_and_validates_model_replies() {
+        let config = StructuredOutputConfig {
+            actions: vec!["flee".to_string(), "call_guards".to_string()],
+            ..Default::default()
+        };
+        let reply = "Here you go: {\"dialogue\": \"Guards!\", \"mood\": \"fear\", \
+                     \"actions\": [{\"name\": \"call_guards\"}]}";
+        let response = config.parse(reply).unwrap();
+        assert_eq!(response.mood, "fear");
+        assert_eq!(response.actions, vec![ActionIntent { name: "call_guards".to_string(), target: None }]);
+
+        assert!(config.parse("Guards!").is_err());
+        assert!(config.parse("{\"dialogue\": \"Hi\", \"mood\": \"smug\", \"actions\": []}").is_err());
+        let err = config
+            .parse("{\"dialogue\": \"Hi\", \"mood\": \"joy\", \"actions\": [{\"name\": \"dance\"}]}")
+            .unwrap_err();
+        assert!(config.retry_instruction(&err).contains("'dance' is not one of: flee, call_guards"));
+
+        let mut emotions = EmotionalState::new();
+        assert_eq!(StructuredResponse::from_text("Hello.", &emotions, &[]).mood, NEUTRAL_MOOD);
+        emotions.anger = 0.7;
+        let response = StructuredResponse::from_text("Out!", &emotions, &["combat|call_guards".to_string()]);
+        assert_eq!((response.mood.as_str(), response.actions.len()), ("anger", 1));
+    }
+}
@@ -0,0 +1,323 @@
+//! OTLP trace export for the Oxyde SDK
+//!
+//! Available with the `otlp` feature. The agent pipeline is instrumented with
+//! `tracing` spans (moderation, intent analysis, behavior selection, memory
+//! retrieval, inference, and TTS); this module installs a global subscriber
+//! that batches finished spans and ships them to an OpenTelemetry collector
+//! over OTLP/HTTP using the JSON encoding.
+//!
+//! Studios that already run their own `tracing` subscriber can skip this
+//! module entirely and consume the spans with their own exporter.
+
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::{OxydeError, Result};
+
+/// Configuration for the OTLP exporter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OtlpConfig {
+    /// Collector base URL; spans are posted to `{endpoint}/v1/traces`
+    #[serde(default = "default_endpoint")]
+    pub endpoint: String,
+
+    /// Value of the `service.name` resource attribute
+    #[serde(default = "default_service_name")]
+    pub service_name: String,
+
+    /// Extra HTTP headers sent with every export (e.g. authentication)
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+
+    /// Maximum number of spans per export request
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+
+    /// Maximum time a finished span waits before being exported, in milliseconds
+    #[serde(default = "default_flush_interval")]
+    pub flush_interval_ms: u64,
+}
+
+fn default_endpoint() -> String {
+    "http://localhost:4318".to_string()
+}
+
+fn default_service_name() -> String {
+    "oxyde".to_string()
+}
+
+fn default_batch_size() -> usize {
+    256
+}
+
+fn default_flush_interval() -> u64 {
+    2000
+}
+
+impl Default for OtlpConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: default_endpoint(),
+            service_name: default_service_name(),
+            headers: HashMap::new(),
+            batch_size: default_batch_size(),
+            flush_interval_ms: default_flush_interval(),
+        }
+    }
+}
+
+/// Guard that flushes pending spans and stops the exporter when dropped
+pub struct OtlpGuard {
+    sender: Option<Sender<ExportMessage>>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl Drop for OtlpGuard {
+    fn drop(&mut self) {
+        if let Some(sender) = self.sender.take() {
+            let _ = sender.send(ExportMessage::Shutdown);
+        }
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Install a global `tracing` subscriber that exports spans over OTLP/HTTP
+///
+/// # Arguments
+///
+/// * `config` - Exporter configuration
+///
+/// # Returns
+///
+/// A guard that must be kept alive for as long as spans should be exported
+pub fn init_otlp(config: OtlpConfig) -> Result<OtlpGuard> {
+    let (sender, receiver) = mpsc::channel();
+    let layer = OtlpLayer {
+        sender: sender.clone(),
+    };
+
+    tracing::subscriber::set_global_default(tracing_subscriber::registry().with(layer))
+        .map_err(|e| OxydeError::ConfigurationError(format!("Failed to install tracing subscriber: {}", e)))?;
+
+    let worker = std::thread::Builder::new()
+        .name("oxyde-otlp".to_string())
+        .spawn(move || run_exporter(config, receiver))
+        .map_err(OxydeError::IoError)?;
+
+    Ok(OtlpGuard {
+        sender: Some(sender),
+        worker: Some(worker),
+    })
+}
+
+enum ExportMessage {
+    Span(SpanData),
+    Shutdown,
+}
+
+#[derive(Debug, Clone)]
+struct SpanData {
+    trace_id: String,
+    span_id: String,
+    parent_span_id: Option<String>,
+    name: String,
+    start_unix_nano: u128,
+    end_unix_nano: u128,
+    attributes: Vec<(String, String)>,
+}
+
+/// `tracing` layer that records span timing and fields for export
+struct OtlpLayer {
+    sender: Sender<ExportMessage>,
+}
+
+struct FieldVisitor<'a>(&'a mut Vec<(String, String)>);
+
+impl Visit for FieldVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.push((field.name().to_string(), value.to_string()));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0.push((field.name().to_string(), format!("{:?}", value)));
+    }
+}
+
+fn unix_nanos() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+}
+
+fn random_hex(bytes: usize) -> String {
+    (0..bytes).map(|_| format!("{:02x}", rand::random::<u8>())).collect()
+}
+
+impl<S> Layer<S> for OtlpLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else { return };
+
+        let parent = span
+            .parent()
+            .and_then(|parent| parent.extensions().get::<SpanData>().map(|d| (d.trace_id.clone(), d.span_id.clone())));
+
+        let mut attributes = Vec::new();
+        attrs.record(&mut FieldVisitor(&mut attributes));
+
+        let (trace_id, parent_span_id) = match parent {
+            Some((trace_id, span_id)) => (trace_id, Some(span_id)),
+            None => (random_hex(16), None),
+        };
+
+        span.extensions_mut().insert(SpanData {
+            trace_id,
+            span_id: random_hex(8),
+            parent_span_id,
+            name: span.name().to_string(),
+            start_unix_nano: unix_nanos(),
+            end_unix_nano: 0,
+            attributes,
+        });
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(data) = span.extensions_mut().get_mut::<SpanData>() {
+                values.record(&mut FieldVisitor(&mut data.attributes));
+            }
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(&id) {
+            if let Some(mut data) = span.extensions_mut().remove::<SpanData>() {
+                data.end_unix_nano = unix_nanos();
+                let _ = self.sender.send(ExportMessage::Span(data));
+            }
+        }
+    }
+}
+
+/// Exporter loop, run on a dedicated thread with its own runtime
+fn run_exporter(config: OtlpConfig, receiver: Receiver<ExportMessage>) {
+    let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            log::error!("Failed to start OTLP exporter runtime: {}", e);
+            return;
+        }
+    };
+    let client = reqwest::Client::new();
+    let url = format!("{}/v1/traces", config.endpoint.trim_end_matches('/'));
+    let flush_interval = Duration::from_millis(config.flush_interval_ms);
+
+    let mut batch = Vec::new();
+    let mut deadline = Instant::now() + flush_interval;
+    loop {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        let shutdown = match receiver.recv_timeout(timeout) {
+            Ok(ExportMessage::Span(span)) => {
+                batch.push(span);
+                false
+            }
+            Ok(ExportMessage::Shutdown) | Err(RecvTimeoutError::Disconnected) => true,
+            Err(RecvTimeoutError::Timeout) => false,
+        };
+
+        if !batch.is_empty() && (shutdown || batch.len() >= config.batch_size || Instant::now() >= deadline) {
+            let body = encode_spans(&config.service_name, &batch);
+            let mut request = client.post(&url).json(&body);
+            for (name, value) in &config.headers {
+                request = request.header(name, value);
+            }
+            if let Err(e) = runtime.block_on(request.send()).and_then(|r| r.error_for_status()) {
+                log::warn!("Failed to export {} spans to {}: {}", batch.len(), url, e);
+            }
+            batch.clear();
+        }
+
+        if shutdown {
+            break;
+        }
+        if Instant::now() >= deadline {
+            deadline = Instant::now() + flush_interval;
+        }
+    }
+}
+
+/// Encode spans as an OTLP `ExportTraceServiceRequest` in JSON form
+fn encode_spans(service_name: &str, spans: &[SpanData]) -> serde_json::Value {
+    let spans: Vec<_> = spans
+        .iter()
+        .map(|span| {
+            serde_json::json!({
+                "traceId": span.trace_id,
+                "spanId": span.span_id,
+                "parentSpanId": span.parent_span_id.clone().unwrap_or_default(),
+                "name": span.name,
+                "kind": 1, // SPAN_KIND_INTERNAL
+                "startTimeUnixNano": span.start_unix_nano.to_string(),
+                "endTimeUnixNano": span.end_unix_nano.to_string(),
+                "attributes": span.attributes.iter().map(|(key, value)| serde_json::json!({
+                    "key": key,
+                    "value": { "stringValue": value },
+                })).collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [{
+                    "key": "service.name",
+                    "value": { "stringValue": service_name },
+                }],
+            },
+            "scopeSpans": [{
+                "scope": { "name": "oxyde", "version": crate::VERSION },
+                "spans": spans,
+            }],
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_spans_links_parent() {
+        let span = SpanData {
+            trace_id: "0".repeat(32),
+            span_id: "1".repeat(16),
+            parent_span_id: Some("2".repeat(16)),
+            name: "inference".to_string(),
+            start_unix_nano: 10,
+            end_unix_nano: 20,
+            attributes: vec![("provider".to_string(), "local".to_string())],
+        };
+
+        let body = encode_spans("test-service", &[span]);
+        let encoded = &body["resourceSpans"][0]["scopeSpans"][0]["spans"][0];
+        assert_eq!(encoded["name"], "inference");
+        assert_eq!(encoded["parentSpanId"], "2".repeat(16));
+        assert_eq!(encoded["endTimeUnixNano"], "20");
+        assert_eq!(encoded["attributes"][0]["value"]["stringValue"], "local");
+    }
+}
@@ -0,0 +1,212 @@
+//! Agent templates
+//!
+//! Games often spawn many NPCs from one configuration, such as a village full
+//! of "Villager" agents. An [`AgentTemplate`] validates the configuration and
+//! builds its compiled patterns and prompt layers once; every instance it
+//! spawns shares those through `Arc`s but has its own memories, emotions,
+//! context, topics and relationships with the player, and its own inference
+//! statistics, model policy and mock script. Persisted memories are kept in a
+//! subdirectory of `memory.store.dir` per instance, named after the instance,
+//! or `instance-<n>` for the n-th unnamed one.
+//!
+//! ```no_run
+//! use oxyde::config::AgentConfig;
+//! use oxyde::template::AgentTemplate;
+//!
+//! # async fn spawn() -> oxyde::Result<()> {
+//! let template = AgentTemplate::new(AgentConfig::from_file("villager.yaml")?)?;
+//! let farmer = template.instantiate_named("Villager (farmer)").await;
+//! let smith = template.instantiate_named("Villager (smith)").await;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::agent::{Agent, SharedAgentParts};
+use crate::config::{AgentConfig, MemoryConfig};
+use crate::oxyde_game::behavior::Behavior;
+use crate::Result;
+
+/// Builds a fresh behavior for each instance
+type BehaviorFactory = Arc<dyn Fn() -> Box<dyn Behavior> + Send + Sync>;
+
+/// Shared configuration from which many independent agents are spawned
+pub struct AgentTemplate {
+    /// Immutable parts shared by every instance
+    parts: SharedAgentParts,
+
+    /// Behaviors added to every instance
+    behaviors: Vec<BehaviorFactory>,
+
+    /// Number of instances spawned so far
+    spawned: AtomicUsize,
+}
+
+impl AgentTemplate {
+    /// Create a template from a configuration
+    ///
+    /// The configuration is validated once here rather than per instance.
+    /// If the configuration has a `tts` section, instances share one TTS
+    /// service.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Configuration shared by every instance
+    ///
+    /// # Returns
+    ///
+    /// The template, or an error if the configuration is invalid
+    pub fn new(config: AgentConfig) -> Result<Self> {
+        config.validate()?;
+        Ok(Self {
+            parts: SharedAgentParts::new(config, true),
+            behaviors: Vec::new(),
+            spawned: AtomicUsize::new(0),
+        })
+    }
+
+    /// Add a behavior to every instance
+    ///
+    /// Behaviors can hold per-agent state such as cooldowns, so `factory` is
+    /// called once per instance.
+    pub fn with_behavior<B, F>(mut self, factory: F) -> Self
+    where
+        B: Behavior + 'static,
+        F: Fn() -> B + Send + Sync + 'static,
+    {
+        self.behaviors.push(Arc::new(move || Box::new(factory()) as Box<dyn Behavior>));
+        self
+    }
+
+    /// The configuration shared by every instance
+    pub fn config(&self) -> &AgentConfig {
+        self.parts.config()
+    }
+
+    /// Number of instances spawned from this template
+    pub fn instance_count(&self) -> usize {
+        self.spawned.load(Ordering::Relaxed)
+    }
+
+    /// Spawn an instance named after the configuration
+    ///
+    /// Its memories are persisted under `instance-<n>`, where `n` counts the
+    /// instances spawned before it.
+    pub async fn instantiate(&self) -> Agent {
+        self.spawn(None).await
+    }
+
+    /// Spawn an instance with its own display name
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name reported by the instance's `name()`
+    pub async fn instantiate_named(&self, name: impl Into<String>) -> Agent {
+        self.spawn(Some(name.into())).await
+    }
+
+    async fn spawn(&self, name: Option<String>) -> Agent {
+        let index = self.spawned.fetch_add(1, Ordering::Relaxed);
+        let config = &self.config().memory;
+        let instance = name.clone().unwrap_or_else(|| format!("instance-{}", index));
+        let memory = MemoryConfig {
+            store: config.store.instance(&instance),
+            ..config.clone()
+        };
+        let agent = Agent::from_parts(&self.parts, name, memory);
+        for factory in &self.behaviors {
+            agent.add_boxed_behavior(factory()).await;
+        }
+        agent
+    }
+}
+
+impl fmt::Debug for AgentTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AgentTemplate")
+            .field("name", &self.config().agent.name)
+            .field("behaviors", &self.behaviors.len())
+            .field("instances", &self.instance_count())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AgentPersonality, InferenceConfig};
+    use crate::memory::MemoryCategory;
+    use crate::oxyde_game::behavior::GreetingBehavior;
+
+    fn villager_config() -> AgentConfig {
+        AgentConfig {
+            agent: AgentPersonality {
+                name: "Villager".to_string(),
+                role: "Villager".to_string(),
+                backstory: vec!["Lives in the village".to_string()],
+                knowledge: vec!["The well is in the square".to_string()],
+            },
+            inference: InferenceConfig {
+                use_local: true,
+                local_model_path: Some("models/test.bin".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_instances_share_config_but_not_state() {
+        let template = AgentTemplate::new(villager_config())
+            .unwrap()
+            .with_behavior(|| GreetingBehavior::new("Hello there!"));
+
+        let farmer = template.instantiate_named("Farmer").await;
+        let smith = template.instantiate().await;
+        assert_eq!(template.instance_count(), 2);
+        assert_eq!(farmer.name(), "Farmer");
+        assert_eq!(smith.name(), "Villager");
+        assert_ne!(farmer.id(), smith.id());
+        assert!(std::ptr::eq(farmer.shared_parts().config(), smith.shared_parts().config()));
+        assert!(!std::ptr::eq(farmer.mock_provider(), smith.mock_provider()));
+
+        farmer.start().await.unwrap();
+        smith.start().await.unwrap();
+        farmer.process_input("Hello! I'm Aria and I need help with my crops").await.unwrap();
+        farmer.update_emotion("trust", 0.5).await;
+
+        assert!(farmer.memory_count().await > smith.memory_count().await);
+        let farmer_trust = farmer.emotional_state().await.trust;
+        let smith_trust = smith.emotional_state().await.trust;
+        assert!(farmer_trust > smith_trust);
+    }
+
+    #[tokio::test]
+    async fn test_instances_persist_memories_separately() {
+        let dir = std::env::temp_dir().join(format!("oxyde-villagers-{}", uuid::Uuid::new_v4()));
+        let mut config = villager_config();
+        config.memory.persistence = true;
+        config.memory.store.dir = Some(dir.clone());
+        let template = AgentTemplate::new(config).unwrap();
+
+        let farmer = template.instantiate_named("Farmer").await;
+        let smith = template.instantiate().await;
+        let copy = farmer.clone_for_binding();
+        farmer.add_memory(MemoryCategory::Episodic, "The crows ate the seed", 0.8, None).await.unwrap();
+        smith.add_memory(MemoryCategory::Episodic, "The forge needs coal", 0.8, None).await.unwrap();
+        copy.add_memory(MemoryCategory::Episodic, "The fence is broken", 0.8, None).await.unwrap();
+        drop((farmer, smith, copy));
+
+        // Each instance recovers only its own memories, from its own directory
+        let farmer = template.instantiate_named("Farmer").await;
+        let memories = farmer.get_memories_by_category(MemoryCategory::Episodic).await;
+        assert_eq!(memories.len(), 1);
+        assert_eq!(memories[0].content, "The crows ate the seed");
+        assert!(dir.join("instance-1").is_dir());
+        drop(farmer);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,402 @@
+//! Test utilities for custom behaviors
+//!
+//! Testing a behavior normally means constructing intents, context maps, and
+//! emotional states by hand. This module provides builders for each, and a
+//! [`BehaviorTester`] that runs a behavior the way an agent would and makes
+//! assertions on what it produced.
+//!
+//! The module is compiled for the SDK's own tests and, for downstream crates,
+//! behind the `testkit` feature:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! oxyde = { version = "0.1", features = ["testkit"] }
+//! ```
+//!
+//! ```ignore
+//! use oxyde::oxyde_game::behavior::GreetingBehavior;
+//! use oxyde::testkit::{BehaviorTester, ContextBuilder};
+//!
+//! #[tokio::test]
+//! async fn greets_nearby_players() {
+//!     BehaviorTester::new(GreetingBehavior::new("Hello!"))
+//!         .input("hello there")
+//!         .context(ContextBuilder::new().player_distance(2.0))
+//!         .run()
+//!         .await
+//!         .assert_response("Hello!");
+//! }
+//! ```
+
+use crate::agent::AgentContext;
+pub use crate::context::ContextBuilder;
+use crate::oxyde_game::behavior::{Behavior, BehaviorResult, EmotionInfluence};
+use crate::oxyde_game::emotion::EmotionalState;
+use crate::oxyde_game::intent::{Intent, IntentType};
+
+/// Emotion names in the order of [`EmotionalState::as_vector`]
+const EMOTIONS: [&str; 8] = ["joy", "trust", "fear", "surprise", "sadness", "disgust", "anger", "anticipation"];
+
+/// Get an emotion's value by name
+///
+/// # Panics
+///
+/// Panics if the name is not one of the eight Plutchik emotions
+pub fn emotion_value(state: &EmotionalState, emotion: &str) -> f32 {
+    let index = EMOTIONS
+        .iter()
+        .position(|e| *e == emotion)
+        .unwrap_or_else(|| panic!("unknown emotion {:?}", emotion));
+    state.as_vector()[index]
+}
+
+/// Builder for [`Intent`] values
+#[derive(Debug, Clone)]
+pub struct IntentBuilder {
+    intent_type: IntentType,
+    confidence: f64,
+    input: String,
+    keywords: Option<Vec<String>>,
+}
+
+impl IntentBuilder {
+    /// Start an intent of the given type with full confidence and no input
+    pub fn new(intent_type: IntentType) -> Self {
+        Self {
+            intent_type,
+            confidence: 1.0,
+            input: String::new(),
+            keywords: None,
+        }
+    }
+
+    /// Set the raw input; keywords are extracted from it unless set explicitly
+    pub fn input(mut self, input: &str) -> Self {
+        self.input = input.to_string();
+        self
+    }
+
+    /// Set the classification confidence
+    pub fn confidence(mut self, confidence: f64) -> Self {
+        self.confidence = confidence;
+        self
+    }
+
+    /// Set the keywords
+    pub fn keywords(mut self, keywords: &[&str]) -> Self {
+        self.keywords = Some(keywords.iter().map(|k| k.to_string()).collect());
+        self
+    }
+
+    /// Build the intent
+    pub fn build(self) -> Intent {
+        let keywords = self
+            .keywords
+            .unwrap_or_else(|| Intent::extract_keywords(&self.input));
+        Intent::new(self.intent_type, self.confidence, &self.input, keywords)
+    }
+}
+
+impl From<IntentBuilder> for Intent {
+    fn from(builder: IntentBuilder) -> Self {
+        builder.build()
+    }
+}
+
+/// Builder for [`EmotionalState`] values
+#[derive(Debug, Clone)]
+pub struct EmotionBuilder {
+    state: EmotionalState,
+}
+
+impl Default for EmotionBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EmotionBuilder {
+    /// Start a neutral emotional state
+    pub fn new() -> Self {
+        Self {
+            state: EmotionalState::new(),
+        }
+    }
+
+    /// Set an emotion; its Plutchik opposite is set to the negated value
+    pub fn with(mut self, emotion: &str, value: f32) -> Self {
+        let current = emotion_value(&self.state, emotion);
+        self.state.update_emotion(emotion, value - current);
+        self
+    }
+
+    /// Build the emotional state
+    pub fn build(self) -> EmotionalState {
+        self.state
+    }
+}
+
+impl From<EmotionBuilder> for EmotionalState {
+    fn from(builder: EmotionBuilder) -> Self {
+        builder.build()
+    }
+}
+
+/// Runs a behavior against a prepared intent, context, and emotional state
+pub struct BehaviorTester<B: Behavior> {
+    behavior: B,
+    intent: Option<Intent>,
+    input: String,
+    context: AgentContext,
+    emotions: EmotionalState,
+}
+
+impl<B: Behavior> BehaviorTester<B> {
+    /// Test a behavior; the default input is empty
+    pub fn new(behavior: B) -> Self {
+        Self {
+            behavior,
+            intent: None,
+            input: String::new(),
+            context: AgentContext::new(),
+            emotions: EmotionalState::new(),
+        }
+    }
+
+    /// Use a specific intent
+    pub fn intent(mut self, intent: impl Into<Intent>) -> Self {
+        self.intent = Some(intent.into());
+        self
+    }
+
+    /// Use the intent the agent would classify for player input
+    pub fn input(mut self, input: &str) -> Self {
+        self.intent = None;
+        self.input = input.to_string();
+        self
+    }
+
+    /// Use a context
+    pub fn context(mut self, context: impl Into<AgentContext>) -> Self {
+        self.context = context.into();
+        self
+    }
+
+    /// Use an emotional state for triggers and priority modifiers
+    pub fn emotions(mut self, emotions: impl Into<EmotionalState>) -> Self {
+        self.emotions = emotions.into();
+        self
+    }
+
+    /// Get the behavior under test
+    pub fn behavior(&self) -> &B {
+        &self.behavior
+    }
+
+    /// Run the behavior as an agent would
+    ///
+    /// The emotion trigger and intent match are checked first; the behavior
+    /// only executes when both pass. Its emotion influences are applied to a
+    /// copy of the emotional state when it executes.
+    pub async fn run(&self) -> BehaviorOutcome {
+        let intent = match &self.intent {
+            Some(intent) => intent.clone(),
+            None => Intent::analyze(&self.input)
+                .await
+                .unwrap_or_else(|_| IntentBuilder::new(IntentType::Chat).input(&self.input).build()),
+        };
+        let triggered = self
+            .behavior
+            .emotion_trigger()
+            .is_none_or(|trigger| trigger.matches(&self.emotions));
+        let matched = triggered && self.behavior.matches_intent(&intent).await;
+
+        let mut emotions_after = self.emotions.clone();
+        let mut influences = Vec::new();
+        let result = if matched {
+            let result = self.behavior.execute(&intent, &self.context).await;
+            if result.is_ok() {
+                influences = self.behavior.emotion_influences();
+                for influence in &influences {
+                    emotions_after.update_emotion(&influence.emotion, influence.delta);
+                }
+            }
+            Some(result.map_err(|e| e.to_string()))
+        } else {
+            None
+        };
+
+        BehaviorOutcome {
+            triggered,
+            matched,
+            intent,
+            priority: self.behavior.priority() as i32 + self.behavior.emotional_priority_modifier(&self.emotions),
+            result,
+            influences,
+            emotions_before: self.emotions.clone(),
+            emotions_after,
+        }
+    }
+}
+
+/// What a behavior did in a [`BehaviorTester`] run
+#[derive(Debug)]
+pub struct BehaviorOutcome {
+    /// Whether the emotion trigger passed
+    pub triggered: bool,
+
+    /// Whether the trigger passed and the behavior matched the intent
+    pub matched: bool,
+
+    /// Intent the behavior was run against
+    pub intent: Intent,
+
+    /// Base priority plus emotional modifier
+    pub priority: i32,
+
+    /// Execution result, or `None` if the behavior did not execute
+    pub result: Option<std::result::Result<BehaviorResult, String>>,
+
+    /// Emotion influences applied after execution
+    pub influences: Vec<EmotionInfluence>,
+
+    /// Emotional state before execution
+    pub emotions_before: EmotionalState,
+
+    /// Emotional state after influences were applied
+    pub emotions_after: EmotionalState,
+}
+
+impl BehaviorOutcome {
+    /// Response text, if the behavior responded
+    pub fn response(&self) -> Option<&str> {
+        match &self.result {
+            Some(Ok(BehaviorResult::Response(text))) => Some(text),
+            Some(Ok(BehaviorResult::ResponseWithAction { response, .. })) => Some(response),
+            _ => None,
+        }
+    }
+
+    /// Assert that the behavior matched and executed
+    pub fn assert_matched(&self) -> &Self {
+        assert!(self.triggered, "emotion trigger did not pass");
+        assert!(self.matched, "behavior did not match the intent");
+        self
+    }
+
+    /// Assert that the behavior did not execute
+    pub fn assert_not_matched(&self) -> &Self {
+        assert!(!self.matched, "behavior matched, result: {:?}", self.result);
+        self
+    }
+
+    /// Assert that the behavior responded with exactly `expected`
+    pub fn assert_response(&self, expected: &str) -> &Self {
+        assert_eq!(self.response(), Some(expected), "result: {:?}", self.result);
+        self
+    }
+
+    /// Assert that the behavior responded with text containing `fragment`
+    pub fn assert_response_contains(&self, fragment: &str) -> &Self {
+        let response = self.response().unwrap_or_else(|| panic!("no response, result: {:?}", self.result));
+        assert!(response.contains(fragment), "response {:?} does not contain {:?}", response, fragment);
+        self
+    }
+
+    /// Assert that the behavior emitted exactly `expected` as an action
+    pub fn assert_action(&self, expected: &str) -> &Self {
+        match &self.result {
+            Some(Ok(BehaviorResult::Action(action))) => assert_eq!(action, expected),
+            Some(Ok(BehaviorResult::ResponseWithAction { action, .. })) => assert_eq!(action, expected),
+            other => panic!("expected action {:?}, result: {:?}", expected, other),
+        }
+        self
+    }
+
+    /// Assert that the behavior executed and returned nothing
+    pub fn assert_no_result(&self) -> &Self {
+        assert!(
+            matches!(self.result, Some(Ok(BehaviorResult::None))),
+            "expected no result, result: {:?}",
+            self.result
+        );
+        self
+    }
+
+    /// Assert that execution failed with an error containing `fragment`
+    pub fn assert_error(&self, fragment: &str) -> &Self {
+        match &self.result {
+            Some(Err(error)) => assert!(error.contains(fragment), "error {:?} does not contain {:?}", error, fragment),
+            other => panic!("expected error, result: {:?}", other),
+        }
+        self
+    }
+
+    /// Assert that the behavior declares an influence on `emotion` with the given delta
+    pub fn assert_influence(&self, emotion: &str, delta: f32) -> &Self {
+        assert!(
+            self.influences
+                .iter()
+                .any(|i| i.emotion == emotion && (i.delta - delta).abs() < 1e-4),
+            "no influence {} {:+}, influences: {:?}",
+            emotion,
+            delta,
+            self.influences
+        );
+        self
+    }
+
+    /// Assert that executing the behavior raised an emotion
+    pub fn assert_emotion_increased(&self, emotion: &str) -> &Self {
+        let before = emotion_value(&self.emotions_before, emotion);
+        let after = emotion_value(&self.emotions_after, emotion);
+        assert!(after > before, "{} went from {} to {}", emotion, before, after);
+        self
+    }
+
+    /// Assert that executing the behavior lowered an emotion
+    pub fn assert_emotion_decreased(&self, emotion: &str) -> &Self {
+        let before = emotion_value(&self.emotions_before, emotion);
+        let after = emotion_value(&self.emotions_after, emotion);
+        assert!(after < before, "{} went from {} to {}", emotion, before, after);
+        self
+    }
+
+    /// Assert the effective priority
+    pub fn assert_priority(&self, expected: i32) -> &Self {
+        assert_eq!(self.priority, expected, "unexpected effective priority");
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oxyde_game::behavior::{AggressiveBehavior, GreetingBehavior};
+
+    #[tokio::test]
+    async fn test_behavior_tester() {
+        BehaviorTester::new(GreetingBehavior::new("Hello!"))
+            .input("hello there")
+            .context(ContextBuilder::new().player_distance(2.0))
+            .run()
+            .await
+            .assert_matched()
+            .assert_response("Hello!");
+
+        BehaviorTester::new(GreetingBehavior::new("Hello!"))
+            .intent(IntentBuilder::new(IntentType::Greeting).input("hi"))
+            .run()
+            .await
+            .assert_matched()
+            .assert_no_result();
+
+        let calm = BehaviorTester::new(AggressiveBehavior::new(0.5)).run().await;
+        calm.assert_not_matched();
+        assert!(!calm.triggered);
+
+        let state = EmotionBuilder::new().with("anger", 0.8).build();
+        assert_eq!(emotion_value(&state, "anger"), 0.8);
+        assert_eq!(emotion_value(&state, "fear"), -0.8);
+    }
+}
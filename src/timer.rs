@@ -0,0 +1,78 @@
+//! Clocks and timers that work in the browser
+//!
+//! Tokio's timers need a Tokio runtime driving them, which browsers don't
+//! have: promises returned to JavaScript are polled by the browser's event
+//! loop instead. `std::time::Instant::now` panics on `wasm32-unknown-unknown`
+//! too. Code on the input pipeline uses this module instead, which is Tokio
+//! and `std` natively and `setTimeout` and `performance.now()` in the browser.
+//!
+//! Background tasks started with `tokio::spawn` (forgetting, greeting
+//! anticipation) still need a Tokio runtime and are not available in the
+//! browser.
+
+use std::future::Future;
+use std::time::Duration;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+pub use web_time::Instant;
+
+/// Error returned when a future outlives its timeout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Elapsed;
+
+/// Wait for the given duration
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await
+}
+
+/// Wait for the given duration
+#[cfg(target_arch = "wasm32")]
+pub async fn sleep(duration: Duration) {
+    gloo_timers::future::sleep(duration).await
+}
+
+/// Run a future, giving up once `duration` has passed
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn timeout<F: Future>(duration: Duration, future: F) -> Result<F::Output, Elapsed> {
+    tokio::time::timeout(duration, future).await.map_err(|_| Elapsed)
+}
+
+/// Run a future, giving up once `duration` has passed
+#[cfg(target_arch = "wasm32")]
+pub async fn timeout<F: Future>(duration: Duration, future: F) -> Result<F::Output, Elapsed> {
+    use futures::future::{select, Either};
+
+    let future = std::pin::pin!(future);
+    let timer = std::pin::pin!(sleep(duration));
+    match select(future, timer).await {
+        Either::Left((output, _)) => Ok(output),
+        Either::Right(_) => Err(Elapsed),
+    }
+}
+
+/// Run a future, giving up at `deadline`
+pub async fn timeout_at<F: Future>(deadline: Instant, future: F) -> Result<F::Output, Elapsed> {
+    timeout(deadline.saturating_duration_since(Instant::now()), future).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_timeout_gives_up_at_the_deadline() {
+        let slow = sleep(Duration::from_secs(60));
+        assert_eq!(timeout(Duration::from_millis(10), slow).await, Err(Elapsed));
+
+        let deadline = Instant::now() + Duration::from_secs(60);
+        assert_eq!(timeout_at(deadline, async { 7 }).await, Ok(7));
+
+        // A deadline already passed still lets a ready future finish
+        let passed = Instant::now();
+        sleep(Duration::from_millis(1)).await;
+        assert_eq!(timeout_at(passed, async { 7 }).await, Ok(7));
+    }
+}
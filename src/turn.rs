@@ -0,0 +1,148 @@
+//! Turn-based execution for the Oxyde SDK
+//!
+//! This module provides the input and output types for [`Agent::step`](crate::agent::Agent::step),
+//! a synchronous, deterministic alternative to `process_input` intended for
+//! turn-based games, lockstep simulation, and unit tests.
+
+use std::collections::HashMap;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+
+use crate::oxyde_game::intent::IntentType;
+use crate::AgentContext;
+
+/// Context key holding the per-turn RNG seed passed to behaviors
+pub const TURN_SEED_KEY: &str = "turn_seed";
+
+/// Context key holding the current turn number passed to behaviors
+pub const TURN_NUMBER_KEY: &str = "turn";
+
+/// How a turn may fall back to inference when no behavior responds
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum TurnInference {
+    /// Never run inference; the turn produces no response text
+    #[default]
+    Disabled,
+    /// Look up the response by exact (trimmed, lowercased) input text
+    Cached(HashMap<String, String>),
+    /// Run the local inference provider configured for the agent
+    Local,
+}
+
+/// Input for a single deterministic turn
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TurnInput {
+    /// Turn number supplied by the game simulation
+    pub turn: u64,
+
+    /// Player input for this turn
+    pub input: String,
+
+    /// Seed for any randomness used while executing the turn
+    pub seed: u64,
+
+    /// Context for this turn only, merged over the agent's stored context
+    #[serde(default)]
+    pub context: AgentContext,
+
+    /// Inference fallback used when no behavior responds
+    #[serde(default)]
+    pub inference: TurnInference,
+}
+
+impl TurnInput {
+    /// Create a new turn input with a zero seed and inference disabled
+    ///
+    /// # Arguments
+    ///
+    /// * `turn` - Turn number supplied by the game simulation
+    /// * `input` - Player input for this turn
+    pub fn new(turn: u64, input: &str) -> Self {
+        Self {
+            turn,
+            input: input.to_string(),
+            seed: 0,
+            context: AgentContext::new(),
+            inference: TurnInference::Disabled,
+        }
+    }
+
+    /// Set the RNG seed for this turn
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Set the per-turn context
+    pub fn with_context(mut self, context: AgentContext) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Set the inference fallback mode
+    pub fn with_inference(mut self, inference: TurnInference) -> Self {
+        self.inference = inference;
+        self
+    }
+
+    /// Seed derived from the input seed and turn number
+    ///
+    /// Mixing in the turn number keeps consecutive turns from repeating the
+    /// same random choices when the game reuses one seed for a whole match.
+    pub fn effective_seed(&self) -> u64 {
+        self.seed ^ self.turn.wrapping_mul(0x9E37_79B9_7F4A_7C15)
+    }
+}
+
+/// Where the response for a turn came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TurnSource {
+    /// Input was rejected by content moderation
+    Moderation,
+    /// The agent is unavailable during its current scheduled activity
+    Schedule,
+    /// A behavior produced the response
+    Behavior,
+    /// The cached response table produced the response
+    Cache,
+    /// The local inference provider produced the response
+    Inference,
+    /// Nothing produced a response
+    None,
+}
+
+/// Result of a single deterministic turn
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TurnOutput {
+    /// Turn number this output belongs to
+    pub turn: u64,
+
+    /// Response text, if any
+    pub response: Option<String>,
+
+    /// Actions emitted by behaviors during the turn, in execution order
+    pub actions: Vec<String>,
+
+    /// Intent detected from the input
+    pub intent: IntentType,
+
+    /// Source of the response
+    pub source: TurnSource,
+
+    /// Emotion vector at the end of the turn
+    pub emotions: [f32; 8],
+}
+
+/// Build an RNG for a behavior invocation
+///
+/// Uses the turn seed from the context when present so behaviors executed
+/// through [`Agent::step`](crate::agent::Agent::step) make reproducible choices,
+/// and falls back to entropy otherwise.
+pub(crate) fn context_rng(context: &AgentContext) -> StdRng {
+    match context.get(TURN_SEED_KEY).and_then(|v| v.as_u64()) {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    }
+}
@@ -28,6 +28,18 @@ pub fn generate_id() -> String {
     format!("oxid-{}-{}", timestamp, counter)
 }
 
+/// Returns the current timestamp in seconds
+///
+/// # Returns
+///
+/// The current time in seconds since the Unix epoch
+pub fn current_timestamp_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
 /// Returns the current timestamp in milliseconds
 ///
 /// # Returns
@@ -83,7 +95,9 @@ pub fn load_moderation_patterns(patterns_file: &str) -> Result<RegexSet> {
 ///
 /// This function only flags severe categories (sexual content, hate speech, violence, self-harm)
 /// and ignores mild harassment to be more appropriate for game contexts where players might
-/// express frustration or be rude to NPCs.
+/// express frustration or be rude to NPCs. Without the `reqwest` feature it
+/// always returns an error.
+#[cfg(feature = "reqwest")]
 pub async fn check_cloud_moderation(content: &str, api_key: &str) -> Result<bool> {
     let client = reqwest::Client::new();
     
@@ -126,6 +140,14 @@ pub async fn check_cloud_moderation(content: &str, api_key: &str) -> Result<bool
     Ok(should_moderate)
 }
 
+/// Check content against cloud moderation; unavailable without the `reqwest` feature
+#[cfg(not(feature = "reqwest"))]
+pub async fn check_cloud_moderation(_content: &str, _api_key: &str) -> Result<bool> {
+    Err(crate::OxydeError::InferenceError(
+        "Cloud moderation requires the `reqwest` feature".to_string()
+    ))
+}
+
 /// Calculate the relevance score for a memory based on its content and a query
 ///
 /// # Arguments
@@ -192,6 +214,54 @@ pub fn truncate_string(s: &str, max_len: usize) -> String {
     }
 }
 
+/// Punctuation ending a sentence
+const SENTENCE_PUNCTUATION: [char; 4] = ['.', '!', '?', '…'];
+
+/// Quotes and brackets that may close a sentence after its punctuation
+const SENTENCE_CLOSERS: [char; 7] = ['"', '\'', ')', ']', '”', '’', '»'];
+
+/// Byte offsets just past each sentence end in the text
+///
+/// A sentence ends at a run of `.`, `!`, `?` or `…` and the closing quotes
+/// or brackets after it, followed by whitespace or the end of the text, so
+/// decimals and abbreviations inside a word do not split.
+pub fn sentence_ends(text: &str) -> Vec<usize> {
+    let mut ends = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        if !SENTENCE_PUNCTUATION.contains(&c) {
+            continue;
+        }
+        let mut end = index + c.len_utf8();
+        while let Some(&(next_index, next)) = chars.peek() {
+            if !SENTENCE_PUNCTUATION.contains(&next) && !SENTENCE_CLOSERS.contains(&next) {
+                break;
+            }
+            end = next_index + next.len_utf8();
+            chars.next();
+        }
+        if chars.peek().is_none_or(|(_, next)| next.is_whitespace()) {
+            ends.push(end);
+        }
+    }
+    ends
+}
+
+/// Split text into its trimmed, non-empty sentences
+///
+/// Sentences end where [`sentence_ends`] finds them; trailing text without
+/// final punctuation is a sentence of its own.
+pub fn sentences(text: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    for end in sentence_ends(text).into_iter().chain(std::iter::once(text.len())) {
+        sentences.push(text[start..end].trim());
+        start = end;
+    }
+    sentences.retain(|sentence| !sentence.is_empty());
+    sentences
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -230,4 +300,13 @@ mod tests {
         assert_eq!(truncated.len(), 20);
         assert!(truncated.ends_with("..."));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_sentences_end_at_punctuation_before_whitespace() {
+        let text = "It costs 2.5 gold. \"Deal?\" he asked… Fine!";
+        assert_eq!(sentence_ends(text), vec![18, 26, 38, text.len()]);
+        assert_eq!(sentences(text), vec!["It costs 2.5 gold.", "\"Deal?\"", "he asked…", "Fine!"]);
+        assert_eq!(sentences("No punctuation here "), vec!["No punctuation here"]);
+        assert!(sentences("  ").is_empty());
+    }
+}
@@ -0,0 +1,207 @@
+//! Response length budgets per interaction channel
+//!
+//! A line shown in a speech bubble has to be much shorter than one shown on a
+//! dialogue screen. The game names the channel a response is shown on in the
+//! `verbosity` context key, and the agent's verbosity configuration gives
+//! each channel a word and character budget:
+//!
+//! ```yaml
+//! verbosity:
+//!   default_channel: dialog
+//!   channels:
+//!     bubble:
+//!       max_words: 12
+//!       max_chars: 80
+//!     dialog:
+//!       max_words: 60
+//! ```
+//!
+//! The budget is stated in the inference prompt, together with any sentence
+//! length adaptive sampling asks for, and generated responses
+//! that still exceed it are cut after the last whole sentence that fits.
+//! Responses written by behaviors are not shortened.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::agent::AgentContext;
+use crate::postprocess::truncate;
+use crate::utils::sentence_ends;
+use crate::{OxydeError, Result};
+
+/// Context key naming the channel a response will be shown on
+pub const VERBOSITY_KEY: &str = "verbosity";
+
+/// Length limits for responses on one channel
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerbosityBudget {
+    /// Maximum words per response
+    #[serde(default)]
+    pub max_words: Option<usize>,
+
+    /// Maximum characters per response
+    #[serde(default)]
+    pub max_chars: Option<usize>,
+}
+
+impl VerbosityBudget {
+    /// Prompt instruction stating the budget
+    pub fn instruction(&self) -> Option<String> {
+        length_instruction(Some(*self), None)
+    }
+
+    /// The budget as stated in prompts, such as `12 words and 80 characters`
+    fn limit(&self) -> Option<String> {
+        match (self.max_words, self.max_chars) {
+            (Some(words), Some(chars)) => Some(format!("{} words and {} characters", words, chars)),
+            (Some(words), None) => Some(format!("{} words", words)),
+            (None, Some(chars)) => Some(format!("{} characters", chars)),
+            (None, None) => None,
+        }
+    }
+
+    /// Whether text is within the budget
+    pub fn fits(&self, text: &str) -> bool {
+        self.max_words.is_none_or(|max| text.split_whitespace().count() <= max)
+            && self.max_chars.is_none_or(|max| text.chars().count() <= max)
+    }
+
+    /// Shorten text to the budget
+    ///
+    /// Keeps as many whole sentences as fit. If not even the first sentence
+    /// fits, it is cut to the word budget and then the character budget,
+    /// ending with an ellipsis.
+    pub fn enforce(&self, text: &str) -> String {
+        let text = text.trim();
+        if self.fits(text) {
+            return text.to_string();
+        }
+
+        let kept = sentence_ends(text)
+            .into_iter()
+            .map(|end| text[..end].trim_end())
+            .take_while(|prefix| self.fits(prefix))
+            .last();
+        if let Some(kept) = kept {
+            return kept.to_string();
+        }
+
+        let mut cut = text.to_string();
+        if let Some(max) = self.max_words.filter(|max| text.split_whitespace().count() > *max) {
+            let words: Vec<&str> = text.split_whitespace().take(max).collect();
+            cut = format!("{}…", words.join(" ").trim_end_matches([',', ';', ':']));
+        }
+        match self.max_chars {
+            Some(max) => truncate(&cut, max),
+            None => cut,
+        }
+    }
+}
+
+/// Prompt instruction stating a reply's budget and the longest sentence
+/// adaptive sampling asks for, as one instruction
+///
+/// The sentence length is left out when the budget allows no more words.
+pub fn length_instruction(budget: Option<VerbosityBudget>, max_sentence_words: Option<usize>) -> Option<String> {
+    let sentence_words = max_sentence_words
+        .filter(|words| budget.and_then(|budget| budget.max_words).is_none_or(|max| max > *words));
+    match (budget.and_then(|budget| budget.limit()), sentence_words) {
+        (Some(limit), Some(words)) => Some(format!(
+            "Keep your reply to at most {}, in short sentences of at most {} words.",
+            limit, words
+        )),
+        (Some(limit), None) => Some(format!("Keep your reply to at most {}.", limit)),
+        (None, Some(words)) => Some(format!("Speak in short sentences of at most {} words.", words)),
+        (None, None) => None,
+    }
+}
+
+/// Configuration for response length budgets
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VerbosityConfig {
+    /// Channel used when the context names none
+    #[serde(default)]
+    pub default_channel: Option<String>,
+
+    /// Budget for each channel
+    #[serde(default)]
+    pub channels: HashMap<String, VerbosityBudget>,
+}
+
+impl VerbosityConfig {
+    /// Validate the verbosity configuration
+    pub fn validate(&self) -> Result<()> {
+        for (channel, budget) in &self.channels {
+            if budget.max_words == Some(0) || budget.max_chars == Some(0) {
+                return Err(OxydeError::ConfigurationError(format!(
+                    "Verbosity limits for channel {} must be greater than 0",
+                    channel
+                )));
+            }
+        }
+        if let Some(channel) = &self.default_channel {
+            if !self.channels.contains_key(channel) {
+                return Err(OxydeError::ConfigurationError(format!(
+                    "Default verbosity channel {} has no budget",
+                    channel
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Budget for the channel named in context, or the default channel
+    ///
+    /// Unknown channels have no budget.
+    pub fn budget_for(&self, context: &AgentContext) -> Option<VerbosityBudget> {
+        let channel = context
+            .get(VERBOSITY_KEY)
+            .and_then(|v| v.as_str())
+            .or(self.default_channel.as_deref())?;
+        self.channels.get(channel).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budgets_cut_at_sentence_boundaries() {
+        let config: VerbosityConfig = serde_yaml::from_str(
+            "default_channel: dialog\n\
+             channels:\n  \
+               bubble:\n    max_words: 6\n    max_chars: 40\n  \
+               dialog:\n    max_words: 60\n",
+        )
+        .unwrap();
+        config.validate().unwrap();
+
+        let mut context = AgentContext::new();
+        assert_eq!(config.budget_for(&context).unwrap().max_words, Some(60));
+        context.insert(VERBOSITY_KEY.to_string(), serde_json::json!("bubble"));
+        let budget = config.budget_for(&context).unwrap();
+        assert_eq!(
+            budget.instruction().unwrap(),
+            "Keep your reply to at most 6 words and 40 characters."
+        );
+
+        let response = "Welcome, traveler! The forge is hot today. Mind the sparks.";
+        assert_eq!(budget.enforce(response), "Welcome, traveler!");
+        assert_eq!(budget.enforce("Fine."), "Fine.");
+        assert_eq!(
+            budget.enforce("I have swords, shields, helmets and boots for sale."),
+            "I have swords, shields, helmets and…"
+        );
+        assert_eq!(
+            VerbosityBudget { max_words: None, max_chars: Some(5) }.enforce("Extraordinary"),
+            "Extr…"
+        );
+
+        context.insert(VERBOSITY_KEY.to_string(), serde_json::json!("billboard"));
+        assert!(config.budget_for(&context).is_none());
+        let missing = VerbosityConfig { default_channel: Some("bubble".to_string()), ..Default::default() };
+        assert!(missing.validate().is_err());
+    }
+}
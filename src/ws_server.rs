@@ -0,0 +1,598 @@
+//! WebSocket sessions for server-authoritative browser games
+//!
+//! Browser multiplayer games that keep NPC inference on the server can expose
+//! the agents in a binding's [`AgentRegistry`] over WebSocket. Each connection
+//! is a bidirectional session: clients send player input for any agent and
+//! subscribe to the responses, actions and emotion updates of the agents they
+//! care about, so every player near an NPC sees what it says.
+//!
+//! Messages are JSON text frames tagged with `type`:
+//!
+//! ```text
+//! -> {"type":"subscribe","agent_id":"..."}
+//! <- {"type":"subscribed","agent_id":"..."}
+//! -> {"type":"input","agent_id":"...","text":"Hello!","request_id":7}
+//! <- {"type":"response","agent_id":"...","text":"Welcome, traveler."}
+//! <- {"type":"emotions","agent_id":"...","emotions":{"joy":0.1,...}}
+//! <- {"type":"reply","agent_id":"...","text":"Welcome, traveler.","request_id":7}
+//! ```
+//!
+//! `reply` goes only to the client that sent the input; `response`,
+//! `action` and `emotions` go to every subscriber, including the sender if
+//! it is subscribed. Responses produced outside a session, for example by
+//! game scripts calling `process_input` directly, are pushed to subscribers
+//! too.
+//!
+//! Each connection has a bounded queue of outgoing messages. A client that
+//! stops reading fills it, after which the server stops reading that
+//! client's messages until it catches up. A client may also only have a few
+//! inputs in flight; further inputs are answered with an `error` until one
+//! of them is replied to.
+//!
+//! This module requires the `ws-server` feature.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, mpsc, Semaphore};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::agent::{Agent, AgentEvent};
+use crate::oxyde_game::bindings::AgentRegistry;
+use crate::oxyde_game::emotion::EmotionalState;
+use crate::{OxydeError, Result};
+
+/// Default number of updates buffered per agent for slow subscribers
+const DEFAULT_CHANNEL_CAPACITY: usize = 64;
+
+/// Default number of messages queued for a client before the server waits
+/// for it to read them
+const DEFAULT_OUTBOX_CAPACITY: usize = 256;
+
+/// Default number of inputs a client may have in flight at once
+const DEFAULT_MAX_INPUTS_IN_FLIGHT: usize = 4;
+
+/// Pause after a failed accept, so running out of file descriptors does
+/// not spin the accept loop
+const ACCEPT_RETRY_DELAY: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// A message from a client
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// Receive updates from an agent
+    Subscribe {
+        /// Registry ID of the agent
+        agent_id: String,
+    },
+
+    /// Stop receiving updates from an agent
+    Unsubscribe {
+        /// Registry ID of the agent
+        agent_id: String,
+    },
+
+    /// Send player input to an agent
+    Input {
+        /// Registry ID of the agent
+        agent_id: String,
+
+        /// Player input
+        text: String,
+
+        /// Echoed in the reply so clients can match it to the input
+        #[serde(default)]
+        request_id: Option<u64>,
+    },
+}
+
+/// A message from the server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// The client is now subscribed to an agent
+    Subscribed {
+        /// Registry ID of the agent
+        agent_id: String,
+    },
+
+    /// The client is no longer subscribed to an agent
+    Unsubscribed {
+        /// Registry ID of the agent
+        agent_id: String,
+    },
+
+    /// The agent's answer to this client's input
+    Reply {
+        /// Registry ID of the agent
+        agent_id: String,
+
+        /// Agent response
+        text: String,
+
+        /// Request ID from the input
+        request_id: Option<u64>,
+    },
+
+    /// The agent responded to someone
+    Response {
+        /// Registry ID of the agent
+        agent_id: String,
+
+        /// Agent response
+        text: String,
+    },
+
+    /// The agent performed an action
+    Action {
+        /// Registry ID of the agent
+        agent_id: String,
+
+        /// Action data from the behavior
+        action: String,
+    },
+
+    /// The agent's emotions after an input
+    Emotions {
+        /// Registry ID of the agent
+        agent_id: String,
+
+        /// Current emotional state
+        emotions: EmotionalState,
+    },
+
+    /// A message could not be handled
+    Error {
+        /// Description of the problem
+        message: String,
+
+        /// Request ID from the input, if the error answers one
+        request_id: Option<u64>,
+    },
+}
+
+/// Serves WebSocket sessions for the agents in a registry
+pub struct SessionServer {
+    /// Agents that clients can talk to
+    registry: AgentRegistry,
+
+    /// Update channels for agents with at least one subscription so far
+    channels: Mutex<HashMap<String, broadcast::Sender<ServerMessage>>>,
+
+    /// Updates buffered per agent before slow subscribers miss some
+    channel_capacity: usize,
+
+    /// Messages queued per connection before the server waits for the
+    /// client to read them
+    outbox_capacity: usize,
+
+    /// Inputs a connection may have in flight at once
+    max_inputs_in_flight: usize,
+}
+
+impl SessionServer {
+    /// Create a server for the agents in a registry
+    ///
+    /// # Arguments
+    ///
+    /// * `registry` - Registry shared with a binding, see `WasmBinding::registry`
+    pub fn new(registry: AgentRegistry) -> Self {
+        Self {
+            registry,
+            channels: Mutex::new(HashMap::new()),
+            channel_capacity: DEFAULT_CHANNEL_CAPACITY,
+            outbox_capacity: DEFAULT_OUTBOX_CAPACITY,
+            max_inputs_in_flight: DEFAULT_MAX_INPUTS_IN_FLIGHT,
+        }
+    }
+
+    /// Set how many updates are buffered per agent for slow subscribers
+    pub fn with_channel_capacity(mut self, capacity: usize) -> Self {
+        self.channel_capacity = capacity.max(1);
+        self
+    }
+
+    /// Set how many messages are queued per connection before the server
+    /// stops reading from a client that does not read its messages
+    pub fn with_outbox_capacity(mut self, capacity: usize) -> Self {
+        self.outbox_capacity = capacity.max(1);
+        self
+    }
+
+    /// Set how many inputs a connection may have in flight at once
+    pub fn with_max_inputs_in_flight(mut self, max: usize) -> Self {
+        self.max_inputs_in_flight = max.max(1);
+        self
+    }
+
+    /// Bind to an address and serve sessions
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - Address to listen on, such as `0.0.0.0:9001`
+    pub async fn listen(self: Arc<Self>, addr: &str) -> Result<()> {
+        let listener = TcpListener::bind(addr).await?;
+        log::info!("WebSocket session server listening on {}", listener.local_addr()?);
+        self.serve(listener).await
+    }
+
+    /// Serve sessions on a bound listener
+    ///
+    /// Runs until the returned future is dropped. Failed accepts, such as a
+    /// connection reset before it was accepted or running out of file
+    /// descriptors, are logged and retried after a short pause.
+    pub async fn serve(self: Arc<Self>, listener: TcpListener) -> Result<()> {
+        loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    log::warn!("WebSocket session server failed to accept a connection: {}", e);
+                    tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+                    continue;
+                }
+            };
+            let server = self.clone();
+            tokio::spawn(async move {
+                if let Err(e) = server.run_session(stream).await {
+                    log::warn!("WebSocket session with {} ended: {}", peer, e);
+                }
+            });
+        }
+    }
+
+    /// Number of sessions subscribed to an agent
+    pub fn subscriber_count(&self, agent_id: &str) -> usize {
+        self.lock_channels()
+            .get(agent_id)
+            .map_or(0, |channel| channel.receiver_count())
+    }
+
+    /// Run one client session until the client disconnects
+    async fn run_session(self: Arc<Self>, stream: TcpStream) -> Result<()> {
+        let socket = tokio_tungstenite::accept_async(stream).await.map_err(ws_error)?;
+        let (mut sink, mut source) = socket.split();
+
+        // Subscriptions and input tasks all write through one queue
+        let (outgoing, mut outbox) = mpsc::channel::<ServerMessage>(self.outbox_capacity);
+        let inputs = Arc::new(Semaphore::new(self.max_inputs_in_flight));
+        tokio::spawn(async move {
+            while let Some(message) = outbox.recv().await {
+                let Ok(text) = serde_json::to_string(&message) else {
+                    continue;
+                };
+                if sink.send(Message::Text(text)).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut subscriptions = HashMap::new();
+        let result = async {
+            while let Some(frame) = source.next().await {
+                let text = match frame.map_err(ws_error)? {
+                    Message::Text(text) => text,
+                    Message::Close(_) => break,
+                    // Pings are answered by the WebSocket layer
+                    _ => continue,
+                };
+                let reply = match serde_json::from_str::<ClientMessage>(&text) {
+                    Ok(message) => self.handle(message, &outgoing, &inputs, &mut subscriptions),
+                    Err(e) => Some(ServerMessage::Error {
+                        message: format!("Invalid message: {}", e),
+                        request_id: None,
+                    }),
+                };
+                // Waiting for room stops reading from a client that is not reading
+                if let Some(reply) = reply {
+                    if outgoing.send(reply).await.is_err() {
+                        break;
+                    }
+                }
+            }
+            Ok(())
+        }
+        .await;
+
+        for task in subscriptions.into_values() {
+            task.abort();
+        }
+        result
+    }
+
+    /// Handle a message from a client
+    ///
+    /// # Returns
+    ///
+    /// The message to answer with right away, if any; inputs are answered
+    /// once they are processed
+    fn handle(
+        self: &Arc<Self>,
+        message: ClientMessage,
+        outgoing: &mpsc::Sender<ServerMessage>,
+        inputs: &Arc<Semaphore>,
+        subscriptions: &mut HashMap<String, JoinHandle<()>>,
+    ) -> Option<ServerMessage> {
+        let reply = match message {
+            ClientMessage::Subscribe { agent_id } => match self.agent(&agent_id) {
+                Ok(agent) => {
+                    if !subscriptions.contains_key(&agent_id) {
+                        let updates = self.channel(&agent_id, &agent).subscribe();
+                        let task = tokio::spawn(forward(updates, outgoing.clone()));
+                        subscriptions.insert(agent_id.clone(), task);
+                    }
+                    ServerMessage::Subscribed { agent_id }
+                }
+                Err(e) => ServerMessage::Error {
+                    message: e.to_string(),
+                    request_id: None,
+                },
+            },
+            ClientMessage::Unsubscribe { agent_id } => {
+                if let Some(task) = subscriptions.remove(&agent_id) {
+                    task.abort();
+                }
+                ServerMessage::Unsubscribed { agent_id }
+            }
+            ClientMessage::Input {
+                agent_id,
+                text,
+                request_id,
+            } => match self.agent(&agent_id) {
+                Ok(agent) => {
+                    let Ok(permit) = inputs.clone().try_acquire_owned() else {
+                        return Some(ServerMessage::Error {
+                            message: format!(
+                                "Too many inputs in flight; wait for a reply before sending more than {}",
+                                self.max_inputs_in_flight
+                            ),
+                            request_id,
+                        });
+                    };
+                    // Inputs run concurrently; the agent's request queue orders them
+                    let server = self.clone();
+                    let outgoing = outgoing.clone();
+                    tokio::spawn(async move {
+                        let reply = match agent.process_input(&text).await {
+                            Ok(text) => ServerMessage::Reply {
+                                agent_id: agent_id.clone(),
+                                text,
+                                request_id,
+                            },
+                            Err(e) => ServerMessage::Error {
+                                message: e.to_string(),
+                                request_id,
+                            },
+                        };
+                        let emotions = agent.emotional_state().await;
+                        server.publish(&agent_id, ServerMessage::Emotions { agent_id: agent_id.clone(), emotions });
+                        // Free the slot first, so a client answering the reply is not refused
+                        drop(permit);
+                        let _ = outgoing.send(reply).await;
+                    });
+                    return None;
+                }
+                Err(e) => ServerMessage::Error {
+                    message: e.to_string(),
+                    request_id,
+                },
+            },
+        };
+        Some(reply)
+    }
+
+    /// Look up an agent in the registry
+    fn agent(&self, agent_id: &str) -> Result<Arc<Agent>> {
+        let agents = self.registry.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        agents
+            .get(agent_id)
+            .cloned()
+            .ok_or_else(|| OxydeError::BindingError(format!("Agent with ID {} not found", agent_id)))
+    }
+
+    /// Get an agent's update channel, creating it on first use
+    ///
+    /// Creating the channel registers callbacks that push the agent's
+    /// responses and actions to it.
+    fn channel(&self, agent_id: &str, agent: &Agent) -> broadcast::Sender<ServerMessage> {
+        let mut channels = self.lock_channels();
+        if let Some(channel) = channels.get(agent_id) {
+            return channel.clone();
+        }
+
+        let (channel, _) = broadcast::channel(self.channel_capacity);
+        for event in [AgentEvent::Response, AgentEvent::Action] {
+            let channel = channel.clone();
+            let agent_id = agent_id.to_string();
+            agent.on_event(event, move |_, data| {
+                let update = match event {
+                    AgentEvent::Action => ServerMessage::Action {
+                        agent_id: agent_id.clone(),
+                        action: data.to_string(),
+                    },
+                    _ => ServerMessage::Response {
+                        agent_id: agent_id.clone(),
+                        text: data.to_string(),
+                    },
+                };
+                // Sending fails only when nobody is subscribed
+                let _ = channel.send(update);
+            });
+        }
+        channels.insert(agent_id.to_string(), channel.clone());
+        channel
+    }
+
+    /// Push an update to an agent's subscribers, if it has a channel
+    fn publish(&self, agent_id: &str, update: ServerMessage) {
+        if let Some(channel) = self.lock_channels().get(agent_id) {
+            let _ = channel.send(update);
+        }
+    }
+
+    fn lock_channels(&self) -> std::sync::MutexGuard<'_, HashMap<String, broadcast::Sender<ServerMessage>>> {
+        self.channels.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl std::fmt::Debug for SessionServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SessionServer")
+            .field("channels", &self.lock_channels().len())
+            .field("channel_capacity", &self.channel_capacity)
+            .field("outbox_capacity", &self.outbox_capacity)
+            .field("max_inputs_in_flight", &self.max_inputs_in_flight)
+            .finish()
+    }
+}
+
+/// Forward an agent's updates to a session until either side closes
+///
+/// While the session's queue is full, updates wait in the agent's channel,
+/// and the oldest are dropped once it overflows.
+async fn forward(mut updates: broadcast::Receiver<ServerMessage>, outgoing: mpsc::Sender<ServerMessage>) {
+    loop {
+        match updates.recv().await {
+            Ok(update) => {
+                if outgoing.send(update).await.is_err() {
+                    break;
+                }
+            }
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                log::warn!("WebSocket subscriber fell behind and missed {} updates", skipped);
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+/// Convert a WebSocket error
+fn ws_error(error: tokio_tungstenite::tungstenite::Error) -> OxydeError {
+    OxydeError::BindingError(format!("WebSocket error: {}", error))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::config::{AgentConfig, AgentPersonality, InferenceConfig};
+    use crate::oxyde_game::bindings::WasmBinding;
+
+    type Client = tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<TcpStream>>;
+
+    async fn send(client: &mut Client, message: serde_json::Value) {
+        client.send(Message::Text(message.to_string())).await.unwrap();
+    }
+
+    async fn receive(client: &mut Client) -> ServerMessage {
+        loop {
+            let frame = tokio::time::timeout(Duration::from_secs(5), client.next())
+                .await
+                .expect("server message")
+                .unwrap()
+                .unwrap();
+            if let Message::Text(text) = frame {
+                return serde_json::from_str(&text).unwrap();
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_subscribers_receive_other_players_conversations() {
+        let config = AgentConfig {
+            agent: AgentPersonality {
+                name: "Innkeeper".to_string(),
+                role: "Innkeeper".to_string(),
+                backstory: vec![],
+                knowledge: vec![],
+            },
+            inference: InferenceConfig {
+                use_local: true,
+                local_model_path: Some("models/test.bin".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let binding = WasmBinding::new();
+        let agent = Arc::new(Agent::new(config));
+        binding.register_agent(agent.id(), agent.clone());
+        let agent_id = agent.id().to_string();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let server = Arc::new(SessionServer::new(binding.registry()));
+        tokio::spawn(server.clone().serve(listener));
+
+        let (mut watcher, _) = tokio_tungstenite::connect_async(&url).await.unwrap();
+        let (mut speaker, _) = tokio_tungstenite::connect_async(&url).await.unwrap();
+
+        send(&mut watcher, serde_json::json!({ "type": "subscribe", "agent_id": agent_id })).await;
+        assert_eq!(receive(&mut watcher).await, ServerMessage::Subscribed { agent_id: agent_id.clone() });
+        assert_eq!(server.subscriber_count(&agent_id), 1);
+
+        send(
+            &mut speaker,
+            serde_json::json!({ "type": "input", "agent_id": agent_id, "text": "Any rooms free?", "request_id": 7 }),
+        )
+        .await;
+        let expected = "This is a simulated response to: Any rooms free?".to_string();
+        assert_eq!(
+            receive(&mut speaker).await,
+            ServerMessage::Reply { agent_id: agent_id.clone(), text: expected.clone(), request_id: Some(7) }
+        );
+        assert_eq!(receive(&mut watcher).await, ServerMessage::Response { agent_id: agent_id.clone(), text: expected });
+        assert!(matches!(receive(&mut watcher).await, ServerMessage::Emotions { .. }));
+
+        send(&mut speaker, serde_json::json!({ "type": "subscribe", "agent_id": "missing" })).await;
+        assert!(matches!(receive(&mut speaker).await, ServerMessage::Error { request_id: None, .. }));
+    }
+
+    #[tokio::test]
+    async fn test_inputs_in_flight_are_capped_per_connection() {
+        let yaml = r#"
+agent:
+  name: Innkeeper
+  role: Innkeeper
+  backstory: []
+  knowledge: []
+memory: {}
+inference:
+  provider: mock
+  mock:
+    latency_ms: 200
+"#;
+        let config: AgentConfig = serde_yaml::from_str(yaml).unwrap();
+        let binding = WasmBinding::new();
+        let agent = Arc::new(Agent::new(config));
+        binding.register_agent(agent.id(), agent.clone());
+        let agent_id = agent.id().to_string();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let server = Arc::new(SessionServer::new(binding.registry()).with_max_inputs_in_flight(1));
+        tokio::spawn(server.serve(listener));
+
+        let (mut client, _) = tokio_tungstenite::connect_async(&url).await.unwrap();
+        for request_id in [1, 2] {
+            send(
+                &mut client,
+                serde_json::json!({ "type": "input", "agent_id": agent_id, "text": "A room?", "request_id": request_id }),
+            )
+            .await;
+        }
+        assert!(matches!(receive(&mut client).await, ServerMessage::Error { request_id: Some(2), .. }));
+        assert!(matches!(receive(&mut client).await, ServerMessage::Reply { request_id: Some(1), .. }));
+
+        // The reply frees the slot
+        send(
+            &mut client,
+            serde_json::json!({ "type": "input", "agent_id": agent_id, "text": "A room?", "request_id": 3 }),
+        )
+        .await;
+        assert!(matches!(receive(&mut client).await, ServerMessage::Reply { request_id: Some(3), .. }));
+    }
+}
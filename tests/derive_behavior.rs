@@ -0,0 +1,83 @@
+use std::time::Duration;
+
+use oxyde::agent::AgentContext;
+use oxyde::oxyde_game::behavior::{Behavior, BehaviorResult};
+use oxyde::oxyde_game::emotion::EmotionalState;
+use oxyde::oxyde_game::intent::{Intent, IntentType};
+
+#[derive(Debug, Behavior)]
+#[behavior(
+    intents(Threat, Hostile),
+    priority = 80,
+    trigger = "fear > 0.5 AND trust < 0",
+    influence(fear = 0.1, joy = -0.05),
+    priority_modifier(fear = 20.0, sadness = -10.0),
+    timeout_ms = 250
+)]
+struct Flee;
+
+impl Flee {
+    async fn respond(&self, _intent: &Intent, _context: &AgentContext) -> oxyde::Result<BehaviorResult> {
+        Ok(BehaviorResult::Action("flee".to_string()))
+    }
+}
+
+#[derive(Debug, Behavior)]
+#[behavior(keywords("Sword", "armor"), activities("shopkeeping"), hours(8.0, 18.0), execute = "quote")]
+struct Smith {
+    price: u32,
+}
+
+impl Smith {
+    async fn quote(&self, intent: &Intent, _context: &AgentContext) -> oxyde::Result<BehaviorResult> {
+        Ok(BehaviorResult::Response(format!("{} costs {} gold.", intent.raw_input, self.price)))
+    }
+}
+
+fn intent(intent_type: IntentType, raw_input: &str) -> Intent {
+    Intent::new(intent_type, 1.0, raw_input, Vec::new())
+}
+
+#[tokio::test]
+async fn test_derive_generates_behavior_plumbing() {
+    let flee = Flee;
+    assert!(flee.matches_intent(&intent(IntentType::Threat, "")).await);
+    assert!(!flee.matches_intent(&intent(IntentType::Greeting, "")).await);
+    assert_eq!(flee.priority(), 80);
+    assert_eq!(flee.timeout(), Some(Duration::from_millis(250)));
+    assert_eq!(flee.available_hours(), None);
+
+    let influences: Vec<(String, f32)> = flee
+        .emotion_influences()
+        .into_iter()
+        .map(|influence| (influence.emotion, influence.delta))
+        .collect();
+    assert_eq!(influences, vec![("fear".to_string(), 0.1), ("joy".to_string(), -0.05)]);
+
+    let mut emotions = EmotionalState::new();
+    emotions.update_emotion("fear", 0.8);
+    emotions.update_emotion("sadness", 0.3);
+    assert_eq!(flee.emotional_priority_modifier(&emotions), 13);
+    let trigger = flee.emotion_trigger().unwrap();
+    assert!(!trigger.matches(&emotions));
+    emotions.update_emotion("trust", -0.4);
+    assert!(trigger.matches(&emotions));
+
+    let result = flee.execute(&intent(IntentType::Threat, ""), &AgentContext::new()).await.unwrap();
+    assert!(matches!(result, BehaviorResult::Action(action) if action == "flee"));
+}
+
+#[tokio::test]
+async fn test_derive_defaults_and_custom_execute() {
+    let smith = Smith { price: 40 };
+    assert!(smith.matches_intent(&intent(IntentType::Question, "How much is that SWORD?")).await);
+    assert!(!smith.matches_intent(&intent(IntentType::Question, "Any bread?")).await);
+    assert_eq!(smith.priority(), 50);
+    assert!(smith.emotion_influences().is_empty());
+    assert_eq!(smith.scheduled_activities(), vec!["shopkeeping".to_string()]);
+    assert_eq!(smith.available_hours(), Some((8.0, 18.0)));
+    assert_eq!(smith.persuasion_difficulty(), None);
+
+    let result = smith.execute(&intent(IntentType::Question, "The sword"), &AgentContext::new()).await.unwrap();
+    assert!(matches!(result, BehaviorResult::Response(text) if text == "The sword costs 40 gold."));
+}
@@ -0,0 +1,395 @@
+//! Headless load testing
+//!
+//! `oxyde-cli loadtest --agents 200 --rps 50 --duration 60s` spawns agents
+//! from one template, all answering with the mock provider, and sends them
+//! player inputs at a fixed rate, round-robin. The report gives:
+//!
+//! - throughput and response latency percentiles
+//! - lock contention: inputs that had to wait for an earlier input to the
+//!   same agent, how long they waited for the agent's turn lock, the deepest
+//!   per-agent queue, and inputs rejected as busy. Inputs to one agent are
+//!   serialized by that lock, so waits on the agent's other locks only come
+//!   from calls made outside `process_input`, which the load test doesn't make
+//! - resident memory at the start, peak and end of the run (Linux only)
+//!
+//! Mock responses take no time unless the configuration sets
+//! `inference.mock.latency_ms`, so latencies measure the SDK's own overhead.
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use oxyde::config::AgentConfig;
+use oxyde::template::AgentTemplate;
+use oxyde::{Agent, OxydeError, Result};
+use serde::Serialize;
+use tokio::task::JoinSet;
+
+/// Agent used when no configuration is given
+const DEFAULT_AGENT: &str = "\
+agent:
+  name: Villager
+  role: villager
+  backstory: [\"Has lived in the village all their life.\"]
+  knowledge: []
+memory: {}
+inference:
+  provider: mock
+";
+
+/// Inputs sent to the agents, in turn
+const INPUTS: &[&str] = &[
+    "Hello there!",
+    "What's the news in town?",
+    "Can you help me find the blacksmith?",
+    "I'm looking for work.",
+    "Goodbye for now.",
+];
+
+/// How often resident memory is sampled
+const MEMORY_SAMPLE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Parameters of a load test
+#[derive(Debug, Clone)]
+pub struct LoadTestOptions {
+    /// Agents spawned
+    pub agents: usize,
+    /// Inputs sent per second, across all agents
+    pub rps: f64,
+    /// How long inputs are sent for
+    pub duration: Duration,
+    /// Agent configuration; a villager answering with the mock provider
+    /// when `None`
+    pub config: Option<AgentConfig>,
+}
+
+/// Response latency percentiles in milliseconds
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct LatencySummary {
+    /// Median
+    pub p50: f64,
+    /// 95th percentile
+    pub p95: f64,
+    /// 99th percentile
+    pub p99: f64,
+    /// Slowest response
+    pub max: f64,
+}
+
+impl LatencySummary {
+    fn from_samples(mut samples: Vec<Duration>) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        samples.sort();
+        let at = |quantile: f64| {
+            let index = ((samples.len() as f64 * quantile).ceil() as usize).clamp(1, samples.len()) - 1;
+            samples[index].as_secs_f64() * 1000.0
+        };
+        Self {
+            p50: at(0.50),
+            p95: at(0.95),
+            p99: at(0.99),
+            max: at(1.0),
+        }
+    }
+}
+
+/// Results of a load test
+#[derive(Debug, Clone, Serialize)]
+pub struct LoadTestReport {
+    /// Agents spawned
+    pub agents: usize,
+    /// Inputs per second requested
+    pub target_rps: f64,
+    /// Time from the first input to the last response, in seconds
+    pub elapsed_s: f64,
+    /// Time taken to spawn the agents, in milliseconds
+    pub spawn_ms: f64,
+    /// Inputs sent
+    pub requests: u64,
+    /// Inputs answered
+    pub succeeded: u64,
+    /// Inputs that failed
+    pub failed: u64,
+    /// Inputs answered per second
+    pub throughput_rps: f64,
+    /// Latency of answered inputs
+    pub latency_ms: LatencySummary,
+    /// Inputs that waited for an earlier input to the same agent
+    pub queued: u64,
+    /// Inputs answered with the response to a later input
+    pub coalesced: u64,
+    /// Inputs rejected because the agent was busy
+    pub rejected: u64,
+    /// Most inputs waiting on a single agent at once
+    pub peak_queue_depth: usize,
+    /// Total time inputs waited for an agent's turn lock, in milliseconds
+    pub lock_wait_ms: u64,
+    /// Longest time a single input waited for an agent's turn lock, in
+    /// milliseconds
+    pub max_lock_wait_ms: u64,
+    /// Resident memory before spawning agents, in KiB
+    pub rss_start_kb: Option<u64>,
+    /// Highest resident memory sampled, in KiB
+    pub rss_peak_kb: Option<u64>,
+    /// Resident memory after the run, in KiB
+    pub rss_end_kb: Option<u64>,
+}
+
+impl LoadTestReport {
+    /// Inputs that waited for another input, as a share of inputs sent
+    pub fn contention_rate(&self) -> f64 {
+        if self.requests == 0 {
+            0.0
+        } else {
+            self.queued as f64 / self.requests as f64
+        }
+    }
+
+    /// Mean time a queued input waited for its agent's turn, in milliseconds
+    pub fn mean_lock_wait_ms(&self) -> f64 {
+        if self.queued == 0 {
+            0.0
+        } else {
+            self.lock_wait_ms as f64 / self.queued as f64
+        }
+    }
+}
+
+impl fmt::Display for LoadTestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kb = |value: Option<u64>| value.map_or("n/a".to_string(), |kb| format!("{:.1} MiB", kb as f64 / 1024.0));
+        writeln!(f, "Load test: {} agents at {} requests/s", self.agents, self.target_rps)?;
+        writeln!(f, "  Spawned agents in {:.0} ms", self.spawn_ms)?;
+        writeln!(
+            f,
+            "  Requests: {} sent, {} succeeded, {} failed in {:.1} s",
+            self.requests, self.succeeded, self.failed, self.elapsed_s
+        )?;
+        writeln!(f, "  Throughput: {:.1} responses/s", self.throughput_rps)?;
+        writeln!(
+            f,
+            "  Latency: p50 {:.2} ms, p95 {:.2} ms, p99 {:.2} ms, max {:.2} ms",
+            self.latency_ms.p50, self.latency_ms.p95, self.latency_ms.p99, self.latency_ms.max
+        )?;
+        writeln!(
+            f,
+            "  Contention: {} queued ({:.1}%), {} coalesced, {} rejected, peak queue depth {}",
+            self.queued,
+            self.contention_rate() * 100.0,
+            self.coalesced,
+            self.rejected,
+            self.peak_queue_depth
+        )?;
+        writeln!(
+            f,
+            "  Lock waits: {} ms in total, {:.2} ms mean, {} ms max",
+            self.lock_wait_ms,
+            self.mean_lock_wait_ms(),
+            self.max_lock_wait_ms
+        )?;
+        writeln!(
+            f,
+            "  Memory: {} at start, {} peak, {} at end",
+            kb(self.rss_start_kb),
+            kb(self.rss_peak_kb),
+            kb(self.rss_end_kb)
+        )
+    }
+}
+
+/// Parse a duration such as `60s`, `2m`, `500ms` or `90` (seconds)
+pub fn parse_duration(text: &str) -> Result<Duration> {
+    let text = text.trim();
+    let split = text.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let value: f64 = number
+        .parse()
+        .map_err(|_| OxydeError::CliError(format!("Invalid duration '{}'", text)))?;
+    let seconds = match unit {
+        "" | "s" => value,
+        "ms" => value / 1000.0,
+        "m" => value * 60.0,
+        "h" => value * 3600.0,
+        _ => return Err(OxydeError::CliError(format!("Unknown duration unit '{}'; use ms, s, m or h", unit))),
+    };
+    if seconds <= 0.0 {
+        return Err(OxydeError::CliError(format!("Duration '{}' must be positive", text)));
+    }
+    Ok(Duration::from_secs_f64(seconds))
+}
+
+/// Spawn agents and send them inputs at the requested rate
+pub async fn run_load_test(options: LoadTestOptions) -> Result<LoadTestReport> {
+    if options.agents == 0 {
+        return Err(OxydeError::CliError("Load tests need at least one agent".to_string()));
+    }
+    if options.rps.is_nan() || options.rps <= 0.0 {
+        return Err(OxydeError::CliError("Load test rate must be positive".to_string()));
+    }
+
+    let rss_start_kb = resident_memory_kb();
+    let peak = Arc::new(Mutex::new(rss_start_kb));
+    let sampler = tokio::spawn(sample_memory(peak.clone()));
+
+    let config = match options.config {
+        Some(config) => config,
+        None => serde_yaml::from_str(DEFAULT_AGENT).map_err(|e| OxydeError::CliError(e.to_string()))?,
+    };
+    let spawn_started = Instant::now();
+    let template = AgentTemplate::new(config.offline())?;
+    let mut agents = Vec::with_capacity(options.agents);
+    for _ in 0..options.agents {
+        agents.push(Arc::new(template.instantiate().await));
+    }
+    let spawn_ms = spawn_started.elapsed().as_secs_f64() * 1000.0;
+
+    let latencies = Arc::new(Mutex::new(Vec::new()));
+    let mut tasks = JoinSet::new();
+    let mut ticker = tokio::time::interval(Duration::from_secs_f64(1.0 / options.rps));
+    let started = Instant::now();
+    let mut requests = 0u64;
+    while started.elapsed() < options.duration {
+        ticker.tick().await;
+        let agent = agents[requests as usize % agents.len()].clone();
+        let input = INPUTS[(requests as usize / agents.len()) % INPUTS.len()];
+        let latencies = latencies.clone();
+        tasks.spawn(async move {
+            let sent = Instant::now();
+            let result = agent.process_input(input).await;
+            if result.is_ok() {
+                latencies.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).push(sent.elapsed());
+            }
+            result.is_ok()
+        });
+        requests += 1;
+    }
+
+    let mut succeeded = 0;
+    let mut failed = 0;
+    while let Some(outcome) = tasks.join_next().await {
+        match outcome {
+            Ok(true) => succeeded += 1,
+            _ => failed += 1,
+        }
+    }
+    let elapsed = started.elapsed();
+    sampler.abort();
+
+    let rss_end_kb = resident_memory_kb();
+    let rss_peak_kb = (*peak.lock().unwrap_or_else(|poisoned| poisoned.into_inner())).max(rss_end_kb);
+    let latencies = std::mem::take(&mut *latencies.lock().unwrap_or_else(|poisoned| poisoned.into_inner()));
+    let mut report = LoadTestReport {
+        agents: agents.len(),
+        target_rps: options.rps,
+        elapsed_s: elapsed.as_secs_f64(),
+        spawn_ms,
+        requests,
+        succeeded,
+        failed,
+        throughput_rps: succeeded as f64 / elapsed.as_secs_f64().max(f64::EPSILON),
+        latency_ms: LatencySummary::from_samples(latencies),
+        queued: 0,
+        coalesced: 0,
+        rejected: 0,
+        peak_queue_depth: 0,
+        lock_wait_ms: 0,
+        max_lock_wait_ms: 0,
+        rss_start_kb,
+        rss_peak_kb,
+        rss_end_kb,
+    };
+    for agent in &agents {
+        add_queue_stats(&mut report, agent);
+    }
+    Ok(report)
+}
+
+fn add_queue_stats(report: &mut LoadTestReport, agent: &Agent) {
+    let stats = agent.request_queue_stats();
+    report.queued += stats.queued;
+    report.coalesced += stats.coalesced;
+    report.rejected += stats.rejected;
+    report.peak_queue_depth = report.peak_queue_depth.max(stats.peak_depth);
+    report.lock_wait_ms += stats.wait_ms;
+    report.max_lock_wait_ms = report.max_lock_wait_ms.max(stats.max_wait_ms);
+}
+
+/// Record the highest resident memory until aborted
+async fn sample_memory(peak: Arc<Mutex<Option<u64>>>) {
+    let mut ticker = tokio::time::interval(MEMORY_SAMPLE_INTERVAL);
+    loop {
+        ticker.tick().await;
+        if let Some(current) = resident_memory_kb() {
+            let mut peak = peak.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            *peak = Some(peak.map_or(current, |peak| peak.max(current)));
+        }
+    }
+}
+
+/// Resident memory of this process in KiB, where the platform reports it
+fn resident_memory_kb() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration_units() {
+        assert_eq!(parse_duration("60s").unwrap(), Duration::from_secs(60));
+        assert_eq!(parse_duration("2m").unwrap(), Duration::from_secs(120));
+        assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_duration(" 90 ").unwrap(), Duration::from_secs(90));
+        assert!(parse_duration("0s").is_err());
+        assert!(parse_duration("5 days").is_err());
+        assert!(parse_duration("soon").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_load_test_reports_contention_on_slow_agents() {
+        // One agent answering in 20 ms, sent an input every 5 ms, has to queue them
+        let mut config: AgentConfig = serde_yaml::from_str(DEFAULT_AGENT).unwrap();
+        config.inference.mock.latency_ms = 20;
+        let report = run_load_test(LoadTestOptions {
+            agents: 1,
+            rps: 200.0,
+            duration: Duration::from_millis(100),
+            config: Some(config),
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(report.agents, 1);
+        assert!(report.requests > 1);
+        assert_eq!(report.succeeded + report.failed, report.requests);
+        assert_eq!(report.failed, 0);
+        assert!(report.queued > 0);
+        assert!(report.peak_queue_depth > 0);
+        assert!(report.lock_wait_ms > 0);
+        assert!(report.max_lock_wait_ms >= 20);
+        assert!(report.latency_ms.max >= 20.0);
+
+        let rendered = report.to_string();
+        assert!(rendered.contains("Contention:"));
+        assert!(rendered.contains("Lock waits:"));
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["requests"], report.requests);
+    }
+
+    #[tokio::test]
+    async fn test_load_test_rejects_empty_runs() {
+        let options = LoadTestOptions {
+            agents: 0,
+            rps: 10.0,
+            duration: Duration::from_millis(10),
+            config: None,
+        };
+        assert!(run_load_test(options.clone()).await.is_err());
+        assert!(run_load_test(LoadTestOptions { agents: 1, rps: 0.0, ..options }).await.is_err());
+    }
+}
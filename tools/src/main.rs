@@ -7,16 +7,28 @@ use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process;
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use clap::{Parser, Subcommand};
 use oxyde::agent::{Agent, AgentState};
-use oxyde::config::{AgentConfig, BehaviorConfig, InferenceConfig, MemoryConfig};
+use oxyde::audio::{TTSConfig, TTSProvider, TTSService};
+use oxyde::config::{AgentConfig, BehaviorConfig, CONFIG_VERSION};
+use oxyde::dialogue_export::{DialogueFormat, DialogueGraph};
+use oxyde::interaction_log::read_log;
+use oxyde::knowledge::{ingest_dir, IngestOptions};
+use oxyde::memory_stats::MemorySession;
 use oxyde::oxyde_game::behavior::factory;
 use oxyde::oxyde_game::intent::Intent;
+use oxyde::package::{AgentPackage, PACKAGE_EXTENSION};
 use oxyde::{OxydeError, Result};
 use tokio::time::sleep;
 
+mod loadtest;
+mod unreal;
+
+use loadtest::{parse_duration, run_load_test, LoadTestOptions};
+use unreal::deploy_unreal_agents;
+
 /// CLI arguments parser
 #[derive(Parser)]
 #[clap(author, version, about = "CLI tool for Oxyde SDK")]
@@ -80,8 +92,25 @@ enum Commands {
         /// Enable memory persistence
         #[clap(long)]
         persistent_memory: bool,
+
+        /// Run without API keys or network access, using the mock inference
+        /// and TTS providers and in-memory storage
+        #[clap(long, conflicts_with_all = ["local_only", "persistent_memory"])]
+        offline: bool,
     },
     
+    /// Check an agent configuration, reporting changes needed for configs
+    /// written by older SDK versions
+    Validate {
+        /// Path to agent configuration file
+        #[clap(short, long)]
+        config: String,
+
+        /// Rewrite the file in the current config version
+        #[clap(short, long)]
+        upgrade: bool,
+    },
+
     /// Convert an agent between formats
     Convert {
         /// Input configuration file
@@ -96,6 +125,154 @@ enum Commands {
         #[clap(short, long)]
         output: String,
     },
+
+    /// Bundle an agent directory into a single .oxyde package
+    Pack {
+        /// Directory with the agent configuration and optional prompts,
+        /// knowledge, and voices subdirectories
+        #[clap(short, long)]
+        source: String,
+
+        /// Output package path; defaults to <agent name>.oxyde
+        #[clap(short, long)]
+        output: Option<String>,
+    },
+
+    /// Extract a .oxyde package into a directory
+    Unpack {
+        /// Package to extract
+        #[clap(short, long)]
+        package: String,
+
+        /// Output directory
+        #[clap(short, long, default_value = "unpacked")]
+        output: String,
+    },
+
+    /// List the voices of TTS providers and synthesize sample clips
+    Voices {
+        /// Providers to list (elevenlabs, mock); defaults to the providers
+        /// configured in --config, or elevenlabs
+        #[clap(short, long)]
+        provider: Vec<String>,
+
+        /// Agent configuration whose TTS settings, such as api_key_ref and
+        /// output_format, are used
+        #[clap(short, long)]
+        config: Option<String>,
+
+        /// Only list these voice IDs
+        #[clap(long)]
+        voice: Vec<String>,
+
+        /// Text to synthesize a sample clip of for each voice
+        #[clap(short, long)]
+        text: Option<String>,
+
+        /// Directory sample clips are written to
+        #[clap(short, long, default_value = "voice_samples")]
+        out: String,
+    },
+
+    /// Manage agent knowledge
+    Kb {
+        /// Knowledge subcommand to execute
+        #[clap(subcommand)]
+        command: KbCommands,
+    },
+
+    /// Report memory distributions from a saved memory session
+    MemoryReport {
+        /// Session file saved with Agent::save_memory_session
+        #[clap(short, long)]
+        session: String,
+
+        /// Report format (text, csv)
+        #[clap(short, long, default_value = "text")]
+        format: String,
+
+        /// Output file path; prints to stdout when omitted
+        #[clap(short, long)]
+        output: Option<String>,
+    },
+
+    /// Export interaction logs as a dialogue tree grouped by intent and
+    /// emotion, for review in Twine or graph tools
+    ExportDialogue {
+        /// Interaction log files (JSON lines)
+        #[clap(short, long, required = true)]
+        log: Vec<String>,
+
+        /// Output format (twee, json)
+        #[clap(short, long, default_value = "twee")]
+        format: String,
+
+        /// Output file path; prints to stdout when omitted
+        #[clap(short, long)]
+        output: Option<String>,
+    },
+
+    /// Drive many concurrent agents with the mock provider and report
+    /// throughput, latency, lock contention and memory usage
+    Loadtest {
+        /// Agents to spawn
+        #[clap(short, long, default_value_t = 200)]
+        agents: usize,
+
+        /// Inputs sent per second, across all agents
+        #[clap(short, long, default_value_t = 50.0)]
+        rps: f64,
+
+        /// How long inputs are sent for, such as 60s, 5m or 500ms
+        #[clap(short, long, default_value = "60s")]
+        duration: String,
+
+        /// Agent configuration to spawn agents from; inference always uses
+        /// the mock provider
+        #[clap(short, long)]
+        config: Option<String>,
+
+        /// Report format (text, json)
+        #[clap(short, long, default_value = "text")]
+        format: String,
+
+        /// Output file path; prints to stdout when omitted
+        #[clap(short, long)]
+        output: Option<String>,
+    },
+}
+
+/// Knowledge subcommands
+#[derive(Subcommand)]
+enum KbCommands {
+    /// Split a directory of Markdown and text lore files into knowledge
+    /// entries of an agent configuration
+    Ingest {
+        /// Directory of lore files
+        #[clap(short, long)]
+        dir: String,
+
+        /// Agent configuration the entries are added to
+        #[clap(short, long, required_unless_present = "report")]
+        config: Option<String>,
+
+        /// Only check the files, printing chunk and token counts, skipped
+        /// files and duplicates; fails if any file was skipped
+        #[clap(long)]
+        report: bool,
+
+        /// Report format (text, json)
+        #[clap(short, long, default_value = "text")]
+        format: String,
+
+        /// Largest file read, in bytes
+        #[clap(long)]
+        max_file_bytes: Option<u64>,
+
+        /// Estimated tokens per entry; longer paragraphs are split
+        #[clap(long)]
+        max_chunk_tokens: Option<usize>,
+    },
 }
 
 /// Run the CLI tool
@@ -127,12 +304,43 @@ async fn main() -> Result<()> {
         Commands::Deploy { config, scene, engine, output } => {
             deploy_agents(&config, &scene, &engine, &output).await?;
         }
-        Commands::Test { config, local_only, persistent_memory } => {
-            test_agent(&config, local_only, persistent_memory).await?;
+        Commands::Test { config, local_only, persistent_memory, offline } => {
+            test_agent(&config, local_only, persistent_memory, offline).await?;
+        }
+        Commands::Validate { config, upgrade } => {
+            validate_agent_config(&config, upgrade)?;
         }
         Commands::Convert { input, format, output } => {
             convert_agent_config(&input, &format, &output).await?;
         }
+        Commands::Pack { source, output } => {
+            pack_agent(&source, output.as_deref())?;
+        }
+        Commands::Unpack { package, output } => {
+            unpack_agent(&package, &output)?;
+        }
+        Commands::Voices { provider, config, voice, text, out } => {
+            preview_voices(&provider, config.as_deref(), &voice, text.as_deref(), &out).await?;
+        }
+        Commands::Kb { command: KbCommands::Ingest { dir, config, report, format, max_file_bytes, max_chunk_tokens } } => {
+            let defaults = IngestOptions::default();
+            let options = IngestOptions {
+                max_file_bytes: max_file_bytes.unwrap_or(defaults.max_file_bytes),
+                max_chunk_tokens: max_chunk_tokens.unwrap_or(defaults.max_chunk_tokens),
+                ..defaults
+            };
+            let config = if report { None } else { config.as_deref() };
+            ingest_knowledge(&dir, config, &format, &options)?;
+        }
+        Commands::MemoryReport { session, format, output } => {
+            memory_report(&session, &format, output.as_deref())?;
+        }
+        Commands::ExportDialogue { log, format, output } => {
+            export_dialogue(&log, &format, output.as_deref())?;
+        }
+        Commands::Loadtest { agents, rps, duration, config, format, output } => {
+            load_test(agents, rps, &duration, config.as_deref(), &format, output.as_deref()).await?;
+        }
     }
     
     Ok(())
@@ -158,14 +366,12 @@ async fn create_agent_config(name: &str, role: &str, output: &str) -> Result<()>
                 "Knows common greetings and customs".to_string(),
             ],
         },
-        memory: MemoryConfig::default(),
-        inference: InferenceConfig::default(),
         behavior: create_default_behaviors(),
-        tts: None,
         moderation: oxyde::config::ModerationConfig {
             enabled: false,
             ..Default::default()
-        }
+        },
+        ..Default::default()
     };
     
     // Determine output format
@@ -177,7 +383,7 @@ async fn create_agent_config(name: &str, role: &str, output: &str) -> Result<()>
         let json = serde_json::to_string_pretty(&agent_config)?;
         fs::write(output, json)?;
     } else {
-        let yaml = serde_yaml::to_string(&agent_config)?;
+        let yaml = serde_yaml::to_string(&agent_config).map_err(|e| OxydeError::CliError(e.to_string()))?;
         fs::write(output, yaml)?;
     }
     
@@ -191,27 +397,42 @@ fn create_default_behaviors() -> HashMap<String, BehaviorConfig> {
     
     // Greeting behavior
     let greeting = BehaviorConfig {
+        behavior_type: None,
         trigger: "proximity".to_string(),
         cooldown: 60,
         priority: 10,
+        condition: None,
+        emotion_trigger: None,
+        timeout_ms: None,
+        timeout_response: None,
         parameters: HashMap::new(),
     };
     behaviors.insert("greeting".to_string(), greeting);
     
     // Dialogue behavior
     let dialogue = BehaviorConfig {
+        behavior_type: None,
         trigger: "chat".to_string(),
         cooldown: 0,
         priority: 20,
+        condition: None,
+        emotion_trigger: None,
+        timeout_ms: None,
+        timeout_response: None,
         parameters: HashMap::new(),
     };
     behaviors.insert("dialogue".to_string(), dialogue);
     
     // Movement behavior
     let movement = BehaviorConfig {
+        behavior_type: Some("stationary".to_string()),
         trigger: "movement".to_string(),
         cooldown: 0,
         priority: 5,
+        condition: None,
+        emotion_trigger: None,
+        timeout_ms: None,
+        timeout_response: None,
         parameters: HashMap::new(),
     };
     behaviors.insert("movement".to_string(), movement);
@@ -278,6 +499,10 @@ fn deploy_unity_agents(
     let manager_script = generate_unity_manager_script(agents);
     fs::write(scripts_dir.join("OxydeAgentManager.cs"), manager_script)?;
     
+    // Generate async wrapper and main-thread dispatcher
+    let async_script = generate_unity_async_script();
+    fs::write(scripts_dir.join("OxydeAsync.cs"), async_script)?;
+    
     // Generate agent controller scripts
     for (i, agent) in agents.iter().enumerate() {
         // Write agent configuration to Unity Resources folder
@@ -304,6 +529,7 @@ fn generate_unity_manager_script(agents: &[AgentConfig]) -> String {
     format!(
         r#"using UnityEngine;
 using System.Collections.Generic;
+using System.Threading.Tasks;
 
 namespace Oxyde.Unity
 {{
@@ -367,6 +593,35 @@ namespace Oxyde.Unity
         
         // Process input for the nearest agent
         public string ProcessInputForNearestAgent(Transform player, string input, float maxDistance = 5f)
+        {{
+            OxydeAgent nearestAgent = FindNearestAgent(player, maxDistance);
+            if (nearestAgent != null)
+            {{
+                return nearestAgent.ProcessInput(input);
+            }}
+            
+            return "No one is close enough to hear you.";
+        }}
+        
+        // Process input for the nearest agent without blocking the main thread
+        public Task<string> ProcessInputForNearestAgentAsync(Transform player, string input, float maxDistance = 5f)
+        {{
+            OxydeAgent nearestAgent = FindNearestAgent(player, maxDistance);
+            if (nearestAgent is OxydeAsyncAgent asyncAgent)
+            {{
+                return asyncAgent.ProcessInputAsync(input);
+            }}
+            
+            if (nearestAgent != null)
+            {{
+                return Task.FromResult(nearestAgent.ProcessInput(input));
+            }}
+            
+            return Task.FromResult("No one is close enough to hear you.");
+        }}
+        
+        // Find the closest registered agent within range
+        private OxydeAgent FindNearestAgent(Transform player, float maxDistance)
         {{
             OxydeAgent nearestAgent = null;
             float closestDistance = maxDistance;
@@ -381,12 +636,7 @@ namespace Oxyde.Unity
                 }}
             }}
             
-            if (nearestAgent != null)
-            {{
-                return nearestAgent.ProcessInput(input);
-            }}
-            
-            return "No one is close enough to hear you.";
+            return nearestAgent;
         }}
     }}
 }}
@@ -399,13 +649,14 @@ fn generate_unity_agent_script(agent: &AgentConfig, config_filename: &str) -> St
     format!(
         r#"using UnityEngine;
 using System.Collections.Generic;
+using System.Threading.Tasks;
 
 namespace Oxyde.Unity
 {{
     /// <summary>
     /// Controller for the {} agent
     /// </summary>
-    public class {}Controller : OxydeAgent
+    public class {}Controller : OxydeAsyncAgent
     {{
         // Agent configuration
         [SerializeField] private string configResourcePath = "AgentConfigs/{}";
@@ -491,16 +742,19 @@ namespace Oxyde.Unity
             }}
         }}
         
-        private void TryGreetPlayer()
+        private async void TryGreetPlayer()
         {{
-            // Process a proximity "greeting" intent
-            string response = ProcessIntent("proximity");
-            
-            if (!string.IsNullOrEmpty(response))
+            // Skip if a response is already on its way
+            if (IsProcessing)
             {{
-                ShowDialogue(response);
-                lastGreetingTime = Time.time;
+                return;
             }}
+            
+            // Mark the greeting immediately so Update doesn't queue duplicates
+            lastGreetingTime = Time.time;
+            
+            // Process a proximity "greeting" intent; the response is shown by OnResponseReceived
+            await ProcessIntentAsync("proximity");
         }}
         
         // Show dialogue bubble with text
@@ -537,8 +791,17 @@ namespace Oxyde.Unity
             return response;
         }}
         
+        // Show responses from async requests in the dialogue bubble
+        protected override void OnResponseReceived(string response)
+        {{
+            if (!string.IsNullOrEmpty(response))
+            {{
+                ShowDialogue(response);
+            }}
+        }}
+        
         // Process a specific intent type
-        private string ProcessIntent(string intentType)
+        private Task<string> ProcessIntentAsync(string intentType)
         {{
             // Create a context JSON with the intent type
             Dictionary<string, object> intentContext = new Dictionary<string, object>()
@@ -552,8 +815,8 @@ namespace Oxyde.Unity
             // Update context with intent
             UpdateContext(intentContext);
             
-            // Process the input
-            return ProcessInput(inputText);
+            // Process the input off the main thread
+            return ProcessInputAsync(inputText);
         }}
         
         // Called when player enters detection range
@@ -584,6 +847,232 @@ namespace Oxyde.Unity
     )
 }
 
+/// Generate the Unity async wrapper script
+///
+/// Native calls run on the thread pool so `ProcessInput` never blocks the
+/// main thread; results and UnityEvent callbacks are marshaled back through
+/// `OxydeMainThreadDispatcher`.
+fn generate_unity_async_script() -> String {
+    r#"using System;
+using System.Collections.Concurrent;
+using System.Collections.Generic;
+using System.Threading;
+using System.Threading.Tasks;
+using UnityEngine;
+using UnityEngine.Events;
+
+namespace Oxyde.Unity
+{
+    /// <summary>
+    /// UnityEvent raised with an agent response
+    /// </summary>
+    [Serializable]
+    public class OxydeResponseEvent : UnityEvent<string> { }
+
+    /// <summary>
+    /// UnityEvent raised with an emotion vector
+    /// [joy, trust, fear, surprise, sadness, disgust, anger, anticipation]
+    /// </summary>
+    [Serializable]
+    public class OxydeEmotionEvent : UnityEvent<float[]> { }
+
+    /// <summary>
+    /// Runs queued actions on the Unity main thread
+    /// </summary>
+    public class OxydeMainThreadDispatcher : MonoBehaviour
+    {
+        private static OxydeMainThreadDispatcher instance;
+        private static readonly ConcurrentQueue<Action> pending = new ConcurrentQueue<Action>();
+        private static int mainThreadId = -1;
+
+        // Create the dispatcher before any scene loads so it is always available
+        [RuntimeInitializeOnLoadMethod(RuntimeInitializeLoadType.BeforeSceneLoad)]
+        private static void Initialize()
+        {
+            if (instance != null)
+            {
+                return;
+            }
+            
+            mainThreadId = Thread.CurrentThread.ManagedThreadId;
+            GameObject dispatcherObject = new GameObject("Oxyde Main Thread Dispatcher");
+            instance = dispatcherObject.AddComponent<OxydeMainThreadDispatcher>();
+            DontDestroyOnLoad(dispatcherObject);
+        }
+        
+        // Whether the caller is already on the main thread
+        public static bool IsMainThread => Thread.CurrentThread.ManagedThreadId == mainThreadId;
+        
+        // Queue an action to run on the main thread during the next Update
+        public static void Enqueue(Action action)
+        {
+            if (action != null)
+            {
+                pending.Enqueue(action);
+            }
+        }
+        
+        private void Update()
+        {
+            while (pending.TryDequeue(out Action action))
+            {
+                try
+                {
+                    action();
+                }
+                catch (Exception ex)
+                {
+                    Debug.LogException(ex);
+                }
+            }
+        }
+    }
+
+    /// <summary>
+    /// Task-based wrappers over the Oxyde native API
+    /// </summary>
+    public static class OxydeAsync
+    {
+        // Native calls for the same agent are serialized so responses arrive in order
+        private static readonly ConcurrentDictionary<string, SemaphoreSlim> agentLocks =
+            new ConcurrentDictionary<string, SemaphoreSlim>();
+        
+        // Process input on a background thread; the task completes on the main thread
+        public static Task<string> ProcessInputAsync(string agentId, string input, CancellationToken cancellationToken = default)
+        {
+            return RunOnWorker(agentId, () => OxydeUnity.ProcessInput(agentId, input), cancellationToken);
+        }
+        
+        // Read the emotion vector on a background thread; the task completes on the main thread
+        public static Task<float[]> GetEmotionVectorAsync(string agentId, CancellationToken cancellationToken = default)
+        {
+            return RunOnWorker(agentId, () => OxydeUnity.GetAgentEmotionVector(agentId), cancellationToken);
+        }
+        
+        // Update agent context on a background thread; the task completes on the main thread
+        public static Task<bool> UpdateAgentContextAsync(string agentId, string contextJson, CancellationToken cancellationToken = default)
+        {
+            return RunOnWorker(agentId, () => OxydeUnity.UpdateAgentContext(agentId, contextJson), cancellationToken);
+        }
+        
+        private static Task<T> RunOnWorker<T>(string agentId, Func<T> call, CancellationToken cancellationToken)
+        {
+            var completion = new TaskCompletionSource<T>();
+            SemaphoreSlim agentLock = agentLocks.GetOrAdd(agentId ?? string.Empty, _ => new SemaphoreSlim(1, 1));
+            
+            Task.Run(async () =>
+            {
+                try
+                {
+                    await agentLock.WaitAsync(cancellationToken);
+                    T result;
+                    try
+                    {
+                        result = call();
+                    }
+                    finally
+                    {
+                        agentLock.Release();
+                    }
+                    OxydeMainThreadDispatcher.Enqueue(() => completion.TrySetResult(result));
+                }
+                catch (OperationCanceledException)
+                {
+                    OxydeMainThreadDispatcher.Enqueue(() => completion.TrySetCanceled());
+                }
+                catch (Exception ex)
+                {
+                    OxydeMainThreadDispatcher.Enqueue(() => completion.TrySetException(ex));
+                }
+            });
+            
+            return completion.Task;
+        }
+    }
+
+    /// <summary>
+    /// Oxyde agent that processes input without blocking the main thread
+    /// and raises UnityEvents for responses and emotion changes
+    /// </summary>
+    public abstract class OxydeAsyncAgent : OxydeAgent
+    {
+        [Header("Events")]
+        [SerializeField] private OxydeResponseEvent onResponse = new OxydeResponseEvent();
+        [SerializeField] private OxydeEmotionEvent onEmotionsChanged = new OxydeEmotionEvent();
+        
+        // Raised on the main thread when the agent responds
+        public OxydeResponseEvent OnResponse => onResponse;
+        
+        // Raised on the main thread when the emotion vector changes
+        public OxydeEmotionEvent OnEmotionsChanged => onEmotionsChanged;
+        
+        // Whether a request is currently in flight
+        public bool IsProcessing => pendingRequests > 0;
+        
+        private int pendingRequests = 0;
+        private float[] lastPublishedEmotions;
+        
+        // Process input on a background thread and raise OnResponse when done
+        public virtual async Task<string> ProcessInputAsync(string input, CancellationToken cancellationToken = default)
+        {
+            if (!IsInitialized)
+            {
+                return "Agent not initialized";
+            }
+            
+            pendingRequests++;
+            try
+            {
+                string response = await OxydeAsync.ProcessInputAsync(AgentId, input, cancellationToken);
+                OnResponseReceived(response);
+                onResponse.Invoke(response);
+                return response;
+            }
+            finally
+            {
+                pendingRequests--;
+            }
+        }
+        
+        // Called on the main thread before OnResponse is raised
+        protected virtual void OnResponseReceived(string response)
+        {
+        }
+        
+        protected override void UpdateEmotionData()
+        {
+            base.UpdateEmotionData();
+            
+            if (lastPublishedEmotions == null || !EmotionsEqual(lastPublishedEmotions, EmotionVector))
+            {
+                lastPublishedEmotions = (float[])EmotionVector.Clone();
+                onEmotionsChanged.Invoke(lastPublishedEmotions);
+            }
+        }
+        
+        private static bool EmotionsEqual(float[] a, float[] b)
+        {
+            if (a.Length != b.Length)
+            {
+                return false;
+            }
+            
+            for (int i = 0; i < a.Length; i++)
+            {
+                if (Mathf.Abs(a[i] - b[i]) > 0.001f)
+                {
+                    return false;
+                }
+            }
+            
+            return true;
+        }
+    }
+}
+"#
+    .to_string()
+}
+
 /// Generate Unity scene setup script
 fn generate_unity_scene_script(agents: &[AgentConfig], scene_config: &serde_json::Value) -> String {
     // This is a simplified version; a real implementation would use scene_config
@@ -650,7 +1139,7 @@ namespace Oxyde.Unity
             for (int i = 0; i < Mathf.Min(agentPrefabs.Length, positions.Length); i++)
             {{
                 GameObject agentObject = Instantiate(agentPrefabs[i], positions[i], Quaternion.identity);
-                agentObject.name = $"NPC_{i}";
+                agentObject.name = $"NPC_{{i}}";
             }}
             
             Debug.Log($"Spawned {{Mathf.Min(agentPrefabs.Length, positions.Length)}} agents");
@@ -661,349 +1150,6 @@ namespace Oxyde.Unity
     )
 }
 
-/// Deploy agents for Unreal engine
-fn deploy_unreal_agents(
-    agents: &[AgentConfig],
-    scene_config: &serde_json::Value,
-    output: &str,
-) -> Result<()> {
-    println!("Generating Unreal-specific files...");
-    
-    // Create Unreal-specific directories
-    let include_dir = PathBuf::from(output).join("Public");
-    let source_dir = PathBuf::from(output).join("Private");
-    let configs_dir = PathBuf::from(output).join("Content/Oxyde/Configs");
-    fs::create_dir_all(&include_dir)?;
-    fs::create_dir_all(&source_dir)?;
-    fs::create_dir_all(&configs_dir)?;
-    
-    // Generate header files
-    let oxyde_header = generate_unreal_oxyde_header();
-    fs::write(include_dir.join("OxydeNPC.h"), oxyde_header)?;
-    
-    let agent_header = generate_unreal_agent_header(agents);
-    fs::write(include_dir.join("OxydeAgentTypes.h"), agent_header)?;
-    
-    // Generate source files
-    let oxyde_source = generate_unreal_oxyde_source();
-    fs::write(source_dir.join("OxydeNPC.cpp"), oxyde_source)?;
-    
-    // Write agent configurations
-    for (i, agent) in agents.iter().enumerate() {
-        let config_json = serde_json::to_string_pretty(agent)?;
-        let config_filename = format!("Agent_{}.json", agent.agent.name.replace(" ", ""));
-        fs::write(configs_dir.join(config_filename), config_json)?;
-    }
-    
-    println!("Generated Unreal Engine integration files in: {}", output);
-    Ok(())
-}
-
-/// Generate Unreal Engine header file
-fn generate_unreal_oxyde_header() -> String {
-    r#"// Copyright Epic Games, Inc. All Rights Reserved.
-
-#pragma once
-
-#include "CoreMinimal.h"
-#include "GameFramework/Character.h"
-#include "OxydeAgentTypes.h"
-#include "OxydeNPC.generated.h"
-
-UCLASS()
-class OXYDE_API AOxydeNPC : public ACharacter
-{
-    GENERATED_BODY()
-
-public:
-    // Sets default values for this character's properties
-    AOxydeNPC();
-
-    // Called every frame
-    virtual void Tick(float DeltaTime) override;
-
-    // Called to bind functionality to input
-    virtual void SetupPlayerInputComponent(class UInputComponent* PlayerInputComponent) override;
-
-    // Initialize the NPC agent
-    UFUNCTION(BlueprintCallable, Category = "Oxyde")
-    bool InitializeAgent(FString ConfigPath);
-
-    // Process input for the agent
-    UFUNCTION(BlueprintCallable, Category = "Oxyde")
-    FString ProcessInput(FString Input);
-
-    // Update agent context
-    UFUNCTION(BlueprintCallable, Category = "Oxyde")
-    void UpdateContext(FString ContextJSON);
-
-    // Get agent name
-    UFUNCTION(BlueprintPure, Category = "Oxyde")
-    FString GetAgentName() const;
-
-    // Get agent role
-    UFUNCTION(BlueprintPure, Category = "Oxyde")
-    FString GetAgentRole() const;
-
-protected:
-    // Called when the game starts or when spawned
-    virtual void BeginPlay() override;
-
-    // Agent configuration
-    UPROPERTY(EditDefaultsOnly, BlueprintReadOnly, Category = "Oxyde")
-    FString ConfigPath;
-
-    // Agent state
-    UPROPERTY(VisibleAnywhere, BlueprintReadOnly, Category = "Oxyde")
-    FOxydeAgentState AgentState;
-
-    // Dialogue component
-    UPROPERTY(VisibleAnywhere, BlueprintReadOnly, Category = "Oxyde")
-    class UDialogueComponent* DialogueComponent;
-
-private:
-    // Agent ID returned by the Oxyde SDK
-    FString AgentId;
-
-    // Generate context JSON from the current state
-    FString GenerateContextJSON();
-};
-"#.to_string()
-}
-
-/// Generate Unreal Engine agent header
-fn generate_unreal_agent_header(agents: &[AgentConfig]) -> String {
-    let mut agent_enum_values = String::new();
-    
-    for agent in agents {
-        let enum_name = agent.agent.name.replace(" ", "");
-        agent_enum_values.push_str(&format!("    {}Agent,\n", enum_name));
-    }
-    
-    format!(
-        r#"// Copyright Epic Games, Inc. All Rights Reserved.
-
-#pragma once
-
-#include "CoreMinimal.h"
-#include "OxydeAgentTypes.generated.h"
-
-// Agent types available in the game
-UENUM(BlueprintType)
-enum class EOxydeAgentType : uint8
-{{
-{}
-}};
-
-// Agent state information
-USTRUCT(BlueprintType)
-struct FOxydeAgentState
-{{
-    GENERATED_BODY()
-
-    // Agent ID
-    UPROPERTY(VisibleAnywhere, BlueprintReadOnly, Category = "Oxyde")
-    FString Id;
-
-    // Agent name
-    UPROPERTY(VisibleAnywhere, BlueprintReadOnly, Category = "Oxyde")
-    FString Name;
-
-    // Agent role
-    UPROPERTY(VisibleAnywhere, BlueprintReadOnly, Category = "Oxyde")
-    FString Role;
-
-    // Current state
-    UPROPERTY(VisibleAnywhere, BlueprintReadOnly, Category = "Oxyde")
-    FString State;
-
-    // Last response
-    UPROPERTY(VisibleAnywhere, BlueprintReadOnly, Category = "Oxyde")
-    FString LastResponse;
-}};
-"#,
-        agent_enum_values
-    )
-}
-
-/// Generate Unreal Engine source file
-fn generate_unreal_oxyde_source() -> String {
-    r#"// Copyright Epic Games, Inc. All Rights Reserved.
-
-#include "OxydeNPC.h"
-#include "OxydeUnreal.h"
-#include "Components/TextRenderComponent.h"
-#include "Components/WidgetComponent.h"
-#include "Kismet/GameplayStatics.h"
-#include "Kismet/KismetSystemLibrary.h"
-#include "Json.h"
-
-// Sets default values
-AOxydeNPC::AOxydeNPC()
-{
-    // Set this character to call Tick() every frame
-    PrimaryActorTick.bCanEverTick = true;
-
-    // Create dialogue component
-    DialogueComponent = CreateDefaultSubobject<UWidgetComponent>(TEXT("DialogueComponent"));
-    DialogueComponent->SetupAttachment(RootComponent);
-    DialogueComponent->SetRelativeLocation(FVector(0, 0, 100));
-    DialogueComponent->SetWidgetSpace(EWidgetSpace::Screen);
-    DialogueComponent->SetDrawSize(FVector2D(200, 100));
-}
-
-// Called when the game starts or when spawned
-void AOxydeNPC::BeginPlay()
-{
-    Super::BeginPlay();
-    
-    // Initialize Oxyde SDK
-    OxydeUnreal::Init();
-    
-    // Initialize agent if config path is set
-    if (!ConfigPath.IsEmpty())
-    {
-        InitializeAgent(ConfigPath);
-    }
-}
-
-// Called every frame
-void AOxydeNPC::Tick(float DeltaTime)
-{
-    Super::Tick(DeltaTime);
-
-    // Update agent context with player distance
-    APawn* PlayerPawn = UGameplayStatics::GetPlayerPawn(GetWorld(), 0);
-    if (PlayerPawn && !AgentId.IsEmpty())
-    {
-        float Distance = FVector::Dist(GetActorLocation(), PlayerPawn->GetActorLocation());
-        
-        // Create context JSON
-        TSharedPtr<FJsonObject> ContextObj = MakeShareable(new FJsonObject);
-        ContextObj->SetNumberField("player_distance", Distance);
-        ContextObj->SetNumberField("player_x", PlayerPawn->GetActorLocation().X);
-        ContextObj->SetNumberField("player_y", PlayerPawn->GetActorLocation().Y);
-        ContextObj->SetNumberField("player_z", PlayerPawn->GetActorLocation().Z);
-        ContextObj->SetNumberField("npc_x", GetActorLocation().X);
-        ContextObj->SetNumberField("npc_y", GetActorLocation().Y);
-        ContextObj->SetNumberField("npc_z", GetActorLocation().Z);
-        
-        FString ContextJSON;
-        TSharedRef<TJsonWriter<>> Writer = TJsonWriterFactory<>::Create(&ContextJSON);
-        FJsonSerializer::Serialize(ContextObj.ToSharedRef(), Writer);
-        
-        // Update agent context
-        UpdateContext(ContextJSON);
-        
-        // Auto greet player if close enough
-        static float LastGreetingTime = -999.0f;
-        const float GreetingCooldown = 60.0f;
-        
-        if (Distance < 300.0f && (GetWorld()->GetTimeSeconds() - LastGreetingTime > GreetingCooldown))
-        {
-            FString Response = ProcessInput("");
-            if (!Response.IsEmpty())
-            {
-                LastGreetingTime = GetWorld()->GetTimeSeconds();
-                AgentState.LastResponse = Response;
-                
-                // Display dialogue (would use widget in real implementation)
-                UE_LOG(LogTemp, Display, TEXT("%s: %s"), *AgentState.Name, *Response);
-            }
-        }
-    }
-}
-
-// Called to bind functionality to input
-void AOxydeNPC::SetupPlayerInputComponent(UInputComponent* PlayerInputComponent)
-{
-    Super::SetupPlayerInputComponent(PlayerInputComponent);
-}
-
-// Initialize the agent
-bool AOxydeNPC::InitializeAgent(FString ConfigPath)
-{
-    if (ConfigPath.IsEmpty())
-    {
-        UE_LOG(LogTemp, Error, TEXT("Cannot initialize agent: Configuration path is empty"));
-        return false;
-    }
-
-    // Check if config file exists
-    if (!FPaths::FileExists(ConfigPath))
-    {
-        UE_LOG(LogTemp, Error, TEXT("Cannot initialize agent: Configuration file not found: %s"), *ConfigPath);
-        return false;
-    }
-
-    // Create agent using Oxyde SDK
-    AgentId = OxydeUnreal::CreateAgent(TCHAR_TO_UTF8(*ConfigPath));
-    
-    if (AgentId.IsEmpty())
-    {
-        UE_LOG(LogTemp, Error, TEXT("Failed to create agent from config: %s"), *ConfigPath);
-        return false;
-    }
-    
-    // Get agent info
-    FString AgentJSON = OxydeUnreal::GetAgentState(TCHAR_TO_UTF8(*AgentId));
-    TSharedPtr<FJsonObject> AgentObj;
-    TSharedRef<TJsonReader<>> Reader = TJsonReaderFactory<>::Create(AgentJSON);
-    
-    if (FJsonSerializer::Deserialize(Reader, AgentObj) && AgentObj.IsValid())
-    {
-        AgentState.Id = AgentId;
-        AgentState.Name = AgentObj->GetStringField("name");
-        AgentState.Role = AgentObj->GetStringField("role");
-        AgentState.State = AgentObj->GetStringField("state");
-    }
-    
-    UE_LOG(LogTemp, Display, TEXT("Initialized agent: %s (Role: %s)"), *AgentState.Name, *AgentState.Role);
-    return true;
-}
-
-// Process input for the agent
-FString AOxydeNPC::ProcessInput(FString Input)
-{
-    if (AgentId.IsEmpty())
-    {
-        UE_LOG(LogTemp, Warning, TEXT("Cannot process input: Agent not initialized"));
-        return FString();
-    }
-
-    // Call Oxyde SDK to process input
-    FString Response = OxydeUnreal::ProcessInput(TCHAR_TO_UTF8(*AgentId), TCHAR_TO_UTF8(*Input));
-    AgentState.LastResponse = Response;
-    
-    return Response;
-}
-
-// Update agent context
-void AOxydeNPC::UpdateContext(FString ContextJSON)
-{
-    if (AgentId.IsEmpty() || ContextJSON.IsEmpty())
-    {
-        return;
-    }
-
-    // Call Oxyde SDK to update context
-    OxydeUnreal::UpdateAgentContext(TCHAR_TO_UTF8(*AgentId), TCHAR_TO_UTF8(*ContextJSON));
-}
-
-// Get agent name
-FString AOxydeNPC::GetAgentName() const
-{
-    return AgentState.Name;
-}
-
-// Get agent role
-FString AOxydeNPC::GetAgentRole() const
-{
-    return AgentState.Role;
-}
-"#.to_string()
-}
-
 /// Deploy agents for WebAssembly (browser-based games)
 fn deploy_wasm_agents(
     agents: &[AgentConfig],
@@ -1516,11 +1662,12 @@ async fn test_agent(
     config_path: &str,
     local_only: bool,
     persistent_memory: bool,
+    offline: bool,
 ) -> Result<()> {
     println!("Loading agent from: {}", config_path);
     
     // Load agent configuration
-    let mut config = AgentConfig::from_file(config_path)?;
+    let mut config = load_agent_config(config_path)?;
     
     // Override configuration based on command-line flags
     if local_only {
@@ -1530,9 +1677,14 @@ async fn test_agent(
     if persistent_memory {
         config.memory.persistence = true;
     }
+
+    if offline {
+        config = config.offline();
+        println!("Offline mode: mock inference and speech, memories kept in memory");
+    }
     
-    // Create agent
-    let agent = Agent::new(config);
+    // Create agent; offline agents also synthesize speech, which needs no API key
+    let agent = if offline { Agent::new_with_tts(config) } else { Agent::new(config) };
     
     // Start agent
     agent.start().await?;
@@ -1559,6 +1711,13 @@ async fn test_agent(
         match agent.process_input(input).await {
             Ok(response) => {
                 println!("{}: {}", agent.name(), response);
+                if offline {
+                    let emotions = agent.emotional_state().await;
+                    match agent.speak(&response, &emotions, 0.0).await {
+                        Ok(audio) => println!("  [speech: {} ms of {:?} audio]", audio.duration_ms, audio.format),
+                        Err(err) => println!("  [speech failed: {}]", err),
+                    }
+                }
             },
             Err(err) => {
                 println!("Error: {}", err);
@@ -1582,7 +1741,7 @@ async fn convert_agent_config(
     println!("Converting agent configuration: {} -> {}", input_path, output_path);
     
     // Load input configuration
-    let config = AgentConfig::from_file(input_path)?;
+    let config = load_agent_config(input_path)?;
     
     // Write in the specified format
     match format.to_lowercase().as_str() {
@@ -1591,7 +1750,7 @@ async fn convert_agent_config(
             fs::write(output_path, json)?;
         },
         "yaml" | "yml" => {
-            let yaml = serde_yaml::to_string(&config)?;
+            let yaml = serde_yaml::to_string(&config).map_err(|e| OxydeError::CliError(e.to_string()))?;
             fs::write(output_path, yaml)?;
         },
         _ => {
@@ -1602,3 +1761,385 @@ async fn convert_agent_config(
     println!("Conversion complete");
     Ok(())
 }
+
+/// Validate an agent configuration, printing migration warnings
+fn validate_agent_config(path: &str, upgrade: bool) -> Result<()> {
+    let (config, report) = AgentConfig::from_file_migrated(path)?;
+
+    for warning in &report.warnings {
+        println!("warning: {}", warning);
+    }
+    if !report.migrated() {
+        println!("{} is valid (config version {})", path, CONFIG_VERSION);
+        return Ok(());
+    }
+
+    println!(
+        "{} is valid but uses config version {}; the current version is {}",
+        path, report.from_version, CONFIG_VERSION
+    );
+    if upgrade {
+        config.save_to_file(path)?;
+        println!("Upgraded {} to config version {}", path, CONFIG_VERSION);
+    } else {
+        println!("Run with --upgrade to rewrite it in the current version");
+    }
+    Ok(())
+}
+
+/// Chunk a lore directory, printing the ingestion report and adding the
+/// chunks to an agent configuration's knowledge if one is given
+fn ingest_knowledge(dir: &str, config_path: Option<&str>, format: &str, options: &IngestOptions) -> Result<()> {
+    let ingestion = ingest_dir(Path::new(dir), options)?;
+    let report = &ingestion.report;
+    match format {
+        "text" => print!("{}", report),
+        "json" => println!("{}", serde_json::to_string_pretty(report)?),
+        other => {
+            return Err(OxydeError::CliError(format!(
+                "Unknown report format '{}'; expected text or json",
+                other
+            )))
+        }
+    }
+
+    let Some(config_path) = config_path else {
+        if report.skipped.is_empty() {
+            return Ok(());
+        }
+        return Err(OxydeError::CliError(format!(
+            "{} of the files in {} cannot be ingested",
+            report.skipped.len(),
+            dir
+        )));
+    };
+
+    let mut config = AgentConfig::from_file(config_path)?;
+    let added = ingestion.add_to(&mut config.agent.knowledge);
+    config.save_to_file(config_path)?;
+    println!("Added {} knowledge entries to {}", added, config_path);
+    Ok(())
+}
+
+/// Load an agent configuration from a config file or a .oxyde package
+fn load_agent_config(path: &str) -> Result<AgentConfig> {
+    if Path::new(path).extension().and_then(|e| e.to_str()) == Some(PACKAGE_EXTENSION) {
+        AgentConfig::from_package(path)
+    } else {
+        AgentConfig::from_file(path)
+    }
+}
+
+/// Bundle an agent directory into a .oxyde package
+fn pack_agent(source: &str, output: Option<&str>) -> Result<()> {
+    println!("Packing agent from: {}", source);
+
+    let package = AgentPackage::from_dir(source)?;
+    let output = match output {
+        Some(output) => PathBuf::from(output),
+        None => PathBuf::from(format!(
+            "{}.{}",
+            package.manifest.name.to_lowercase().replace(' ', "_"),
+            PACKAGE_EXTENSION
+        )),
+    };
+    package.save(&output)?;
+
+    println!("  Config:    {}", package.manifest.config);
+    println!("  Prompts:   {}", package.manifest.prompts.len());
+    println!("  Knowledge: {}", package.manifest.knowledge.len());
+    println!("  Voices:    {}", package.manifest.voices.len());
+    println!("Packed {} into {}", package.manifest.name, output.display());
+    Ok(())
+}
+
+/// Extract a .oxyde package into a directory
+fn unpack_agent(package_path: &str, output: &str) -> Result<()> {
+    println!("Unpacking agent package: {}", package_path);
+
+    let package = AgentPackage::load(package_path)?;
+    package.unpack(output)?;
+
+    println!(
+        "Unpacked {} ({} files) into {}",
+        package.manifest.name,
+        package.files.len(),
+        output
+    );
+    Ok(())
+}
+
+/// List provider voices and synthesize sample clips of them
+async fn preview_voices(
+    providers: &[String],
+    config_path: Option<&str>,
+    voice_ids: &[String],
+    text: Option<&str>,
+    out: &str,
+) -> Result<()> {
+    let tts_config = match config_path {
+        Some(path) => load_agent_config(path)?.tts,
+        None => None,
+    };
+
+    let mut selected = providers
+        .iter()
+        .map(|name| name.parse::<TTSProvider>().map_err(OxydeError::AudioError))
+        .collect::<Result<Vec<_>>>()?;
+    if selected.is_empty() {
+        match &tts_config {
+            Some(tts) => {
+                selected.push(tts.default_provider.clone());
+                selected.extend(tts.degradation.secondary_provider.clone());
+            }
+            None => selected.push(TTSProvider::ElevenLabs),
+        }
+    }
+    selected.dedup();
+
+    if text.is_some() {
+        fs::create_dir_all(out)?;
+    }
+    for provider in selected {
+        // The mock provider only produces uncompressed audio
+        let mut config = tts_config.clone().unwrap_or_else(TTSConfig::mock);
+        config.default_provider = provider.clone();
+        config.cache_dir = None;
+        if provider == TTSProvider::Mock && !config.output_format.is_uncompressed() {
+            config.output_format = TTSConfig::mock().output_format;
+        }
+        let service = TTSService::new(provider.clone(), config.clone());
+
+        let voices = service.list_voices().await.map_err(OxydeError::AudioError)?;
+        let voices: Vec<_> = voices
+            .into_iter()
+            .filter(|voice| voice_ids.is_empty() || voice_ids.contains(&voice.id))
+            .collect();
+        println!("{} ({} voices)", provider.as_str(), voices.len());
+
+        for voice in voices {
+            println!("  {:<24} {:<20} {}", voice.id, voice.name, voice.label_summary());
+            let Some(text) = text else {
+                continue;
+            };
+            let audio = service.synthesize_sample(&voice.id, text).await.map_err(OxydeError::AudioError)?;
+            let file_name: String = format!("{}_{}", provider.as_str(), voice.name)
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c.to_ascii_lowercase() } else { '_' })
+                .collect();
+            let path = Path::new(out).join(format!("{}_{}.{}", file_name, voice.id, config.output_format.extension()));
+            fs::write(&path, &audio.data)?;
+            println!("    sample: {} ({} ms)", path.display(), audio.duration_ms);
+        }
+    }
+    Ok(())
+}
+
+/// Render memory distributions from a saved memory session
+fn memory_report(session_path: &str, format: &str, output: Option<&str>) -> Result<()> {
+    let session = MemorySession::load(session_path)?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let stats = session.stats(now);
+
+    let report = match format.to_lowercase().as_str() {
+        "text" => stats.to_text(),
+        "csv" => stats.to_csv(),
+        _ => {
+            return Err(OxydeError::CliError(format!("Unsupported report format: {}", format)));
+        }
+    };
+
+    match output {
+        Some(path) => {
+            fs::write(path, report)?;
+            println!("Wrote memory report for {} memories to {}", stats.total, path);
+        }
+        None => print!("{}", report),
+    }
+    Ok(())
+}
+
+/// Export interaction logs as a dialogue tree
+fn export_dialogue(logs: &[String], format: &str, output: Option<&str>) -> Result<()> {
+    let format: DialogueFormat = format.parse()?;
+    let mut records = Vec::new();
+    for log in logs {
+        records.extend(read_log(Path::new(log))?);
+    }
+    let graph = DialogueGraph::from_records(&records);
+    let rendered = graph.render(format)?;
+
+    match output {
+        Some(path) => {
+            fs::write(path, rendered)?;
+            println!(
+                "Exported {} exchanges as {} dialogue nodes to {}",
+                records.len(),
+                graph.nodes.len(),
+                path
+            );
+        }
+        None => print!("{}", rendered),
+    }
+    Ok(())
+}
+
+/// Run a load test and write its report
+async fn load_test(
+    agents: usize,
+    rps: f64,
+    duration: &str,
+    config_path: Option<&str>,
+    format: &str,
+    output: Option<&str>,
+) -> Result<()> {
+    let duration = parse_duration(duration)?;
+    if !matches!(format.to_lowercase().as_str(), "text" | "json") {
+        return Err(OxydeError::CliError(format!("Unsupported report format: {}", format)));
+    }
+    let config = config_path.map(load_agent_config).transpose()?;
+
+    eprintln!("Sending {} inputs/s to {} agents for {:?}...", rps, agents, duration);
+    let report = run_load_test(LoadTestOptions { agents, rps, duration, config }).await?;
+    let rendered = match format.to_lowercase().as_str() {
+        "json" => serde_json::to_string_pretty(&report)? + "\n",
+        _ => report.to_string(),
+    };
+
+    match output {
+        Some(path) => {
+            fs::write(path, rendered)?;
+            println!("Wrote load test report to {}", path);
+        }
+        None => print!("{}", rendered),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fresh directory for a test's files
+    fn temp_dir(name: &str) -> PathBuf {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+        let dir = std::env::temp_dir().join(format!("oxyde-cli-{}-{}-{}", name, process::id(), nanos));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_voices_command_writes_samples() {
+        let dir = temp_dir("voices");
+        let out = dir.join("samples");
+        preview_voices(&["mock".to_string()], None, &[], Some("Welcome, traveler."), out.to_str().unwrap())
+            .await
+            .unwrap();
+
+        let sample = fs::read(out.join("mock_silence_default.wav")).unwrap();
+        assert_eq!(&sample[..4], b"RIFF");
+
+        // Voices outside the selection get no sample
+        let filtered = dir.join("filtered");
+        let ids = ["narrator".to_string()];
+        preview_voices(&["mock".to_string()], None, &ids, Some("Hello"), filtered.to_str().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(fs::read_dir(&filtered).unwrap().count(), 0);
+
+        assert!(preview_voices(&["nobody".to_string()], None, &[], None, out.to_str().unwrap()).await.is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_load_test_command_writes_json_report() {
+        let dir = temp_dir("loadtest");
+        let output = dir.join("report.json");
+        load_test(2, 100.0, "50ms", None, "json", output.to_str()).await.unwrap();
+
+        let report: serde_json::Value = serde_json::from_str(&fs::read_to_string(&output).unwrap()).unwrap();
+        assert_eq!(report["agents"], 2);
+        assert!(report["requests"].as_u64().unwrap() > 0);
+        assert!(report.get("lock_wait_ms").is_some());
+
+        assert!(load_test(2, 100.0, "50ms", None, "xml", None).await.is_err());
+        assert!(load_test(2, 100.0, "forever", None, "text", None).await.is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_kb_ingest_adds_entries_to_the_config() {
+        let dir = temp_dir("kb");
+        let lore = dir.join("lore");
+        fs::create_dir_all(&lore).unwrap();
+        fs::write(lore.join("history.md"), "# The Founding\n\nVael was founded by exiles.\n").unwrap();
+        let config_path = dir.join("agent.yaml");
+        fs::write(
+            &config_path,
+            "agent:\n  name: Ysolde\n  role: Archivist\n  backstory: []\n  knowledge: []\nmemory: {}\ninference:\n  provider: mock\n",
+        )
+        .unwrap();
+        let config = config_path.to_str().unwrap();
+
+        let options = IngestOptions::default();
+        ingest_knowledge(lore.to_str().unwrap(), Some(config), "json", &options).unwrap();
+        ingest_knowledge(lore.to_str().unwrap(), Some(config), "text", &options).unwrap();
+        let saved = AgentConfig::from_file(config).unwrap();
+        assert_eq!(saved.agent.knowledge, ["The Founding: Vael was founded by exiles."]);
+
+        // A report over files that cannot be ingested fails
+        fs::write(lore.join("broken.md"), "```\nfireball\n").unwrap();
+        assert!(ingest_knowledge(lore.to_str().unwrap(), None, "text", &options).is_err());
+        assert!(ingest_knowledge(lore.to_str().unwrap(), None, "xml", &options).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_export_dialogue_writes_twee_and_json() {
+        let dir = temp_dir("dialogue");
+        let log = dir.join("interactions.jsonl");
+        let record = |input: &str, response: &str| {
+            let record = oxyde::interaction_log::InteractionRecord {
+                timestamp: 0,
+                agent_id: "agent".to_string(),
+                agent_name: "Marla".to_string(),
+                input: input.to_string(),
+                intent: Some("greeting".to_string()),
+                system_prompt: String::new(),
+                memories: Vec::new(),
+                provider: "mock".to_string(),
+                model: None,
+                experiment: None,
+                variant: None,
+                tokens: 0,
+                latency_ms: 0,
+                response: response.to_string(),
+                final_response: response.to_string(),
+                emotions_before: Default::default(),
+                emotions_after: Default::default(),
+            };
+            serde_json::to_string(&record).unwrap()
+        };
+        fs::write(&log, format!("{}\n\n{}\n", record("Hi", "Welcome!"), record("Hello", "Welcome!"))).unwrap();
+        let logs = [log.to_str().unwrap().to_string()];
+
+        let twee = dir.join("dialogue.twee");
+        export_dialogue(&logs, "twee", twee.to_str()).unwrap();
+        let written = fs::read_to_string(&twee).unwrap();
+        assert!(written.starts_with(":: StoryTitle\nMarla dialogue\n"));
+        assert!(written.contains("Marla: Welcome!"));
+
+        let json = dir.join("dialogue.json");
+        export_dialogue(&logs, "json", json.to_str()).unwrap();
+        let graph: DialogueGraph = serde_json::from_str(&fs::read_to_string(&json).unwrap()).unwrap();
+        assert_eq!(graph.nodes[0].count, 2);
+
+        assert!(export_dialogue(&logs, "ink", None).is_err());
+        fs::write(&log, "not a record\n").unwrap();
+        assert!(export_dialogue(&logs, "json", None).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,1394 @@
+//! Unreal Engine plugin generation
+//!
+//! `oxyde-cli deploy --engine unreal` writes a complete plugin that can be
+//! dropped into a project's `Plugins` directory:
+//!
+//! ```text
+//! Oxyde/
+//!   Oxyde.uplugin
+//!   Content/Configs/Agent_<Name>.json
+//!   Source/Oxyde/Oxyde.Build.cs
+//!   Source/Oxyde/Public/     module, Blueprint library, async nodes, NPC actor, dialogue widget
+//!   Source/Oxyde/Private/    implementations and the worker-thread dispatcher
+//!   Source/ThirdParty/OxydeFFI/include/oxyde.h
+//!   Source/ThirdParty/OxydeFFI/lib/<Platform>/   prebuilt oxyde-ffi library
+//! ```
+//!
+//! The Blueprint function library wraps the C ABI from `crates/oxyde-ffi`,
+//! whose header is embedded so the plugin always matches the SDK it was
+//! generated with.
+//!
+//! FFI calls block until the agent answers, so the plugin also generates
+//! latent Blueprint nodes (`Process Input Async`, `Speak Async`) that run the
+//! call on the plugin's worker threads and fire `OnResponse` or `OnFailure`
+//! back on the game thread. The NPC actor and dialogue widget use them, so
+//! dialogue never stalls a frame.
+
+use std::fs;
+use std::path::PathBuf;
+
+use oxyde::config::AgentConfig;
+use oxyde::Result;
+
+/// Name of the generated plugin and its runtime module
+const PLUGIN_NAME: &str = "Oxyde";
+
+/// C header of the FFI crate the Blueprint library wraps
+const FFI_HEADER: &str = include_str!("../../crates/oxyde-ffi/include/oxyde.h");
+
+/// Deploy agents for Unreal Engine as a plugin
+pub fn deploy_unreal_agents(
+    agents: &[AgentConfig],
+    _scene_config: &serde_json::Value,
+    output: &str,
+) -> Result<()> {
+    println!("Generating Unreal Engine plugin...");
+
+    let plugin_dir = PathBuf::from(output).join(PLUGIN_NAME);
+    let module_dir = plugin_dir.join("Source").join(PLUGIN_NAME);
+    let public_dir = module_dir.join("Public");
+    let private_dir = module_dir.join("Private");
+    let ffi_dir = plugin_dir.join("Source/ThirdParty/OxydeFFI");
+    let configs_dir = plugin_dir.join("Content/Configs");
+    for dir in [&public_dir, &private_dir, &ffi_dir.join("include"), &configs_dir] {
+        fs::create_dir_all(dir)?;
+    }
+    for platform in ["Win64", "Mac", "Linux"] {
+        fs::create_dir_all(ffi_dir.join("lib").join(platform))?;
+    }
+
+    // Plugin descriptor and build rules
+    fs::write(plugin_dir.join(format!("{}.uplugin", PLUGIN_NAME)), generate_unreal_plugin_descriptor()?)?;
+    fs::write(module_dir.join(format!("{}.Build.cs", PLUGIN_NAME)), generate_unreal_build_rules())?;
+
+    // C ABI
+    fs::write(ffi_dir.join("include/oxyde.h"), FFI_HEADER)?;
+    fs::write(ffi_dir.join("README.md"), generate_unreal_ffi_readme())?;
+
+    // Module, worker dispatcher, Blueprint library and async nodes, NPC actor, and dialogue widget
+    let sources = [
+        (&public_dir, "OxydeModule.h", generate_unreal_module_header()),
+        (&private_dir, "OxydeModule.cpp", generate_unreal_module_source()),
+        (&private_dir, "OxydeWorker.h", generate_unreal_worker_header()),
+        (&private_dir, "OxydeWorker.cpp", generate_unreal_worker_source()),
+        (&public_dir, "OxydeAgentTypes.h", generate_unreal_agent_header(agents)),
+        (&public_dir, "OxydeBlueprintLibrary.h", generate_unreal_library_header()),
+        (&private_dir, "OxydeBlueprintLibrary.cpp", generate_unreal_library_source()),
+        (&public_dir, "OxydeAsyncActions.h", generate_unreal_async_header()),
+        (&private_dir, "OxydeAsyncActions.cpp", generate_unreal_async_source()),
+        (&public_dir, "OxydeNPC.h", generate_unreal_npc_header()),
+        (&private_dir, "OxydeNPC.cpp", generate_unreal_npc_source()),
+        (&public_dir, "OxydeDialogueWidget.h", generate_unreal_widget_header()),
+        (&private_dir, "OxydeDialogueWidget.cpp", generate_unreal_widget_source()),
+    ];
+    for (dir, name, contents) in sources {
+        fs::write(dir.join(name), contents)?;
+    }
+
+    // Agent configurations
+    for agent in agents {
+        let config_json = serde_json::to_string_pretty(agent)?;
+        fs::write(configs_dir.join(config_filename(agent)), config_json)?;
+    }
+
+    println!("Generated Unreal Engine plugin in: {}", plugin_dir.display());
+    println!("Copy the oxyde-ffi library for each platform into Source/ThirdParty/OxydeFFI/lib/<Platform>");
+    Ok(())
+}
+
+/// File name of an agent's configuration inside the plugin content
+fn config_filename(agent: &AgentConfig) -> String {
+    format!("Agent_{}.json", agent.agent.name.replace(' ', ""))
+}
+
+/// Generate the `.uplugin` descriptor
+fn generate_unreal_plugin_descriptor() -> Result<String> {
+    let descriptor = serde_json::json!({
+        "FileVersion": 3,
+        "Version": 1,
+        "VersionName": oxyde::VERSION,
+        "FriendlyName": "Oxyde",
+        "Description": "AI-driven NPC agents powered by the Oxyde SDK",
+        "Category": "AI",
+        "CreatedBy": "Oxyde Labs",
+        "CreatedByURL": "https://github.com/Oxyde-Labs/Oxyde",
+        "CanContainContent": true,
+        "IsBetaVersion": true,
+        "Modules": [
+            {
+                "Name": PLUGIN_NAME,
+                "Type": "Runtime",
+                "LoadingPhase": "Default",
+                "PlatformAllowList": ["Win64", "Mac", "Linux"]
+            }
+        ]
+    });
+    Ok(serde_json::to_string_pretty(&descriptor)?)
+}
+
+/// Generate the module build rules, linking the prebuilt FFI library
+fn generate_unreal_build_rules() -> String {
+    r#"// Build rules for the Oxyde runtime module
+
+using System.IO;
+using UnrealBuildTool;
+
+public class Oxyde : ModuleRules
+{
+    public Oxyde(ReadOnlyTargetRules Target) : base(Target)
+    {
+        PCHUsage = PCHUsageMode.UseExplicitOrSharedPCHs;
+
+        PublicDependencyModuleNames.AddRange(new string[]
+        {
+            "Core",
+            "CoreUObject",
+            "Engine",
+            "UMG",
+        });
+
+        PrivateDependencyModuleNames.AddRange(new string[]
+        {
+            "Json",
+            "Projects",
+            "Slate",
+            "SlateCore",
+        });
+
+        string FFIDir = Path.Combine(ModuleDirectory, "..", "ThirdParty", "OxydeFFI");
+        PublicIncludePaths.Add(Path.Combine(FFIDir, "include"));
+
+        if (Target.Platform == UnrealTargetPlatform.Win64)
+        {
+            string LibDir = Path.Combine(FFIDir, "lib", "Win64");
+            PublicAdditionalLibraries.Add(Path.Combine(LibDir, "oxyde_ffi.dll.lib"));
+            PublicDelayLoadDLLs.Add("oxyde_ffi.dll");
+            RuntimeDependencies.Add("$(BinaryOutputDir)/oxyde_ffi.dll", Path.Combine(LibDir, "oxyde_ffi.dll"));
+        }
+        else if (Target.Platform == UnrealTargetPlatform.Mac)
+        {
+            string Library = Path.Combine(FFIDir, "lib", "Mac", "liboxyde_ffi.dylib");
+            PublicAdditionalLibraries.Add(Library);
+            RuntimeDependencies.Add("$(BinaryOutputDir)/liboxyde_ffi.dylib", Library);
+        }
+        else if (Target.Platform == UnrealTargetPlatform.Linux)
+        {
+            string Library = Path.Combine(FFIDir, "lib", "Linux", "liboxyde_ffi.so");
+            PublicAdditionalLibraries.Add(Library);
+            RuntimeDependencies.Add("$(BinaryOutputDir)/liboxyde_ffi.so", Library);
+        }
+    }
+}
+"#
+    .to_string()
+}
+
+/// Generate instructions for supplying the prebuilt FFI library
+fn generate_unreal_ffi_readme() -> String {
+    r#"# Oxyde FFI library
+
+The plugin links the Oxyde C ABI from the `oxyde-ffi` crate. Build it for
+each target platform and copy the outputs into `lib/<Platform>`:
+
+```sh
+cargo build -p oxyde-ffi --release
+```
+
+| Platform | Files                                   |
+|----------|-----------------------------------------|
+| Win64    | `oxyde_ffi.dll`, `oxyde_ffi.dll.lib`    |
+| Mac      | `liboxyde_ffi.dylib`                    |
+| Linux    | `liboxyde_ffi.so`                       |
+
+`include/oxyde.h` was generated alongside this plugin and matches the SDK
+version in `Oxyde.uplugin`. The module refuses to start if the library's
+ABI version does not match the header.
+"#
+    .to_string()
+}
+
+/// Generate the module class header
+fn generate_unreal_module_header() -> String {
+    r#"// Oxyde runtime module
+
+#pragma once
+
+#include "CoreMinimal.h"
+#include "Modules/ModuleManager.h"
+
+DECLARE_LOG_CATEGORY_EXTERN(LogOxyde, Log, All);
+
+class OXYDE_API FOxydeModule : public IModuleInterface
+{
+public:
+    virtual void StartupModule() override;
+    virtual void ShutdownModule() override;
+
+    // Whether the FFI library loaded and its ABI version matches the header
+    static bool IsAvailable();
+
+private:
+    // Threads running blocking Oxyde calls for the async Blueprint nodes
+    static constexpr int32 NumWorkerThreads = 2;
+
+    void* LibraryHandle = nullptr;
+    bool bAvailable = false;
+};
+"#
+    .to_string()
+}
+
+/// Generate the module class source
+fn generate_unreal_module_source() -> String {
+    r#"// Oxyde runtime module
+
+#include "OxydeModule.h"
+#include "OxydeWorker.h"
+#include "Interfaces/IPluginManager.h"
+#include "Misc/Paths.h"
+#include "oxyde.h"
+
+DEFINE_LOG_CATEGORY(LogOxyde);
+
+void FOxydeModule::StartupModule()
+{
+#if PLATFORM_WINDOWS
+    // The library is delay-loaded; load it from the plugin before first use
+    const FString BaseDir = IPluginManager::Get().FindPlugin(TEXT("Oxyde"))->GetBaseDir();
+    const FString LibraryPath = FPaths::Combine(BaseDir, TEXT("Source/ThirdParty/OxydeFFI/lib/Win64/oxyde_ffi.dll"));
+    LibraryHandle = FPlatformProcess::GetDllHandle(*LibraryPath);
+    if (!LibraryHandle)
+    {
+        UE_LOG(LogOxyde, Error, TEXT("Failed to load Oxyde library from %s"), *LibraryPath);
+        return;
+    }
+#endif
+
+    const uint32 Version = oxyde_abi_version();
+    if (Version != OXYDE_ABI_VERSION)
+    {
+        UE_LOG(LogOxyde, Error, TEXT("Oxyde library ABI version %u does not match header version %u"), Version, OXYDE_ABI_VERSION);
+        return;
+    }
+
+    OxydeWorker::Startup(NumWorkerThreads);
+    bAvailable = true;
+    UE_LOG(LogOxyde, Log, TEXT("Oxyde SDK loaded (ABI version %u)"), Version);
+}
+
+void FOxydeModule::ShutdownModule()
+{
+    bAvailable = false;
+    OxydeWorker::Shutdown();
+    if (LibraryHandle)
+    {
+        FPlatformProcess::FreeDllHandle(LibraryHandle);
+        LibraryHandle = nullptr;
+    }
+}
+
+bool FOxydeModule::IsAvailable()
+{
+    FOxydeModule* Module = FModuleManager::GetModulePtr<FOxydeModule>(TEXT("Oxyde"));
+    return Module && Module->bAvailable;
+}
+
+IMPLEMENT_MODULE(FOxydeModule, Oxyde)
+"#
+    .to_string()
+}
+
+/// Generate the shared agent types header
+fn generate_unreal_agent_header(agents: &[AgentConfig]) -> String {
+    let mut agent_enum_values = String::new();
+
+    for agent in agents {
+        let enum_name = agent.agent.name.replace(' ', "");
+        agent_enum_values.push_str(&format!(
+            "    {}Agent UMETA(DisplayName = \"{}\"),\n",
+            enum_name, agent.agent.name
+        ));
+    }
+
+    format!(
+        r#"// Oxyde agent types
+
+#pragma once
+
+#include "CoreMinimal.h"
+#include "OxydeAgentTypes.generated.h"
+
+// Agent types available in the game
+UENUM(BlueprintType)
+enum class EOxydeAgentType : uint8
+{{
+{}}};
+
+// Agent state information
+USTRUCT(BlueprintType)
+struct OXYDE_API FOxydeAgentState
+{{
+    GENERATED_BODY()
+
+    // Agent name
+    UPROPERTY(VisibleAnywhere, BlueprintReadOnly, Category = "Oxyde")
+    FString Name;
+
+    // Agent role
+    UPROPERTY(VisibleAnywhere, BlueprintReadOnly, Category = "Oxyde")
+    FString Role;
+
+    // Last response
+    UPROPERTY(VisibleAnywhere, BlueprintReadOnly, Category = "Oxyde")
+    FString LastResponse;
+}};
+
+// Emotion values, each in the range -1.0 to 1.0
+USTRUCT(BlueprintType)
+struct OXYDE_API FOxydeEmotions
+{{
+    GENERATED_BODY()
+
+    UPROPERTY(VisibleAnywhere, BlueprintReadOnly, Category = "Oxyde")
+    float Joy = 0.0f;
+
+    UPROPERTY(VisibleAnywhere, BlueprintReadOnly, Category = "Oxyde")
+    float Trust = 0.0f;
+
+    UPROPERTY(VisibleAnywhere, BlueprintReadOnly, Category = "Oxyde")
+    float Fear = 0.0f;
+
+    UPROPERTY(VisibleAnywhere, BlueprintReadOnly, Category = "Oxyde")
+    float Surprise = 0.0f;
+
+    UPROPERTY(VisibleAnywhere, BlueprintReadOnly, Category = "Oxyde")
+    float Sadness = 0.0f;
+
+    UPROPERTY(VisibleAnywhere, BlueprintReadOnly, Category = "Oxyde")
+    float Disgust = 0.0f;
+
+    UPROPERTY(VisibleAnywhere, BlueprintReadOnly, Category = "Oxyde")
+    float Anger = 0.0f;
+
+    UPROPERTY(VisibleAnywhere, BlueprintReadOnly, Category = "Oxyde")
+    float Anticipation = 0.0f;
+}};
+"#,
+        agent_enum_values
+    )
+}
+
+/// Generate the Blueprint function library header
+fn generate_unreal_library_header() -> String {
+    r#"// Blueprint access to the Oxyde C ABI
+
+#pragma once
+
+#include "CoreMinimal.h"
+#include "HAL/ThreadSafeCounter.h"
+#include "Kismet/BlueprintFunctionLibrary.h"
+#include "OxydeAgentTypes.h"
+#include "OxydeBlueprintLibrary.generated.h"
+
+struct OxydeAgent;
+
+// Owns an Oxyde agent; the agent is destroyed with this object once no
+// async call is using it
+UCLASS(BlueprintType)
+class OXYDE_API UOxydeAgentHandle : public UObject
+{
+    GENERATED_BODY()
+
+public:
+    virtual bool IsReadyForFinishDestroy() override;
+    virtual void FinishDestroy() override;
+
+    // Whether the handle holds a live agent
+    UFUNCTION(BlueprintPure, Category = "Oxyde")
+    bool IsValidAgent() const { return Agent != nullptr && !bReleaseWhenIdle; }
+
+    // Stop and destroy the agent, waiting for in-flight async calls to finish first
+    void Release();
+
+    OxydeAgent* Agent = nullptr;
+
+    // Async calls running on worker threads
+    FThreadSafeCounter PendingCalls;
+
+    // Release was requested while async calls were running
+    bool bReleaseWhenIdle = false;
+};
+
+UCLASS()
+class OXYDE_API UOxydeBlueprintLibrary : public UBlueprintFunctionLibrary
+{
+    GENERATED_BODY()
+
+public:
+    // Path of an agent configuration shipped in the plugin's Content/Configs
+    UFUNCTION(BlueprintPure, Category = "Oxyde")
+    static FString GetAgentConfigPath(const FString& FileName);
+
+    // Create and start an agent from a configuration file (JSON, YAML, or TOML)
+    UFUNCTION(BlueprintCallable, Category = "Oxyde", meta = (WorldContext = "WorldContextObject"))
+    static UOxydeAgentHandle* CreateAgentFromFile(UObject* WorldContextObject, const FString& ConfigPath, FString& Error);
+
+    // Create and start an agent from a JSON configuration string
+    UFUNCTION(BlueprintCallable, Category = "Oxyde", meta = (WorldContext = "WorldContextObject"))
+    static UOxydeAgentHandle* CreateAgentFromJson(UObject* WorldContextObject, const FString& ConfigJson, FString& Error);
+
+    // Stop an agent and release it
+    UFUNCTION(BlueprintCallable, Category = "Oxyde")
+    static void DestroyAgent(UOxydeAgentHandle* Agent);
+
+    // Process player input; blocks until the agent responds, so prefer Process Input Async
+    UFUNCTION(BlueprintCallable, Category = "Oxyde")
+    static bool ProcessInput(UOxydeAgentHandle* Agent, const FString& Input, FString& Response);
+
+    // Merge a JSON object into the agent's context
+    UFUNCTION(BlueprintCallable, Category = "Oxyde")
+    static bool UpdateContext(UOxydeAgentHandle* Agent, const FString& ContextJson);
+
+    // Get the agent's current emotions
+    UFUNCTION(BlueprintCallable, Category = "Oxyde")
+    static bool GetEmotions(UOxydeAgentHandle* Agent, FOxydeEmotions& Emotions);
+
+    // Adjust one emotion by a delta
+    UFUNCTION(BlueprintCallable, Category = "Oxyde")
+    static bool UpdateEmotion(UOxydeAgentHandle* Agent, const FString& Emotion, float Delta);
+
+    // Add a memory to the agent
+    UFUNCTION(BlueprintCallable, Category = "Oxyde")
+    static bool AddMemory(UOxydeAgentHandle* Agent, const FString& Category, const FString& Content, float Importance = 0.5f);
+
+    // Get the number of memories the agent holds
+    UFUNCTION(BlueprintCallable, Category = "Oxyde")
+    static int32 GetMemoryCount(UOxydeAgentHandle* Agent);
+
+    // Retrieve memories relevant to a query as a JSON array
+    UFUNCTION(BlueprintCallable, Category = "Oxyde")
+    static bool Recall(UOxydeAgentHandle* Agent, const FString& Query, int32 Limit, FString& MemoriesJson);
+
+    // Synthesize speech (MP3) with the agent's voice; requires TTS in the configuration
+    UFUNCTION(BlueprintCallable, Category = "Oxyde")
+    static bool Speak(UOxydeAgentHandle* Agent, const FString& Text, float Urgency, TArray<uint8>& Audio);
+
+    // Message for the last failed call on this thread
+    UFUNCTION(BlueprintPure, Category = "Oxyde")
+    static FString GetLastError();
+};
+"#
+    .to_string()
+}
+
+/// Generate the Blueprint function library source
+fn generate_unreal_library_source() -> String {
+    r#"// Blueprint access to the Oxyde C ABI
+
+#include "OxydeBlueprintLibrary.h"
+#include "OxydeModule.h"
+#include "Interfaces/IPluginManager.h"
+#include "Misc/Paths.h"
+#include "oxyde.h"
+
+namespace
+{
+    bool Check(OxydeStatus Status, const TCHAR* Operation)
+    {
+        if (Status == OXYDE_STATUS_OK)
+        {
+            return true;
+        }
+        UE_LOG(LogOxyde, Warning, TEXT("%s failed (%d): %s"), Operation, (int32)Status, *UOxydeBlueprintLibrary::GetLastError());
+        return false;
+    }
+
+    OxydeAgent* AgentOf(UOxydeAgentHandle* Handle)
+    {
+        return Handle && Handle->IsValidAgent() ? Handle->Agent : nullptr;
+    }
+
+    FString TakeString(char* Value)
+    {
+        FString Result = UTF8_TO_TCHAR(Value);
+        oxyde_string_free(Value);
+        return Result;
+    }
+
+    UOxydeAgentHandle* StartAgent(UObject* Outer, OxydeStatus Status, OxydeAgent* Agent, FString& Error)
+    {
+        if (Status != OXYDE_STATUS_OK || oxyde_agent_start(Agent) != OXYDE_STATUS_OK)
+        {
+            Error = UOxydeBlueprintLibrary::GetLastError();
+            oxyde_agent_destroy(Agent);
+            return nullptr;
+        }
+        UOxydeAgentHandle* Handle = NewObject<UOxydeAgentHandle>(Outer);
+        Handle->Agent = Agent;
+        return Handle;
+    }
+}
+
+bool UOxydeAgentHandle::IsReadyForFinishDestroy()
+{
+    return PendingCalls.GetValue() == 0 && Super::IsReadyForFinishDestroy();
+}
+
+void UOxydeAgentHandle::FinishDestroy()
+{
+    Release();
+    Super::FinishDestroy();
+}
+
+void UOxydeAgentHandle::Release()
+{
+    if (PendingCalls.GetValue() > 0)
+    {
+        // The worker dispatcher releases the agent when the last call completes
+        bReleaseWhenIdle = true;
+        return;
+    }
+    if (Agent)
+    {
+        oxyde_agent_stop(Agent);
+        oxyde_agent_destroy(Agent);
+        Agent = nullptr;
+    }
+}
+
+FString UOxydeBlueprintLibrary::GetAgentConfigPath(const FString& FileName)
+{
+    const FString ContentDir = IPluginManager::Get().FindPlugin(TEXT("Oxyde"))->GetContentDir();
+    return FPaths::Combine(ContentDir, TEXT("Configs"), FileName);
+}
+
+UOxydeAgentHandle* UOxydeBlueprintLibrary::CreateAgentFromFile(UObject* WorldContextObject, const FString& ConfigPath, FString& Error)
+{
+    if (!FOxydeModule::IsAvailable())
+    {
+        Error = TEXT("Oxyde library is not loaded");
+        return nullptr;
+    }
+    OxydeAgent* Agent = nullptr;
+    const OxydeStatus Status = oxyde_agent_create_from_file(TCHAR_TO_UTF8(*ConfigPath), &Agent);
+    return StartAgent(WorldContextObject, Status, Agent, Error);
+}
+
+UOxydeAgentHandle* UOxydeBlueprintLibrary::CreateAgentFromJson(UObject* WorldContextObject, const FString& ConfigJson, FString& Error)
+{
+    if (!FOxydeModule::IsAvailable())
+    {
+        Error = TEXT("Oxyde library is not loaded");
+        return nullptr;
+    }
+    OxydeAgent* Agent = nullptr;
+    const OxydeStatus Status = oxyde_agent_create_from_json(TCHAR_TO_UTF8(*ConfigJson), &Agent);
+    return StartAgent(WorldContextObject, Status, Agent, Error);
+}
+
+void UOxydeBlueprintLibrary::DestroyAgent(UOxydeAgentHandle* Agent)
+{
+    if (Agent)
+    {
+        Agent->Release();
+    }
+}
+
+bool UOxydeBlueprintLibrary::ProcessInput(UOxydeAgentHandle* Agent, const FString& Input, FString& Response)
+{
+    char* Output = nullptr;
+    if (!Check(oxyde_agent_process_input(AgentOf(Agent), TCHAR_TO_UTF8(*Input), &Output), TEXT("ProcessInput")))
+    {
+        return false;
+    }
+    Response = TakeString(Output);
+    return true;
+}
+
+bool UOxydeBlueprintLibrary::UpdateContext(UOxydeAgentHandle* Agent, const FString& ContextJson)
+{
+    return Check(oxyde_agent_update_context(AgentOf(Agent), TCHAR_TO_UTF8(*ContextJson)), TEXT("UpdateContext"));
+}
+
+bool UOxydeBlueprintLibrary::GetEmotions(UOxydeAgentHandle* Agent, FOxydeEmotions& Emotions)
+{
+    OxydeEmotions Values;
+    if (!Check(oxyde_agent_get_emotions(AgentOf(Agent), &Values), TEXT("GetEmotions")))
+    {
+        return false;
+    }
+    Emotions.Joy = Values.joy;
+    Emotions.Trust = Values.trust;
+    Emotions.Fear = Values.fear;
+    Emotions.Surprise = Values.surprise;
+    Emotions.Sadness = Values.sadness;
+    Emotions.Disgust = Values.disgust;
+    Emotions.Anger = Values.anger;
+    Emotions.Anticipation = Values.anticipation;
+    return true;
+}
+
+bool UOxydeBlueprintLibrary::UpdateEmotion(UOxydeAgentHandle* Agent, const FString& Emotion, float Delta)
+{
+    return Check(oxyde_agent_update_emotion(AgentOf(Agent), TCHAR_TO_UTF8(*Emotion), Delta), TEXT("UpdateEmotion"));
+}
+
+bool UOxydeBlueprintLibrary::AddMemory(UOxydeAgentHandle* Agent, const FString& Category, const FString& Content, float Importance)
+{
+    return Check(
+        oxyde_agent_add_memory(AgentOf(Agent), TCHAR_TO_UTF8(*Category), TCHAR_TO_UTF8(*Content), Importance),
+        TEXT("AddMemory"));
+}
+
+int32 UOxydeBlueprintLibrary::GetMemoryCount(UOxydeAgentHandle* Agent)
+{
+    uint32_t Count = 0;
+    return Check(oxyde_agent_memory_count(AgentOf(Agent), &Count), TEXT("GetMemoryCount")) ? (int32)Count : 0;
+}
+
+bool UOxydeBlueprintLibrary::Recall(UOxydeAgentHandle* Agent, const FString& Query, int32 Limit, FString& MemoriesJson)
+{
+    char* Output = nullptr;
+    if (!Check(oxyde_agent_recall(AgentOf(Agent), TCHAR_TO_UTF8(*Query), (uint32_t)FMath::Max(Limit, 0), &Output), TEXT("Recall")))
+    {
+        return false;
+    }
+    MemoriesJson = TakeString(Output);
+    return true;
+}
+
+bool UOxydeBlueprintLibrary::Speak(UOxydeAgentHandle* Agent, const FString& Text, float Urgency, TArray<uint8>& Audio)
+{
+    OxydeAudio Output = {};
+    if (!Check(oxyde_agent_speak(AgentOf(Agent), TCHAR_TO_UTF8(*Text), Urgency, &Output), TEXT("Speak")))
+    {
+        return false;
+    }
+    Audio = TArray<uint8>(Output.data, (int32)Output.len);
+    oxyde_audio_free(&Output);
+    return true;
+}
+
+FString UOxydeBlueprintLibrary::GetLastError()
+{
+    const char* Error = oxyde_last_error();
+    return Error ? FString(UTF8_TO_TCHAR(Error)) : FString();
+}
+"#
+    .to_string()
+}
+
+/// Generate the worker-thread dispatcher header
+fn generate_unreal_worker_header() -> String {
+    r#"// Worker threads for blocking Oxyde calls
+
+#pragma once
+
+#include "CoreMinimal.h"
+#include "oxyde.h"
+
+class UOxydeAgentHandle;
+
+// Outcome of an Oxyde call made on a worker thread
+struct FOxydeCallResult
+{
+    bool bSuccess = false;
+
+    // Text output on success, or the error message on failure
+    FString Value;
+
+    // Binary output, such as synthesized audio
+    TArray<uint8> Bytes;
+
+    // Take ownership of a string output; reads the error on the calling thread
+    static FOxydeCallResult FromString(OxydeStatus Status, char* Output);
+
+    // Failure carrying the last error of the calling thread
+    static FOxydeCallResult Failure();
+};
+
+namespace OxydeWorker
+{
+    // Start the worker threads; called when the module starts
+    void Startup(int32 NumThreads);
+
+    // Stop the worker threads; called when the module shuts down
+    void Shutdown();
+
+    // Run Work against the agent on a worker thread, then OnComplete on the game thread.
+    // Must be called on the game thread. The agent stays alive until OnComplete has run.
+    void Dispatch(
+        UOxydeAgentHandle* Agent,
+        TUniqueFunction<FOxydeCallResult(OxydeAgent*)> Work,
+        TUniqueFunction<void(const FOxydeCallResult&)> OnComplete);
+}
+"#
+    .to_string()
+}
+
+/// Generate the worker-thread dispatcher source
+fn generate_unreal_worker_source() -> String {
+    r#"// Worker threads for blocking Oxyde calls
+
+#include "OxydeWorker.h"
+#include "OxydeBlueprintLibrary.h"
+#include "OxydeModule.h"
+#include "Async/Async.h"
+#include "Misc/QueuedThreadPool.h"
+
+namespace
+{
+    FQueuedThreadPool* Pool = nullptr;
+
+    void CompleteOnGameThread(TUniqueFunction<void()> Completion)
+    {
+        AsyncTask(ENamedThreads::GameThread, MoveTemp(Completion));
+    }
+}
+
+FOxydeCallResult FOxydeCallResult::FromString(OxydeStatus Status, char* Output)
+{
+    if (Status != OXYDE_STATUS_OK)
+    {
+        return Failure();
+    }
+    FOxydeCallResult Result;
+    Result.bSuccess = true;
+    Result.Value = UTF8_TO_TCHAR(Output);
+    oxyde_string_free(Output);
+    return Result;
+}
+
+FOxydeCallResult FOxydeCallResult::Failure()
+{
+    // Errors are per thread, so this must run on the thread that made the call
+    const char* Error = oxyde_last_error();
+    FOxydeCallResult Result;
+    Result.Value = Error ? FString(UTF8_TO_TCHAR(Error)) : FString(TEXT("Unknown error"));
+    return Result;
+}
+
+void OxydeWorker::Startup(int32 NumThreads)
+{
+    if (Pool)
+    {
+        return;
+    }
+    Pool = FQueuedThreadPool::Allocate();
+    if (!Pool->Create(NumThreads, 256 * 1024, TPri_Normal, TEXT("OxydeWorker")))
+    {
+        UE_LOG(LogOxyde, Error, TEXT("Failed to start Oxyde worker threads"));
+        delete Pool;
+        Pool = nullptr;
+    }
+}
+
+void OxydeWorker::Shutdown()
+{
+    if (Pool)
+    {
+        Pool->Destroy();
+        delete Pool;
+        Pool = nullptr;
+    }
+}
+
+void OxydeWorker::Dispatch(
+    UOxydeAgentHandle* Agent,
+    TUniqueFunction<FOxydeCallResult(OxydeAgent*)> Work,
+    TUniqueFunction<void(const FOxydeCallResult&)> OnComplete)
+{
+    check(IsInGameThread());
+
+    if (!Pool || !Agent || !Agent->IsValidAgent())
+    {
+        FOxydeCallResult Result;
+        Result.Value = Pool ? TEXT("Agent is not valid") : TEXT("Oxyde library is not loaded");
+        CompleteOnGameThread([Result = MoveTemp(Result), OnComplete = MoveTemp(OnComplete)]() { OnComplete(Result); });
+        return;
+    }
+
+    // Holds off the handle's destruction until the completion has run
+    Agent->PendingCalls.Increment();
+    OxydeAgent* RawAgent = Agent->Agent;
+    AsyncPool(*Pool, [Agent, RawAgent, Work = MoveTemp(Work), OnComplete = MoveTemp(OnComplete)]() mutable
+    {
+        FOxydeCallResult Result = Work(RawAgent);
+        CompleteOnGameThread([Agent, Result = MoveTemp(Result), OnComplete = MoveTemp(OnComplete)]()
+        {
+            OnComplete(Result);
+            if (Agent->PendingCalls.Decrement() == 0 && Agent->bReleaseWhenIdle)
+            {
+                Agent->Release();
+            }
+        });
+    });
+}
+"#
+    .to_string()
+}
+
+/// Generate the latent Blueprint node header
+fn generate_unreal_async_header() -> String {
+    r#"// Latent Blueprint nodes that run Oxyde calls off the game thread
+
+#pragma once
+
+#include "CoreMinimal.h"
+#include "Kismet/BlueprintAsyncActionBase.h"
+#include "OxydeAsyncActions.generated.h"
+
+class UOxydeAgentHandle;
+struct FOxydeCallResult;
+
+DECLARE_DYNAMIC_MULTICAST_DELEGATE_OneParam(FOxydeAsyncResponseSignature, const FString&, Response);
+DECLARE_DYNAMIC_MULTICAST_DELEGATE_OneParam(FOxydeAsyncAudioSignature, const TArray<uint8>&, Audio);
+DECLARE_DYNAMIC_MULTICAST_DELEGATE_OneParam(FOxydeAsyncFailureSignature, const FString&, Error);
+
+// Process player input on a worker thread
+UCLASS()
+class OXYDE_API UOxydeProcessInputAsync : public UBlueprintAsyncActionBase
+{
+    GENERATED_BODY()
+
+public:
+    // Process player input without blocking; OnResponse fires on the game thread
+    UFUNCTION(BlueprintCallable, Category = "Oxyde", meta = (BlueprintInternalUseOnly = "true", WorldContext = "WorldContextObject"))
+    static UOxydeProcessInputAsync* ProcessInputAsync(UObject* WorldContextObject, UOxydeAgentHandle* Agent, const FString& Input);
+
+    virtual void Activate() override;
+
+    // Called with the agent's response
+    UPROPERTY(BlueprintAssignable)
+    FOxydeAsyncResponseSignature OnResponse;
+
+    // Called with the error message if the agent could not respond
+    UPROPERTY(BlueprintAssignable)
+    FOxydeAsyncFailureSignature OnFailure;
+
+private:
+    void Finish(const FOxydeCallResult& Result);
+
+    UPROPERTY()
+    UOxydeAgentHandle* Agent = nullptr;
+
+    FString Input;
+};
+
+// Synthesize speech on a worker thread
+UCLASS()
+class OXYDE_API UOxydeSpeakAsync : public UBlueprintAsyncActionBase
+{
+    GENERATED_BODY()
+
+public:
+    // Synthesize speech (MP3) without blocking; OnAudio fires on the game thread
+    UFUNCTION(BlueprintCallable, Category = "Oxyde", meta = (BlueprintInternalUseOnly = "true", WorldContext = "WorldContextObject"))
+    static UOxydeSpeakAsync* SpeakAsync(UObject* WorldContextObject, UOxydeAgentHandle* Agent, const FString& Text, float Urgency);
+
+    virtual void Activate() override;
+
+    // Called with the synthesized audio
+    UPROPERTY(BlueprintAssignable)
+    FOxydeAsyncAudioSignature OnAudio;
+
+    // Called with the error message if synthesis failed
+    UPROPERTY(BlueprintAssignable)
+    FOxydeAsyncFailureSignature OnFailure;
+
+private:
+    void Finish(const FOxydeCallResult& Result);
+
+    UPROPERTY()
+    UOxydeAgentHandle* Agent = nullptr;
+
+    FString Text;
+    float Urgency = 0.0f;
+};
+"#
+    .to_string()
+}
+
+/// Generate the latent Blueprint node source
+fn generate_unreal_async_source() -> String {
+    r#"// Latent Blueprint nodes that run Oxyde calls off the game thread
+
+#include "OxydeAsyncActions.h"
+#include "OxydeBlueprintLibrary.h"
+#include "OxydeModule.h"
+#include "OxydeWorker.h"
+
+UOxydeProcessInputAsync* UOxydeProcessInputAsync::ProcessInputAsync(UObject* WorldContextObject, UOxydeAgentHandle* Agent, const FString& Input)
+{
+    UOxydeProcessInputAsync* Action = NewObject<UOxydeProcessInputAsync>();
+    Action->Agent = Agent;
+    Action->Input = Input;
+    Action->RegisterWithGameInstance(WorldContextObject);
+    return Action;
+}
+
+void UOxydeProcessInputAsync::Activate()
+{
+    TWeakObjectPtr<UOxydeProcessInputAsync> WeakThis(this);
+    OxydeWorker::Dispatch(
+        Agent,
+        [Input = Input](OxydeAgent* Handle)
+        {
+            char* Output = nullptr;
+            return FOxydeCallResult::FromString(oxyde_agent_process_input(Handle, TCHAR_TO_UTF8(*Input), &Output), Output);
+        },
+        [WeakThis](const FOxydeCallResult& Result)
+        {
+            if (UOxydeProcessInputAsync* Action = WeakThis.Get())
+            {
+                Action->Finish(Result);
+            }
+        });
+}
+
+void UOxydeProcessInputAsync::Finish(const FOxydeCallResult& Result)
+{
+    if (Result.bSuccess)
+    {
+        OnResponse.Broadcast(Result.Value);
+    }
+    else
+    {
+        UE_LOG(LogOxyde, Warning, TEXT("ProcessInputAsync failed: %s"), *Result.Value);
+        OnFailure.Broadcast(Result.Value);
+    }
+    SetReadyToDestroy();
+}
+
+UOxydeSpeakAsync* UOxydeSpeakAsync::SpeakAsync(UObject* WorldContextObject, UOxydeAgentHandle* Agent, const FString& Text, float Urgency)
+{
+    UOxydeSpeakAsync* Action = NewObject<UOxydeSpeakAsync>();
+    Action->Agent = Agent;
+    Action->Text = Text;
+    Action->Urgency = Urgency;
+    Action->RegisterWithGameInstance(WorldContextObject);
+    return Action;
+}
+
+void UOxydeSpeakAsync::Activate()
+{
+    TWeakObjectPtr<UOxydeSpeakAsync> WeakThis(this);
+    OxydeWorker::Dispatch(
+        Agent,
+        [Text = Text, Urgency = Urgency](OxydeAgent* Handle)
+        {
+            OxydeAudio Output = {};
+            if (oxyde_agent_speak(Handle, TCHAR_TO_UTF8(*Text), Urgency, &Output) != OXYDE_STATUS_OK)
+            {
+                return FOxydeCallResult::Failure();
+            }
+            FOxydeCallResult Result;
+            Result.bSuccess = true;
+            Result.Bytes = TArray<uint8>(Output.data, (int32)Output.len);
+            oxyde_audio_free(&Output);
+            return Result;
+        },
+        [WeakThis](const FOxydeCallResult& Result)
+        {
+            if (UOxydeSpeakAsync* Action = WeakThis.Get())
+            {
+                Action->Finish(Result);
+            }
+        });
+}
+
+void UOxydeSpeakAsync::Finish(const FOxydeCallResult& Result)
+{
+    if (Result.bSuccess)
+    {
+        OnAudio.Broadcast(Result.Bytes);
+    }
+    else
+    {
+        UE_LOG(LogOxyde, Warning, TEXT("SpeakAsync failed: %s"), *Result.Value);
+        OnFailure.Broadcast(Result.Value);
+    }
+    SetReadyToDestroy();
+}
+"#
+    .to_string()
+}
+
+/// Generate the NPC actor header
+fn generate_unreal_npc_header() -> String {
+    r#"// NPC character driven by an Oxyde agent
+
+#pragma once
+
+#include "CoreMinimal.h"
+#include "GameFramework/Character.h"
+#include "OxydeAgentTypes.h"
+#include "OxydeNPC.generated.h"
+
+class UOxydeAgentHandle;
+
+DECLARE_DYNAMIC_MULTICAST_DELEGATE_OneParam(FOxydeResponseSignature, const FString&, Response);
+
+UCLASS()
+class OXYDE_API AOxydeNPC : public ACharacter
+{
+    GENERATED_BODY()
+
+public:
+    AOxydeNPC();
+
+    virtual void Tick(float DeltaTime) override;
+
+    // Create the agent from a configuration file
+    UFUNCTION(BlueprintCallable, Category = "Oxyde")
+    bool InitializeAgent(const FString& Path);
+
+    // Process input for the agent and broadcast the response; blocks the game thread
+    UFUNCTION(BlueprintCallable, Category = "Oxyde")
+    FString ProcessInput(const FString& Input);
+
+    // Process input on a worker thread and broadcast the response when it arrives
+    UFUNCTION(BlueprintCallable, Category = "Oxyde")
+    void ProcessInputAsync(const FString& Input);
+
+    // Merge a JSON object into the agent's context
+    UFUNCTION(BlueprintCallable, Category = "Oxyde")
+    void UpdateContext(const FString& ContextJSON);
+
+    UFUNCTION(BlueprintPure, Category = "Oxyde")
+    FString GetAgentName() const { return AgentState.Name; }
+
+    UFUNCTION(BlueprintPure, Category = "Oxyde")
+    FString GetAgentRole() const { return AgentState.Role; }
+
+    UFUNCTION(BlueprintPure, Category = "Oxyde")
+    UOxydeAgentHandle* GetAgent() const { return Agent; }
+
+    // Called with every response the agent gives
+    UPROPERTY(BlueprintAssignable, Category = "Oxyde")
+    FOxydeResponseSignature OnResponse;
+
+protected:
+    virtual void BeginPlay() override;
+    virtual void EndPlay(const EEndPlayReason::Type EndPlayReason) override;
+
+    // Configuration file name in the plugin's Content/Configs, or an absolute path
+    UPROPERTY(EditAnywhere, BlueprintReadOnly, Category = "Oxyde")
+    FString ConfigPath;
+
+    // Distance in units within which the NPC greets the player
+    UPROPERTY(EditAnywhere, BlueprintReadOnly, Category = "Oxyde")
+    float GreetingDistance = 300.0f;
+
+    // Seconds between context updates
+    UPROPERTY(EditAnywhere, BlueprintReadOnly, Category = "Oxyde")
+    float ContextUpdateInterval = 0.25f;
+
+    UPROPERTY(VisibleAnywhere, BlueprintReadOnly, Category = "Oxyde")
+    FOxydeAgentState AgentState;
+
+private:
+    void HandleResponse(const FString& Response);
+
+    UPROPERTY()
+    UOxydeAgentHandle* Agent = nullptr;
+
+    float TimeSinceContextUpdate = 0.0f;
+    bool bPlayerNearby = false;
+};
+"#
+    .to_string()
+}
+
+/// Generate the NPC actor source
+fn generate_unreal_npc_source() -> String {
+    r#"// NPC character driven by an Oxyde agent
+
+#include "OxydeNPC.h"
+#include "OxydeBlueprintLibrary.h"
+#include "OxydeModule.h"
+#include "OxydeWorker.h"
+#include "Dom/JsonObject.h"
+#include "Kismet/GameplayStatics.h"
+#include "Misc/FileHelper.h"
+#include "Misc/Paths.h"
+#include "Serialization/JsonSerializer.h"
+
+AOxydeNPC::AOxydeNPC()
+{
+    PrimaryActorTick.bCanEverTick = true;
+}
+
+void AOxydeNPC::BeginPlay()
+{
+    Super::BeginPlay();
+
+    if (!ConfigPath.IsEmpty())
+    {
+        const FString Path = FPaths::IsRelative(ConfigPath) ? UOxydeBlueprintLibrary::GetAgentConfigPath(ConfigPath) : ConfigPath;
+        InitializeAgent(Path);
+    }
+}
+
+void AOxydeNPC::EndPlay(const EEndPlayReason::Type EndPlayReason)
+{
+    UOxydeBlueprintLibrary::DestroyAgent(Agent);
+    Agent = nullptr;
+    Super::EndPlay(EndPlayReason);
+}
+
+void AOxydeNPC::Tick(float DeltaTime)
+{
+    Super::Tick(DeltaTime);
+
+    APawn* PlayerPawn = UGameplayStatics::GetPlayerPawn(GetWorld(), 0);
+    TimeSinceContextUpdate += DeltaTime;
+    if (!PlayerPawn || !Agent || TimeSinceContextUpdate < ContextUpdateInterval)
+    {
+        return;
+    }
+    TimeSinceContextUpdate = 0.0f;
+
+    const FVector PlayerLocation = PlayerPawn->GetActorLocation();
+    const float Distance = FVector::Dist(GetActorLocation(), PlayerLocation);
+    const bool bNearby = Distance < GreetingDistance;
+
+    TSharedPtr<FJsonObject> Context = MakeShareable(new FJsonObject);
+    Context->SetNumberField(TEXT("player_distance"), Distance);
+    Context->SetNumberField(TEXT("player_x"), PlayerLocation.X);
+    Context->SetNumberField(TEXT("player_y"), PlayerLocation.Y);
+    Context->SetNumberField(TEXT("player_z"), PlayerLocation.Z);
+    Context->SetBoolField(TEXT("player_nearby"), bNearby);
+
+    FString ContextJSON;
+    TSharedRef<TJsonWriter<>> Writer = TJsonWriterFactory<>::Create(&ContextJSON);
+    FJsonSerializer::Serialize(Context.ToSharedRef(), Writer);
+    UpdateContext(ContextJSON);
+
+    // Greet the player when they first come into range
+    if (bNearby && !bPlayerNearby)
+    {
+        ProcessInputAsync(TEXT("hello"));
+    }
+    bPlayerNearby = bNearby;
+}
+
+bool AOxydeNPC::InitializeAgent(const FString& Path)
+{
+    FString Error;
+    Agent = UOxydeBlueprintLibrary::CreateAgentFromFile(this, Path, Error);
+    if (!Agent)
+    {
+        UE_LOG(LogOxyde, Error, TEXT("Failed to create agent from %s: %s"), *Path, *Error);
+        return false;
+    }
+
+    // Read the name and role from the configuration
+    FString ConfigJSON;
+    TSharedPtr<FJsonObject> Config;
+    if (FFileHelper::LoadFileToString(ConfigJSON, *Path)
+        && FJsonSerializer::Deserialize(TJsonReaderFactory<>::Create(ConfigJSON), Config)
+        && Config.IsValid())
+    {
+        const TSharedPtr<FJsonObject>* Personality;
+        if (Config->TryGetObjectField(TEXT("agent"), Personality))
+        {
+            AgentState.Name = (*Personality)->GetStringField(TEXT("name"));
+            AgentState.Role = (*Personality)->GetStringField(TEXT("role"));
+        }
+    }
+
+    UE_LOG(LogOxyde, Log, TEXT("Initialized agent: %s (Role: %s)"), *AgentState.Name, *AgentState.Role);
+    return true;
+}
+
+FString AOxydeNPC::ProcessInput(const FString& Input)
+{
+    FString Response;
+    if (!UOxydeBlueprintLibrary::ProcessInput(Agent, Input, Response))
+    {
+        return FString();
+    }
+    HandleResponse(Response);
+    return Response;
+}
+
+void AOxydeNPC::ProcessInputAsync(const FString& Input)
+{
+    TWeakObjectPtr<AOxydeNPC> WeakThis(this);
+    OxydeWorker::Dispatch(
+        Agent,
+        [Input](OxydeAgent* Handle)
+        {
+            char* Output = nullptr;
+            return FOxydeCallResult::FromString(oxyde_agent_process_input(Handle, TCHAR_TO_UTF8(*Input), &Output), Output);
+        },
+        [WeakThis](const FOxydeCallResult& Result)
+        {
+            AOxydeNPC* NPC = WeakThis.Get();
+            if (!NPC)
+            {
+                return;
+            }
+            if (Result.bSuccess)
+            {
+                NPC->HandleResponse(Result.Value);
+            }
+            else
+            {
+                UE_LOG(LogOxyde, Warning, TEXT("%s failed to respond: %s"), *NPC->AgentState.Name, *Result.Value);
+            }
+        });
+}
+
+void AOxydeNPC::HandleResponse(const FString& Response)
+{
+    if (Response.IsEmpty())
+    {
+        return;
+    }
+    AgentState.LastResponse = Response;
+    OnResponse.Broadcast(Response);
+}
+
+void AOxydeNPC::UpdateContext(const FString& ContextJSON)
+{
+    if (!ContextJSON.IsEmpty())
+    {
+        UOxydeBlueprintLibrary::UpdateContext(Agent, ContextJSON);
+    }
+}
+"#
+    .to_string()
+}
+
+/// Generate the example dialogue widget header
+fn generate_unreal_widget_header() -> String {
+    r#"// Example dialogue widget: bind a Widget Blueprint deriving from this class
+
+#pragma once
+
+#include "CoreMinimal.h"
+#include "Blueprint/UserWidget.h"
+#include "Types/SlateEnums.h"
+#include "OxydeDialogueWidget.generated.h"
+
+class AOxydeNPC;
+class UButton;
+class UEditableTextBox;
+class UTextBlock;
+
+UCLASS()
+class OXYDE_API UOxydeDialogueWidget : public UUserWidget
+{
+    GENERATED_BODY()
+
+public:
+    // Start a conversation with an NPC
+    UFUNCTION(BlueprintCallable, Category = "Oxyde")
+    void SetSpeaker(AOxydeNPC* NPC);
+
+    // Send the typed input to the NPC
+    UFUNCTION(BlueprintCallable, Category = "Oxyde")
+    void SendInput();
+
+protected:
+    virtual void NativeConstruct() override;
+
+    UPROPERTY(meta = (BindWidget))
+    UTextBlock* SpeakerText;
+
+    UPROPERTY(meta = (BindWidget))
+    UTextBlock* ResponseText;
+
+    UPROPERTY(meta = (BindWidget))
+    UEditableTextBox* InputBox;
+
+    UPROPERTY(meta = (BindWidgetOptional))
+    UButton* SendButton;
+
+private:
+    UFUNCTION()
+    void HandleSendClicked();
+
+    UFUNCTION()
+    void HandleInputCommitted(const FText& Text, ETextCommit::Type CommitMethod);
+
+    UFUNCTION()
+    void HandleResponse(const FString& Response);
+
+    UPROPERTY()
+    AOxydeNPC* Speaker = nullptr;
+};
+"#
+    .to_string()
+}
+
+/// Generate the example dialogue widget source
+fn generate_unreal_widget_source() -> String {
+    r#"// Example dialogue widget
+
+#include "OxydeDialogueWidget.h"
+#include "OxydeNPC.h"
+#include "Components/Button.h"
+#include "Components/EditableTextBox.h"
+#include "Components/TextBlock.h"
+
+void UOxydeDialogueWidget::NativeConstruct()
+{
+    Super::NativeConstruct();
+
+    if (SendButton)
+    {
+        SendButton->OnClicked.AddDynamic(this, &UOxydeDialogueWidget::HandleSendClicked);
+    }
+    InputBox->OnTextCommitted.AddDynamic(this, &UOxydeDialogueWidget::HandleInputCommitted);
+}
+
+void UOxydeDialogueWidget::SetSpeaker(AOxydeNPC* NPC)
+{
+    if (Speaker)
+    {
+        Speaker->OnResponse.RemoveDynamic(this, &UOxydeDialogueWidget::HandleResponse);
+    }
+    Speaker = NPC;
+    ResponseText->SetText(FText::GetEmpty());
+    if (Speaker)
+    {
+        Speaker->OnResponse.AddDynamic(this, &UOxydeDialogueWidget::HandleResponse);
+        SpeakerText->SetText(FText::FromString(Speaker->GetAgentName()));
+    }
+}
+
+void UOxydeDialogueWidget::SendInput()
+{
+    const FString Input = InputBox->GetText().ToString().TrimStartAndEnd();
+    if (!Speaker || Input.IsEmpty())
+    {
+        return;
+    }
+    InputBox->SetText(FText::GetEmpty());
+    // The response arrives through HandleResponse once the worker thread finishes
+    Speaker->ProcessInputAsync(Input);
+}
+
+void UOxydeDialogueWidget::HandleSendClicked()
+{
+    SendInput();
+}
+
+void UOxydeDialogueWidget::HandleInputCommitted(const FText& Text, ETextCommit::Type CommitMethod)
+{
+    if (CommitMethod == ETextCommit::OnEnter)
+    {
+        SendInput();
+    }
+}
+
+void UOxydeDialogueWidget::HandleResponse(const FString& Response)
+{
+    ResponseText->SetText(FText::FromString(Response));
+}
+"#
+    .to_string()
+}